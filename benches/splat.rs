@@ -0,0 +1,166 @@
+//! Benchmarks of the kernel truncation radius trading splat cost for accuracy of the tails,
+//! and of direct splatting against binning then convolving via FFT on 1000 level books
+//!
+//! Run with `cargo bench --bench splat`. Next to the timings, the relative L1 difference of
+//! each truncation against a 6 sigma reference, and of FFT against direct splatting, is printed
+//! to quantify the visual impact.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use std::hint::black_box;
+use std::iter::zip;
+
+use bookedblocks::splat::{Bandwidth, Kernel, splat_1d, splat_1d_fft, splat_2d, splat_2d_fft};
+
+/// truncation radii compared, in standard deviations
+const TRUNCATIONS: [f64; 4] = [3.0, 4.0, 5.0, 6.0];
+
+/// synthetic book with unevenly sized levels, as (time, price, volume)
+fn source(number_times: usize, number_prices: usize) -> Vec<(f64, f64, f64)> {
+    (0..number_times)
+        .flat_map(|time| {
+            (0..number_prices).map(move |price| {
+                (
+                    time as f64 / number_times as f64,
+                    price as f64 / number_prices as f64,
+                    1.0 + ((time * 7 + price * 13) % 11) as f64,
+                )
+            })
+        })
+        .collect()
+}
+
+/// relative L1 difference of a support against a reference
+fn relative_difference<'a>(
+    reference: impl Iterator<Item = &'a f64> + Clone,
+    support: impl Iterator<Item = &'a f64>,
+) -> f64 {
+    let total = reference.clone().fold(0.0, |acc, value| acc + value.abs());
+    zip(reference, support).fold(0.0, |acc, (lhs, rhs)| acc + (lhs - rhs).abs()) / total
+}
+
+fn bench_splat_1d(criterion: &mut Criterion) {
+    let source = source(1, 200)
+        .into_iter()
+        .map(|(_, price, volume)| (price, volume))
+        .collect::<Vec<_>>();
+    let kernel = |truncation| Kernel {
+        truncation_in_deviations: truncation,
+        ..Kernel::default()
+    };
+
+    let reference = splat_1d(&kernel(6.0), &(0.0, 1.0), 2000, source.clone());
+    let mut group = criterion.benchmark_group("splat_1d");
+    for truncation in TRUNCATIONS {
+        let support = splat_1d(&kernel(truncation), &(0.0, 1.0), 2000, source.clone());
+        println!(
+            "splat_1d {} sigma: relative difference to 6 sigma {:.2e}",
+            truncation,
+            relative_difference(reference.iter(), support.iter())
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(truncation),
+            &truncation,
+            |bencher, truncation| {
+                bencher.iter(|| {
+                    splat_1d(
+                        &kernel(*truncation),
+                        &(0.0, 1.0),
+                        2000,
+                        black_box(source.clone()),
+                    )
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_splat_2d(criterion: &mut Criterion) {
+    let source = source(60, 100);
+    let kernel = |truncation| Kernel {
+        truncation_in_deviations: truncation,
+        ..Kernel::default()
+    };
+    let ranges = (&(0.0, 1.0), &(0.0, 1.0));
+
+    let reference = splat_2d(&kernel(6.0), ranges, (370, 200), source.clone());
+    let mut group = criterion.benchmark_group("splat_2d");
+    group.sample_size(10);
+    for truncation in TRUNCATIONS {
+        let support = splat_2d(&kernel(truncation), ranges, (370, 200), source.clone());
+        println!(
+            "splat_2d {} sigma: relative difference to 6 sigma {:.2e}",
+            truncation,
+            relative_difference(reference.iter(), support.iter())
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(truncation),
+            &truncation,
+            |bencher, truncation| {
+                bencher.iter(|| {
+                    splat_2d(
+                        &kernel(*truncation),
+                        ranges,
+                        (370, 200),
+                        black_box(source.clone()),
+                    )
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_fft(criterion: &mut Criterion) {
+    let kernel = Kernel {
+        bandwidth: Bandwidth::Fraction(0.02),
+        ..Kernel::default()
+    };
+
+    let source_1d = source(1, 1000)
+        .into_iter()
+        .map(|(_, price, volume)| (price, volume))
+        .collect::<Vec<_>>();
+    println!(
+        "splat_1d_fft: relative difference to direct {:.2e}",
+        relative_difference(
+            splat_1d(&kernel, &(0.0, 1.0), 2000, source_1d.clone()).iter(),
+            splat_1d_fft(&kernel, &(0.0, 1.0), 2000, source_1d.clone()).iter()
+        )
+    );
+
+    let mut group = criterion.benchmark_group("fft_1d");
+    group.bench_function("direct", |bencher| {
+        bencher.iter(|| splat_1d(&kernel, &(0.0, 1.0), 2000, black_box(source_1d.clone())))
+    });
+    group.bench_function("fft", |bencher| {
+        bencher.iter(|| splat_1d_fft(&kernel, &(0.0, 1.0), 2000, black_box(source_1d.clone())))
+    });
+    group.finish();
+
+    let source_2d = source(60, 1000);
+    let ranges = (&(0.0, 1.0), &(0.0, 1.0));
+    println!(
+        "splat_2d_fft: relative difference to direct {:.2e}",
+        relative_difference(
+            splat_2d(&kernel, ranges, (370, 200), source_2d.clone()).iter(),
+            splat_2d_fft(&kernel, ranges, (370, 200), source_2d.clone()).iter()
+        )
+    );
+
+    let mut group = criterion.benchmark_group("fft_2d");
+    group.sample_size(10);
+    group.bench_function("direct", |bencher| {
+        bencher.iter(|| splat_2d(&kernel, ranges, (370, 200), black_box(source_2d.clone())))
+    });
+    group.bench_function("fft", |bencher| {
+        bencher.iter(|| splat_2d_fft(&kernel, ranges, (370, 200), black_box(source_2d.clone())))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_splat_1d, bench_splat_2d, bench_fft);
+criterion_main!(benches);
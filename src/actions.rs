@@ -1,22 +1,355 @@
-use crate::feed::{Booked, TickerState};
+use crate::alerts::AlertRule;
+use crate::export::ExportFormat;
+use crate::feed::{Booked, TickerState, Traded};
+use crate::orders::{Account, OrderUpdate};
+use crate::pipeline::View;
+use crate::screenshot::ScreenshotFormat;
+use crate::trading::OrderTicket;
+
+use tokio::spawn;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+use tokio::sync::{Mutex, oneshot};
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Enum of the questions asked about the current state, e.g. by the REST API
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// symbols subscribed
+    Symbols,
+    /// latest cached book of a symbol
+    Book(String),
+    /// latest order map splatted for a symbol
+    Heatmap(String),
+    /// history of a symbol replayed to an interface attached to a daemon
+    Replay(String),
+}
+
+/// Enum of the ways actions enter an action queue backed up by slow handlers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Priority {
+    /// wait for room in the queue, never dropped
+    Required,
+    /// skipped while the same request is still queued
+    Coalesced,
+    /// dropped when the queue is full
+    Droppable,
+}
 
 /// Enum encapsulating different actions that can be performed by application
 #[derive(Debug)]
 pub enum Action {
+    /// Raise an alert to the user
+    Alert(String),
+    /// Watch a new alert rule, or stop watching every rule if None
+    AlertRule(Option<AlertRule>),
+    /// Report a recoverable error to the user, who dismisses it
+    Error(String),
+    /// Dump the arrays on screen for the current ticker to files
+    Export(ExportFormat),
+    /// Dump the cached history of the current ticker to an Arrow file
+    ExportHistory,
     /// Provide log message
     Inform(String),
+    /// Load a recording from disk to inspect it in place of a live ticker
+    OpenRecording(PathBuf),
+    /// Flush the completed time slices of every book history to disk
+    PersistHistory,
+    /// Preview the depth of a ticker from a one-shot snapshot without subscribing to it
+    PreviewTicker(String),
+    /// List the recordings on disk in the library page
+    ScanLibrary,
+    /// Write the frame on screen to a file, in the configured format if None
+    Screenshot(Option<ScreenshotFormat>),
+    /// Resubscribe every ticker with books of a number of levels per side
+    SetBookDepth(i32),
+    /// Show the books of a number of seconds back
+    SetTimeWindow(u64),
+    /// Answer a query on the current state as JSON, None when there is nothing to answer with
+    Query(Query, oneshot::Sender<Option<String>>),
+    /// Restart the interface loops and the feed listener that stopped, or shut down if they
+    /// cannot be
+    Supervise,
+    /// Send an order confirmed by the user to the exchange
+    SubmitOrder(OrderTicket),
     /// Subscribe a new ticker to feed
     SubscribeTicker(String),
     /// Quit the application
     Quit,
+    /// Replace the book of a ticker with a fresh snapshot after an inconsistency was detected
+    ResyncBook(Booked),
+    /// Replace the book of a ticker with one replayed from the history of a daemon
+    ReplayBook(Booked),
     /// Run processign pipeline to update given ticker
     RunPipeline(String),
+    /// Switch market depth between density and cumulative representations
+    ToggleDepthMode,
+    /// Switch a view, or all views if None, between kernel density estimation and exact binning
+    ToggleSplatMode(Option<View>),
     /// Unsubscribe existing ticker
     UnsubscribeTicker(String),
     /// Update order book cache with new information
     UpdateBook(Booked),
+    /// Record the number of decimals the prices of a ticker are quoted with
+    UpdatePriceDecimals(String, u32),
+    /// Replace the balances and open orders of the user
+    UpdateAccount(Account),
+    /// Merge changes to the orders of the user
+    UpdateOwnOrders(Vec<OrderUpdate>),
     /// Update ticker data with latest information
     UpdateTicker(TickerState),
+    /// Record newly executed trades
+    UpdateTrades(Vec<Traded>),
     // Provide a log warning
     Warn(String),
+    /// Write the aggregates of the latest books to the time series database
+    WriteTimeSeries,
+}
+
+impl Action {
+    /// name of the action variant, used to aggregate handling metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Alert(_) => "Alert",
+            Action::AlertRule(_) => "AlertRule",
+            Action::Error(_) => "Error",
+            Action::Export(_) => "Export",
+            Action::ExportHistory => "ExportHistory",
+            Action::Inform(_) => "Inform",
+            Action::OpenRecording(_) => "OpenRecording",
+            Action::PersistHistory => "PersistHistory",
+            Action::PreviewTicker(_) => "PreviewTicker",
+            Action::ScanLibrary => "ScanLibrary",
+            Action::Screenshot(_) => "Screenshot",
+            Action::SetBookDepth(_) => "SetBookDepth",
+            Action::SetTimeWindow(_) => "SetTimeWindow",
+            Action::Query(_, _) => "Query",
+            Action::Supervise => "Supervise",
+            Action::SubmitOrder(_) => "SubmitOrder",
+            Action::SubscribeTicker(_) => "SubscribeTicker",
+            Action::Quit => "Quit",
+            Action::ResyncBook(_) => "ResyncBook",
+            Action::ReplayBook(_) => "ReplayBook",
+            Action::RunPipeline(_) => "RunPipeline",
+            Action::ToggleDepthMode => "ToggleDepthMode",
+            Action::ToggleSplatMode(_) => "ToggleSplatMode",
+            Action::UnsubscribeTicker(_) => "UnsubscribeTicker",
+            Action::UpdateBook(_) => "UpdateBook",
+            Action::UpdatePriceDecimals(_, _) => "UpdatePriceDecimals",
+            Action::UpdateAccount(_) => "UpdateAccount",
+            Action::UpdateOwnOrders(_) => "UpdateOwnOrders",
+            Action::UpdateTicker(_) => "UpdateTicker",
+            Action::UpdateTrades(_) => "UpdateTrades",
+            Action::Warn(_) => "Warn",
+            Action::WriteTimeSeries => "WriteTimeSeries",
+        }
+    }
+
+    /// way the action enters a backed up queue, book, ticker and trade updates always waiting
+    /// for room while informative messages are dropped first
+    pub fn priority(&self) -> Priority {
+        match self {
+            Action::RunPipeline(_) => Priority::Coalesced,
+            Action::Inform(_) => Priority::Droppable,
+            _ => Priority::Required,
+        }
+    }
+}
+
+/// Sender end of an action queue entering actions according to their priority, counting the
+/// actions kept out of the queue
+#[derive(Clone, Debug)]
+pub struct ActionSender {
+    sender: Sender<Action>,
+    /// symbols with a pipeline run queued and not yet handled
+    queued_pipelines: Arc<Mutex<HashSet<String>>>,
+    /// number of actions kept out of the queue by name, since last taken
+    shed: Arc<Mutex<HashMap<&'static str, usize>>>,
+}
+
+impl ActionSender {
+    /// constructor
+    pub fn new(sender: Sender<Action>) -> ActionSender {
+        ActionSender {
+            sender,
+            queued_pipelines: Arc::new(Mutex::new(HashSet::new())),
+            shed: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// plain sender end of the queue, for producers of required actions only
+    pub fn sender(&self) -> Sender<Action> {
+        self.sender.clone()
+    }
+
+    /// number of actions the queue holds
+    pub fn max_capacity(&self) -> usize {
+        self.sender.max_capacity()
+    }
+
+    /// number of actions the queue has room for
+    pub fn capacity(&self) -> usize {
+        self.sender.capacity()
+    }
+
+    /// enter an action in the queue according to its priority, failing only once closed
+    pub async fn send(&self, action: Action) -> Result<(), SendError<Action>> {
+        match action.priority() {
+            Priority::Required => self.sender.send(action).await,
+            Priority::Coalesced => {
+                if let Action::RunPipeline(symbol) = &action
+                    && !self.queued_pipelines.lock().await.insert(symbol.clone())
+                {
+                    *self.shed.lock().await.entry(action.name()).or_default() += 1;
+                    return Ok(());
+                }
+                self.sender.send(action).await
+            }
+            Priority::Droppable => match self.sender.try_send(action) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(action)) => {
+                    *self.shed.lock().await.entry(action.name()).or_default() += 1;
+                    Ok(())
+                }
+                Err(TrySendError::Closed(action)) => Err(SendError(action)),
+            },
+        }
+    }
+
+    /// enter an action in the queue without ever waiting for room, for the handlers of the queue
+    /// which would otherwise wait on themselves, failing only once closed
+    ///
+    /// when full, coalesced and droppable actions are dropped while required actions wait for
+    /// room in a task of their own
+    pub async fn send_now(&self, action: Action) -> Result<(), SendError<Action>> {
+        if let Action::RunPipeline(symbol) = &action
+            && !self.queued_pipelines.lock().await.insert(symbol.clone())
+        {
+            *self.shed.lock().await.entry(action.name()).or_default() += 1;
+            return Ok(());
+        }
+        match self.sender.try_send(action) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(action)) if action.priority() == Priority::Required => {
+                let sender = self.sender.clone();
+                spawn(async move {
+                    let _ = sender.send(action).await;
+                });
+                Ok(())
+            }
+            Err(TrySendError::Full(action)) => {
+                self.dequeued(&action).await;
+                *self.shed.lock().await.entry(action.name()).or_default() += 1;
+                Ok(())
+            }
+            Err(TrySendError::Closed(action)) => Err(SendError(action)),
+        }
+    }
+
+    /// acknowledge an action taken out of the queue, letting its requests in again
+    pub async fn dequeued(&self, action: &Action) {
+        if let Action::RunPipeline(symbol) = action {
+            self.queued_pipelines.lock().await.remove(symbol);
+        }
+    }
+
+    /// number of actions kept out of the queue by name since the last call
+    pub async fn take_shed(&self) -> HashMap<&'static str, usize> {
+        std::mem::take(&mut *self.shed.lock().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn test_action_sender() {
+        let (sender, mut receiver) = channel(2);
+        let sender = ActionSender::new(sender);
+
+        // pipeline requests are coalesced until handled
+        sender
+            .send(Action::RunPipeline("BTC/USD".to_string()))
+            .await
+            .unwrap();
+        sender
+            .send(Action::RunPipeline("BTC/USD".to_string()))
+            .await
+            .unwrap();
+        sender
+            .send(Action::RunPipeline("ETH/USD".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(sender.capacity(), 0);
+
+        // informative messages are dropped once full
+        sender
+            .send(Action::Inform("ignored".to_string()))
+            .await
+            .unwrap();
+        let shed = sender.take_shed().await;
+        assert_eq!(shed.get("RunPipeline"), Some(&1));
+        assert_eq!(shed.get("Inform"), Some(&1));
+        assert!(sender.take_shed().await.is_empty());
+
+        let action = receiver.recv().await.unwrap();
+        sender.dequeued(&action).await;
+        sender
+            .send(Action::RunPipeline("BTC/USD".to_string()))
+            .await
+            .unwrap();
+        assert!(sender.take_shed().await.is_empty());
+        assert_eq!(receiver.recv().await.unwrap().name(), "RunPipeline",);
+        assert_eq!(sender.capacity(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_now() {
+        let (sender, mut receiver) = channel(1);
+        let sender = ActionSender::new(sender);
+
+        // required actions wait for room without holding up the sender, never shed
+        sender
+            .send_now(Action::Error("queued".to_string()))
+            .await
+            .unwrap();
+        sender
+            .send_now(Action::Error("waiting".to_string()))
+            .await
+            .unwrap();
+        sender
+            .send_now(Action::Inform("dropped".to_string()))
+            .await
+            .unwrap();
+        sender
+            .send_now(Action::RunPipeline("BTC/USD".to_string()))
+            .await
+            .unwrap();
+        let shed = sender.take_shed().await;
+        assert_eq!(shed.get("Error"), None);
+        assert_eq!(shed.get("Inform"), Some(&1));
+        assert_eq!(shed.get("RunPipeline"), Some(&1));
+        for message in ["queued", "waiting"] {
+            match receiver.recv().await.unwrap() {
+                Action::Error(error) => assert_eq!(error, message),
+                action => panic!("Expected an error, got {}", action.name()),
+            }
+        }
+
+        // a pipeline request dropped for lack of room is let in again
+        sender
+            .send_now(Action::RunPipeline("BTC/USD".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(receiver.recv().await.unwrap().name(), "RunPipeline");
+
+        drop(receiver);
+        assert!(sender.send_now(Action::Quit).await.is_err());
+    }
 }
@@ -0,0 +1,334 @@
+use crate::clock;
+use crate::error::BookedError;
+use crate::feed::parse_symbol;
+use crate::walls::Wall;
+
+use hyper::Request;
+use hyper_tls::HttpsConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use serde_json::json;
+
+/// Enum of the conditions an alert rule watches the book of a symbol for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    /// mid price crossing a level, either way
+    PriceCrosses(f64),
+    /// spread widening beyond a number of basis points of mid
+    SpreadAbove(f64),
+    /// wall of more than a quantity appearing on either side
+    WallAbove(f64),
+}
+
+/// Condition watched on the books of a symbol
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertRule {
+    pub symbol: String,
+    pub condition: Condition,
+}
+
+impl AlertRule {
+    /// rule written as `SYMBOL price|spread|wall THRESHOLD`, e.g. `BTC/USD spread 10bps`
+    pub fn parse(text: &str) -> Result<AlertRule, BookedError> {
+        let words = text.split_whitespace().collect::<Vec<_>>();
+        let (symbol, kind, threshold) = match words[..] {
+            [symbol, kind, threshold] => (parse_symbol(symbol)?, kind, threshold),
+            _ => {
+                return Err(BookedError::Parse(
+                    "expected SYMBOL price|spread|wall THRESHOLD".to_string(),
+                ));
+            }
+        };
+        let threshold = match threshold
+            .strip_suffix("bps")
+            .filter(|_| kind == "spread")
+            .unwrap_or(threshold)
+            .parse::<f64>()
+        {
+            Ok(threshold) if threshold > 0.0 && threshold.is_finite() => threshold,
+            Ok(_) => {
+                return Err(BookedError::Parse(
+                    "alert thresholds should be positive".to_string(),
+                ));
+            }
+            Err(message) => {
+                return Err(BookedError::Parse(format!(
+                    "Invalid threshold '{}': {}",
+                    threshold, message
+                )));
+            }
+        };
+        let condition = match kind {
+            "price" => Condition::PriceCrosses(threshold),
+            "spread" => Condition::SpreadAbove(threshold),
+            "wall" => Condition::WallAbove(threshold),
+            _ => {
+                return Err(BookedError::Parse(format!(
+                    "Unknown alert '{}', expected price, spread or wall",
+                    kind
+                )));
+            }
+        };
+        Ok(AlertRule { symbol, condition })
+    }
+
+    /// description of the rule, as shown in the interface
+    pub fn describe(&self) -> String {
+        match self.condition {
+            Condition::PriceCrosses(level) => format!("{} mid crossing {}", self.symbol, level),
+            Condition::SpreadAbove(bps) => format!("{} spread above {}bps", self.symbol, bps),
+            Condition::WallAbove(quantity) => format!("{} wall above {}", self.symbol, quantity),
+        }
+    }
+}
+
+/// Evaluator of alert rules against the books received, triggering each rule once every time
+/// its condition starts holding
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    /// rules watched along with the value they last observed, mid price or spread
+    rules: Vec<(AlertRule, Option<f64>)>,
+}
+
+impl AlertEngine {
+    /// watch a new rule
+    pub fn add(&mut self, rule: AlertRule) {
+        self.rules.push((rule, None));
+    }
+
+    /// stop watching every rule
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    /// messages of the rules of a symbol triggered by its latest best ask and bid
+    pub fn check_book(&mut self, symbol: &str, ask: f64, bid: f64) -> Vec<String> {
+        let mid = (ask + bid) / 2.0;
+        let spread = (ask - bid) / mid * 1e4;
+        let mut messages = vec![];
+        for (rule, last) in self.rules.iter_mut() {
+            if rule.symbol != symbol {
+                continue;
+            }
+            match rule.condition {
+                Condition::PriceCrosses(level) => {
+                    if let Some(previous) = *last
+                        && (previous < level && mid >= level || previous > level && mid <= level)
+                    {
+                        messages.push(format!("{} mid crossed {} at {}", symbol, level, mid));
+                    }
+                    *last = Some(mid);
+                }
+                Condition::SpreadAbove(bps) => {
+                    if spread > bps && last.is_none_or(|previous| previous <= bps) {
+                        messages.push(format!(
+                            "{} spread widened to {:.1}bps, above {}bps",
+                            symbol, spread, bps
+                        ));
+                    }
+                    *last = Some(spread);
+                }
+                Condition::WallAbove(_) => (),
+            }
+        }
+        messages
+    }
+
+    /// messages of the rules of a symbol triggered by a newly appeared wall
+    pub fn check_wall(&self, symbol: &str, wall: &Wall) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter_map(|(rule, _)| match rule.condition {
+                Condition::WallAbove(quantity)
+                    if rule.symbol == symbol && wall.quantity > quantity =>
+                {
+                    Some(format!(
+                        "Wall of {} {} at {}, above {}",
+                        wall.quantity, symbol, wall.price, quantity
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Enum of the outbound channels triggered alerts are forwarded to
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlertSink {
+    /// POST of a JSON payload to a URL
+    Webhook(String),
+    /// message of a Telegram bot to a chat
+    Telegram { token: String, chat: String },
+    /// message of a Discord bot to a channel
+    Discord { token: String, channel: String },
+}
+
+impl AlertSink {
+    /// sink written as `webhook:URL`, `telegram:TOKEN@CHAT` or `discord:TOKEN@CHANNEL`
+    pub fn parse(text: &str) -> Result<AlertSink, BookedError> {
+        let addressed = |address: &str| match address.split_once('@') {
+            Some((token, recipient)) if !token.is_empty() && !recipient.is_empty() => {
+                Ok((token.to_string(), recipient.to_string()))
+            }
+            _ => Err(BookedError::Parse(format!(
+                "Invalid address '{}', expected TOKEN@RECIPIENT",
+                address
+            ))),
+        };
+        match text.trim().split_once(':') {
+            Some(("webhook", url)) if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(AlertSink::Webhook(url.to_string()))
+            }
+            Some(("webhook", url)) => {
+                Err(BookedError::Parse(format!("Invalid webhook URL '{}'", url)))
+            }
+            Some(("telegram", address)) => {
+                let (token, chat) = addressed(address)?;
+                Ok(AlertSink::Telegram { token, chat })
+            }
+            Some(("discord", address)) => {
+                let (token, channel) = addressed(address)?;
+                Ok(AlertSink::Discord { token, channel })
+            }
+            _ => Err(BookedError::Parse(
+                "expected webhook:URL, telegram:TOKEN@CHAT or discord:TOKEN@CHANNEL".to_string(),
+            )),
+        }
+    }
+
+    /// name of the sink, as shown in the interface
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertSink::Webhook(_) => "webhook",
+            AlertSink::Telegram { .. } => "Telegram",
+            AlertSink::Discord { .. } => "Discord",
+        }
+    }
+
+    /// private utility method building the request forwarding a message to the sink
+    fn request(&self, message: &str) -> Result<Request<String>, BookedError> {
+        let (url, body) = match self {
+            AlertSink::Webhook(url) => (
+                url.clone(),
+                json!({
+                    "source": "bookedblocks",
+                    "time": clock::now().timestamp(),
+                    "message": message,
+                }),
+            ),
+            AlertSink::Telegram { token, chat } => (
+                format!("https://api.telegram.org/bot{}/sendMessage", token),
+                json!({"chat_id": chat, "text": message}),
+            ),
+            AlertSink::Discord { channel, .. } => (
+                format!("https://discord.com/api/v10/channels/{}/messages", channel),
+                json!({"content": message}),
+            ),
+        };
+        let mut request = Request::post(url).header("Content-Type", "application/json");
+        if let AlertSink::Discord { token, .. } = self {
+            request = request.header("Authorization", format!("Bot {}", token));
+        }
+        Ok(request.body(body.to_string())?)
+    }
+
+    /// Forward a message to the sink
+    pub async fn send(&self, message: &str) -> Result<(), BookedError> {
+        let client: Client<_, String> =
+            Client::builder(TokioExecutor::new()).build(HttpsConnector::new());
+        let response = client.request(self.request(message)?).await?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(BookedError::Status {
+                service: self.name(),
+                status: response.status(),
+            }),
+        }
+    }
+}
+
+/// Show a message as a desktop notification through notify-send
+pub fn notify_desktop(message: &str) -> Result<(), BookedError> {
+    tokio::process::Command::new("notify-send")
+        .arg("bookedblocks")
+        .arg(message)
+        .spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walls::Side;
+
+    #[test]
+    fn test_alert_sink() {
+        assert_eq!(
+            AlertSink::parse("telegram:123:ABC@@alerts").unwrap(),
+            AlertSink::Telegram {
+                token: "123:ABC".to_string(),
+                chat: "@alerts".to_string(),
+            }
+        );
+        assert!(AlertSink::parse("webhook:example.com").is_err());
+        assert!(AlertSink::parse("discord:token").is_err());
+        assert!(AlertSink::parse("slack:token@channel").is_err());
+
+        let request = AlertSink::parse("discord:secret@42")
+            .unwrap()
+            .request("BTC/USD mid crossed 100")
+            .unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://discord.com/api/v10/channels/42/messages"
+        );
+        assert_eq!(request.headers()["Authorization"], "Bot secret");
+        assert_eq!(request.body(), r#"{"content":"BTC/USD mid crossed 100"}"#);
+    }
+
+    #[test]
+    fn test_alert_engine() {
+        assert_eq!(
+            AlertRule::parse("btc/usd spread 10bps").unwrap(),
+            AlertRule {
+                symbol: "BTC/USD".to_string(),
+                condition: Condition::SpreadAbove(10.0),
+            }
+        );
+        assert!(AlertRule::parse("BTC/USD price").is_err());
+        assert!(AlertRule::parse("BTC/USD volume 10").is_err());
+        assert!(AlertRule::parse("BTC/USD wall -5").is_err());
+        assert!(AlertRule::parse("BTC/USD price 10bps").is_err());
+
+        let mut engine = AlertEngine::default();
+        engine.add(AlertRule::parse("BTC/USD price 100").unwrap());
+        engine.add(AlertRule::parse("BTC/USD spread 50").unwrap());
+        engine.add(AlertRule::parse("BTC/USD wall 5").unwrap());
+
+        // nothing crossed on the first book, the spread of 100bps starts above the threshold
+        assert_eq!(engine.check_book("BTC/USD", 99.5, 98.5).len(), 1);
+        assert!(engine.check_book("ETH/USD", 101.0, 100.0).is_empty());
+        // the mid crosses up while the spread stays above the threshold
+        assert_eq!(
+            engine.check_book("BTC/USD", 101.0, 100.0),
+            vec!["BTC/USD mid crossed 100 at 100.5"]
+        );
+        assert!(engine.check_book("BTC/USD", 100.6, 100.5).is_empty());
+        // the spread widens again and the mid crosses down
+        assert_eq!(engine.check_book("BTC/USD", 100.0, 99.0).len(), 2);
+
+        let mut wall = Wall {
+            side: Side::Bid,
+            price: 99.0,
+            quantity: 4.0,
+            start: 0,
+            end: None,
+        };
+        assert!(engine.check_wall("BTC/USD", &wall).is_empty());
+        wall.quantity = 6.0;
+        assert_eq!(engine.check_wall("BTC/USD", &wall).len(), 1);
+        engine.clear();
+        assert!(engine.check_wall("BTC/USD", &wall).is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::actions::{Action, Query};
+use crate::error::BookedError;
+use crate::feed::parse_symbol;
+use crate::pipeline::{BookHistory, SplattedBlocks};
+
+use hyper::body::Incoming;
+use hyper::header::{CONTENT_TYPE, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::json;
+
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+use tokio::task::spawn;
+use tracing::warn;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+/// Serve the REST API at an address, its queries being answered through the action queue,
+/// returning the address listened at
+pub async fn serve(address: SocketAddr, sender: Sender<Action>) -> Result<SocketAddr, BookedError> {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(message) => return Err(BookedError::Server(format!("{:?}", message))),
+    };
+    let address = match listener.local_addr() {
+        Ok(address) => address,
+        Err(message) => return Err(BookedError::Server(format!("{:?}", message))),
+    };
+    spawn(accept(listener, sender));
+    Ok(address)
+}
+
+/// private utility method accepting connections until the application exits
+async fn accept(listener: TcpListener, sender: Sender<Action>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(message) => {
+                warn!(%message, "could not accept http client");
+                continue;
+            }
+        };
+        let sender = sender.clone();
+        spawn(async move {
+            let service = service_fn(move |request| handle(request, sender.clone()));
+            if let Err(message) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!(%message, "http connection failed");
+            }
+        });
+    }
+}
+
+/// private utility method answering a request with JSON
+async fn handle(
+    request: Request<Incoming>,
+    sender: Sender<Action>,
+) -> Result<Response<String>, Infallible> {
+    let (status, body) = answer(request.method(), request.uri().path(), &sender).await;
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Ok(response)
+}
+
+/// private utility method answering a path with a status and JSON body
+async fn answer(method: &Method, path: &str, sender: &Sender<Action>) -> (StatusCode, String) {
+    let error = |message: &str| json!({ "error": message }).to_string();
+    if method != Method::GET {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            error("Only GET is supported"),
+        );
+    }
+    let Some(query) = route(path) else {
+        return (
+            StatusCode::NOT_FOUND,
+            error("Expected /symbols, /book/{symbol} or /heatmap/{symbol}"),
+        );
+    };
+    let (responder, answered) = oneshot::channel();
+    if sender.send(Action::Query(query, responder)).await.is_err() {
+        return (StatusCode::SERVICE_UNAVAILABLE, error("Shutting down"));
+    }
+    match answered.await {
+        Ok(Some(body)) => (StatusCode::OK, body),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            error("Nothing cached for this symbol"),
+        ),
+        Err(_) => (StatusCode::SERVICE_UNAVAILABLE, error("Query dropped")),
+    }
+}
+
+/// query of a path, symbols being written with a plain or percent encoded slash
+pub fn route(path: &str) -> Option<Query> {
+    let path = path.replace("%2F", "/").replace("%2f", "/");
+    if path == "/symbols" {
+        return Some(Query::Symbols);
+    }
+    if let Some(symbol) = path.strip_prefix("/book/") {
+        return parse_symbol(symbol).ok().map(Query::Book);
+    }
+    if let Some(symbol) = path.strip_prefix("/heatmap/") {
+        return parse_symbol(symbol).ok().map(Query::Heatmap);
+    }
+    None
+}
+
+/// answer to the symbols query, in alphabetical order
+pub fn symbols_json<'a>(symbols: impl Iterator<Item = &'a String>) -> String {
+    let mut symbols = symbols.collect::<Vec<_>>();
+    symbols.sort();
+    json!(symbols).to_string()
+}
+
+/// answer to the book query, asks from the best up and bids from the best down, None before
+/// the first book
+pub async fn book_json(symbol: &str, history: &BookHistory) -> Option<String> {
+    let ((time, asks), (_, bids)) = history.get_latest_book().await;
+    if asks.is_empty() && bids.is_empty() {
+        return None;
+    }
+    let asks = asks
+        .iter()
+        .map(|(price, quantity)| [price.value, *quantity])
+        .collect::<Vec<_>>();
+    let bids = bids
+        .iter()
+        .rev()
+        .map(|(price, quantity)| [price.value, *quantity])
+        .collect::<Vec<_>>();
+    Some(
+        json!({
+            "symbol": symbol,
+            "time": time,
+            "asks": asks,
+            "bids": bids,
+        })
+        .to_string(),
+    )
+}
+
+/// answer to the heatmap query, the resting volumes given as one row of prices per time
+pub fn heatmap_json(symbol: &str, blocks: &SplattedBlocks) -> String {
+    let volumes = blocks
+        .volumes
+        .rows()
+        .into_iter()
+        .map(|row| row.to_vec())
+        .collect::<Vec<_>>();
+    json!({
+        "symbol": symbol,
+        "mode": format!("{:?}", blocks.mode),
+        "time_range": blocks.grid.time_range,
+        "price_axis": blocks.grid.price_axis(),
+        "price_scale": format!("{:?}", blocks.grid.price_scale),
+        "volumes": volumes,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn test_api() {
+        assert_eq!(route("/symbols"), Some(Query::Symbols));
+        assert_eq!(
+            route("/book/btc/usd"),
+            Some(Query::Book("BTC/USD".to_string()))
+        );
+        assert_eq!(
+            route("/heatmap/ETH%2FUSD"),
+            Some(Query::Heatmap("ETH/USD".to_string()))
+        );
+        assert_eq!(route("/book/BTC"), None);
+        assert_eq!(route("/orders"), None);
+
+        let (sender, mut receiver) = channel(8);
+        let address = serve("127.0.0.1:0".parse().unwrap(), sender).await.unwrap();
+        spawn(async move {
+            while let Some(Action::Query(query, responder)) = receiver.recv().await {
+                let symbols = ["BTC/USD".to_string()];
+                let _ = responder.send(match query {
+                    Query::Symbols => Some(symbols_json(symbols.iter())),
+                    _ => None,
+                });
+            }
+        });
+
+        let request = |path: &str| {
+            let request = format!(
+                "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                path
+            );
+            async move {
+                let mut stream = TcpStream::connect(address).await.unwrap();
+                stream.write_all(request.as_bytes()).await.unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                response
+            }
+        };
+        let response = request("/symbols").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("content-type: application/json"));
+        assert!(response.ends_with(r#"["BTC/USD"]"#));
+        assert!(
+            request("/book/BTC/USD")
+                .await
+                .starts_with("HTTP/1.1 404 Not Found")
+        );
+    }
+}
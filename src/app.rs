@@ -1,65 +1,776 @@
-use crate::actions::Action;
-use crate::feed::TickerState;
-use crate::pipeline::{SplattedBlocks, SplattedDepth, SplattedVolumes};
+use crate::actions::{Action, ActionSender};
+use crate::cadence::Cadence;
+use crate::clock;
+use crate::config::save_layout;
+use crate::daily::DailyStats;
+use crate::error::BookedError;
+use crate::export::ExportFormat;
+use crate::feed::{TickerState, parse_symbol};
+use crate::input::{Command, Input, InputWidget, history_path, load_history, save_history};
+use crate::labels::{Labels, TimeLabels, volume_label};
+use crate::layout::{Focus, LayoutAreas, Panel, PanelLayout, RESIZE_STEP};
+use crate::logs::{LogBuffer, Severity};
+use crate::metrics::{Diagnostics, HandlerStats};
+use crate::orders::{Account, OwnOrder};
+use crate::pipeline::{
+    BasisOperation, DepthMode, Fill, Level, PriceScale, Splatted, SplattedActivity,
+    SplattedArbitrage, SplattedBasis, SplattedBlocks, SplattedDepth, SplattedFlicker,
+    SplattedImbalance, SplattedImpact, SplattedLadder, SplattedLevels, SplattedPrices,
+    SplattedProfile, SplattedQueue, SplattedSlippage, SplattedTrades, SplattedTwaDepth,
+    SplattedVolatility, SplattedVolumes, StageTiming, widen,
+};
+use crate::plugins::{PanelContext, PanelPlugins, registered_panels};
+use crate::recording::RecordingInfo;
+use crate::scripting::Indicators;
+use crate::splat::SplatMode;
+use crate::terminal::TerminalGuard;
+use crate::theme::{ColorDepth, Gradient, HeatColors, Theme};
+use crate::trading::OrderTicket;
+use crate::walls::{Side, Wall};
 
+use chrono::DateTime;
+use clap::ValueEnum;
 use crossterm::event::{self, Event};
-use ratatui::Frame;
-use ratatui::layout::{Alignment, Constraint, Layout};
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Layout, Position, Rect};
 use ratatui::style::{Color, Style, Stylize};
 use ratatui::symbols;
-use ratatui::text::Text;
-use ratatui::widgets::{Axis, Block, Chart, Dataset, GraphType, Paragraph, Widget};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{
+    Axis, Block, Chart, Clear, Dataset, GraphType, Paragraph, Row, Table, Widget, Wrap,
+};
+use ratatui::{Frame, Terminal};
 
 use tokio::sync::Mutex;
-use tokio::sync::mpsc::Sender;
-use tokio::task::{JoinHandle, spawn};
-use tokio::time::{Duration, interval};
+use tokio::task::{JoinHandle, spawn, spawn_blocking};
+use tokio::time::{Duration, sleep};
+use tracing::warn;
 
+use std::cmp::max;
 use std::collections::HashMap;
+use std::iter::{once, zip};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Number of request periods between two pipeline runs of a ticker in a background tab
+const BACKGROUND_PIPELINE_PERIOD: usize = 5;
+
+/// Number of times the pipeline request loop is restarted before giving up on the interface
+const MAX_RESTARTS: usize = 5;
+
 /// Enum of different pages one could move to in application
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Page {
+    Library,
     Search,
     Logs,
+    Stats,
     Ticker,
+    Watchlist,
+    Diagnostics,
+    Account,
+    Indicators,
+}
+
+/// Seconds a newly raised alert stands out as a highlighted banner
+const ALERT_BANNER_SECONDS: i64 = 10;
+
+/// Number of times the time axis of the order map can be halved by zooming in
+const MAX_TIME_ZOOM: u32 = 6;
+
+/// Fraction of the displayed time range the arrow keys pan by
+const PAN_FRACTION: f64 = 0.1;
+
+/// Columns and rows of screenshots taken when the size of the terminal is unknown
+const SCREENSHOT_SIZE: (u16, u16) = (120, 40);
+
+/// Pages cycled through with the bracket keys, in order
+const CYCLED_PAGES: [Page; 7] = [
+    Page::Ticker,
+    Page::Watchlist,
+    Page::Stats,
+    Page::Diagnostics,
+    Page::Account,
+    Page::Indicators,
+    Page::Logs,
+];
+
+/// Levels of the characters mid price sparklines are drawn with, lowest first
+const SPARKLINE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Command bound to a key on the ticker, watchlist, stats, diagnostics and logs pages
+#[derive(Clone, Copy, Debug)]
+enum Binding {
+    Help,
+    Prompt,
+    Search,
+    Unsubscribe,
+    NextTicker,
+    PreviousTicker,
+    Split,
+    Freeze,
+    NextPage,
+    PreviousPage,
+    Watchlist,
+    Stats,
+    Diagnostics,
+    Account,
+    Indicators,
+    Logs,
+    Library,
+    Slippage,
+    Splat,
+    Depth,
+    ColorScale,
+    Theme,
+    Export,
+    Screenshot,
+    Crosshair,
+    ZoomIn,
+    ZoomOut,
+    PanBack,
+    PanForward,
+    Live,
+    Oldest,
+    ResetTime,
+    TogglePanel(Panel),
+    ResizeRows(i16),
+    ResizeColumns(i16),
+    Fullscreen,
+    Focus,
+    Slower,
+    Faster,
+    Quit,
+}
+
+/// Keys bound on the ticker, watchlist, stats, diagnostics, account, indicators and logs pages, in
+/// the order listed by the help overlay
+const KEY_BINDINGS: [(event::KeyCode, &str, Binding); 46] = [
+    (
+        event::KeyCode::Char('?'),
+        "show or hide this help",
+        Binding::Help,
+    ),
+    (
+        event::KeyCode::Char(':'),
+        "open the command prompt",
+        Binding::Prompt,
+    ),
+    (
+        event::KeyCode::Char('/'),
+        "search a symbol to subscribe to",
+        Binding::Search,
+    ),
+    (
+        event::KeyCode::Char('u'),
+        "unsubscribe from the current symbol",
+        Binding::Unsubscribe,
+    ),
+    (event::KeyCode::Tab, "next symbol tab", Binding::NextTicker),
+    (
+        event::KeyCode::BackTab,
+        "previous symbol tab",
+        Binding::PreviousTicker,
+    ),
+    (
+        event::KeyCode::Char('v'),
+        "display the next tab side by side",
+        Binding::Split,
+    ),
+    (
+        event::KeyCode::Char(' '),
+        "freeze or resume the displayed data",
+        Binding::Freeze,
+    ),
+    (
+        event::KeyCode::Char('p'),
+        "freeze or resume the displayed data",
+        Binding::Freeze,
+    ),
+    (
+        event::KeyCode::Char(']'),
+        "next of the ticker, watchlist, stats, diagnostics, account, indicators and logs pages",
+        Binding::NextPage,
+    ),
+    (
+        event::KeyCode::Char('['),
+        "previous of the ticker, stats and logs pages",
+        Binding::PreviousPage,
+    ),
+    (
+        event::KeyCode::Char('w'),
+        "watchlist page",
+        Binding::Watchlist,
+    ),
+    (event::KeyCode::Char('m'), "stats page", Binding::Stats),
+    (
+        event::KeyCode::Char('g'),
+        "diagnostics page",
+        Binding::Diagnostics,
+    ),
+    (event::KeyCode::Char('a'), "account page", Binding::Account),
+    (
+        event::KeyCode::Char('i'),
+        "series plotted by scripts",
+        Binding::Indicators,
+    ),
+    (event::KeyCode::Char('l'), "logs page", Binding::Logs),
+    (
+        event::KeyCode::Char('r'),
+        "library of recordings",
+        Binding::Library,
+    ),
+    (
+        event::KeyCode::Char('s'),
+        "estimate the slippage of a quantity",
+        Binding::Slippage,
+    ),
+    (
+        event::KeyCode::Char('b'),
+        "cycle the splat mode",
+        Binding::Splat,
+    ),
+    (
+        event::KeyCode::Char('c'),
+        "toggle the depth mode",
+        Binding::Depth,
+    ),
+    (
+        event::KeyCode::Char('n'),
+        "cycle the order map color scale",
+        Binding::ColorScale,
+    ),
+    (
+        event::KeyCode::Char('t'),
+        "cycle the color theme",
+        Binding::Theme,
+    ),
+    (
+        event::KeyCode::Char('e'),
+        "export the current data to npy",
+        Binding::Export,
+    ),
+    (
+        event::KeyCode::Char('P'),
+        "screenshot the current frame",
+        Binding::Screenshot,
+    ),
+    (
+        event::KeyCode::Char('x'),
+        "show or hide the crosshair",
+        Binding::Crosshair,
+    ),
+    (
+        event::KeyCode::Char('+'),
+        "zoom in on the time axis",
+        Binding::ZoomIn,
+    ),
+    (
+        event::KeyCode::Char('-'),
+        "zoom out of the time axis",
+        Binding::ZoomOut,
+    ),
+    (
+        event::KeyCode::Left,
+        "scrub back through the cached books",
+        Binding::PanBack,
+    ),
+    (
+        event::KeyCode::Right,
+        "scrub forward to newer books",
+        Binding::PanForward,
+    ),
+    (
+        event::KeyCode::End,
+        "snap back to the live books",
+        Binding::Live,
+    ),
+    (
+        event::KeyCode::Home,
+        "scrub back to the oldest cached books",
+        Binding::Oldest,
+    ),
+    (
+        event::KeyCode::Char('0'),
+        "reset the order map zoom and pan",
+        Binding::ResetTime,
+    ),
+    (
+        event::KeyCode::Char('M'),
+        "show or hide the order map",
+        Binding::TogglePanel(Panel::Map),
+    ),
+    (
+        event::KeyCode::Char('D'),
+        "show or hide the depth",
+        Binding::TogglePanel(Panel::Depth),
+    ),
+    (
+        event::KeyCode::Char('V'),
+        "show or hide the time series",
+        Binding::TogglePanel(Panel::Volumes),
+    ),
+    (
+        event::KeyCode::Char('T'),
+        "show or hide the ticker",
+        Binding::TogglePanel(Panel::Ticker),
+    ),
+    (
+        event::KeyCode::Char('}'),
+        "grow the top row of panels",
+        Binding::ResizeRows(RESIZE_STEP),
+    ),
+    (
+        event::KeyCode::Char('{'),
+        "shrink the top row of panels",
+        Binding::ResizeRows(-RESIZE_STEP),
+    ),
+    (
+        event::KeyCode::Char('>'),
+        "grow the left column of panels",
+        Binding::ResizeColumns(RESIZE_STEP),
+    ),
+    (
+        event::KeyCode::Char('<'),
+        "shrink the left column of panels",
+        Binding::ResizeColumns(-RESIZE_STEP),
+    ),
+    (
+        event::KeyCode::Char('f'),
+        "fill the terminal with the focused widget",
+        Binding::Fullscreen,
+    ),
+    (
+        event::KeyCode::Char('F'),
+        "focus the next widget",
+        Binding::Focus,
+    ),
+    (
+        event::KeyCode::Char('('),
+        "refresh half as often",
+        Binding::Slower,
+    ),
+    (
+        event::KeyCode::Char(')'),
+        "refresh twice as often",
+        Binding::Faster,
+    ),
+    (event::KeyCode::Char('q'), "quit", Binding::Quit),
+];
+
+/// Default key a pressed key stands for, None for default keys bound elsewhere
+fn default_key(
+    rebound: &HashMap<event::KeyCode, event::KeyCode>,
+    code: event::KeyCode,
+) -> Option<event::KeyCode> {
+    match rebound.get(&code) {
+        Some(default) => Some(*default),
+        None if rebound.values().any(|default| *default == code) => None,
+        None => Some(code),
+    }
+}
+
+/// Name of a key as shown in the help overlay
+fn key_name(code: event::KeyCode) -> String {
+    match code {
+        event::KeyCode::Char(' ') => "Space".to_string(),
+        event::KeyCode::Char(character) => character.to_string(),
+        event::KeyCode::BackTab => "Shift+Tab".to_string(),
+        code => format!("{:?}", code),
+    }
+}
+
+/// Whether the order map ends on the live books or was scrubbed back into the cached history,
+/// along with its zoom
+fn time_travel_line(state: &State) -> Line<'static> {
+    let zoom = match state.time_zoom {
+        0 => String::new(),
+        zoom => format!("x{} zoom ", 1 << zoom),
+    };
+    if state.time_pan == 0 {
+        return Line::from(vec![
+            Span::from(" ● LIVE ").fg(state.theme.positive()).bold(),
+            Span::from(zoom),
+        ]);
+    }
+    let end = state
+        .current_ticker
+        .as_ref()
+        .and_then(|symbol| state.last_updates.get(symbol))
+        .and_then(|time| DateTime::from_timestamp(time / 1000 - state.time_pan, 0));
+    let end = match end {
+        Some(end) => format!("at {} ", end.format("%H:%M:%S")),
+        None => String::new(),
+    };
+    Line::from(vec![
+        Span::from(" ◀ HISTORICAL ").yellow().bold(),
+        Span::from(format!(
+            "{}s back {}{}(End for live) ",
+            state.time_pan, end, zoom
+        ))
+        .yellow(),
+    ])
+}
+
+/// Status of the feed and of the current symbol shown at the bottom of every page
+fn status_line(state: &State) -> Line<'static> {
+    let connection = match state.connected {
+        Some(true) => Span::from(" live ").fg(state.theme.positive()).bold(),
+        Some(false) => Span::from(" disconnected ")
+            .fg(state.theme.negative())
+            .bold(),
+        None => Span::from(" connecting ").fg(state.theme.accent()),
+    };
+    let symbol = state
+        .current_ticker
+        .clone()
+        .unwrap_or("no symbol".to_string());
+    let window = match state.time_window {
+        Some(window) => format!("{}s window", window),
+        None => "no window".to_string(),
+    };
+    let age = match state
+        .current_ticker
+        .as_ref()
+        .and_then(|symbol| state.last_updates.get(symbol))
+    {
+        Some(time) => {
+            let age = (clock::now().timestamp_millis() - time).max(0) as f64 / 1000.0;
+            format!("updated {:.1}s ago", age)
+        }
+        None => "no update yet".to_string(),
+    };
+    let rate = format!("{:.1} msgs/s", state.message_rate);
+    let theme = format!("{} theme", state.theme.name());
+    let focus = format!("{} focused", state.focus.name());
+
+    let mut spans = vec![connection];
+    for field in [symbol, window, age, rate, theme, focus] {
+        spans.push(Span::from("| ").dark_gray());
+        spans.push(Span::from(format!("{} ", field)));
+    }
+    Line::from(spans)
+}
+
+/// Areas of the panels drawn in the last frame, used to route mouse events
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PanelAreas {
+    /// order map of the current ticker
+    pub map: Option<Rect>,
+    /// half of the split view displaying the other symbol
+    pub partner: Option<Rect>,
+}
+
+/// Data computed for one subscribed symbol, kept while its tab is not visible
+#[derive(Clone, Debug, Default)]
+pub struct SymbolView {
+    pub ticker_data: Option<TickerState>,
+    pub splatted: Vec<Splatted>,
+    pub walls: Option<Vec<Wall>>,
+    pub daily: Option<DailyStats>,
+    /// number of decimals the prices of the symbol are quoted with, once fetched
+    pub price_decimals: Option<u32>,
+    /// orders of the user resting in the book of the symbol
+    pub own_orders: Vec<OwnOrder>,
+    /// time taken by the last pipeline run of the symbol
+    pub pipeline_elapsed: Option<Duration>,
+    /// series plotted by scripts for the symbol
+    pub indicators: Indicators,
 }
 
 /// State data structure relevant to rendering interface
 #[derive(Clone, Debug)]
 pub struct State {
     pub page: Page,
-    pub sender: Sender<Action>,
+    pub sender: ActionSender,
     pub tickers: Option<Vec<String>>,
     pub current_ticker: Option<String>,
-    pub ticker_data: Option<TickerState>,
-    pub depth: Option<SplattedDepth>,
-    pub volumes: Option<SplattedVolumes>,
-    pub blocks: Option<SplattedBlocks>,
+    /// symbol displayed side by side with the current one, None to display it alone
+    pub split: Option<String>,
+    /// data of every subscribed symbol, the current one being displayed
+    pub views: HashMap<String, SymbolView>,
+    pub slippage_quantity: Option<f64>,
+    pub slippage: Option<SplattedSlippage>,
+    pub queue_price: Option<f64>,
+    pub queue: Option<SplattedQueue>,
+    pub arbitrage: Option<SplattedArbitrage>,
+    pub basis: Option<SplattedBasis>,
+    /// number of (time, price) points the order map panel can display
+    pub map_resolution: Option<(usize, usize)>,
+    pub alert: Option<String>,
+    /// time in seconds the alert was raised at
+    pub alert_time: Option<i64>,
+    pub command: Input,
+    /// file the command history is kept in between sessions, None to not keep it
+    pub history_file: Option<PathBuf>,
+    pub prompting: bool,
+    /// whether the help overlay listing key bindings is shown
+    pub help: bool,
+    /// recoverable error shown in a modal until dismissed
+    pub error: Option<String>,
+    /// whether orders are only validated by the exchange, None while order entry is disabled
+    pub trading: Option<bool>,
+    /// order shown in a modal until the user confirms or discards it
+    pub confirming: Option<OrderTicket>,
+    /// balances and open orders of the user, None while the account is not followed
+    pub account: Option<Account>,
+    /// number of changes made to the state, the interface only being drawn again once it moved
+    pub generation: u64,
+    /// periods at which the interface is refreshed
+    pub cadence: Cadence,
+    /// keys bound in place of default ones, mapped to the default key they replace
+    pub rebound: HashMap<event::KeyCode, event::KeyCode>,
+    /// data of the symbols displayed while frozen, the live data being updated underneath
+    pub frozen: Option<HashMap<String, SymbolView>>,
+    /// whether mouse events are captured, leaving the native terminal selection otherwise
+    pub mouse_capture: bool,
+    /// number of times the time axis of the order map is halved by zooming in
+    pub time_zoom: u32,
+    /// seconds the time axis of the order map is panned back from the latest book
+    pub time_pan: i64,
+    /// column the order map is being dragged from
+    pub drag: Option<u16>,
+    /// point of the order map read out, as fractions of its time and price axes
+    pub crosshair: Option<(f64, f64)>,
+    pub areas: PanelAreas,
+    pub search: Input,
+    pub library: Vec<RecordingInfo>,
+    pub library_selection: usize,
+    /// index of the symbol selected in the watchlist page
+    pub watchlist_selection: usize,
+    pub metrics: Vec<HandlerStats>,
+    /// most recent messages informed and warned about
+    pub logs: LogBuffer,
+    /// number of entries the logs page is scrolled up from the most recent one
+    pub logs_scroll: usize,
+    /// bytes held by the cached book histories along with their budget, if any
+    pub cache_memory: Option<(usize, Option<usize>)>,
+    /// time taken by every step of the last pipeline run
+    pub pipeline_timings: Vec<StageTiming>,
+    /// health of the session since startup
+    pub diagnostics: Diagnostics,
+    /// whether the feed is still listening to the exchange, None until first checked
+    pub connected: Option<bool>,
+    /// feed messages received per second over the last few seconds
+    pub message_rate: f64,
+    /// exchange time in milliseconds of the last book update of every symbol
+    pub last_updates: HashMap<String, i64>,
+    /// length in seconds of the time range displayed by the order map
+    pub time_window: Option<u64>,
+    /// length in seconds of the history cached for the displayed symbol, bounding the pan
+    pub time_cached: Option<i64>,
+    pub preview: Option<(String, SplattedDepth)>,
+    pub color_scale: ColorScale,
+    /// colors the interface is drawn with
+    pub theme: Theme,
+    /// gradient the order map volumes are colored along
+    pub gradient: Gradient,
+    /// colors the terminal displays
+    pub color_depth: ColorDepth,
+    /// symbol the order map cells and chart bars are drawn with
+    pub marker: PlotMarker,
+    /// arrangement of the ticker page panels
+    pub layout: PanelLayout,
+    /// configuration file the layout is kept in, None to not keep it
+    pub layout_file: Option<PathBuf>,
+    /// widget of the ticker page acted upon by full screen
+    pub focus: Focus,
+    /// whether the focused widget fills the whole terminal
+    pub fullscreen: bool,
+    /// way time axes are labelled
+    pub time_labels: TimeLabels,
+    /// panels registered by third-party crates, drawn below the ticker panel
+    pub plugins: PanelPlugins,
+}
+
+impl State {
+    /// mark the state as changed, for the interface to be drawn again
+    pub fn touch(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// copy of the state to draw, None while unchanged since the generation last drawn
+    pub fn changed(&self, drawn_generation: &mut Option<u64>) -> Option<State> {
+        if *drawn_generation == Some(self.generation) {
+            return None;
+        }
+        *drawn_generation = Some(self.generation);
+        Some(self.clone())
+    }
+
+    /// formatting of the axis labels of a symbol quoted with a number of decimals
+    pub fn labels(&self, price_decimals: Option<u32>) -> Labels {
+        Labels {
+            time: self.time_labels,
+            price_decimals,
+        }
+    }
+
+    /// colors of the order map cells in the current theme
+    pub fn heat_colors(&self) -> HeatColors {
+        HeatColors {
+            theme: self.theme,
+            scale: self.color_scale,
+            gradient: self.gradient,
+            depth: self.color_depth,
+        }
+    }
+
+    /// symbol displayed side by side with a ticker when it is one of the two split symbols
+    pub fn split_partner(&self, ticker: &str) -> Option<String> {
+        match (&self.current_ticker, &self.split) {
+            (Some(current), Some(split)) if current != split => {
+                if current == ticker {
+                    Some(split.clone())
+                } else if split == ticker {
+                    Some(current.clone())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Title of a price axis, in basis points when prices are displayed around a peg
+fn price_title(peg: &Option<f64>, scale: &PriceScale) -> String {
+    let title = match peg {
+        Some(peg) => format!("Price (bp from {:})", peg),
+        None => "Price".to_string(),
+    };
+    match scale {
+        PriceScale::Linear => title,
+        PriceScale::Log => format!("{} log", title),
+    }
+}
+
+/// Label of a price axis tick, in basis points when prices are displayed around a peg
+fn price_label(price: f64, peg: &Option<f64>, labels: &Labels) -> String {
+    match peg {
+        Some(peg) => format!("{:+.1}", (price / peg - 1.0) * 10_000.0),
+        None => labels.price(price),
+    }
+}
+
+/// Label of a support or resistance level, in basis points when prices are displayed around a peg
+fn level_label(level: &Level, peg: &Option<f64>, labels: &Labels) -> String {
+    let kind = match level.side {
+        Side::Bid => "S",
+        Side::Ask => "R",
+    };
+    format!(
+        "{} {} ({:.0}%)",
+        kind,
+        price_label(level.price, peg, labels),
+        level.persistence * 100.0
+    )
 }
 
 /// Widget for rendering TickerState in interface
 struct TickerWidget {
     state: TickerState,
+    daily: Option<DailyStats>,
+    volatility: Option<SplattedVolatility>,
+    impact: Option<SplattedImpact>,
+    twa_depth: Option<SplattedTwaDepth>,
+    theme: Theme,
+    labels: Labels,
 }
 
 impl TickerWidget {
     /// constructor
-    pub fn new(state: TickerState) -> TickerWidget {
-        TickerWidget { state }
+    pub fn new(
+        state: TickerState,
+        daily: Option<DailyStats>,
+        volatility: Option<SplattedVolatility>,
+        impact: Option<SplattedImpact>,
+        twa_depth: Option<SplattedTwaDepth>,
+        theme: Theme,
+        labels: Labels,
+    ) -> TickerWidget {
+        TickerWidget {
+            state,
+            daily,
+            volatility,
+            impact,
+            twa_depth,
+            theme,
+            labels,
+        }
     }
 }
 
 impl Widget for TickerWidget {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
-        let vchunks = Layout::vertical(vec![
-            Constraint::Percentage(2),
-            Constraint::Percentage(48),
-            Constraint::Percentage(48),
-            Constraint::Percentage(2),
-        ])
-        .split(area.clone());
+        // statistics computed locally go in an extra row below the exchange's figures
+        let mut local_values = Vec::new();
+        if let Some(daily) = &self.daily {
+            local_values.push((
+                format!("Open {} {:?}", daily.day, daily.rollover),
+                self.labels.price(daily.open),
+            ));
+            local_values.push(("Day High".to_string(), self.labels.price(daily.high)));
+            local_values.push(("Day Low".to_string(), self.labels.price(daily.low)));
+            local_values.push(("Day Volume".to_string(), volume_label(daily.volume)));
+        }
+        if let Some(volatility) = &self.volatility {
+            local_values.push((
+                format!("Realized Vol {}s", volatility.window_in_seconds),
+                match volatility.latest() {
+                    Some(latest) => format!("{:.1} %", latest * 100.0),
+                    None => "-".to_string(),
+                },
+            ));
+        }
+        if let Some(impact) = &self.impact {
+            local_values.push((
+                format!("Kyle Lambda n={}", impact.samples),
+                match (impact.lambda, impact.r_squared) {
+                    (Some(lambda), Some(r_squared)) => {
+                        format!("{:.2e} (R² {:.2})", lambda, r_squared)
+                    }
+                    _ => "-".to_string(),
+                },
+            ));
+        }
+        if let Some(twa_depth) = &self.twa_depth {
+            let format_depth = |depth: Option<f64>| match depth {
+                Some(depth) => format!("{:.2}", depth),
+                None => "-".to_string(),
+            };
+            local_values.push((
+                format!(
+                    "TWA Depth {}s",
+                    twa_depth.time_range.1 - twa_depth.time_range.0
+                ),
+                format!(
+                    "{} / {}",
+                    format_depth(twa_depth.bid_depth),
+                    format_depth(twa_depth.ask_depth)
+                ),
+            ));
+        }
+
+        let vchunks = if local_values.is_empty() {
+            Layout::vertical(vec![
+                Constraint::Percentage(2),
+                Constraint::Percentage(48),
+                Constraint::Percentage(48),
+                Constraint::Percentage(2),
+            ])
+            .split(area)
+        } else {
+            Layout::vertical(vec![
+                Constraint::Percentage(2),
+                Constraint::Percentage(32),
+                Constraint::Percentage(32),
+                Constraint::Percentage(32),
+                Constraint::Percentage(2),
+            ])
+            .split(area)
+        };
 
         let top_chunks = Layout::horizontal(vec![
             Constraint::Percentage(2),
@@ -81,14 +792,14 @@ impl Widget for TickerWidget {
         ])
         .split(vchunks[2]);
 
-        let green_bold = Style::new().green().bold();
-        let red_bold = Style::new().red().bold();
+        let positive_bold = Style::new().fg(self.theme.positive()).bold();
+        let negative_bold = Style::new().fg(self.theme.negative()).bold();
         let just_bold = Style::new().bold();
 
         let ask_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.ask))
+            Text::from(self.labels.price(self.state.ask))
                 .alignment(Alignment::Center)
-                .style(green_bold.clone()),
+                .style(positive_bold.clone()),
         )
         .block(Block::bordered().title("Ask"))
         .alignment(Alignment::Center);
@@ -96,9 +807,9 @@ impl Widget for TickerWidget {
         ask_widget.render(top_chunks[1], buf);
 
         let bid_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.bid))
+            Text::from(self.labels.price(self.state.bid))
                 .alignment(Alignment::Center)
-                .style(red_bold.clone()),
+                .style(negative_bold.clone()),
         )
         .block(Block::bordered().title("Bid"))
         .alignment(Alignment::Center);
@@ -112,9 +823,9 @@ impl Widget for TickerWidget {
             ))
             .alignment(Alignment::Center)
             .style(if self.state.change < 0.0 {
-                red_bold.clone()
+                negative_bold.clone()
             } else {
-                green_bold.clone()
+                positive_bold.clone()
             }),
         )
         .block(Block::bordered().title("24hr Change"))
@@ -123,7 +834,7 @@ impl Widget for TickerWidget {
         change_widget.render(top_chunks[2], buf);
 
         let last_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.last))
+            Text::from(self.labels.price(self.state.last))
                 .alignment(Alignment::Center)
                 .style(just_bold.clone()),
         )
@@ -133,9 +844,9 @@ impl Widget for TickerWidget {
         last_widget.render(bottom_chunks[2], buf);
 
         let high_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.high))
+            Text::from(self.labels.price(self.state.high))
                 .alignment(Alignment::Center)
-                .style(green_bold.clone()),
+                .style(positive_bold.clone()),
         )
         .block(Block::bordered().title("High"))
         .alignment(Alignment::Center);
@@ -143,9 +854,9 @@ impl Widget for TickerWidget {
         high_widget.render(top_chunks[3], buf);
 
         let low_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.low))
+            Text::from(self.labels.price(self.state.low))
                 .alignment(Alignment::Center)
-                .style(red_bold.clone()),
+                .style(negative_bold.clone()),
         )
         .block(Block::bordered().title("Low"))
         .alignment(Alignment::Center);
@@ -153,7 +864,7 @@ impl Widget for TickerWidget {
         low_widget.render(bottom_chunks[3], buf);
 
         let volume_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.volume))
+            Text::from(volume_label(self.state.volume))
                 .alignment(Alignment::Center)
                 .style(just_bold.clone()),
         )
@@ -163,7 +874,7 @@ impl Widget for TickerWidget {
         volume_widget.render(top_chunks[4], buf);
 
         let vwap_widget = Paragraph::new(
-            Text::from(format!("{:}", self.state.vwap))
+            Text::from(self.labels.price(self.state.vwap))
                 .alignment(Alignment::Center)
                 .style(just_bold.clone()),
         )
@@ -171,33 +882,156 @@ impl Widget for TickerWidget {
         .alignment(Alignment::Center);
 
         vwap_widget.render(bottom_chunks[4], buf);
+
+        if !local_values.is_empty() {
+            let width = 96 / local_values.len() as u16;
+            let local_chunks = Layout::horizontal(
+                once(Constraint::Percentage(2))
+                    .chain(local_values.iter().map(|_| Constraint::Percentage(width)))
+                    .chain(once(Constraint::Percentage(2)))
+                    .collect::<Vec<_>>(),
+            )
+            .split(vchunks[3]);
+
+            for (index, (title, value)) in local_values.into_iter().enumerate() {
+                Paragraph::new(
+                    Text::from(value)
+                        .alignment(Alignment::Center)
+                        .style(just_bold),
+                )
+                .block(Block::bordered().title(title))
+                .alignment(Alignment::Center)
+                .render(local_chunks[index + 1], buf);
+            }
+        }
+    }
+}
+
+/// Widget for rendering the top levels of the latest book as a ladder to interface
+struct LadderWidget {
+    ladder: SplattedLadder,
+    own_orders: Vec<OwnOrder>,
+    theme: Theme,
+    labels: Labels,
+}
+
+impl LadderWidget {
+    pub fn new(
+        ladder: SplattedLadder,
+        own_orders: Vec<OwnOrder>,
+        theme: Theme,
+        labels: Labels,
+    ) -> LadderWidget {
+        LadderWidget {
+            ladder,
+            own_orders,
+            theme,
+            labels,
+        }
+    }
+}
+
+impl Widget for LadderWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        // asks above bids, both sides meeting at the spread, as many levels as the panel fits
+        let per_side = (area.height.saturating_sub(3) / 2) as usize;
+        // levels holding orders of the user stand out along with the quantity they have left
+        let row = |(price, size, cumulative): &(f64, f64, f64)| {
+            let own = self
+                .own_orders
+                .iter()
+                .filter(|order| order.price == *price)
+                .map(|order| order.remaining)
+                .sum::<f64>();
+            let cells = vec![
+                self.labels.price(*price),
+                format!("{:.4}", size),
+                format!("{:.4}", cumulative),
+                match own > 0.0 {
+                    true => format!("{:.4}", own),
+                    false => String::new(),
+                },
+            ];
+            match own > 0.0 {
+                true => Row::new(cells).bold().underlined(),
+                false => Row::new(cells),
+            }
+        };
+        let asks = self
+            .ladder
+            .asks
+            .iter()
+            .take(per_side)
+            .rev()
+            .map(|level| row(level).fg(self.theme.positive()));
+        let bids = self
+            .ladder
+            .bids
+            .iter()
+            .take(per_side)
+            .map(|level| row(level).fg(self.theme.negative()));
+
+        Table::new(
+            asks.chain(bids).collect::<Vec<_>>(),
+            vec![
+                Constraint::Percentage(28),
+                Constraint::Percentage(24),
+                Constraint::Percentage(24),
+                Constraint::Percentage(24),
+            ],
+        )
+        .header(Row::new(vec!["Price", "Size", "Total", "Own"]).bold())
+        .block(Block::bordered().title("Ladder"))
+        .render(area, buf)
     }
 }
 
 /// Widget for rendering market depth to interface
 struct DepthWidget {
     depth: SplattedDepth,
+    levels: Option<SplattedLevels>,
+    theme: Theme,
+    marker: PlotMarker,
+    labels: Labels,
 }
 
 impl DepthWidget {
-    pub fn new(depth: SplattedDepth) -> DepthWidget {
-        DepthWidget { depth }
+    pub fn new(
+        depth: SplattedDepth,
+        levels: Option<SplattedLevels>,
+        theme: Theme,
+        marker: PlotMarker,
+        labels: Labels,
+    ) -> DepthWidget {
+        DepthWidget {
+            depth,
+            levels,
+            theme,
+            marker,
+            labels,
+        }
     }
 }
 
 impl Widget for DepthWidget {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         let x_axis = Axis::default()
-            .title("Price")
+            .title(price_title(&self.depth.peg, &self.depth.price_scale))
             .bounds([self.depth.price_range.0, self.depth.price_range.1])
-            .labels([
-                format!("{:}", self.depth.price_range.0),
-                format!(
-                    "{:}",
-                    (self.depth.price_range.0 + self.depth.price_range.1) / 2.0
-                ),
-                format!("{:}", self.depth.price_range.1),
-            ]);
+            .labels(
+                [
+                    self.depth.price_range.0,
+                    (self.depth.price_range.0 + self.depth.price_range.1) / 2.0,
+                    self.depth.price_range.1,
+                ]
+                .map(|value| {
+                    price_label(
+                        self.depth.price_scale.price(value),
+                        &self.depth.peg,
+                        &self.labels,
+                    )
+                }),
+            );
 
         let max_vol = self.depth.volumes.iter().fold(f64::MIN, |acc, volume| {
             if acc < volume.abs() {
@@ -211,9 +1045,9 @@ impl Widget for DepthWidget {
             .title("Volumes")
             .bounds([-max_vol, max_vol])
             .labels([
-                format!("{:}", max_vol),
-                format!("0.0"),
-                format!("{:}", max_vol),
+                volume_label(max_vol),
+                "0".to_string(),
+                volume_label(max_vol),
             ]);
 
         let step = (self.depth.price_range.1 - self.depth.price_range.0)
@@ -236,9 +1070,9 @@ impl Widget for DepthWidget {
         let ask_dataset = Dataset::default()
             .name("Asks")
             .data(&ask_graph)
-            .marker(symbols::Marker::HalfBlock)
+            .marker(self.marker.symbol())
             .graph_type(GraphType::Bar)
-            .green();
+            .fg(self.theme.positive());
 
         let bid_graph = self
             .depth
@@ -258,12 +1092,45 @@ impl Widget for DepthWidget {
         let bid_dataset = Dataset::default()
             .name("Bids")
             .data(&bid_graph)
-            .marker(symbols::Marker::HalfBlock)
+            .marker(self.marker.symbol())
             .graph_type(GraphType::Bar)
-            .red();
+            .fg(self.theme.negative());
+
+        let level_graphs = self
+            .levels
+            .iter()
+            .flat_map(|levels| levels.levels.iter())
+            .map(|level| {
+                let price = self.depth.price_scale.position(level.price);
+                (
+                    level_label(level, &self.depth.peg, &self.labels),
+                    vec![(price, -max_vol), (price, max_vol)],
+                )
+            })
+            .filter(|(_, graph)| {
+                graph[0].0 >= self.depth.price_range.0 && graph[0].0 <= self.depth.price_range.1
+            })
+            .collect::<Vec<_>>();
+
+        let mut datasets = vec![ask_dataset, bid_dataset];
+        for (label, graph) in level_graphs.iter() {
+            datasets.push(
+                Dataset::default()
+                    .name(label.clone())
+                    .data(graph)
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .gray(),
+            );
+        }
+
+        let title = match self.depth.mode {
+            DepthMode::Density => "Depth",
+            DepthMode::Cumulative => "Cumulative Depth",
+        };
 
-        let chart = Chart::new(vec![ask_dataset, bid_dataset])
-            .block(Block::bordered().title("Depth"))
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(title))
             .x_axis(x_axis)
             .y_axis(y_axis);
 
@@ -274,30 +1141,36 @@ impl Widget for DepthWidget {
 /// Widget for rendering market volumes to interface
 struct VolumeWidget {
     volumes: SplattedVolumes,
+    theme: Theme,
+    marker: PlotMarker,
+    labels: Labels,
 }
 
 impl VolumeWidget {
-    pub fn new(volumes: SplattedVolumes) -> VolumeWidget {
-        VolumeWidget { volumes }
+    pub fn new(
+        volumes: SplattedVolumes,
+        theme: Theme,
+        marker: PlotMarker,
+        labels: Labels,
+    ) -> VolumeWidget {
+        VolumeWidget {
+            volumes,
+            theme,
+            marker,
+            labels,
+        }
     }
 }
 
 impl Widget for VolumeWidget {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         let x_axis = Axis::default()
-            .title("Time (s)")
+            .title(self.labels.time_title())
             .bounds([
                 self.volumes.time_range.0 as f64,
                 self.volumes.time_range.1 as f64,
             ])
-            .labels([
-                format!("{:}", self.volumes.time_range.1 - self.volumes.time_range.0),
-                format!(
-                    "{:}",
-                    (self.volumes.time_range.1 - self.volumes.time_range.0) / 2
-                ),
-                "now".to_string(),
-            ]);
+            .labels(self.labels.time_axis(self.volumes.time_range));
 
         let max_vol = self
             .volumes
@@ -327,9 +1200,9 @@ impl Widget for VolumeWidget {
             .title("Volumes")
             .bounds([-max_vol, max_vol])
             .labels([
-                format!("{:}", max_vol),
-                format!("0.0"),
-                format!("{:}", max_vol),
+                volume_label(max_vol),
+                "0".to_string(),
+                volume_label(max_vol),
             ]);
 
         let step = ((self.volumes.time_range.1 - self.volumes.time_range.0) as f64)
@@ -352,9 +1225,9 @@ impl Widget for VolumeWidget {
         let ask_dataset = Dataset::default()
             .name("Asks")
             .data(&ask_graph)
-            .marker(symbols::Marker::HalfBlock)
+            .marker(self.marker.symbol())
             .graph_type(GraphType::Bar)
-            .green();
+            .fg(self.theme.positive());
 
         let bid_graph = self
             .volumes
@@ -374,9 +1247,9 @@ impl Widget for VolumeWidget {
         let bid_dataset = Dataset::default()
             .name("Bids")
             .data(&bid_graph)
-            .marker(symbols::Marker::HalfBlock)
+            .marker(self.marker.symbol())
             .graph_type(GraphType::Bar)
-            .red();
+            .fg(self.theme.negative());
 
         let chart = Chart::new(vec![bid_dataset, ask_dataset])
             .block(Block::bordered().title("Order Volumes"))
@@ -387,122 +1260,264 @@ impl Widget for VolumeWidget {
     }
 }
 
-/// Widget for rendering order book heat map to interface
-struct HeatMapWidget {
-    blocks: SplattedBlocks,
+/// Widget for rendering bid/ask imbalance ratio oscillator to interface
+struct ImbalanceRatioWidget {
+    volumes: SplattedVolumes,
+    theme: Theme,
+    labels: Labels,
 }
 
-impl HeatMapWidget {
-    pub fn new(blocks: SplattedBlocks) -> HeatMapWidget {
-        HeatMapWidget { blocks }
+impl ImbalanceRatioWidget {
+    pub fn new(volumes: SplattedVolumes, theme: Theme, labels: Labels) -> ImbalanceRatioWidget {
+        ImbalanceRatioWidget {
+            volumes,
+            theme,
+            labels,
+        }
     }
 }
 
-impl Widget for HeatMapWidget {
+impl Widget for ImbalanceRatioWidget {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         let x_axis = Axis::default()
-            .title("Time (s)")
+            .title(self.labels.time_title())
             .bounds([
-                self.blocks.grid.time_range.0 as f64,
-                self.blocks.grid.time_range.1 as f64,
+                self.volumes.time_range.0 as f64,
+                self.volumes.time_range.1 as f64,
             ])
-            .labels([
-                format!(
-                    "{:}",
-                    self.blocks.grid.time_range.1 - self.blocks.grid.time_range.0
-                ),
-                format!(
-                    "{:}",
-                    (self.blocks.grid.time_range.1 - self.blocks.grid.time_range.0) / 2
-                ),
-                "now".to_string(),
-            ]);
+            .labels(self.labels.time_axis(self.volumes.time_range));
 
         let y_axis = Axis::default()
-            .title("Price")
+            .title("Bid/Ask")
+            .bounds([-1.0, 1.0])
+            .labels(["-1.0".to_string(), "0.0".to_string(), "1.0".to_string()]);
+
+        let step = ((self.volumes.time_range.1 - self.volumes.time_range.0) as f64)
+            / (self.volumes.imbalance_ratios.len() as f64);
+        let ratio_graph = self
+            .volumes
+            .imbalance_ratios
+            .iter()
+            .enumerate()
+            .map(|(index, ratio)| {
+                (
+                    ((index as f64) * step) + self.volumes.time_range.0 as f64,
+                    *ratio,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let zero_graph = vec![
+            (self.volumes.time_range.0 as f64, 0.0),
+            (self.volumes.time_range.1 as f64, 0.0),
+        ];
+
+        let zero_dataset = Dataset::default()
+            .data(&zero_graph)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .dark_gray();
+
+        let ratio_dataset = Dataset::default()
+            .name("(bid - ask) / (bid + ask)")
+            .data(&ratio_graph)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .fg(self.theme.accent());
+
+        let chart = Chart::new(vec![zero_dataset, ratio_dataset])
+            .block(Block::bordered().title("Book Imbalance"))
+            .x_axis(x_axis)
+            .y_axis(y_axis);
+
+        chart.render(area, buf)
+    }
+}
+
+/// Widget for rendering order flow imbalance to interface
+struct ImbalanceWidget {
+    imbalance: SplattedImbalance,
+    theme: Theme,
+    marker: PlotMarker,
+    labels: Labels,
+}
+
+impl ImbalanceWidget {
+    pub fn new(
+        imbalance: SplattedImbalance,
+        theme: Theme,
+        marker: PlotMarker,
+        labels: Labels,
+    ) -> ImbalanceWidget {
+        ImbalanceWidget {
+            imbalance,
+            theme,
+            marker,
+            labels,
+        }
+    }
+}
+
+impl Widget for ImbalanceWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let x_axis = Axis::default()
+            .title(self.labels.time_title())
             .bounds([
-                self.blocks.grid.price_range.0,
-                self.blocks.grid.price_range.1,
+                self.imbalance.time_range.0 as f64,
+                self.imbalance.time_range.1 as f64,
             ])
+            .labels(self.labels.time_axis(self.imbalance.time_range));
+
+        let max_imbalance = self
+            .imbalance
+            .imbalances
+            .iter()
+            .fold(0.0, |acc, imbalance| {
+                if acc < imbalance.abs() {
+                    imbalance.abs()
+                } else {
+                    acc
+                }
+            });
+
+        let y_axis = Axis::default()
+            .title("OFI")
+            .bounds([-max_imbalance, max_imbalance])
             .labels([
-                format!("{:}", self.blocks.grid.price_range.0),
-                format!(
-                    "{:}",
-                    (self.blocks.grid.price_range.1 + self.blocks.grid.price_range.0) / 2.0
-                ),
-                format!("{:}", self.blocks.grid.price_range.1),
+                volume_label(-max_imbalance),
+                "0".to_string(),
+                volume_label(max_imbalance),
             ]);
 
-        let max_vol =
-            self.blocks.volumes.iter().fold(
-                0.0,
-                |acc, vol| if acc < vol.abs() { vol.abs() } else { acc },
-            );
-        let color_map = |vol: f64| {
-            if vol < 0.0 {
-                Color::Rgb(
-                    (((vol.abs() / max_vol) * 9.0 + 1.0).round() * 25.5) as u8,
-                    0,
-                    0,
-                )
-            } else {
-                Color::Rgb(
-                    0,
-                    (((vol.abs() / max_vol) * 9.0 + 1.0).round() * 25.5) as u8,
-                    0,
-                )
-            }
+        let step = ((self.imbalance.time_range.1 - self.imbalance.time_range.0) as f64)
+            / (self.imbalance.imbalances.len() as f64);
+        let graph = |sign: f64| {
+            self.imbalance
+                .imbalances
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(index, imbalance)| {
+                    (
+                        ((index as f64) * step) + self.imbalance.time_range.0 as f64,
+                        if imbalance * sign > 0.0 {
+                            imbalance
+                        } else {
+                            0.0
+                        },
+                    )
+                })
+                .filter(|(_, imbalance)| *imbalance != 0.0)
+                .collect::<Vec<_>>()
         };
 
-        let mut layered_points: HashMap<Color, Vec<(f64, f64)>> = HashMap::new();
+        let buy_graph = graph(1.0);
+        let buy_dataset = Dataset::default()
+            .name("Buy pressure")
+            .data(&buy_graph)
+            .marker(self.marker.symbol())
+            .graph_type(GraphType::Bar)
+            .fg(self.theme.positive());
 
-        let time_step = (self.blocks.grid.time_range.1 - self.blocks.grid.time_range.0) as f64
-            / (self.blocks.volumes.shape()[0] as f64);
-        let price_step = (self.blocks.grid.price_range.1 - self.blocks.grid.price_range.0)
-            / (self.blocks.volumes.shape()[1] as f64);
+        let sell_graph = graph(-1.0);
+        let sell_dataset = Dataset::default()
+            .name("Sell pressure")
+            .data(&sell_graph)
+            .marker(self.marker.symbol())
+            .graph_type(GraphType::Bar)
+            .fg(self.theme.negative());
 
-        for (t_grid, row) in self.blocks.volumes.rows().into_iter().enumerate() {
-            for (p_grid, volume) in row.into_iter().enumerate() {
-                if volume.abs() >= 0.001 * max_vol {
-                    let color = color_map(*volume);
-                    let point = (
-                        time_step * t_grid as f64 + self.blocks.grid.time_range.0 as f64,
-                        price_step * p_grid as f64 + self.blocks.grid.price_range.0 as f64,
-                    );
-                    if let Some(points) = layered_points.get_mut(&color) {
-                        points.push(point);
-                    } else {
-                        layered_points.insert(color, vec![point]);
-                    }
-                }
-            }
+        let chart = Chart::new(vec![sell_dataset, buy_dataset])
+            .block(Block::bordered().title("Order Flow Imbalance"))
+            .x_axis(x_axis)
+            .y_axis(y_axis);
+
+        chart.render(area, buf)
+    }
+}
+
+/// Widget for rendering order arrival, amendment and cancellation rates to interface
+struct ActivityWidget {
+    activity: SplattedActivity,
+    theme: Theme,
+    labels: Labels,
+}
+
+impl ActivityWidget {
+    pub fn new(activity: SplattedActivity, theme: Theme, labels: Labels) -> ActivityWidget {
+        ActivityWidget {
+            activity,
+            theme,
+            labels,
         }
+    }
+}
 
-        let mut sorted_points = layered_points
-            .into_iter()
-            .map(|(color, points)| {
-                let (red, green) = match color.clone() {
-                    Color::Rgb(red, green, _) => (red, green),
-                    _ => (0, 0),
-                };
-                (red as u16 + green as u16, color, points)
-            })
-            .collect::<Vec<_>>();
-        sorted_points.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+impl Widget for ActivityWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let time_range = self.activity.time_range;
+        let x_axis = Axis::default()
+            .title(self.labels.time_title())
+            .bounds([time_range.0 as f64, time_range.1 as f64])
+            .labels(self.labels.time_axis(time_range));
 
-        let datasets = sorted_points
+        let max_rate = self
+            .activity
+            .arrivals
             .iter()
-            .map(|(_, color, points)| {
-                Dataset::default()
-                    .data(points)
-                    .marker(symbols::Marker::HalfBlock)
-                    .graph_type(GraphType::Scatter)
-                    .style(color.clone())
-            })
-            .collect::<Vec<_>>();
+            .chain(self.activity.amendments.iter())
+            .chain(self.activity.cancellations.iter())
+            .fold(0.0, |acc: f64, rate| acc.max(*rate));
+
+        let y_axis = Axis::default()
+            .title("Levels/s")
+            .bounds([0.0, max_rate])
+            .labels([
+                "0.0".to_string(),
+                format!("{:.1}", max_rate / 2.0),
+                format!("{:.1}", max_rate),
+            ]);
+
+        let step = ((time_range.1 - time_range.0) as f64) / (self.activity.arrivals.len() as f64);
+        let graph = |rates: &Vec<f64>| {
+            rates
+                .iter()
+                .enumerate()
+                .map(|(index, rate)| (((index as f64) * step) + time_range.0 as f64, *rate))
+                .collect::<Vec<_>>()
+        };
+
+        let arrivals = graph(&self.activity.arrivals);
+        let amendments = graph(&self.activity.amendments);
+        let cancellations = graph(&self.activity.cancellations);
+        let datasets = vec![
+            Dataset::default()
+                .name("Amended")
+                .data(&amendments)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .fg(self.theme.accent()),
+            Dataset::default()
+                .name("Added")
+                .data(&arrivals)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .fg(self.theme.positive()),
+            Dataset::default()
+                .name("Removed")
+                .data(&cancellations)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .fg(self.theme.negative()),
+        ];
+
+        let title = match self.activity.mode {
+            SplatMode::Kernel(_) => "Book Activity",
+            SplatMode::Histogram => "Book Activity (binned)",
+        };
 
         let chart = Chart::new(datasets)
-            .block(Block::bordered().title("Order Map"))
+            .block(Block::bordered().title(title))
             .x_axis(x_axis)
             .y_axis(y_axis);
 
@@ -510,112 +1525,2580 @@ impl Widget for HeatMapWidget {
     }
 }
 
-/// Encapsulation structure for handling user interface
-pub struct App {
-    render_loop: JoinHandle<Result<(), String>>,
-    pipeline_request_loop: JoinHandle<Result<(), String>>,
-    state: Arc<Mutex<State>>,
+/// Widget for rendering volume profile sideways, sharing the price axis of the heat map
+struct ProfileWidget {
+    profile: SplattedProfile,
+    flicker: Option<SplattedFlicker>,
+    theme: Theme,
+    marker: PlotMarker,
 }
 
-impl App {
-    /// constructor
-    pub async fn new(sender: Sender<Action>) -> App {
-        let state = Arc::new(Mutex::new(State {
-            page: Page::Ticker,
-            sender: sender.clone(),
-            tickers: None,
-            current_ticker: None,
-            ticker_data: None,
-            depth: None,
-            volumes: None,
-            blocks: None,
-        }));
-        let clonned_state = state.clone();
-        let render_loop = spawn(App::run(clonned_state));
+impl ProfileWidget {
+    pub fn new(
+        profile: SplattedProfile,
+        flicker: Option<SplattedFlicker>,
+        theme: Theme,
+        marker: PlotMarker,
+    ) -> ProfileWidget {
+        ProfileWidget {
+            profile,
+            flicker,
+            theme,
+            marker,
+        }
+    }
+}
+
+impl Widget for ProfileWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let max_vol = self
+            .profile
+            .ask_volumes
+            .iter()
+            .chain(self.profile.bid_volumes.iter())
+            .fold(0.0, |acc, volume| if acc < *volume { *volume } else { acc });
+
+        let x_axis = Axis::default()
+            .title("Vol")
+            .bounds([0.0, max_vol])
+            .labels(["0".to_string(), volume_label(max_vol)]);
+
+        let y_axis =
+            Axis::default().bounds([self.profile.price_range.0, self.profile.price_range.1]);
+
+        // horizontal bars are drawn as rows of points, one per terminal column
+        let column_step = max_vol / (area.width.max(1) as f64);
+        let price_step = (self.profile.price_range.1 - self.profile.price_range.0)
+            / (self.profile.ask_volumes.len() as f64);
+        let bars = |volumes: &Vec<f64>| {
+            volumes
+                .iter()
+                .enumerate()
+                .filter(|(_, volume)| column_step > 0.0 && **volume >= column_step)
+                .flat_map(|(index, volume)| {
+                    let price = (index as f64) * price_step + self.profile.price_range.0;
+                    (0..=((volume / column_step) as usize))
+                        .map(move |column| ((column as f64) * column_step, price))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let bid_bars = bars(&self.profile.bid_volumes);
+        let bid_dataset = Dataset::default()
+            .data(&bid_bars)
+            .marker(self.marker.symbol())
+            .graph_type(GraphType::Scatter)
+            .fg(self.theme.negative());
+
+        let ask_bars = bars(&self.profile.ask_volumes);
+        let ask_dataset = Dataset::default()
+            .data(&ask_bars)
+            .marker(self.marker.symbol())
+            .graph_type(GraphType::Scatter)
+            .fg(self.theme.positive());
+
+        // flicker scores of both sides are overlaid on the volume scale
+        let flicker_graph = match &self.flicker {
+            Some(flicker) => {
+                let scores = zip(flicker.ask_scores.iter(), flicker.bid_scores.iter())
+                    .map(|(ask, bid)| ask + bid)
+                    .collect::<Vec<_>>();
+                let max_score = scores.iter().fold(0.0, |acc: f64, score| acc.max(*score));
+                let flicker_step =
+                    (flicker.price_range.1 - flicker.price_range.0) / (scores.len().max(1) as f64);
+                if max_score > 0.0 {
+                    scores
+                        .iter()
+                        .enumerate()
+                        .map(|(index, score)| {
+                            (
+                                score / max_score * max_vol,
+                                (index as f64) * flicker_step + flicker.price_range.0,
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                } else {
+                    vec![]
+                }
+            }
+            None => vec![],
+        };
+        let flicker_name = match &self.flicker {
+            Some(flicker) => format!("Flicker <{}s", flicker.horizon_in_seconds),
+            None => String::new(),
+        };
+        let flicker_dataset = Dataset::default()
+            .name(flicker_name)
+            .data(&flicker_graph)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .light_magenta();
+
+        let chart = Chart::new(vec![bid_dataset, ask_dataset, flicker_dataset])
+            .block(Block::bordered().title(match self.profile.mode {
+                SplatMode::Kernel(_) => "Profile",
+                SplatMode::Histogram => "Profile (binned)",
+            }))
+            .x_axis(x_axis)
+            .y_axis(y_axis);
+
+        chart.render(area, buf)
+    }
+}
+
+/// Enum of different ways volumes are normalized before being mapped to heat map colors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorScale {
+    Linear,
+    /// logarithmic, spreading colors over three decades of volume below the maximum
+    Log,
+    /// gamma correction lifting low volumes next to large walls
+    Gamma,
+}
+
+impl ColorScale {
+    /// next color scale in the cycle
+    pub fn cycled(self) -> ColorScale {
+        match self {
+            ColorScale::Linear => ColorScale::Log,
+            ColorScale::Log => ColorScale::Gamma,
+            ColorScale::Gamma => ColorScale::Linear,
+        }
+    }
+
+    /// volume normalized to [0, 1] relative to a maximal volume
+    pub fn normalize(self, volume: f64, max_volume: f64) -> f64 {
+        let ratio = (volume.abs() / max_volume).min(1.0);
+        match self {
+            ColorScale::Linear => ratio,
+            ColorScale::Log => (1.0 + 1000.0 * ratio).ln() / 1001.0_f64.ln(),
+            ColorScale::Gamma => ratio.powf(0.4),
+        }
+    }
+}
+
+/// Enum of the symbols the order map cells and chart bars are drawn with
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum PlotMarker {
+    /// one point per column and two per row
+    HalfBlock,
+    /// two points per column and four per row, for terminals rendering braille well
+    Braille,
+    /// one point per character
+    Dot,
+}
+
+impl PlotMarker {
+    /// symbol drawn by the charts
+    pub fn symbol(self) -> symbols::Marker {
+        match self {
+            PlotMarker::HalfBlock => symbols::Marker::HalfBlock,
+            PlotMarker::Braille => symbols::Marker::Braille,
+            PlotMarker::Dot => symbols::Marker::Dot,
+        }
+    }
+
+    /// number of points per character along columns and rows
+    pub fn points_per_character(self) -> (usize, usize) {
+        match self {
+            PlotMarker::HalfBlock => (1, 2),
+            PlotMarker::Braille => (2, 4),
+            PlotMarker::Dot => (1, 1),
+        }
+    }
+}
+
+/// Widget for rendering order book heat map to interface
+struct HeatMapWidget {
+    blocks: SplattedBlocks,
+    prices: Option<SplattedPrices>,
+    trades: Option<SplattedTrades>,
+    levels: Option<SplattedLevels>,
+    walls: Option<Vec<Wall>>,
+    own_orders: Vec<OwnOrder>,
+    /// point under the crosshair as fractions of the time and price axes
+    crosshair: Option<(f64, f64)>,
+    colors: HeatColors,
+    marker: PlotMarker,
+    labels: Labels,
+}
+
+impl HeatMapWidget {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        blocks: SplattedBlocks,
+        prices: Option<SplattedPrices>,
+        trades: Option<SplattedTrades>,
+        levels: Option<SplattedLevels>,
+        walls: Option<Vec<Wall>>,
+        own_orders: Vec<OwnOrder>,
+        crosshair: Option<(f64, f64)>,
+        colors: HeatColors,
+        marker: PlotMarker,
+        labels: Labels,
+    ) -> HeatMapWidget {
+        HeatMapWidget {
+            blocks,
+            prices,
+            trades,
+            levels,
+            walls,
+            own_orders,
+            crosshair,
+            colors,
+            marker,
+            labels,
+        }
+    }
+}
+
+impl Widget for HeatMapWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let x_axis = Axis::default()
+            .title(self.labels.time_title())
+            .bounds([
+                self.blocks.grid.time_range.0 as f64,
+                self.blocks.grid.time_range.1 as f64,
+            ])
+            .labels(self.labels.time_axis(self.blocks.grid.time_range));
+
+        let price_axis = self.blocks.grid.price_axis();
+        let price_scale = self.blocks.grid.price_scale;
+        let y_axis = Axis::default()
+            .title(price_title(&self.blocks.grid.peg, &price_scale))
+            .bounds([price_axis.0, price_axis.1])
+            .labels(
+                [
+                    price_axis.0,
+                    (price_axis.1 + price_axis.0) / 2.0,
+                    price_axis.1,
+                ]
+                .map(|value| {
+                    price_label(
+                        price_scale.price(value),
+                        &self.blocks.grid.peg,
+                        &self.labels,
+                    )
+                }),
+            );
+
+        let max_vol = self.blocks.volumes.iter().fold(0.0, |acc, vol| {
+            let vol = widen(*vol).abs();
+            if acc < vol { vol } else { acc }
+        });
+        let color_map = |vol: f64| self.colors.color(vol, max_vol);
+
+        // points of every color, with the largest volume drawn in it to layer brighter cells on top
+        let mut layered_points: HashMap<Color, (f64, Vec<(f64, f64)>)> = HashMap::new();
+
+        let time_step = (self.blocks.grid.time_range.1 - self.blocks.grid.time_range.0) as f64
+            / (self.blocks.volumes.shape()[0] as f64);
+        let price_step = (price_axis.1 - price_axis.0) / (self.blocks.volumes.shape()[1] as f64);
+
+        for (t_grid, row) in self.blocks.volumes.rows().into_iter().enumerate() {
+            for (p_grid, volume) in row.into_iter().enumerate() {
+                let volume = widen(*volume);
+                if volume.abs() >= 0.001 * max_vol {
+                    let color = color_map(volume);
+                    let point = (
+                        time_step * t_grid as f64 + self.blocks.grid.time_range.0 as f64,
+                        price_step * p_grid as f64 + price_axis.0,
+                    );
+                    let (largest, points) = layered_points.entry(color).or_insert((0.0, vec![]));
+                    *largest = volume.abs().max(*largest);
+                    points.push(point);
+                }
+            }
+        }
+
+        let mut sorted_points = layered_points
+            .into_iter()
+            .map(|(color, (largest, points))| (largest, color, points))
+            .collect::<Vec<_>>();
+        sorted_points.sort_by(|lhs, rhs| lhs.0.total_cmp(&rhs.0));
+
+        let mut datasets = sorted_points
+            .iter()
+            .map(|(_, color, points)| {
+                Dataset::default()
+                    .data(points)
+                    .marker(self.marker.symbol())
+                    .graph_type(GraphType::Scatter)
+                    .style(color.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let wall_graphs = self
+            .walls
+            .iter()
+            .flatten()
+            .filter(|wall| {
+                wall.price >= self.blocks.grid.price_range.0
+                    && wall.price <= self.blocks.grid.price_range.1
+            })
+            .map(|wall| {
+                let start = max(wall.start, self.blocks.grid.time_range.0) as f64;
+                let end = wall.end.unwrap_or(self.blocks.grid.time_range.1) as f64;
+                let price = price_scale.position(wall.price);
+                (wall.side, vec![(start, price), (end, price)])
+            })
+            .collect::<Vec<_>>();
+
+        for (side, graph) in wall_graphs.iter() {
+            let dataset = Dataset::default()
+                .data(graph)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line);
+            datasets.push(match side {
+                Side::Ask => dataset.light_cyan(),
+                Side::Bid => dataset.light_magenta(),
+            });
+        }
+
+        let own_graphs = self
+            .own_orders
+            .iter()
+            .filter(|order| {
+                order.price >= self.blocks.grid.price_range.0
+                    && order.price <= self.blocks.grid.price_range.1
+            })
+            .map(|order| {
+                let price = price_scale.position(order.price);
+                let side = match order.side {
+                    Side::Ask => "sell",
+                    Side::Bid => "buy",
+                };
+                (
+                    format!("Own {} {}", side, order.remaining),
+                    vec![
+                        (self.blocks.grid.time_range.0 as f64, price),
+                        (self.blocks.grid.time_range.1 as f64, price),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        for (label, graph) in own_graphs.iter() {
+            datasets.push(
+                Dataset::default()
+                    .name(label.clone())
+                    .data(graph)
+                    .marker(symbols::Marker::Block)
+                    .graph_type(GraphType::Line)
+                    .yellow(),
+            );
+        }
+
+        let on_axis = |prices: &Vec<(f64, f64)>| {
+            prices
+                .iter()
+                .map(|(time, price)| (*time, price_scale.position(*price)))
+                .collect::<Vec<_>>()
+        };
+        let price_graphs = self
+            .prices
+            .as_ref()
+            .map(|prices| (on_axis(&prices.mid_prices), on_axis(&prices.micro_prices)));
+        if let Some((mid_prices, micro_prices)) = &price_graphs {
+            datasets.push(
+                Dataset::default()
+                    .name("Mid")
+                    .data(mid_prices)
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .white(),
+            );
+            datasets.push(
+                Dataset::default()
+                    .name("Microprice")
+                    .data(micro_prices)
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .fg(self.colors.theme.accent()),
+            );
+        }
+
+        let level_graphs = self
+            .levels
+            .iter()
+            .flat_map(|levels| levels.levels.iter())
+            .map(|level| {
+                let price = price_scale.position(level.price);
+                (
+                    level_label(level, &self.blocks.grid.peg, &self.labels),
+                    vec![
+                        (self.blocks.grid.time_range.0 as f64, price),
+                        (self.blocks.grid.time_range.1 as f64, price),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        for (label, graph) in level_graphs.iter() {
+            datasets.push(
+                Dataset::default()
+                    .name(label.clone())
+                    .data(graph)
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .gray(),
+            );
+        }
+
+        let trade_graphs = self.trades.as_ref().map(|trades| {
+            let points = |trades: &Vec<(f64, f64, f64)>| {
+                trades
+                    .iter()
+                    .map(|(time, price, _)| (*time, *price))
+                    .collect::<Vec<_>>()
+            };
+            (points(&trades.buys), points(&trades.sells))
+        });
+        if let Some((buys, sells)) = &trade_graphs {
+            datasets.push(
+                Dataset::default()
+                    .name("Buys")
+                    .data(buys)
+                    .marker(symbols::Marker::Dot)
+                    .graph_type(GraphType::Scatter)
+                    .light_blue(),
+            );
+            datasets.push(
+                Dataset::default()
+                    .name("Sells")
+                    .data(sells)
+                    .marker(symbols::Marker::Dot)
+                    .graph_type(GraphType::Scatter)
+                    .light_red(),
+            );
+        }
+
+        let readout = self.crosshair.map(|(time_fraction, price_fraction)| {
+            self.blocks.sample(time_fraction, price_fraction)
+        });
+        let crosshair_graphs = readout.map(|(time, price, _)| {
+            let price = price_scale.position(price);
+            (
+                vec![(time, price_axis.0), (time, price_axis.1)],
+                vec![
+                    (self.blocks.grid.time_range.0 as f64, price),
+                    (self.blocks.grid.time_range.1 as f64, price),
+                ],
+            )
+        });
+        if let Some((vertical, horizontal)) = &crosshair_graphs {
+            for graph in [vertical, horizontal] {
+                datasets.push(
+                    Dataset::default()
+                        .data(graph)
+                        .marker(symbols::Marker::Braille)
+                        .graph_type(GraphType::Line)
+                        .light_green(),
+                );
+            }
+        }
+
+        let title = match self.blocks.mode {
+            SplatMode::Kernel(_) => "Order Map".to_string(),
+            SplatMode::Histogram => "Order Map (binned)".to_string(),
+        };
+        let title = match self.colors.scale {
+            ColorScale::Linear => title,
+            ColorScale::Log => format!("{} log colors", title),
+            ColorScale::Gamma => format!("{} gamma colors", title),
+        };
+
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(x_axis)
+            .y_axis(y_axis);
+
+        chart.render(area, buf);
+
+        // readout of the point under the crosshair in the top left corner of the plot
+        if let Some((time, price, volume)) = readout {
+            let clock = match DateTime::from_timestamp(time as i64, 0) {
+                Some(clock) => clock.format("%H:%M:%S").to_string(),
+                None => format!("{:.0}", time),
+            };
+            let lines = vec![
+                Line::from(format!(
+                    "Time   {} ({:+.0}s)",
+                    clock,
+                    time - self.blocks.grid.time_range.1 as f64
+                )),
+                Line::from(format!(
+                    "Price  {}",
+                    price_label(price, &self.blocks.grid.peg, &self.labels)
+                )),
+                Line::from(format!("Volume {}", volume_label(volume))),
+            ];
+            let info_area = Rect::new(area.x + 14, area.y + 1, 32, 5).intersection(area);
+            Clear.render(info_area, buf);
+            Paragraph::new(lines)
+                .block(Block::bordered().title("Crosshair"))
+                .render(info_area, buf);
+        }
+    }
+}
+
+/// Point of the plot of an order map drawn in an area under a position, as fractions of the time
+/// and price axes, with the same margins as its resolution
+fn crosshair_at(area: Rect, position: Position) -> (f64, f64) {
+    let (columns, rows) = (
+        area.width.saturating_sub(2 + 12),
+        area.height.saturating_sub(2 + 3),
+    );
+    let column = position.x.saturating_sub(area.x + 1 + 12);
+    let row = position.y.saturating_sub(area.y + 1);
+    (
+        (column as f64 / columns.max(1) as f64).clamp(0.0, 1.0),
+        (1.0 - row as f64 / rows.max(1) as f64).clamp(0.0, 1.0),
+    )
+}
+
+/// Number of (time, price) points visible in the plot of an order map drawn in an area with a
+/// marker, less borders, axis labels and titles
+fn map_resolution(area: Rect, marker: PlotMarker) -> (usize, usize) {
+    let columns = area.width.saturating_sub(2 + 12) as usize;
+    let rows = area.height.saturating_sub(2 + 3) as usize;
+    let (column_points, row_points) = marker.points_per_character();
+    (
+        (column_points * columns).max(10),
+        (row_points * rows).max(10),
+    )
+}
+
+/// Widget for rendering executable arbitrage spreads between two symbols to interface
+struct ArbitrageWidget {
+    arbitrage: SplattedArbitrage,
+    labels: Labels,
+}
+
+impl ArbitrageWidget {
+    pub fn new(arbitrage: SplattedArbitrage, labels: Labels) -> ArbitrageWidget {
+        ArbitrageWidget { arbitrage, labels }
+    }
+}
+
+impl Widget for ArbitrageWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let x_axis = Axis::default()
+            .title(self.labels.time_title())
+            .bounds([
+                self.arbitrage.time_range.0 as f64,
+                self.arbitrage.time_range.1 as f64,
+            ])
+            .labels(self.labels.time_axis(self.arbitrage.time_range));
+
+        let (min_spread, max_spread) = self
+            .arbitrage
+            .first_to_second
+            .iter()
+            .chain(self.arbitrage.second_to_first.iter())
+            .fold(
+                (-self.arbitrage.fee_in_bps, self.arbitrage.fee_in_bps),
+                |(low, high), (_, spread)| (low.min(*spread), high.max(*spread)),
+            );
+
+        let y_axis = Axis::default()
+            .title("bp")
+            .bounds([min_spread, max_spread])
+            .labels([format!("{:.1}", min_spread), format!("{:.1}", max_spread)]);
+
+        let fee_graph = vec![
+            (
+                self.arbitrage.time_range.0 as f64,
+                self.arbitrage.fee_in_bps,
+            ),
+            (
+                self.arbitrage.time_range.1 as f64,
+                self.arbitrage.fee_in_bps,
+            ),
+        ];
+
+        let fee_dataset = Dataset::default()
+            .name("Fees")
+            .data(&fee_graph)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .dark_gray();
+
+        let forward_name = format!(
+            "{:} -> {:}",
+            self.arbitrage.symbols.0, self.arbitrage.symbols.1
+        );
+        let forward_dataset = Dataset::default()
+            .name(forward_name)
+            .data(&self.arbitrage.first_to_second)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .green();
+
+        let backward_name = format!(
+            "{:} -> {:}",
+            self.arbitrage.symbols.1, self.arbitrage.symbols.0
+        );
+        let backward_dataset = Dataset::default()
+            .name(backward_name)
+            .data(&self.arbitrage.second_to_first)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .red();
+
+        let title = if self.arbitrage.is_profitable() {
+            "Arbitrage (open)"
+        } else {
+            "Arbitrage"
+        };
+
+        let chart = Chart::new(vec![fee_dataset, forward_dataset, backward_dataset])
+            .block(Block::bordered().title(title))
+            .x_axis(x_axis)
+            .y_axis(y_axis);
+
+        chart.render(area, buf)
+    }
+}
+
+/// Widget for rendering a basis series between two symbols to interface
+struct BasisWidget {
+    basis: SplattedBasis,
+    labels: Labels,
+}
+
+impl BasisWidget {
+    pub fn new(basis: SplattedBasis, labels: Labels) -> BasisWidget {
+        BasisWidget { basis, labels }
+    }
+}
+
+impl Widget for BasisWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let x_axis = Axis::default()
+            .title(self.labels.time_title())
+            .bounds([
+                self.basis.time_range.0 as f64,
+                self.basis.time_range.1 as f64,
+            ])
+            .labels(self.labels.time_axis(self.basis.time_range));
+
+        let (min_value, max_value) = self
+            .basis
+            .series
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(low, high), (_, value)| {
+                (low.min(*value), high.max(*value))
+            });
+        let (min_value, max_value) = if min_value < max_value {
+            (min_value, max_value)
+        } else if min_value == max_value {
+            (min_value - 1.0, max_value + 1.0)
+        } else {
+            (0.0, 1.0)
+        };
+
+        let (operator, unit) = match self.basis.operation {
+            BasisOperation::Difference => ("-", "Difference"),
+            BasisOperation::Ratio => ("/", "Ratio"),
+        };
+
+        let y_axis = Axis::default()
+            .title(unit)
+            .bounds([min_value, max_value])
+            .labels([format!("{:.4}", min_value), format!("{:.4}", max_value)]);
+
+        let name = format!(
+            "{:} {:} {:}",
+            self.basis.symbols.0, operator, self.basis.symbols.1
+        );
+        let dataset = Dataset::default()
+            .name(name)
+            .data(&self.basis.series)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .cyan();
+
+        let title = match self.basis.correlation {
+            Some(correlation) => format!("Basis (corr {:.2})", correlation),
+            None => "Basis".to_string(),
+        };
+
+        let chart = Chart::new(vec![dataset])
+            .block(Block::bordered().title(title))
+            .x_axis(x_axis)
+            .y_axis(y_axis);
+
+        chart.render(area, buf)
+    }
+}
+
+/// Widget for rendering the series plotted by scripts for a symbol to interface, one chart per
+/// series
+struct IndicatorsWidget {
+    symbol: Option<String>,
+    indicators: Indicators,
+    labels: Labels,
+}
+
+impl IndicatorsWidget {
+    pub fn new(symbol: Option<String>, indicators: Indicators, labels: Labels) -> IndicatorsWidget {
+        IndicatorsWidget {
+            symbol,
+            indicators,
+            labels,
+        }
+    }
+}
+
+impl Widget for IndicatorsWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let title = match &self.symbol {
+            Some(symbol) => format!("Indicators of {}", symbol),
+            None => "Indicators".to_string(),
+        };
+        if self.indicators.series.is_empty() {
+            Paragraph::new("Start with --script to plot series computed by Rhai scripts")
+                .alignment(Alignment::Center)
+                .block(Block::bordered().title(title))
+                .render(area, buf);
+            return;
+        }
+
+        let chunks = Layout::vertical(vec![
+            Constraint::Ratio(
+                1,
+                self.indicators.series.len() as u32
+            );
+            self.indicators.series.len()
+        ])
+        .split(area);
+        for ((name, series), chunk) in self.indicators.series.iter().zip(chunks.iter()) {
+            let points = series
+                .iter()
+                .map(|(time, value)| (*time as f64, *value))
+                .collect::<Vec<_>>();
+            let time_range = match (series.front(), series.back()) {
+                (Some((first, _)), Some((last, _))) if first < last => (*first, *last),
+                (Some((first, _)), _) => (*first - 1, *first + 1),
+                _ => (0, 1),
+            };
+            let (min_value, max_value) = points
+                .iter()
+                .fold((f64::MAX, f64::MIN), |(low, high), (_, value)| {
+                    (low.min(*value), high.max(*value))
+                });
+            let (min_value, max_value) = if min_value < max_value {
+                (min_value, max_value)
+            } else if min_value == max_value {
+                (min_value - 1.0, max_value + 1.0)
+            } else {
+                (0.0, 1.0)
+            };
+
+            let x_axis = Axis::default()
+                .bounds([time_range.0 as f64, time_range.1 as f64])
+                .labels(self.labels.time_axis(time_range));
+            let y_axis = Axis::default()
+                .bounds([min_value, max_value])
+                .labels([format!("{:.4}", min_value), format!("{:.4}", max_value)]);
+            let dataset = Dataset::default()
+                .data(&points)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .cyan();
+            let title = match points.last() {
+                Some((_, value)) => format!("{} ({:.4})", name, value),
+                None => name.clone(),
+            };
+
+            Chart::new(vec![dataset])
+                .block(Block::bordered().title(title))
+                .x_axis(x_axis)
+                .y_axis(y_axis)
+                .render(*chunk, buf);
+        }
+    }
+}
+
+/// Widget for rendering the most recent log entries to interface
+struct LogsWidget {
+    logs: LogBuffer,
+    scroll: usize,
+}
+
+impl LogsWidget {
+    pub fn new(logs: LogBuffer, scroll: usize) -> LogsWidget {
+        LogsWidget { logs, scroll }
+    }
+}
+
+impl Widget for LogsWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let time = |seconds: i64| match DateTime::from_timestamp(seconds, 0) {
+            Some(time) => time.format("%H:%M:%S").to_string(),
+            None => seconds.to_string(),
+        };
+
+        // newest entries at the bottom, scrolling walks back towards the oldest ones
+        let visible = area.height.saturating_sub(2) as usize;
+        let lines = self
+            .logs
+            .entries()
+            .rev()
+            .skip(self.scroll)
+            .take(visible)
+            .map(|entry| {
+                let severity = match entry.severity {
+                    Severity::Info => Span::from(entry.severity.name()).blue(),
+                    Severity::Warning => Span::from(entry.severity.name()).yellow().bold(),
+                    Severity::Error => Span::from(entry.severity.name()).red().bold(),
+                };
+                Line::from(vec![
+                    Span::from(time(entry.time)).dark_gray(),
+                    Span::from(" "),
+                    severity,
+                    Span::from(" "),
+                    Span::from(entry.message.clone()),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let title = match self.logs.entries().len() {
+            0 => "Logs (empty)".to_string(),
+            count => format!("Logs ({} of {})", count - self.scroll.min(count), count),
+        };
+        Paragraph::new(Text::from(lines.into_iter().rev().collect::<Vec<_>>()))
+            .block(Block::bordered().title(title))
+            .render(area, buf)
+    }
+}
+
+/// Widget for rendering the recordings found on disk to interface
+struct LibraryWidget {
+    recordings: Vec<RecordingInfo>,
+    selection: usize,
+}
+
+impl LibraryWidget {
+    pub fn new(recordings: Vec<RecordingInfo>, selection: usize) -> LibraryWidget {
+        LibraryWidget {
+            recordings,
+            selection,
+        }
+    }
+}
+
+impl Widget for LibraryWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let time = |seconds: i64| match DateTime::from_timestamp(seconds, 0) {
+            Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => seconds.to_string(),
+        };
+
+        let rows = self
+            .recordings
+            .iter()
+            .enumerate()
+            .map(|(index, recording)| {
+                let row = Row::new(vec![
+                    recording
+                        .path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    recording.symbol.clone(),
+                    time(recording.time_range.0),
+                    time(recording.time_range.1),
+                    format!("{:.1} kB", recording.size_in_bytes as f64 / 1000.0),
+                    format!("{}", recording.message_count),
+                ]);
+                if index == self.selection {
+                    row.reversed()
+                } else {
+                    row
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let title = if self.recordings.is_empty() {
+            "Library (no recordings found)"
+        } else {
+            "Library"
+        };
+
+        Table::new(
+            rows,
+            vec![
+                Constraint::Percentage(24),
+                Constraint::Percentage(14),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+            ],
+        )
+        .header(Row::new(vec!["File", "Symbol", "From", "To", "Size", "Messages"]).bold())
+        .block(Block::bordered().title(title))
+        .render(area, buf)
+    }
+}
+
+/// Characters drawing the last values of a series as bars, one per column at most
+fn sparkline(values: &[f64], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let (low, high) = values
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(low, high), value| {
+            (low.min(*value), high.max(*value))
+        });
+    let top = SPARKLINE_LEVELS.len() - 1;
+    values
+        .iter()
+        .map(|value| match high > low {
+            true => SPARKLINE_LEVELS[((value - low) / (high - low) * top as f64).round() as usize],
+            false => SPARKLINE_LEVELS[top / 2],
+        })
+        .collect()
+}
+
+/// Widget for rendering the last price, daily change and recent mid prices of every subscribed
+/// symbol to interface
+struct WatchlistWidget {
+    rows: Vec<(String, SymbolView, Labels)>,
+    selection: usize,
+    theme: Theme,
+}
+
+impl WatchlistWidget {
+    pub fn new(
+        rows: Vec<(String, SymbolView, Labels)>,
+        selection: usize,
+        theme: Theme,
+    ) -> WatchlistWidget {
+        WatchlistWidget {
+            rows,
+            selection,
+            theme,
+        }
+    }
+}
+
+impl Widget for WatchlistWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let sparkline_width = (area.width as usize * 55 / 100).saturating_sub(2);
+        let rows = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, (symbol, view, labels))| {
+                let (last, change) = match &view.ticker_data {
+                    Some(ticker) => {
+                        let color = match ticker.change_pct >= 0.0 {
+                            true => self.theme.positive(),
+                            false => self.theme.negative(),
+                        };
+                        (
+                            Span::from(labels.price(ticker.last)),
+                            Span::from(format!("{:+.2}%", ticker.change_pct)).fg(color),
+                        )
+                    }
+                    None => (Span::from("-"), Span::from("-")),
+                };
+                let mids = view
+                    .splatted
+                    .iter()
+                    .find_map(|splatted| match splatted {
+                        Splatted::Prices(splatted) => Some(
+                            splatted
+                                .mid_prices
+                                .iter()
+                                .map(|(_, mid)| *mid)
+                                .collect::<Vec<_>>(),
+                        ),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                let row = Row::new(vec![
+                    Span::from(symbol.clone()),
+                    last,
+                    change,
+                    Span::from(sparkline(&mids, sparkline_width)).fg(self.theme.accent()),
+                ]);
+                if index == self.selection {
+                    row.reversed()
+                } else {
+                    row
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let title = if self.rows.is_empty() {
+            "Watchlist (no symbols subscribed)"
+        } else {
+            "Watchlist (Enter to visualize)"
+        };
+
+        Table::new(
+            rows,
+            vec![
+                Constraint::Percentage(15),
+                Constraint::Percentage(18),
+                Constraint::Percentage(12),
+                Constraint::Percentage(55),
+            ],
+        )
+        .header(Row::new(vec!["Symbol", "Last", "24h change", "Mid price"]).bold())
+        .block(Block::bordered().title(title))
+        .render(area, buf)
+    }
+}
+
+/// Widget for rendering the slippage of a hypothetical order to interface
+struct SlippageWidget {
+    slippage: SplattedSlippage,
+}
+
+impl SlippageWidget {
+    pub fn new(slippage: SplattedSlippage) -> SlippageWidget {
+        SlippageWidget { slippage }
+    }
+}
+
+impl Widget for SlippageWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let row = |side: &str, fill: &Option<Fill>| match fill {
+            Some(fill) => Row::new(vec![
+                side.to_string(),
+                format!("{:.6}", fill.average_price),
+                format!("{:+.2}", fill.slippage_in_bps),
+                format!("{}", fill.levels_consumed),
+                if fill.filled_quantity < self.slippage.quantity {
+                    format!("{} (partial)", fill.filled_quantity)
+                } else {
+                    format!("{}", fill.filled_quantity)
+                },
+            ]),
+            None => Row::new(vec![side.to_string(), "-".to_string()]),
+        };
+
+        Table::new(
+            vec![
+                row("Buy", &self.slippage.buy).green(),
+                row("Sell", &self.slippage.sell).red(),
+            ],
+            vec![
+                Constraint::Percentage(12),
+                Constraint::Percentage(26),
+                Constraint::Percentage(18),
+                Constraint::Percentage(14),
+                Constraint::Percentage(30),
+            ],
+        )
+        .header(Row::new(vec!["Side", "Avg Price", "Slip (bp)", "Levels", "Filled"]).bold())
+        .block(Block::bordered().title(format!(
+            "Slippage of {} around mid {}",
+            self.slippage.quantity, self.slippage.mid_price
+        )))
+        .render(area, buf)
+    }
+}
+
+/// Widget for rendering the queue composition of a price level to interface
+struct QueueWidget {
+    queue: SplattedQueue,
+}
+
+impl QueueWidget {
+    pub fn new(queue: SplattedQueue) -> QueueWidget {
+        QueueWidget { queue }
+    }
+}
+
+impl Widget for QueueWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let (side, color) = match self.queue.side {
+            Side::Ask => ("ask", Color::Red),
+            Side::Bid => ("bid", Color::Green),
+        };
+        let block = Block::bordered().title(format!(
+            "Queue at {} {} (front first, inferred from L2)",
+            side, self.queue.price
+        ));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let chunks = Layout::vertical(vec![Constraint::Length(1), Constraint::Min(0)]).split(inner);
+
+        let total = self
+            .queue
+            .segments
+            .iter()
+            .fold(0.0, |acc, segment| acc + segment.quantity);
+        let spans = self
+            .queue
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let width = max(
+                    1,
+                    (segment.quantity / total * chunks[0].width as f64).round() as usize,
+                );
+                let span = Span::raw("█".repeat(width));
+                if index % 2 == 0 {
+                    span.fg(color)
+                } else {
+                    span.fg(color).dim()
+                }
+            })
+            .collect::<Vec<_>>();
+        Paragraph::new(Line::from(spans)).render(chunks[0], buf);
+
+        let latest = self
+            .queue
+            .segments
+            .iter()
+            .map(|segment| segment.time)
+            .max()
+            .unwrap_or_default();
+        let rows = self
+            .queue
+            .segments
+            .iter()
+            .map(|segment| {
+                Row::new(vec![
+                    format!("{}", latest - segment.time),
+                    format!("{}", segment.quantity),
+                    format!("{:.1}", 100.0 * segment.quantity / total),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        Table::new(
+            rows,
+            vec![
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+            ],
+        )
+        .header(Row::new(vec!["Age (s)", "Quantity", "Share (%)"]).bold())
+        .render(chunks[1], buf)
+    }
+}
+
+/// Widget for rendering the handling time breakdown of the dispatch loop to interface
+struct StatsWidget {
+    metrics: Vec<HandlerStats>,
+    cache_memory: Option<(usize, Option<usize>)>,
+}
+
+impl StatsWidget {
+    pub fn new(
+        metrics: Vec<HandlerStats>,
+        cache_memory: Option<(usize, Option<usize>)>,
+    ) -> StatsWidget {
+        StatsWidget {
+            metrics,
+            cache_memory,
+        }
+    }
+}
+
+impl Widget for StatsWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let milliseconds = |duration: Duration| format!("{:.3}", duration.as_secs_f64() * 1000.0);
+        let mebibytes = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+
+        let rows = self
+            .metrics
+            .into_iter()
+            .map(|stats| {
+                let row = Row::new(vec![
+                    stats.name.to_string(),
+                    format!("{}", stats.count),
+                    milliseconds(stats.p50),
+                    milliseconds(stats.p90),
+                    milliseconds(stats.p99),
+                    milliseconds(stats.max),
+                    format!("{}", stats.over_budget),
+                    format!("{}", stats.dropped),
+                ]);
+                if stats.over_budget > 0 {
+                    row.red()
+                } else {
+                    row
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Table::new(
+            rows,
+            vec![
+                Constraint::Percentage(23),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "Action", "Count", "p50 (ms)", "p90 (ms)", "p99 (ms)", "Max (ms)", "Slow",
+                "Dropped",
+            ])
+            .bold(),
+        )
+        .block(
+            Block::bordered()
+                .title("Dispatch handling times")
+                .title_bottom(match self.cache_memory {
+                    Some((usage, budget)) => format!(
+                        "Books cache {:.1} MiB / {}",
+                        mebibytes(usage),
+                        match budget {
+                            Some(budget) => format!("{:.1} MiB", mebibytes(budget)),
+                            None => "unlimited".to_string(),
+                        }
+                    ),
+                    None => "Books cache -".to_string(),
+                }),
+        )
+        .render(area, buf)
+    }
+}
+
+/// Widget for rendering the balances and open orders of the user to interface
+struct AccountWidget {
+    account: Option<Account>,
+}
+
+impl AccountWidget {
+    pub fn new(account: Option<Account>) -> AccountWidget {
+        AccountWidget { account }
+    }
+}
+
+impl Widget for AccountWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let Some(account) = self.account else {
+            Paragraph::new("Start with --account to follow the balances and open orders")
+                .alignment(Alignment::Center)
+                .block(Block::bordered().title("Account"))
+                .render(area, buf);
+            return;
+        };
+        let chunks = Layout::vertical(vec![Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(area);
+
+        let balances = account
+            .balances
+            .iter()
+            .map(|balance| {
+                Row::new(vec![
+                    balance.asset.clone(),
+                    format!("{}", balance.total),
+                    format!("{}", balance.held),
+                    format!("{}", balance.total - balance.held),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Table::new(balances, vec![Constraint::Percentage(25); 4])
+            .header(Row::new(vec!["Asset", "Total", "Held", "Available"]).bold())
+            .block(Block::bordered().title("Balances"))
+            .render(chunks[0], buf);
+
+        let open_orders = account
+            .open_orders
+            .iter()
+            .map(|order| {
+                let (side, color) = match order.side {
+                    Side::Bid => ("buy", Color::Green),
+                    Side::Ask => ("sell", Color::Red),
+                };
+                Row::new(vec![
+                    order.id.clone(),
+                    order.pair.clone(),
+                    side.to_string(),
+                    order.kind.clone(),
+                    format!("{}", order.price),
+                    format!("{}", order.quantity),
+                    format!("{}", order.filled),
+                ])
+                .fg(color)
+            })
+            .collect::<Vec<_>>();
+        Table::new(
+            open_orders,
+            vec![
+                Constraint::Percentage(22),
+                Constraint::Percentage(13),
+                Constraint::Percentage(9),
+                Constraint::Percentage(13),
+                Constraint::Percentage(15),
+                Constraint::Percentage(14),
+                Constraint::Percentage(14),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "Order", "Pair", "Side", "Type", "Price", "Quantity", "Filled",
+            ])
+            .bold(),
+        )
+        .block(
+            Block::bordered()
+                .title("Open orders")
+                .title_bottom(format!("{} open", account.open_orders.len())),
+        )
+        .render(chunks[1], buf);
+    }
+}
+
+/// Widget for rendering the health of the session to interface, to debug stalls
+struct DiagnosticsWidget {
+    diagnostics: Diagnostics,
+    metrics: Vec<HandlerStats>,
+    /// time taken by the last pipeline run of every symbol
+    pipelines: HashMap<String, Duration>,
+    connected: Option<bool>,
+}
+
+impl DiagnosticsWidget {
+    pub fn new(
+        diagnostics: Diagnostics,
+        metrics: Vec<HandlerStats>,
+        pipelines: HashMap<String, Duration>,
+        connected: Option<bool>,
+    ) -> DiagnosticsWidget {
+        DiagnosticsWidget {
+            diagnostics,
+            metrics,
+            pipelines,
+            connected,
+        }
+    }
+}
+
+impl Widget for DiagnosticsWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let chunks =
+            Layout::horizontal(vec![Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(area);
+
+        let seconds = self.diagnostics.uptime.as_secs();
+        let feed = match self.connected {
+            Some(true) => "live".green(),
+            Some(false) => "down".red(),
+            None => "connecting".yellow(),
+        };
+        let queue = format!(
+            "{} / {}",
+            self.diagnostics.queued, self.diagnostics.queue_capacity
+        );
+        let mut lines = vec![
+            Line::from(format!(
+                "Uptime          {:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            )),
+            Line::from(vec![Span::from("Feed            "), feed]),
+            Line::from(format!("Reconnects      {}", self.diagnostics.reconnects)),
+            match self.diagnostics.queued * 2 > self.diagnostics.queue_capacity {
+                true => Line::from(format!("Action queue    {}", queue)).red(),
+                false => Line::from(format!("Action queue    {}", queue)),
+            },
+            Line::from(format!(
+                "Actions handled {}",
+                self.metrics.iter().map(|stats| stats.count).sum::<usize>()
+            )),
+            Line::from(""),
+        ];
+        lines.extend(
+            self.metrics
+                .iter()
+                .map(|stats| Line::from(format!("  {:<20}{}", stats.name, stats.count))),
+        );
+        Paragraph::new(lines)
+            .block(Block::bordered().title("Session"))
+            .render(chunks[0], buf);
+
+        let milliseconds = |duration: Duration| format!("{:.1}", duration.as_secs_f64() * 1000.0);
+        let rows = self
+            .diagnostics
+            .caches
+            .iter()
+            .map(|cache| {
+                Row::new(vec![
+                    cache.symbol.clone(),
+                    format!("{}", cache.books),
+                    format!("{}", cache.trades),
+                    format!("{:.2}", cache.bytes as f64 / (1024.0 * 1024.0)),
+                    match self.pipelines.get(&cache.symbol) {
+                        Some(elapsed) => milliseconds(*elapsed),
+                        None => "-".to_string(),
+                    },
+                ])
+            })
+            .collect::<Vec<_>>();
+        Table::new(
+            rows,
+            vec![
+                Constraint::Percentage(24),
+                Constraint::Percentage(19),
+                Constraint::Percentage(19),
+                Constraint::Percentage(19),
+                Constraint::Percentage(19),
+            ],
+        )
+        .header(Row::new(vec!["Symbol", "Books", "Trades", "MiB", "Pipeline (ms)"]).bold())
+        .block(Block::bordered().title("Caches"))
+        .render(chunks[1], buf)
+    }
+}
+
+/// Widget for rendering the time taken by every step of the last pipeline run to interface
+struct PipelineTimingsWidget {
+    timings: Vec<StageTiming>,
+    /// time between display updates the pipeline runs should fit in
+    budget: Duration,
+}
+
+impl PipelineTimingsWidget {
+    pub fn new(timings: Vec<StageTiming>, budget: Duration) -> PipelineTimingsWidget {
+        PipelineTimingsWidget { timings, budget }
+    }
+}
+
+impl Widget for PipelineTimingsWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let total = self
+            .timings
+            .iter()
+            .map(|timing| timing.elapsed)
+            .sum::<Duration>();
+        let share = |elapsed: Duration| {
+            if total.is_zero() {
+                0.0
+            } else {
+                100.0 * elapsed.as_secs_f64() / total.as_secs_f64()
+            }
+        };
+
+        let rows = self
+            .timings
+            .into_iter()
+            .map(|timing| {
+                Row::new(vec![
+                    timing.name.to_string(),
+                    format!("{:.3}", timing.elapsed.as_secs_f64() * 1000.0),
+                    format!("{:.1}", share(timing.elapsed)),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let title = format!(
+            "Pipeline stage times, {:.3} ms in total",
+            total.as_secs_f64() * 1000.0
+        );
+        let block = if total > self.budget {
+            Block::bordered().title(title).red()
+        } else {
+            Block::bordered().title(title)
+        };
+
+        Table::new(
+            rows,
+            vec![
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ],
+        )
+        .header(Row::new(vec!["Stage", "Time (ms)", "Share (%)"]).bold())
+        .block(block)
+        .render(area, buf)
+    }
+}
+
+/// Widget for rendering the key bindings to interface
+struct HelpWidget {
+    rebound: HashMap<event::KeyCode, event::KeyCode>,
+}
+
+impl HelpWidget {
+    pub fn new(rebound: HashMap<event::KeyCode, event::KeyCode>) -> HelpWidget {
+        HelpWidget { rebound }
+    }
+}
+
+impl Widget for HelpWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let rows = KEY_BINDINGS
+            .iter()
+            .map(|(code, description, _)| {
+                let bound = self
+                    .rebound
+                    .iter()
+                    .find(|(_, default)| *default == code)
+                    .map_or(*code, |(bound, _)| *bound);
+                (key_name(bound), *description)
+            })
+            .chain([
+                ("1-9".to_string(), "jump to a symbol tab"),
+                ("Esc".to_string(), "back to the ticker page"),
+            ])
+            .map(|(key, description)| {
+                Row::new(vec![Span::from(key).bold(), Span::from(description)])
+            })
+            .collect::<Vec<_>>();
+
+        Table::new(rows, vec![Constraint::Length(10), Constraint::Min(0)])
+            .block(Block::bordered().title("Keys (? or Esc to close)"))
+            .render(area, buf)
+    }
+}
+
+/// Widget for rendering a recoverable error to interface
+struct ErrorWidget<'a> {
+    message: &'a str,
+}
+
+impl<'a> ErrorWidget<'a> {
+    pub fn new(message: &'a str) -> ErrorWidget<'a> {
+        ErrorWidget { message }
+    }
+}
+
+impl Widget for ErrorWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        Paragraph::new(self.message)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::bordered()
+                    .title("Error (Esc or Enter to dismiss)")
+                    .red()
+                    .bold(),
+            )
+            .render(area, buf)
+    }
+}
+
+/// Widget for asking the user to confirm an order before it is sent
+struct ConfirmWidget<'a> {
+    ticket: &'a OrderTicket,
+    dry_run: bool,
+}
+
+impl<'a> ConfirmWidget<'a> {
+    pub fn new(ticket: &'a OrderTicket, dry_run: bool) -> ConfirmWidget<'a> {
+        ConfirmWidget { ticket, dry_run }
+    }
+}
+
+impl Widget for ConfirmWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let (mode, color) = match self.dry_run {
+            true => ("Dry run, only validated by the exchange", Color::Yellow),
+            false => ("Live, sent to the exchange", Color::Red),
+        };
+        Paragraph::new(vec![
+            Line::from(self.ticket.describe()).bold(),
+            Line::from(mode),
+        ])
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::bordered()
+                .title("Confirm order (y to send, n or Esc to discard)")
+                .fg(color),
+        )
+        .render(area, buf)
+    }
+}
+
+/// Encapsulation structure for handling user interface
+pub struct App {
+    /// loop drawing the interface and reading user input, None once joined
+    render_loop: Option<JoinHandle<Result<(), BookedError>>>,
+    pipeline_request_loop: JoinHandle<Result<(), BookedError>>,
+    /// number of times the pipeline request loop was restarted
+    restarts: usize,
+    state: Arc<Mutex<State>>,
+}
+
+impl App {
+    /// constructor
+    pub async fn new(sender: ActionSender) -> App {
+        let history_file = history_path();
+        let state = Arc::new(Mutex::new(State {
+            page: Page::Ticker,
+            sender: sender.clone(),
+            tickers: None,
+            current_ticker: None,
+            split: None,
+            views: HashMap::new(),
+            slippage_quantity: None,
+            slippage: None,
+            queue_price: None,
+            queue: None,
+            arbitrage: None,
+            basis: None,
+            map_resolution: None,
+            alert: None,
+            alert_time: None,
+            command: Input::new(
+                history_file
+                    .as_deref()
+                    .map(load_history)
+                    .unwrap_or_default(),
+            ),
+            history_file,
+            prompting: false,
+            help: false,
+            error: None,
+            trading: None,
+            confirming: None,
+            account: None,
+            generation: 0,
+            cadence: Cadence::default(),
+            rebound: HashMap::new(),
+            frozen: None,
+            mouse_capture: true,
+            time_zoom: 0,
+            time_pan: 0,
+            drag: None,
+            crosshair: None,
+            areas: PanelAreas::default(),
+            search: Input::new(vec![]),
+            library: vec![],
+            library_selection: 0,
+            watchlist_selection: 0,
+            metrics: vec![],
+            logs: LogBuffer::new(1000),
+            logs_scroll: 0,
+            cache_memory: None,
+            pipeline_timings: vec![],
+            diagnostics: Diagnostics::default(),
+            connected: None,
+            message_rate: 0.0,
+            last_updates: HashMap::new(),
+            time_window: None,
+            time_cached: None,
+            preview: None,
+            color_scale: ColorScale::Linear,
+            theme: Theme::Default,
+            gradient: Gradient::Shades,
+            color_depth: ColorDepth::detect(),
+            marker: PlotMarker::HalfBlock,
+            layout: PanelLayout::default(),
+            layout_file: None,
+            focus: Focus::Map,
+            fullscreen: false,
+            time_labels: TimeLabels::Relative,
+            plugins: registered_panels(),
+        }));
+        let clonned_state = state.clone();
+        let render_loop = spawn(App::run(clonned_state));
 
         let clonned_sender = sender.clone();
         let clonned_state = state.clone();
         let pipeline_request_loop = spawn(App::request_pipeline(clonned_sender, clonned_state));
 
         App {
-            render_loop,
+            render_loop: Some(render_loop),
             pipeline_request_loop,
+            restarts: 0,
             state,
         }
     }
 
+    /// Stop the interface loops and leave the terminal as found, for fatal errors
+    pub async fn shutdown(&mut self) {
+        self.pipeline_request_loop.abort();
+        if let Some(render_loop) = self.render_loop.take() {
+            render_loop.abort();
+            // the render loop restores the terminal once dropped at its next await point
+            let _ = render_loop.await;
+        }
+    }
+
+    /// Join the interface loops that stopped, restarting the pipeline request loop, returning the
+    /// reason of a restart and failing once a loop cannot be restarted
+    pub async fn supervise(&mut self) -> Result<Option<String>, BookedError> {
+        if let Some(render_loop) = self.render_loop.take_if(|handle| handle.is_finished()) {
+            let reason = match render_loop.await {
+                // the loop only returns once the user quit
+                Ok(Ok(())) => return Ok(None),
+                Ok(Err(error)) => error.to_string(),
+                Err(message) => message.to_string(),
+            };
+            return Err(BookedError::TaskStopped {
+                task: "Render loop",
+                reason,
+            });
+        }
+
+        if !self.pipeline_request_loop.is_finished() {
+            return Ok(None);
+        }
+        let sender = self.state.lock().await.sender.clone();
+        let restarted = spawn(App::request_pipeline(sender, self.state.clone()));
+        let reason = match std::mem::replace(&mut self.pipeline_request_loop, restarted).await {
+            Ok(Ok(())) => "returned".to_string(),
+            Ok(Err(error)) => error.to_string(),
+            Err(message) => message.to_string(),
+        };
+        self.restarts += 1;
+        if self.restarts > MAX_RESTARTS {
+            return Err(BookedError::TaskStopped {
+                task: "Pipeline request loop",
+                reason: format!("{} after {} restarts", reason, MAX_RESTARTS),
+            });
+        }
+        Ok(Some(format!(
+            "Pipeline request loop stopped ({}), restarted",
+            reason
+        )))
+    }
+
+    /// Capture mouse events, or leave them to the terminal for native text selection
+    pub async fn set_mouse_capture(&self, mouse_capture: bool) {
+        self.state.lock().await.mouse_capture = mouse_capture;
+    }
+
+    /// Set the colors the interface is drawn with
+    pub async fn set_theme(&self, theme: Theme) {
+        self.state.lock().await.theme = theme;
+    }
+
+    /// Set the arrangement of the ticker page panels and the configuration file it is kept in
+    pub async fn set_layout(&self, layout: PanelLayout, layout_file: Option<PathBuf>) {
+        let mut locked_state = self.state.lock().await;
+        locked_state.layout = layout;
+        locked_state.layout_file = layout_file;
+    }
+
+    /// Set the gradient the order map volumes are colored along
+    pub async fn set_gradient(&self, gradient: Gradient) {
+        self.state.lock().await.gradient = gradient;
+    }
+
+    /// Set the colors the terminal displays, detected from its environment by default
+    pub async fn set_color_depth(&self, color_depth: ColorDepth) {
+        self.state.lock().await.color_depth = color_depth;
+    }
+
+    /// Set the symbol the order map cells and chart bars are drawn with
+    pub async fn set_marker(&self, marker: PlotMarker) {
+        self.state.lock().await.marker = marker;
+    }
+
+    /// Set the periods at which the interface is refreshed
+    pub async fn set_cadence(&self, cadence: Cadence) {
+        self.state.lock().await.cadence = cadence;
+    }
+
+    /// Bind keys in place of default ones, given as pairs of a default key and its replacement
+    pub async fn set_key_bindings(&self, bindings: Vec<(char, char)>) -> Result<(), BookedError> {
+        let mut rebound = HashMap::new();
+        for (default, bound) in bindings {
+            let default = event::KeyCode::Char(default);
+            if !KEY_BINDINGS.iter().any(|(code, _, _)| *code == default) {
+                return Err(BookedError::Config(format!(
+                    "No action is bound to {}",
+                    key_name(default)
+                )));
+            }
+            if rebound
+                .insert(event::KeyCode::Char(bound), default)
+                .is_some()
+            {
+                return Err(BookedError::Config(format!(
+                    "{} is bound more than once",
+                    bound
+                )));
+            }
+        }
+        self.state.lock().await.rebound = rebound;
+        Ok(())
+    }
+
+    /// Set the way time axes are labelled
+    pub async fn set_time_labels(&self, time_labels: TimeLabels) {
+        self.state.lock().await.time_labels = time_labels;
+    }
+
+    /// enable order entry, orders only being validated by the exchange in dry run
+    pub async fn set_trading(&self, dry_run: bool) {
+        self.state.lock().await.trading = Some(dry_run);
+    }
+
     /// Set the current ticker in the application state
     pub async fn set_current_ticker(&self, ticker: String) {
         let mut locked_state = self.state.lock().await;
         locked_state.current_ticker = Some(ticker.clone());
     }
 
+    /// Set the subscribed tickers, moving away from the current ticker if it was removed
+    pub async fn set_tickers(&self, mut tickers: Vec<String>) {
+        tickers.sort();
+        let mut locked_state = self.state.lock().await;
+        if let Some(current) = &locked_state.current_ticker
+            && !tickers.contains(current)
+        {
+            locked_state.current_ticker = tickers.first().cloned();
+        }
+        locked_state
+            .views
+            .retain(|symbol, _| tickers.contains(symbol));
+        if let Some(split) = &locked_state.split
+            && !tickers.contains(split)
+        {
+            locked_state.split = None;
+        }
+        locked_state.tickers = Some(tickers);
+    }
+
     /// Get the state object used for rendering
     pub fn get_state(&self) -> Arc<Mutex<State>> {
         self.state.clone()
     }
 
-    /// Method to request pipeline updates regularly and keep state data updated
+    /// Render the current state into an off-screen buffer of the size of the terminal, as it
+    /// would be drawn on the next frame
+    pub async fn screenshot(&self) -> Result<Buffer, BookedError> {
+        let clonned_state = self.state.lock().await.clone();
+        let (width, height) = crossterm::terminal::size().unwrap_or(SCREENSHOT_SIZE);
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+        terminal.draw(|frame| {
+            App::render(frame, clonned_state);
+        })?;
+        Ok(terminal.backend().buffer().clone())
+    }
+
+    /// Method to request pipeline updates regularly and keep state data updated, the visible
+    /// ticker on every tick and the background tabs in turn every few ticks
     async fn request_pipeline(
-        sender: Sender<Action>,
+        sender: ActionSender,
         state: Arc<Mutex<State>>,
-    ) -> Result<(), String> {
-        let mut timer = interval(Duration::from_secs(1));
+    ) -> Result<(), BookedError> {
+        let (mut tick, mut turn) = (0_usize, 0_usize);
+        loop {
+            // read on every tick for the cadence to be adjustable at runtime
+            let period = state.lock().await.cadence.pipeline;
+            sleep(period).await;
+            let (visible, background) = {
+                let locked_state = state.lock().await;
+                let mut visible = locked_state
+                    .current_ticker
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if let Some(current) = &locked_state.current_ticker {
+                    visible.extend(locked_state.split_partner(current));
+                }
+                let background = locked_state
+                    .tickers
+                    .iter()
+                    .flatten()
+                    .filter(|symbol| !visible.contains(symbol))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (visible, background)
+            };
+
+            let mut requested = visible;
+            tick += 1;
+            if tick % BACKGROUND_PIPELINE_PERIOD == 0 && !background.is_empty() {
+                requested.push(background[turn % background.len()].clone());
+                turn += 1;
+            }
+            for symbol in requested {
+                sender.send(Action::RunPipeline(symbol)).await?;
+            }
+        }
+    }
+
+    /// Run rendering loop for user interface
+    async fn run(state: Arc<Mutex<State>>) -> Result<(), BookedError> {
+        // the terminal is left as found whenever the loop stops, even aborted on a fatal error
+        let mut guard = TerminalGuard::new();
+
+        let mut run_result = Ok(());
+        let mut mouse_captured = false;
+        let mut drawn_generation = None;
         loop {
-            timer.tick().await;
-            match &state.lock().await.current_ticker {
-                Some(symbol) => match sender.send(Action::RunPipeline(symbol.clone())).await {
-                    Ok(()) => (),
-                    Err(message) => return Err(format!("{:?}", message)),
+            // a panicking task restored the terminal before being restarted, everything is drawn
+            // again on a screen taken back
+            match guard.reenter() {
+                Ok(true) => {
+                    mouse_captured = false;
+                    drawn_generation = None;
+                }
+                Ok(false) => (),
+                Err(message) => {
+                    run_result = Err(message.into());
+                    break;
+                }
+            }
+
+            // cloning and drawing the state is skipped until it changed
+            let (clonned_state, poll) = {
+                let locked_state = state.lock().await;
+                (
+                    locked_state.changed(&mut drawn_generation),
+                    locked_state.cadence.poll,
+                )
+            };
+            if let Some(clonned_state) = clonned_state {
+                if clonned_state.mouse_capture != mouse_captured {
+                    let captured = match clonned_state.mouse_capture {
+                        true => crossterm::execute!(std::io::stdout(), event::EnableMouseCapture),
+                        false => crossterm::execute!(std::io::stdout(), event::DisableMouseCapture),
+                    };
+                    if let Err(message) = captured {
+                        run_result = Err(message.into());
+                        break;
+                    }
+                    mouse_captured = clonned_state.mouse_capture;
+                }
+
+                let mut areas = PanelAreas::default();
+                match guard
+                    .terminal
+                    .draw(|frame| areas = App::render(frame, clonned_state))
+                {
+                    Ok(_) => (),
+                    Err(message) => {
+                        run_result = Err(message.into());
+                        break;
+                    }
+                }
+
+                {
+                    let mut locked_state = state.lock().await;
+                    if let Some(area) = areas.map {
+                        locked_state.map_resolution =
+                            Some(map_resolution(area, locked_state.marker));
+                    }
+                    locked_state.areas = areas;
+                }
+            }
+
+            match event::poll(poll) {
+                Ok(true) => match event::read() {
+                    Ok(Event::Key(press)) => {
+                        // the state is released before sending, the dispatcher locking it while
+                        // the queue is full
+                        let (action, sender) = {
+                            let mut locked_state = state.lock().await;
+                            locked_state.touch();
+                            (
+                                App::handle_key(&mut locked_state, press.code),
+                                locked_state.sender.clone(),
+                            )
+                        };
+                        if let Some(action) = action {
+                            let quitting = matches!(action, Action::Quit);
+                            match sender.send(action).await {
+                                Ok(()) => (),
+                                Err(message) => {
+                                    run_result = Err(message.into());
+                                    break;
+                                }
+                            }
+                            if quitting {
+                                break;
+                            }
+                        }
+                    }
+                    Ok(Event::Mouse(mouse)) => {
+                        let mut locked_state = state.lock().await;
+                        locked_state.touch();
+                        App::handle_mouse(&mut locked_state, mouse);
+                    }
+                    Ok(Event::Resize(_, _)) => state.lock().await.touch(),
+                    _ => (),
+                },
+                Ok(false) => (),
+                Err(message) => {
+                    run_result = Err(message.into());
+                    break;
+                }
+            }
+        }
+
+        run_result
+    }
+
+    /// Apply an editing key to an input, returning whether the key was consumed
+    fn edit(input: &mut Input, code: event::KeyCode, candidates: &[String]) -> bool {
+        match code {
+            event::KeyCode::Backspace => input.pop(),
+            event::KeyCode::Up => input.previous(),
+            event::KeyCode::Down => input.next(),
+            event::KeyCode::Tab => input.complete(candidates),
+            event::KeyCode::Char(character) => input.push(character),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Render the order map and depth of one of two symbols displayed side by side, returning
+    /// the area of the order map when drawn
+    fn render_half(
+        frame: &mut Frame,
+        area: Rect,
+        symbol: &str,
+        view: SymbolView,
+        state: &State,
+        crosshair: Option<(f64, f64)>,
+    ) -> Option<Rect> {
+        let labels = state.labels(view.price_decimals);
+        let block = Block::bordered().title(symbol.to_string());
+        let chunks =
+            Layout::horizontal(vec![Constraint::Percentage(75), Constraint::Percentage(25)])
+                .split(block.inner(area));
+        frame.render_widget(block, area);
+
+        let (mut depth, mut blocks, mut prices, mut trades, mut levels) =
+            (None, None, None, None, None);
+        for splatted in view.splatted {
+            match splatted {
+                Splatted::Depth(splatted) => depth = Some(splatted),
+                Splatted::Blocks(splatted) => blocks = Some(splatted),
+                Splatted::Prices(splatted) => prices = Some(splatted),
+                Splatted::Trades(splatted) => trades = Some(splatted),
+                Splatted::Levels(splatted) => levels = Some(splatted),
+                _ => (),
+            }
+        }
+
+        match depth {
+            Some(splatted) => frame.render_widget(
+                DepthWidget::new(splatted, levels.clone(), state.theme, state.marker, labels),
+                chunks[1],
+            ),
+            None => frame.render_widget(
+                Paragraph::new("Loading...").alignment(Alignment::Center),
+                chunks[1],
+            ),
+        }
+        match blocks {
+            Some(splatted) => {
+                let blocks_widget = HeatMapWidget::new(
+                    splatted,
+                    prices,
+                    trades,
+                    levels,
+                    view.walls,
+                    view.own_orders,
+                    crosshair,
+                    state.heat_colors(),
+                    state.marker,
+                    labels,
+                );
+                frame.render_widget(blocks_widget, chunks[0]);
+                Some(chunks[0])
+            }
+            None => {
+                frame.render_widget(
+                    Paragraph::new("Loading...").alignment(Alignment::Center),
+                    chunks[0],
+                );
+                None
+            }
+        }
+    }
+
+    /// Display the next tab side by side with the current ticker, or the current ticker alone
+    fn toggle_split(state: &mut State) {
+        if state.split.take().is_some() {
+            return;
+        }
+        let current = state.current_ticker.clone();
+        App::cycle_ticker(state, 1);
+        state.split = state.current_ticker.clone();
+        state.current_ticker = current;
+    }
+
+    /// Switch to the page a number of steps away among the cycled pages, wrapping around
+    fn cycle_page(state: &mut State, step: isize) {
+        let position = CYCLED_PAGES
+            .iter()
+            .position(|page| *page == state.page)
+            .unwrap_or(0);
+        let next = (position as isize + step).rem_euclid(CYCLED_PAGES.len() as isize) as usize;
+        state.page = CYCLED_PAGES[next].clone();
+    }
+
+    /// Switch the current ticker to the subscribed one a number of tabs away, wrapping around
+    fn cycle_ticker(state: &mut State, step: isize) {
+        let symbols = state.tickers.clone().unwrap_or_default();
+        if symbols.is_empty() {
+            return;
+        }
+        let position = state
+            .current_ticker
+            .as_ref()
+            .and_then(|current| symbols.iter().position(|symbol| symbol == current))
+            .unwrap_or(0);
+        let next = (position as isize + step).rem_euclid(symbols.len() as isize) as usize;
+        state.current_ticker = Some(symbols[next].clone());
+    }
+
+    /// Update state with a key press and return the action it triggers, if any
+    fn handle_key(state: &mut State, code: event::KeyCode) -> Option<Action> {
+        let symbols = state.tickers.clone().unwrap_or_default();
+
+        if state.error.is_some() {
+            if let event::KeyCode::Esc | event::KeyCode::Enter = code {
+                state.error = None;
+            }
+            return None;
+        }
+
+        if let Some(ticket) = state.confirming.take() {
+            return match code {
+                event::KeyCode::Char('y') => Some(Action::SubmitOrder(ticket)),
+                event::KeyCode::Char('n') | event::KeyCode::Esc => None,
+                _ => {
+                    state.confirming = Some(ticket);
+                    None
+                }
+            };
+        }
+
+        if state.prompting {
+            match code {
+                event::KeyCode::Esc => {
+                    state.command.clear();
+                    state.prompting = false;
+                }
+                event::KeyCode::Enter => match Command::parse(&state.command.text, &symbols) {
+                    Ok(command) => {
+                        let line = state.command.submit();
+                        // history is a convenience kept away from the locked state, failing to
+                        // keep it should not block commands
+                        if let Some(path) = state.history_file.clone() {
+                            spawn_blocking(move || {
+                                if let Err(message) = save_history(&path, &line) {
+                                    warn!(%message, "could not keep command history");
+                                }
+                            });
+                        }
+                        state.prompting = false;
+                        return App::execute(state, command);
+                    }
+                    Err(message) => state.command.error = Some(message),
+                },
+                code => {
+                    let candidates = Command::candidates(&state.command.text, &symbols);
+                    App::edit(&mut state.command, code, &candidates);
+                }
+            }
+            return None;
+        }
+
+        if state.help {
+            if let Some(event::KeyCode::Esc | event::KeyCode::Char('?')) =
+                default_key(&state.rebound, code)
+            {
+                state.help = false;
+            }
+            return None;
+        }
+
+        match state.page {
+            Page::Search => match code {
+                event::KeyCode::Esc => {
+                    state.search.clear();
+                    state.preview = None;
+                    state.page = Page::Ticker;
+                }
+                event::KeyCode::Enter => match parse_symbol(&state.search.text) {
+                    Ok(symbol) => {
+                        state.search.submit();
+                        state.preview = None;
+                        state.page = Page::Ticker;
+                        return Some(Action::SubscribeTicker(symbol));
+                    }
+                    Err(message) => state.search.error = Some(message.to_string()),
+                },
+                event::KeyCode::F(2) => match parse_symbol(&state.search.text) {
+                    Ok(symbol) => return Some(Action::PreviewTicker(symbol)),
+                    Err(message) => state.search.error = Some(message.to_string()),
                 },
-                None => (),
+                code => {
+                    App::edit(&mut state.search, code, &symbols);
+                }
+            },
+            Page::Library => match code {
+                event::KeyCode::Esc => state.page = Page::Ticker,
+                event::KeyCode::Up => {
+                    state.library_selection = state.library_selection.saturating_sub(1)
+                }
+                event::KeyCode::Down if state.library_selection + 1 < state.library.len() => {
+                    state.library_selection += 1
+                }
+                event::KeyCode::Enter => {
+                    if let Some(recording) = state.library.get(state.library_selection) {
+                        state.page = Page::Ticker;
+                        return Some(Action::OpenRecording(recording.path.clone()));
+                    }
+                }
+                event::KeyCode::Char('q') => return Some(Action::Quit),
+                _ => (),
+            },
+            Page::Watchlist => {
+                let symbols = state.tickers.clone().unwrap_or_default();
+                match code {
+                    event::KeyCode::Up => {
+                        state.watchlist_selection = state.watchlist_selection.saturating_sub(1)
+                    }
+                    event::KeyCode::Down => {
+                        if state.watchlist_selection + 1 < symbols.len() {
+                            state.watchlist_selection += 1;
+                        }
+                    }
+                    event::KeyCode::Enter => {
+                        if let Some(symbol) = symbols.get(state.watchlist_selection) {
+                            state.current_ticker = Some(symbol.clone());
+                            state.page = Page::Ticker;
+                        }
+                    }
+                    code => return App::dispatch(state, code),
+                }
+            }
+            Page::Logs => {
+                let last = state.logs.entries().len().saturating_sub(1);
+                match code {
+                    event::KeyCode::Up => state.logs_scroll = (state.logs_scroll + 1).min(last),
+                    event::KeyCode::Down => state.logs_scroll = state.logs_scroll.saturating_sub(1),
+                    event::KeyCode::PageUp => {
+                        state.logs_scroll = (state.logs_scroll + 10).min(last)
+                    }
+                    event::KeyCode::PageDown => {
+                        state.logs_scroll = state.logs_scroll.saturating_sub(10)
+                    }
+                    event::KeyCode::Home => state.logs_scroll = last,
+                    event::KeyCode::End => state.logs_scroll = 0,
+                    code => return App::dispatch(state, code),
+                }
+            }
+            Page::Ticker if state.crosshair.is_some() => match code {
+                event::KeyCode::Esc => state.crosshair = None,
+                event::KeyCode::Left => App::move_crosshair(state, -1, 0),
+                event::KeyCode::Right => App::move_crosshair(state, 1, 0),
+                event::KeyCode::Down => App::move_crosshair(state, 0, -1),
+                event::KeyCode::Up => App::move_crosshair(state, 0, 1),
+                code => return App::dispatch(state, code),
+            },
+            _ => return App::dispatch(state, code),
+        }
+        None
+    }
+
+    /// Move the crosshair by a number of cells of the order map along its time and price axes
+    fn move_crosshair(state: &mut State, time_cells: i32, price_cells: i32) {
+        let (time_values, price_values) = state.map_resolution.unwrap_or((100, 100));
+        if let Some((time_fraction, price_fraction)) = state.crosshair {
+            state.crosshair = Some((
+                (time_fraction + time_cells as f64 / time_values as f64).clamp(0.0, 1.0),
+                (price_fraction + price_cells as f64 / price_values as f64).clamp(0.0, 1.0),
+            ));
+        }
+    }
+
+    /// Update state with a mouse event on the ticker page: clicking the other half of the split
+    /// view focuses its symbol, scrolling over the order map zooms its time axis and dragging it
+    /// pans back in time
+    fn handle_mouse(state: &mut State, mouse: event::MouseEvent) {
+        if state.error.is_some() || state.prompting || state.help || state.page != Page::Ticker {
+            return;
+        }
+        let position = Position::new(mouse.column, mouse.row);
+        let over_map = state.areas.map.is_some_and(|area| area.contains(position));
+        match mouse.kind {
+            event::MouseEventKind::Down(event::MouseButton::Left) => {
+                if state
+                    .areas
+                    .partner
+                    .is_some_and(|area| area.contains(position))
+                {
+                    std::mem::swap(&mut state.current_ticker, &mut state.split);
+                } else if over_map {
+                    state.drag = Some(mouse.column);
+                }
+            }
+            event::MouseEventKind::Drag(event::MouseButton::Left) => {
+                if let Some(column) = state.drag {
+                    // dragging towards the right brings older books into view
+                    let columns = mouse.column as f64 - column as f64;
+                    let shift = (columns * App::seconds_per_column(state)).round() as i64;
+                    if shift != 0 {
+                        state.time_pan = (state.time_pan + shift).clamp(0, App::max_pan(state));
+                        state.drag = Some(mouse.column);
+                    }
+                }
+            }
+            event::MouseEventKind::Up(event::MouseButton::Left) => state.drag = None,
+            event::MouseEventKind::Moved if over_map && state.crosshair.is_some() => {
+                state.crosshair = state.areas.map.map(|area| crosshair_at(area, position))
+            }
+            event::MouseEventKind::ScrollUp if over_map => App::zoom(state, 1),
+            event::MouseEventKind::ScrollDown if over_map => App::zoom(state, -1),
+            _ => (),
+        }
+    }
+
+    /// Halve the time range of the order map a number of times, doubling it for negative steps
+    fn zoom(state: &mut State, step: i32) {
+        state.time_zoom = state
+            .time_zoom
+            .saturating_add_signed(step)
+            .min(MAX_TIME_ZOOM);
+    }
+
+    /// Pan the order map by a fraction of its time range, back in time for positive fractions
+    fn pan(state: &mut State, fraction: f64) {
+        let span = App::time_span(state).unwrap_or(0) as f64;
+        let shift = (span * fraction).round() as i64;
+        state.time_pan = (state.time_pan + shift).clamp(0, App::max_pan(state));
+    }
+
+    /// private utility method giving the furthest the order map can be panned back while staying
+    /// within the cached history
+    fn max_pan(state: &State) -> i64 {
+        match (state.time_cached, state.time_window) {
+            (Some(cached), Some(window)) => (cached - window as i64).max(0),
+            _ => 0,
+        }
+    }
+
+    /// Change the arrangement of the ticker page panels and keep it for the next sessions
+    fn change_layout(state: &mut State, change: impl FnOnce(&mut PanelLayout)) {
+        change(&mut state.layout);
+        // the layout is a convenience, failing to keep it should not block the interface
+        if let Some(path) = &state.layout_file {
+            let _ = save_layout(path, &state.layout);
+        }
+    }
+
+    /// private utility method giving the seconds covered by a column of the displayed order map
+    fn seconds_per_column(state: &State) -> f64 {
+        match (App::time_span(state), state.map_resolution) {
+            (Some(span), Some((time_values, _))) if time_values > 0 => {
+                let (column_points, _) = state.marker.points_per_character();
+                column_points as f64 * span as f64 / time_values as f64
             }
+            _ => 0.0,
         }
     }
 
-    /// Run rendering loop for user interface
-    async fn run(state: Arc<Mutex<State>>) -> Result<(), String> {
-        let mut terminal = ratatui::init();
+    /// private utility method giving the seconds covered by the displayed order map
+    fn time_span(state: &State) -> Option<i64> {
+        state
+            .current_ticker
+            .as_ref()
+            .and_then(|symbol| state.views.get(symbol))
+            .into_iter()
+            .flat_map(|view| view.splatted.iter())
+            .find_map(|splatted| match splatted {
+                Splatted::Blocks(splatted) => Some(splatted.grid.time_range),
+                _ => None,
+            })
+            .map(|(start, end)| end - start)
+    }
 
-        let mut run_result = Ok(());
-        loop {
-            let clonned_state = state.lock().await.clone();
-            match terminal.draw(|frame| App::render(frame, clonned_state)) {
-                Ok(_) => (),
-                Err(message) => {
-                    run_result = Err(format!("{:?}", message));
-                    break;
+    /// Apply the binding of a key on the ticker, stats and logs pages and return the action it
+    /// triggers, if any
+    fn dispatch(state: &mut State, code: event::KeyCode) -> Option<Action> {
+        match code {
+            event::KeyCode::Esc => {
+                state.fullscreen = false;
+                state.page = Page::Ticker;
+            }
+            event::KeyCode::Char(digit @ '1'..='9') => {
+                let index = digit as usize - '1' as usize;
+                if let Some(symbol) = state.tickers.iter().flatten().nth(index) {
+                    state.current_ticker = Some(symbol.clone());
                 }
             }
-
-            match event::poll(std::time::Duration::from_millis(100)) {
-                Ok(true) => match event::read() {
-                    Ok(Event::Key(press)) => {
-                        if press.code == event::KeyCode::Char('q') {
-                            match state.lock().await.sender.send(Action::Quit).await {
-                                Ok(()) => (),
-                                Err(message) => run_result = Err(format!("{:?}", message)),
-                            }
-                            break;
+            code => {
+                let code = default_key(&state.rebound, code)?;
+                let (_, _, binding) = KEY_BINDINGS.iter().find(|(bound, _, _)| *bound == code)?;
+                match *binding {
+                    Binding::Help => state.help = true,
+                    Binding::Prompt => state.prompting = true,
+                    Binding::Search => state.page = Page::Search,
+                    Binding::Unsubscribe => {
+                        return state.current_ticker.clone().map(Action::UnsubscribeTicker);
+                    }
+                    Binding::NextTicker => App::cycle_ticker(state, 1),
+                    Binding::PreviousTicker => App::cycle_ticker(state, -1),
+                    Binding::Split => App::toggle_split(state),
+                    Binding::Freeze => {
+                        state.frozen = match state.frozen {
+                            Some(_) => None,
+                            None => Some(state.views.clone()),
                         }
                     }
-                    _ => (),
-                },
-                Ok(false) => (),
-                Err(message) => {
-                    run_result = Err(format!("{:?}", message));
-                    break;
+                    Binding::NextPage => App::cycle_page(state, 1),
+                    Binding::PreviousPage => App::cycle_page(state, -1),
+                    Binding::Watchlist => state.page = Page::Watchlist,
+                    Binding::Stats => state.page = Page::Stats,
+                    Binding::Diagnostics => state.page = Page::Diagnostics,
+                    Binding::Account => state.page = Page::Account,
+                    Binding::Indicators => state.page = Page::Indicators,
+                    Binding::Logs => state.page = Page::Logs,
+                    Binding::Library => return Some(Action::ScanLibrary),
+                    Binding::Slippage => {
+                        state.command.clear();
+                        "slippage "
+                            .chars()
+                            .for_each(|character| state.command.push(character));
+                        state.prompting = true;
+                    }
+                    Binding::Splat => return Some(Action::ToggleSplatMode(None)),
+                    Binding::Depth => return Some(Action::ToggleDepthMode),
+                    Binding::ColorScale => state.color_scale = state.color_scale.cycled(),
+                    Binding::Theme => state.theme = state.theme.cycled(),
+                    Binding::Export => return Some(Action::Export(ExportFormat::Npy)),
+                    Binding::Screenshot => return Some(Action::Screenshot(None)),
+                    Binding::Crosshair => {
+                        state.crosshair = match state.crosshair {
+                            Some(_) => None,
+                            None => Some((1.0, 0.5)),
+                        }
+                    }
+                    Binding::ZoomIn => App::zoom(state, 1),
+                    Binding::ZoomOut => App::zoom(state, -1),
+                    Binding::PanBack => App::pan(state, PAN_FRACTION),
+                    Binding::PanForward => App::pan(state, -PAN_FRACTION),
+                    Binding::Live => state.time_pan = 0,
+                    Binding::Oldest => state.time_pan = App::max_pan(state),
+                    Binding::ResetTime => {
+                        state.time_zoom = 0;
+                        state.time_pan = 0;
+                    }
+                    Binding::TogglePanel(panel) => {
+                        App::change_layout(state, |layout| layout.toggle(panel))
+                    }
+                    Binding::ResizeRows(step) => {
+                        App::change_layout(state, |layout| layout.resize_rows(step))
+                    }
+                    Binding::ResizeColumns(step) => {
+                        App::change_layout(state, |layout| layout.resize_columns(step))
+                    }
+                    Binding::Fullscreen => state.fullscreen = !state.fullscreen,
+                    Binding::Slower => {
+                        state.cadence = state.cadence.slowed();
+                        return Some(Action::Inform(state.cadence.describe()));
+                    }
+                    Binding::Faster => {
+                        state.cadence = state.cadence.sped_up();
+                        return Some(Action::Inform(state.cadence.describe()));
+                    }
+                    Binding::Focus => state.focus = state.focus.cycled(),
+                    Binding::Quit => return Some(Action::Quit),
                 }
             }
         }
+        None
+    }
 
-        ratatui::restore();
-        run_result
+    /// Apply a validated command from the prompt and return the action it triggers, if any
+    fn execute(state: &mut State, command: Command) -> Option<Action> {
+        match command {
+            Command::Alert(rule) => Some(Action::AlertRule(rule)),
+            Command::BookDepth(depth) => Some(Action::SetBookDepth(depth)),
+            Command::Cancel(order_id) => {
+                App::confirm(state, OrderTicket::Cancel { order_id });
+                None
+            }
+            Command::Depth => Some(Action::ToggleDepthMode),
+            Command::Export(format) => Some(Action::Export(format)),
+            Command::ExportHistory => Some(Action::ExportHistory),
+            Command::Screenshot(format) => Some(Action::Screenshot(format)),
+            Command::Library => Some(Action::ScanLibrary),
+            Command::Logs => {
+                state.page = Page::Logs;
+                None
+            }
+            Command::Queue(price) => {
+                state.queue_price = price;
+                if price.is_none() {
+                    state.queue = None;
+                }
+                None
+            }
+            Command::Order {
+                side,
+                quantity,
+                price,
+            } => {
+                match state.current_ticker.clone() {
+                    Some(symbol) => App::confirm(
+                        state,
+                        OrderTicket::Place {
+                            symbol,
+                            side,
+                            quantity,
+                            price,
+                        },
+                    ),
+                    None => state.error = Some("No ticker to place the order on".to_string()),
+                }
+                None
+            }
+            Command::Quit => Some(Action::Quit),
+            Command::Slippage(quantity) => {
+                state.slippage_quantity = quantity;
+                if quantity.is_none() {
+                    state.slippage = None;
+                }
+                None
+            }
+            Command::Splat(view) => Some(Action::ToggleSplatMode(view)),
+            Command::Stats => {
+                state.page = Page::Stats;
+                None
+            }
+            Command::Subscribe(symbol) => Some(Action::SubscribeTicker(symbol)),
+            Command::Ticker(symbol) => {
+                state.current_ticker = Some(symbol);
+                None
+            }
+            Command::Unsubscribe(symbol) => Some(Action::UnsubscribeTicker(symbol)),
+            Command::Window(seconds) => Some(Action::SetTimeWindow(seconds)),
+        }
+    }
+
+    /// private utility method asking the user to confirm an order, if order entry is enabled
+    fn confirm(state: &mut State, ticket: OrderTicket) {
+        match state.trading {
+            Some(_) => state.confirming = Some(ticket),
+            None => state.error = Some("Order entry needs the --trading flag".to_string()),
+        }
     }
 
     /// Render single frame using provided state object
-    fn render(frame: &mut Frame, state: State) {
-        let top_block = Block::bordered().title("bookedblocks");
+    /// Render the state to the frame, returning the areas of the panels mouse events act on
+    fn render(frame: &mut Frame, mut state: State) -> PanelAreas {
+        let mut areas = PanelAreas::default();
+        let top_block = match state.frozen.take() {
+            Some(frozen) => {
+                // symbols subscribed since freezing are displayed live
+                state.views.extend(frozen);
+                Block::bordered().title("bookedblocks (frozen)")
+            }
+            None => Block::bordered().title("bookedblocks"),
+        }
+        .title_bottom(status_line(&state));
 
         match state.page {
             Page::Search => {
@@ -633,10 +4116,36 @@ impl App {
                 ])
                 .split(vchunks[1]);
 
-                frame.render_widget(Block::bordered().title("Search"), hchunks[1]);
+                frame.render_widget(
+                    InputWidget::new(
+                        &state.search,
+                        "Search (Enter: subscribe, F2: preview depth)",
+                        "",
+                    ),
+                    hchunks[1],
+                );
+
+                let labels = state.labels(None);
+                if let Some((symbol, depth)) = state.preview {
+                    let preview_chunk = Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(vchunks[2])[1];
+                    let preview_block =
+                        Block::bordered().title(format!("{} (snapshot, not subscribed)", symbol));
+                    let depth_chunk = preview_block.inner(preview_chunk);
+                    frame.render_widget(preview_block, preview_chunk);
+                    frame.render_widget(
+                        DepthWidget::new(depth, None, state.theme, state.marker, labels),
+                        depth_chunk,
+                    );
+                }
             }
-            Page::Ticker => match state.current_ticker {
+            Page::Ticker => match state.current_ticker.clone() {
                 Some(symbol) => {
+                    let view = state.views.remove(&symbol).unwrap_or_default();
                     let vchunks = Layout::vertical(vec![
                         Constraint::Percentage(2),
                         Constraint::Percentage(96),
@@ -651,7 +4160,36 @@ impl App {
                     ])
                     .split(vchunks[1]);
 
-                    let ticker_block = Block::bordered().title(symbol.clone());
+                    let tabs = Line::from(
+                        state
+                            .tickers
+                            .iter()
+                            .flatten()
+                            .enumerate()
+                            .flat_map(|(index, ticker)| {
+                                let tab = Span::from(format!(" {}:{} ", index + 1, ticker));
+                                let tab = if *ticker == symbol {
+                                    tab.reversed().bold()
+                                } else {
+                                    tab.dark_gray()
+                                };
+                                [tab, Span::from("|")]
+                            })
+                            .collect::<Vec<_>>(),
+                    );
+                    let fresh = state
+                        .alert_time
+                        .is_some_and(|time| clock::now().timestamp() - time < ALERT_BANNER_SECONDS);
+                    let mut ticker_block = match &state.alert {
+                        Some(alert) if fresh => Block::bordered().title(tabs).title_bottom(
+                            Line::from(format!(" {} ", alert)).white().on_red().bold(),
+                        ),
+                        Some(alert) => Block::bordered()
+                            .title(tabs)
+                            .title_bottom(alert.clone().red().bold()),
+                        None => Block::bordered().title(tabs),
+                    };
+                    ticker_block = ticker_block.title(time_travel_line(&state).right_aligned());
                     frame.render_widget(ticker_block, hchunks[1]);
 
                     let data_chunk = Layout::vertical(vec![
@@ -668,73 +4206,279 @@ impl App {
                         .split(hchunks[1])[1],
                     )[1];
 
-                    let vertical_data_chunks = Layout::vertical(vec![
-                        Constraint::Percentage(65),
-                        Constraint::Percentage(35),
-                    ])
-                    .split(data_chunk);
-
-                    let top_data_chunks = Layout::horizontal(vec![
-                        Constraint::Percentage(65),
-                        Constraint::Percentage(35),
-                    ])
-                    .split(vertical_data_chunks[0]);
+                    if !state.fullscreen
+                        && let Some(partner) = state.split_partner(&symbol)
+                    {
+                        let partner_view = state.views.remove(&partner).unwrap_or_default();
+                        let halves = Layout::horizontal(vec![
+                            Constraint::Percentage(50),
+                            Constraint::Percentage(50),
+                        ])
+                        .split(data_chunk);
+                        areas.map = App::render_half(
+                            frame,
+                            halves[0],
+                            &symbol,
+                            view,
+                            &state,
+                            state.crosshair,
+                        );
+                        areas.partner = Some(halves[1]);
+                        App::render_half(frame, halves[1], &partner, partner_view, &state, None);
+                    } else {
+                        let panels = if state.fullscreen {
+                            frame.render_widget(Clear, frame.area());
+                            LayoutAreas::focused(state.focus, frame.area())
+                        } else {
+                            state.layout.split(data_chunk)
+                        };
+                        let labels = state.labels(view.price_decimals);
 
-                    let bottom_data_chunks = Layout::horizontal(vec![
-                        Constraint::Percentage(65),
-                        Constraint::Percentage(35),
-                    ])
-                    .split(vertical_data_chunks[1]);
+                        let time_series_chunks = Layout::vertical(vec![
+                            Constraint::Percentage(31),
+                            Constraint::Percentage(23),
+                            Constraint::Percentage(23),
+                            Constraint::Percentage(23),
+                        ])
+                        .split(panels.volumes.unwrap_or_default());
 
-                    match state.depth {
-                        Some(splatted) => {
-                            let depth_widget = DepthWidget::new(splatted);
-                            frame.render_widget(depth_widget, top_data_chunks[1]);
-                        }
-                        None => {
-                            frame.render_widget(
-                                Paragraph::new("Loading...").alignment(Alignment::Center),
-                                top_data_chunks[1],
-                            );
+                        let (mut depth, mut volumes, mut blocks, mut imbalance) =
+                            (None, None, None, None);
+                        let (mut prices, mut profile, mut flicker, mut volatility) =
+                            (None, None, None, None);
+                        let (mut impact, mut activity, mut trades, mut levels) =
+                            (None, None, None, None);
+                        let (mut twa_depth, mut ladder) = (None, None);
+                        // plugin panels are handed the results whole, only copied when drawn
+                        let plugin_splatted = match state.plugins.is_empty() {
+                            true => vec![],
+                            false => view.splatted.clone(),
+                        };
+                        for splatted in view.splatted {
+                            match splatted {
+                                Splatted::Depth(splatted) => depth = Some(splatted),
+                                Splatted::Volumes(splatted) => volumes = Some(splatted),
+                                Splatted::Blocks(splatted) => blocks = Some(splatted),
+                                Splatted::Imbalance(splatted) => imbalance = Some(splatted),
+                                Splatted::Prices(splatted) => prices = Some(splatted),
+                                Splatted::Profile(splatted) => profile = Some(splatted),
+                                Splatted::Flicker(splatted) => flicker = Some(splatted),
+                                Splatted::Volatility(splatted) => volatility = Some(splatted),
+                                Splatted::Impact(splatted) => impact = Some(splatted),
+                                Splatted::Activity(splatted) => activity = Some(splatted),
+                                Splatted::Trades(splatted) => trades = Some(splatted),
+                                Splatted::Levels(splatted) => levels = Some(splatted),
+                                Splatted::TwaDepth(splatted) => twa_depth = Some(splatted),
+                                Splatted::Ladder(splatted) => ladder = Some(splatted),
+                            }
                         }
-                    }
 
-                    match state.volumes {
-                        Some(splatted) => {
-                            let volume_widget = VolumeWidget::new(splatted);
-                            frame.render_widget(volume_widget, bottom_data_chunks[0]);
+                        if let Some(area) = panels.depth {
+                            match depth {
+                                Some(splatted) => {
+                                    let depth_widget = DepthWidget::new(
+                                        splatted,
+                                        levels.clone(),
+                                        state.theme,
+                                        state.marker,
+                                        labels,
+                                    );
+                                    frame.render_widget(depth_widget, area);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        area,
+                                    );
+                                }
+                            }
                         }
-                        None => {
-                            frame.render_widget(
-                                Paragraph::new("Loading...").alignment(Alignment::Center),
-                                bottom_data_chunks[0],
-                            );
+
+                        if let Some(area) = panels.ladder {
+                            match ladder {
+                                Some(splatted) => {
+                                    frame.render_widget(
+                                        LadderWidget::new(
+                                            splatted,
+                                            view.own_orders.clone(),
+                                            state.theme,
+                                            labels,
+                                        ),
+                                        area,
+                                    );
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        area,
+                                    );
+                                }
+                            }
                         }
-                    }
 
-                    match state.blocks {
-                        Some(splatted) => {
-                            let blocks_widget = HeatMapWidget::new(splatted);
-                            frame.render_widget(blocks_widget, top_data_chunks[0]);
+                        if panels.volumes.is_some() {
+                            match volumes {
+                                Some(splatted) => {
+                                    let ratio_widget = ImbalanceRatioWidget::new(
+                                        splatted.clone(),
+                                        state.theme,
+                                        labels,
+                                    );
+                                    frame.render_widget(ratio_widget, time_series_chunks[1]);
+                                    let volume_widget = VolumeWidget::new(
+                                        splatted,
+                                        state.theme,
+                                        state.marker,
+                                        labels,
+                                    );
+                                    frame.render_widget(volume_widget, time_series_chunks[0]);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        time_series_chunks[0],
+                                    );
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        time_series_chunks[1],
+                                    );
+                                }
+                            }
+
+                            match imbalance {
+                                Some(splatted) => {
+                                    let imbalance_widget = ImbalanceWidget::new(
+                                        splatted,
+                                        state.theme,
+                                        state.marker,
+                                        labels,
+                                    );
+                                    frame.render_widget(imbalance_widget, time_series_chunks[2]);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        time_series_chunks[2],
+                                    );
+                                }
+                            }
+
+                            match activity {
+                                Some(splatted) => {
+                                    let activity_widget =
+                                        ActivityWidget::new(splatted, state.theme, labels);
+                                    frame.render_widget(activity_widget, time_series_chunks[3]);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        time_series_chunks[3],
+                                    );
+                                }
+                            }
                         }
-                        None => {
-                            frame.render_widget(
-                                Paragraph::new("Loading...").alignment(Alignment::Center),
-                                top_data_chunks[0],
-                            );
+
+                        if let Some(area) = panels.map {
+                            match blocks {
+                                Some(splatted) => {
+                                    let blocks_widget = HeatMapWidget::new(
+                                        splatted,
+                                        prices,
+                                        trades,
+                                        levels.clone(),
+                                        view.walls,
+                                        view.own_orders.clone(),
+                                        state.crosshair,
+                                        state.heat_colors(),
+                                        state.marker,
+                                        labels,
+                                    );
+                                    frame.render_widget(blocks_widget, area);
+                                    areas.map = Some(area);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        area,
+                                    );
+                                }
+                            }
                         }
-                    }
 
-                    match state.ticker_data {
-                        Some(ticker) => {
-                            let ticker_widget = TickerWidget::new(ticker);
-                            frame.render_widget(ticker_widget, bottom_data_chunks[1]);
+                        if let Some(area) = panels.profile {
+                            match profile {
+                                Some(splatted) => {
+                                    let profile_widget = ProfileWidget::new(
+                                        splatted,
+                                        flicker,
+                                        state.theme,
+                                        state.marker,
+                                    );
+                                    frame.render_widget(profile_widget, area);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        area,
+                                    );
+                                }
+                            }
                         }
-                        None => {
-                            frame.render_widget(
-                                Paragraph::new("Loading...").alignment(Alignment::Center),
-                                bottom_data_chunks[1],
-                            );
+
+                        if let Some(area) = panels.ticker {
+                            let side_panels = 1
+                                + state.arbitrage.is_some() as u32
+                                + state.basis.is_some() as u32
+                                + state.plugins.len() as u32;
+                            let side_chunks = Layout::vertical(
+                                (0..side_panels).map(|_| Constraint::Ratio(1, side_panels)),
+                            )
+                            .split(area);
+                            let ticker_chunk = side_chunks[0];
+                            let mut side_index = 1;
+                            if let Some(splatted) = state.arbitrage {
+                                let arbitrage_widget = ArbitrageWidget::new(splatted, labels);
+                                frame.render_widget(arbitrage_widget, side_chunks[side_index]);
+                                side_index += 1;
+                            }
+                            if let Some(splatted) = state.basis {
+                                let basis_widget = BasisWidget::new(splatted, labels);
+                                frame.render_widget(basis_widget, side_chunks[side_index]);
+                                side_index += 1;
+                            }
+                            let context = PanelContext {
+                                symbol: &symbol,
+                                ticker: view.ticker_data.as_ref(),
+                                splatted: &plugin_splatted,
+                            };
+                            for plugin in state.plugins.iter() {
+                                let block = Block::bordered().title(plugin.name());
+                                let inner = block.inner(side_chunks[side_index]);
+                                frame.render_widget(block, side_chunks[side_index]);
+                                plugin.render(&context, inner, frame.buffer_mut());
+                                side_index += 1;
+                            }
+
+                            match view.ticker_data {
+                                Some(ticker) => {
+                                    let ticker_widget = TickerWidget::new(
+                                        ticker,
+                                        view.daily,
+                                        volatility,
+                                        impact,
+                                        twa_depth,
+                                        state.theme,
+                                        labels,
+                                    );
+                                    frame.render_widget(ticker_widget, ticker_chunk);
+                                }
+                                None => {
+                                    frame.render_widget(
+                                        Paragraph::new("Loading...").alignment(Alignment::Center),
+                                        ticker_chunk,
+                                    );
+                                }
+                            }
                         }
                     }
                 }
@@ -743,9 +4487,884 @@ impl App {
                     frame.area(),
                 ),
             },
-            Page::Logs => (),
+            Page::Library => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                frame.render_widget(
+                    LibraryWidget::new(state.library, state.library_selection),
+                    chunk,
+                );
+            }
+            Page::Stats => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                let chunks =
+                    Layout::vertical(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .split(chunk);
+
+                frame.render_widget(
+                    StatsWidget::new(state.metrics, state.cache_memory),
+                    chunks[0],
+                );
+                frame.render_widget(
+                    PipelineTimingsWidget::new(state.pipeline_timings, state.cadence.pipeline),
+                    chunks[1],
+                );
+            }
+            Page::Indicators => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                let indicators = state
+                    .current_ticker
+                    .as_ref()
+                    .and_then(|symbol| state.views.get(symbol))
+                    .map(|view| view.indicators.clone())
+                    .unwrap_or_default();
+                let price_decimals = state
+                    .current_ticker
+                    .as_ref()
+                    .and_then(|symbol| state.views.get(symbol))
+                    .and_then(|view| view.price_decimals);
+                frame.render_widget(
+                    IndicatorsWidget::new(
+                        state.current_ticker.clone(),
+                        indicators,
+                        state.labels(price_decimals),
+                    ),
+                    chunk,
+                );
+            }
+            Page::Account => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                frame.render_widget(AccountWidget::new(state.account), chunk);
+            }
+            Page::Diagnostics => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                let pipelines = state
+                    .views
+                    .iter()
+                    .filter_map(|(symbol, view)| {
+                        view.pipeline_elapsed
+                            .map(|elapsed| (symbol.clone(), elapsed))
+                    })
+                    .collect();
+                frame.render_widget(
+                    DiagnosticsWidget::new(
+                        state.diagnostics,
+                        state.metrics,
+                        pipelines,
+                        state.connected,
+                    ),
+                    chunk,
+                );
+            }
+            Page::Watchlist => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                let symbols = state.tickers.clone().unwrap_or_default();
+                let rows = symbols
+                    .into_iter()
+                    .map(|symbol| {
+                        let view = state.views.remove(&symbol).unwrap_or_default();
+                        let labels = state.labels(view.price_decimals);
+                        (symbol, view, labels)
+                    })
+                    .collect();
+                frame.render_widget(
+                    WatchlistWidget::new(rows, state.watchlist_selection, state.theme),
+                    chunk,
+                );
+            }
+            Page::Logs => {
+                let chunk = Layout::vertical(vec![
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ])
+                .split(
+                    Layout::horizontal(vec![
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ])
+                    .split(frame.area())[1],
+                )[1];
+
+                frame.render_widget(LogsWidget::new(state.logs, state.logs_scroll), chunk);
+            }
+        };
+
+        frame.render_widget(top_block, frame.area());
+
+        if let (Page::Ticker, Some(slippage)) = (&state.page, state.slippage) {
+            let popup_chunk = Layout::vertical(vec![
+                Constraint::Percentage(40),
+                Constraint::Length(5),
+                Constraint::Min(0),
+            ])
+            .split(
+                Layout::horizontal(vec![
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(25),
+                ])
+                .split(frame.area())[1],
+            )[1];
+            frame.render_widget(Clear, popup_chunk);
+            frame.render_widget(SlippageWidget::new(slippage), popup_chunk);
+        }
+
+        if let (Page::Ticker, Some(queue)) = (&state.page, state.queue) {
+            let popup_chunk = Layout::vertical(vec![
+                Constraint::Percentage(10),
+                Constraint::Length(12),
+                Constraint::Min(0),
+            ])
+            .split(
+                Layout::horizontal(vec![
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(25),
+                ])
+                .split(frame.area())[1],
+            )[1];
+            frame.render_widget(Clear, popup_chunk);
+            frame.render_widget(QueueWidget::new(queue), popup_chunk);
+        }
+
+        if state.help {
+            let popup_chunk = Layout::vertical(vec![
+                Constraint::Min(0),
+                Constraint::Length(KEY_BINDINGS.len() as u16 + 4),
+                Constraint::Min(0),
+            ])
+            .split(
+                Layout::horizontal(vec![
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(25),
+                ])
+                .split(frame.area())[1],
+            )[1];
+            frame.render_widget(Clear, popup_chunk);
+            frame.render_widget(HelpWidget::new(state.rebound.clone()), popup_chunk);
+        }
+
+        if state.prompting {
+            let prompt_chunk = Layout::vertical(vec![Constraint::Min(0), Constraint::Length(3)])
+                .split(frame.area())[1];
+            frame.render_widget(Clear, prompt_chunk);
+            frame.render_widget(
+                InputWidget::new(&state.command, "Command", ":"),
+                prompt_chunk,
+            );
+        }
+
+        if let (Some(ticket), Some(dry_run)) = (&state.confirming, state.trading) {
+            let popup_chunk = Layout::vertical(vec![
+                Constraint::Min(0),
+                Constraint::Length(6),
+                Constraint::Min(0),
+            ])
+            .split(
+                Layout::horizontal(vec![
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(25),
+                ])
+                .split(frame.area())[1],
+            )[1];
+            frame.render_widget(Clear, popup_chunk);
+            frame.render_widget(ConfirmWidget::new(ticket, dry_run), popup_chunk);
+        }
+
+        if let Some(error) = &state.error {
+            let popup_chunk = Layout::vertical(vec![
+                Constraint::Min(0),
+                Constraint::Length(7),
+                Constraint::Min(0),
+            ])
+            .split(
+                Layout::horizontal(vec![
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(25),
+                ])
+                .split(frame.area())[1],
+            )[1];
+            frame.render_widget(Clear, popup_chunk);
+            frame.render_widget(ErrorWidget::new(error), popup_chunk);
+        }
+
+        areas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pipeline::RenderGrid;
+
+    use ndarray::Array2;
+    use tokio::sync::mpsc::{Receiver, channel};
+    use tokio::time::timeout;
+
+    /// interface without its render loop, which needs a terminal, along with its action queue
+    async fn headless_app() -> (App, Receiver<Action>) {
+        let (sender, receiver) = channel(16);
+        let mut app = App::new(ActionSender::new(sender)).await;
+        if let Some(render_loop) = app.render_loop.take() {
+            render_loop.abort();
+        }
+        (app, receiver)
+    }
+
+    /// private utility method replacing the pipeline request loop by a task that stopped
+    async fn stop_pipeline_request_loop(app: &mut App, task: JoinHandle<Result<(), BookedError>>) {
+        timeout(Duration::from_secs(5), async {
+            while !task.is_finished() {
+                sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        std::mem::replace(&mut app.pipeline_request_loop, task).abort();
+    }
+
+    /// private utility method giving the current ticker an order map of 100 seconds drawn over
+    /// 100 columns, out of 300 cached seconds
+    fn scrubbable(state: &mut State) {
+        let symbol = "BTC/USD".to_string();
+        let grid = RenderGrid {
+            number_time_values: 100,
+            time_range: (0, 100),
+            number_price_values: 10,
+            price_range: (1.0, 2.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let blocks = SplattedBlocks {
+            mode: SplatMode::Histogram,
+            grid,
+            volumes: Array2::zeros((100, 10)),
+        };
+        state.views.insert(
+            symbol.clone(),
+            SymbolView {
+                splatted: vec![Splatted::Blocks(blocks)],
+                ..SymbolView::default()
+            },
+        );
+        state.current_ticker = Some(symbol);
+        state.time_window = Some(100);
+        state.time_cached = Some(300);
+        state.areas.map = Some(Rect::new(0, 0, 100, 20));
+        state.map_resolution = Some((100, 40));
+    }
+
+    #[tokio::test]
+    async fn test_changed() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        let mut drawn_generation = None;
+
+        // the first frame is drawn, idle ticks are not until the state changes
+        let mut locked_state = state.lock().await;
+        assert!(locked_state.changed(&mut drawn_generation).is_some());
+        assert!(locked_state.changed(&mut drawn_generation).is_none());
+        assert!(locked_state.changed(&mut drawn_generation).is_none());
+        locked_state.alert = Some("alert".to_string());
+        locked_state.touch();
+        let changed = locked_state.changed(&mut drawn_generation).unwrap();
+        assert_eq!(changed.alert, Some("alert".to_string()));
+        assert!(locked_state.changed(&mut drawn_generation).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_supervise_restarts() {
+        let (mut app, _receiver) = headless_app().await;
+        assert_eq!(app.supervise().await.unwrap(), None);
+
+        // a loop that failed or panicked is replaced by a running one
+        stop_pipeline_request_loop(
+            &mut app,
+            spawn(async { Err(BookedError::Parse("failed".to_string())) }),
+        )
+        .await;
+        assert_eq!(
+            app.supervise().await.unwrap(),
+            Some("Pipeline request loop stopped (failed), restarted".to_string())
+        );
+        assert!(!app.pipeline_request_loop.is_finished());
+
+        stop_pipeline_request_loop(&mut app, spawn(async { panic!("panicked") })).await;
+        let reason = app.supervise().await.unwrap().unwrap();
+        assert!(reason.contains("panicked"));
+        assert!(!app.pipeline_request_loop.is_finished());
+        assert_eq!(app.restarts, 2);
+        app.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_supervise_panic_hook() {
+        let (mut app, _receiver) = headless_app().await;
+
+        // the hook restores the terminal without ending the process, the panic reaching the
+        // supervisor which restarts the loop
+        crate::terminal::install_panic_hook();
+        stop_pipeline_request_loop(&mut app, spawn(async { panic!("hooked panic") })).await;
+        drop(std::panic::take_hook());
+
+        let reason = app.supervise().await.unwrap().unwrap();
+        assert!(reason.contains("hooked panic"));
+        assert!(!app.pipeline_request_loop.is_finished());
+        assert_eq!(app.restarts, 1);
+        app.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_supervise_gives_up() {
+        let (mut app, _receiver) = headless_app().await;
+        for _ in 0..MAX_RESTARTS {
+            stop_pipeline_request_loop(
+                &mut app,
+                spawn(async { Err(BookedError::Parse("failed".to_string())) }),
+            )
+            .await;
+            assert!(app.supervise().await.unwrap().is_some());
+        }
+
+        stop_pipeline_request_loop(
+            &mut app,
+            spawn(async { Err(BookedError::Parse("failed".to_string())) }),
+        )
+        .await;
+        match app.supervise().await {
+            Err(BookedError::TaskStopped { task, reason }) => {
+                assert_eq!(task, "Pipeline request loop");
+                assert_eq!(reason, format!("failed after {} restarts", MAX_RESTARTS));
+            }
+            _ => panic!("supervision did not give up"),
+        }
+        app.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_color_scale() {
+        // every scale spans [0, 1], log and gamma lifting a volume small next to the maximum
+        for scale in [ColorScale::Linear, ColorScale::Log, ColorScale::Gamma] {
+            assert_eq!(scale.normalize(0.0, 100.0), 0.0);
+            assert_eq!(scale.normalize(-100.0, 100.0), 1.0);
+            assert_eq!(scale.normalize(200.0, 100.0), 1.0);
+            assert!(scale.normalize(1.0, 100.0) < scale.normalize(2.0, 100.0));
+        }
+        let linear = ColorScale::Linear.normalize(1.0, 100.0);
+        assert!(ColorScale::Log.normalize(1.0, 100.0) > 0.3);
+        assert!(ColorScale::Gamma.normalize(1.0, 100.0) > linear * 10.0);
+        let colors = |scale| HeatColors {
+            theme: Theme::Default,
+            scale,
+            gradient: Gradient::Shades,
+            depth: ColorDepth::TrueColor,
+        };
+        assert_ne!(
+            colors(ColorScale::Log).color(-1.0, 100.0),
+            colors(ColorScale::Linear).color(-1.0, 100.0)
+        );
+
+        // the key cycles through the scales back to linear
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+        for scale in [ColorScale::Log, ColorScale::Gamma, ColorScale::Linear] {
+            assert!(App::handle_key(&mut locked_state, event::KeyCode::Char('n')).is_none());
+            assert_eq!(locked_state.color_scale, scale);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ticker_tabs() {
+        let (app, _receiver) = headless_app().await;
+        let symbols = ["SOL/USD", "BTC/USD", "ETH/USD"].map(|symbol| symbol.to_string());
+        app.set_tickers(symbols.to_vec()).await;
+        app.set_current_ticker("BTC/USD".to_string()).await;
+        let state = app.get_state();
+
+        // tabs are sorted and cycled both ways, digits selecting them directly
+        let mut locked_state = state.lock().await;
+        for (code, current) in [
+            (event::KeyCode::Tab, "ETH/USD"),
+            (event::KeyCode::Tab, "SOL/USD"),
+            (event::KeyCode::Tab, "BTC/USD"),
+            (event::KeyCode::BackTab, "SOL/USD"),
+            (event::KeyCode::Char('2'), "ETH/USD"),
+            (event::KeyCode::Char('9'), "ETH/USD"),
+        ] {
+            App::handle_key(&mut locked_state, code);
+            assert_eq!(locked_state.current_ticker.as_deref(), Some(current));
+        }
+
+        // each symbol keeps its own data, dropped along with its tab
+        for symbol in &symbols {
+            locked_state
+                .views
+                .insert(symbol.clone(), SymbolView::default());
+        }
+        drop(locked_state);
+        app.set_tickers(vec!["SOL/USD".to_string(), "BTC/USD".to_string()])
+            .await;
+        let locked_state = state.lock().await;
+        assert_eq!(locked_state.current_ticker.as_deref(), Some("BTC/USD"));
+        assert_eq!(locked_state.views.len(), 2);
+        assert!(!locked_state.views.contains_key("ETH/USD"));
+    }
+
+    #[tokio::test]
+    async fn test_key_bindings() {
+        let (app, _receiver) = headless_app().await;
+        app.set_tickers(vec!["BTC/USD".to_string()]).await;
+        app.set_current_ticker("BTC/USD".to_string()).await;
+        app.set_key_bindings(vec![('q', 'k')]).await.unwrap();
+        assert!(app.set_key_bindings(vec![('Q', 'k')]).await.is_err());
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+
+        // pages are switched from the table and left with Esc
+        App::handle_key(&mut locked_state, event::KeyCode::Char('m'));
+        assert_eq!(locked_state.page, Page::Stats);
+        App::handle_key(&mut locked_state, event::KeyCode::Char('l'));
+        assert_eq!(locked_state.page, Page::Logs);
+        App::handle_key(&mut locked_state, event::KeyCode::Esc);
+        assert_eq!(locked_state.page, Page::Ticker);
+        assert!(matches!(
+            App::handle_key(&mut locked_state, event::KeyCode::Char('u')),
+            Some(Action::UnsubscribeTicker(symbol)) if symbol == "BTC/USD"
+        ));
+
+        // the help overlay swallows keys until closed
+        App::handle_key(&mut locked_state, event::KeyCode::Char('?'));
+        assert!(locked_state.help);
+        assert!(App::handle_key(&mut locked_state, event::KeyCode::Char('k')).is_none());
+        App::handle_key(&mut locked_state, event::KeyCode::Char('?'));
+        assert!(!locked_state.help);
+
+        // a rebound key replaces its default one
+        assert!(App::handle_key(&mut locked_state, event::KeyCode::Char('q')).is_none());
+        assert!(matches!(
+            App::handle_key(&mut locked_state, event::KeyCode::Char('k')),
+            Some(Action::Quit)
+        ));
+
+        // the overlay lists every binding under the keys actually bound
+        let area = Rect::new(0, 0, 100, KEY_BINDINGS.len() as u16 + 4);
+        let mut buffer = Buffer::empty(area);
+        HelpWidget::new(locked_state.rebound.clone()).render(area, &mut buffer);
+        let lines = (0..area.height)
+            .map(|row| {
+                (0..area.width)
+                    .map(|column| buffer[(column, row)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        for (_, description, _) in KEY_BINDINGS.iter() {
+            assert!(lines.iter().any(|line| line.contains(description)));
+        }
+        let quit = lines.iter().find(|line| line.contains(" quit ")).unwrap();
+        assert!(quit.starts_with("│k "));
+    }
+
+    #[tokio::test]
+    async fn test_handle_mouse() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+        scrubbable(&mut locked_state);
+        let mouse = |kind, column| event::MouseEvent {
+            kind,
+            column,
+            row: 5,
+            modifiers: event::KeyModifiers::NONE,
+        };
+
+        // the wheel zooms over the order map only
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::ScrollUp, 10),
+        );
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::ScrollUp, 150),
+        );
+        assert_eq!(locked_state.time_zoom, 1);
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::ScrollDown, 10),
+        );
+        assert_eq!(locked_state.time_zoom, 0);
+
+        // dragging right pans back a second per column, within the cached history
+        let left = event::MouseButton::Left;
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::Down(left), 50),
+        );
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::Drag(left), 60),
+        );
+        assert_eq!(locked_state.time_pan, 10);
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::Drag(left), 400),
+        );
+        assert_eq!(locked_state.time_pan, 200);
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::Up(left), 400),
+        );
+        assert_eq!(locked_state.drag, None);
+
+        // clicking the other half of the split view focuses its symbol
+        locked_state.split = Some("ETH/USD".to_string());
+        locked_state.areas.partner = Some(Rect::new(100, 0, 50, 20));
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::Down(left), 120),
+        );
+        assert_eq!(locked_state.current_ticker.as_deref(), Some("ETH/USD"));
+        assert_eq!(locked_state.split.as_deref(), Some("BTC/USD"));
+
+        // the overlays keep the mouse away from the panels
+        locked_state.help = true;
+        App::handle_mouse(
+            &mut locked_state,
+            mouse(event::MouseEventKind::Down(left), 120),
+        );
+        assert_eq!(locked_state.current_ticker.as_deref(), Some("ETH/USD"));
+    }
+
+    #[tokio::test]
+    async fn test_time_travel_keys() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+        scrubbable(&mut locked_state);
+
+        // zooming stops at its deepest level
+        for _ in 0..MAX_TIME_ZOOM + 2 {
+            App::handle_key(&mut locked_state, event::KeyCode::Char('+'));
+        }
+        assert_eq!(locked_state.time_zoom, MAX_TIME_ZOOM);
+        App::handle_key(&mut locked_state, event::KeyCode::Char('-'));
+        assert_eq!(locked_state.time_zoom, MAX_TIME_ZOOM - 1);
+
+        // arrows pan by a tenth of the displayed range, within the cached history
+        for (code, time_pan) in [
+            (event::KeyCode::Left, 10),
+            (event::KeyCode::Left, 20),
+            (event::KeyCode::Right, 10),
+            (event::KeyCode::Right, 0),
+            (event::KeyCode::Right, 0),
+            (event::KeyCode::Home, 200),
+            (event::KeyCode::Left, 200),
+            (event::KeyCode::End, 0),
+            (event::KeyCode::Home, 200),
+        ] {
+            App::handle_key(&mut locked_state, code);
+            assert_eq!(locked_state.time_pan, time_pan);
+        }
+        App::handle_key(&mut locked_state, event::KeyCode::Char('0'));
+        assert_eq!((locked_state.time_zoom, locked_state.time_pan), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_time_travel_line() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+        scrubbable(&mut locked_state);
+        locked_state
+            .last_updates
+            .insert("BTC/USD".to_string(), 3_600_000);
+        let text = |state: &State| {
+            time_travel_line(state)
+                .spans
+                .iter()
+                .map(|span| span.content.to_string())
+                .collect::<String>()
+        };
+
+        assert_eq!(text(&locked_state), " ● LIVE ");
+        App::handle_key(&mut locked_state, event::KeyCode::Left);
+        assert_eq!(
+            text(&locked_state),
+            " ◀ HISTORICAL 10s back at 00:59:50 (End for live) "
+        );
+        App::handle_key(&mut locked_state, event::KeyCode::Char('+'));
+        App::handle_key(&mut locked_state, event::KeyCode::Home);
+        assert_eq!(
+            text(&locked_state),
+            " ◀ HISTORICAL 200s back at 00:56:40 x2 zoom (End for live) "
+        );
+        App::handle_key(&mut locked_state, event::KeyCode::End);
+        assert_eq!(text(&locked_state), " ● LIVE x2 zoom ");
+
+        // without a cached history to scrub through, the order map stays live
+        locked_state.time_cached = None;
+        App::handle_key(&mut locked_state, event::KeyCode::Home);
+        assert_eq!(locked_state.time_pan, 0);
+    }
+
+    #[tokio::test]
+    async fn test_freeze() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        {
+            let mut locked_state = state.lock().await;
+            scrubbable(&mut locked_state);
+            App::handle_key(&mut locked_state, event::KeyCode::Char(' '));
+            // the pipeline keeps updating the live data underneath
+            locked_state.views.get_mut("BTC/USD").unwrap().splatted = vec![];
+        }
+        let text = |buffer: Buffer| {
+            buffer
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        let frozen = text(app.screenshot().await.unwrap());
+        assert!(frozen.contains("bookedblocks (frozen)"));
+        // the order map is drawn along its time axis from the frozen data only
+        assert!(frozen.contains("Time (s)"));
+
+        App::handle_key(&mut *state.lock().await, event::KeyCode::Char(' '));
+        assert!(state.lock().await.frozen.is_none());
+        let live = text(app.screenshot().await.unwrap());
+        assert!(!live.contains("(frozen)"));
+        assert!(!live.contains("Time (s)"));
+    }
+
+    #[tokio::test]
+    async fn test_marker() {
+        // braille quadruples the points of the order map, dots halve them
+        let area = Rect::new(0, 0, 114, 25);
+        assert_eq!(map_resolution(area, PlotMarker::HalfBlock), (100, 40));
+        assert_eq!(map_resolution(area, PlotMarker::Braille), (200, 80));
+        assert_eq!(map_resolution(area, PlotMarker::Dot), (100, 20));
+        assert_eq!(
+            map_resolution(Rect::new(0, 0, 10, 5), PlotMarker::Braille),
+            (10, 10)
+        );
+
+        let (app, _receiver) = headless_app().await;
+        scrubbable(&mut *app.get_state().lock().await);
+        let braille = |symbol: &str| {
+            symbol
+                .chars()
+                .any(|character| ('\u{2801}'..='\u{28ff}').contains(&character))
+        };
+        let drawn = app.screenshot().await.unwrap();
+        assert!(drawn.content().iter().any(|cell| cell.symbol() == "▀"));
+        assert!(!drawn.content().iter().any(|cell| braille(cell.symbol())));
+
+        app.set_marker(PlotMarker::Braille).await;
+        let drawn = app.screenshot().await.unwrap();
+        assert!(!drawn.content().iter().any(|cell| cell.symbol() == "▀"));
+        assert!(drawn.content().iter().any(|cell| braille(cell.symbol())));
+    }
+
+    #[tokio::test]
+    async fn test_fullscreen() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        scrubbable(&mut *state.lock().await);
+        let area = Rect::new(0, 0, 120, 40);
+        let draw = |state: State| {
+            let mut terminal = Terminal::new(TestBackend::new(area.width, area.height)).unwrap();
+            let mut areas = PanelAreas::default();
+            terminal
+                .draw(|frame| areas = App::render(frame, state))
+                .unwrap();
+            areas.map
+        };
+
+        let map = draw(state.lock().await.clone()).unwrap();
+        assert!(map.width < area.width && map.height < area.height);
+
+        // the focused order map takes the whole terminal, other widgets hide it
+        let mut locked_state = state.lock().await;
+        App::handle_key(&mut locked_state, event::KeyCode::Char('f'));
+        assert_eq!(draw(locked_state.clone()), Some(area));
+        App::handle_key(&mut locked_state, event::KeyCode::Char('F'));
+        assert_eq!(locked_state.focus, Focus::Depth);
+        assert_eq!(draw(locked_state.clone()), None);
+
+        App::handle_key(&mut locked_state, event::KeyCode::Esc);
+        assert!(!locked_state.fullscreen);
+        assert_eq!(draw(locked_state.clone()), Some(map));
+    }
+
+    #[tokio::test]
+    async fn test_watchlist() {
+        // the last values of a series fit the width, a flat series sitting halfway
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], 8),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[8.0, 1.0, 2.0, 3.0], 3), "▁▅█");
+        assert_eq!(sparkline(&[2.0, 2.0], 8), "▄▄");
+        assert_eq!(sparkline(&[], 8), "");
+
+        let (app, _receiver) = headless_app().await;
+        app.set_tickers(vec!["BTC/USD".to_string(), "ETH/USD".to_string()])
+            .await;
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+        locked_state.views.insert(
+            "ETH/USD".to_string(),
+            SymbolView {
+                ticker_data: Some(TickerState {
+                    ask: 2001.0,
+                    ask_quantity: 1.0,
+                    bid: 1999.0,
+                    bid_quantity: 1.0,
+                    change: 30.0,
+                    change_pct: 1.5,
+                    high: 2010.0,
+                    low: 1950.0,
+                    last: 2000.0,
+                    symbol: "ETH/USD".to_string(),
+                    volume: 100.0,
+                    vwap: 1990.0,
+                }),
+                ..SymbolView::default()
+            },
+        );
+        App::handle_key(&mut locked_state, event::KeyCode::Char('w'));
+        assert_eq!(locked_state.page, Page::Watchlist);
+        let drawn = {
+            let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+            let clonned_state = locked_state.clone();
+            terminal
+                .draw(|frame| {
+                    App::render(frame, clonned_state);
+                })
+                .unwrap();
+            terminal.backend().buffer().clone()
         };
+        let line = (0..40)
+            .map(|row| {
+                (0..120)
+                    .map(|column| drawn[(column, row)].symbol())
+                    .collect::<String>()
+            })
+            .find(|line| line.contains("ETH/USD"))
+            .unwrap();
+        assert!(line.contains("2,000") && line.contains("+1.50%"));
+
+        // the selection stays on the listed symbols and Enter visualizes it
+        App::handle_key(&mut locked_state, event::KeyCode::Down);
+        App::handle_key(&mut locked_state, event::KeyCode::Down);
+        assert_eq!(locked_state.watchlist_selection, 1);
+        App::handle_key(&mut locked_state, event::KeyCode::Enter);
+        assert_eq!(locked_state.page, Page::Ticker);
+        assert_eq!(locked_state.current_ticker.as_deref(), Some("ETH/USD"));
+    }
+
+    #[tokio::test]
+    async fn test_dismiss_error() {
+        let (app, _receiver) = headless_app().await;
+        let state = app.get_state();
+        let mut locked_state = state.lock().await;
+        locked_state.error = Some("failed".to_string());
 
-        frame.render_widget(top_block, frame.area())
+        // the modal swallows keys until dismissed
+        assert!(App::handle_key(&mut locked_state, event::KeyCode::Char('q')).is_none());
+        App::handle_key(&mut locked_state, event::KeyCode::Char('m'));
+        assert_eq!(locked_state.page, Page::Ticker);
+        assert!(locked_state.error.is_some());
+        App::handle_key(&mut locked_state, event::KeyCode::Enter);
+        assert!(locked_state.error.is_none());
+        assert!(matches!(
+            App::handle_key(&mut locked_state, event::KeyCode::Char('q')),
+            Some(Action::Quit)
+        ));
     }
 }
@@ -0,0 +1,216 @@
+use crate::error::BookedError;
+use crate::feed::Booked;
+use crate::pipeline::{Splatted, Volume};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio_tungstenite::accept_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{Receiver, Sender, channel};
+use tokio::task::spawn;
+use tracing::{info, warn};
+
+use std::net::SocketAddr;
+
+/// Number of messages kept for clients lagging behind before they start missing some
+const CLIENT_BUFFER_SIZE: usize = 256;
+
+/// private message sent to the clients, tagged with its type
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Broadcast<'a> {
+    Book(&'a Booked),
+    Depth {
+        symbol: &'a str,
+        mode: String,
+        price_range: (f64, f64),
+        price_scale: String,
+        volumes: &'a [f64],
+    },
+    Volumes {
+        symbol: &'a str,
+        time_range: (i64, i64),
+        ask_volumes: &'a [f64],
+        bid_volumes: &'a [f64],
+        imbalance_ratios: &'a [f64],
+    },
+    Blocks {
+        symbol: &'a str,
+        mode: String,
+        time_range: (i64, i64),
+        price_axis: (f64, f64),
+        price_scale: String,
+        /// resting volumes, one row of prices per time
+        volumes: Vec<Vec<Volume>>,
+    },
+}
+
+/// Local websocket server rebroadcasting the raw book updates and the splatted depth, volumes and
+/// order map to every connected client as JSON, for browser based or remote frontends
+#[derive(Clone)]
+pub struct Broadcaster {
+    sender: Sender<String>,
+    address: SocketAddr,
+}
+
+impl Broadcaster {
+    /// constructor, listening for clients at an address
+    pub async fn bind(address: SocketAddr) -> Result<Broadcaster, BookedError> {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(message) => return Err(BookedError::Server(format!("{:?}", message))),
+        };
+        let address = match listener.local_addr() {
+            Ok(address) => address,
+            Err(message) => return Err(BookedError::Server(format!("{:?}", message))),
+        };
+        let (sender, _) = channel(CLIENT_BUFFER_SIZE);
+        spawn(Broadcaster::accept(listener, sender.clone()));
+        Ok(Broadcaster { sender, address })
+    }
+
+    /// address the server listens at
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// rebroadcast a book update
+    pub fn book(&self, booked: &Booked) {
+        self.send(Broadcast::Book(booked));
+    }
+
+    /// rebroadcast the depth, volumes and order map splatted for a symbol
+    pub fn splatted(&self, symbol: &str, splatted: &[Splatted]) {
+        for splatted in splatted.iter() {
+            match splatted {
+                Splatted::Depth(depth) => self.send(Broadcast::Depth {
+                    symbol,
+                    mode: format!("{:?}", depth.mode),
+                    price_range: depth.price_range,
+                    price_scale: format!("{:?}", depth.price_scale),
+                    volumes: &depth.volumes,
+                }),
+                Splatted::Volumes(volumes) => self.send(Broadcast::Volumes {
+                    symbol,
+                    time_range: volumes.time_range,
+                    ask_volumes: &volumes.ask_volumes,
+                    bid_volumes: &volumes.bid_volumes,
+                    imbalance_ratios: &volumes.imbalance_ratios,
+                }),
+                Splatted::Blocks(blocks) => self.send(Broadcast::Blocks {
+                    symbol,
+                    mode: format!("{:?}", blocks.mode),
+                    time_range: blocks.grid.time_range,
+                    price_axis: blocks.grid.price_axis(),
+                    price_scale: format!("{:?}", blocks.grid.price_scale),
+                    volumes: blocks
+                        .volumes
+                        .rows()
+                        .into_iter()
+                        .map(|row| row.to_vec())
+                        .collect(),
+                }),
+                _ => (),
+            }
+        }
+    }
+
+    /// private utility method serializing a message for the connected clients, if any
+    fn send(&self, broadcast: Broadcast) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        match serde_json::to_string(&broadcast) {
+            // failing to send only means every client disconnected meanwhile
+            Ok(text) => {
+                let _ = self.sender.send(text);
+            }
+            Err(message) => warn!(%message, "could not serialize broadcast"),
+        }
+    }
+
+    /// private utility method accepting clients until the application exits
+    async fn accept(listener: TcpListener, sender: Sender<String>) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    spawn(Broadcaster::serve(stream, peer, sender.subscribe()));
+                }
+                Err(message) => warn!(%message, "could not accept websocket client"),
+            }
+        }
+    }
+
+    /// private utility method forwarding the broadcast messages to a client until it disconnects
+    async fn serve(stream: TcpStream, peer: SocketAddr, mut receiver: Receiver<String>) {
+        let mut websocket = match accept_async(stream).await {
+            Ok(websocket) => websocket,
+            Err(message) => {
+                warn!(%peer, %message, "websocket handshake failed");
+                return;
+            }
+        };
+        info!(%peer, "websocket client connected");
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(text) => {
+                        if websocket.send(Message::text(text)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(%peer, skipped, "websocket client lagging behind");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                incoming = websocket.next() => match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // clients only listen, anything else they send is ignored
+                    Some(Ok(_)) => (),
+                },
+            }
+        }
+        info!(%peer, "websocket client disconnected");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::Order;
+
+    use tokio::time::{Duration, sleep};
+    use tokio_tungstenite::connect_async;
+
+    #[tokio::test]
+    async fn test_broadcaster() {
+        let broadcaster = Broadcaster::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let (mut client, _) = connect_async(format!("ws://{}", broadcaster.address()))
+            .await
+            .unwrap();
+        while broadcaster.sender.receiver_count() == 0 {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        broadcaster.book(&Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: "2023-11-14T22:13:20+00:00".to_string(),
+            bids: vec![],
+            asks: vec![Order {
+                price: 100.5,
+                quantity: 1.0,
+            }],
+        });
+        let received = client.next().await.unwrap().unwrap();
+        let value: serde_json::Value = serde_json::from_str(received.to_text().unwrap()).unwrap();
+        assert_eq!(value["type"], "book");
+        assert_eq!(value["symbol"], "BTC/USD");
+        assert_eq!(value["asks"][0]["price"], 100.5);
+    }
+}
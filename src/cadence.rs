@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+/// Shortest and longest periods at which the pipeline of the current ticker can be requested
+const PIPELINE_BOUNDS: (Duration, Duration) = (Duration::from_millis(250), Duration::from_secs(10));
+
+/// Shortest and longest periods at which terminal events can be polled for
+const POLL_BOUNDS: (Duration, Duration) = (Duration::from_millis(25), Duration::from_secs(1));
+
+/// Periods at which the interface is refreshed, slowed down for slow ssh sessions or sped up for
+/// fast local terminals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cadence {
+    /// period at which the pipeline of the current ticker is requested
+    pub pipeline: Duration,
+    /// period at which terminal events are polled for, bounding the latency of key presses
+    pub poll: Duration,
+}
+
+impl Default for Cadence {
+    fn default() -> Cadence {
+        Cadence {
+            pipeline: Duration::from_secs(1),
+            poll: Duration::from_millis(100),
+        }
+    }
+}
+
+impl Cadence {
+    /// constructor, clamping periods within their bounds
+    pub fn new(pipeline: Duration, poll: Duration) -> Cadence {
+        Cadence {
+            pipeline: pipeline.clamp(PIPELINE_BOUNDS.0, PIPELINE_BOUNDS.1),
+            poll: poll.clamp(POLL_BOUNDS.0, POLL_BOUNDS.1),
+        }
+    }
+
+    /// cadence refreshing half as often
+    pub fn slowed(self) -> Cadence {
+        Cadence::new(self.pipeline * 2, self.poll * 2)
+    }
+
+    /// cadence refreshing twice as often
+    pub fn sped_up(self) -> Cadence {
+        Cadence::new(self.pipeline / 2, self.poll / 2)
+    }
+
+    /// description of the cadence, as shown in the interface
+    pub fn describe(&self) -> String {
+        format!(
+            "Refreshing every {} ms, polling keys every {} ms",
+            self.pipeline.as_millis(),
+            self.poll.as_millis()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cadence() {
+        let cadence = Cadence::default();
+        assert_eq!(
+            cadence.slowed(),
+            Cadence {
+                pipeline: Duration::from_secs(2),
+                poll: Duration::from_millis(200),
+            }
+        );
+        assert_eq!(cadence.slowed().sped_up(), cadence);
+        // each period stops at its own bound
+        assert_eq!(
+            cadence.sped_up().sped_up().sped_up(),
+            Cadence {
+                pipeline: Duration::from_millis(250),
+                poll: Duration::from_millis(25),
+            }
+        );
+        assert_eq!(
+            Cadence::new(Duration::from_secs(60), Duration::ZERO),
+            Cadence {
+                pipeline: Duration::from_secs(10),
+                poll: Duration::from_millis(25),
+            }
+        );
+    }
+}
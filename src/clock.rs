@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Instant;
+
+/// Monotonic time base mapped onto exchange time
+///
+/// the wall clock is read once at startup, after which time only advances with the monotonic
+/// clock, so that NTP or DST adjustments never move data timestamps backwards. Exchange
+/// timestamps observed along the way push the mapping forward when the local clock lags behind.
+#[derive(Debug)]
+pub struct Clock {
+    /// monotonic instant the mapping is anchored at
+    origin: Instant,
+    /// exchange time in milliseconds corresponding to the origin
+    anchor_in_ms: AtomicI64,
+}
+
+impl Clock {
+    /// constructor
+    pub fn new(anchor: DateTime<Utc>, origin: Instant) -> Clock {
+        Clock {
+            origin,
+            anchor_in_ms: AtomicI64::new(anchor.timestamp_millis()),
+        }
+    }
+
+    /// milliseconds elapsed on the monotonic clock from the origin to an instant
+    fn elapsed_in_ms(&self, instant: Instant) -> i64 {
+        instant.saturating_duration_since(self.origin).as_millis() as i64
+    }
+
+    /// exchange time at a monotonic instant
+    pub fn at(&self, instant: Instant) -> DateTime<Utc> {
+        let time_in_ms = self.anchor_in_ms.load(Ordering::Relaxed) + self.elapsed_in_ms(instant);
+        DateTime::from_timestamp_millis(time_in_ms).unwrap_or_default()
+    }
+
+    /// exchange time now
+    pub fn now(&self) -> DateTime<Utc> {
+        self.at(Instant::now())
+    }
+
+    /// account for an exchange timestamp received at a monotonic instant, only ever moving the
+    /// mapping forward
+    pub fn observe_at(&self, exchange_time: DateTime<Utc>, instant: Instant) {
+        self.anchor_in_ms.fetch_max(
+            exchange_time.timestamp_millis() - self.elapsed_in_ms(instant),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// account for an exchange timestamp received now
+    pub fn observe(&self, exchange_time: DateTime<Utc>) {
+        self.observe_at(exchange_time, Instant::now())
+    }
+}
+
+/// Clock shared by the whole application, anchored the first time it is used
+static CLOCK: LazyLock<Clock> = LazyLock::new(|| Clock::new(Utc::now(), Instant::now()));
+
+/// exchange time now according to the application clock
+pub fn now() -> DateTime<Utc> {
+    CLOCK.now()
+}
+
+/// account for an exchange timestamp in the application clock
+pub fn observe(exchange_time: DateTime<Utc>) {
+    CLOCK.observe(exchange_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_clock_follows_monotonic_time() {
+        let origin = Instant::now();
+        let anchor = DateTime::from_timestamp(1_000, 0).unwrap();
+        let clock = Clock::new(anchor, origin);
+
+        assert_eq!(clock.at(origin), anchor);
+        assert_eq!(
+            clock.at(origin + Duration::from_secs(90)),
+            DateTime::from_timestamp(1_090, 0).unwrap()
+        );
+        assert_eq!(clock.at(origin - Duration::from_secs(1)), anchor);
+    }
+
+    #[test]
+    fn test_clock_ignores_wall_clock_jumps() {
+        let origin = Instant::now();
+        let clock = Clock::new(DateTime::from_timestamp(1_000, 0).unwrap(), origin);
+
+        // an exchange timestamp an hour behind, e.g. after a DST or NTP jump, is ignored
+        clock.observe_at(
+            DateTime::from_timestamp(1_010 - 3_600, 0).unwrap(),
+            origin + Duration::from_secs(10),
+        );
+        assert_eq!(
+            clock.at(origin + Duration::from_secs(20)),
+            DateTime::from_timestamp(1_020, 0).unwrap()
+        );
+
+        // an exchange timestamp ahead of the local clock moves the mapping forward
+        clock.observe_at(
+            DateTime::from_timestamp(1_035, 0).unwrap(),
+            origin + Duration::from_secs(30),
+        );
+        assert_eq!(
+            clock.at(origin + Duration::from_secs(40)),
+            DateTime::from_timestamp(1_045, 0).unwrap()
+        );
+    }
+}
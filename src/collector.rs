@@ -0,0 +1,650 @@
+use crate::actions::{Action, Query};
+use crate::api::{self, book_json, heatmap_json, symbols_json};
+use crate::broadcast::Broadcaster;
+use crate::clock;
+use crate::emit::{Emitter, Summary};
+use crate::error::BookedError;
+use crate::export::{ExportFormat, export_snapshot};
+use crate::feed::{Booked, Feed};
+#[cfg(feature = "kafka")]
+use crate::kafka::KafkaSink;
+use crate::pipeline::{BookHistory, Pipeline, Splatted, SplattedBlocks};
+use crate::relay::{Relay, replay_lines};
+use crate::timeseries::TimeSeriesWriter;
+use crate::{RECONNECT_PERIOD, TIMESERIES_PERIOD};
+
+use chrono::DateTime;
+use clap::ValueEnum;
+
+use tokio::sync::mpsc::{Receiver, Sender, channel};
+use tokio::sync::{Mutex, oneshot};
+use tokio::task::{JoinHandle, spawn};
+use tokio::time::interval;
+use tracing::{error, info, warn};
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs::{File, OpenOptions, create_dir_all};
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Outputs written by the headless collector
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Collected {
+    /// splatted order map, depth and volumes of every symbol, exported periodically
+    Splatted,
+    /// book updates as received, in the recording format read by the library page
+    Raw,
+    /// both splatted arrays and raw updates
+    Both,
+}
+
+/// Destination of the raw book updates
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawOutput {
+    /// standard output, a `# symbol: <SYMBOL>` line announcing every change of symbol
+    Stdout,
+    /// one `<SYMBOL>-<unix seconds>.txt` recording per symbol in a directory
+    Directory(PathBuf),
+    /// a single recording file, created or truncated, laid out as the standard output
+    File(PathBuf),
+}
+
+/// Collector running the feed, the book histories and the pipeline without any interface, for
+/// servers gathering data without a terminal
+pub struct Collector {
+    /// receiver end of action queue
+    action_receiver: Receiver<Action>,
+    /// sender end of action queue
+    action_sender: Sender<Action>,
+    /// order book data feed
+    feed: Feed,
+    /// time window in seconds of the book histories
+    time_cache_window_seconds: usize,
+    /// book history of every subscribed symbol
+    histories: HashMap<String, BookHistory>,
+    /// pipeline splatting the histories
+    pipeline: Pipeline,
+    /// period between two pipeline runs of every symbol
+    period: Duration,
+    /// directory and format of the splatted arrays, None to skip splatting
+    exports: Option<(PathBuf, ExportFormat)>,
+    /// destination of the raw book updates, None to skip them
+    raw: Option<RawOutput>,
+    /// open recording of every symbol when writing raw updates to a directory
+    recordings: HashMap<String, BufWriter<File>>,
+    /// recording file open when writing raw updates to a single file
+    raw_file: Option<BufWriter<File>>,
+    /// symbol of the last raw update written to standard output or to a single file
+    last_raw_symbol: Option<String>,
+    /// writer of the processed stream for other tools, None to emit nothing
+    emitter: Option<Emitter>,
+    /// websocket server rebroadcasting books and splatted arrays, None to serve nothing
+    broadcaster: Option<Broadcaster>,
+    /// writer of the per second aggregates of the books, None to write nothing
+    timeseries: Option<TimeSeriesWriter>,
+    /// publisher of the books, trades and tickers received to Kafka, None to publish nothing
+    #[cfg(feature = "kafka")]
+    kafka: Option<KafkaSink>,
+    /// Unix socket server relaying the collection to attached interfaces, None to relay nothing
+    relay: Option<Relay>,
+    /// whether the REST API is served, its heatmaps needing pipeline runs
+    serving: bool,
+    /// latest order map splatted for every symbol
+    heatmaps: Arc<Mutex<HashMap<String, SplattedBlocks>>>,
+    /// pipeline run of every symbol still in flight, to skip runs piling up behind it
+    pipelines_in_flight: HashMap<String, JoinHandle<()>>,
+    /// instant of the last attempt at connecting the feed again, None before the first
+    last_reconnect: Option<Instant>,
+}
+
+impl Collector {
+    /// constructor
+    pub async fn new(
+        buffer_size: usize,
+        websocket_timeout_seconds: u64,
+        book_depth: i32,
+        time_cache_window_seconds: usize,
+        pipeline: Pipeline,
+        period: Duration,
+    ) -> Result<Collector, BookedError> {
+        let (sender, receiver) = channel::<Action>(buffer_size);
+        let feed = Feed::new(websocket_timeout_seconds, book_depth, sender.clone()).await?;
+
+        Ok(Collector {
+            action_receiver: receiver,
+            action_sender: sender,
+            feed,
+            time_cache_window_seconds,
+            histories: HashMap::new(),
+            pipeline,
+            period,
+            exports: None,
+            raw: None,
+            recordings: HashMap::new(),
+            raw_file: None,
+            last_raw_symbol: None,
+            emitter: None,
+            broadcaster: None,
+            timeseries: None,
+            #[cfg(feature = "kafka")]
+            kafka: None,
+            relay: None,
+            serving: false,
+            heatmaps: Arc::new(Mutex::new(HashMap::new())),
+            pipelines_in_flight: HashMap::new(),
+            last_reconnect: None,
+        })
+    }
+
+    /// export the splatted arrays of every symbol to a directory, in a format
+    pub fn set_exports(&mut self, directory: PathBuf, format: ExportFormat) {
+        self.exports = Some((directory, format));
+    }
+
+    /// write the raw book updates to a destination
+    pub fn set_raw_output(&mut self, raw: RawOutput) {
+        self.raw = Some(raw);
+    }
+
+    /// emit the books, tickers and pipeline summaries collected for other tools
+    pub fn set_emitter(&mut self, emitter: Emitter) {
+        self.emitter = Some(emitter);
+    }
+
+    /// rebroadcast the books collected and the arrays splatted to websocket clients
+    pub fn set_broadcaster(&mut self, broadcaster: Broadcaster) {
+        self.broadcaster = Some(broadcaster);
+    }
+
+    /// write the aggregates of the latest books to a time series database every second
+    pub fn set_timeseries(&mut self, writer: TimeSeriesWriter) {
+        self.timeseries = Some(writer);
+    }
+
+    /// publish the books, trades and tickers received to Kafka topics
+    #[cfg(feature = "kafka")]
+    pub fn set_kafka(&mut self, kafka: KafkaSink) {
+        self.kafka = Some(kafka);
+    }
+
+    /// serve the REST API at an address, returning the address listened at
+    pub async fn serve_api(&mut self, address: SocketAddr) -> Result<SocketAddr, BookedError> {
+        self.serving = true;
+        api::serve(address, self.action_sender.clone()).await
+    }
+
+    /// listen for interfaces attaching at a Unix socket, to which the collection is relayed
+    pub fn serve_socket(&mut self, path: &Path) -> Result<(), BookedError> {
+        self.relay = Some(Relay::bind(path, self.action_sender.clone())?);
+        Ok(())
+    }
+
+    /// subscribe a symbol to the feed, ignoring already collected symbols
+    pub async fn subscribe(&mut self, ticker: String) -> Result<(), BookedError> {
+        if self.histories.contains_key(&ticker) {
+            return Ok(());
+        }
+        self.histories.insert(
+            ticker.clone(),
+            BookHistory::new(self.time_cache_window_seconds),
+        );
+        if let Some(relay) = &self.relay {
+            relay.subscribed(&ticker);
+        }
+        self.feed.subscribe(ticker).await
+    }
+
+    /// private utility method requesting the splatting of every symbol collected regularly, the
+    /// symbols subscribed by attached interfaces included
+    async fn schedule_pipelines(
+        sender: Sender<Action>,
+        period: Duration,
+    ) -> Result<(), BookedError> {
+        let mut timer = interval(period);
+        // the first tick completes immediately, before any book came in
+        timer.tick().await;
+        loop {
+            timer.tick().await;
+            let (responder, answered) = oneshot::channel();
+            sender
+                .send(Action::Query(Query::Symbols, responder))
+                .await?;
+            let symbols = match answered.await {
+                Ok(Some(symbols)) => {
+                    serde_json::from_str::<Vec<String>>(&symbols).unwrap_or_default()
+                }
+                _ => vec![],
+            };
+            for symbol in symbols {
+                sender.send(Action::RunPipeline(symbol)).await?;
+            }
+        }
+    }
+
+    /// private utility method splatting a history in a separate thread, keeping its order map for
+    /// the REST API, rebroadcasting the arrays, emitting the summary of the run and exporting the
+    /// arrays
+    fn spawn_pipeline(
+        symbol: String,
+        history: BookHistory,
+        pipeline: Pipeline,
+        exports: Option<(PathBuf, ExportFormat)>,
+        emitter: Option<Emitter>,
+        broadcaster: Option<Broadcaster>,
+        heatmaps: Arc<Mutex<HashMap<String, SplattedBlocks>>>,
+    ) -> JoinHandle<()> {
+        spawn(async move {
+            let start = Instant::now();
+            let (splatted, _) = pipeline.run(&history).await;
+            if let Some(blocks) = splatted.iter().find_map(|splatted| match splatted {
+                Splatted::Blocks(blocks) => Some(blocks.clone()),
+                _ => None,
+            }) {
+                heatmaps.lock().await.insert(symbol.clone(), blocks);
+            }
+            if let Some(broadcaster) = broadcaster {
+                broadcaster.splatted(&symbol, &splatted);
+            }
+            if let Some(emitter) = emitter {
+                let summary =
+                    Summary::new(symbol.clone(), &history, &splatted, start.elapsed()).await;
+                if let Err(message) = emitter.summary(&summary).await {
+                    warn!(symbol, %message, "could not emit summary");
+                }
+            }
+            let Some((directory, format)) = exports else {
+                return;
+            };
+            let (mut blocks, mut depth, mut volumes) = (None, None, None);
+            for splatted in splatted.iter() {
+                match splatted {
+                    Splatted::Blocks(splatted) => blocks = Some(splatted),
+                    Splatted::Depth(splatted) => depth = Some(splatted),
+                    Splatted::Volumes(splatted) => volumes = Some(splatted),
+                    _ => (),
+                }
+            }
+            let (Some(blocks), Some(depth), Some(volumes)) = (blocks, depth, volumes) else {
+                return;
+            };
+            let Some(time) = history.latest_time().await else {
+                return;
+            };
+            match export_snapshot(&directory, &symbol, time, format, blocks, depth, volumes) {
+                Ok(folder) => info!(symbol, folder = %folder.display(), "exported"),
+                Err(message) => warn!(symbol, %message, "could not export"),
+            }
+        })
+    }
+
+    /// private utility method writing a book update to the raw output
+    fn write_raw(&mut self, booked: &Booked) -> Result<(), BookedError> {
+        let Some(raw) = &self.raw else {
+            return Ok(());
+        };
+        let lines = recording_lines(booked)?;
+        match raw {
+            RawOutput::Stdout | RawOutput::File(_) => {
+                let mut stdout;
+                let output: &mut dyn Write = match raw {
+                    RawOutput::File(path) => match &mut self.raw_file {
+                        Some(file) => file,
+                        None => {
+                            self.raw_file
+                                .insert(BufWriter::new(File::create(path).map_err(|message| {
+                                    BookedError::Storage(message.to_string())
+                                })?))
+                        }
+                    },
+                    _ => {
+                        stdout = std::io::stdout().lock();
+                        &mut stdout
+                    }
+                };
+                if self.last_raw_symbol.as_ref() != Some(&booked.symbol) {
+                    self.last_raw_symbol = Some(booked.symbol.clone());
+                    writeln!(output, "# symbol: {}", booked.symbol)
+                        .map_err(|message| BookedError::Storage(message.to_string()))?;
+                }
+                output
+                    .write_all(lines.as_bytes())
+                    .map_err(|message| BookedError::Storage(message.to_string()))
+            }
+            RawOutput::Directory(directory) => {
+                let recording = match self.recordings.entry(booked.symbol.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(
+                        open_recording(directory, &booked.symbol)
+                            .map_err(|message| BookedError::Storage(message.to_string()))?,
+                    ),
+                };
+                recording
+                    .write_all(lines.as_bytes())
+                    .map_err(|message| BookedError::Storage(message.to_string()))
+            }
+        }
+    }
+
+    /// private utility method flushing the raw output, so that recordings can be read while
+    /// collecting
+    fn flush_raw(&mut self) -> Result<(), BookedError> {
+        let flushed = match &self.raw {
+            Some(RawOutput::Stdout) => std::io::stdout().flush(),
+            Some(RawOutput::Directory(_)) => self
+                .recordings
+                .values_mut()
+                .try_for_each(|recording| recording.flush()),
+            Some(RawOutput::File(_)) => match &mut self.raw_file {
+                Some(file) => file.flush(),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        };
+        flushed.map_err(|message| BookedError::Storage(message.to_string()))
+    }
+
+    /// private utility method connecting the feed again once its listener stopped, at most once
+    /// per reconnection period
+    async fn reconnect(&mut self) {
+        if self.feed.is_live()
+            || self
+                .last_reconnect
+                .is_some_and(|last| last.elapsed() < RECONNECT_PERIOD)
+        {
+            return;
+        }
+        self.last_reconnect = Some(Instant::now());
+        if let Err(message) = self.feed.reconnect().await {
+            warn!(%message, "could not reconnect the feed");
+            return;
+        }
+        info!("feed reconnected");
+        for ticker in self.histories.keys().cloned().collect::<Vec<_>>() {
+            if let Err(message) = self.feed.subscribe(ticker.clone()).await {
+                warn!(ticker, %message, "could not subscribe again");
+            }
+        }
+    }
+
+    /// run the collection until interrupted
+    pub async fn run(&mut self) -> Result<(), BookedError> {
+        if !self.feed.is_live() {
+            return Err(BookedError::NotLive);
+        }
+        info!(symbols = ?self.histories.keys().collect::<Vec<_>>(), "collection started");
+
+        spawn(Collector::schedule_pipelines(
+            self.action_sender.clone(),
+            self.period,
+        ));
+        if self.timeseries.is_some() {
+            let sender = self.action_sender.clone();
+            spawn(async move {
+                let mut timer = interval(TIMESERIES_PERIOD);
+                loop {
+                    timer.tick().await;
+                    if sender.send(Action::WriteTimeSeries).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        let sender = self.action_sender.clone();
+        spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                let _ = sender.send(Action::Quit).await;
+            }
+        });
+
+        while let Some(action) = self.action_receiver.recv().await {
+            match action {
+                Action::UpdateBook(booked) => {
+                    if let Some(broadcaster) = &self.broadcaster {
+                        broadcaster.book(&booked);
+                    }
+                    if let Some(relay) = &self.relay {
+                        relay.book(&booked);
+                    }
+                    if let Some(emitter) = &self.emitter
+                        && let Err(message) = emitter.book(&booked).await
+                    {
+                        error!(symbol = booked.symbol, %message, "could not emit book");
+                    }
+                    #[cfg(feature = "kafka")]
+                    if let Some(kafka) = &self.kafka
+                        && let Err(message) = kafka.book(&booked)
+                    {
+                        error!(symbol = booked.symbol, %message, "could not publish book");
+                    }
+                    if let Err(message) = self.write_raw(&booked) {
+                        error!(symbol = booked.symbol, %message, "could not write raw update");
+                    }
+                    if let Some(history) = self.histories.get_mut(&booked.symbol) {
+                        let symbol = booked.symbol.clone();
+                        if let Err(message) = history.update(booked).await {
+                            error!(symbol, %message, "could not update the book");
+                        }
+                    }
+                }
+                Action::UpdateTrades(trades) => {
+                    if let Some(relay) = &self.relay {
+                        relay.trades(&trades);
+                    }
+                    #[cfg(feature = "kafka")]
+                    if let Some(kafka) = &self.kafka
+                        && let Err(message) = kafka.trades(&trades)
+                    {
+                        error!(%message, "could not publish trades");
+                    }
+                    if let Some(symbol) = trades.first().map(|trade| trade.symbol.clone())
+                        && let Some(history) = self.histories.get_mut(&symbol)
+                        && let Err(message) = history.update_trades(trades).await
+                    {
+                        error!(symbol, %message, "could not record trades");
+                    }
+                }
+                Action::UpdateTicker(ticker) => {
+                    if let Some(relay) = &self.relay {
+                        relay.ticker(&ticker);
+                    }
+                    if let Some(emitter) = &self.emitter
+                        && let Err(message) = emitter.ticker(&ticker).await
+                    {
+                        error!(symbol = ticker.symbol, %message, "could not emit ticker");
+                    }
+                    #[cfg(feature = "kafka")]
+                    if let Some(kafka) = &self.kafka
+                        && let Err(message) = kafka.ticker(&ticker)
+                    {
+                        error!(symbol = ticker.symbol, %message, "could not publish ticker");
+                    }
+                }
+                Action::RunPipeline(symbol) => {
+                    self.reconnect().await;
+                    self.flush_raw()?;
+                    let Some(history) = self.histories.get(&symbol) else {
+                        continue;
+                    };
+                    if self.exports.is_none()
+                        && self.emitter.is_none()
+                        && self.broadcaster.is_none()
+                        && !self.serving
+                    {
+                        continue;
+                    }
+                    if self
+                        .pipelines_in_flight
+                        .get(&symbol)
+                        .is_some_and(|handle| !handle.is_finished())
+                    {
+                        warn!(
+                            symbol,
+                            "skipping pipeline run, the previous one is still running"
+                        );
+                        continue;
+                    }
+                    let handle = Collector::spawn_pipeline(
+                        symbol.clone(),
+                        history.extract_window(0, i64::MAX).await,
+                        self.pipeline.clone(),
+                        self.exports.clone(),
+                        self.emitter.clone(),
+                        self.broadcaster.clone(),
+                        self.heatmaps.clone(),
+                    );
+                    self.pipelines_in_flight.insert(symbol, handle);
+                }
+                Action::Alert(message) | Action::Inform(message) => info!("{}", message),
+                Action::Warn(message) => warn!("{}", message),
+                Action::Error(message) => error!("{}", message),
+                Action::Query(query, responder) => {
+                    let answer = match query {
+                        Query::Symbols => Some(symbols_json(self.histories.keys())),
+                        Query::Book(symbol) => match self.histories.get(&symbol) {
+                            Some(history) => book_json(&symbol, history).await,
+                            None => None,
+                        },
+                        Query::Heatmap(symbol) => self
+                            .heatmaps
+                            .lock()
+                            .await
+                            .get(&symbol)
+                            .map(|blocks| heatmap_json(&symbol, blocks)),
+                        Query::Replay(symbol) => match self.histories.get(&symbol) {
+                            Some(history) => match replay_lines(&symbol, history).await {
+                                Ok(lines) => Some(lines),
+                                Err(message) => {
+                                    warn!(symbol, %message, "could not replay history");
+                                    None
+                                }
+                            },
+                            None => None,
+                        },
+                    };
+                    // the client may have hung up meanwhile
+                    let _ = responder.send(answer);
+                }
+                Action::WriteTimeSeries => {
+                    if let Some(writer) = &mut self.timeseries
+                        && let Err(message) = writer.push(self.histories.iter()).await
+                    {
+                        warn!(%message, "could not queue aggregates");
+                    }
+                }
+                Action::SubscribeTicker(ticker) => {
+                    if let Err(message) = self.subscribe(ticker.clone()).await {
+                        error!(ticker, %message, "could not subscribe");
+                    }
+                }
+                Action::Quit => break,
+                _ => (),
+            }
+        }
+
+        info!("collection stopped");
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &self.kafka
+            && let Err(message) = kafka.flush()
+        {
+            error!(%message, "could not flush kafka");
+        }
+        self.flush_raw()
+    }
+}
+
+/// lines of a book update in the `<unix seconds> <ask|bid> <price> <quantity>` recording format
+pub fn recording_lines(booked: &Booked) -> Result<String, BookedError> {
+    let time = match DateTime::parse_from_rfc3339(&booked.timestamp) {
+        Ok(time) => time.timestamp(),
+        Err(source) => {
+            return Err(BookedError::Timestamp {
+                timestamp: booked.timestamp.clone(),
+                source,
+            });
+        }
+    };
+    let mut lines = String::new();
+    for (side, orders) in [("ask", &booked.asks), ("bid", &booked.bids)] {
+        for order in orders.iter() {
+            lines.push_str(&format!(
+                "{} {} {} {}\n",
+                time, side, order.price, order.quantity
+            ));
+        }
+    }
+    Ok(lines)
+}
+
+/// private utility method creating the recording of a symbol in a directory, headed by its
+/// symbol
+fn open_recording(directory: &Path, symbol: &str) -> std::io::Result<BufWriter<File>> {
+    create_dir_all(directory)?;
+    let path = directory.join(format!(
+        "{}-{}.txt",
+        symbol.replace('/', "-"),
+        clock::now().timestamp()
+    ));
+    let mut recording = BufWriter::new(OpenOptions::new().create_new(true).write(true).open(path)?);
+    writeln!(recording, "# symbol: {}", symbol)?;
+    Ok(recording)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::Order;
+    use crate::recording::load_recording;
+
+    use std::fs::write;
+
+    #[test]
+    fn test_recording_lines() {
+        let booked = Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0)
+                .unwrap()
+                .to_rfc3339(),
+            bids: vec![Order {
+                price: 99.5,
+                quantity: 2.0,
+            }],
+            asks: vec![
+                Order {
+                    price: 100.5,
+                    quantity: 1.0,
+                },
+                Order {
+                    price: 101.0,
+                    quantity: 0.0,
+                },
+            ],
+        };
+        let lines = recording_lines(&booked).unwrap();
+        assert_eq!(
+            lines,
+            "1700000000 ask 100.5 1\n1700000000 ask 101 0\n1700000000 bid 99.5 2\n"
+        );
+
+        // raw updates read back as recordings
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("collector.txt");
+        write(&path, format!("# symbol: BTC/USD\n{}", lines)).unwrap();
+        let loaded = load_recording(&path, "BTC/USD").unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].timestamp, booked.timestamp);
+        assert_eq!(loaded[0].asks, booked.asks);
+        assert_eq!(loaded[0].bids, booked.bids);
+
+        let malformed = Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: "yesterday".to_string(),
+            bids: vec![],
+            asks: vec![],
+        };
+        assert!(recording_lines(&malformed).is_err());
+    }
+}
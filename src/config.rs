@@ -0,0 +1,234 @@
+use crate::error::BookedError;
+use crate::input::BOOK_DEPTHS;
+use crate::layout::PanelLayout;
+use crate::theme::Theme;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use toml_edit::{DocumentMut, Item, Table, value};
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Settings read from the configuration file, None for those left to the command line flags or
+/// their defaults
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// number of actions the dispatch queue holds
+    pub buffer_size: Option<usize>,
+    /// seconds the websocket feed waits for messages before giving up
+    pub ws_timeout: Option<u64>,
+    /// number of levels per side of the subscribed books
+    pub depth: Option<i32>,
+    /// seconds of books kept in cache
+    pub cache_window: Option<usize>,
+    /// seconds of books displayed
+    pub visual_window: Option<u64>,
+    /// resolution of the time axes of the views
+    pub time_res: Option<usize>,
+    /// resolution of the price axes of the views
+    pub price_res: Option<usize>,
+    /// name of the theme, as given to --theme
+    pub theme: Option<String>,
+    /// milliseconds between display updates
+    pub refresh_ms: Option<u64>,
+    /// milliseconds between polls for key presses
+    pub poll_ms: Option<u64>,
+    /// keys bound in place of the default ones, e.g. `q = "x"` to quit with x
+    pub keys: BTreeMap<String, String>,
+    /// arrangement of the ticker page panels, saved whenever changed from the interface
+    pub layout: Option<PanelLayout>,
+}
+
+impl Config {
+    /// configuration written in TOML
+    pub fn parse(text: &str) -> Result<Config, BookedError> {
+        let mut config: Config = toml::from_str(text)?;
+        if let Some(depth) = config.depth
+            && !BOOK_DEPTHS.contains(&depth)
+        {
+            return Err(BookedError::Config(format!(
+                "depth should be one of {:?}",
+                BOOK_DEPTHS
+            )));
+        }
+        let counts = [
+            config.buffer_size,
+            config.cache_window,
+            config.time_res,
+            config.price_res,
+        ];
+        let seconds = [config.ws_timeout, config.visual_window];
+        if counts.contains(&Some(0)) || seconds.contains(&Some(0)) {
+            return Err(BookedError::Config(
+                "sizes, windows, timeouts and resolutions should be positive".to_string(),
+            ));
+        }
+        config.theme()?;
+        config.key_bindings()?;
+        config.layout = config.layout.map(PanelLayout::sanitized);
+        Ok(config)
+    }
+
+    /// theme of the configuration, when set
+    pub fn theme(&self) -> Result<Option<Theme>, BookedError> {
+        match &self.theme {
+            Some(name) => match Theme::from_str(name, true) {
+                Ok(theme) => Ok(Some(theme)),
+                Err(_) => Err(BookedError::Config(format!("Unknown theme '{}'", name))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// pairs of a default key and the key bound in its place
+    pub fn key_bindings(&self) -> Result<Vec<(char, char)>, BookedError> {
+        let single = |key: &str| {
+            let mut characters = key.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None) => Ok(character),
+                _ => Err(BookedError::Config(format!(
+                    "Invalid key '{}', expected a single character",
+                    key
+                ))),
+            }
+        };
+        self.keys
+            .iter()
+            .map(|(default, bound)| Ok((single(default)?, single(bound)?)))
+            .collect()
+    }
+}
+
+/// Location of the configuration file, under $XDG_CONFIG_HOME or ~/.config
+pub fn config_path() -> Option<PathBuf> {
+    std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|config| !config.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .map(|config| config.join("bookedblocks").join("config.toml"))
+}
+
+/// Read a configuration file, the default location being allowed to be missing
+pub fn load_config(path: Option<&Path>) -> Result<Config, BookedError> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match config_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(text) => match Config::parse(&text) {
+            Ok(config) => Ok(config),
+            Err(source) => Err(BookedError::ConfigFile {
+                path,
+                source: Box::new(source),
+            }),
+        },
+        Err(source) if required || source.kind() != std::io::ErrorKind::NotFound => {
+            Err(BookedError::File { path, source })
+        }
+        Err(_) => Ok(Config::default()),
+    }
+}
+
+/// Keep the panel layout in the `[layout]` table of a configuration file, leaving the rest of
+/// the file as written
+pub fn save_layout(path: &Path, layout: &PanelLayout) -> Result<(), BookedError> {
+    let file = |source| BookedError::File {
+        path: path.to_path_buf(),
+        source,
+    };
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(source) => return Err(file(source)),
+    };
+    let mut document = text.parse::<DocumentMut>()?;
+
+    let mut table = Table::new();
+    table["map"] = value(layout.map);
+    table["depth"] = value(layout.depth);
+    table["volumes"] = value(layout.volumes);
+    table["ticker"] = value(layout.ticker);
+    table["rows"] = value(layout.rows as i64);
+    table["columns"] = value(layout.columns as i64);
+    document["layout"] = Item::Table(table);
+
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory).map_err(file)?;
+    }
+    std::fs::write(path, document.to_string()).map_err(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layout::{Panel, RESIZE_STEP};
+
+    #[test]
+    fn test_config() {
+        let config = Config::parse(
+            r#"
+            depth = 500
+            visual-window = 120
+            theme = "high-contrast"
+
+            [keys]
+            q = "x"
+            "?" = "h"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.depth, Some(500));
+        assert_eq!(config.visual_window, Some(120));
+        assert_eq!(config.buffer_size, None);
+        assert_eq!(config.theme().unwrap(), Some(Theme::HighContrast));
+        assert_eq!(config.key_bindings().unwrap(), vec![('?', 'h'), ('q', 'x')]);
+
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::parse("depht = 500").is_err());
+        assert!(Config::parse("depth = \"deep\"").is_err());
+        assert!(Config::parse("depth = 50").is_err());
+        assert!(Config::parse("time-res = 0").is_err());
+        assert!(Config::parse("theme = \"solarized\"").is_err());
+        assert!(Config::parse("[keys]\nq = \"Ctrl+x\"").is_err());
+        assert!(Config::parse("[layout]\npanels = 3").is_err());
+    }
+
+    #[test]
+    fn test_layout() {
+        let config = Config::parse("[layout]\ndepth = false\nrows = 500").unwrap();
+        assert_eq!(
+            config.layout,
+            Some(PanelLayout {
+                depth: false,
+                rows: 80,
+                ..PanelLayout::default()
+            })
+        );
+
+        // the layout is saved along the settings and comments already written
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("layout.toml");
+        std::fs::write(&path, "# written by hand\ndepth = 500\n").unwrap();
+        let mut layout = PanelLayout::default();
+        layout.toggle(Panel::Volumes);
+        layout.resize_columns(-RESIZE_STEP);
+        save_layout(&path, &layout).unwrap();
+        save_layout(&path, &layout).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("# written by hand\n"));
+        let config = load_config(Some(&path)).unwrap();
+        assert_eq!(config.depth, Some(500));
+        assert_eq!(config.layout, Some(layout));
+    }
+}
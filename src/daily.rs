@@ -0,0 +1,153 @@
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+
+/// Time zone in which a trading day starts and ends
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Rollover {
+    /// midnight UTC
+    Utc,
+    /// midnight in the exchange's time zone, UTC for Kraken
+    Exchange,
+    /// midnight in the local time zone of the machine
+    Local,
+}
+
+impl Rollover {
+    /// trading day a unix timestamp in seconds belongs to
+    pub fn day(&self, time: i64) -> Option<NaiveDate> {
+        let utc = DateTime::from_timestamp(time, 0)?;
+        match self {
+            Rollover::Utc | Rollover::Exchange => Some(utc.date_naive()),
+            Rollover::Local => Some(utc.with_timezone(&Local).date_naive()),
+        }
+    }
+}
+
+/// Statistics of a symbol over the current trading day
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyStats {
+    pub rollover: Rollover,
+    pub day: NaiveDate,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub volume: f64,
+}
+
+/// Tracker of daily statistics computed locally, resetting at each rollover
+#[derive(Clone, Debug)]
+pub struct DailyTracker {
+    rollover: Rollover,
+    stats: Option<DailyStats>,
+    /// last rolling 24h volume reported by the exchange
+    rolling_volume: Option<f64>,
+}
+
+impl DailyTracker {
+    /// constructor
+    pub fn new(rollover: Rollover) -> DailyTracker {
+        DailyTracker {
+            rollover,
+            stats: None,
+            rolling_volume: None,
+        }
+    }
+
+    /// start a new day if time is past the rollover, returning the stats of the current day
+    fn roll(&mut self, time: i64, price: f64) -> Option<&mut DailyStats> {
+        let day = self.rollover.day(time)?;
+        if self.stats.as_ref().is_none_or(|stats| stats.day < day) {
+            self.stats = Some(DailyStats {
+                rollover: self.rollover,
+                day,
+                open: price,
+                high: price,
+                low: price,
+                volume: 0.0,
+            });
+        }
+        self.stats.as_mut()
+    }
+
+    /// account for a mid price observed at a unix timestamp in seconds
+    pub fn update_price(&mut self, time: i64, price: f64) {
+        if let Some(stats) = self.roll(time, price) {
+            stats.high = stats.high.max(price);
+            stats.low = stats.low.min(price);
+        }
+    }
+
+    /// account for the exchange's rolling 24h volume reported at a unix timestamp in seconds
+    ///
+    /// without a trade feed, traded volume is approximated by the increases of the rolling
+    /// volume, which misses trades concurrent with older trades leaving the 24h window
+    pub fn update_volume(&mut self, time: i64, rolling_volume: f64) {
+        let increase = match self.rolling_volume {
+            Some(previous) => (rolling_volume - previous).max(0.0),
+            None => 0.0,
+        };
+        self.rolling_volume = Some(rolling_volume);
+
+        if let Some(stats) = self.stats.as_mut() {
+            if self.rollover.day(time).is_some_and(|day| day > stats.day) {
+                self.stats = None;
+            } else {
+                stats.volume += increase;
+            }
+        }
+    }
+
+    /// statistics of the current day, if any price was observed
+    pub fn stats(&self) -> Option<DailyStats> {
+        self.stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_rollover_day() {
+        assert_eq!(
+            Rollover::Utc.day(DAY - 1),
+            NaiveDate::from_ymd_opt(1970, 1, 1)
+        );
+        assert_eq!(Rollover::Utc.day(DAY), NaiveDate::from_ymd_opt(1970, 1, 2));
+        assert_eq!(Rollover::Exchange.day(DAY), Rollover::Utc.day(DAY));
+    }
+
+    #[test]
+    fn test_daily_tracker() {
+        let mut tracker = DailyTracker::new(Rollover::Utc);
+        assert_eq!(tracker.stats(), None);
+
+        tracker.update_volume(10, 100.0);
+        tracker.update_price(10, 5.0);
+        tracker.update_price(20, 7.0);
+        tracker.update_price(30, 3.0);
+        tracker.update_volume(40, 104.0);
+        tracker.update_volume(50, 102.0);
+        tracker.update_volume(60, 103.0);
+
+        let stats = tracker.stats().unwrap();
+        assert_eq!(stats.day, NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+        assert_eq!(
+            (stats.open, stats.high, stats.low, stats.volume),
+            (5.0, 7.0, 3.0, 5.0)
+        );
+
+        tracker.update_price(DAY + 10, 4.0);
+        let stats = tracker.stats().unwrap();
+        assert_eq!(stats.day, NaiveDate::from_ymd_opt(1970, 1, 2).unwrap());
+        assert_eq!(
+            (stats.open, stats.high, stats.low, stats.volume),
+            (4.0, 4.0, 4.0, 0.0)
+        );
+
+        tracker.update_volume(2 * DAY + 10, 110.0);
+        assert_eq!(tracker.stats(), None);
+    }
+}
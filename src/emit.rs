@@ -0,0 +1,191 @@
+use crate::error::BookedError;
+use crate::feed::{Booked, TickerState};
+use crate::pipeline::{BookHistory, Splatted};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use tokio::sync::Mutex;
+
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Format of the processed stream emitted for other tools
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum EmitFormat {
+    /// one JSON object per line, tagged with its type
+    Jsonl,
+}
+
+/// Summary of a pipeline run over the history of a symbol
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub symbol: String,
+    /// unix seconds of the latest book of the history
+    pub time: i64,
+    pub best_ask: Option<f64>,
+    pub best_bid: Option<f64>,
+    /// latest resting volumes of each side and their normalized imbalance
+    pub ask_volume: Option<f64>,
+    pub bid_volume: Option<f64>,
+    pub imbalance: Option<f64>,
+    /// price range of the order map
+    pub price_range: Option<(f64, f64)>,
+    pub elapsed_ms: f64,
+}
+
+impl Summary {
+    /// constructor, from the history splatted and the outputs of the run
+    pub async fn new(
+        symbol: String,
+        history: &BookHistory,
+        splatted: &[Splatted],
+        elapsed: Duration,
+    ) -> Summary {
+        let ((time, asks), (_, bids)) = history.get_latest_book().await;
+        let mut summary = Summary {
+            symbol,
+            time,
+            best_ask: asks.get_first().map(|(price, _)| price.value),
+            best_bid: bids.get_last().map(|(price, _)| price.value),
+            ask_volume: None,
+            bid_volume: None,
+            imbalance: None,
+            price_range: None,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        };
+        for splatted in splatted.iter() {
+            match splatted {
+                Splatted::Volumes(volumes) => {
+                    summary.ask_volume = volumes.ask_volumes.last().copied();
+                    summary.bid_volume = volumes.bid_volumes.last().copied();
+                    summary.imbalance = volumes.imbalance_ratios.last().copied();
+                }
+                Splatted::Blocks(blocks) => summary.price_range = Some(blocks.grid.price_range),
+                _ => (),
+            }
+        }
+        summary
+    }
+}
+
+/// private record of the stream, tagged with its type
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Emitted<'a> {
+    Book(&'a Booked),
+    Ticker(&'a TickerState),
+    Summary(&'a Summary),
+}
+
+/// Writer of the books, tickers and pipeline summaries as newline delimited JSON, shared between
+/// the dispatcher and the pipeline threads
+#[derive(Clone)]
+pub struct Emitter {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Emitter {
+    /// constructor, appending to a file or writing to the standard output if None
+    pub fn new(destination: Option<&Path>) -> Result<Emitter, BookedError> {
+        let writer: Box<dyn Write + Send> = match destination {
+            Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => Box::new(BufWriter::new(file)),
+                Err(message) => return Err(BookedError::Emit(format!("{:?}", message))),
+            },
+            None => Box::new(std::io::stdout()),
+        };
+        Ok(Emitter {
+            writer: Arc::new(Mutex::new(writer)),
+        })
+    }
+
+    /// emit a book update
+    pub async fn book(&self, booked: &Booked) -> Result<(), BookedError> {
+        self.emit(Emitted::Book(booked)).await
+    }
+
+    /// emit a ticker update
+    pub async fn ticker(&self, ticker: &TickerState) -> Result<(), BookedError> {
+        self.emit(Emitted::Ticker(ticker)).await
+    }
+
+    /// emit the summary of a pipeline run
+    pub async fn summary(&self, summary: &Summary) -> Result<(), BookedError> {
+        self.emit(Emitted::Summary(summary)).await
+    }
+
+    /// private utility method writing a record on its own line, flushed for consumers reading the
+    /// stream as it comes
+    async fn emit(&self, emitted: Emitted<'_>) -> Result<(), BookedError> {
+        let mut line = match serde_json::to_string(&emitted) {
+            Ok(line) => line,
+            Err(message) => return Err(BookedError::Emit(format!("{:?}", message))),
+        };
+        line.push('\n');
+        let mut writer = self.writer.lock().await;
+        match writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.flush())
+        {
+            Ok(()) => Ok(()),
+            Err(message) => Err(BookedError::Emit(format!("{:?}", message))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::Order;
+
+    use std::fs::read_to_string;
+
+    #[tokio::test]
+    async fn test_emitter() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("emit.jsonl");
+        let emitter = Emitter::new(Some(&path)).unwrap();
+
+        let booked = Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: "2023-11-14T22:13:20+00:00".to_string(),
+            bids: vec![Order {
+                price: 99.5,
+                quantity: 2.0,
+            }],
+            asks: vec![Order {
+                price: 100.5,
+                quantity: 1.0,
+            }],
+        };
+        emitter.book(&booked).await.unwrap();
+        let mut history = BookHistory::new(60);
+        history.update(booked).await.unwrap();
+        let summary = Summary::new("BTC/USD".to_string(), &history, &[], Duration::ZERO).await;
+        assert_eq!(summary.time, 1_700_000_000);
+        assert_eq!(summary.best_bid, Some(99.5));
+        assert_eq!(summary.best_ask, Some(100.5));
+        emitter.summary(&summary).await.unwrap();
+
+        let content = read_to_string(&path).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                concat!(
+                    r#"{"type":"book","symbol":"BTC/USD","timestamp":"2023-11-14T22:13:20+00:00","#,
+                    r#""bids":[{"price":99.5,"quantity":2.0}],"asks":[{"price":100.5,"quantity":1.0}]}"#
+                ),
+                concat!(
+                    r#"{"type":"summary","symbol":"BTC/USD","time":1700000000,"best_ask":100.5,"#,
+                    r#""best_bid":99.5,"ask_volume":null,"bid_volume":null,"imbalance":null,"#,
+                    r#""price_range":null,"elapsed_ms":0.0}"#
+                ),
+            ]
+        );
+    }
+}
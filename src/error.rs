@@ -0,0 +1,148 @@
+use kraken_async_rs::clients::errors::ClientError;
+use kraken_async_rs::wss::errors::WSSError;
+
+use thiserror::Error;
+use tokio::sync::mpsc::error::SendError;
+use tokio::task::JoinError;
+
+use std::path::PathBuf;
+
+/// Enum of the errors of the feed, of the book histories and of the dispatch of actions
+#[derive(Debug, Error)]
+pub enum BookedError {
+    /// decimal sent by the exchange without a f64 representation
+    #[error("Failed to convert {0} to f64")]
+    Decimal(String),
+    /// timestamp that is not RFC 3339
+    #[error("Invalid timestamp '{timestamp}': {source}")]
+    Timestamp {
+        timestamp: String,
+        #[source]
+        source: chrono::ParseError,
+    },
+    /// symbol not written as BASE/QUOTE
+    #[error("Invalid symbol '{0}', expected BASE/QUOTE")]
+    Symbol(String),
+    /// book history missing the snapshots an update is applied on
+    #[error("Inconsistent book history: {0}")]
+    History(String),
+    /// request to the REST API that could not be made or answered
+    #[error("REST request failed: {0}")]
+    Client(#[from] ClientError),
+    /// errors, or lack of result, the exchange answered a request with
+    #[error("Exchange answered: {0}")]
+    Exchange(String),
+    /// websocket connection that could not be opened or written to
+    #[error("Websocket failed: {0}")]
+    Websocket(#[from] WSSError),
+    /// websocket connection silent for longer than its timeout, in seconds
+    #[error("No message from the feed within {0} seconds")]
+    Timeout(u64),
+    /// listener of the websocket connection that panicked or was cancelled
+    #[error("Feed listener stopped: {0}")]
+    Listener(#[from] JoinError),
+    /// task of the interface that stopped and could not be restarted
+    #[error("{task} stopped: {reason}")]
+    TaskStopped { task: &'static str, reason: String },
+    /// feed whose listener stopped before the startup subscriptions were made
+    #[error("Feed stopped listening before startup subscriptions")]
+    NotLive,
+    /// environment variables missing for the authenticated channels
+    #[error("Own orders and order entry need the {0} and {1} variables")]
+    Credentials(&'static str, &'static str),
+    /// action queue whose receiving end was dropped, nothing being dispatched anymore
+    #[error("Action queue closed")]
+    QueueClosed,
+    /// recordings, history slices or database holding unexpected content
+    #[error("Storage failed: {0}")]
+    Storage(String),
+    /// file or directory that could not be read, written or created
+    #[error("I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// file that could not be read or written, named in the message
+    #[error("Could not access {}: {source}", .path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// SQLite archive that could not be opened, written or queried
+    #[error("SQLite failed: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    /// parquet slice that could not be written or read
+    #[error("Parquet failed: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    /// Arrow archive that could not be written
+    #[error("Arrow failed: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    /// PNG image that could not be encoded
+    #[error("PNG encoding failed: {0}")]
+    PngEncoding(#[from] png::EncodingError),
+    /// PNG image that could not be decoded
+    #[error("PNG decoding failed: {0}")]
+    PngDecoding(#[from] png::DecodingError),
+    /// HTTP request that could not be built
+    #[error("Invalid HTTP request: {0}")]
+    Http(#[from] hyper::http::Error),
+    /// HTTP request that could not be sent or answered
+    #[error("HTTP request failed: {0}")]
+    HttpClient(#[from] hyper_util::client::legacy::Error),
+    /// HTTP request answered with an error status
+    #[error("{service} answered {status}")]
+    Status {
+        service: &'static str,
+        status: hyper::StatusCode,
+    },
+    /// PostgreSQL database that could not be connected to or written to
+    #[error("PostgreSQL failed: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+    /// configuration file that is not valid TOML
+    #[error("Invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+    /// configuration file that is not valid TOML, when edited in place
+    #[error("Invalid TOML: {0}")]
+    TomlEdit(#[from] toml_edit::TomlError),
+    /// log files that could not be opened or logger that could not be installed
+    #[error("Logging failed: {0}")]
+    Logging(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// name, rule or sink written by the user that could not be parsed
+    #[error("{0}")]
+    Parse(String),
+    /// processed stream that could not be serialized or written
+    #[error("Could not emit: {0}")]
+    Emit(String),
+    /// events that could not be serialized or handed over to the message brokers
+    #[error("Could not publish: {0}")]
+    Publish(String),
+    /// time series database that could not be connected to or written to
+    #[error("Time series write failed: {0}")]
+    TimeSeries(String),
+    /// local server that could not be bound or serve a client
+    #[error("Server failed: {0}")]
+    Server(String),
+    /// daemon whose socket could not be bound, attached to or relayed through
+    #[error("Daemon relay failed: {0}")]
+    Relay(String),
+    /// order that could not be turned into a request to the exchange
+    #[error("Invalid order: {0}")]
+    Order(String),
+    /// script that could not be read, compiled or run
+    #[error("Script failed: {0}")]
+    Script(String),
+    /// settings rejected by the interface or conflicting with each other
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    /// configuration file whose settings could not be parsed, named in the message
+    #[error("Invalid {}: {source}", .path.display())]
+    ConfigFile {
+        path: PathBuf,
+        #[source]
+        source: Box<BookedError>,
+    },
+}
+
+impl<T> From<SendError<T>> for BookedError {
+    fn from(_: SendError<T>) -> BookedError {
+        BookedError::QueueClosed
+    }
+}
@@ -0,0 +1,319 @@
+use crate::error::BookedError;
+use crate::pipeline::{SplattedBlocks, SplattedDepth, SplattedVolumes, widen};
+
+use itertools::Itertools;
+
+use std::fs::{create_dir_all, write};
+use std::path::{Path, PathBuf};
+
+/// File format of exported arrays
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// numpy binary arrays of little endian 64 bit floats
+    Npy,
+    /// comma separated values, one row per line
+    Csv,
+}
+
+impl ExportFormat {
+    /// every format, in the order they are listed to the user
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Npy, ExportFormat::Csv];
+
+    /// name of the format, also the extension of its files
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Npy => "npy",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// format from its name
+    pub fn parse(name: &str) -> Result<ExportFormat, BookedError> {
+        match ExportFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+        {
+            Some(format) => Ok(format),
+            None => Err(BookedError::Parse(format!(
+                "Unknown format '{}', expected one of {}",
+                name,
+                ExportFormat::ALL.map(|format| format.name()).join(", ")
+            ))),
+        }
+    }
+}
+
+/// encode a row major array of given shape in the numpy `.npy` version 1.0 format
+fn npy_bytes(shape: &[usize], values: &[f64]) -> Vec<u8> {
+    let dimensions = match shape {
+        [length] => format!("({},)", length),
+        _ => format!(
+            "({})",
+            shape.iter().map(|length| length.to_string()).join(",")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}",
+        dimensions
+    );
+    // magic, version and header length take 10 bytes, the header ends with a newline and pads
+    // the data to a multiple of 64 bytes
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    values
+        .iter()
+        .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+    bytes
+}
+
+/// encode a row major array of given shape as comma separated values under a header line
+fn csv_bytes(shape: &[usize], values: &[f64], header: &str) -> Vec<u8> {
+    let columns = shape.get(1).copied().unwrap_or(1).max(1);
+    let mut text = format!("{}\n", header);
+    for row in values.chunks(columns) {
+        text.push_str(&row.iter().map(|value| value.to_string()).join(","));
+        text.push('\n');
+    }
+    text.into_bytes()
+}
+
+/// write a row major array of given shape to `<directory>/<name>.<format>`
+fn write_array(
+    directory: &Path,
+    name: &str,
+    format: ExportFormat,
+    shape: &[usize],
+    values: &[f64],
+    header: &str,
+) -> Result<(), BookedError> {
+    let bytes = match format {
+        ExportFormat::Npy => npy_bytes(shape, values),
+        ExportFormat::Csv => csv_bytes(shape, values, header),
+    };
+    write(directory.join(format!("{}.{}", name, format.name())), bytes)?;
+    Ok(())
+}
+
+/// private utility method formatting an optional number as JSON
+fn json_number(value: Option<f64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+/// dump the splatted order map, depth and volumes of a symbol along with the metadata of their
+/// axes into a new `<SYMBOL>-<unix seconds>` folder of a directory, returning the folder
+pub fn export_snapshot(
+    directory: &Path,
+    symbol: &str,
+    time: i64,
+    format: ExportFormat,
+    blocks: &SplattedBlocks,
+    depth: &SplattedDepth,
+    volumes: &SplattedVolumes,
+) -> Result<PathBuf, BookedError> {
+    let folder = directory.join(format!("{}-{}", symbol.replace('/', "-"), time));
+    create_dir_all(&folder)?;
+
+    let blocks_values = blocks
+        .volumes
+        .iter()
+        .map(|volume| widen(*volume))
+        .collect::<Vec<_>>();
+    let blocks_header = (0..blocks.grid.number_price_values)
+        .map(|index| format!("price_{}", index))
+        .join(",");
+    write_array(
+        &folder,
+        "blocks",
+        format,
+        blocks.volumes.shape(),
+        &blocks_values,
+        &blocks_header,
+    )?;
+
+    write_array(
+        &folder,
+        "depth",
+        format,
+        &[depth.volumes.len()],
+        &depth.volumes,
+        "volume",
+    )?;
+
+    let volumes_values = volumes
+        .ask_volumes
+        .iter()
+        .zip(volumes.bid_volumes.iter())
+        .zip(volumes.imbalance_ratios.iter())
+        .flat_map(|((ask, bid), imbalance)| [*ask, *bid, *imbalance])
+        .collect::<Vec<_>>();
+    write_array(
+        &folder,
+        "volumes",
+        format,
+        &[volumes_values.len() / 3, 3],
+        &volumes_values,
+        "ask,bid,imbalance",
+    )?;
+
+    let grid = &blocks.grid;
+    let price_axis = grid.price_axis();
+    let metadata = format!(
+        concat!(
+            "{{\n",
+            "  \"symbol\": \"{}\",\n",
+            "  \"exported_at\": {},\n",
+            "  \"blocks_mode\": \"{:?}\",\n",
+            "  \"time_range\": [{}, {}],\n",
+            "  \"number_time_values\": {},\n",
+            "  \"price_range\": [{}, {}],\n",
+            "  \"price_axis\": [{}, {}],\n",
+            "  \"number_price_values\": {},\n",
+            "  \"price_scale\": \"{:?}\",\n",
+            "  \"peg\": {},\n",
+            "  \"depth_mode\": \"{:?}\",\n",
+            "  \"depth_price_axis\": [{}, {}],\n",
+            "  \"volumes_time_range\": [{}, {}]\n",
+            "}}\n"
+        ),
+        symbol,
+        time,
+        blocks.mode,
+        grid.time_range.0,
+        grid.time_range.1,
+        grid.number_time_values,
+        grid.price_range.0,
+        grid.price_range.1,
+        price_axis.0,
+        price_axis.1,
+        grid.number_price_values,
+        grid.price_scale,
+        json_number(grid.peg),
+        depth.mode,
+        depth.price_range.0,
+        depth.price_range.1,
+        volumes.time_range.0,
+        volumes.time_range.1,
+    );
+    write(folder.join("grid.json"), metadata)?;
+    Ok(folder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{DepthMode, PriceScale, RenderGrid};
+    use crate::splat::SplatMode;
+
+    use ndarray::Array2;
+    use std::fs::{read, read_to_string};
+
+    #[test]
+    fn test_npy_bytes() {
+        let bytes = npy_bytes(&[2, 3], &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_length) % 64, 0);
+        let header = String::from_utf8(bytes[10..10 + header_length].to_vec()).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2,3), }"));
+        assert!(header.ends_with('\n'));
+
+        let data = &bytes[10 + header_length..];
+        assert_eq!(data.len(), 6 * 8);
+        assert_eq!(f64::from_le_bytes(data[40..48].try_into().unwrap()), 5.0);
+
+        let bytes = npy_bytes(&[4], &[0.0; 4]);
+        let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let header = String::from_utf8(bytes[10..10 + header_length].to_vec()).unwrap();
+        assert!(header.contains("'shape': (4,)"));
+    }
+
+    #[test]
+    fn test_export_snapshot() {
+        let grid = RenderGrid {
+            number_time_values: 2,
+            time_range: (0, 10),
+            number_price_values: 3,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let blocks = SplattedBlocks {
+            mode: SplatMode::Histogram,
+            grid: grid.clone(),
+            volumes: Array2::from_shape_vec((2, 3), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]).unwrap(),
+        };
+        let depth = SplattedDepth {
+            mode: DepthMode::Density,
+            price_range: grid.price_range,
+            price_scale: grid.price_scale,
+            peg: None,
+            volumes: vec![1.0, 0.0, 2.0],
+        };
+        let volumes = SplattedVolumes {
+            time_range: grid.time_range,
+            ask_volumes: vec![1.0, 2.0],
+            bid_volumes: vec![3.0, 2.0],
+            imbalance_ratios: vec![0.5, 0.0],
+        };
+
+        let temporary = tempfile::tempdir().unwrap();
+        let directory = temporary.path().join("exports");
+        let folder = export_snapshot(
+            &directory,
+            "BTC/USD",
+            42,
+            ExportFormat::Csv,
+            &blocks,
+            &depth,
+            &volumes,
+        )
+        .unwrap();
+        assert_eq!(folder, directory.join("BTC-USD-42"));
+
+        assert_eq!(
+            read_to_string(folder.join("blocks.csv")).unwrap(),
+            "price_0,price_1,price_2\n0,1,2\n3,4,5\n"
+        );
+        assert_eq!(
+            read_to_string(folder.join("depth.csv")).unwrap(),
+            "volume\n1\n0\n2\n"
+        );
+        assert_eq!(
+            read_to_string(folder.join("volumes.csv")).unwrap(),
+            "ask,bid,imbalance\n1,3,0.5\n2,2,0\n"
+        );
+        let metadata = read_to_string(folder.join("grid.json")).unwrap();
+        assert!(metadata.contains("\"time_range\": [0, 10]"));
+        assert!(metadata.contains("\"peg\": null"));
+
+        export_snapshot(
+            &directory,
+            "BTC/USD",
+            42,
+            ExportFormat::Npy,
+            &blocks,
+            &depth,
+            &volumes,
+        )
+        .unwrap();
+        let bytes = read(folder.join("volumes.npy")).unwrap();
+        let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!(bytes.len(), 10 + header_length + 6 * 8);
+    }
+
+    #[test]
+    fn test_parse_export_format() {
+        assert_eq!(ExportFormat::parse("npy").unwrap(), ExportFormat::Npy);
+        assert_eq!(ExportFormat::parse("csv").unwrap(), ExportFormat::Csv);
+        assert!(ExportFormat::parse("parquet").is_err());
+    }
+}
@@ -1,9 +1,24 @@
 use crate::actions::Action;
-
-use kraken_async_rs::wss::{BidAsk, L2, Ticker};
+use crate::clock;
+use crate::error::BookedError;
+use crate::orders::{Account, OrderUpdate};
+use crate::relay::Attached;
+
+use kraken_async_rs::clients::core_kraken_client::CoreKrakenClient;
+use kraken_async_rs::clients::kraken_client::KrakenClient;
+use kraken_async_rs::crypto::nonce_provider::{IncreasingNonceProvider, NonceProvider};
+use kraken_async_rs::crypto::secrets::Token;
+use kraken_async_rs::request_types::{
+    OpenOrdersRequest, OrderbookRequest, StringCSV, TradableAssetPairsRequest,
+};
+use kraken_async_rs::response_types::{BuySell, Orderbook as RestOrderbook};
+use kraken_async_rs::secrets::secrets_provider::{
+    EnvSecretsProvider, SecretsProvider, StaticSecretsProvider,
+};
+use kraken_async_rs::wss::{BidAsk, L2, Ticker, Trade};
 use kraken_async_rs::wss::{
-    BookSubscription, KrakenMessageStream, KrakenWSSClient, TickerSubscription, WS_KRAKEN,
-    WS_KRAKEN_AUTH,
+    BookSubscription, ExecutionSubscription, KrakenMessageStream, KrakenWSSClient,
+    TickerSubscription, TradesSubscription, WS_KRAKEN, WS_KRAKEN_AUTH,
 };
 use kraken_async_rs::wss::{ChannelMessage, Message, WssMessage};
 
@@ -12,11 +27,15 @@ use tokio::sync::mpsc::Sender;
 use tokio::task::{JoinHandle, spawn};
 use tokio::time::{Duration, sleep, timeout};
 use tokio_stream::StreamExt;
+use tracing::{error, info, warn};
 
 use num_traits::cast::ToPrimitive;
+use serde::{Deserialize, Serialize};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 macro_rules! decimal_to_f64 {
@@ -24,14 +43,14 @@ macro_rules! decimal_to_f64 {
         match $value.to_f64() {
             Some(val) => val,
             None => {
-                return Err(format!("Failed to convert {:?} to f64", $value));
+                return Err(BookedError::Decimal(format!("{:?}", $value)));
             }
         }
     };
 }
 
 /// Data structure holding general information on a symbol
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TickerState {
     pub ask: f64,
     pub ask_quantity: f64,
@@ -49,7 +68,7 @@ pub struct TickerState {
 
 impl TickerState {
     /// convert from kraken_async_rs
-    pub fn from_ticker(ticker: Ticker) -> Result<TickerState, String> {
+    pub fn from_ticker(ticker: Ticker) -> Result<TickerState, BookedError> {
         Ok(TickerState {
             ask: decimal_to_f64!(ticker.ask),
             ask_quantity: decimal_to_f64!(ticker.ask_quantity),
@@ -68,7 +87,7 @@ impl TickerState {
 }
 
 /// Data structure holding information on an order or order level
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Order {
     pub price: f64,
     pub quantity: f64,
@@ -76,7 +95,7 @@ pub struct Order {
 
 impl Order {
     /// convert from kraken_async_rs
-    pub fn from_bid_ask(bid_ask: BidAsk) -> Result<Order, String> {
+    pub fn from_bid_ask(bid_ask: BidAsk) -> Result<Order, BookedError> {
         Ok(Order {
             price: decimal_to_f64!(bid_ask.price),
             quantity: decimal_to_f64!(bid_ask.quantity),
@@ -85,7 +104,7 @@ impl Order {
 }
 
 /// Data structure holding an order book update
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Booked {
     pub symbol: String,
     pub timestamp: String,
@@ -95,21 +114,21 @@ pub struct Booked {
 
 impl Booked {
     /// convert from kraken_async_rs
-    pub fn from_orderbook(book: L2) -> Result<Booked, String> {
+    pub fn from_orderbook(book: L2) -> Result<Booked, BookedError> {
         match book {
             L2::Orderbook(snapshot) => Ok(Booked {
                 symbol: snapshot.symbol,
-                timestamp: Utc::now().to_rfc3339(),
+                timestamp: clock::now().to_rfc3339(),
                 bids: snapshot
                     .bids
                     .into_iter()
                     .map(Order::from_bid_ask)
-                    .collect::<Result<Vec<_>, String>>()?,
+                    .collect::<Result<Vec<_>, BookedError>>()?,
                 asks: snapshot
                     .asks
                     .into_iter()
                     .map(Order::from_bid_ask)
-                    .collect::<Result<Vec<_>, String>>()?,
+                    .collect::<Result<Vec<_>, BookedError>>()?,
             }),
             L2::Update(update) => Ok(Booked {
                 symbol: update.symbol,
@@ -118,27 +137,288 @@ impl Booked {
                     .bids
                     .into_iter()
                     .map(Order::from_bid_ask)
-                    .collect::<Result<Vec<_>, String>>()?,
+                    .collect::<Result<Vec<_>, BookedError>>()?,
                 asks: update
                     .asks
                     .into_iter()
                     .map(Order::from_bid_ask)
-                    .collect::<Result<Vec<_>, String>>()?,
+                    .collect::<Result<Vec<_>, BookedError>>()?,
             }),
         }
     }
 }
 
-/// Encapsulating object for the websocket connection to Kraken API
+impl Booked {
+    /// convert from a kraken_async_rs REST snapshot
+    pub fn from_snapshot(symbol: String, book: RestOrderbook) -> Result<Booked, BookedError> {
+        let orders = |levels: Vec<kraken_async_rs::response_types::BidAsk>| {
+            levels
+                .into_iter()
+                .map(|level| {
+                    Ok(Order {
+                        price: decimal_to_f64!(level.price),
+                        quantity: decimal_to_f64!(level.volume),
+                    })
+                })
+                .collect::<Result<Vec<_>, BookedError>>()
+        };
+
+        Ok(Booked {
+            symbol,
+            timestamp: clock::now().to_rfc3339(),
+            bids: orders(book.bids)?,
+            asks: orders(book.asks)?,
+        })
+    }
+}
+
+/// Enum of the sides of the taker of a trade
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Aggressor {
+    /// taker bought, consuming asks
+    Buy,
+    /// taker sold, consuming bids
+    Sell,
+}
+
+/// Data structure holding an executed trade
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Traded {
+    pub symbol: String,
+    pub timestamp: String,
+    pub aggressor: Aggressor,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+impl Traded {
+    /// convert from kraken_async_rs
+    pub fn from_trade(trade: Trade) -> Result<Traded, BookedError> {
+        Ok(Traded {
+            symbol: trade.symbol,
+            timestamp: trade.timestamp,
+            aggressor: match trade.side {
+                BuySell::Buy => Aggressor::Buy,
+                BuySell::Sell => Aggressor::Sell,
+            },
+            price: decimal_to_f64!(trade.price),
+            quantity: decimal_to_f64!(trade.quantity),
+        })
+    }
+}
+
+/// Validate a ticker symbol written by the user, e.g. BTC/USD
+pub fn parse_symbol(text: &str) -> Result<String, BookedError> {
+    let symbol = text.trim().to_uppercase();
+    match symbol.split_once('/') {
+        Some((base, quote))
+            if !base.is_empty()
+                && !quote.is_empty()
+                && symbol
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '/')
+                && !quote.contains('/') =>
+        {
+            Ok(symbol)
+        }
+        _ => Err(BookedError::Symbol(text.trim().to_string())),
+    }
+}
+
+/// Fetch a one-shot REST snapshot of the book of a symbol without subscribing to it
+pub async fn fetch_snapshot(symbol: String, depth: i32) -> Result<Booked, BookedError> {
+    let mut client = public_client();
+
+    // the REST API names pairs without separator, e.g. BTCUSD for BTC/USD
+    let request = OrderbookRequest::builder(symbol.replace('/', ""))
+        .count(depth as i64)
+        .build();
+    let response = client.get_orderbook(&request).await?;
+    if !response.error.is_empty() {
+        return Err(BookedError::Exchange(response.error.join(", ")));
+    }
+
+    // the response is keyed by the exchange's own name of the pair, e.g. XXBTZUSD
+    match response.result.and_then(|books| books.into_values().next()) {
+        Some(book) => Booked::from_snapshot(symbol, book),
+        None => Err(BookedError::Exchange(format!(
+            "no book returned for {}",
+            symbol
+        ))),
+    }
+}
+
+/// private utility method building a client of the public REST endpoints
+fn public_client() -> CoreKrakenClient {
+    // credentials are not needed for public endpoints
+    let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+        Box::new(Arc::new(Mutex::new(StaticSecretsProvider::new("", ""))));
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    CoreKrakenClient::new(secrets_provider, nonce_provider)
+}
+
+/// Fetch the number of decimals the prices of a symbol are quoted with
+pub async fn fetch_price_decimals(symbol: String) -> Result<u32, BookedError> {
+    let mut client = public_client();
+    let request = TradableAssetPairsRequest::builder()
+        .pair(StringCSV::new(vec![symbol.replace('/', "")]))
+        .build();
+    let response = client.get_tradable_asset_pairs(&request).await?;
+    if !response.error.is_empty() {
+        return Err(BookedError::Exchange(response.error.join(", ")));
+    }
+
+    match response.result.and_then(|pairs| pairs.into_values().next()) {
+        Some(pair) => Ok(pair.pair_decimals.max(0) as u32),
+        None => Err(BookedError::Exchange(format!(
+            "no instrument returned for {}",
+            symbol
+        ))),
+    }
+}
+
+/// Environment variables holding the API key and secret the authenticated channels are read with
+pub const API_KEY_VARIABLES: (&str, &str) = ("KRAKEN_API_KEY", "KRAKEN_API_SECRET");
+
+/// private utility method building a client authenticated with the API key and secret of the
+/// environment
+fn private_client() -> Result<CoreKrakenClient, BookedError> {
+    let (key, secret) = API_KEY_VARIABLES;
+    if std::env::var(key).is_err() || std::env::var(secret).is_err() {
+        return Err(BookedError::Credentials(key, secret));
+    }
+    let secrets_provider: Box<Arc<Mutex<dyn SecretsProvider>>> =
+        Box::new(Arc::new(Mutex::new(EnvSecretsProvider::new(key, secret))));
+    let nonce_provider: Box<Arc<Mutex<dyn NonceProvider>>> =
+        Box::new(Arc::new(Mutex::new(IncreasingNonceProvider::new())));
+    Ok(CoreKrakenClient::new(secrets_provider, nonce_provider))
+}
+
+/// Fetch a token for the authenticated websocket with the API key and secret of the environment
+pub async fn fetch_websockets_token() -> Result<Token, BookedError> {
+    let mut client = private_client()?;
+    let response = client.get_websockets_token().await?;
+    match response.result {
+        Some(result) => Ok(result.token),
+        None => Err(BookedError::Exchange(response.error.join(", "))),
+    }
+}
+
+/// Fetch the balances and open orders of the user with the API key and secret of the environment
+pub async fn fetch_account() -> Result<Account, BookedError> {
+    let mut client = private_client()?;
+    let balances = match client.get_extended_balances().await? {
+        response if !response.error.is_empty() => {
+            return Err(BookedError::Exchange(response.error.join(", ")));
+        }
+        response => response.result.unwrap_or_default(),
+    };
+    let request = OpenOrdersRequest::builder().build();
+    let open_orders = match client.get_open_orders(&request).await? {
+        response if !response.error.is_empty() => {
+            return Err(BookedError::Exchange(response.error.join(", ")));
+        }
+        response => response
+            .result
+            .map(|orders| orders.open)
+            .unwrap_or_default(),
+    };
+    Account::from_responses(balances, open_orders)
+}
+
+/// Connect to the authenticated executions channel and forward the changes to the orders of the
+/// user to the action queue from a separate thread
+pub async fn connect_own_orders(sender: Sender<Action>) -> Result<(), BookedError> {
+    let token = fetch_websockets_token().await?;
+    let mut client = KrakenWSSClient::new_with_urls(WS_KRAKEN, WS_KRAKEN_AUTH);
+    let mut connection = client.connect_auth::<WssMessage>().await?;
+    let mut subscription = ExecutionSubscription::new(token);
+    subscription.snapshot_orders = Some(true);
+    subscription.snapshot_trades = Some(false);
+    connection
+        .send(&Message::new_subscription(subscription, 0))
+        .await?;
+    info!(url = WS_KRAKEN_AUTH, "executions connected");
+
+    spawn(async move {
+        while let Some(communication) = connection.next().await {
+            let action = match communication {
+                Ok(WssMessage::Channel(ChannelMessage::Execution(executions))) => {
+                    Action::UpdateOwnOrders(
+                        executions
+                            .data
+                            .into_iter()
+                            .map(OrderUpdate::from_execution)
+                            .collect(),
+                    )
+                }
+                Ok(WssMessage::Error(err)) => Action::Warn(format!("{:?}", err)),
+                Err(err) => Action::Warn(format!("{:?}", err)),
+                Ok(_) => continue,
+            };
+            if sender.send(action).await.is_err() {
+                return;
+            }
+        }
+        let _ = sender
+            .send(Action::Warn("Executions connection closed".to_string()))
+            .await;
+    });
+    Ok(())
+}
+
+/// Enum of the connections a feed reads from
+#[derive(Clone)]
+enum Connection {
+    /// websocket connection to Kraken WS API
+    Exchange(Arc<Mutex<KrakenMessageStream<WssMessage>>>),
+    /// Unix socket connection to a collector daemon, and the path of its socket
+    Daemon(Arc<Attached>, PathBuf),
+}
+
+/// Encapsulating object for the websocket connection to Kraken API, or to a collector daemon
+/// relaying it
 pub struct Feed {
-    // websocket connection to Kraken WS API
-    connection: Arc<Mutex<KrakenMessageStream<WssMessage>>>,
+    // connection the messages are read from
+    connection: Connection,
     // the depth to request the book data
     depth: i32,
     // handle to websocket listener
-    listener_handle: JoinHandle<Result<(), String>>,
+    listener_handle: JoinHandle<Result<(), BookedError>>,
     // request id counter
     request_id: i64,
+    // seconds without message after which the listener gives up on the connection
+    timeout_in_seconds: u64,
+    // sender end of the action queue messages are forwarded to
+    sender: Sender<Action>,
+}
+
+/// private utility method opening a websocket connection and spawning its listener
+async fn connect(
+    timeout_in_seconds: u64,
+    sender: Sender<Action>,
+) -> Result<
+    (
+        Arc<Mutex<KrakenMessageStream<WssMessage>>>,
+        JoinHandle<Result<(), BookedError>>,
+    ),
+    BookedError,
+> {
+    let mut client = KrakenWSSClient::new_with_urls(WS_KRAKEN, WS_KRAKEN_AUTH);
+    let connection = match client.connect::<WssMessage>().await {
+        Ok(connection) => Arc::new(Mutex::new(connection)),
+        Err(message) => {
+            error!(error = ?message, url = WS_KRAKEN, "feed connection failed");
+            return Err(message.into());
+        }
+    };
+
+    let cloned_connection = connection.clone();
+    let listener_handle = spawn(async move {
+        listen_to_connection(sender, cloned_connection, timeout_in_seconds).await
+    });
+    Ok((connection, listener_handle))
 }
 
 /// method to be spawned in separate thread that listens to websocket connection and forwards to
@@ -147,7 +427,7 @@ async fn listen_to_connection(
     sender: Sender<Action>,
     connection: Arc<Mutex<KrakenMessageStream<WssMessage>>>,
     timeout_in_seconds: u64,
-) -> Result<(), String> {
+) -> Result<(), BookedError> {
     loop {
         loop {
             let mut stream = connection.lock().await;
@@ -159,11 +439,27 @@ async fn listen_to_connection(
                             ChannelMessage::Heartbeat => break,
 
                             ChannelMessage::Orderbook(booked) => {
-                                action =
-                                    Action::UpdateBook(match Booked::from_orderbook(booked.data) {
+                                let casted = match Booked::from_orderbook(booked.data) {
+                                    Ok(casted) => casted,
+                                    Err(message) => return Err(message),
+                                };
+                                if let Ok(time) = DateTime::parse_from_rfc3339(&casted.timestamp) {
+                                    clock::observe(time.with_timezone(&Utc));
+                                }
+                                action = Action::UpdateBook(casted)
+                            }
+                            ChannelMessage::Trade(trades) => {
+                                action = Action::UpdateTrades(
+                                    match trades
+                                        .data
+                                        .into_iter()
+                                        .map(Traded::from_trade)
+                                        .collect::<Result<Vec<_>, BookedError>>()
+                                    {
                                         Ok(casted) => casted,
                                         Err(message) => return Err(message),
-                                    })
+                                    },
+                                )
                             }
                             ChannelMessage::Ticker(tick) => {
                                 action = Action::UpdateTicker(
@@ -179,19 +475,24 @@ async fn listen_to_connection(
                             action = Action::Inform(format!("{:?}", information));
                         }
                         Ok(WssMessage::Error(err)) => {
+                            warn!(error = ?err, "feed error message");
                             action = Action::Warn(format!("{:?}", err));
                         }
                         Err(err) => {
+                            warn!(error = ?err, "feed message could not be read");
                             action = Action::Warn(format!("{:?}", err));
                         }
                     }
-                    match sender.send(action).await {
-                        Ok(_) => (),
-                        Err(send_err) => return Err(format!("{:?}", send_err)),
-                    };
+                    sender.send(action).await?;
+                }
+                Ok(None) => {
+                    info!("feed connection closed");
+                    return Ok(());
+                }
+                Err(_) => {
+                    error!(timeout_in_seconds, "feed connection timed out");
+                    return Err(BookedError::Timeout(timeout_in_seconds));
                 }
-                Ok(None) => return Ok(()),
-                Err(message) => return Err(format!("{:?}", message)),
             }
         }
 
@@ -206,28 +507,77 @@ impl Feed {
         timeout_in_seconds: u64,
         depth: i32,
         sender: Sender<Action>,
-    ) -> Result<Feed, String> {
-        let mut client = KrakenWSSClient::new_with_urls(WS_KRAKEN, WS_KRAKEN_AUTH);
-        let connection = match client.connect::<WssMessage>().await {
-            Ok(connection) => Arc::new(Mutex::new(connection)),
-            Err(message) => return Err(format!("{:?}", message)),
-        };
+    ) -> Result<Feed, BookedError> {
+        let (connection, listener_handle) = connect(timeout_in_seconds, sender.clone()).await?;
+        info!(url = WS_KRAKEN, depth, "feed connected");
+
+        Ok(Feed {
+            connection: Connection::Exchange(connection),
+            depth,
+            listener_handle,
+            request_id: 0,
+            timeout_in_seconds,
+            sender,
+        })
+    }
 
-        let cloned_connection = connection.clone();
-        let listener_handle = spawn(async move {
-            listen_to_connection(sender, cloned_connection, timeout_in_seconds).await
-        });
+    /// constructor, attaching to the socket of a collector daemon in place of the exchange
+    pub async fn attach(
+        path: &Path,
+        timeout_in_seconds: u64,
+        depth: i32,
+        sender: Sender<Action>,
+    ) -> Result<Feed, BookedError> {
+        let (attached, listener_handle) =
+            Attached::connect(path, HashSet::new(), sender.clone()).await?;
+        info!(socket = %path.display(), "feed attached");
 
         Ok(Feed {
-            connection,
+            connection: Connection::Daemon(Arc::new(attached), path.to_path_buf()),
             depth,
             listener_handle,
             request_id: 0,
+            timeout_in_seconds,
+            sender,
         })
     }
 
+    /// replace a connection whose listener stopped by a fresh one, without any subscription
+    /// to the exchange, the symbols followed on a daemon being requested again
+    pub async fn reconnect(&mut self) -> Result<(), BookedError> {
+        match &self.connection {
+            Connection::Exchange(_) => {
+                let (connection, listener_handle) =
+                    connect(self.timeout_in_seconds, self.sender.clone()).await?;
+                info!(url = WS_KRAKEN, depth = self.depth, "feed reconnected");
+                self.connection = Connection::Exchange(connection);
+                self.listener_handle = listener_handle;
+            }
+            Connection::Daemon(attached, path) => {
+                let (attached, listener_handle) =
+                    Attached::connect(path, attached.subscribed().await, self.sender.clone())
+                        .await?;
+                info!(socket = %path.display(), "feed attached again");
+                self.connection = Connection::Daemon(Arc::new(attached), path.clone());
+                self.listener_handle = listener_handle;
+            }
+        }
+        Ok(())
+    }
+
+    /// whether the feed reads from a daemon, which replays its histories, rather than the
+    /// exchange
+    pub fn is_attached(&self) -> bool {
+        matches!(self.connection, Connection::Daemon(_, _))
+    }
+
     /// subscribe a new ticker symbol
-    pub async fn subscribe(&mut self, ticker: String) -> Result<(), String> {
+    pub async fn subscribe(&mut self, ticker: String) -> Result<(), BookedError> {
+        info!(ticker, depth = self.depth, "subscribing");
+        let connection = match &self.connection {
+            Connection::Exchange(connection) => connection,
+            Connection::Daemon(attached, _) => return attached.subscribe(ticker).await,
+        };
         let mut book_subscription = BookSubscription::new(vec![ticker.clone()]);
         book_subscription.snapshot = Some(true);
         book_subscription.depth = Some(self.depth);
@@ -241,21 +591,39 @@ impl Feed {
             Message::new_subscription(ticker_subscription, self.request_id);
         self.request_id += 1;
 
-        let mut writable = self.connection.lock().await;
+        let trades_subscription = TradesSubscription::new(vec![ticker.clone()]);
+        let trades_subscription_message =
+            Message::new_subscription(trades_subscription, self.request_id);
+        self.request_id += 1;
+
+        let mut writable = connection.lock().await;
 
         match writable.send(&ticker_subscription_message).await {
             Ok(_) => (),
-            Err(message) => return Err(format!("{:?}", message)),
+            Err(message) => return Err(message.into()),
+        };
+
+        match writable.send(&trades_subscription_message).await {
+            Ok(_) => (),
+            Err(message) => return Err(message.into()),
         };
 
         match writable.send(&book_subscription_message).await {
             Ok(_) => Ok(()),
-            Err(message) => Err(format!("{:?}", message)),
+            Err(message) => Err(message.into()),
         }
     }
 
     /// unsubscribe a previously subscribed ticker
-    pub async fn unsubscribe(&mut self, ticker: String) -> Result<(), String> {
+    pub async fn unsubscribe(&mut self, ticker: String) -> Result<(), BookedError> {
+        info!(ticker, "unsubscribing");
+        let connection = match &self.connection {
+            Connection::Exchange(connection) => connection,
+            Connection::Daemon(attached, _) => {
+                attached.unsubscribe(&ticker).await;
+                return Ok(());
+            }
+        };
         let mut book_subscription = BookSubscription::new(vec![ticker.clone()]);
         book_subscription.depth = Some(self.depth);
 
@@ -270,28 +638,54 @@ impl Feed {
         self.request_id += 1;
         ticker_subscription_message.method = "unsubscribe".to_string();
 
-        let mut writable = self.connection.lock().await;
+        let trades_subscription = TradesSubscription::new(vec![ticker.clone()]);
+        let mut trades_subscription_message =
+            Message::new_subscription(trades_subscription, self.request_id);
+        self.request_id += 1;
+        trades_subscription_message.method = "unsubscribe".to_string();
+
+        let mut writable = connection.lock().await;
 
         match writable.send(&ticker_subscription_message).await {
             Ok(_) => (),
-            Err(message) => return Err(format!("{:?}", message)),
+            Err(message) => return Err(message.into()),
+        };
+
+        match writable.send(&trades_subscription_message).await {
+            Ok(_) => (),
+            Err(message) => return Err(message.into()),
         };
 
         match writable.send(&book_subscription_message).await {
             Ok(_) => Ok(()),
-            Err(message) => Err(format!("{:?}", message)),
+            Err(message) => Err(message.into()),
         }
     }
 
+    /// depth of the books requested from the exchange
+    pub fn depth(&self) -> i32 {
+        self.depth
+    }
+
+    /// request books of a depth on the next subscriptions
+    pub fn set_depth(&mut self, depth: i32) {
+        self.depth = depth;
+    }
+
+    /// whether the thread listening at websocket is still running
+    pub fn is_live(&self) -> bool {
+        !self.listener_handle.is_finished()
+    }
+
     /// check that the thread litening at websocket is ok
-    pub async fn check_listener(self) -> Result<Option<Feed>, String> {
+    pub async fn check_listener(self) -> Result<Option<Feed>, BookedError> {
         if self.listener_handle.is_finished() {
             return match self.listener_handle.await {
                 Ok(val) => match val {
                     Ok(()) => Ok(None),
                     Err(message) => Err(message),
                 },
-                Err(message) => Err(format!("{:?}", message)),
+                Err(message) => Err(message.into()),
             };
         }
 
@@ -300,6 +694,8 @@ impl Feed {
             depth: self.depth,
             request_id: self.request_id,
             listener_handle: self.listener_handle,
+            timeout_in_seconds: self.timeout_in_seconds,
+            sender: self.sender,
         }))
     }
 }
@@ -308,7 +704,7 @@ impl Feed {
 mod tests {
     use super::*;
 
-    use kraken_async_rs::wss::{BidAsk, L2, Orderbook, OrderbookUpdate, Ticker};
+    use kraken_async_rs::wss::{BidAsk, L2, MarketLimit, Orderbook, OrderbookUpdate, Ticker};
 
     use tokio::sync::mpsc::channel;
     use tokio::time::{Duration, timeout};
@@ -463,6 +859,27 @@ mod tests {
         assert!(order.quantity == 100.0);
     }
 
+    #[tokio::test]
+    async fn test_traded_transfer() {
+        let trade = Trade {
+            symbol: "ETH/EUR".to_string(),
+            side: BuySell::Sell,
+            quantity: Decimal::new(15, 1),
+            price: Decimal::new(2000, 0),
+            order_type: MarketLimit::Market,
+            trade_id: 1,
+            timestamp: "2024-01-01T00:00:00.000000Z".to_string(),
+        };
+
+        let traded = Traded::from_trade(trade).unwrap();
+
+        assert_eq!(traded.symbol, "ETH/EUR");
+        assert_eq!(traded.aggressor, Aggressor::Sell);
+        assert_eq!(traded.price, 2000.0);
+        assert_eq!(traded.quantity, 1.5);
+        assert_eq!(traded.timestamp, "2024-01-01T00:00:00.000000Z");
+    }
+
     #[tokio::test]
     async fn test_booked_zeros_transfer() {
         let l2 = zero_orderbook_case();
@@ -533,6 +950,43 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_booked_snapshot_transfer() {
+        let level = |price: i64, volume: i64| kraken_async_rs::response_types::BidAsk {
+            price: Decimal::new(price, 1),
+            volume: Decimal::new(volume, 0),
+            time: 0,
+        };
+        let book = RestOrderbook {
+            asks: vec![level(11, 2), level(12, 3)],
+            bids: vec![level(9, 4)],
+        };
+
+        let booked = Booked::from_snapshot("Ticker/Symbol".to_string(), book).unwrap();
+
+        assert_eq!(booked.symbol, "Ticker/Symbol");
+        assert_eq!(
+            booked.asks,
+            vec![
+                Order {
+                    price: 1.1,
+                    quantity: 2.0
+                },
+                Order {
+                    price: 1.2,
+                    quantity: 3.0
+                }
+            ]
+        );
+        assert_eq!(
+            booked.bids,
+            vec![Order {
+                price: 0.9,
+                quantity: 4.0
+            }]
+        );
+    }
+
     #[tokio::test]
     async fn construct_feed() {
         let (sender, mut receiver) = channel::<Action>(10);
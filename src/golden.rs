@@ -0,0 +1,353 @@
+use crate::app::ColorScale;
+use crate::error::BookedError;
+use crate::pipeline::{BookHistory, SplattedBlocks, widen};
+use crate::recording::load_recording;
+use crate::theme::{ColorDepth, Gradient, HeatColors, Theme};
+
+use ratatui::style::Color;
+
+use std::fs::{File, create_dir_all};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Perceptual difference (CIE76 delta E) under which two pixels are considered identical
+const JUST_NOTICEABLE_DIFFERENCE: f64 = 2.3;
+
+/// Fraction of perceptually different pixels tolerated before an image comparison fails
+const DIFFERENT_PIXELS_THRESHOLD: f64 = 0.005;
+
+/// Height in pixels of rendered 1D series
+const SERIES_HEIGHT: usize = 64;
+
+/// Environment variable writing the golden images instead of comparing against them
+const UPDATE_GOLDEN: &str = "BOOKEDBLOCKS_UPDATE_GOLDEN";
+
+/// Simple RGB image buffer
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// constructor of a black image
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![0; 3 * width * height],
+        }
+    }
+
+    /// set the color of a pixel, row 0 being the top of the image
+    pub fn set(&mut self, column: usize, row: usize, color: (u8, u8, u8)) {
+        let index = 3 * (row * self.width + column);
+        self.pixels[index] = color.0;
+        self.pixels[index + 1] = color.1;
+        self.pixels[index + 2] = color.2;
+    }
+
+    /// write image to PNG file
+    pub fn write_png(&self, path: &Path) -> Result<(), BookedError> {
+        let file = File::create(path)?;
+
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    /// read image from PNG file
+    pub fn read_png(path: &Path) -> Result<Image, BookedError> {
+        let file = File::open(path)?;
+
+        let mut reader = png::Decoder::new(BufReader::new(file)).read_info()?;
+
+        let mut buffer = vec![0; reader.output_buffer_size().unwrap_or(0)];
+        let info = reader.next_frame(&mut buffer)?;
+
+        if info.color_type != png::ColorType::Rgb || info.bit_depth != png::BitDepth::Eight {
+            return Err(BookedError::Storage(format!(
+                "Unsupported golden image format {:?} {:?} in {:?}",
+                info.color_type, info.bit_depth, path
+            )));
+        }
+
+        buffer.truncate(info.buffer_size());
+        Ok(Image {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels: buffer,
+        })
+    }
+}
+
+/// replay a fixture recording into a book history
+pub async fn replay_recording(path: &Path, time_window_in_seconds: usize) -> BookHistory {
+    let mut history = BookHistory::new(time_window_in_seconds);
+    for booked in load_recording(path, "Fixture/Symbol").unwrap() {
+        history.update(booked).await.unwrap();
+    }
+    history
+}
+
+/// private utility method converting terminal colors to RGB
+fn rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(red, green, blue) => (red, green, blue),
+        Color::Red => (255, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::White => (255, 255, 255),
+        _ => (0, 0, 0),
+    }
+}
+
+/// render heat map to image, one pixel per grid cell with time on x and price going up on y
+pub fn render_blocks(blocks: &SplattedBlocks) -> Image {
+    let (number_times, number_prices) = (blocks.volumes.shape()[0], blocks.volumes.shape()[1]);
+    let max_volume = blocks
+        .volumes
+        .iter()
+        .fold(0.0, |acc: f64, volume| acc.max(widen(*volume).abs()));
+
+    let colors = HeatColors {
+        theme: Theme::Default,
+        scale: ColorScale::Linear,
+        gradient: Gradient::Shades,
+        depth: ColorDepth::TrueColor,
+    };
+    let mut image = Image::new(number_times, number_prices);
+    for ((t_grid, p_grid), volume) in blocks.volumes.indexed_iter() {
+        let volume = widen(*volume);
+        if volume.abs() >= 0.001 * max_volume {
+            image.set(
+                t_grid,
+                number_prices - 1 - p_grid,
+                rgb(colors.color(volume, max_volume)),
+            );
+        }
+    }
+
+    image
+}
+
+/// render signed 1D series to image as bars, positive values in green going up and negative
+/// values in red going down
+pub fn render_series(values: &[f64]) -> Image {
+    let max_value = values
+        .iter()
+        .fold(0.0, |acc: f64, value| acc.max(value.abs()));
+    let half_height = SERIES_HEIGHT / 2;
+
+    let mut image = Image::new(values.len(), SERIES_HEIGHT);
+    if max_value == 0.0 {
+        return image;
+    }
+
+    for (column, value) in values.iter().enumerate() {
+        let extent = ((value.abs() / max_value) * (half_height as f64)).round() as usize;
+        for offset in 0..extent.min(half_height) {
+            if *value > 0.0 {
+                image.set(column, half_height - 1 - offset, rgb(Color::Green));
+            } else {
+                image.set(column, half_height + offset, rgb(Color::Red));
+            }
+        }
+    }
+
+    image
+}
+
+/// private utility method converting an sRGB pixel to CIE L*a*b*
+fn to_lab(pixel: &[u8]) -> (f64, f64, f64) {
+    let linear = |channel: u8| {
+        let value = channel as f64 / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (red, green, blue) = (linear(pixel[0]), linear(pixel[1]), linear(pixel[2]));
+
+    let x = (0.4124 * red + 0.3576 * green + 0.1805 * blue) / 0.95047;
+    let y = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+    let z = (0.0193 * red + 0.1192 * green + 0.9505 * blue) / 1.08883;
+
+    let f = |value: f64| {
+        if value > 0.008856 {
+            value.cbrt()
+        } else {
+            7.787 * value + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// fraction of pixels perceptually different between two images, 1.0 if shapes differ
+pub fn perceptual_difference(lhs: &Image, rhs: &Image) -> f64 {
+    if lhs.width != rhs.width || lhs.height != rhs.height {
+        return 1.0;
+    }
+
+    let number_pixels = lhs.width * lhs.height;
+    if number_pixels == 0 {
+        return 0.0;
+    }
+
+    let different = lhs
+        .pixels
+        .chunks(3)
+        .zip(rhs.pixels.chunks(3))
+        .filter(|(lhs_pixel, rhs_pixel)| {
+            let (lhs_lab, rhs_lab) = (to_lab(lhs_pixel), to_lab(rhs_pixel));
+            ((lhs_lab.0 - rhs_lab.0).powi(2)
+                + (lhs_lab.1 - rhs_lab.1).powi(2)
+                + (lhs_lab.2 - rhs_lab.2).powi(2))
+            .sqrt()
+                > JUST_NOTICEABLE_DIFFERENCE
+        })
+        .count();
+
+    different as f64 / number_pixels as f64
+}
+
+/// compare image against golden image of given name, only written when `UPDATE_GOLDEN` is set so
+/// that a missing golden image fails instead of passing unchecked
+pub fn assert_matches_golden(name: &str, image: &Image) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let golden_path = root.join("fixtures/golden").join(format!("{}.png", name));
+
+    if std::env::var_os(UPDATE_GOLDEN).is_some() {
+        create_dir_all(golden_path.parent().unwrap()).unwrap();
+        image.write_png(&golden_path).unwrap();
+        return;
+    }
+    if !golden_path.exists() {
+        panic!(
+            "golden image {:?} is missing, run with {} set to write it",
+            golden_path, UPDATE_GOLDEN
+        );
+    }
+
+    let golden = Image::read_png(&golden_path).unwrap();
+    let difference = perceptual_difference(&golden, image);
+
+    if difference > DIFFERENT_PIXELS_THRESHOLD {
+        let output_directory: PathBuf = root.join("target/golden");
+        create_dir_all(&output_directory).unwrap();
+        let actual_path = output_directory.join(format!("{}.actual.png", name));
+        image.write_png(&actual_path).unwrap();
+
+        panic!(
+            "{:.2}% of pixels differ from golden image {:?}, actual image written to {:?}",
+            100.0 * difference,
+            golden_path,
+            actual_path
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pipeline::{Pipeline, Splatted};
+    use crate::splat::Kernel;
+
+    fn recording(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/recordings")
+            .join(name)
+    }
+
+    #[test]
+    fn test_perceptual_difference() {
+        let mut lhs = Image::new(10, 10);
+        let rhs = lhs.clone();
+        assert_eq!(perceptual_difference(&lhs, &rhs), 0.0);
+
+        lhs.set(0, 0, (1, 0, 0));
+        assert_eq!(perceptual_difference(&lhs, &rhs), 0.0);
+
+        lhs.set(1, 0, (0, 255, 0));
+        assert_eq!(perceptual_difference(&lhs, &rhs), 0.01);
+
+        assert_eq!(perceptual_difference(&lhs, &Image::new(5, 10)), 1.0);
+    }
+
+    #[test]
+    fn test_missing_golden() {
+        // golden images are only ever written on request
+        if std::env::var_os(UPDATE_GOLDEN).is_some() {
+            return;
+        }
+        let panicked =
+            std::panic::catch_unwind(|| assert_matches_golden("missing", &Image::new(1, 1)))
+                .unwrap_err();
+        let message = panicked.downcast_ref::<String>().unwrap();
+        assert!(message.contains("missing.png"));
+        assert!(message.contains(UPDATE_GOLDEN));
+        assert!(
+            !Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures/golden/missing.png")
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_golden_synthetic_kernel() {
+        let history = replay_recording(&recording("synthetic.txt"), 120).await;
+        let pipeline = Pipeline::new(90, 180, 120, Kernel::default());
+
+        let (splatted, _) = pipeline.run(&history).await;
+        let (Splatted::Depth(depth), Splatted::Volumes(volumes), Splatted::Blocks(blocks)) =
+            (&splatted[0], &splatted[1], &splatted[2])
+        else {
+            panic!("unexpected order of pipeline stages");
+        };
+        let Splatted::Imbalance(imbalance) = &splatted[3] else {
+            panic!("unexpected order of pipeline stages");
+        };
+
+        assert_matches_golden("synthetic_kernel_blocks", &render_blocks(blocks));
+        assert_matches_golden("synthetic_kernel_depth", &render_series(&depth.volumes));
+        assert_matches_golden(
+            "synthetic_kernel_volumes",
+            &render_series(
+                &volumes
+                    .ask_volumes
+                    .iter()
+                    .zip(volumes.bid_volumes.iter())
+                    .map(|(ask, bid)| ask - bid)
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        assert_matches_golden(
+            "synthetic_kernel_imbalance",
+            &render_series(&imbalance.imbalances),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_golden_synthetic_histogram() {
+        let history = replay_recording(&recording("synthetic.txt"), 120).await;
+        let mut pipeline = Pipeline::new(90, 180, 120, Kernel::default());
+        pipeline.toggle_splat_mode(None);
+
+        let (splatted, _) = pipeline.run(&history).await;
+        let (Splatted::Depth(depth), Splatted::Blocks(blocks)) = (&splatted[0], &splatted[2])
+        else {
+            panic!("unexpected order of pipeline stages");
+        };
+
+        assert_matches_golden("synthetic_histogram_blocks", &render_blocks(blocks));
+        assert_matches_golden("synthetic_histogram_depth", &render_series(&depth.volumes));
+    }
+}
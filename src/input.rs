@@ -0,0 +1,624 @@
+use crate::alerts::AlertRule;
+use crate::error::BookedError;
+use crate::export::ExportFormat;
+use crate::feed::parse_symbol;
+use crate::pipeline::View;
+use crate::screenshot::ScreenshotFormat;
+use crate::walls::Side;
+
+use ratatui::style::Stylize;
+use ratatui::widgets::{Block, Paragraph, Widget};
+
+use std::path::{Path, PathBuf};
+
+/// Names of the commands accepted by the command prompt
+pub const COMMANDS: [&str; 18] = [
+    "alert",
+    "buy",
+    "cancel",
+    "depth",
+    "export",
+    "library",
+    "logs",
+    "queue",
+    "quit",
+    "screenshot",
+    "sell",
+    "slippage",
+    "splat",
+    "stats",
+    "subscribe",
+    "ticker",
+    "unsubscribe",
+    "window",
+];
+
+/// Number of commands kept in the history between sessions
+pub const HISTORY_LENGTH: usize = 500;
+
+/// Number of levels per side the exchange accepts to stream books with
+pub const BOOK_DEPTHS: [i32; 5] = [10, 25, 100, 500, 1000];
+
+/// Enum of the commands one can issue from the command prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// watch a new alert rule, None to stop watching every rule
+    Alert(Option<AlertRule>),
+    /// resubscribe every ticker with books of a number of levels per side
+    BookDepth(i32),
+    /// cancel a resting order of the user
+    Cancel(String),
+    /// switch market depth between density and cumulative representations
+    Depth,
+    /// dump the arrays on screen to files in a format
+    Export(ExportFormat),
+    /// dump the cached history of the current ticker to an Arrow file
+    ExportHistory,
+    /// browse recordings on disk
+    Library,
+    /// show the most recent messages informed and warned about
+    Logs,
+    /// place an order of a quantity on the current ticker, at a limit price or at market if None
+    Order {
+        side: Side,
+        quantity: f64,
+        price: Option<f64>,
+    },
+    /// show the queue composition of the level nearest to a price, None to stop
+    Queue(Option<f64>),
+    /// quit the application
+    Quit,
+    /// write the frame on screen to a file, in the configured format if None
+    Screenshot(Option<ScreenshotFormat>),
+    /// estimate the slippage of an order size live, None to stop
+    Slippage(Option<f64>),
+    /// switch a view, or all views if None, between kernel density estimation and exact binning
+    Splat(Option<View>),
+    /// show the handling time breakdown of the dispatch loop
+    Stats,
+    /// subscribe a new ticker to the feed
+    Subscribe(String),
+    /// visualize an already subscribed ticker
+    Ticker(String),
+    /// unsubscribe an existing ticker
+    Unsubscribe(String),
+    /// show the books of a number of seconds back
+    Window(u64),
+}
+
+impl Command {
+    /// Parse and validate a command line against the currently subscribed symbols
+    pub fn parse(line: &str, symbols: &[String]) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Err("Empty command".to_string()),
+        };
+        // alert rules span several words
+        if name == "alert" {
+            let rule = words.collect::<Vec<_>>().join(" ");
+            return match rule.is_empty() {
+                true => Ok(Command::Alert(None)),
+                false => Ok(Command::Alert(Some(
+                    AlertRule::parse(&rule).map_err(|error| error.to_string())?,
+                ))),
+            };
+        }
+        // orders take a quantity and an optional limit price
+        if let Some(side) = match name {
+            "buy" => Some(Side::Bid),
+            "sell" => Some(Side::Ask),
+            _ => None,
+        } {
+            let positive = |word: Option<&str>| match word.map(|word| word.parse::<f64>()) {
+                Some(Ok(value)) if value > 0.0 && value.is_finite() => Ok(Some(value)),
+                Some(_) => Err(format!("'{}' expects a positive quantity and price", name)),
+                None => Ok(None),
+            };
+            let Some(quantity) = positive(words.next())? else {
+                return Err(format!("'{}' expects a quantity", name));
+            };
+            let price = positive(words.next())?;
+            if words.next().is_some() {
+                return Err(format!("Too many arguments to '{}'", name));
+            }
+            return Ok(Command::Order {
+                side,
+                quantity,
+                price,
+            });
+        }
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("Too many arguments to '{}'", name));
+        }
+
+        let subscribed = |argument: Option<&str>| -> Result<String, String> {
+            let symbol = match argument {
+                Some(argument) => parse_symbol(argument).map_err(|error| error.to_string())?,
+                None => return Err(format!("'{}' expects a symbol", name)),
+            };
+            if symbols.contains(&symbol) {
+                Ok(symbol)
+            } else {
+                Err(format!("Symbol {} is not subscribed", symbol))
+            }
+        };
+
+        let command = match name {
+            "cancel" => match argument {
+                Some(argument) => Command::Cancel(argument.to_string()),
+                None => return Err("'cancel' expects an order id".to_string()),
+            },
+            "depth" => match argument.map(|argument| argument.parse::<i32>()) {
+                Some(Ok(depth)) if BOOK_DEPTHS.contains(&depth) => Command::BookDepth(depth),
+                Some(_) => return Err(format!("'depth' expects one of {:?} levels", BOOK_DEPTHS)),
+                None => Command::Depth,
+            },
+            "export" => match argument {
+                Some("arrow") => Command::ExportHistory,
+                Some(argument) => Command::Export(
+                    ExportFormat::parse(argument).map_err(|error| error.to_string())?,
+                ),
+                None => Command::Export(ExportFormat::Npy),
+            },
+            "library" => Command::Library,
+            "logs" => Command::Logs,
+            "queue" => match argument.map(|argument| argument.parse::<f64>()) {
+                Some(Ok(price)) if price > 0.0 && price.is_finite() => Command::Queue(Some(price)),
+                Some(_) => return Err("'queue' expects a positive price".to_string()),
+                None => Command::Queue(None),
+            },
+            "quit" => Command::Quit,
+            "screenshot" => Command::Screenshot(
+                argument
+                    .map(ScreenshotFormat::parse)
+                    .transpose()
+                    .map_err(|error| error.to_string())?,
+            ),
+            "slippage" => match argument.map(|argument| argument.parse::<f64>()) {
+                Some(Ok(quantity)) if quantity > 0.0 && quantity.is_finite() => {
+                    Command::Slippage(Some(quantity))
+                }
+                Some(_) => return Err("'slippage' expects a positive quantity".to_string()),
+                None => Command::Slippage(None),
+            },
+            "splat" => match argument {
+                Some(argument) => Command::Splat(Some(View::parse(argument)?)),
+                None => Command::Splat(None),
+            },
+            "stats" => Command::Stats,
+            "subscribe" | "sub" => match argument {
+                Some(argument) => {
+                    Command::Subscribe(parse_symbol(argument).map_err(|error| error.to_string())?)
+                }
+                None => return Err("'subscribe' expects a symbol".to_string()),
+            },
+            "ticker" => Command::Ticker(subscribed(argument)?),
+            "unsubscribe" | "unsub" => Command::Unsubscribe(subscribed(argument)?),
+            "window" => match argument.map(|argument| argument.parse::<u64>()) {
+                Some(Ok(seconds)) if seconds > 0 => Command::Window(seconds),
+                _ => return Err("'window' expects a positive number of seconds".to_string()),
+            },
+            _ => return Err(format!("Unknown command '{}'", name)),
+        };
+
+        match (&command, argument) {
+            (Command::Library | Command::Logs | Command::Quit | Command::Stats, Some(_)) => {
+                Err(format!("'{}' takes no arguments", name))
+            }
+            _ => Ok(command),
+        }
+    }
+
+    /// Candidates for completing the last word of a partially written command line
+    pub fn candidates(line: &str, symbols: &[String]) -> Vec<String> {
+        match line.split_once(' ') {
+            None => COMMANDS.iter().map(|name| name.to_string()).collect(),
+            Some((name, _))
+                if ["ticker", "unsubscribe", "unsub", "subscribe", "sub"].contains(&name) =>
+            {
+                symbols.to_vec()
+            }
+            Some(("splat", _)) => View::ALL.map(|view| view.name().to_string()).to_vec(),
+            Some(("export", _)) => ExportFormat::ALL
+                .map(|format| format.name())
+                .into_iter()
+                .chain(["arrow"])
+                .map(|name| name.to_string())
+                .collect(),
+            Some(("screenshot", _)) => ScreenshotFormat::ALL
+                .map(|format| format.name().to_string())
+                .to_vec(),
+            Some(_) => vec![],
+        }
+    }
+}
+
+/// Longest prefix shared by all given words
+fn common_prefix(words: &[&String]) -> String {
+    match words.split_first() {
+        Some((first, rest)) => rest.iter().fold(first.to_string(), |prefix, word| {
+            prefix
+                .chars()
+                .zip(word.chars())
+                .take_while(|(lhs, rhs)| lhs == rhs)
+                .map(|(character, _)| character)
+                .collect()
+        }),
+        None => String::new(),
+    }
+}
+
+/// Single line text input with history navigation, prefix completion and inline errors
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    /// text currently written
+    pub text: String,
+    /// previously submitted lines, oldest first
+    history: Vec<String>,
+    /// position in history while navigating, None when editing a new line
+    history_index: Option<usize>,
+    /// validation error of the last submission
+    pub error: Option<String>,
+}
+
+impl Input {
+    /// constructor
+    pub fn new(history: Vec<String>) -> Input {
+        Input {
+            history,
+            ..Default::default()
+        }
+    }
+
+    /// write a character at the end of the line
+    pub fn push(&mut self, character: char) {
+        self.text.push(character);
+        self.history_index = None;
+        self.error = None;
+    }
+
+    /// erase the last character of the line
+    pub fn pop(&mut self) {
+        self.text.pop();
+        self.history_index = None;
+        self.error = None;
+    }
+
+    /// reset the line being written
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.history_index = None;
+        self.error = None;
+    }
+
+    /// take the written line and record it in history
+    pub fn submit(&mut self) -> String {
+        let line = self.text.trim().to_string();
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > HISTORY_LENGTH {
+                self.history.remove(0);
+            }
+        }
+        self.clear();
+        line
+    }
+
+    /// recall the previous line in history
+    pub fn previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if !self.history.is_empty() => self.history.len() - 1,
+            None => return,
+        };
+        self.history_index = Some(index);
+        self.text = self.history[index].clone();
+    }
+
+    /// recall the next line in history, back to an empty line after the most recent one
+    pub fn next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.text = self.history[index + 1].clone();
+            }
+            Some(_) => self.clear(),
+            None => (),
+        }
+    }
+
+    /// complete the last word with the longest prefix shared by the matching candidates
+    pub fn complete(&mut self, candidates: &[String]) {
+        let start = self.text.rfind(' ').map(|index| index + 1).unwrap_or(0);
+        let word = self.text[start..].to_uppercase();
+        let matches = candidates
+            .iter()
+            .filter(|candidate| candidate.to_uppercase().starts_with(&word))
+            .collect::<Vec<_>>();
+
+        let completed = common_prefix(&matches);
+        if completed.len() > word.len() {
+            self.text.truncate(start);
+            self.text.push_str(&completed);
+            if matches.len() == 1 {
+                self.text.push(' ');
+            }
+        }
+        self.error = None;
+    }
+}
+
+/// Location of the command history kept between sessions
+pub fn history_path() -> Option<PathBuf> {
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".bookedblocks_history"))
+}
+
+/// Read the last commands of the history kept between sessions
+pub fn load_history(path: &Path) -> Vec<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => last_lines(&content),
+        Err(_) => vec![],
+    }
+}
+
+/// Append a command to the history kept between sessions, only keeping its last commands so the
+/// file read at launch stays small
+pub fn save_history(path: &Path, line: &str) -> Result<(), BookedError> {
+    let file = |source| BookedError::File {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut history = match std::fs::read_to_string(path) {
+        Ok(content) => last_lines(&content),
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(source) => return Err(file(source)),
+    };
+    if history.last().map(String::as_str) != Some(line) {
+        history.push(line.to_string());
+    }
+    let start = history.len().saturating_sub(HISTORY_LENGTH);
+    let mut content = history[start..].join("\n");
+    content.push('\n');
+    std::fs::write(path, content).map_err(file)
+}
+
+/// private utility function giving the last commands of a history
+fn last_lines(content: &str) -> Vec<String> {
+    let lines = content.lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(HISTORY_LENGTH)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Widget for rendering an Input to interface
+pub struct InputWidget<'a> {
+    input: &'a Input,
+    title: &'a str,
+    prefix: &'a str,
+}
+
+impl<'a> InputWidget<'a> {
+    pub fn new(input: &'a Input, title: &'a str, prefix: &'a str) -> InputWidget<'a> {
+        InputWidget {
+            input,
+            title,
+            prefix,
+        }
+    }
+}
+
+impl Widget for InputWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let block = match &self.input.error {
+            Some(error) => Block::bordered()
+                .title(self.title)
+                .title_bottom(error.clone().red().bold()),
+            None => Block::bordered().title(self.title),
+        };
+
+        Paragraph::new(format!("{}{}_", self.prefix, self.input.text))
+            .block(block)
+            .render(area, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let symbols = vec!["BTC/USD".to_string()];
+
+        assert_eq!(Command::parse("depth", &symbols), Ok(Command::Depth));
+        assert_eq!(Command::parse("stats", &symbols), Ok(Command::Stats));
+        assert_eq!(Command::parse("splat", &symbols), Ok(Command::Splat(None)));
+        assert_eq!(
+            Command::parse("splat profile", &symbols),
+            Ok(Command::Splat(Some(View::Profile)))
+        );
+        assert!(Command::parse("splat chart", &symbols).is_err());
+        assert_eq!(
+            Command::parse("subscribe eth/usd", &symbols),
+            Ok(Command::Subscribe("ETH/USD".to_string()))
+        );
+        assert_eq!(
+            Command::parse("ticker BTC/USD", &symbols),
+            Ok(Command::Ticker("BTC/USD".to_string()))
+        );
+        assert!(Command::parse("", &symbols).is_err());
+        assert!(Command::parse("launch", &symbols).is_err());
+        assert!(Command::parse("depth now", &symbols).is_err());
+        assert_eq!(
+            Command::parse("depth 500", &symbols),
+            Ok(Command::BookDepth(500))
+        );
+        assert!(Command::parse("depth 50", &symbols).is_err());
+        assert_eq!(
+            Command::parse("window 120", &symbols),
+            Ok(Command::Window(120))
+        );
+        assert!(Command::parse("window", &symbols).is_err());
+        assert_eq!(
+            Command::parse("unsub btc/usd", &symbols),
+            Ok(Command::Unsubscribe("BTC/USD".to_string()))
+        );
+        assert!(Command::parse("subscribe BTCUSD", &symbols).is_err());
+        assert!(Command::parse("ticker ETH/USD", &symbols).is_err());
+        assert!(Command::parse("unsubscribe", &symbols).is_err());
+        assert_eq!(
+            Command::parse("slippage 2.5", &symbols),
+            Ok(Command::Slippage(Some(2.5)))
+        );
+        assert_eq!(
+            Command::parse("slippage", &symbols),
+            Ok(Command::Slippage(None))
+        );
+        assert!(Command::parse("slippage -1", &symbols).is_err());
+        assert!(Command::parse("slippage lots", &symbols).is_err());
+        assert_eq!(
+            Command::parse("queue 101.5", &symbols),
+            Ok(Command::Queue(Some(101.5)))
+        );
+        assert_eq!(Command::parse("queue", &symbols), Ok(Command::Queue(None)));
+        assert_eq!(
+            Command::parse("alert ETH/USD wall 50", &symbols),
+            Ok(Command::Alert(Some(
+                AlertRule::parse("ETH/USD wall 50").unwrap()
+            )))
+        );
+        assert_eq!(Command::parse("alert", &symbols), Ok(Command::Alert(None)));
+        assert!(Command::parse("alert BTC/USD price", &symbols).is_err());
+        assert!(Command::parse("queue top", &symbols).is_err());
+        assert_eq!(
+            Command::parse("export", &symbols),
+            Ok(Command::Export(ExportFormat::Npy))
+        );
+        assert_eq!(
+            Command::parse("export csv", &symbols),
+            Ok(Command::Export(ExportFormat::Csv))
+        );
+        assert_eq!(
+            Command::parse("export arrow", &symbols),
+            Ok(Command::ExportHistory)
+        );
+        assert!(Command::parse("export xlsx", &symbols).is_err());
+        assert_eq!(
+            Command::parse("screenshot", &symbols),
+            Ok(Command::Screenshot(None))
+        );
+        assert_eq!(
+            Command::parse("screenshot png", &symbols),
+            Ok(Command::Screenshot(Some(ScreenshotFormat::Png)))
+        );
+        assert!(Command::parse("screenshot jpeg", &symbols).is_err());
+        assert_eq!(
+            Command::parse("buy 0.5 101.5", &symbols),
+            Ok(Command::Order {
+                side: Side::Bid,
+                quantity: 0.5,
+                price: Some(101.5)
+            })
+        );
+        assert_eq!(
+            Command::parse("sell 2", &symbols),
+            Ok(Command::Order {
+                side: Side::Ask,
+                quantity: 2.0,
+                price: None
+            })
+        );
+        assert!(Command::parse("buy", &symbols).is_err());
+        assert!(Command::parse("sell 1 -5", &symbols).is_err());
+        assert!(Command::parse("buy 1 100 now", &symbols).is_err());
+        assert_eq!(
+            Command::parse("cancel OQCLML-BW3P3-BUCMWZ", &symbols),
+            Ok(Command::Cancel("OQCLML-BW3P3-BUCMWZ".to_string()))
+        );
+        assert!(Command::parse("cancel", &symbols).is_err());
+    }
+
+    #[test]
+    fn test_input_history() {
+        let mut input = Input::new(vec!["depth".to_string()]);
+        for character in "splat".chars() {
+            input.push(character);
+        }
+        assert_eq!(input.submit(), "splat");
+        assert_eq!(input.text, "");
+
+        input.previous();
+        assert_eq!(input.text, "splat");
+        input.previous();
+        assert_eq!(input.text, "depth");
+        input.previous();
+        assert_eq!(input.text, "depth");
+        input.next();
+        assert_eq!(input.text, "splat");
+        input.next();
+        assert_eq!(input.text, "");
+
+        input.push('s');
+        input.push('p');
+        input.push('l');
+        input.push('a');
+        input.push('t');
+        input.submit();
+        input.previous();
+        input.previous();
+        assert_eq!(input.text, "depth");
+    }
+
+    #[test]
+    fn test_saved_history() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("history");
+        assert!(load_history(&path).is_empty());
+
+        // repeated commands are kept once, the oldest ones dropped past the history length
+        save_history(&path, "depth").unwrap();
+        save_history(&path, "depth").unwrap();
+        save_history(&path, "stats").unwrap();
+        assert_eq!(load_history(&path), vec!["depth", "stats"]);
+        for index in 0..HISTORY_LENGTH {
+            save_history(&path, &format!("window {}", index)).unwrap();
+        }
+        let history = load_history(&path);
+        assert_eq!(history.len(), HISTORY_LENGTH);
+        assert_eq!(history[0], "window 0");
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), HISTORY_LENGTH);
+
+        assert!(matches!(
+            save_history(directory.path(), "depth"),
+            Err(BookedError::File { .. })
+        ));
+    }
+
+    #[test]
+    fn test_input_complete() {
+        let symbols = vec!["BTC/USD".to_string(), "BTC/EUR".to_string()];
+        let mut input = Input::new(vec![]);
+
+        input.push('s');
+        input.complete(&Command::candidates(&input.text, &symbols));
+        assert_eq!(input.text, "s");
+
+        input.push('u');
+        input.complete(&Command::candidates(&input.text, &symbols));
+        assert_eq!(input.text, "subscribe ");
+
+        input.push('b');
+        input.complete(&Command::candidates(&input.text, &symbols));
+        assert_eq!(input.text, "subscribe BTC/");
+
+        input.push('e');
+        input.complete(&Command::candidates(&input.text, &symbols));
+        assert_eq!(input.text, "subscribe BTC/EUR ");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::BookedError;
+use crate::feed::{Booked, TickerState, Traded};
+
+use rdkafka::message::Message;
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::{ClientConfig, ClientContext};
+use serde::Serialize;
+use tracing::warn;
+
+use std::time::Duration;
+
+/// Topic the events are published to unless another template is given
+pub const DEFAULT_TOPIC_TEMPLATE: &str = "bookedblocks.{symbol}.{type}";
+
+/// Time the messages still queued are given to be delivered when the sink is flushed
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// private event published, tagged with its type
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Published<'a> {
+    Book(&'a Booked),
+    Trade(&'a Traded),
+    Ticker(&'a TickerState),
+}
+
+impl Published<'_> {
+    /// private utility method for the type the event is tagged with
+    fn kind(&self) -> &'static str {
+        match self {
+            Published::Book(_) => "book",
+            Published::Trade(_) => "trade",
+            Published::Ticker(_) => "ticker",
+        }
+    }
+
+    /// private utility method for the symbol of the event
+    fn symbol(&self) -> &str {
+        match self {
+            Published::Book(booked) => &booked.symbol,
+            Published::Trade(traded) => &traded.symbol,
+            Published::Ticker(ticker) => &ticker.symbol,
+        }
+    }
+}
+
+/// private context logging the messages the brokers could not be delivered
+struct DeliveryLogger;
+
+impl ClientContext for DeliveryLogger {}
+
+impl ProducerContext for DeliveryLogger {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _: ()) {
+        if let Err((error, message)) = delivery_result {
+            warn!(topic = message.topic(), %error, "could not deliver to kafka");
+        }
+    }
+}
+
+/// Publisher of the book updates, trades and tickers received to Kafka topics as JSON, tagged
+/// with their type and keyed by symbol so the events of a symbol keep their order
+pub struct KafkaSink {
+    producer: ThreadedProducer<DeliveryLogger>,
+    topic_template: String,
+}
+
+impl KafkaSink {
+    /// constructor, from a comma separated list of brokers and a topic template in which
+    /// `{symbol}` and `{type}` are replaced
+    pub fn new(brokers: &str, topic_template: String) -> Result<KafkaSink, BookedError> {
+        match ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create_with_context(DeliveryLogger)
+        {
+            Ok(producer) => Ok(KafkaSink {
+                producer,
+                topic_template,
+            }),
+            Err(message) => Err(BookedError::Publish(format!("{:?}", message))),
+        }
+    }
+
+    /// topic of an event of a type for a symbol, written BASE-QUOTE as topics cannot hold
+    /// slashes
+    pub fn topic(&self, symbol: &str, kind: &str) -> String {
+        self.topic_template
+            .replace("{symbol}", &symbol.replace('/', "-"))
+            .replace("{type}", kind)
+    }
+
+    /// publish a book update
+    pub fn book(&self, booked: &Booked) -> Result<(), BookedError> {
+        self.publish(Published::Book(booked))
+    }
+
+    /// publish trades, one message each
+    pub fn trades(&self, trades: &[Traded]) -> Result<(), BookedError> {
+        trades
+            .iter()
+            .try_for_each(|traded| self.publish(Published::Trade(traded)))
+    }
+
+    /// publish a ticker update
+    pub fn ticker(&self, ticker: &TickerState) -> Result<(), BookedError> {
+        self.publish(Published::Ticker(ticker))
+    }
+
+    /// wait for the messages still queued to be delivered, e.g. before quitting
+    pub fn flush(&self) -> Result<(), BookedError> {
+        match self.producer.flush(FLUSH_TIMEOUT) {
+            Ok(()) => Ok(()),
+            Err(message) => Err(BookedError::Publish(format!("{:?}", message))),
+        }
+    }
+
+    /// private utility method queueing an event for delivery, failing if the queue is full
+    fn publish(&self, published: Published<'_>) -> Result<(), BookedError> {
+        let payload = match serde_json::to_string(&published) {
+            Ok(payload) => payload,
+            Err(message) => return Err(BookedError::Publish(format!("{:?}", message))),
+        };
+        let topic = self.topic(published.symbol(), published.kind());
+        match self.producer.send(
+            BaseRecord::to(&topic)
+                .key(published.symbol())
+                .payload(&payload),
+        ) {
+            Ok(()) => Ok(()),
+            Err((message, _)) => Err(BookedError::Publish(format!("{:?}", message))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::Order;
+
+    #[test]
+    fn test_kafka_sink() {
+        // messages are only queued, no broker needs to listen
+        let sink = KafkaSink::new("127.0.0.1:9", DEFAULT_TOPIC_TEMPLATE.to_string()).unwrap();
+        assert_eq!(sink.topic("BTC/USD", "book"), "bookedblocks.BTC-USD.book");
+        let sink = KafkaSink::new("127.0.0.1:9", "books-{symbol}".to_string()).unwrap();
+        assert_eq!(sink.topic("ETH/EUR", "trade"), "books-ETH-EUR");
+
+        let booked = Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+            bids: vec![Order {
+                price: 99.5,
+                quantity: 2.0,
+            }],
+            asks: vec![],
+        };
+        let payload = serde_json::to_value(Published::Book(&booked)).unwrap();
+        assert_eq!(payload["type"], "book");
+        assert_eq!(payload["symbol"], "BTC/USD");
+        assert!(sink.book(&booked).is_ok());
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::DateTime;
+use clap::ValueEnum;
+
+/// Enum of the ways time axes are labelled
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum TimeLabels {
+    /// seconds before the latest book
+    Relative,
+    /// wall clock time in UTC
+    Clock,
+}
+
+/// Formatting of the axis labels of a symbol
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Labels {
+    pub time: TimeLabels,
+    /// number of decimals the prices of the symbol are quoted with, when known
+    pub price_decimals: Option<u32>,
+}
+
+impl Labels {
+    /// title of time axes
+    pub fn time_title(&self) -> &'static str {
+        match self.time {
+            TimeLabels::Relative => "Time (s)",
+            TimeLabels::Clock => "Time (UTC)",
+        }
+    }
+
+    /// labels of the start, middle and end of a time axis spanning a range of seconds
+    pub fn time_axis(&self, time_range: (i64, i64)) -> [String; 3] {
+        let span = time_range.1 - time_range.0;
+        match self.time {
+            TimeLabels::Relative => [span.to_string(), (span / 2).to_string(), "now".to_string()],
+            TimeLabels::Clock => {
+                [time_range.0, time_range.0 + span / 2, time_range.1].map(|seconds| {
+                    match DateTime::from_timestamp(seconds, 0) {
+                        Some(time) => time.format("%H:%M:%S").to_string(),
+                        None => seconds.to_string(),
+                    }
+                })
+            }
+        }
+    }
+
+    /// price with thousands separators, rounded to the decimals of the symbol when known
+    pub fn price(&self, price: f64) -> String {
+        match self.price_decimals {
+            Some(decimals) => group_thousands(&format!("{:.*}", decimals as usize, price)),
+            None => group_thousands(&format!("{:}", price)),
+        }
+    }
+}
+
+/// Number with commas between the thousands of its integer part
+pub fn group_thousands(number: &str) -> String {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", number),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+    if !integer.chars().all(|character| character.is_ascii_digit()) {
+        return number.to_string();
+    }
+
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+/// Volume with an SI suffix and three significant digits, e.g. 12.3k
+pub fn volume_label(volume: f64) -> String {
+    let magnitude = volume.abs();
+    let (scaled, suffix) = match magnitude {
+        _ if magnitude >= 1e9 => (volume / 1e9, "G"),
+        _ if magnitude >= 1e6 => (volume / 1e6, "M"),
+        _ if magnitude >= 1e3 => (volume / 1e3, "k"),
+        _ => (volume, ""),
+    };
+    let decimals = match scaled.abs() {
+        value if value >= 100.0 => 0,
+        value if value >= 10.0 => 1,
+        _ => 2,
+    };
+    format!("{:.*}{}", decimals, scaled, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(group_thousands("1234567.891"), "1,234,567.891");
+        assert_eq!(group_thousands("-123456"), "-123,456");
+        assert_eq!(group_thousands("999"), "999");
+        assert_eq!(group_thousands("NaN"), "NaN");
+
+        assert_eq!(volume_label(0.5), "0.50");
+        assert_eq!(volume_label(12_345.0), "12.3k");
+        assert_eq!(volume_label(-2_500_000.0), "-2.50M");
+        assert_eq!(volume_label(450e9), "450G");
+
+        let mut labels = Labels {
+            time: TimeLabels::Relative,
+            price_decimals: Some(1),
+        };
+        assert_eq!(labels.price(65432.16), "65,432.2");
+        assert_eq!(labels.time_axis((3600, 3900)), ["300", "150", "now"]);
+        labels.time = TimeLabels::Clock;
+        assert_eq!(
+            labels.time_axis((3600, 3900)),
+            ["01:00:00", "01:02:30", "01:05:00"]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use serde::Deserialize;
+
+/// Smallest share in percent of the terminal a row or column of panels can be resized to
+const MIN_SHARE: u16 = 20;
+
+/// Percentage points a row or column of panels grows or shrinks by per key press
+pub const RESIZE_STEP: i16 = 5;
+
+/// Enum of the panels of the ticker page that can be hidden
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Panel {
+    /// order map and volume profile
+    Map,
+    /// depth chart and ladder
+    Depth,
+    /// volume, imbalance and activity time series
+    Volumes,
+    /// ticker, arbitrage and basis
+    Ticker,
+}
+
+/// Enum of the widgets of the ticker page that can fill the whole terminal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Focus {
+    Map,
+    Depth,
+    Volumes,
+    Ladder,
+}
+
+impl Focus {
+    /// next widget in the cycle
+    pub fn cycled(self) -> Focus {
+        match self {
+            Focus::Map => Focus::Depth,
+            Focus::Depth => Focus::Volumes,
+            Focus::Volumes => Focus::Ladder,
+            Focus::Ladder => Focus::Map,
+        }
+    }
+
+    /// name of the widget, as shown in the interface
+    pub fn name(self) -> &'static str {
+        match self {
+            Focus::Map => "order map",
+            Focus::Depth => "depth",
+            Focus::Volumes => "time series",
+            Focus::Ladder => "ladder",
+        }
+    }
+}
+
+/// Arrangement of the panels of the ticker page, kept between sessions in the `[layout]` table
+/// of the configuration file
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PanelLayout {
+    pub map: bool,
+    pub depth: bool,
+    pub volumes: bool,
+    pub ticker: bool,
+    /// share in percent of the height taken by the map and depth row
+    pub rows: u16,
+    /// share in percent of the width taken by the map and volumes column
+    pub columns: u16,
+}
+
+impl Default for PanelLayout {
+    fn default() -> PanelLayout {
+        PanelLayout {
+            map: true,
+            depth: true,
+            volumes: true,
+            ticker: true,
+            rows: 65,
+            columns: 65,
+        }
+    }
+}
+
+/// Areas of the widgets of the ticker page, None for hidden ones
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LayoutAreas {
+    pub map: Option<Rect>,
+    pub profile: Option<Rect>,
+    pub depth: Option<Rect>,
+    pub ladder: Option<Rect>,
+    pub volumes: Option<Rect>,
+    pub ticker: Option<Rect>,
+}
+
+impl LayoutAreas {
+    /// areas of a single widget filling an area
+    pub fn focused(focus: Focus, area: Rect) -> LayoutAreas {
+        let mut areas = LayoutAreas::default();
+        match focus {
+            Focus::Map => areas.map = Some(area),
+            Focus::Depth => areas.depth = Some(area),
+            Focus::Volumes => areas.volumes = Some(area),
+            Focus::Ladder => areas.ladder = Some(area),
+        }
+        areas
+    }
+}
+
+impl PanelLayout {
+    /// whether a panel is displayed
+    pub fn shown(&self, panel: Panel) -> bool {
+        match panel {
+            Panel::Map => self.map,
+            Panel::Depth => self.depth,
+            Panel::Volumes => self.volumes,
+            Panel::Ticker => self.ticker,
+        }
+    }
+
+    /// show a hidden panel or hide a displayed one, keeping at least one panel displayed
+    pub fn toggle(&mut self, panel: Panel) {
+        let shown = [self.map, self.depth, self.volumes, self.ticker];
+        if self.shown(panel) && shown.iter().filter(|shown| **shown).count() == 1 {
+            return;
+        }
+        match panel {
+            Panel::Map => self.map = !self.map,
+            Panel::Depth => self.depth = !self.depth,
+            Panel::Volumes => self.volumes = !self.volumes,
+            Panel::Ticker => self.ticker = !self.ticker,
+        }
+    }
+
+    /// grow the top row of panels by a number of percentage points, shrinking it when negative
+    pub fn resize_rows(&mut self, step: i16) {
+        self.rows =
+            (self.rows as i16 + step).clamp(MIN_SHARE as i16, 100 - MIN_SHARE as i16) as u16;
+    }
+
+    /// grow the left column of panels by a number of percentage points, shrinking it when
+    /// negative
+    pub fn resize_columns(&mut self, step: i16) {
+        self.columns =
+            (self.columns as i16 + step).clamp(MIN_SHARE as i16, 100 - MIN_SHARE as i16) as u16;
+    }
+
+    /// areas of the displayed panels, rows and columns without a displayed panel giving their
+    /// space to the others
+    pub fn split(&self, area: Rect) -> LayoutAreas {
+        let halves = |area: Rect, share: u16, first: bool, second: bool, vertical: bool| {
+            let constraints = match (first, second) {
+                (true, true) => vec![
+                    Constraint::Percentage(share),
+                    Constraint::Percentage(100 - share),
+                ],
+                (true, false) => vec![Constraint::Percentage(100), Constraint::Length(0)],
+                _ => vec![Constraint::Length(0), Constraint::Percentage(100)],
+            };
+            let chunks = match vertical {
+                true => Layout::vertical(constraints).split(area),
+                false => Layout::horizontal(constraints).split(area),
+            };
+            (first.then_some(chunks[0]), second.then_some(chunks[1]))
+        };
+
+        let (top, bottom) = halves(
+            area,
+            self.rows,
+            self.map || self.depth,
+            self.volumes || self.ticker,
+            true,
+        );
+        let (map, depth) = match top {
+            Some(top) => halves(top, self.columns, self.map, self.depth, false),
+            None => (None, None),
+        };
+        let (volumes, ticker) = match bottom {
+            Some(bottom) => halves(bottom, self.columns, self.volumes, self.ticker, false),
+            None => (None, None),
+        };
+        let (map, profile) = match map {
+            Some(map) => halves(map, 85, true, true, false),
+            None => (None, None),
+        };
+        let (depth, ladder) = match depth {
+            Some(depth) => halves(depth, 55, true, true, false),
+            None => (None, None),
+        };
+        LayoutAreas {
+            map,
+            profile,
+            depth,
+            ladder,
+            volumes,
+            ticker,
+        }
+    }
+
+    /// layout keeping at least one panel displayed and the rows and columns within their
+    /// bounds, for layouts edited by hand
+    pub fn sanitized(mut self) -> PanelLayout {
+        if !(self.map || self.depth || self.volumes || self.ticker) {
+            self.map = true;
+        }
+        self.resize_rows(0);
+        self.resize_columns(0);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panel_layout() {
+        let mut layout = PanelLayout::default();
+        let area = Rect::new(0, 0, 100, 100);
+        let areas = layout.split(area);
+        assert_eq!(areas.map, Some(Rect::new(0, 0, 55, 65)));
+        assert_eq!(areas.profile, Some(Rect::new(55, 0, 10, 65)));
+        assert_eq!(areas.ticker, Some(Rect::new(65, 65, 35, 35)));
+
+        // the volumes get the whole bottom row, the map the whole top row without depth
+        layout.toggle(Panel::Ticker);
+        layout.toggle(Panel::Depth);
+        layout.resize_rows(-RESIZE_STEP);
+        let areas = layout.split(area);
+        assert_eq!(areas.map, Some(Rect::new(0, 0, 85, 60)));
+        assert_eq!(areas.volumes, Some(Rect::new(0, 60, 100, 40)));
+        assert_eq!(
+            (areas.depth, areas.ladder, areas.ticker),
+            (None, None, None)
+        );
+
+        // the last displayed panel stays displayed
+        layout.toggle(Panel::Volumes);
+        layout.toggle(Panel::Map);
+        assert!(layout.map);
+        assert_eq!(layout.split(area).map, Some(Rect::new(0, 0, 85, 100)));
+        assert_eq!(
+            LayoutAreas::focused(Focus::Ladder, area),
+            LayoutAreas {
+                ladder: Some(area),
+                ..LayoutAreas::default()
+            }
+        );
+
+        layout.resize_columns(100);
+        assert_eq!(layout.columns, 80);
+        assert_eq!(layout.sanitized(), layout);
+        assert_eq!(
+            PanelLayout {
+                map: false,
+                depth: false,
+                volumes: false,
+                ticker: false,
+                rows: 500,
+                columns: 0,
+            }
+            .sanitized(),
+            PanelLayout {
+                map: true,
+                depth: false,
+                volumes: false,
+                ticker: false,
+                rows: 80,
+                columns: 20,
+            }
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Engine of bookedblocks, usable without its terminal interface
+//!
+//! [`Feed`] streams the books, tickers and trades of Kraken symbols as [`Action`]s, whose
+//! [`Booked`] updates are accumulated over a time window by a [`BookHistory`]. A [`Pipeline`]
+//! splats such a history into a time and price grid of resting volumes, [`SplattedBlocks`], along
+//! with the depth and volumes series drawn next to it.
+
+/// Messages exchanged between the feed, the dispatcher and the interface
+pub mod actions;
+/// Alert rules watched on the books, and the channels triggered alerts are forwarded to
+pub mod alerts;
+/// Monotonic time base mapped onto exchange time
+pub mod clock;
+/// Errors of the feed, of the book histories and of the dispatch of actions
+pub mod error;
+/// Export of the splatted arrays to files
+pub mod export;
+/// Kraken websocket and REST clients, and the book, ticker and trade types they produce
+pub mod feed;
+/// Orders of the user read from the executions channel
+pub mod orders;
+/// Book histories and the pipeline splatting them into views
+pub mod pipeline;
+/// Panels third-party crates add to the ticker page
+pub mod plugins;
+/// Unix socket relay between a collector daemon and the interfaces attached to it
+pub mod relay;
+/// Screenshots of the interface as ANSI text, SVG or PNG
+pub mod screenshot;
+/// Rhai scripts hooked onto the books and pipeline results, plotting series and raising alerts
+pub mod scripting;
+/// Kernels and splatting of orders onto grids
+pub mod splat;
+/// Order entry through the authenticated websocket
+pub mod trading;
+/// Detection of walls of resting volume
+pub mod walls;
+
+pub use actions::Action;
+pub use error::BookedError;
+pub use feed::{Booked, Feed, Order, TickerState, Traded, fetch_snapshot};
+pub use pipeline::{BookHistory, Pipeline, SplattedBlocks};
+pub use splat::{Bandwidth, Kernel, KernelShape};
+
+#[cfg(test)]
+mod tests {
+    use crate::pipeline::Splatted;
+    use crate::{BookHistory, Booked, Kernel, Order, Pipeline, SplattedBlocks};
+
+    #[tokio::test]
+    async fn test_embedded_engine() {
+        let mut history = BookHistory::new(60);
+        for second in 0..10 {
+            let booked = Booked {
+                symbol: "BTC/USD".to_string(),
+                timestamp: chrono::DateTime::from_timestamp(second, 0)
+                    .unwrap()
+                    .to_rfc3339(),
+                bids: vec![Order {
+                    price: 99.0,
+                    quantity: 1.0,
+                }],
+                asks: vec![Order {
+                    price: 101.0,
+                    quantity: 2.0,
+                }],
+            };
+            history.update(booked).await.unwrap();
+        }
+
+        let pipeline = Pipeline::new(60, 10, 20, Kernel::default());
+        let (splatted, timings) = pipeline.run(&history).await;
+        assert!(!timings.is_empty());
+        let blocks: &SplattedBlocks = splatted
+            .iter()
+            .find_map(|splatted| match splatted {
+                Splatted::Blocks(blocks) => Some(blocks),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(blocks.volumes.dim(), (10, 20));
+        assert_eq!(blocks.grid.price_range, (99.0, 101.0));
+        assert!(blocks.volumes.iter().any(|volume| *volume > 0.0));
+    }
+}
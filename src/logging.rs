@@ -0,0 +1,69 @@
+use crate::error::BookedError;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+
+use std::path::Path;
+
+/// install the global subscriber writing log events up to a level into a file of a directory
+/// rolled over daily, keeping the last week of files, since the terminal is taken by the
+/// interface; events are written until the returned guard is dropped
+pub fn init(directory: &Path, level: LevelFilter) -> Result<WorkerGuard, BookedError> {
+    let appender = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("bookedblocks")
+        .filename_suffix("log")
+        .max_log_files(7)
+        .build(directory)
+    {
+        Ok(appender) => appender,
+        Err(source) => return Err(BookedError::Logging(source.into())),
+    };
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    match tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_max_level(level)
+        .try_init()
+    {
+        Ok(()) => Ok(guard),
+        Err(source) => Err(BookedError::Logging(source)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tracing::{debug, warn};
+
+    #[test]
+    fn test_init() {
+        let temporary = tempfile::tempdir().unwrap();
+        let directory = temporary.path().join("logs");
+        let guard = init(&directory, LevelFilter::INFO).unwrap();
+        warn!(symbol = "BTC/USD", "logged warning");
+        debug!("filtered debug");
+        // only one subscriber is ever installed
+        assert!(matches!(
+            init(&directory, LevelFilter::INFO),
+            Err(BookedError::Logging(_))
+        ));
+        // events are flushed to the file once the guard is dropped
+        drop(guard);
+
+        let files = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        let name = files[0].file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("bookedblocks") && name.ends_with("log"));
+        let written = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(written.contains("WARN"));
+        assert!(written.contains("logged warning symbol=\"BTC/USD\""));
+        assert!(!written.contains("filtered debug"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+
+/// Severity of a logged message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// short name of the severity, as shown in front of messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARN",
+            Severity::Error => "ERROR",
+        }
+    }
+}
+
+/// Message logged by the application
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    /// unix time in seconds the message was logged at
+    pub time: i64,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Bounded in memory buffer of the most recent log entries, oldest first
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    /// number of entries kept before the oldest ones are dropped
+    pub capacity: usize,
+    entries: VecDeque<LogEntry>,
+}
+
+impl LogBuffer {
+    /// constructor
+    pub fn new(capacity: usize) -> LogBuffer {
+        LogBuffer {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// append a message, dropping the oldest entries beyond capacity
+    pub fn push(&mut self, time: i64, severity: Severity, message: String) {
+        self.entries.push_back(LogEntry {
+            time,
+            severity,
+            message,
+        });
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// entries kept, oldest first
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &LogEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_buffer() {
+        let mut logs = LogBuffer::new(2);
+        assert_eq!(logs.entries().len(), 0);
+
+        logs.push(1, Severity::Info, "first".to_string());
+        logs.push(2, Severity::Warning, "second".to_string());
+        logs.push(3, Severity::Info, "third".to_string());
+
+        assert_eq!(logs.entries().len(), 2);
+        let messages = logs
+            .entries()
+            .map(|entry| entry.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["second", "third"]);
+        assert_eq!(logs.entries().next().unwrap().severity, Severity::Warning);
+    }
+}
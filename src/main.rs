@@ -1,31 +1,168 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use itertools::Itertools;
 
 use tokio;
 use tokio::sync::Mutex;
 use tokio::sync::mpsc::{Receiver, Sender, channel};
-use tokio::task::{JoinHandle, spawn};
+use tokio::task::{JoinHandle, spawn, spawn_blocking};
+use tokio::time::interval;
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::filter::LevelFilter;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-mod actions;
-use actions::Action;
+use actions::{Action, ActionSender, Query};
+use bookedblocks::actions;
+
+use alerts::{AlertEngine, AlertRule, AlertSink, notify_desktop};
+use bookedblocks::alerts;
+
+mod broadcast;
+use broadcast::Broadcaster;
+
+mod api;
+use api::{book_json, heatmap_json, symbols_json};
 
 mod app;
-use app::{App, State};
+use app::{App, Page, PlotMarker, State};
+
+mod cadence;
+use cadence::Cadence;
+
+mod collector;
+use collector::{Collected, Collector, RawOutput};
+
+use bookedblocks::clock;
+use bookedblocks::error;
+use error::BookedError;
+
+mod config;
+use config::{config_path, load_config};
+
+mod daily;
+use daily::{DailyTracker, Rollover};
+
+mod emit;
+use emit::{EmitFormat, Emitter, Summary};
+
+use bookedblocks::export;
+use export::{ExportFormat, export_snapshot};
+
+use bookedblocks::feed;
+use feed::{
+    Feed, TickerState, connect_own_orders, fetch_account, fetch_price_decimals, fetch_snapshot,
+};
+
+mod input;
+use input::BOOK_DEPTHS;
+
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "kafka")]
+use kafka::{DEFAULT_TOPIC_TEMPLATE, KafkaSink};
+
+mod labels;
+use labels::TimeLabels;
+
+mod layout;
+use layout::PanelLayout;
+
+mod logging;
+
+mod logs;
+use logs::Severity;
+
+mod metrics;
+use metrics::{ActionMetrics, CacheStats, Diagnostics, MessageRate};
+
+use bookedblocks::orders;
+use orders::OwnOrders;
+
+mod recording;
+use recording::{
+    ArchiveFormat, archive_recording, inspect_recording, load_recording, scan_recordings,
+};
+
+mod persistence;
+use persistence::{HistoryPersister, Slice, load_history, write_arrow, write_slice};
+
+use bookedblocks::pipeline;
+use pipeline::{
+    BasisOperation, BookHistory, Pipeline, PriceBand, PriceRange, PriceScale, Splatted, StageTiming,
+};
+
+use bookedblocks::plugins;
+
+mod sanity;
+use sanity::BookChecker;
+
+use bookedblocks::relay;
+
+use bookedblocks::screenshot;
+use screenshot::{ScreenshotFormat, write_screenshot};
+
+use bookedblocks::scripting;
+use scripting::Scripts;
+
+use bookedblocks::splat;
+use splat::{ADAPTIVE_NEIGHBOURS, Bandwidth, Kernel, KernelShape, Normalization};
+
+mod storage;
+use storage::Storage;
+
+mod terminal;
+
+mod theme;
+use theme::{ColorDepth, Gradient, Theme};
+
+mod timeseries;
+use timeseries::{TimeSeriesSink, TimeSeriesWriter};
 
-mod feed;
-use feed::{Feed, TickerState};
+use bookedblocks::trading;
+use trading::Trader;
 
-mod pipeline;
-use pipeline::{BookHistory, Pipeline};
+use bookedblocks::walls;
+use walls::{Side, WallDetector, WallEvent};
 
-mod splat;
+#[cfg(all(test, feature = "golden"))]
+mod golden;
+
+/// Time to wait between two attempts at connecting the feed again once its listener stopped
+const RECONNECT_PERIOD: Duration = Duration::from_secs(10);
+
+/// Time between two checks of the interface loops and the feed listener
+const SUPERVISION_PERIOD: Duration = Duration::from_secs(1);
+
+/// Time between two polls of the balances and open orders of the user
+const ACCOUNT_PERIOD: Duration = Duration::from_secs(15);
+
+/// Length in seconds of the record batches of the histories exported to Arrow files
+const ARROW_BATCH_SECONDS: i64 = 60;
+
+/// Time between two writes of the aggregates of the latest books to the time series database
+const TIMESERIES_PERIOD: Duration = Duration::from_secs(1);
+
+/// Time between two enforcements of the memory budget of the cached histories
+const BUDGET_PERIOD: Duration = Duration::from_secs(1);
 
 /// Local cache in Dispatch holding all order book histories
 struct BooksCache {
     time_cache_window_seconds: usize,
     cache: HashMap<String, BookHistory>,
+    /// bytes the histories may hold before older books get downsampled, None for no limit
+    memory_budget: Option<usize>,
+    /// order in which symbols were last viewed, higher meaning more recently
+    views: HashMap<String, u64>,
+    /// number of pipeline runs requested so far
+    view_count: u64,
+    /// bytes held by the histories when the budget was last enforced
+    usage: usize,
+    /// time the budget was last enforced, None before the first book
+    last_enforced: Option<Instant>,
 }
 
 impl BooksCache {
@@ -33,7 +170,81 @@ impl BooksCache {
         BooksCache {
             time_cache_window_seconds,
             cache: HashMap::new(),
+            memory_budget: None,
+            views: HashMap::new(),
+            view_count: 0,
+            usage: 0,
+            last_enforced: None,
+        }
+    }
+
+    /// size of the history of every symbol, in alphabetical order
+    pub async fn cache_stats(&self) -> Vec<CacheStats> {
+        let mut caches = vec![];
+        for (symbol, history) in self.cache.iter() {
+            let (books, trades) = history.sizes().await;
+            caches.push(CacheStats {
+                symbol: symbol.clone(),
+                books,
+                trades,
+                bytes: history.memory_usage().await,
+            });
+        }
+        caches.sort_by(|lhs, rhs| lhs.symbol.cmp(&rhs.symbol));
+        caches
+    }
+
+    /// mark a symbol as the most recently viewed
+    pub fn viewed(&mut self, symbol: &str) {
+        self.view_count += 1;
+        self.views.insert(symbol.to_string(), self.view_count);
+    }
+
+    /// downsample the older half of the histories, least recently viewed symbols first and ever
+    /// more coarsely, until they fit in the memory budget, returning the memory they use
+    pub async fn enforce_budget(&mut self) -> usize {
+        let mut usage = 0;
+        for history in self.cache.values() {
+            usage += history.memory_usage().await;
+        }
+        let Some(budget) = self.memory_budget else {
+            return usage;
+        };
+
+        let mut symbols = self.cache.keys().cloned().collect::<Vec<_>>();
+        symbols.sort_by_key(|symbol| self.views.get(symbol).cloned().unwrap_or_default());
+        let mut step = 2;
+        while usage > budget && step <= self.time_cache_window_seconds as i64 {
+            for symbol in symbols.iter() {
+                if usage <= budget {
+                    break;
+                }
+                let history = &self.cache[symbol];
+                let Some(latest) = history.latest_time().await else {
+                    continue;
+                };
+                let previous_usage = history.memory_usage().await;
+                history
+                    .thin(latest - (self.time_cache_window_seconds / 2) as i64, step)
+                    .await;
+                usage = usage - previous_usage + history.memory_usage().await;
+            }
+            step *= 2;
+        }
+        usage
+    }
+
+    /// enforce the memory budget at most once per budget period, books only growing the
+    /// histories slowly, returning the memory they used when it was last enforced
+    pub async fn throttled_budget(&mut self, now: Instant) -> usize {
+        if self
+            .last_enforced
+            .is_none_or(|last| now.duration_since(last) >= BUDGET_PERIOD)
+        {
+            self.last_enforced = Some(now);
+            self.usage = self.enforce_budget().await;
         }
+        self.usage
     }
 }
 
@@ -42,7 +253,7 @@ struct Dispatch {
     /// receiver end of action queue
     action_receiver: Receiver<Action>,
     /// sender end of action queue
-    action_sender: Sender<Action>,
+    action_sender: ActionSender,
     /// order book data feed
     feed: Feed,
     /// cache for the ticker state data
@@ -51,12 +262,93 @@ struct Dispatch {
     books: BooksCache,
     /// prototype pattern pipeline for copying into pipeline threads
     pipeline: Pipeline,
+    /// half width in basis points of the band used for pegged pairs, None to disable bands
+    band_half_width_in_bps: Option<f64>,
+    /// price resolution used for pegged pairs
+    band_price_resolution: usize,
+    /// fixed price bands of pegged pairs
+    price_bands: HashMap<String, PriceBand>,
+    /// pair of symbols monitored for arbitrage along with round trip fees in basis points
+    arbitrage: Option<((String, String), f64)>,
+    /// pair of symbols whose mid prices are combined into a basis series
+    basis: Option<((String, String), BasisOperation)>,
+    /// multiple of the median level size above which resting orders are considered walls
+    wall_multiple: f64,
+    /// liquidity wall detectors of every subscribed ticker
+    walls: HashMap<String, WallDetector>,
+    /// multiple of the recent mid beyond which updated levels are flagged as price spikes
+    spike_multiple: f64,
+    /// sanity checkers of the book updates of every subscribed ticker
+    checkers: HashMap<String, BookChecker>,
+    /// whether books found inconsistent are replaced by a freshly fetched snapshot
+    resync_on_anomaly: bool,
+    /// tickers whose fresh snapshot is being fetched, to avoid piling up resyncs
+    resyncing: HashSet<String>,
+    /// alert rules watched on the books received
+    alerts: AlertEngine,
+    /// whether triggered alerts are also shown as desktop notifications
+    desktop_notifications: bool,
+    /// outbound channels triggered alerts are forwarded to
+    alert_sinks: Vec<AlertSink>,
+    /// orders of the user resting in the books, when following them
+    own_orders: OwnOrders,
+    /// authenticated connection orders confirmed by the user are sent through, if enabled
+    trader: Option<Trader>,
+    /// time zone in which daily statistics roll over
+    rollover: Rollover,
+    /// daily statistics trackers of every subscribed ticker
+    daily: HashMap<String, DailyTracker>,
+    /// directory holding recordings listed in the library page
+    recordings_directory: PathBuf,
+    /// directory snapshots of the arrays on screen are exported to
+    exports_directory: PathBuf,
+    /// format of the screenshots taken with the screenshot key
+    screenshot_format: ScreenshotFormat,
+    /// writer of completed time slices of book histories, None to keep them in memory only
+    persister: Option<HistoryPersister>,
+    /// writer of the per second aggregates of the books, None to write nothing
+    timeseries: Option<TimeSeriesWriter>,
+    /// SQLite archive of every update received, None to keep them in memory only
+    storage: Option<Storage>,
+    /// directory or SQLite archive book histories are loaded from when subscribing, None to
+    /// start empty
+    history_directory: Option<PathBuf>,
+    /// tickers loaded from disk whose next update is a live snapshot replacing the stale book
+    resumed: HashSet<String>,
+    /// writer of the processed stream for other tools, None to emit nothing
+    emitter: Option<Emitter>,
+    /// websocket server rebroadcasting books and splatted arrays, None to serve nothing
+    broadcaster: Option<Broadcaster>,
+    /// publisher of the books, trades and tickers received to Kafka, None to publish nothing
+    #[cfg(feature = "kafka")]
+    kafka: Option<KafkaSink>,
+    /// scripts run after every pipeline run, None to run none
+    scripts: Option<Scripts>,
+    /// handling time metrics of every kind of action
+    metrics: ActionMetrics,
+    /// rate of the book, ticker and trade messages received from the feed
+    message_rate: MessageRate,
+    /// exchange time in milliseconds of the last book update received for every symbol
+    last_updates: HashMap<String, i64>,
+    /// pipeline run of every ticker still in flight, to skip requests piling up behind it
+    pipelines_in_flight: HashMap<String, JoinHandle<()>>,
+    /// tickers to subscribe once the feed is confirmed live, before processing any action
+    staged_subscriptions: Vec<String>,
+    /// recordings to open once the feed is confirmed live, after the staged tickers
+    staged_recordings: Vec<PathBuf>,
+    /// instant the session started at
+    started: Instant,
+    /// number of times the feed was connected again after its listener stopped
+    reconnects: usize,
+    /// instant of the last attempt at connecting the feed again, None before the first
+    last_reconnect: Option<Instant>,
     /// encapsulation structure for the user interface
     app: App,
 }
 
 impl Dispatch {
     /// constructor
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         buffer_size: usize,
         websocket_timeout_seconds: u64,
@@ -65,17 +357,29 @@ impl Dispatch {
         time_visual_window_seconds: u64,
         time_resolution: usize,
         price_resolution: usize,
-    ) -> Result<Dispatch, String> {
+        band_half_width_in_bps: Option<f64>,
+        band_price_resolution: usize,
+        kernel: Kernel,
+        attach: Option<&Path>,
+    ) -> Result<Dispatch, BookedError> {
         let (sender, receiver) = channel::<Action>(buffer_size);
+        // shared with the interface, so that pipeline requests are coalesced across both
+        let action_sender = ActionSender::new(sender.clone());
 
-        let feed = match Feed::new(websocket_timeout_seconds, book_depth, sender.clone()).await {
+        let feed = match attach {
+            Some(path) => {
+                Feed::attach(path, websocket_timeout_seconds, book_depth, sender.clone()).await
+            }
+            None => Feed::new(websocket_timeout_seconds, book_depth, sender.clone()).await,
+        };
+        let feed = match feed {
             Ok(feed) => feed,
             Err(message) => return Err(message),
         };
 
         Ok(Dispatch {
             action_receiver: receiver,
-            action_sender: sender.clone(),
+            action_sender: action_sender.clone(),
             feed,
             tickers: HashMap::new(),
             books: BooksCache::new(time_cache_window_seconds),
@@ -83,142 +387,3058 @@ impl Dispatch {
                 time_visual_window_seconds,
                 time_resolution,
                 price_resolution,
+                kernel,
             ),
-            app: App::new(sender.clone()).await,
+            band_half_width_in_bps,
+            band_price_resolution,
+            price_bands: HashMap::new(),
+            arbitrage: None,
+            basis: None,
+            wall_multiple: 10.0,
+            walls: HashMap::new(),
+            spike_multiple: 5.0,
+            checkers: HashMap::new(),
+            resync_on_anomaly: false,
+            resyncing: HashSet::new(),
+            alerts: AlertEngine::default(),
+            desktop_notifications: false,
+            alert_sinks: vec![],
+            own_orders: OwnOrders::default(),
+            trader: None,
+            rollover: Rollover::Utc,
+            daily: HashMap::new(),
+            recordings_directory: PathBuf::from("recordings"),
+            exports_directory: PathBuf::from("exports"),
+            screenshot_format: ScreenshotFormat::Svg,
+            persister: None,
+            timeseries: None,
+            storage: None,
+            history_directory: None,
+            resumed: HashSet::new(),
+            emitter: None,
+            broadcaster: None,
+            #[cfg(feature = "kafka")]
+            kafka: None,
+            scripts: None,
+            metrics: ActionMetrics::new(Duration::from_millis(50), 256),
+            message_rate: MessageRate::new(Duration::from_secs(5)),
+            last_updates: HashMap::new(),
+            pipelines_in_flight: HashMap::new(),
+            staged_subscriptions: vec![],
+            staged_recordings: vec![],
+            started: Instant::now(),
+            reconnects: 0,
+            last_reconnect: None,
+            app: App::new(action_sender).await,
         })
     }
 
     /// spawn a pipeline run in a separate thread with given book history and deposit into state
+    /// along with its timings and the series plotted by scripts, warning when it runs longer than
+    /// the display update period
     async fn spawn_pipeline(
+        ticker: String,
         history: BookHistory,
         pipeline: Pipeline,
         state: Arc<Mutex<State>>,
+        sender: ActionSender,
+        emitter: Option<Emitter>,
+        broadcaster: Option<Broadcaster>,
+        scripts: Option<Scripts>,
     ) -> JoinHandle<()> {
         spawn(async move {
-            let buffer = pipeline.run(&history).await;
-            let mut locked_state = state.lock().await;
-            locked_state.depth = Some(buffer.0);
-            locked_state.volumes = Some(buffer.1);
-            locked_state.blocks = Some(buffer.2);
-        })
-    }
-
-    /// run action queue dispatching
-    pub async fn run(&mut self) -> Result<(), String> {
-        while let Some(action) = self.action_receiver.recv().await {
-            match action {
-                Action::Inform(message) => (), // TODO: setup logs
-                Action::SubscribeTicker(ticker) => {
-                    self.tickers.insert(ticker.clone(), None);
-                    self.books.cache.insert(
-                        ticker.clone(),
-                        BookHistory::new(self.books.time_cache_window_seconds.clone()),
-                    );
-                    self.app.set_current_ticker(ticker.clone()).await;
-
-                    match self.feed.subscribe(ticker).await {
-                        Ok(()) => (),
-                        Err(message) => {
-                            match self.action_sender.send(Action::Warn(message)).await {
-                                Ok(_) => (),
-                                Err(message) => return Err(format!("{:?}", message)),
-                            }
-                        }
-                    }
+            let start = Instant::now();
+            let (splatted, mut timings) = pipeline.run(&history).await;
+            // slippage and queue are only estimated for the visible ticker
+            let (slippage_quantity, queue_price) = {
+                let locked_state = state.lock().await;
+                match locked_state.current_ticker == Some(ticker.clone()) {
+                    true => (locked_state.slippage_quantity, locked_state.queue_price),
+                    false => (None, None),
                 }
-                Action::RunPipeline(ticker) => match self.books.cache.get(&ticker) {
-                    Some(history) => {
-                        let cloned_history = history.extract_window(0, i64::MAX).await;
-                        Dispatch::spawn_pipeline(
-                            cloned_history,
-                            self.pipeline.clone(),
-                            self.app.get_state(),
-                        )
-                        .await;
+            };
+            let slippage = match slippage_quantity {
+                Some(quantity) => Some(pipeline.run_slippage(&history, quantity).await),
+                None => None,
+            };
+            let queue = match queue_price {
+                Some(price) => pipeline.run_queue(&history, price).await,
+                None => None,
+            };
+            if slippage_quantity.is_some() || queue_price.is_some() {
+                let splatting = timings
+                    .iter()
+                    .map(|timing| timing.elapsed)
+                    .sum::<Duration>();
+                let elapsed = start.elapsed().saturating_sub(splatting);
+                timings.push(StageTiming {
+                    name: "Slippage & Queue",
+                    elapsed,
+                });
+            }
+            let elapsed = start.elapsed();
+            if let Some(broadcaster) = broadcaster {
+                broadcaster.splatted(&ticker, &splatted);
+            }
+            if let Some(emitter) = emitter {
+                let summary = Summary::new(ticker.clone(), &history, &splatted, elapsed).await;
+                if let Err(message) = emitter.summary(&summary).await {
+                    let _ = sender.send(Action::Warn(message.to_string())).await;
+                }
+            }
+            let scripted = match scripts {
+                Some(scripts) => {
+                    let (output, errors) = scripts.run(&ticker, &history, &splatted).await;
+                    for error in errors {
+                        let _ = sender.send(Action::Warn(error.to_string())).await;
                     }
-                    None => (),
-                },
-                Action::UnsubscribeTicker(ticker) => {
-                    match self.feed.unsubscribe(ticker.clone()).await {
-                        Ok(()) => (),
-                        Err(message) => {
-                            match self.action_sender.send(Action::Warn(message)).await {
-                                Ok(_) => (),
-                                Err(message) => return Err(format!("{:?}", message)),
-                            }
-                        }
+                    for alert in output.alerts.iter() {
+                        let _ = sender.send(Action::Alert(alert.clone())).await;
                     }
-
-                    self.tickers.remove(&ticker);
-                    self.books.cache.remove(&ticker);
+                    Some((history.latest_time().await, output.points))
                 }
-                Action::Quit => break,
-                Action::UpdateBook(update) => {
-                    let symbol = update.symbol.clone();
-                    match self.books.cache.get_mut(&symbol) {
-                        Some(history) => {
-                            history.update(update).await?;
-                        }
-                        None => {
-                            return Err(format!(
-                                "Got book update for {} while symbol was absent from cache.",
-                                symbol
-                            ));
-                        }
-                    }
+                None => None,
+            };
+            let budget = {
+                let mut locked_state = state.lock().await;
+                if locked_state.current_ticker == Some(ticker.clone()) {
+                    locked_state.slippage = slippage;
+                    locked_state.queue = queue;
+                    locked_state.pipeline_timings = timings;
                 }
-                Action::UpdateTicker(update) => {
-                    let symbol = update.symbol.clone();
-                    match self.tickers.insert(symbol.clone(), Some(update.clone())) {
-                        Some(_) => (),
-                        None => {
-                            return Err(format!(
-                                "Got ticker update for {} while symbol was absent from cache.",
-                                symbol
-                            ));
-                        }
-                    }
+                let view = locked_state.views.entry(ticker.clone()).or_default();
+                view.splatted = splatted;
+                view.pipeline_elapsed = Some(elapsed);
+                if let Some((Some(time), points)) = scripted {
+                    view.indicators.push(time, &points);
+                }
+                locked_state.touch();
+                locked_state.cadence.pipeline
+            };
+
+            debug!(ticker, ?elapsed, "pipeline run");
+            if elapsed > budget {
+                let warning = format!(
+                    "Pipeline run of {} took {:?}, longer than the {:?} between display updates",
+                    ticker, elapsed, budget
+                );
+                let _ = sender.send(Action::Warn(warning)).await;
+            }
+        })
+    }
+
+    /// spawn the fetch of a one-shot snapshot in a separate thread and deposit its depth into
+    /// state, without caching the book
+    async fn spawn_preview(
+        ticker: String,
+        book_depth: i32,
+        pipeline: Pipeline,
+        state: Arc<Mutex<State>>,
+    ) -> JoinHandle<()> {
+        spawn(async move {
+            let preview = async {
+                let booked = fetch_snapshot(ticker.clone(), book_depth).await?;
+                let mut history = BookHistory::new(0);
+                history.update(booked).await?;
+                Ok::<_, BookedError>(pipeline.run_depth(&history).await)
+            }
+            .await;
+
+            let mut locked_state = state.lock().await;
+            match preview {
+                Ok(depth) => locked_state.preview = Some((ticker, depth)),
+                Err(message) => {
+                    locked_state.search.error =
+                        Some(format!("Could not preview {}: {}", ticker, message))
+                }
+            }
+            locked_state.touch();
+        })
+    }
+
+    /// spawn an arbitrage analysis in a separate thread, deposit into state and raise an alert
+    /// when the spread starts beating the fees
+    async fn spawn_arbitrage(
+        symbols: (String, String),
+        histories: (BookHistory, BookHistory),
+        fee_in_bps: f64,
+        pipeline: Pipeline,
+        state: Arc<Mutex<State>>,
+    ) -> JoinHandle<()> {
+        spawn(async move {
+            let arbitrage = pipeline
+                .run_arbitrage(symbols, (&histories.0, &histories.1), fee_in_bps)
+                .await;
+
+            let alert = if arbitrage.is_profitable() {
+                Some(format!(
+                    "Arbitrage between {} and {} at {:.1} bp beats {:.1} bp fees",
+                    arbitrage.symbols.0,
+                    arbitrage.symbols.1,
+                    arbitrage.latest_best_spread().unwrap_or(0.0),
+                    arbitrage.fee_in_bps
+                ))
+            } else {
+                None
+            };
 
-                    self.app.get_state().lock().await.ticker_data = Some(update);
+            let sender = {
+                let mut locked_state = state.lock().await;
+                let was_profitable = locked_state
+                    .arbitrage
+                    .as_ref()
+                    .is_some_and(|previous| previous.is_profitable());
+                locked_state.arbitrage = Some(arbitrage);
+                locked_state.touch();
+                if was_profitable {
+                    return;
                 }
-                Action::Warn(message) => (), // TODO: setup warnings
+                locked_state.sender.clone()
+            };
+
+            if let Some(message) = alert {
+                let _ = sender.send(Action::Alert(message)).await;
+            }
+        })
+    }
+
+    /// spawn a basis analysis in a separate thread and deposit into state
+    async fn spawn_basis(
+        symbols: (String, String),
+        histories: (BookHistory, BookHistory),
+        operation: BasisOperation,
+        pipeline: Pipeline,
+        state: Arc<Mutex<State>>,
+    ) -> JoinHandle<()> {
+        spawn(async move {
+            let basis = pipeline
+                .run_basis(symbols, (&histories.0, &histories.1), operation)
+                .await;
+
+            let mut locked_state = state.lock().await;
+            locked_state.basis = Some(basis);
+            locked_state.touch();
+        })
+    }
+
+    /// monitor a basis series between the mid prices of two subscribed symbols
+    pub fn set_basis(&mut self, symbols: (String, String), operation: BasisOperation) {
+        self.basis = Some((symbols, operation));
+    }
+
+    /// monitor executable arbitrage spreads between two subscribed symbols
+    pub fn set_arbitrage(&mut self, symbols: (String, String), fee_in_bps: f64) {
+        self.arbitrage = Some((symbols, fee_in_bps));
+    }
+
+    /// set the multiple of the median level size above which resting orders are considered walls
+    pub fn set_wall_multiple(&mut self, wall_multiple: f64) {
+        self.wall_multiple = wall_multiple;
+    }
+
+    /// set the multiple of the recent mid beyond which updated levels are flagged as price spikes
+    pub fn set_spike_multiple(&mut self, spike_multiple: f64) {
+        self.spike_multiple = spike_multiple;
+    }
+
+    /// watch an alert rule on the books received
+    pub fn add_alert(&mut self, rule: AlertRule) {
+        self.alerts.add(rule);
+    }
+
+    /// also show triggered alerts as desktop notifications
+    pub fn set_desktop_notifications(&mut self, desktop_notifications: bool) {
+        self.desktop_notifications = desktop_notifications;
+    }
+
+    /// forward triggered alerts to an outbound channel
+    pub fn add_alert_sink(&mut self, sink: AlertSink) {
+        self.alert_sinks.push(sink);
+    }
+
+    /// spawn the forwarding of a triggered alert to an outbound channel in a separate thread,
+    /// warning when it fails
+    fn spawn_alert_sink(sink: AlertSink, message: String, sender: ActionSender) -> JoinHandle<()> {
+        spawn(async move {
+            if let Err(warning) = sink.send(&message).await {
+                let warning = format!("Could not forward alert to {}: {}", sink.name(), warning);
+                let _ = sender.send(Action::Warn(warning)).await;
+            }
+        })
+    }
+
+    /// private utility method raising the alert of a triggered rule, also on the desktop and
+    /// the outbound channels when enabled
+    async fn trigger_alert(&self, message: String) -> Result<(), BookedError> {
+        for sink in &self.alert_sinks {
+            Dispatch::spawn_alert_sink(sink.clone(), message.clone(), self.action_sender.clone());
+        }
+        if self.desktop_notifications
+            && let Err(warning) = notify_desktop(&message)
+        {
+            let warning = format!("Could not show desktop notification: {}", warning);
+            if let Err(message) = self.action_sender.send_now(Action::Warn(warning)).await {
+                return Err(message.into());
             }
         }
+        match self.action_sender.send_now(Action::Alert(message)).await {
+            Ok(_) => Ok(()),
+            Err(message) => Err(message.into()),
+        }
+    }
+
+    /// follow the orders of the user through the authenticated executions channel
+    pub async fn follow_own_orders(&self) -> Result<(), BookedError> {
+        connect_own_orders(self.action_sender.sender()).await
+    }
+
+    /// show the balances and open orders of the user, polled regularly from the REST API
+    pub async fn follow_account(&self) -> Result<(), BookedError> {
+        let account = fetch_account().await?;
+        self.action_sender
+            .send(Action::UpdateAccount(account))
+            .await?;
+        spawn(Dispatch::poll_account(self.action_sender.clone()));
         Ok(())
     }
 
-    /// get action queue sender copy
-    pub fn sender(&self) -> Sender<Action> {
-        self.action_sender.clone()
+    /// enable order entry through the authenticated websocket, orders only being validated by the
+    /// exchange in dry run
+    pub async fn enable_trading(&mut self, dry_run: bool) -> Result<(), BookedError> {
+        let trader = Trader::connect(dry_run, self.action_sender.sender()).await?;
+        self.app.set_trading(trader.is_dry_run()).await;
+        self.trader = Some(trader);
+        Ok(())
     }
-}
 
-/// Visualizer of Kraken order books
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    /// ticker symbol to visualize
-    #[arg(required = true)]
-    ticker: String,
-}
+    /// replace books found inconsistent by a freshly fetched snapshot
+    pub fn set_resync_on_anomaly(&mut self, resync_on_anomaly: bool) {
+        self.resync_on_anomaly = resync_on_anomaly;
+    }
 
-#[tokio::main]
-async fn main() -> Result<(), String> {
-    let args = Args::parse();
+    /// downsample older books once the histories hold more than a number of bytes
+    pub fn set_memory_budget(&mut self, memory_budget: usize) {
+        self.books.memory_budget = Some(memory_budget);
+    }
 
-    let mut dispatch = match Dispatch::new(1000, 200, 100, 5 * 60, 3 * 60, 370, 200).await {
-        Ok(dispatch) => dispatch,
-        Err(message) => return Err(message),
-    };
+    /// set the time zone in which daily statistics roll over
+    pub fn set_rollover(&mut self, rollover: Rollover) {
+        self.rollover = rollover;
+    }
 
-    let sender = dispatch.sender();
+    /// set the directory holding recordings listed in the library page
+    pub fn set_recordings_directory(&mut self, recordings_directory: PathBuf) {
+        self.recordings_directory = recordings_directory;
+    }
 
-    let running = dispatch.run();
+    /// flush book histories to parquet files in a directory, one file per time slice
+    pub fn set_persistence(&mut self, directory: PathBuf, slice_in_seconds: i64) {
+        self.persister = Some(HistoryPersister::new(directory, slice_in_seconds));
+    }
 
-    match sender.send(Action::SubscribeTicker(args.ticker)).await {
-        Ok(_) => (),
-        Err(message) => return Err(format!("{:?}", message)),
-    };
+    /// write the aggregates of the latest books to a time series database every second
+    pub fn set_timeseries(&mut self, writer: TimeSeriesWriter) {
+        self.timeseries = Some(writer);
+    }
+
+    /// record every book update, ticker and trade received into a SQLite archive
+    pub fn set_storage(&mut self, path: &Path) -> Result<(), BookedError> {
+        self.storage = Some(Storage::open(path)?);
+        Ok(())
+    }
+
+    /// load book histories persisted in a directory when subscribing to their symbol
+    pub fn set_history_directory(&mut self, history_directory: PathBuf) {
+        self.history_directory = Some(history_directory);
+    }
+
+    /// load the persisted books of a ticker within the cache window into its history
+    async fn resume_history(&mut self, ticker: &str) -> Result<(), BookedError> {
+        let Some(directory) = &self.history_directory else {
+            return Ok(());
+        };
+        let since = clock::now().timestamp() - self.books.time_cache_window_seconds as i64;
+        let (snapshots, trades) = if directory.is_file() {
+            let storage = Storage::open(directory)?;
+            (
+                storage.books(ticker, since)?,
+                storage.trades(ticker, since)?,
+            )
+        } else {
+            (load_history(directory, ticker, since)?, vec![])
+        };
+        let Some(history) = self.books.cache.get_mut(ticker) else {
+            return Ok(());
+        };
+        history.update_trades(trades).await?;
+
+        let mut latest = None;
+        for booked in snapshots {
+            history.replace(booked).await?;
+            latest = Some(history.get_latest_book().await.0.0);
+        }
+        if let Some(time) = latest {
+            self.resumed.insert(ticker.to_string());
+            if let Some(persister) = &mut self.persister {
+                persister.resume_after(ticker, time);
+            }
+        }
+        Ok(())
+    }
+
+    /// spawn the fetch of a fresh snapshot of a book found inconsistent in a separate thread,
+    /// sending it back to replace the cached book
+    fn spawn_resync(ticker: String, book_depth: i32, sender: ActionSender) -> JoinHandle<()> {
+        spawn(async move {
+            let action = match fetch_snapshot(ticker.clone(), book_depth).await {
+                Ok(booked) => Action::ResyncBook(booked),
+                Err(message) => {
+                    Action::Warn(format!("Could not resynchronize {}: {}", ticker, message))
+                }
+            };
+            let _ = sender.send(action).await;
+        })
+    }
+
+    /// spawn the fetch of the number of decimals the prices of a ticker are quoted with in a
+    /// separate thread, sending it back to format the labels of the ticker
+    fn spawn_price_decimals(ticker: String, sender: ActionSender) -> JoinHandle<()> {
+        spawn(async move {
+            let action = match fetch_price_decimals(ticker.clone()).await {
+                Ok(decimals) => Action::UpdatePriceDecimals(ticker, decimals),
+                Err(message) => Action::Warn(format!(
+                    "Could not fetch the price decimals of {}: {}",
+                    ticker, message
+                )),
+            };
+            let _ = sender.send(action).await;
+        })
+    }
+
+    /// request the persistence of book histories regularly
+    async fn schedule_persistence(
+        sender: ActionSender,
+        period: Duration,
+    ) -> Result<(), BookedError> {
+        let mut timer = interval(period);
+        loop {
+            timer.tick().await;
+            match sender.send(Action::PersistHistory).await {
+                Ok(()) => (),
+                Err(message) => return Err(message.into()),
+            }
+        }
+    }
+
+    /// request the writing of the aggregates of the latest books regularly
+    async fn schedule_timeseries(sender: ActionSender) -> Result<(), BookedError> {
+        let mut timer = interval(TIMESERIES_PERIOD);
+        loop {
+            timer.tick().await;
+            match sender.send(Action::WriteTimeSeries).await {
+                Ok(()) => (),
+                Err(message) => return Err(message.into()),
+            }
+        }
+    }
+
+    /// request the supervision of the interface loops and the feed listener regularly
+    async fn schedule_supervision(sender: ActionSender) -> Result<(), BookedError> {
+        let mut timer = interval(SUPERVISION_PERIOD);
+        loop {
+            timer.tick().await;
+            match sender.send(Action::Supervise).await {
+                Ok(()) => (),
+                Err(message) => return Err(message.into()),
+            }
+        }
+    }
+
+    /// poll the balances and open orders of the user regularly, warning about failed polls
+    async fn poll_account(sender: ActionSender) -> Result<(), BookedError> {
+        let mut timer = interval(ACCOUNT_PERIOD);
+        // the first tick completes immediately, right after the account was first fetched
+        timer.tick().await;
+        loop {
+            timer.tick().await;
+            let action = match fetch_account().await {
+                Ok(account) => Action::UpdateAccount(account),
+                Err(message) => Action::Warn(format!("Could not poll the account: {}", message)),
+            };
+            match sender.send(action).await {
+                Ok(()) => (),
+                Err(message) => return Err(message.into()),
+            }
+        }
+    }
+
+    /// write slices in a blocking thread, warning about the ones that could not be written
+    fn spawn_persistence(
+        directory: PathBuf,
+        slices: Vec<Slice>,
+        sender: Sender<Action>,
+    ) -> JoinHandle<()> {
+        spawn_blocking(move || {
+            for slice in slices.iter() {
+                if let Err(message) = write_slice(&directory, slice) {
+                    let _ = sender.blocking_send(Action::Warn(format!(
+                        "Could not persist {} from {}: {}",
+                        slice.symbol, slice.start, message
+                    )));
+                }
+            }
+        })
+    }
+
+    /// emit the books, tickers and pipeline summaries received for other tools
+    pub fn set_emitter(&mut self, emitter: Emitter) {
+        self.emitter = Some(emitter);
+    }
+
+    /// run scripts after every pipeline run, plotting their series and raising their alerts
+    pub fn set_scripts(&mut self, scripts: Scripts) {
+        self.scripts = Some(scripts);
+    }
+
+    /// rebroadcast the books received and the arrays splatted to websocket clients
+    pub fn set_broadcaster(&mut self, broadcaster: Broadcaster) {
+        self.broadcaster = Some(broadcaster);
+    }
+
+    /// publish the books, trades and tickers received to Kafka topics
+    #[cfg(feature = "kafka")]
+    pub fn set_kafka(&mut self, kafka: KafkaSink) {
+        self.kafka = Some(kafka);
+    }
+
+    /// serve the REST API at an address, returning the address listened at
+    pub async fn serve_api(&self, address: SocketAddr) -> Result<SocketAddr, BookedError> {
+        api::serve(address, self.action_sender.sender()).await
+    }
+
+    /// set the directory snapshots of the arrays on screen are exported to
+    pub fn set_exports_directory(&mut self, exports_directory: PathBuf) {
+        self.exports_directory = exports_directory;
+    }
+
+    /// set the format of the screenshots taken with the screenshot key
+    pub fn set_screenshot_format(&mut self, format: ScreenshotFormat) {
+        self.screenshot_format = format;
+    }
+
+    /// space the price axis of every view linearly or logarithmically
+    pub fn set_price_scale(&mut self, price_scale: PriceScale) {
+        self.pipeline.set_price_scale(price_scale);
+    }
+
+    /// fit the price range of every view to the whole book, a percentile of its volume or a
+    /// distance around mid
+    pub fn set_price_range(&mut self, price_range: PriceRange) {
+        self.pipeline.set_price_range(price_range);
+    }
+
+    /// fade older book snapshots in the order map with an exponential decay of given half life
+    pub fn set_decay_half_life(&mut self, half_life_in_seconds: Option<f64>) {
+        self.pipeline.set_decay_half_life(half_life_in_seconds);
+    }
+
+    /// set the number of levels per side listed by the ladder
+    pub fn set_ladder_levels(&mut self, levels: usize) {
+        self.pipeline.set_ladder_levels(levels);
+    }
+
+    /// set the time above which handling an action raises a warning
+    pub fn set_handler_budget(&mut self, budget: Duration) {
+        self.metrics.budget = budget;
+    }
+
+    /// capture mouse events in the interface, or leave them to the terminal
+    pub async fn set_mouse_capture(&mut self, mouse_capture: bool) {
+        self.app.set_mouse_capture(mouse_capture).await;
+    }
 
-    running.await
+    /// draw the interface with the colors of a theme
+    pub async fn set_theme(&mut self, theme: Theme) {
+        self.app.set_theme(theme).await;
+    }
+
+    /// arrange the ticker page panels, keeping changes in a configuration file
+    pub async fn set_layout(&mut self, layout: PanelLayout, layout_file: Option<PathBuf>) {
+        self.app.set_layout(layout, layout_file).await;
+    }
+
+    /// color the order map volumes along a gradient
+    pub async fn set_gradient(&mut self, gradient: Gradient) {
+        self.app.set_gradient(gradient).await;
+    }
+
+    /// draw with the colors a terminal displays
+    pub async fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.app.set_color_depth(color_depth).await;
+    }
+
+    /// refresh the interface at a cadence
+    pub async fn set_cadence(&mut self, cadence: Cadence) {
+        self.app.set_cadence(cadence).await;
+    }
+
+    /// bind keys in place of default ones, given as pairs of a default key and its replacement
+    pub async fn set_key_bindings(
+        &mut self,
+        bindings: Vec<(char, char)>,
+    ) -> Result<(), BookedError> {
+        self.app.set_key_bindings(bindings).await
+    }
+
+    /// label time axes with seconds before the latest book or with the wall clock
+    pub async fn set_time_labels(&mut self, time_labels: TimeLabels) {
+        self.app.set_time_labels(time_labels).await;
+    }
+
+    /// draw the order map cells and chart bars with a marker
+    pub async fn set_marker(&mut self, marker: PlotMarker) {
+        self.app.set_marker(marker).await;
+    }
+
+    /// load a recording into the books cache under its own key and visualize it
+    async fn open_recording(&mut self, path: PathBuf) -> Result<(), BookedError> {
+        let info = inspect_recording(&path)?;
+        let mut history = BookHistory::new(self.books.time_cache_window_seconds);
+        for booked in load_recording(&path, &info.symbol)? {
+            history.update(booked).await?;
+        }
+
+        let key = format!(
+            "{} [{}]",
+            info.symbol,
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        );
+        self.books.cache.insert(key.clone(), history);
+
+        if let Some(band) = self.band_half_width_in_bps.and_then(|half_width| {
+            PriceBand::detect(&info.symbol, half_width, self.band_price_resolution)
+        }) {
+            self.price_bands.insert(key.clone(), band);
+        }
+
+        self.app.set_current_ticker(key).await;
+        Ok(())
+    }
+
+    /// stage a ticker to be subscribed exactly once when the action loop starts
+    pub fn stage_subscription(&mut self, ticker: String) {
+        if !self.staged_subscriptions.contains(&ticker) {
+            self.staged_subscriptions.push(ticker);
+        }
+    }
+
+    /// stage a recording to be opened when the action loop starts
+    pub fn stage_recording(&mut self, path: PathBuf) {
+        self.staged_recordings.push(path);
+    }
+
+    /// subscribe a ticker to the feed and set up its caches, ignoring already subscribed tickers
+    async fn subscribe(&mut self, ticker: String) -> Result<(), BookedError> {
+        if self.tickers.contains_key(&ticker) {
+            return match self
+                .action_sender
+                .send_now(Action::Warn(format!("{} is already subscribed", ticker)))
+                .await
+            {
+                Ok(_) => Ok(()),
+                Err(message) => Err(message.into()),
+            };
+        }
+
+        self.tickers.insert(ticker.clone(), None);
+        self.app
+            .set_tickers(self.tickers.keys().cloned().collect())
+            .await;
+        self.books.cache.insert(
+            ticker.clone(),
+            BookHistory::new(self.books.time_cache_window_seconds.clone()),
+        );
+        self.walls.insert(
+            ticker.clone(),
+            WallDetector::new(
+                self.wall_multiple,
+                self.books.time_cache_window_seconds as i64,
+            ),
+        );
+        self.checkers
+            .insert(ticker.clone(), BookChecker::new(self.spike_multiple));
+        self.daily
+            .insert(ticker.clone(), DailyTracker::new(self.rollover));
+        self.app.set_current_ticker(ticker.clone()).await;
+
+        if let Err(message) = self.resume_history(&ticker).await {
+            let warning = format!("Could not load the history of {}: {}", ticker, message);
+            if let Err(message) = self.action_sender.send_now(Action::Warn(warning)).await {
+                return Err(message.into());
+            }
+        }
+
+        if let Some(band) = self.band_half_width_in_bps.and_then(|half_width| {
+            PriceBand::detect(&ticker, half_width, self.band_price_resolution)
+        }) {
+            self.price_bands.insert(ticker.clone(), band);
+        }
+
+        Dispatch::spawn_price_decimals(ticker.clone(), self.action_sender.clone());
+        match self.feed.subscribe(ticker).await {
+            Ok(()) => Ok(()),
+            Err(message) => match self
+                .action_sender
+                .send_now(Action::Warn(message.to_string()))
+                .await
+            {
+                Ok(_) => Ok(()),
+                Err(message) => Err(message.into()),
+            },
+        }
+    }
+
+    /// private utility method subscribing every ticker to the feed again, the fresh snapshots
+    /// replacing the books kept so far
+    async fn resubscribe(&mut self) -> Result<(), BookedError> {
+        for ticker in self.tickers.keys().cloned().collect::<Vec<_>>() {
+            if let Err(message) = self.feed.subscribe(ticker.clone()).await
+                && let Err(message) = self
+                    .action_sender
+                    .send_now(Action::Warn(message.to_string()))
+                    .await
+            {
+                return Err(message.into());
+            }
+            // daemons replay the histories of the tickers requested again
+            if !self.feed.is_attached() && self.resyncing.insert(ticker.clone()) {
+                Dispatch::spawn_resync(ticker, self.feed.depth(), self.action_sender.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// private utility method forgetting the pipeline runs that finished, warning about those that
+    /// panicked, their symbol being run again on its next request
+    async fn supervise_pipelines(&mut self) -> Result<(), BookedError> {
+        let finished: Vec<String> = self
+            .pipelines_in_flight
+            .iter()
+            .filter(|(_, handle)| handle.is_finished())
+            .map(|(ticker, _)| ticker.clone())
+            .collect();
+        for ticker in finished {
+            if let Some(handle) = self.pipelines_in_flight.remove(&ticker)
+                && let Err(error) = handle.await
+                && error.is_panic()
+            {
+                let warning = format!("Pipeline run of {} stopped ({})", ticker, error);
+                if let Err(message) = self.action_sender.send_now(Action::Warn(warning)).await {
+                    return Err(message.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// private utility method connecting the feed again once its listener stopped, at most once
+    /// per reconnection period
+    async fn reconnect(&mut self) -> Result<(), BookedError> {
+        if self.feed.is_live()
+            || self
+                .last_reconnect
+                .is_some_and(|last| last.elapsed() < RECONNECT_PERIOD)
+        {
+            return Ok(());
+        }
+        self.last_reconnect = Some(Instant::now());
+        let action = match self.feed.reconnect().await {
+            Ok(()) => {
+                self.reconnects += 1;
+                self.resubscribe().await?;
+                Action::Inform("Feed reconnected".to_string())
+            }
+            Err(message) => Action::Warn(format!("Could not reconnect the feed: {}", message)),
+        };
+        match self.action_sender.send_now(action).await {
+            Ok(_) => Ok(()),
+            Err(message) => Err(message.into()),
+        }
+    }
+
+    /// run action queue dispatching, leaving the terminal as found before returning a fatal error
+    pub async fn run(&mut self) -> Result<(), BookedError> {
+        let result = self.dispatch().await;
+        if let Err(message) = &result {
+            error!(%message, "dispatch stopped");
+            self.app.shutdown().await;
+        }
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &self.kafka
+            && let Err(message) = kafka.flush()
+        {
+            warn!(%message, "could not flush kafka");
+        }
+        result
+    }
+
+    /// private utility method dispatching the action queue, subscribing staged tickers once the
+    /// feed is confirmed live
+    async fn dispatch(&mut self) -> Result<(), BookedError> {
+        if !self.feed.is_live() {
+            return Err(BookedError::NotLive);
+        }
+        info!(tickers = ?self.staged_subscriptions, "dispatch started");
+
+        for ticker in std::mem::take(&mut self.staged_subscriptions) {
+            self.subscribe(ticker).await?;
+        }
+        for path in std::mem::take(&mut self.staged_recordings) {
+            self.open_recording(path).await?;
+        }
+
+        spawn(Dispatch::schedule_supervision(self.action_sender.clone()));
+        if self.timeseries.is_some() {
+            spawn(Dispatch::schedule_timeseries(self.action_sender.clone()));
+        }
+        if let Some(persister) = &self.persister {
+            spawn(Dispatch::schedule_persistence(
+                self.action_sender.clone(),
+                Duration::from_secs(persister.slice_in_seconds as u64),
+            ));
+        }
+
+        while let Some(action) = self.action_receiver.recv().await {
+            self.action_sender.dequeued(&action).await;
+            let name = action.name();
+            let start = Instant::now();
+            if let Action::UpdateBook(_) | Action::UpdateTicker(_) | Action::UpdateTrades(_) =
+                action
+            {
+                self.message_rate.record(start);
+            }
+            // books and trades only reach the interface through the pipeline runs, and queries
+            // leave it untouched
+            let displayed = !matches!(
+                action,
+                Action::UpdateBook(_)
+                    | Action::ReplayBook(_)
+                    | Action::UpdateTrades(_)
+                    | Action::Query(_, _)
+                    | Action::Supervise
+            );
+            match action {
+                Action::Alert(message) => {
+                    info!(alert = %message);
+                    let state = self.app.get_state();
+                    let mut locked_state = state.lock().await;
+                    locked_state.alert = Some(message);
+                    locked_state.alert_time = Some(clock::now().timestamp());
+                }
+                Action::AlertRule(rule) => {
+                    let message = match rule {
+                        Some(rule) => {
+                            let message = format!("Watching alert on {}", rule.describe());
+                            self.alerts.add(rule);
+                            message
+                        }
+                        None => {
+                            self.alerts.clear();
+                            "Stopped watching every alert".to_string()
+                        }
+                    };
+                    if let Err(message) = self.action_sender.send_now(Action::Inform(message)).await
+                    {
+                        return Err(message.into());
+                    }
+                }
+                Action::Error(message) => {
+                    error!("{}", message);
+                    let time = clock::now().timestamp();
+                    let state = self.app.get_state();
+                    let mut locked_state = state.lock().await;
+                    locked_state
+                        .logs
+                        .push(time, Severity::Error, message.clone());
+                    locked_state.error = Some(message);
+                }
+                Action::Inform(message) => {
+                    info!("{}", message);
+                    let time = clock::now().timestamp();
+                    let state = self.app.get_state();
+                    state.lock().await.logs.push(time, Severity::Info, message);
+                }
+                Action::OpenRecording(path) => {
+                    if let Err(message) = self.open_recording(path.clone()).await {
+                        let alert = format!("Could not open {}: {}", path.display(), message);
+                        match self.action_sender.send_now(Action::Alert(alert)).await {
+                            Ok(_) => (),
+                            Err(message) => return Err(message.into()),
+                        }
+                    }
+                }
+                Action::WriteTimeSeries => {
+                    // recordings opened share the cache but are no live books
+                    let tickers = &self.tickers;
+                    if let Some(writer) = &mut self.timeseries
+                        && let Err(message) = writer
+                            .push(
+                                self.books
+                                    .cache
+                                    .iter()
+                                    .filter(|(symbol, _)| tickers.contains_key(*symbol)),
+                            )
+                            .await
+                    {
+                        warn!(%message, "could not queue aggregates");
+                    }
+                }
+                Action::PersistHistory => {
+                    if let Some(persister) = &mut self.persister {
+                        let mut slices = Vec::new();
+                        for (symbol, history) in self.books.cache.iter() {
+                            slices.extend(persister.completed_slices(symbol, history).await);
+                        }
+                        Dispatch::spawn_persistence(
+                            persister.directory.clone(),
+                            slices,
+                            self.action_sender.sender(),
+                        );
+                    }
+                }
+                Action::PreviewTicker(ticker) => {
+                    let mut pipeline = self.pipeline.clone();
+                    pipeline.set_price_band(self.band_half_width_in_bps.and_then(|half_width| {
+                        PriceBand::detect(&ticker, half_width, self.band_price_resolution)
+                    }));
+                    Dispatch::spawn_preview(
+                        ticker,
+                        self.feed.depth(),
+                        pipeline,
+                        self.app.get_state(),
+                    )
+                    .await;
+                }
+                Action::Export(format) => {
+                    let exported = {
+                        let state = self.app.get_state();
+                        let locked_state = state.lock().await;
+                        let splatted = match &locked_state.current_ticker {
+                            Some(symbol) => locked_state
+                                .views
+                                .get(symbol)
+                                .map(|view| view.splatted.as_slice())
+                                .unwrap_or_default(),
+                            None => &[],
+                        };
+                        let blocks = splatted.iter().find_map(|splatted| {
+                            if let Splatted::Blocks(blocks) = splatted {
+                                Some(blocks)
+                            } else {
+                                None
+                            }
+                        });
+                        let depth = splatted.iter().find_map(|splatted| {
+                            if let Splatted::Depth(depth) = splatted {
+                                Some(depth)
+                            } else {
+                                None
+                            }
+                        });
+                        let volumes = splatted.iter().find_map(|splatted| {
+                            if let Splatted::Volumes(volumes) = splatted {
+                                Some(volumes)
+                            } else {
+                                None
+                            }
+                        });
+                        match (&locked_state.current_ticker, blocks, depth, volumes) {
+                            (Some(symbol), Some(blocks), Some(depth), Some(volumes)) => {
+                                export_snapshot(
+                                    &self.exports_directory,
+                                    symbol,
+                                    clock::now().timestamp(),
+                                    format,
+                                    blocks,
+                                    depth,
+                                    volumes,
+                                )
+                                .map_err(|error| error.to_string())
+                            }
+                            _ => Err("Nothing on screen to export yet".to_string()),
+                        }
+                    };
+                    let action = match exported {
+                        Ok(folder) => Action::Inform(format!("Exported to {}", folder.display())),
+                        Err(message) => Action::Warn(format!("Could not export: {}", message)),
+                    };
+                    match self.action_sender.send_now(action).await {
+                        Ok(_) => (),
+                        Err(message) => return Err(message.into()),
+                    }
+                }
+                Action::ExportHistory => {
+                    let symbol = self.app.get_state().lock().await.current_ticker.clone();
+                    let exported = match symbol.and_then(|symbol| {
+                        self.books
+                            .cache
+                            .get(&symbol)
+                            .map(|history| (symbol, history))
+                    }) {
+                        Some((symbol, history)) => {
+                            let path = self.exports_directory.join(format!(
+                                "{}-{}.arrow",
+                                symbol.replace('/', "-"),
+                                clock::now().timestamp()
+                            ));
+                            let slices = HistoryPersister::new(
+                                self.exports_directory.clone(),
+                                ARROW_BATCH_SECONDS,
+                            )
+                            .remaining_slices(&symbol, history)
+                            .await;
+                            write_arrow(&path, &symbol, &slices)
+                                .map(|_| path)
+                                .map_err(|error| error.to_string())
+                        }
+                        None => Err("No history to export yet".to_string()),
+                    };
+                    let action = match exported {
+                        Ok(path) => Action::Inform(format!("Exported to {}", path.display())),
+                        Err(message) => Action::Warn(format!("Could not export: {}", message)),
+                    };
+                    match self.action_sender.send_now(action).await {
+                        Ok(_) => (),
+                        Err(message) => return Err(message.into()),
+                    }
+                }
+                Action::Screenshot(format) => {
+                    let format = format.unwrap_or(self.screenshot_format);
+                    let symbol = self.app.get_state().lock().await.current_ticker.clone();
+                    let written = self.app.screenshot().await.and_then(|frame| {
+                        write_screenshot(
+                            &self.exports_directory,
+                            symbol.as_deref().unwrap_or("bookedblocks"),
+                            clock::now().timestamp(),
+                            format,
+                            &frame,
+                        )
+                    });
+                    let action = match written {
+                        Ok(path) => {
+                            Action::Inform(format!("Screenshot saved to {}", path.display()))
+                        }
+                        Err(message) => Action::Warn(format!("Could not screenshot: {}", message)),
+                    };
+                    match self.action_sender.send_now(action).await {
+                        Ok(_) => (),
+                        Err(message) => return Err(message.into()),
+                    }
+                }
+                Action::ScanLibrary => {
+                    let recordings = match scan_recordings(&self.recordings_directory) {
+                        Ok(recordings) => recordings,
+                        Err(message) => {
+                            match self
+                                .action_sender
+                                .send_now(Action::Warn(message.to_string()))
+                                .await
+                            {
+                                Ok(_) => (),
+                                Err(message) => return Err(message.into()),
+                            }
+                            vec![]
+                        }
+                    };
+                    let state = self.app.get_state();
+                    let mut locked_state = state.lock().await;
+                    locked_state.library = recordings;
+                    locked_state.library_selection = 0;
+                    locked_state.page = Page::Library;
+                }
+                Action::SetBookDepth(depth) => {
+                    let tickers = self.tickers.keys().cloned().collect::<Vec<_>>();
+                    for ticker in &tickers {
+                        if let Err(message) = self.feed.unsubscribe(ticker.clone()).await
+                            && let Err(message) = self
+                                .action_sender
+                                .send_now(Action::Warn(message.to_string()))
+                                .await
+                        {
+                            return Err(message.into());
+                        }
+                    }
+                    self.feed.set_depth(depth);
+                    self.resubscribe().await?;
+                    let message = format!("Books now requested {} levels deep", depth);
+                    if let Err(message) = self.action_sender.send_now(Action::Inform(message)).await
+                    {
+                        return Err(message.into());
+                    }
+                }
+                Action::SetTimeWindow(seconds) => {
+                    let cached = self.books.time_cache_window_seconds as u64;
+                    let action = if seconds > cached {
+                        Action::Warn(format!(
+                            "Cannot show {}s back, only the last {}s of books are cached",
+                            seconds, cached
+                        ))
+                    } else {
+                        self.pipeline.set_time_window(seconds);
+                        Action::Inform(format!("Showing the last {}s of books", seconds))
+                    };
+                    if let Err(message) = self.action_sender.send_now(action).await {
+                        return Err(message.into());
+                    }
+                }
+                Action::UpdateAccount(account) => {
+                    self.app.get_state().lock().await.account = Some(account);
+                }
+                Action::SubmitOrder(ticket) => {
+                    let action = match &mut self.trader {
+                        Some(trader) => match trader.submit(&ticket).await {
+                            Ok(message) => Action::Inform(message),
+                            Err(message) => Action::Warn(format!(
+                                "Could not send '{}': {}",
+                                ticket.describe(),
+                                message
+                            )),
+                        },
+                        None => Action::Warn("Order entry is not enabled".to_string()),
+                    };
+                    if let Err(message) = self.action_sender.send_now(action).await {
+                        return Err(message.into());
+                    }
+                }
+                Action::SubscribeTicker(ticker) => self.subscribe(ticker).await?,
+                Action::Supervise => {
+                    if let Some(reason) = self.app.supervise().await?
+                        && let Err(message) =
+                            self.action_sender.send_now(Action::Warn(reason)).await
+                    {
+                        return Err(message.into());
+                    }
+                    self.supervise_pipelines().await?;
+                    self.reconnect().await?;
+                }
+                Action::RunPipeline(ticker) => {
+                    self.reconnect().await?;
+                    for (name, count) in self.action_sender.take_shed().await {
+                        self.metrics.record_drops(name, count);
+                    }
+                    if self.app.get_state().lock().await.current_ticker == Some(ticker.clone()) {
+                        self.books.viewed(&ticker);
+                    }
+                    let cache_memory = (self.books.usage, self.books.memory_budget);
+                    match self.books.cache.get(&ticker) {
+                        Some(_)
+                            if self
+                                .pipelines_in_flight
+                                .get(&ticker)
+                                .is_some_and(|handle| !handle.is_finished()) =>
+                        {
+                            self.metrics.record_drop(name);
+                        }
+                        Some(history) => {
+                            // the diagnostics walk every history, only once a run is due
+                            let diagnostics = Diagnostics {
+                                uptime: self.started.elapsed(),
+                                queued: self.action_sender.max_capacity()
+                                    - self.action_sender.capacity(),
+                                queue_capacity: self.action_sender.max_capacity(),
+                                reconnects: self.reconnects,
+                                caches: self.books.cache_stats().await,
+                            };
+                            let cloned_history = history.extract_window(0, i64::MAX).await;
+                            let mut pipeline = self.pipeline.clone();
+                            pipeline.set_price_band(self.price_bands.get(&ticker).cloned());
+                            let (partner, time_pan) = {
+                                let state = self.app.get_state();
+                                let mut locked_state = state.lock().await;
+                                if let Some((time_values, price_values)) =
+                                    locked_state.map_resolution
+                                {
+                                    pipeline.set_resolution(time_values, price_values);
+                                }
+                                let view = locked_state.views.entry(ticker.clone()).or_default();
+                                view.walls =
+                                    self.walls.get(&ticker).map(|detector| detector.walls());
+                                view.daily =
+                                    self.daily.get(&ticker).and_then(|tracker| tracker.stats());
+                                locked_state.metrics = self.metrics.stats();
+                                locked_state.cache_memory = Some(cache_memory);
+                                locked_state.diagnostics = diagnostics;
+                                locked_state.connected = Some(self.feed.is_live());
+                                locked_state.message_rate = self.message_rate.rate(Instant::now());
+                                locked_state.last_updates = self.last_updates.clone();
+                                let partner = locked_state.split_partner(&ticker);
+                                // zoom and pan only apply to the symbols on screen, keeping the
+                                // pan within the cached history
+                                let mut time_pan = 0;
+                                if partner.is_some()
+                                    || locked_state.current_ticker.as_ref() == Some(&ticker)
+                                {
+                                    let window =
+                                        (pipeline.time_window() >> locked_state.time_zoom).max(1);
+                                    let cached = history.time_window_in_seconds as i64;
+                                    locked_state.time_pan =
+                                        locked_state.time_pan.min((cached - window as i64).max(0));
+                                    pipeline.set_time_window(window);
+                                    locked_state.time_window = Some(window);
+                                    locked_state.time_cached = Some(cached);
+                                    time_pan = locked_state.time_pan;
+                                }
+                                (partner, time_pan)
+                            };
+                            // symbols displayed side by side share the end of their time axes
+                            let mut time_end = None;
+                            if let Some(other_history) =
+                                partner.and_then(|partner| self.books.cache.get(&partner))
+                            {
+                                let ends = [
+                                    history.latest_time().await,
+                                    other_history.latest_time().await,
+                                ];
+                                time_end = ends.into_iter().flatten().max();
+                            } else if time_pan > 0 {
+                                time_end = history.latest_time().await;
+                            }
+                            pipeline.set_time_end(time_end.map(|end| end - time_pan));
+
+                            if let Some(((first, second), fee_in_bps)) = &self.arbitrage
+                                && *first == ticker
+                                && let Some(other_history) = self.books.cache.get(second)
+                            {
+                                Dispatch::spawn_arbitrage(
+                                    (first.clone(), second.clone()),
+                                    (
+                                        history.extract_window(0, i64::MAX).await,
+                                        other_history.extract_window(0, i64::MAX).await,
+                                    ),
+                                    *fee_in_bps,
+                                    pipeline.clone(),
+                                    self.app.get_state(),
+                                )
+                                .await;
+                            }
+
+                            if let Some(((first, second), operation)) = &self.basis
+                                && *first == ticker
+                                && let Some(other_history) = self.books.cache.get(second)
+                            {
+                                Dispatch::spawn_basis(
+                                    (first.clone(), second.clone()),
+                                    (
+                                        history.extract_window(0, i64::MAX).await,
+                                        other_history.extract_window(0, i64::MAX).await,
+                                    ),
+                                    *operation,
+                                    pipeline.clone(),
+                                    self.app.get_state(),
+                                )
+                                .await;
+                            }
+
+                            let handle = Dispatch::spawn_pipeline(
+                                ticker.clone(),
+                                cloned_history,
+                                pipeline,
+                                self.app.get_state(),
+                                self.action_sender.clone(),
+                                self.emitter.clone(),
+                                self.broadcaster.clone(),
+                                self.scripts.clone(),
+                            )
+                            .await;
+                            self.pipelines_in_flight.insert(ticker, handle);
+                        }
+                        None => (),
+                    }
+                }
+                Action::ToggleDepthMode => {
+                    let mode = self.pipeline.toggle_depth_mode();
+                    match self
+                        .action_sender
+                        .send_now(Action::Inform(format!("Depth mode set to {:?}", mode)))
+                        .await
+                    {
+                        Ok(_) => (),
+                        Err(message) => return Err(message.into()),
+                    }
+                }
+                Action::ToggleSplatMode(view) => {
+                    let mode = self.pipeline.toggle_splat_mode(view);
+                    let target = match view {
+                        Some(view) => view.name(),
+                        None => "all views",
+                    };
+                    match self
+                        .action_sender
+                        .send_now(Action::Inform(format!(
+                            "Splat mode of {} set to {:?}",
+                            target, mode
+                        )))
+                        .await
+                    {
+                        Ok(_) => (),
+                        Err(message) => return Err(message.into()),
+                    }
+
+                    let current_ticker = self.app.get_state().lock().await.current_ticker.clone();
+                    if let Some(ticker) = current_ticker {
+                        match self
+                            .action_sender
+                            .send_now(Action::RunPipeline(ticker))
+                            .await
+                        {
+                            Ok(_) => (),
+                            Err(message) => return Err(message.into()),
+                        }
+                    }
+                }
+                Action::UnsubscribeTicker(ticker) => {
+                    match self.feed.unsubscribe(ticker.clone()).await {
+                        Ok(()) => (),
+                        Err(message) => {
+                            match self
+                                .action_sender
+                                .send_now(Action::Warn(message.to_string()))
+                                .await
+                            {
+                                Ok(_) => (),
+                                Err(message) => return Err(message.into()),
+                            }
+                        }
+                    }
+
+                    self.tickers.remove(&ticker);
+                    self.app
+                        .set_tickers(self.tickers.keys().cloned().collect())
+                        .await;
+                    self.books.cache.remove(&ticker);
+                    self.books.views.remove(&ticker);
+                    self.price_bands.remove(&ticker);
+                    self.walls.remove(&ticker);
+                    self.checkers.remove(&ticker);
+                    self.resyncing.remove(&ticker);
+                    self.daily.remove(&ticker);
+                    if let Some(handle) = self.pipelines_in_flight.remove(&ticker) {
+                        handle.abort();
+                    }
+                }
+                Action::Query(query, responder) => {
+                    let answer = match query {
+                        Query::Symbols => Some(symbols_json(self.tickers.keys())),
+                        Query::Book(symbol) => match self.books.cache.get(&symbol) {
+                            Some(history) => book_json(&symbol, history).await,
+                            None => None,
+                        },
+                        Query::Heatmap(symbol) => {
+                            let state = self.app.get_state();
+                            let locked_state = state.lock().await;
+                            locked_state.views.get(&symbol).and_then(|view| {
+                                view.splatted.iter().find_map(|splatted| match splatted {
+                                    Splatted::Blocks(blocks) => Some(heatmap_json(&symbol, blocks)),
+                                    _ => None,
+                                })
+                            })
+                        }
+                        // only daemons replay their histories
+                        Query::Replay(_) => None,
+                    };
+                    // the client may have hung up meanwhile
+                    let _ = responder.send(answer);
+                }
+                Action::Quit => break,
+                Action::UpdateBook(update) => {
+                    let symbol = update.symbol.clone();
+                    if let Some(broadcaster) = &self.broadcaster {
+                        broadcaster.book(&update);
+                    }
+                    if let Some(emitter) = &self.emitter
+                        && let Err(message) = emitter.book(&update).await
+                        && let Err(message) = self
+                            .action_sender
+                            .send_now(Action::Warn(message.to_string()))
+                            .await
+                    {
+                        return Err(message.into());
+                    }
+                    #[cfg(feature = "kafka")]
+                    if let Some(kafka) = &self.kafka
+                        && let Err(message) = kafka.book(&update)
+                        && let Err(message) = self
+                            .action_sender
+                            .send_now(Action::Warn(message.to_string()))
+                            .await
+                    {
+                        return Err(message.into());
+                    }
+                    self.last_updates
+                        .insert(symbol.clone(), clock::now().timestamp_millis());
+                    if let Some(storage) = &mut self.storage
+                        && let Err(message) = storage.insert_book(&update)
+                    {
+                        let warning = format!("Could not store book of {}: {}", symbol, message);
+                        if let Err(message) =
+                            self.action_sender.send_now(Action::Warn(warning)).await
+                        {
+                            return Err(message.into());
+                        }
+                    }
+                    self.books.throttled_budget(start).await;
+                    match self.books.cache.get_mut(&symbol) {
+                        Some(history) => {
+                            let replaced = self.resumed.remove(&symbol);
+                            let mut anomalies = vec![];
+                            let checker = self.checkers.get_mut(&symbol);
+                            let updated = if replaced {
+                                history.replace(update).await
+                            } else {
+                                if let Some(checker) = &checker {
+                                    anomalies = checker.check_update(&update);
+                                }
+                                history.update(update).await
+                            };
+                            if let Err(message) = updated {
+                                let error =
+                                    format!("Could not update the book of {}: {}", symbol, message);
+                                if let Err(message) =
+                                    self.action_sender.send_now(Action::Error(error)).await
+                                {
+                                    return Err(message.into());
+                                }
+                                continue;
+                            }
+                            let ((time, asks), (_, bids)) = if replaced {
+                                let latest = history.get_latest_book().await;
+                                if let Some(checker) = checker {
+                                    checker.reset();
+                                }
+                                latest
+                            } else {
+                                let latest = history.get_latest_book().await;
+                                if let Some(checker) = checker
+                                    && let Some(anomaly) =
+                                        checker.check_book(&latest.0.1, &latest.1.1)
+                                {
+                                    anomalies.push(anomaly);
+                                }
+                                latest
+                            };
+
+                            if let (Some(tracker), Some((ask, _)), Some((bid, _))) = (
+                                self.daily.get_mut(&symbol),
+                                asks.get_first(),
+                                bids.get_last(),
+                            ) {
+                                tracker.update_price(time, (ask.value + bid.value) / 2.0);
+                            }
+
+                            if let (Some((ask, _)), Some((bid, _))) =
+                                (asks.get_first(), bids.get_last())
+                            {
+                                for message in self.alerts.check_book(&symbol, ask.value, bid.value)
+                                {
+                                    self.trigger_alert(message).await?;
+                                }
+                            }
+
+                            if let Some(detector) = self.walls.get_mut(&symbol) {
+                                for event in detector.scan(time, &asks, &bids) {
+                                    if let WallEvent::Appeared(wall) = &event {
+                                        for message in self.alerts.check_wall(&symbol, wall) {
+                                            self.trigger_alert(message).await?;
+                                        }
+                                    }
+                                    let (verb, wall) = match event {
+                                        WallEvent::Appeared(wall) => ("appeared", wall),
+                                        WallEvent::Disappeared(wall) => ("disappeared", wall),
+                                    };
+                                    let side = match wall.side {
+                                        Side::Ask => "Ask",
+                                        Side::Bid => "Bid",
+                                    };
+                                    let message = format!(
+                                        "{} wall of {} {} at {} {}",
+                                        side, wall.quantity, symbol, wall.price, verb
+                                    );
+                                    match self.action_sender.send_now(Action::Alert(message)).await
+                                    {
+                                        Ok(_) => (),
+                                        Err(message) => return Err(message.into()),
+                                    }
+                                }
+                            }
+
+                            let inconsistent = !anomalies.is_empty();
+                            for anomaly in anomalies {
+                                let warning = anomaly.describe(&symbol);
+                                if let Err(message) =
+                                    self.action_sender.send_now(Action::Warn(warning)).await
+                                {
+                                    return Err(message.into());
+                                }
+                            }
+                            if inconsistent
+                                && self.resync_on_anomaly
+                                && self.resyncing.insert(symbol.clone())
+                            {
+                                Dispatch::spawn_resync(
+                                    symbol,
+                                    self.feed.depth(),
+                                    self.action_sender.clone(),
+                                );
+                            }
+                        }
+                        None => {
+                            let error = format!(
+                                "Got book update for {} while symbol was absent from cache.",
+                                symbol
+                            );
+                            if let Err(message) =
+                                self.action_sender.send_now(Action::Error(error)).await
+                            {
+                                return Err(message.into());
+                            }
+                        }
+                    }
+                }
+                Action::ResyncBook(booked) => {
+                    // snapshots of tickers unsubscribed meanwhile are dropped
+                    let symbol = booked.symbol.clone();
+                    if self.resyncing.remove(&symbol)
+                        && let Some(history) = self.books.cache.get_mut(&symbol)
+                    {
+                        if let Err(message) = history.replace(booked).await {
+                            let error = format!("Could not resynchronize {}: {}", symbol, message);
+                            if let Err(message) =
+                                self.action_sender.send_now(Action::Error(error)).await
+                            {
+                                return Err(message.into());
+                            }
+                            continue;
+                        }
+                        if let Some(checker) = self.checkers.get_mut(&symbol) {
+                            checker.reset();
+                        }
+                        let message = format!("Resynchronized the book of {}", symbol);
+                        if let Err(message) =
+                            self.action_sender.send_now(Action::Inform(message)).await
+                        {
+                            return Err(message.into());
+                        }
+                    }
+                }
+                Action::ReplayBook(booked) => {
+                    let symbol = booked.symbol.clone();
+                    if let Some(history) = self.books.cache.get_mut(&symbol) {
+                        if let Err(message) = history.replace(booked).await {
+                            let error = format!("Could not replay {}: {}", symbol, message);
+                            if let Err(message) =
+                                self.action_sender.send_now(Action::Error(error)).await
+                            {
+                                return Err(message.into());
+                            }
+                            continue;
+                        }
+                        if let Some(checker) = self.checkers.get_mut(&symbol) {
+                            checker.reset();
+                        }
+                    }
+                }
+                Action::UpdatePriceDecimals(symbol, decimals) => {
+                    // decimals of tickers unsubscribed meanwhile are dropped
+                    if self.tickers.contains_key(&symbol) {
+                        let state = self.app.get_state();
+                        let mut locked_state = state.lock().await;
+                        locked_state.views.entry(symbol).or_default().price_decimals =
+                            Some(decimals);
+                    }
+                }
+                Action::UpdateOwnOrders(updates) => {
+                    for update in updates {
+                        self.own_orders.apply(update);
+                    }
+                    let state = self.app.get_state();
+                    let mut locked_state = state.lock().await;
+                    for symbol in self.tickers.keys() {
+                        locked_state
+                            .views
+                            .entry(symbol.clone())
+                            .or_default()
+                            .own_orders = self.own_orders.resting(symbol);
+                    }
+                }
+                Action::UpdateTicker(update) => {
+                    let symbol = update.symbol.clone();
+                    if let Some(emitter) = &self.emitter
+                        && let Err(message) = emitter.ticker(&update).await
+                        && let Err(message) = self
+                            .action_sender
+                            .send_now(Action::Warn(message.to_string()))
+                            .await
+                    {
+                        return Err(message.into());
+                    }
+                    #[cfg(feature = "kafka")]
+                    if let Some(kafka) = &self.kafka
+                        && let Err(message) = kafka.ticker(&update)
+                        && let Err(message) = self
+                            .action_sender
+                            .send_now(Action::Warn(message.to_string()))
+                            .await
+                    {
+                        return Err(message.into());
+                    }
+                    match self.tickers.insert(symbol.clone(), Some(update.clone())) {
+                        Some(_) => (),
+                        None => {
+                            // the update of a ticker unknown to the cache is not recorded
+                            self.tickers.remove(&symbol);
+                            let error = format!(
+                                "Got ticker update for {} while symbol was absent from cache.",
+                                symbol
+                            );
+                            if let Err(message) =
+                                self.action_sender.send_now(Action::Error(error)).await
+                            {
+                                return Err(message.into());
+                            }
+                            continue;
+                        }
+                    }
+
+                    if let Some(tracker) = self.daily.get_mut(&symbol) {
+                        tracker.update_volume(clock::now().timestamp(), update.volume);
+                    }
+
+                    if let Some(storage) = &mut self.storage
+                        && let Err(message) =
+                            storage.insert_ticker(clock::now().timestamp_millis(), &update)
+                    {
+                        let warning = format!("Could not store ticker of {}: {}", symbol, message);
+                        if let Err(message) =
+                            self.action_sender.send_now(Action::Warn(warning)).await
+                        {
+                            return Err(message.into());
+                        }
+                    }
+
+                    let state = self.app.get_state();
+                    let mut locked_state = state.lock().await;
+                    locked_state.views.entry(symbol).or_default().ticker_data = Some(update);
+                }
+                Action::UpdateTrades(trades) => {
+                    #[cfg(feature = "kafka")]
+                    if let Some(kafka) = &self.kafka
+                        && let Err(message) = kafka.trades(&trades)
+                        && let Err(message) = self
+                            .action_sender
+                            .send_now(Action::Warn(message.to_string()))
+                            .await
+                    {
+                        return Err(message.into());
+                    }
+                    if let Some(storage) = &mut self.storage
+                        && let Err(message) = storage.insert_trades(&trades)
+                    {
+                        let warning = format!("Could not store trades: {}", message);
+                        if let Err(message) =
+                            self.action_sender.send_now(Action::Warn(warning)).await
+                        {
+                            return Err(message.into());
+                        }
+                    }
+                    for (symbol, trades) in
+                        &trades.into_iter().chunk_by(|trade| trade.symbol.clone())
+                    {
+                        if let Some(history) = self.books.cache.get_mut(&symbol)
+                            && let Err(message) = history.update_trades(trades.collect()).await
+                        {
+                            let error =
+                                format!("Could not record trades of {}: {}", symbol, message);
+                            if let Err(message) =
+                                self.action_sender.send_now(Action::Error(error)).await
+                            {
+                                return Err(message.into());
+                            }
+                        }
+                    }
+                }
+                Action::Warn(message) => {
+                    warn!("{}", message);
+                    let time = clock::now().timestamp();
+                    let state = self.app.get_state();
+                    state
+                        .lock()
+                        .await
+                        .logs
+                        .push(time, Severity::Warning, message);
+                }
+            }
+
+            if displayed {
+                self.app.get_state().lock().await.touch();
+            }
+
+            // counted over budget in the stats, logged rather than queued as the queue is likely
+            // full while handlers stall
+            let elapsed = start.elapsed();
+            if self.metrics.record(name, elapsed) {
+                warn!(
+                    "Handling {} took {:?}, over the {:?} budget",
+                    name, elapsed, self.metrics.budget
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Visualizer of Kraken order books
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Ways of running the visualizer
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// visualize a ticker live, or collect it without the interface with --headless
+    Watch {
+        /// ticker symbol to visualize, e.g. BTC/USD
+        ticker: String,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// record the book updates of a ticker as received into a file, without the interface, until
+    /// interrupted
+    Record {
+        /// ticker symbol to record
+        ticker: String,
+        /// recording file written, replaced if it exists
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// replay a recording in the interface, as if opened from the library page
+    Replay {
+        /// recording file to replay
+        file: PathBuf,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// export a recording to an archive that watch resumes from with --from
+    Export {
+        /// recording file to export
+        file: PathBuf,
+        /// format of the archive
+        #[arg(long, value_enum, default_value_t = ArchiveFormat::Parquet)]
+        format: ArchiveFormat,
+        /// archive written, named after the recording with the extension of the format by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// length in seconds of the time slices written to each parquet file
+        #[arg(long, default_value_t = 60)]
+        slice_seconds: i64,
+    },
+}
+
+/// Options of the commands running the feed
+#[derive(clap::Args, Debug)]
+struct Options {
+    /// number of levels per side of the subscribed books, one of 10, 25, 100, 500 or 1000
+    /// [default: 100]
+    #[arg(long, value_parser = parse_depth)]
+    depth: Option<i32>,
+    /// seconds of books kept in cache [default: 300]
+    #[arg(long, value_parser = parse_count)]
+    cache_window: Option<usize>,
+    /// seconds of books displayed, at most the cache window [default: 180]
+    #[arg(long, value_parser = parse_seconds)]
+    visual_window: Option<u64>,
+    /// resolution of the time axes of the views [default: 370]
+    #[arg(long, value_parser = parse_count)]
+    time_res: Option<usize>,
+    /// resolution of the price axes of the views [default: 200]
+    #[arg(long, value_parser = parse_count)]
+    price_res: Option<usize>,
+    /// number of actions the dispatch queue holds [default: 1000]
+    #[arg(long, value_parser = parse_count)]
+    buffer_size: Option<usize>,
+    /// seconds the websocket feed waits for messages before giving up [default: 200]
+    #[arg(long, value_parser = parse_seconds)]
+    ws_timeout: Option<u64>,
+    /// number of standard deviations at which splat kernels are truncated, lower is faster
+    #[arg(long, default_value_t = 5.0, value_parser = parse_truncation)]
+    kernel_truncation: f64,
+    /// shape of the splat kernels
+    #[arg(long, value_enum, default_value_t = KernelShape::Gaussian)]
+    kernel: KernelShape,
+    /// width of the splat kernels: "auto" to derive it from the number of samples, "adaptive" or
+    /// "adaptive:K" to follow the distance of every level to its K-th nearest one, an absolute
+    /// width in price units (e.g. 0.5) or a percentage of the price range (e.g. 2%)
+    #[arg(long, default_value = "auto", value_parser = parse_bandwidth)]
+    bandwidth: Bandwidth,
+    /// quantity splatted grids hold, mass preserving the volume of every book within the grid
+    /// for magnitudes comparable across resolutions and bandwidths
+    #[arg(long, value_enum, default_value_t = Normalization::Density)]
+    normalization: Normalization,
+    /// spacing of the price axis, log giving volatile pairs the same resolution at every price
+    #[arg(long, value_enum, default_value_t = PriceScale::Linear)]
+    price_scale: PriceScale,
+    /// price range of the views: "full" for the whole book, the central percentage of resting
+    /// volume (e.g. 98%) to ignore far out of range orders, or a half width around mid (e.g. 200bps)
+    #[arg(long, default_value = "full", value_parser = parse_price_range)]
+    price_range: PriceRange,
+    /// half life in seconds of an exponential decay fading older liquidity in the order map
+    #[arg(long, value_parser = parse_half_life)]
+    decay_half_life: Option<f64>,
+    /// half width in basis points of the fixed price band used for pegged pairs (e.g. USDT/USD)
+    #[arg(long, default_value_t = 50.0)]
+    band_bps: f64,
+    /// price resolution used within the fixed price band of pegged pairs
+    #[arg(long, default_value_t = 400)]
+    band_price_res: usize,
+    /// always use the order book's price range, even for pegged pairs
+    #[arg(long)]
+    no_band: bool,
+    /// second symbol quoting the same asset to monitor executable arbitrage spreads against
+    #[arg(long)]
+    arbitrage_with: Option<String>,
+    /// round trip fees in basis points an arbitrage has to beat to raise an alert
+    #[arg(long, default_value_t = 52.0)]
+    arbitrage_fee_bps: f64,
+    /// second symbol whose mid price is combined with the ticker's into a basis series
+    #[arg(long)]
+    basis_with: Option<String>,
+    /// operation combining the mid prices of the ticker and the basis symbol
+    #[arg(long, value_enum, default_value_t = BasisOperation::Difference)]
+    basis_op: BasisOperation,
+    /// multiple of the median level size above which resting orders are flagged as walls
+    #[arg(long, default_value_t = 10.0)]
+    wall_multiple: f64,
+    /// multiple of the recent mid beyond which, either way, updated levels are flagged as spikes
+    #[arg(long, default_value_t = 5.0)]
+    spike_multiple: f64,
+    /// replace books found crossed or otherwise inconsistent by a freshly fetched snapshot
+    #[arg(long)]
+    resync_on_anomaly: bool,
+    /// time zone in which the locally computed daily statistics roll over
+    #[arg(long, value_enum, default_value_t = Rollover::Utc)]
+    rollover: Rollover,
+    /// directory holding recordings listed in the library page
+    #[arg(long, default_value = "recordings")]
+    recordings_dir: PathBuf,
+    /// directory snapshots of the arrays on screen are exported to
+    #[arg(long, default_value = "exports")]
+    exports_dir: PathBuf,
+    /// format of the screenshots of the interface taken with the P key, one of ansi, svg or png
+    #[arg(long, value_parser = ScreenshotFormat::parse, default_value = "svg")]
+    screenshot_format: ScreenshotFormat,
+    /// directory book histories are flushed to as parquet files, partitioned by symbol and day
+    #[arg(long)]
+    persist_dir: Option<PathBuf>,
+    /// directory of persisted book histories or SQLite archive to resume from, e.g. the persist
+    /// dir or the SQLite archive of a past run
+    #[arg(long)]
+    from: Option<PathBuf>,
+    /// SQLite archive every book update, ticker and trade received is recorded into
+    #[arg(long)]
+    sqlite: Option<PathBuf>,
+    /// length in seconds of the time slices flushed to each parquet file
+    #[arg(long, default_value_t = 60)]
+    persist_slice_seconds: i64,
+    /// time in milliseconds above which handling a single action raises a warning
+    #[arg(long, default_value_t = 50)]
+    handler_budget_ms: u64,
+    /// directory the log files, rolled over daily, are written to
+    #[arg(long, default_value = "logs")]
+    log_dir: PathBuf,
+    /// most verbose level of the events written to the log files, or off
+    #[arg(long, default_value = "info")]
+    log_level: LevelFilter,
+    /// memory in MiB the cached book histories may use before older books get downsampled
+    #[arg(long)]
+    cache_memory_mb: Option<usize>,
+    /// number of levels per side of the latest book listed by the ladder
+    #[arg(long, default_value_t = 10)]
+    ladder_levels: usize,
+    /// leave mouse events to the terminal, e.g. to select text natively
+    #[arg(long)]
+    no_mouse: bool,
+    /// colors the interface is drawn with, cycled at runtime with t [default: default]
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
+    /// gradient the order map volumes are colored along
+    #[arg(long, value_enum, default_value_t = Gradient::Shades)]
+    gradient: Gradient,
+    /// colors the terminal displays, detected from the COLORTERM and TERM variables by default
+    #[arg(long, value_enum)]
+    color_depth: Option<ColorDepth>,
+    /// symbol the order map cells and chart bars are drawn with, braille quadrupling the points
+    /// displayed on terminals rendering it well
+    #[arg(long, value_enum, default_value_t = PlotMarker::HalfBlock)]
+    marker: PlotMarker,
+    /// label time axes with seconds before the latest book or with the wall clock
+    #[arg(long, value_enum, default_value_t = TimeLabels::Relative)]
+    time_labels: TimeLabels,
+    /// milliseconds between display updates, from 250 to 10000, adjustable at runtime with ( and )
+    /// [default: 1000]
+    #[arg(long)]
+    refresh_ms: Option<u64>,
+    /// milliseconds between polls for key presses, from 25 to 1000 [default: 100]
+    #[arg(long)]
+    poll_ms: Option<u64>,
+    /// alert rule watched from the start, written SYMBOL price|spread|wall THRESHOLD, e.g.
+    /// "BTC/USD price 65000", "BTC/USD spread 10bps" or "BTC/USD wall 50", repeatable
+    #[arg(long, value_parser = AlertRule::parse)]
+    alert: Vec<AlertRule>,
+    /// also show triggered alerts as desktop notifications, through notify-send
+    #[arg(long)]
+    desktop_notifications: bool,
+    /// outbound channel triggered alerts are forwarded to, written webhook:URL to POST a JSON
+    /// payload, telegram:TOKEN@CHAT or discord:TOKEN@CHANNEL to message through a bot, repeatable
+    #[arg(long, value_parser = AlertSink::parse)]
+    alert_sink: Vec<AlertSink>,
+    /// mark the orders of the user on the order map and ladder, read through the authenticated
+    /// feed with the key and secret in the KRAKEN_API_KEY and KRAKEN_API_SECRET variables
+    #[arg(long)]
+    own_orders: bool,
+    /// show the balances and open orders of the user on the account page, polled with the same
+    /// key and secret
+    #[arg(long)]
+    account: bool,
+    /// enable the buy, sell and cancel commands, sent through the authenticated websocket with
+    /// the same key and secret after confirmation, orders only being validated by the exchange
+    /// unless --live-orders is given
+    #[arg(long)]
+    trading: bool,
+    /// place the orders entered for real instead of the default dry run
+    #[arg(long)]
+    live_orders: bool,
+    /// configuration file read in place of ~/.config/bookedblocks/config.toml, its settings being
+    /// overridden by the flags given
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// collect the ticker without the interface, e.g. on a server without a terminal, writing the
+    /// outputs chosen with --collect until interrupted
+    #[arg(long)]
+    headless: bool,
+    /// outputs of the headless collection: splatted arrays exported to the exports dir, raw book
+    /// updates recorded to the recordings dir, or both
+    #[arg(long, value_enum, default_value_t = Collected::Splatted)]
+    collect: Collected,
+    /// seconds between two exports of the splatted arrays when headless
+    #[arg(long, default_value_t = 60, value_parser = parse_seconds)]
+    collect_period: u64,
+    /// format of the splatted arrays exported when headless, npy or csv
+    #[arg(long, default_value = "npy", value_parser = ExportFormat::parse)]
+    collect_format: ExportFormat,
+    /// write the raw book updates collected headless to the standard output instead of the
+    /// recordings dir
+    #[arg(long)]
+    raw_stdout: bool,
+    /// emit every book update, ticker update and pipeline summary for other tools, jsonl writing
+    /// one JSON object per line
+    #[arg(long, value_enum)]
+    emit: Option<EmitFormat>,
+    /// file the emitted stream is appended to, the standard output by default when headless
+    #[arg(long)]
+    emit_to: Option<PathBuf>,
+    /// address of a local websocket server streaming the book updates and the splatted depth,
+    /// volumes and order map to its clients as JSON, e.g. 127.0.0.1:9001
+    #[arg(long)]
+    serve_ws: Option<SocketAddr>,
+    /// address of a local REST API serving /symbols, /book/{symbol} and /heatmap/{symbol} as
+    /// JSON, e.g. 127.0.0.1:8080
+    #[arg(long)]
+    serve_http: Option<SocketAddr>,
+    /// time series database the best bid and ask, spread, total depth and imbalance of every
+    /// book are written to each second, written influx:URL for the write endpoint of InfluxDB,
+    /// authorized with the token in the INFLUX_TOKEN variable, or timescale:CONNECTION for
+    /// TimescaleDB
+    #[arg(long, value_parser = TimeSeriesSink::parse)]
+    timeseries: Option<TimeSeriesSink>,
+    /// comma separated Kafka brokers every book update, trade and ticker received is published
+    /// to as JSON, e.g. localhost:9092
+    #[cfg(feature = "kafka")]
+    #[arg(long)]
+    kafka_brokers: Option<String>,
+    /// topic the events are published to, {symbol} being replaced by the symbol written
+    /// BASE-QUOTE and {type} by book, trade or ticker
+    #[cfg(feature = "kafka")]
+    #[arg(long, default_value = DEFAULT_TOPIC_TEMPLATE)]
+    kafka_topic: String,
+    /// Rhai script whose on_book(book) and on_pipeline(result) functions are called after every
+    /// pipeline run, plotting series on the indicators page with plot(name, value) and raising
+    /// alerts with alert(message), repeatable
+    #[arg(long)]
+    script: Vec<PathBuf>,
+    /// with --headless, Unix socket of a daemon interfaces attach to with --attach, collecting
+    /// on once they are closed
+    #[arg(long)]
+    socket: Option<PathBuf>,
+    /// Unix socket of a headless daemon to attach to, whose books and histories are shown in
+    /// place of a feed of its own
+    #[arg(long)]
+    attach: Option<PathBuf>,
+}
+
+/// parse a kernel truncation radius, in standard deviations
+fn parse_truncation(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(truncation) if (1.0..=10.0).contains(&truncation) => Ok(truncation),
+        Ok(_) => Err("kernel truncation should be between 1 and 10 deviations".to_string()),
+        Err(message) => Err(format!("{:?}", message)),
+    }
+}
+
+/// parse a number of levels per side of the subscribed books
+fn parse_depth(text: &str) -> Result<i32, String> {
+    match text.parse::<i32>() {
+        Ok(depth) if BOOK_DEPTHS.contains(&depth) => Ok(depth),
+        Ok(_) => Err(format!("depth should be one of {:?}", BOOK_DEPTHS)),
+        Err(message) => Err(format!("{:?}", message)),
+    }
+}
+
+/// parse a positive count, e.g. of actions or of points along an axis
+fn parse_count(text: &str) -> Result<usize, String> {
+    match text.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        Ok(_) => Err("count should be positive".to_string()),
+        Err(message) => Err(format!("{:?}", message)),
+    }
+}
+
+/// parse a positive number of seconds
+fn parse_seconds(text: &str) -> Result<u64, String> {
+    match text.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(seconds),
+        Ok(_) => Err("seconds should be positive".to_string()),
+        Err(message) => Err(format!("{:?}", message)),
+    }
+}
+
+/// parse a kernel bandwidth, either "auto", "adaptive" with an optional number of neighbours,
+/// absolute or a percentage of the range
+fn parse_bandwidth(text: &str) -> Result<Bandwidth, String> {
+    if text == "auto" {
+        return Ok(Bandwidth::Samples);
+    }
+    if text == "adaptive" {
+        return Ok(Bandwidth::Adaptive(ADAPTIVE_NEIGHBOURS));
+    }
+    if let Some(neighbours) = text.strip_prefix("adaptive:") {
+        return match neighbours.parse::<usize>() {
+            Ok(neighbours) if neighbours > 0 => Ok(Bandwidth::Adaptive(neighbours)),
+            _ => Err("adaptive bandwidth expects a positive number of neighbours".to_string()),
+        };
+    }
+    let (number, percentage) = match text.strip_suffix('%') {
+        Some(number) => (number, true),
+        None => (text, false),
+    };
+    match number.parse::<f64>() {
+        Ok(width) if width > 0.0 && width.is_finite() && percentage => {
+            Ok(Bandwidth::Fraction(width / 100.0))
+        }
+        Ok(width) if width > 0.0 && width.is_finite() => Ok(Bandwidth::Absolute(width)),
+        Ok(_) => Err("bandwidth should be positive".to_string()),
+        Err(message) => Err(format!("{:?}", message)),
+    }
+}
+
+/// parse a decay half life, in seconds
+fn parse_half_life(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(half_life) if half_life > 0.0 && half_life.is_finite() => Ok(half_life),
+        Ok(_) => Err("decay half life should be positive".to_string()),
+        Err(message) => Err(format!("{:?}", message)),
+    }
+}
+
+/// parse a price range, either "full", a percentage of volume or basis points around mid
+fn parse_price_range(text: &str) -> Result<PriceRange, String> {
+    if text == "full" {
+        return Ok(PriceRange::Full);
+    }
+    if let Some(number) = text.strip_suffix('%') {
+        return match number.parse::<f64>() {
+            Ok(percentage) if percentage > 0.0 && percentage <= 100.0 => {
+                Ok(PriceRange::Percentile(percentage / 100.0))
+            }
+            Ok(_) => Err("price range percentage should be between 0 and 100".to_string()),
+            Err(message) => Err(format!("{:?}", message)),
+        };
+    }
+    if let Some(number) = text.strip_suffix("bps") {
+        return match number.parse::<f64>() {
+            Ok(half_width) if half_width > 0.0 && half_width.is_finite() => {
+                Ok(PriceRange::AroundMid(half_width))
+            }
+            Ok(_) => Err("price range half width should be positive".to_string()),
+            Err(message) => Err(format!("{:?}", message)),
+        };
+    }
+    Err("price range should be \"full\", a percentage or basis points".to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), BookedError> {
+    // replays have no ticker of their own, recordings are collected headless
+    let (ticker, recorded, replayed, args) = match Cli::parse().command {
+        Command::Watch { ticker, options } => (Some(ticker), None, None, options),
+        Command::Record {
+            ticker,
+            output,
+            options,
+        } => (Some(ticker), Some(output), None, options),
+        Command::Replay { file, options } => (None, None, Some(file), options),
+        Command::Export {
+            file,
+            format,
+            output,
+            slice_seconds,
+        } => {
+            let output = output.unwrap_or_else(|| file.with_extension(format.extension()));
+            let count = archive_recording(&file, format, &output, slice_seconds).await?;
+            println!("Exported {} book updates to {}", count, output.display());
+            return Ok(());
+        }
+    };
+    let headless = args.headless || recorded.is_some();
+    // flushes the log files when dropped at exit
+    let _log_guard = logging::init(&args.log_dir, args.log_level)?;
+
+    let band_half_width_in_bps = if args.no_band {
+        None
+    } else {
+        Some(args.band_bps)
+    };
+
+    let config = load_config(args.config.as_deref())?;
+
+    let cache_window = args.cache_window.or(config.cache_window).unwrap_or(5 * 60);
+    let visual_window = args
+        .visual_window
+        .or(config.visual_window)
+        .unwrap_or(3 * 60);
+    if visual_window as usize > cache_window {
+        return Err(BookedError::Config(format!(
+            "The visual window of {}s should fit in the cache window of {}s",
+            visual_window, cache_window
+        )));
+    }
+
+    if !args.script.is_empty() && headless {
+        return Err(BookedError::Config(
+            "Scripts plot their series on the interface".to_string(),
+        ));
+    }
+    if args.socket.is_some() && !headless {
+        return Err(BookedError::Config(
+            "Only headless daemons listen at a --socket".to_string(),
+        ));
+    }
+    if args.attach.is_some() && headless {
+        return Err(BookedError::Config(
+            "A headless daemon cannot --attach to another one".to_string(),
+        ));
+    }
+    if args.account && headless {
+        return Err(BookedError::Config(
+            "The account is only shown by the interface".to_string(),
+        ));
+    }
+    if args.trading && headless {
+        return Err(BookedError::Config(
+            "Order entry needs the interface to confirm orders".to_string(),
+        ));
+    }
+    if args.live_orders && !args.trading {
+        return Err(BookedError::Config(
+            "Only --trading enables --live-orders".to_string(),
+        ));
+    }
+    if replayed.is_some() && headless {
+        return Err(BookedError::Config(
+            "Recordings are replayed in the interface".to_string(),
+        ));
+    }
+    if replayed.is_some() && (args.arbitrage_with.is_some() || args.basis_with.is_some()) {
+        return Err(BookedError::Config(
+            "Arbitrages and bases are monitored on live tickers".to_string(),
+        ));
+    }
+
+    if args.emit.is_some() && args.emit_to.is_none() {
+        if !headless {
+            return Err(BookedError::Config(
+                "Emitting alongside the interface needs --emit-to, the terminal taking the \
+                standard output"
+                    .to_string(),
+            ));
+        }
+        if args.raw_stdout && args.collect != Collected::Splatted && recorded.is_none() {
+            return Err(BookedError::Config(
+                "The raw updates and the emitted stream cannot share the standard output"
+                    .to_string(),
+            ));
+        }
+    }
+    let emitter = match args.emit {
+        Some(EmitFormat::Jsonl) => Some(Emitter::new(args.emit_to.as_deref())?),
+        None => None,
+    };
+    let broadcaster = match args.serve_ws {
+        Some(address) => {
+            let broadcaster = Broadcaster::bind(address).await?;
+            info!(address = %broadcaster.address(), "websocket server listening");
+            Some(broadcaster)
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "kafka")]
+    let kafka = match &args.kafka_brokers {
+        Some(brokers) => Some(KafkaSink::new(brokers, args.kafka_topic.clone())?),
+        None => None,
+    };
+
+    let timeseries = match args.timeseries.clone() {
+        Some(sink) => {
+            let name = sink.name();
+            let writer = TimeSeriesWriter::connect(sink).await?;
+            info!(database = name, "writing time series");
+            Some(writer)
+        }
+        None => None,
+    };
+
+    let serve_api = |served: Result<SocketAddr, BookedError>| {
+        info!(address = %served?, "REST API listening");
+        Ok::<(), BookedError>(())
+    };
+
+    let buffer_size = args.buffer_size.or(config.buffer_size).unwrap_or(1000);
+    let ws_timeout = args.ws_timeout.or(config.ws_timeout).unwrap_or(200);
+    let depth = args.depth.or(config.depth).unwrap_or(100);
+    let time_res = args.time_res.or(config.time_res).unwrap_or(370);
+    let price_res = args.price_res.or(config.price_res).unwrap_or(200);
+    let kernel = Kernel {
+        shape: args.kernel,
+        bandwidth: args.bandwidth,
+        normalization: args.normalization,
+        truncation_in_deviations: args.kernel_truncation,
+    };
+
+    if let Some(ticker) = ticker.clone().filter(|_| headless) {
+        let mut pipeline = Pipeline::new(visual_window, time_res, price_res, kernel);
+        pipeline.set_price_scale(args.price_scale);
+        pipeline.set_price_range(args.price_range);
+        pipeline.set_decay_half_life(args.decay_half_life);
+        pipeline
+            .set_price_band(band_half_width_in_bps.and_then(|half_width| {
+                PriceBand::detect(&ticker, half_width, args.band_price_res)
+            }));
+        let mut collector = Collector::new(
+            buffer_size,
+            ws_timeout,
+            depth,
+            cache_window,
+            pipeline,
+            Duration::from_secs(args.collect_period),
+        )
+        .await?;
+        if args.collect != Collected::Raw && recorded.is_none() {
+            collector.set_exports(args.exports_dir, args.collect_format);
+        }
+        if let Some(emitter) = emitter {
+            collector.set_emitter(emitter);
+        }
+        if let Some(broadcaster) = broadcaster {
+            collector.set_broadcaster(broadcaster);
+        }
+        if let Some(writer) = timeseries {
+            collector.set_timeseries(writer);
+        }
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = kafka {
+            collector.set_kafka(kafka);
+        }
+        if let Some(address) = args.serve_http {
+            serve_api(collector.serve_api(address).await)?;
+        }
+        if let Some(path) = &args.socket {
+            collector.serve_socket(path)?;
+            info!(socket = %path.display(), "daemon listening");
+        }
+        if let Some(output) = recorded {
+            collector.set_raw_output(RawOutput::File(output));
+        } else if args.collect != Collected::Splatted {
+            collector.set_raw_output(match args.raw_stdout {
+                true => RawOutput::Stdout,
+                false => RawOutput::Directory(args.recordings_dir),
+            });
+        }
+        collector.subscribe(ticker).await?;
+        return collector.run().await;
+    }
+
+    terminal::install_panic_hook();
+    let mut dispatch = Dispatch::new(
+        buffer_size,
+        ws_timeout,
+        depth,
+        cache_window,
+        visual_window,
+        time_res,
+        price_res,
+        band_half_width_in_bps,
+        args.band_price_res,
+        kernel,
+        args.attach.as_deref(),
+    )
+    .await?;
+
+    dispatch.set_price_scale(args.price_scale);
+    dispatch.set_price_range(args.price_range);
+    dispatch.set_decay_half_life(args.decay_half_life);
+    dispatch.set_ladder_levels(args.ladder_levels);
+    dispatch.set_wall_multiple(args.wall_multiple);
+    dispatch.set_spike_multiple(args.spike_multiple);
+    dispatch.set_resync_on_anomaly(args.resync_on_anomaly);
+    for rule in args.alert {
+        dispatch.add_alert(rule);
+    }
+    dispatch.set_desktop_notifications(args.desktop_notifications);
+    for sink in args.alert_sink {
+        dispatch.add_alert_sink(sink);
+    }
+    dispatch.set_rollover(args.rollover);
+    dispatch.set_recordings_directory(args.recordings_dir);
+    dispatch.set_exports_directory(args.exports_dir);
+    dispatch.set_screenshot_format(args.screenshot_format);
+    if let Some(emitter) = emitter {
+        dispatch.set_emitter(emitter);
+    }
+    if let Some(broadcaster) = broadcaster {
+        dispatch.set_broadcaster(broadcaster);
+    }
+    if let Some(writer) = timeseries {
+        dispatch.set_timeseries(writer);
+    }
+    #[cfg(feature = "kafka")]
+    if let Some(kafka) = kafka {
+        dispatch.set_kafka(kafka);
+    }
+    if !args.script.is_empty() {
+        dispatch.set_scripts(Scripts::load(&args.script)?);
+    }
+    if let Some(address) = args.serve_http {
+        serve_api(dispatch.serve_api(address).await)?;
+    }
+    if let Some(path) = &args.sqlite {
+        dispatch.set_storage(path)?;
+    }
+    if let Some(directory) = args.from {
+        dispatch.set_history_directory(directory);
+    }
+    if let Some(directory) = args.persist_dir {
+        dispatch.set_persistence(directory, args.persist_slice_seconds);
+    }
+    dispatch.set_handler_budget(Duration::from_millis(args.handler_budget_ms));
+    if let Some(megabytes) = args.cache_memory_mb {
+        dispatch.set_memory_budget(megabytes * 1024 * 1024);
+    }
+    if args.own_orders {
+        dispatch.follow_own_orders().await?;
+    }
+    if args.account {
+        dispatch.follow_account().await?;
+    }
+    if args.trading {
+        dispatch.enable_trading(!args.live_orders).await?;
+    }
+    dispatch.set_mouse_capture(!args.no_mouse).await;
+    dispatch
+        .set_theme(args.theme.or(config.theme()?).unwrap_or(Theme::Default))
+        .await;
+    dispatch
+        .set_layout(
+            config.layout.unwrap_or_default(),
+            args.config.clone().or_else(config_path),
+        )
+        .await;
+    dispatch.set_key_bindings(config.key_bindings()?).await?;
+    dispatch.set_gradient(args.gradient).await;
+    if let Some(color_depth) = args.color_depth {
+        dispatch.set_color_depth(color_depth).await;
+    }
+    dispatch.set_marker(args.marker).await;
+    dispatch.set_time_labels(args.time_labels).await;
+    dispatch
+        .set_cadence(Cadence::new(
+            Duration::from_millis(args.refresh_ms.or(config.refresh_ms).unwrap_or(1000)),
+            Duration::from_millis(args.poll_ms.or(config.poll_ms).unwrap_or(100)),
+        ))
+        .await;
+
+    if let Some(ticker) = ticker {
+        if let Some(other) = &args.arbitrage_with {
+            dispatch.set_arbitrage((ticker.clone(), other.clone()), args.arbitrage_fee_bps);
+            // stage the arbitrage symbol first so the visualized ticker ends up being the current
+            // one
+            dispatch.stage_subscription(other.clone());
+        }
+
+        if let Some(other) = &args.basis_with {
+            dispatch.set_basis((ticker.clone(), other.clone()), args.basis_op);
+            dispatch.stage_subscription(other.clone());
+        }
+
+        dispatch.stage_subscription(ticker);
+    }
+    if let Some(file) = replayed {
+        dispatch.stage_recording(file);
+    }
+
+    dispatch.run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use feed::{Booked, Order};
+    use pipeline::{BookHistory, View};
+    use splat::SplatMode;
+
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::UnixListener;
+    use tokio::time::timeout;
+
+    /// dispatch attached to a fake daemon relaying nothing, along with the subscription requests
+    /// the daemon received; its socket is removed once connected to
+    async fn attached_dispatch(buffer_size: usize) -> (Dispatch, Arc<Mutex<Vec<String>>>) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("daemon.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let requests = Arc::new(Mutex::new(vec![]));
+        let received = requests.clone();
+        spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                received.lock().await.push(line);
+            }
+        });
+
+        let dispatch = Dispatch::new(
+            buffer_size,
+            10,
+            10,
+            60,
+            60,
+            10,
+            10,
+            None,
+            10,
+            Kernel::default(),
+            Some(&path),
+        )
+        .await
+        .unwrap();
+        (dispatch, requests)
+    }
+
+    /// book update of one level per side
+    fn booked(symbol: &str, bid: f64, ask: f64) -> Booked {
+        Booked {
+            symbol: symbol.to_string(),
+            timestamp: "2023-11-14T22:13:20+00:00".to_string(),
+            bids: vec![Order {
+                price: bid,
+                quantity: 1.0,
+            }],
+            asks: vec![Order {
+                price: ask,
+                quantity: 1.0,
+            }],
+        }
+    }
+
+    /// history of one book per second over a number of seconds
+    async fn filled_history(symbol: &str, seconds: i64) -> BookHistory {
+        let mut history = BookHistory::new(60);
+        for second in 0..seconds {
+            let mut update = booked(symbol, 99.0, 101.0);
+            update.bids[0].quantity += second as f64;
+            update.timestamp = format!("2023-11-14T22:13:{:02}+00:00", second);
+            history.update(update).await.unwrap();
+        }
+        history
+    }
+
+    #[tokio::test]
+    async fn test_enforce_budget_least_recently_viewed() {
+        let mut books = BooksCache::new(60);
+        for symbol in ["BTC/USD", "ETH/USD"] {
+            books
+                .cache
+                .insert(symbol.to_string(), filled_history(symbol, 50).await);
+        }
+        books.viewed("ETH/USD");
+        books.viewed("BTC/USD");
+        let full = books.enforce_budget().await;
+        let sizes = books.cache["BTC/USD"].sizes().await;
+
+        books.memory_budget = Some(full - 1);
+        let usage = books.enforce_budget().await;
+        assert!(usage < full);
+        assert!(books.cache["ETH/USD"].sizes().await.0 < sizes.0);
+        assert_eq!(books.cache["BTC/USD"].sizes().await, sizes);
+    }
+
+    #[tokio::test]
+    async fn test_enforce_budget_met() {
+        let mut books = BooksCache::new(60);
+        for symbol in ["BTC/USD", "ETH/USD"] {
+            books
+                .cache
+                .insert(symbol.to_string(), filled_history(symbol, 50).await);
+        }
+        let full = books.enforce_budget().await;
+
+        let budget = full * 4 / 5;
+        books.memory_budget = Some(budget);
+        let usage = books.enforce_budget().await;
+        let mut held = 0;
+        for history in books.cache.values() {
+            held += history.memory_usage().await;
+        }
+        assert!(usage <= budget);
+        assert_eq!(usage, held);
+    }
+
+    #[tokio::test]
+    async fn test_throttled_budget() {
+        let mut books = BooksCache::new(60);
+        books
+            .cache
+            .insert("BTC/USD".to_string(), filled_history("BTC/USD", 50).await);
+        let full = books.enforce_budget().await;
+        books.memory_budget = Some(full - 1);
+
+        // the first book enforces the budget, the following ones within a period do not
+        let now = Instant::now();
+        let usage = books.throttled_budget(now).await;
+        assert!(usage < full);
+        books
+            .cache
+            .insert("BTC/USD".to_string(), filled_history("BTC/USD", 50).await);
+        assert_eq!(books.throttled_budget(now + BUDGET_PERIOD / 2).await, usage);
+        assert_eq!(books.cache["BTC/USD"].memory_usage().await, full);
+        assert_eq!(books.throttled_budget(now + BUDGET_PERIOD).await, usage);
+        assert!(books.cache["BTC/USD"].memory_usage().await < full);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_staged_subscription() {
+        let (mut dispatch, requests) = attached_dispatch(10).await;
+        let symbol = "BTC/USD".to_string();
+        dispatch.stage_subscription(symbol.clone());
+        dispatch.stage_subscription(symbol.clone());
+
+        // the symbol requested again once the loop runs is subscribed to only once
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::SubscribeTicker(symbol.clone()))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the daemon reads the requests in the background
+        timeout(Duration::from_secs(5), async {
+            while requests.lock().await.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let requests = requests.lock().await;
+        let subscriptions = requests
+            .iter()
+            .filter(|request| request.contains("subscribe") && request.contains(&symbol))
+            .count();
+        assert_eq!(subscriptions, 1);
+        assert_eq!(dispatch.tickers.len(), 1);
+        match dispatch.action_receiver.try_recv() {
+            Ok(Action::Warn(message)) => assert_eq!(message, "BTC/USD is already subscribed"),
+            _ => panic!("the repeated request was not turned down"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_touch() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+        let state = dispatch.app.get_state();
+        let generation = state.lock().await.generation;
+
+        // the alert changes the interface, the book only reaches it through a pipeline run
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::Alert("alert".to_string()))
+            .await
+            .unwrap();
+        sender
+            .send(Action::UpdateBook(booked("BTC/USD", 99.0, 101.0)))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(state.lock().await.generation, generation + 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_full_queue() {
+        let (mut dispatch, _) = attached_dispatch(2).await;
+        let symbol = "BTC/USD".to_string();
+        dispatch
+            .books
+            .cache
+            .insert(symbol.clone(), BookHistory::new(60));
+        dispatch.add_alert(AlertRule::parse("BTC/USD spread 1bps").unwrap());
+        dispatch.add_alert(AlertRule::parse("BTC/USD spread 2bps").unwrap());
+
+        // the update leaves room for one of the two alerts it raises, the dispatcher never
+        // waiting on its own queue for the other, which still gets in once there is room
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::UpdateBook(booked(&symbol, 99.0, 101.0)))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        assert_eq!(sender.capacity(), 0);
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .expect("dispatch waited on its own queue")
+            .unwrap();
+
+        assert!(sender.take_shed().await.is_empty());
+        for _ in 0..2 {
+            let action = timeout(Duration::from_secs(5), dispatch.action_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(action.name(), "Alert");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_pipeline_in_flight() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+        let symbol = "BTC/USD".to_string();
+        let mut history = BookHistory::new(60);
+        history.update(booked(&symbol, 99.0, 101.0)).await.unwrap();
+        dispatch.books.cache.insert(symbol.clone(), history);
+        dispatch
+            .pipelines_in_flight
+            .insert(symbol.clone(), spawn(std::future::pending()));
+
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::RunPipeline(symbol.clone()))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the request is skipped, leaving the run in flight and the state untouched
+        let stats = dispatch.metrics.stats();
+        let run_pipeline = stats
+            .iter()
+            .find(|stats| stats.name == "RunPipeline")
+            .unwrap();
+        assert_eq!(run_pipeline.dropped, 1);
+        assert!(!dispatch.pipelines_in_flight[&symbol].is_finished());
+        let state = dispatch.app.get_state();
+        assert!(state.lock().await.diagnostics.caches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_pipeline_panicked() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+        let symbol = "BTC/USD".to_string();
+        let panicked = spawn(async { panic!("run panicked") });
+        while !panicked.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        dispatch
+            .pipelines_in_flight
+            .insert(symbol.clone(), panicked);
+        dispatch
+            .pipelines_in_flight
+            .insert("ETH/USD".to_string(), spawn(std::future::pending()));
+
+        dispatch.supervise_pipelines().await.unwrap();
+
+        // the panic is reported and the run forgotten, the running one left alone
+        assert!(!dispatch.pipelines_in_flight.contains_key(&symbol));
+        assert!(dispatch.pipelines_in_flight.contains_key("ETH/USD"));
+        match dispatch.action_receiver.recv().await {
+            Some(Action::Warn(warning)) => {
+                assert!(warning.contains(&symbol));
+                assert!(warning.contains("run panicked"));
+            }
+            _ => panic!("panicked run not reported"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_splat_mode() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+
+        // the profile alone bins exactly, the other views keep smoothing
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::ToggleSplatMode(Some(View::Profile)))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            dispatch.pipeline.splat_mode(View::Profile),
+            SplatMode::Histogram
+        );
+        assert_eq!(
+            dispatch.pipeline.splat_mode(View::Map),
+            SplatMode::Kernel(Kernel::default())
+        );
+        match dispatch.action_receiver.try_recv() {
+            Ok(Action::Inform(message)) => {
+                assert_eq!(message, "Splat mode of profile set to Histogram")
+            }
+            _ => panic!("the switch was not reported"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spawn_pipeline_timings() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+        let symbol = "BTC/USD".to_string();
+        let state = dispatch.app.get_state();
+        {
+            let mut locked_state = state.lock().await;
+            locked_state.current_ticker = Some(symbol.clone());
+            // any run is slower than a display updated continuously
+            locked_state.cadence.pipeline = Duration::ZERO;
+        }
+
+        Dispatch::spawn_pipeline(
+            symbol.clone(),
+            filled_history(&symbol, 10).await,
+            dispatch.pipeline.clone(),
+            state.clone(),
+            dispatch.action_sender.clone(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .await
+        .unwrap();
+
+        let locked_state = state.lock().await;
+        let names = locked_state
+            .pipeline_timings
+            .iter()
+            .map(|timing| timing.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names[0], "Grid");
+        assert!(names.contains(&"Blocks"));
+        assert!(locked_state.views[&symbol].pipeline_elapsed.is_some());
+        match dispatch.action_receiver.try_recv() {
+            Ok(Action::Warn(message)) => {
+                assert!(message.starts_with("Pipeline run of BTC/USD took"))
+            }
+            _ => panic!("the slow run was not reported"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_time_travel() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+        let symbol = "BTC/USD".to_string();
+        let history = filled_history(&symbol, 50).await;
+        let latest = history.latest_time().await.unwrap();
+        dispatch.books.cache.insert(symbol.clone(), history);
+        let state = dispatch.app.get_state();
+        {
+            let mut locked_state = state.lock().await;
+            locked_state.current_ticker = Some(symbol.clone());
+            locked_state.time_zoom = 1;
+            // panned further back than the cached history allows
+            locked_state.time_pan = 100;
+        }
+
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::RunPipeline(symbol.clone()))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+        timeout(Duration::from_secs(5), async {
+            while !dispatch.pipelines_in_flight[&symbol].is_finished() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // half the visual window ends as far back as the cached minute allows
+        let locked_state = state.lock().await;
+        assert_eq!(locked_state.time_window, Some(30));
+        assert_eq!(locked_state.time_cached, Some(60));
+        assert_eq!(locked_state.time_pan, 30);
+        let time_range = locked_state.views[&symbol]
+            .splatted
+            .iter()
+            .find_map(|splatted| match splatted {
+                Splatted::Blocks(blocks) => Some(blocks.grid.time_range),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(time_range, (latest - 60, latest - 30));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_prompt_settings() {
+        let (mut dispatch, requests) = attached_dispatch(10).await;
+        dispatch.stage_subscription("BTC/USD".to_string());
+
+        // windows longer than the cached books are turned down
+        let sender = dispatch.action_sender.clone();
+        for action in [
+            Action::SetTimeWindow(30),
+            Action::SetTimeWindow(600),
+            Action::SetBookDepth(500),
+            Action::Quit,
+        ] {
+            sender.send(action).await.unwrap();
+        }
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dispatch.pipeline.time_window(), 30);
+        let mut messages = vec![];
+        while let Ok(action) = dispatch.action_receiver.try_recv() {
+            match action {
+                Action::Inform(message) | Action::Warn(message) => messages.push(message),
+                _ => (),
+            }
+        }
+        for message in [
+            "Showing the last 30s of books",
+            "Cannot show 600s back, only the last 60s of books are cached",
+            "Books now requested 500 levels deep",
+        ] {
+            assert!(messages.iter().any(|received| received == message));
+        }
+
+        // the new depth resubscribes the ticker, attached feeds unsubscribing on their side
+        timeout(Duration::from_secs(5), async {
+            while requests.lock().await.len() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let subscription = r#"{"subscribe":{"symbol":"BTC/USD"}}"#;
+        assert_eq!(*requests.lock().await, vec![subscription, subscription]);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_diagnostics() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+        let symbol = "BTC/USD".to_string();
+        dispatch
+            .books
+            .cache
+            .insert(symbol.clone(), filled_history(&symbol, 20).await);
+        dispatch.reconnects = 2;
+
+        let sender = dispatch.action_sender.clone();
+        sender
+            .send(Action::RunPipeline(symbol.clone()))
+            .await
+            .unwrap();
+        sender.send(Action::Quit).await.unwrap();
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the pipeline run hands the health of the session over to the interface
+        let state = dispatch.app.get_state();
+        {
+            let mut locked_state = state.lock().await;
+            let diagnostics = &locked_state.diagnostics;
+            assert_eq!(diagnostics.queue_capacity, 10);
+            assert_eq!(diagnostics.reconnects, 2);
+            assert_eq!(diagnostics.caches.len(), 1);
+            assert_eq!(diagnostics.caches[0].symbol, symbol);
+            assert_eq!(diagnostics.caches[0].books, 40);
+            assert!(diagnostics.caches[0].bytes > 0);
+            locked_state.page = Page::Diagnostics;
+        }
+        let drawn = dispatch.app.screenshot().await.unwrap();
+        let text = drawn
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+        assert!(text.contains("Reconnects      2"));
+        assert!(text.contains("Action queue"));
+        assert!(text.contains("BTC/USD"));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_recoverable_error() {
+        let (mut dispatch, _) = attached_dispatch(10).await;
+
+        // a book of an unknown symbol is reported instead of stopping the dispatch
+        let sender = dispatch.action_sender.clone();
+        for action in [
+            Action::UpdateBook(booked("ETH/USD", 99.0, 101.0)),
+            Action::Error("Could not write the recording".to_string()),
+            Action::Quit,
+        ] {
+            sender.send(action).await.unwrap();
+        }
+        timeout(Duration::from_secs(5), dispatch.run())
+            .await
+            .unwrap()
+            .unwrap();
+        match dispatch.action_receiver.try_recv() {
+            Ok(Action::Error(message)) => assert_eq!(
+                message,
+                "Got book update for ETH/USD while symbol was absent from cache."
+            ),
+            _ => panic!("the unknown book was not reported"),
+        }
+
+        // errors are logged and shown until dismissed
+        let state = dispatch.app.get_state();
+        let locked_state = state.lock().await;
+        assert_eq!(
+            locked_state.error.as_deref(),
+            Some("Could not write the recording")
+        );
+        let entry = locked_state.logs.entries().last().unwrap();
+        assert_eq!(entry.severity, Severity::Error);
+        assert_eq!(entry.message, "Could not write the recording");
+    }
+
+    #[test]
+    fn test_tuning_flags() {
+        let cli = Cli::try_parse_from([
+            "bookedblocks",
+            "watch",
+            "BTC/USD",
+            "--depth",
+            "500",
+            "--cache-window",
+            "120",
+            "--visual-window",
+            "60",
+            "--time-res",
+            "100",
+            "--price-res",
+            "50",
+            "--buffer-size",
+            "20",
+            "--ws-timeout",
+            "30",
+        ])
+        .unwrap();
+        let Command::Watch { ticker, options } = cli.command else {
+            panic!("Expected the watch command");
+        };
+        assert_eq!(ticker, "BTC/USD");
+        assert_eq!(options.depth, Some(500));
+        assert_eq!(options.cache_window, Some(120));
+        assert_eq!(options.visual_window, Some(60));
+        assert_eq!(options.time_res, Some(100));
+        assert_eq!(options.price_res, Some(50));
+        assert_eq!(options.buffer_size, Some(20));
+        assert_eq!(options.ws_timeout, Some(30));
+
+        let cli = Cli::try_parse_from(["bookedblocks", "watch", "BTC/USD"]).unwrap();
+        let Command::Watch { options, .. } = cli.command else {
+            panic!("Expected the watch command");
+        };
+        assert_eq!(options.depth, None);
+        assert_eq!(options.cache_window, None);
+
+        for (flag, value) in [
+            ("--depth", "50"),
+            ("--cache-window", "0"),
+            ("--visual-window", "0"),
+            ("--time-res", "-1"),
+            ("--price-res", "many"),
+            ("--buffer-size", "0"),
+            ("--ws-timeout", "0"),
+        ] {
+            assert!(
+                Cli::try_parse_from(["bookedblocks", "watch", "BTC/USD", flag, value]).is_err(),
+                "{} {} should be rejected",
+                flag,
+                value
+            );
+        }
+
+        assert_eq!(
+            parse_depth("50"),
+            Err(format!("depth should be one of {:?}", BOOK_DEPTHS))
+        );
+        assert_eq!(
+            parse_count("0"),
+            Err("count should be positive".to_string())
+        );
+        assert_eq!(
+            parse_seconds("0"),
+            Err("seconds should be positive".to_string())
+        );
+        assert_eq!(parse_seconds("15"), Ok(15));
+    }
 }
@@ -0,0 +1,239 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Summary of the time taken to handle one kind of action
+#[derive(Clone, Debug, PartialEq)]
+pub struct HandlerStats {
+    pub name: &'static str,
+    /// number of actions handled since startup
+    pub count: usize,
+    /// number of actions that took longer than the budget since startup
+    pub over_budget: usize,
+    /// number of actions skipped since startup, e.g. while a previous one was still in flight or
+    /// when kept out of a backed up action queue
+    pub dropped: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// private utility method for the nearest rank percentile of sorted durations
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Rolling handling time metrics of every kind of action going through the dispatch loop
+#[derive(Clone, Debug)]
+pub struct ActionMetrics {
+    /// time above which handling an action is considered to stall the loop
+    pub budget: Duration,
+    /// number of most recent samples kept per kind of action
+    pub window: usize,
+    samples: HashMap<&'static str, VecDeque<Duration>>,
+    counts: HashMap<&'static str, (usize, usize)>,
+    drops: HashMap<&'static str, usize>,
+}
+
+impl ActionMetrics {
+    /// constructor
+    pub fn new(budget: Duration, window: usize) -> ActionMetrics {
+        ActionMetrics {
+            budget,
+            window,
+            samples: HashMap::new(),
+            counts: HashMap::new(),
+            drops: HashMap::new(),
+        }
+    }
+
+    /// record the time taken to handle an action, returning whether it exceeded the budget
+    pub fn record(&mut self, name: &'static str, elapsed: Duration) -> bool {
+        let samples = self.samples.entry(name).or_default();
+        samples.push_back(elapsed);
+        while samples.len() > self.window {
+            samples.pop_front();
+        }
+
+        let over_budget = elapsed > self.budget;
+        let counts = self.counts.entry(name).or_default();
+        counts.0 += 1;
+        if over_budget {
+            counts.1 += 1;
+        }
+        over_budget
+    }
+
+    /// record an action skipped instead of being handled
+    pub fn record_drop(&mut self, name: &'static str) {
+        *self.drops.entry(name).or_default() += 1;
+    }
+
+    /// record a number of actions kept out of the action queue instead of being handled
+    pub fn record_drops(&mut self, name: &'static str, count: usize) {
+        *self.drops.entry(name).or_default() += count;
+    }
+
+    /// summary of every kind of action handled or dropped so far, sorted by name
+    pub fn stats(&self) -> Vec<HandlerStats> {
+        let mut names = self.samples.keys().collect::<Vec<_>>();
+        names.extend(
+            self.drops
+                .keys()
+                .filter(|name| !self.samples.contains_key(*name)),
+        );
+        let mut stats = names
+            .into_iter()
+            .map(|name| {
+                let mut sorted = self
+                    .samples
+                    .get(name)
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                sorted.sort();
+                let (count, over_budget) = self.counts.get(name).cloned().unwrap_or_default();
+                HandlerStats {
+                    name,
+                    count,
+                    over_budget,
+                    dropped: self.drops.get(name).cloned().unwrap_or_default(),
+                    p50: percentile(&sorted, 0.5),
+                    p90: percentile(&sorted, 0.9),
+                    p99: percentile(&sorted, 0.99),
+                    max: sorted.last().cloned().unwrap_or_default(),
+                }
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by_key(|stats| stats.name);
+        stats
+    }
+}
+
+/// Size of the cached history of one symbol
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheStats {
+    pub symbol: String,
+    /// number of book snapshots held, counting both sides
+    pub books: usize,
+    pub trades: usize,
+    /// rough estimate of the memory held in bytes
+    pub bytes: usize,
+}
+
+/// Health of the session since startup, shown by the diagnostics page
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    pub uptime: Duration,
+    /// number of actions waiting in the queue
+    pub queued: usize,
+    pub queue_capacity: usize,
+    /// number of times the feed was connected again after its listener stopped
+    pub reconnects: usize,
+    pub caches: Vec<CacheStats>,
+}
+
+/// Rolling rate of the messages received over a time window
+#[derive(Clone, Debug)]
+pub struct MessageRate {
+    pub window: Duration,
+    arrivals: VecDeque<Instant>,
+}
+
+impl MessageRate {
+    /// constructor
+    pub fn new(window: Duration) -> MessageRate {
+        MessageRate {
+            window,
+            arrivals: VecDeque::new(),
+        }
+    }
+
+    /// record a message received at an instant, forgetting those older than the window
+    pub fn record(&mut self, at: Instant) {
+        self.arrivals.push_back(at);
+        while let Some(arrival) = self.arrivals.front()
+            && at.saturating_duration_since(*arrival) > self.window
+        {
+            self.arrivals.pop_front();
+        }
+    }
+
+    /// messages per second received over the window up to an instant
+    pub fn rate(&self, at: Instant) -> f64 {
+        let received = self
+            .arrivals
+            .iter()
+            .filter(|arrival| at.saturating_duration_since(**arrival) <= self.window)
+            .count();
+        received as f64 / self.window.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let sorted = (1..=10).map(Duration::from_millis).collect::<Vec<_>>();
+        assert_eq!(percentile(&sorted, 0.5), Duration::from_millis(5));
+        assert_eq!(percentile(&sorted, 0.9), Duration::from_millis(9));
+        assert_eq!(percentile(&sorted, 0.99), Duration::from_millis(10));
+        assert_eq!(percentile(&sorted, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 0.5), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_action_metrics() {
+        let mut metrics = ActionMetrics::new(Duration::from_millis(50), 4);
+        assert!(metrics.stats().is_empty());
+
+        for millis in [10, 20, 30, 40] {
+            assert!(!metrics.record("UpdateBook", Duration::from_millis(millis)));
+        }
+        assert!(metrics.record("UpdateBook", Duration::from_millis(100)));
+        assert!(!metrics.record("RunPipeline", Duration::from_millis(1)));
+        metrics.record_drop("RunPipeline");
+        metrics.record_drop("RunPipeline");
+
+        let stats = metrics.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].name, "RunPipeline");
+        assert_eq!(stats[1].name, "UpdateBook");
+        assert_eq!(stats[0].dropped, 2);
+        assert_eq!(stats[1].dropped, 0);
+        assert_eq!(stats[1].count, 5);
+        assert_eq!(stats[1].over_budget, 1);
+        assert_eq!(stats[1].p50, Duration::from_millis(30));
+        assert_eq!(stats[1].max, Duration::from_millis(100));
+
+        // actions kept out of the queue show up before any was handled
+        metrics.record_drops("Inform", 3);
+        let stats = metrics.stats();
+        assert_eq!(stats[0].name, "Inform");
+        assert_eq!(stats[0].count, 0);
+        assert_eq!(stats[0].dropped, 3);
+    }
+
+    #[test]
+    fn test_message_rate() {
+        let start = Instant::now();
+        let mut rate = MessageRate::new(Duration::from_secs(2));
+        assert_eq!(rate.rate(start), 0.0);
+
+        for millis in [0, 500, 1000, 1500] {
+            rate.record(start + Duration::from_millis(millis));
+        }
+        assert_eq!(rate.rate(start + Duration::from_millis(1500)), 2.0);
+        // messages older than the window no longer count
+        assert_eq!(rate.rate(start + Duration::from_millis(3000)), 1.0);
+        rate.record(start + Duration::from_millis(4000));
+        assert_eq!(rate.rate(start + Duration::from_millis(4000)), 0.5);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::error::BookedError;
+use crate::walls::Side;
+
+use kraken_async_rs::response_types::{BuySell, ExtendedBalances, Order, OrderStatusV2};
+use kraken_async_rs::wss::ExecutionResult;
+
+use num_traits::cast::ToPrimitive;
+use rust_decimal::Decimal;
+
+use std::collections::HashMap;
+
+/// Order of the user resting in the book
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnOrder {
+    pub side: Side,
+    pub price: f64,
+    /// quantity left to fill
+    pub remaining: f64,
+}
+
+/// Change to an order of the user reported by the executions channel, None for unchanged fields
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderUpdate {
+    pub id: String,
+    pub symbol: Option<String>,
+    pub side: Option<Side>,
+    pub price: Option<f64>,
+    pub quantity: Option<f64>,
+    pub filled: Option<f64>,
+    /// whether the order stopped resting, being filled, cancelled or expired
+    pub closed: bool,
+}
+
+impl OrderUpdate {
+    /// convert from kraken_async_rs
+    pub fn from_execution(execution: ExecutionResult) -> OrderUpdate {
+        OrderUpdate {
+            id: execution.order_id,
+            symbol: execution.symbol,
+            side: execution.side.map(|side| match side {
+                BuySell::Buy => Side::Bid,
+                BuySell::Sell => Side::Ask,
+            }),
+            price: execution.limit_price.and_then(|price| price.to_f64()),
+            quantity: execution
+                .order_quantity
+                .and_then(|quantity| quantity.to_f64()),
+            filled: execution
+                .cumulative_quantity
+                .and_then(|quantity| quantity.to_f64()),
+            closed: matches!(
+                execution.order_status,
+                OrderStatusV2::Filled | OrderStatusV2::Canceled | OrderStatusV2::Expired
+            ),
+        }
+    }
+}
+
+/// Orders of the user resting in the books, merged from the updates of the executions channel
+#[derive(Debug, Default)]
+pub struct OwnOrders {
+    orders: HashMap<String, OrderUpdate>,
+}
+
+impl OwnOrders {
+    /// merge an update into the order it changes, forgetting orders no longer resting
+    pub fn apply(&mut self, update: OrderUpdate) {
+        if update.closed {
+            self.orders.remove(&update.id);
+            return;
+        }
+        let order = self.orders.entry(update.id.clone()).or_insert(OrderUpdate {
+            id: update.id.clone(),
+            ..OrderUpdate::default()
+        });
+        order.symbol = update.symbol.or(order.symbol.take());
+        order.side = update.side.or(order.side);
+        order.price = update.price.or(order.price);
+        order.quantity = update.quantity.or(order.quantity);
+        order.filled = update.filled.or(order.filled);
+    }
+
+    /// limit orders of a symbol resting in its book, as far as their level is known
+    pub fn resting(&self, symbol: &str) -> Vec<OwnOrder> {
+        self.orders
+            .values()
+            .filter(|order| order.symbol.as_deref() == Some(symbol))
+            .filter_map(|order| match (order.side, order.price, order.quantity) {
+                (Some(side), Some(price), Some(quantity)) => Some(OwnOrder {
+                    side,
+                    price,
+                    remaining: quantity - order.filled.unwrap_or(0.0),
+                }),
+                _ => None,
+            })
+            .filter(|order| order.remaining > 0.0)
+            .collect()
+    }
+}
+
+/// Amount of an asset held by the user
+#[derive(Clone, Debug, PartialEq)]
+pub struct Balance {
+    pub asset: String,
+    pub total: f64,
+    /// part of the total reserved by open orders
+    pub held: f64,
+}
+
+/// Order of the user open on the exchange, as listed by the REST API
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenOrder {
+    pub id: String,
+    /// pair as named by the REST API, e.g. XBTUSD
+    pub pair: String,
+    pub side: Side,
+    /// order type, e.g. limit or market
+    pub kind: String,
+    /// limit price, zero for orders without one
+    pub price: f64,
+    pub quantity: f64,
+    pub filled: f64,
+}
+
+/// Balances and open orders of the user, polled from the REST API
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Account {
+    /// non zero balances, in alphabetical order of their asset
+    pub balances: Vec<Balance>,
+    /// open orders, the most recently opened first
+    pub open_orders: Vec<OpenOrder>,
+}
+
+impl Account {
+    /// convert from kraken_async_rs
+    pub fn from_responses(
+        balances: ExtendedBalances,
+        open_orders: HashMap<String, Order>,
+    ) -> Result<Account, BookedError> {
+        let number = |value: Decimal| {
+            value
+                .to_f64()
+                .ok_or_else(|| BookedError::Decimal(format!("{:?}", value)))
+        };
+        let mut balances = balances
+            .into_iter()
+            .filter(|(_, balance)| !balance.balance.is_zero())
+            .map(|(asset, balance)| {
+                Ok(Balance {
+                    asset,
+                    total: number(balance.balance)?,
+                    held: number(balance.hold_trade)?,
+                })
+            })
+            .collect::<Result<Vec<_>, BookedError>>()?;
+        balances.sort_by(|lhs, rhs| lhs.asset.cmp(&rhs.asset));
+
+        let mut opened = open_orders
+            .into_iter()
+            .map(|(id, order)| {
+                let open_order = OpenOrder {
+                    id,
+                    side: match order.descr.side {
+                        BuySell::Buy => Side::Bid,
+                        BuySell::Sell => Side::Ask,
+                    },
+                    kind: format!("{:?}", order.descr.order_type).to_lowercase(),
+                    price: number(order.descr.price)?,
+                    quantity: number(order.volume)?,
+                    filled: number(order.volume_executed)?,
+                    pair: order.descr.pair,
+                };
+                Ok((order.open_time, open_order))
+            })
+            .collect::<Result<Vec<_>, BookedError>>()?;
+        opened.sort_by(|lhs, rhs| rhs.0.total_cmp(&lhs.0));
+
+        Ok(Account {
+            balances,
+            open_orders: opened.into_iter().map(|(_, order)| order).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_orders() {
+        let mut orders = OwnOrders::default();
+        orders.apply(OrderUpdate {
+            id: "A".to_string(),
+            symbol: Some("BTC/USD".to_string()),
+            side: Some(Side::Bid),
+            price: Some(100.0),
+            quantity: Some(2.0),
+            ..OrderUpdate::default()
+        });
+        // a fill only reports the quantity filled so far
+        orders.apply(OrderUpdate {
+            id: "A".to_string(),
+            filled: Some(0.5),
+            ..OrderUpdate::default()
+        });
+        // a market order never rests at a level
+        orders.apply(OrderUpdate {
+            id: "B".to_string(),
+            symbol: Some("BTC/USD".to_string()),
+            side: Some(Side::Ask),
+            quantity: Some(1.0),
+            ..OrderUpdate::default()
+        });
+        assert_eq!(
+            orders.resting("BTC/USD"),
+            vec![OwnOrder {
+                side: Side::Bid,
+                price: 100.0,
+                remaining: 1.5,
+            }]
+        );
+        assert!(orders.resting("ETH/USD").is_empty());
+
+        orders.apply(OrderUpdate {
+            id: "A".to_string(),
+            closed: true,
+            ..OrderUpdate::default()
+        });
+        assert!(orders.resting("BTC/USD").is_empty());
+    }
+
+    #[test]
+    fn test_account() {
+        let balances: ExtendedBalances = serde_json::from_str(
+            r#"{
+                "ZUSD": {"balance": "1500.50", "hold_trade": "100.0"},
+                "XXBT": {"balance": "0.25", "hold_trade": "0"},
+                "XETH": {"balance": "0", "hold_trade": "0"}
+            }"#,
+        )
+        .unwrap();
+        let order = |open_time: f64, side: &str, price: &str| {
+            serde_json::from_str::<Order>(&format!(
+                r#"{{
+                    "refid": null, "userref": 0, "status": "open", "opentm": {},
+                    "starttm": 0, "expiretm": 0,
+                    "descr": {{
+                        "pair": "XBTUSD", "type": "{}", "ordertype": "limit", "price": "{}",
+                        "price2": "0", "leverage": "none", "order": "", "close": ""
+                    }},
+                    "vol": "1.5", "vol_exec": "0.5", "cost": "0", "fee": "0", "price": "0",
+                    "stopprice": "0", "limitprice": "0", "misc": "", "oflags": "fciq"
+                }}"#,
+                open_time, side, price
+            ))
+            .unwrap()
+        };
+        let open_orders = HashMap::from([
+            ("OLD".to_string(), order(1.0, "buy", "100.5")),
+            ("NEW".to_string(), order(2.0, "sell", "110")),
+        ]);
+
+        let account = Account::from_responses(balances, open_orders).unwrap();
+        assert_eq!(
+            account.balances,
+            vec![
+                Balance {
+                    asset: "XXBT".to_string(),
+                    total: 0.25,
+                    held: 0.0,
+                },
+                Balance {
+                    asset: "ZUSD".to_string(),
+                    total: 1500.5,
+                    held: 100.0,
+                },
+            ]
+        );
+        assert_eq!(
+            account.open_orders[0],
+            OpenOrder {
+                id: "NEW".to_string(),
+                pair: "XBTUSD".to_string(),
+                side: Side::Ask,
+                kind: "limit".to_string(),
+                price: 110.0,
+                quantity: 1.5,
+                filled: 0.5,
+            }
+        );
+        assert_eq!(account.open_orders[1].id, "OLD");
+        assert_eq!(account.open_orders[1].side, Side::Bid);
+    }
+}
@@ -0,0 +1,577 @@
+use crate::error::BookedError;
+use crate::feed::{Booked, Order};
+use crate::pipeline::BookHistory;
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::DateTime;
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::record::RowAccessor;
+use parquet::schema::parser::parse_message_type;
+
+use std::collections::HashMap;
+use std::fs::{File, create_dir_all, read_dir, rename};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Schema of persisted book levels, one row per level of every book snapshot
+const SCHEMA: &str = "
+message book {
+    REQUIRED INT64 timestamp (TIMESTAMP(MILLIS, true));
+    REQUIRED BYTE_ARRAY side (UTF8);
+    REQUIRED DOUBLE price;
+    REQUIRED DOUBLE quantity;
+}
+";
+
+/// Completed time slice of the book history of a symbol, in columns
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Slice {
+    pub symbol: String,
+    /// start of the slice in unix seconds, included
+    pub start: i64,
+    /// end of the slice in unix seconds, excluded
+    pub end: i64,
+    pub timestamps: Vec<i64>,
+    /// "ask" or "bid"
+    pub sides: Vec<&'static str>,
+    pub prices: Vec<f64>,
+    pub quantities: Vec<f64>,
+}
+
+impl Slice {
+    /// append every level of the books of a snapshot
+    fn push_levels(
+        &mut self,
+        time: i64,
+        side: &'static str,
+        levels: impl Iterator<Item = (f64, f64)>,
+    ) {
+        for (price, quantity) in levels {
+            self.timestamps.push(time);
+            self.sides.push(side);
+            self.prices.push(price);
+            self.quantities.push(quantity);
+        }
+    }
+}
+
+/// Keeps track of the time slices of book histories already flushed to disk
+pub struct HistoryPersister {
+    /// root of the `symbol=<SYMBOL>/date=<YYYY-MM-DD>` partitions
+    pub directory: PathBuf,
+    /// length in seconds of the slices written to each file
+    pub slice_in_seconds: i64,
+    /// end of the last slice flushed for every symbol
+    flushed_until: HashMap<String, i64>,
+}
+
+impl HistoryPersister {
+    /// constructor
+    pub fn new(directory: PathBuf, slice_in_seconds: i64) -> HistoryPersister {
+        HistoryPersister {
+            directory,
+            slice_in_seconds: slice_in_seconds.max(1),
+            flushed_until: HashMap::new(),
+        }
+    }
+
+    /// slices of a history completed since the last call, marking them as flushed; the slice
+    /// holding the latest book is left for later as it may still grow
+    pub async fn completed_slices(&mut self, symbol: &str, history: &BookHistory) -> Vec<Slice> {
+        self.slices_until(symbol, history, false).await
+    }
+
+    /// every slice of a history not flushed yet, including the one holding the latest book,
+    /// e.g. once the history is complete
+    pub async fn remaining_slices(&mut self, symbol: &str, history: &BookHistory) -> Vec<Slice> {
+        self.slices_until(symbol, history, true).await
+    }
+
+    /// private utility method for the slices of a history not flushed yet, up to the one holding
+    /// the latest book included or not
+    async fn slices_until(
+        &mut self,
+        symbol: &str,
+        history: &BookHistory,
+        latest_included: bool,
+    ) -> Vec<Slice> {
+        let readable_asks = history.asks.read().await;
+        let readable_bids = history.bids.read().await;
+
+        let length = self.slice_in_seconds;
+        let (first, latest) = match (readable_asks.get_first(), readable_asks.get_last()) {
+            (Some((first, _)), Some((latest, _))) => (*first, *latest),
+            _ => return vec![],
+        };
+        let completed_until = match latest_included {
+            true => (latest.div_euclid(length) + 1) * length,
+            false => latest.div_euclid(length) * length,
+        };
+        let flushed_until = *self
+            .flushed_until
+            .entry(symbol.to_string())
+            .or_insert(first.div_euclid(length) * length);
+        if completed_until <= flushed_until {
+            return vec![];
+        }
+
+        let mut slices: Vec<Slice> = Vec::new();
+        for ((time, asks), (_, bids)) in readable_asks.iter().zip(readable_bids.iter()) {
+            if *time < flushed_until || *time >= completed_until {
+                continue;
+            }
+            let start = time.div_euclid(length) * length;
+            if slices.last().is_none_or(|slice| slice.start != start) {
+                slices.push(Slice {
+                    symbol: symbol.to_string(),
+                    start,
+                    end: start + length,
+                    ..Default::default()
+                });
+            }
+            let slice = slices.last_mut().unwrap();
+            slice.push_levels(
+                *time,
+                "ask",
+                asks.iter()
+                    .map(|(price, quantity)| (price.value, *quantity)),
+            );
+            slice.push_levels(
+                *time,
+                "bid",
+                bids.iter()
+                    .map(|(price, quantity)| (price.value, *quantity)),
+            );
+        }
+
+        self.flushed_until
+            .insert(symbol.to_string(), completed_until);
+        slices
+    }
+
+    /// consider the slices of a symbol up to the one holding a time as already flushed, e.g.
+    /// after loading them back, so they are not overwritten with partial books
+    pub fn resume_after(&mut self, symbol: &str, time: i64) {
+        let length = self.slice_in_seconds;
+        self.flushed_until.insert(
+            symbol.to_string(),
+            (time + length - 1).div_euclid(length) * length,
+        );
+    }
+}
+
+/// path of the parquet file of a slice, partitioned by symbol and day
+pub fn slice_path(directory: &Path, slice: &Slice) -> PathBuf {
+    let date = match DateTime::from_timestamp(slice.start, 0) {
+        Some(time) => time.format("%Y-%m-%d").to_string(),
+        None => "unknown".to_string(),
+    };
+    directory
+        .join(format!("symbol={}", slice.symbol.replace('/', "-")))
+        .join(format!("date={}", date))
+        .join(format!("{}-{}.parquet", slice.start, slice.end))
+}
+
+/// write a slice to its snappy compressed parquet file, through a temporary file so readers
+/// never see a partial slice
+pub fn write_slice(directory: &Path, slice: &Slice) -> Result<PathBuf, BookedError> {
+    let path = slice_path(directory, slice);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension("parquet.tmp");
+
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let file = File::create(&temporary)?;
+
+    let timestamps = slice
+        .timestamps
+        .iter()
+        .map(|time| time * 1000)
+        .collect::<Vec<_>>();
+    let sides = slice
+        .sides
+        .iter()
+        .map(|side| ByteArray::from(*side))
+        .collect::<Vec<_>>();
+
+    let mut writer = SerializedFileWriter::new(file, schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match index {
+            0 => {
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&timestamps, None, None)?;
+            }
+            1 => {
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&sides, None, None)?;
+            }
+            2 => {
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&slice.prices, None, None)?;
+            }
+            _ => {
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&slice.quantities, None, None)?;
+            }
+        }
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    rename(&temporary, &path)?;
+    Ok(path)
+}
+
+/// write the slices of the book history of a symbol to an Arrow IPC file with the columns of the
+/// parquet files, one record batch per slice and the symbol in the schema metadata, for Python
+/// and Polars users to memory map
+pub fn write_arrow(path: &Path, symbol: &str, slices: &[Slice]) -> Result<(), BookedError> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let schema = Arc::new(Schema::new_with_metadata(
+        vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+                false,
+            ),
+            Field::new("side", DataType::Utf8, false),
+            Field::new("price", DataType::Float64, false),
+            Field::new("quantity", DataType::Float64, false),
+        ],
+        HashMap::from([("symbol".to_string(), symbol.to_string())]),
+    ));
+    let file = File::create(path)?;
+
+    let mut writer = FileWriter::try_new(file, &schema)?;
+    for slice in slices.iter() {
+        let timestamps = slice.timestamps.iter().map(|time| time * 1000);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from_iter_values(timestamps).with_timezone("UTC")),
+            Arc::new(StringArray::from(slice.sides.clone())),
+            Arc::new(Float64Array::from(slice.prices.clone())),
+            Arc::new(Float64Array::from(slice.quantities.clone())),
+        ];
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// private utility method for the parquet files of a symbol holding books from a time on,
+/// ordered by the start of their slice
+fn slice_files(directory: &Path, symbol: &str, since: i64) -> Result<Vec<PathBuf>, BookedError> {
+    let root = directory.join(format!("symbol={}", symbol.replace('/', "-")));
+    if !root.is_dir() {
+        return Ok(vec![]);
+    }
+    let partitions = read_dir(&root)?;
+
+    let mut files = Vec::new();
+    for partition in partitions.flatten() {
+        let Ok(entries) = read_dir(partition.path()) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path
+                .extension()
+                .is_none_or(|extension| extension != "parquet")
+            {
+                continue;
+            }
+            let bounds = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.split_once('-'))
+                .and_then(|(start, end)| {
+                    Some((start.parse::<i64>().ok()?, end.parse::<i64>().ok()?))
+                });
+            if let Some((start, end)) = bounds
+                && end > since
+            {
+                files.push((start, path));
+            }
+        }
+    }
+    files.sort_by_key(|(start, _)| *start);
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// read the levels of a parquet file written by `write_slice`
+fn read_slice(path: &Path) -> Result<Vec<(i64, String, Order)>, BookedError> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+
+    let mut levels = Vec::new();
+    for row in reader.get_row_iter(None)? {
+        let row = row?;
+        levels.push((
+            row.get_timestamp_millis(0)?.div_euclid(1000),
+            row.get_string(1)?.clone(),
+            Order {
+                price: row.get_double(2)?,
+                quantity: row.get_double(3)?,
+            },
+        ));
+    }
+    Ok(levels)
+}
+
+/// load the books persisted for a symbol from a time on as full book snapshots, one per
+/// timestamp, to be replayed with `BookHistory::replace`
+pub fn load_history(
+    directory: &Path,
+    symbol: &str,
+    since: i64,
+) -> Result<Vec<Booked>, BookedError> {
+    let mut snapshots: Vec<(i64, Vec<Order>, Vec<Order>)> = Vec::new();
+    for path in slice_files(directory, symbol, since)? {
+        for (time, side, order) in read_slice(&path)? {
+            if time < since {
+                continue;
+            }
+            if snapshots.last().is_none_or(|(last, _, _)| *last != time) {
+                snapshots.push((time, vec![], vec![]));
+            }
+            let (_, asks, bids) = snapshots.last_mut().unwrap();
+            match side.as_str() {
+                "ask" => asks.push(order),
+                "bid" => bids.push(order),
+                _ => {
+                    return Err(BookedError::Storage(format!(
+                        "Unknown side {} in {}",
+                        side,
+                        path.display()
+                    )));
+                }
+            }
+        }
+    }
+    let mut updates = Vec::new();
+    for (time, asks, bids) in snapshots {
+        let timestamp = match DateTime::from_timestamp(time, 0) {
+            Some(timestamp) => timestamp.to_rfc3339(),
+            None => return Err(BookedError::Storage(format!("Invalid timestamp {}", time))),
+        };
+        updates.push(Booked {
+            symbol: symbol.to_string(),
+            timestamp,
+            asks,
+            bids,
+        });
+    }
+    Ok(updates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn booked_at(time: i64, shift: f64) -> Booked {
+        Booked {
+            symbol: "ETH/EUR".to_string(),
+            timestamp: DateTime::from_timestamp(time, 0).unwrap().to_rfc3339(),
+            asks: vec![Order {
+                price: 5.0 + shift,
+                quantity: 6.0,
+            }],
+            bids: vec![Order {
+                price: 1.0,
+                quantity: 2.0,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completed_slices() {
+        let mut history = BookHistory::new(600);
+        for time in 95..125 {
+            let _ = history.update(booked_at(time, 0.0)).await;
+        }
+
+        let mut persister = HistoryPersister::new(PathBuf::from("unused"), 10);
+        let slices = persister.completed_slices("ETH/EUR", &history).await;
+        // the slice from 120 holds the latest book and is still growing
+        itertools::assert_equal(
+            slices.iter().map(|slice| (slice.start, slice.end)),
+            [(90, 100), (100, 110), (110, 120)],
+        );
+        // two levels per book, five books in the first partial slice
+        assert_eq!(slices[0].timestamps.len(), 10);
+        assert_eq!(slices[1].timestamps.len(), 20);
+        assert!(
+            slices[1]
+                .timestamps
+                .iter()
+                .all(|time| (100..110).contains(time))
+        );
+
+        assert!(
+            persister
+                .completed_slices("ETH/EUR", &history)
+                .await
+                .is_empty()
+        );
+
+        for time in 125..131 {
+            let _ = history.update(booked_at(time, 0.0)).await;
+        }
+        let slices = persister.completed_slices("ETH/EUR", &history).await;
+        itertools::assert_equal(slices.iter().map(|slice| slice.start), [120]);
+    }
+
+    #[tokio::test]
+    async fn test_write_slice() {
+        let mut history = BookHistory::new(600);
+        let _ = history.update(booked_at(86_400, 0.0)).await;
+        let _ = history.update(booked_at(86_401, 1.0)).await;
+        let _ = history.update(booked_at(86_460, 0.0)).await;
+
+        let temporary = tempfile::tempdir().unwrap();
+        let directory = temporary.path().join("persistence");
+        let mut persister = HistoryPersister::new(directory.clone(), 60);
+        let slices = persister.completed_slices("ETH/EUR", &history).await;
+        assert_eq!(slices.len(), 1);
+
+        let path = write_slice(&directory, &slices[0]).unwrap();
+        assert_eq!(
+            path,
+            directory
+                .join("symbol=ETH-EUR")
+                .join("date=1970-01-02")
+                .join("86400-86460.parquet")
+        );
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                let row = row.unwrap();
+                (
+                    row.get_timestamp_millis(0).unwrap(),
+                    row.get_string(1).unwrap().clone(),
+                    row.get_double(2).unwrap(),
+                    row.get_double(3).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // the second update merges a new ask level into the book
+        assert_eq!(
+            rows,
+            vec![
+                (86_400_000, "ask".to_string(), 5.0, 6.0),
+                (86_400_000, "bid".to_string(), 1.0, 2.0),
+                (86_401_000, "ask".to_string(), 5.0, 6.0),
+                (86_401_000, "ask".to_string(), 6.0, 6.0),
+                (86_401_000, "bid".to_string(), 1.0, 2.0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_arrow() {
+        let mut history = BookHistory::new(600);
+        for time in 95..125 {
+            let _ = history.update(booked_at(time, 0.0)).await;
+        }
+        let mut persister = HistoryPersister::new(PathBuf::from("unused"), 10);
+        let slices = persister.remaining_slices("ETH/EUR", &history).await;
+        // the slice from 120 holding the latest book is included
+        assert_eq!(slices.len(), 4);
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("ETH-EUR.arrow");
+        write_arrow(&path, "ETH/EUR", &slices).unwrap();
+
+        let reader =
+            arrow_ipc::reader::FileReader::try_new(File::open(&path).unwrap(), None).unwrap();
+        assert_eq!(reader.schema().metadata()["symbol"], "ETH/EUR");
+        let batches = reader.map(|batch| batch.unwrap()).collect::<Vec<_>>();
+        assert_eq!(batches.len(), 4);
+        // two levels per book, thirty books
+        assert_eq!(
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+            60
+        );
+        let timestamps = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(timestamps.value(0), 95_000);
+    }
+
+    #[tokio::test]
+    async fn test_load_history() {
+        let mut history = BookHistory::new(600);
+        for time in 100..130 {
+            // the ask level jumps every 10 seconds, the previous one is removed
+            let mut booked = booked_at(time, (time / 10) as f64);
+            if time % 10 == 0 && time > 100 {
+                booked.asks.push(Order {
+                    price: 5.0 + (time / 10 - 1) as f64,
+                    quantity: 0.0,
+                });
+            }
+            let _ = history.update(booked).await;
+        }
+
+        let temporary = tempfile::tempdir().unwrap();
+        let directory = temporary.path().join("history");
+        let mut persister = HistoryPersister::new(directory.clone(), 10);
+        for slice in persister.completed_slices("ETH/EUR", &history).await {
+            write_slice(&directory, &slice).unwrap();
+        }
+
+        let updates = load_history(&directory, "ETH/EUR", 105).unwrap();
+        assert_eq!(updates.len(), 15);
+        let mut loaded = BookHistory::new(600);
+        for booked in updates {
+            loaded.replace(booked).await.unwrap();
+        }
+
+        let original = history.top_of_book_window(105, 119).await;
+        let reloaded = loaded.top_of_book_window(105, 119).await;
+        assert_eq!(original, reloaded);
+        assert_eq!(
+            loaded.asks.read().await.get(&112).unwrap().len(),
+            history.asks.read().await.get(&112).unwrap().len()
+        );
+        assert!(load_history(&directory, "BTC/EUR", 0).unwrap().is_empty());
+
+        persister.resume_after("BTC/EUR", 120);
+        let mut resumed = BookHistory::new(600);
+        for time in 115..135 {
+            let _ = resumed.update(booked_at(time, 0.0)).await;
+        }
+        itertools::assert_equal(
+            persister
+                .completed_slices("BTC/EUR", &resumed)
+                .await
+                .iter()
+                .map(|slice| slice.start),
+            [120],
+        );
+    }
+}
@@ -1,14 +1,23 @@
-use crate::feed::{Booked, Order};
-use crate::splat::{splat_1d, splat_2d};
+use crate::clock;
+use crate::error::BookedError;
+use crate::feed::{Aggressor, Booked, Order, Traded};
+use crate::splat::{Kernel, Normalization, SplatMode, project_1d, project_2d};
+use crate::walls::Side;
 
 use tokio::sync::RwLock;
+use tracing::trace;
 
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
+use clap::ValueEnum;
 use ndarray::Array2;
 use rbtree::RBTree;
 
 use std::cmp::{Ordering, max, min};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::iter::zip;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 /// Data structure for price with complete ordering
 #[derive(Clone, Debug, PartialOrd, PartialEq)]
@@ -24,13 +33,39 @@ impl Ord for Price {
     }
 }
 
+/// private utility method copying a book, cloning an empty rbtree is not supported
+fn copy_book(book: &RBTree<Price, f64>) -> RBTree<Price, f64> {
+    RBTree::from_iter(
+        book.iter()
+            .map(|(price, quantity)| (price.clone(), *quantity)),
+    )
+}
+
+/// Snapshot of one side of a book at a time
+type Snapshot = (i64, RBTree<Price, f64>);
+
+/// private utility method setting the quantity of a level, removing emptied levels
+fn set_level(book: &mut RBTree<Price, f64>, order: &Order) {
+    let price = Price { value: order.price };
+    if order.quantity == 0.0 {
+        book.remove(&price);
+    } else {
+        book.replace_or_insert(price, order.quantity);
+    }
+}
+
 /// private utility method for updating a book
+///
+/// updates are applied on the snapshot at or before their time: updates within the same second
+/// are merged into a single snapshot, late updates are inserted in time order and updates older
+/// than the whole history are merged into its oldest snapshot. Snapshots after a late update
+/// take its levels unless they changed them since.
 fn update_books(
     books: &mut RBTree<i64, RBTree<Price, f64>>,
     time_window: usize,
     incoming_time: i64,
     orders: Vec<Order>,
-) -> Result<Option<(i64, RBTree<Price, f64>)>, String> {
+) -> Result<Option<Snapshot>, BookedError> {
     if books.is_empty() {
         books.insert(
             incoming_time,
@@ -41,31 +76,103 @@ fn update_books(
             ),
         );
 
-        Ok(None)
-    } else {
-        let start_time = match books.get_first() {
-            Some((time, _)) => time.clone(),
-            None => return Err("Could not find oldest ask in book history.".to_string()),
-        };
+        return Ok(None);
+    }
 
-        let mut latest = match books.get_last() {
-            Some((_, latest)) => latest.clone(),
-            None => return Err("Could not find latest ask in book history.".to_string()),
-        };
+    let start_time = match books.get_first() {
+        Some((time, _)) => *time,
+        None => {
+            return Err(BookedError::History(
+                "could not find oldest ask in book history".to_string(),
+            ));
+        }
+    };
+    let incoming_time = max(incoming_time, start_time);
 
-        for order in orders.into_iter() {
-            let _ = latest.replace_or_insert(Price { value: order.price }, order.quantity);
-            latest = RBTree::from_iter(latest.into_iter().filter(|(_, value)| *value != 0.0));
+    let (base_time, base) = match books
+        .iter()
+        .take_while(|(time, _)| **time <= incoming_time)
+        .last()
+    {
+        Some((time, book)) => (*time, copy_book(book)),
+        None => {
+            return Err(BookedError::History(
+                "could not find book preceding update in book history".to_string(),
+            ));
         }
+    };
 
-        books.insert(incoming_time.clone(), latest);
+    let mut updated = copy_book(&base);
+    for order in orders.iter() {
+        set_level(&mut updated, order);
+    }
 
-        if (incoming_time - start_time).abs() as usize > time_window {
-            Ok(books.pop_first())
-        } else {
-            Ok(None)
+    for (_, book) in books
+        .iter_mut()
+        .skip_while(|(time, _)| **time <= incoming_time)
+    {
+        for order in orders.iter() {
+            let price = Price { value: order.price };
+            if book.get(&price) == base.get(&price) {
+                set_level(book, order);
+            }
         }
     }
+
+    if base_time == incoming_time {
+        match books.get_mut(&incoming_time) {
+            Some(book) => *book = updated,
+            None => {
+                return Err(BookedError::History(
+                    "could not find book to merge update into".to_string(),
+                ));
+            }
+        }
+        return Ok(None);
+    }
+    books.insert(incoming_time, updated);
+
+    let latest_time = match books.get_last() {
+        Some((time, _)) => *time,
+        None => {
+            return Err(BookedError::History(
+                "could not find latest ask in book history".to_string(),
+            ));
+        }
+    };
+    if (latest_time - start_time) as usize > time_window {
+        Ok(books.pop_first())
+    } else {
+        Ok(None)
+    }
+}
+
+/// Counts of levels that changed between two consecutive book snapshots
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LevelChanges {
+    /// levels appearing at a new price
+    pub added: usize,
+    /// levels whose quantity changed
+    pub amended: usize,
+    /// levels disappearing from the book
+    pub removed: usize,
+}
+
+/// private utility method for the levels that changed from a book snapshot to the next
+fn diff_levels(previous: &RBTree<Price, f64>, current: &RBTree<Price, f64>) -> LevelChanges {
+    let mut changes = LevelChanges::default();
+    for (price, quantity) in current.iter() {
+        match previous.get(price) {
+            None => changes.added += 1,
+            Some(previous_quantity) if previous_quantity != quantity => changes.amended += 1,
+            Some(_) => (),
+        }
+    }
+    changes.removed = previous
+        .iter()
+        .filter(|(price, _)| !current.contains_key(price))
+        .count();
+    changes
 }
 
 /// Order book history for a single ticker symbol
@@ -77,6 +184,8 @@ pub struct BookHistory {
     pub asks: RwLock<RBTree<i64, RBTree<Price, f64>>>,
     /// accelerated data storage for bids
     pub bids: RwLock<RBTree<i64, RBTree<Price, f64>>>,
+    /// trades executed within the time window, oldest first
+    pub trades: RwLock<VecDeque<(i64, Traded)>>,
 }
 
 impl BookHistory {
@@ -86,6 +195,7 @@ impl BookHistory {
             time_window_in_seconds,
             asks: RwLock::new(RBTree::new()),
             bids: RwLock::new(RBTree::new()),
+            trades: RwLock::new(VecDeque::new()),
         }
     }
 
@@ -93,10 +203,15 @@ impl BookHistory {
     pub async fn update(
         &mut self,
         booked: Booked,
-    ) -> Result<Option<((i64, RBTree<Price, f64>), (i64, RBTree<Price, f64>))>, String> {
+    ) -> Result<Option<((i64, RBTree<Price, f64>), (i64, RBTree<Price, f64>))>, BookedError> {
         let incoming_time = match DateTime::parse_from_rfc3339(&booked.timestamp) {
             Ok(time) => time.timestamp(),
-            Err(message) => return Err(format!("{:?}", message)),
+            Err(source) => {
+                return Err(BookedError::Timestamp {
+                    timestamp: booked.timestamp,
+                    source,
+                });
+            }
         };
 
         let writable_asks = &mut self.asks.write().await;
@@ -117,19 +232,17 @@ impl BookHistory {
             ),
         ) {
             (Ok(Some(ret_asks)), Ok(Some(ret_bids))) => Ok(Some((ret_asks, ret_bids))),
-            (Ok(Some(_)), Ok(None)) => {
-                Err("Removed entry from asks during update but not bids.".to_string())
-            }
-            (Ok(None), Ok(Some(_))) => {
-                Err("Removed entry from bids during update but not asks.".to_string())
-            }
+            (Ok(Some(_)), Ok(None)) => Err(BookedError::History(
+                "removed entry from asks during update but not bids".to_string(),
+            )),
+            (Ok(None), Ok(Some(_))) => Err(BookedError::History(
+                "removed entry from bids during update but not asks".to_string(),
+            )),
             (Ok(None), Ok(None)) => Ok(None),
             (Ok(_), Err(message)) => Err(message),
             (Err(message), Ok(_)) => Err(message),
-            (Err(ask_message), Err(bid_message)) => Err(format!(
-                "Ask update error: {}\nBid update error {}",
-                ask_message, bid_message
-            )),
+            // the asks are reported when both sides fail
+            (Err(message), Err(_)) => Err(message),
         }
     }
 
@@ -147,6 +260,98 @@ impl BookHistory {
         }
     }
 
+    /// update the history with a full snapshot of the book, removing the levels absent from it
+    pub async fn replace(
+        &mut self,
+        mut booked: Booked,
+    ) -> Result<Option<((i64, RBTree<Price, f64>), (i64, RBTree<Price, f64>))>, BookedError> {
+        for (books, orders) in [
+            (&self.asks, &mut booked.asks),
+            (&self.bids, &mut booked.bids),
+        ] {
+            let mut prices = orders.iter().map(|order| order.price).collect::<Vec<_>>();
+            prices.sort_by(f64::total_cmp);
+            if let Some((_, latest)) = books.read().await.get_last() {
+                let removed = latest
+                    .iter()
+                    .filter(|(price, _)| {
+                        prices
+                            .binary_search_by(|other| other.total_cmp(&price.value))
+                            .is_err()
+                    })
+                    .map(|(price, _)| Order {
+                        price: price.value,
+                        quantity: 0.0,
+                    })
+                    .collect::<Vec<_>>();
+                orders.extend(removed);
+            }
+        }
+        self.update(booked).await
+    }
+
+    /// record executed trades, forgetting those older than the time window
+    pub async fn update_trades(&mut self, trades: Vec<Traded>) -> Result<(), BookedError> {
+        let mut writable_trades = self.trades.write().await;
+        for trade in trades.into_iter() {
+            let time = match DateTime::parse_from_rfc3339(&trade.timestamp) {
+                Ok(time) => time.timestamp(),
+                Err(source) => {
+                    return Err(BookedError::Timestamp {
+                        timestamp: trade.timestamp,
+                        source,
+                    });
+                }
+            };
+            // keep trades ordered even if their timestamps go backwards
+            let time = match writable_trades.back() {
+                Some((latest_time, _)) => max(time, *latest_time),
+                None => time,
+            };
+            writable_trades.push_back((time, trade));
+        }
+
+        if let Some((latest_time, _)) = writable_trades.back() {
+            let start_time = latest_time - self.time_window_in_seconds as i64;
+            while writable_trades
+                .front()
+                .is_some_and(|(time, _)| *time < start_time)
+            {
+                writable_trades.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    /// count the levels added, amended and removed on both sides from each snapshot to the
+    /// next within a time window
+    pub async fn level_changes(&self, start: i64, end: i64) -> Vec<(i64, LevelChanges)> {
+        let changes = |history: &RBTree<i64, RBTree<Price, f64>>| {
+            history
+                .iter()
+                .zip(history.iter().skip(1))
+                .filter(|(_, (time, _))| (**time >= start) && (**time <= end))
+                .map(|((_, previous), (time, current))| (*time, diff_levels(previous, current)))
+                .collect::<Vec<_>>()
+        };
+
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+
+        zip(changes(&readable_asks), changes(&readable_bids))
+            .map(|((time, asks), (_, bids))| {
+                (
+                    time,
+                    LevelChanges {
+                        added: asks.added + bids.added,
+                        amended: asks.amended + bids.amended,
+                        removed: asks.removed + bids.removed,
+                    },
+                )
+            })
+            .collect()
+    }
+
     /// integrate volumes over prices in time window to get volume(time)
     pub async fn integrate_window(
         &self,
@@ -174,6 +379,181 @@ impl BookHistory {
         (integrate(&readable_asks), integrate(&readable_bids))
     }
 
+    /// average total volume over time window, each book weighted by how long it persisted
+    /// within the window, falling back to the last book when none persisted at all
+    pub async fn time_weighted_depth(&self, start: i64, end: i64) -> (Option<f64>, Option<f64>) {
+        let average = |history: &RBTree<i64, RBTree<Price, f64>>| {
+            let totals = history
+                .iter()
+                .map(|(time, book)| (*time, book.values().sum::<f64>()))
+                .collect::<Vec<_>>();
+            let ends = totals.iter().skip(1).map(|(time, _)| *time).chain([end]);
+
+            let (mut weighted, mut duration, mut last) = (0.0, 0, None);
+            for ((time, total), next) in totals.iter().zip(ends) {
+                if *time > end {
+                    break;
+                }
+                let persisted = next.min(end) - (*time).max(start);
+                if persisted > 0 {
+                    weighted += total * persisted as f64;
+                    duration += persisted;
+                }
+                last = Some(*total);
+            }
+            if duration > 0 {
+                Some(weighted / duration as f64)
+            } else {
+                last
+            }
+        };
+
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+
+        (average(&readable_asks), average(&readable_bids))
+    }
+
+    /// integrate volumes over times in time window to get volume(price)
+    pub async fn integrate_prices(
+        &self,
+        start: i64,
+        end: i64,
+    ) -> (RBTree<Price, f64>, RBTree<Price, f64>) {
+        let integrate = |history: &RBTree<i64, RBTree<Price, f64>>| {
+            let mut integrated: RBTree<Price, f64> = RBTree::new();
+            for (_, book) in history
+                .iter()
+                .filter(|(time, _)| (**time >= start) && (**time <= end))
+            {
+                for (price, quantity) in book.iter() {
+                    match integrated.get_mut(price) {
+                        Some(accumulated) => *accumulated += quantity,
+                        None => integrated.insert(price.clone(), *quantity),
+                    }
+                }
+            }
+            integrated
+        };
+
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+
+        (integrate(&readable_asks), integrate(&readable_bids))
+    }
+
+    /// count price levels appearing and vanishing again within a horizon over the time window
+    pub async fn flicker_prices(
+        &self,
+        start: i64,
+        end: i64,
+        horizon_in_seconds: i64,
+    ) -> (RBTree<Price, f64>, RBTree<Price, f64>) {
+        let count = |history: &RBTree<i64, RBTree<Price, f64>>| {
+            let mut flickers: RBTree<Price, f64> = RBTree::new();
+            // levels present in the first book have unknown appearance times and never count
+            let mut appearances: RBTree<Price, Option<i64>> = RBTree::new();
+            for (index, (time, book)) in history
+                .iter()
+                .filter(|(time, _)| (**time >= start) && (**time <= end))
+                .enumerate()
+            {
+                for price in book.keys() {
+                    if !appearances.contains_key(price) {
+                        appearances.insert(price.clone(), (index > 0).then_some(*time));
+                    }
+                }
+
+                let vanished = appearances
+                    .iter()
+                    .filter(|(price, _)| !book.contains_key(price))
+                    .map(|(price, appearance)| (price.clone(), *appearance))
+                    .collect::<Vec<_>>();
+
+                for (price, appearance) in vanished.into_iter() {
+                    appearances.remove(&price);
+                    if appearance.is_some_and(|appeared| time - appeared <= horizon_in_seconds) {
+                        match flickers.get_mut(&price) {
+                            Some(accumulated) => *accumulated += 1.0,
+                            None => flickers.insert(price, 1.0),
+                        }
+                    }
+                }
+            }
+            flickers
+        };
+
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+
+        (count(&readable_asks), count(&readable_bids))
+    }
+
+    /// time of the latest book, None when empty
+    pub async fn latest_time(&self) -> Option<i64> {
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+        max(
+            readable_asks.get_last().map(|(time, _)| *time),
+            readable_bids.get_last().map(|(time, _)| *time),
+        )
+    }
+
+    /// number of book snapshots, counting both sides, and of trades held by the history
+    pub async fn sizes(&self) -> (usize, usize) {
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+        let readable_trades = self.trades.read().await;
+        (
+            readable_asks.len() + readable_bids.len(),
+            readable_trades.len(),
+        )
+    }
+
+    /// rough estimate of the memory held by the history in bytes, counting tree nodes
+    pub async fn memory_usage(&self) -> usize {
+        // a tree node holds its key, its value, three links and a color
+        const LEVEL_BYTES: usize = 48;
+        const SNAPSHOT_BYTES: usize = 64;
+        let usage = |history: &RBTree<i64, RBTree<Price, f64>>| {
+            history
+                .values()
+                .map(|book| SNAPSHOT_BYTES + book.len() * LEVEL_BYTES)
+                .sum::<usize>()
+        };
+
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+        let readable_trades = self.trades.read().await;
+
+        usage(&readable_asks)
+            + usage(&readable_bids)
+            + readable_trades.len() * std::mem::size_of::<(i64, Traded)>()
+    }
+
+    /// downsample books older than a time, keeping the first book of every period of step
+    /// seconds
+    pub async fn thin(&self, before: i64, step: i64) {
+        let thin = |history: &mut RBTree<i64, RBTree<Price, f64>>| {
+            let mut period = None;
+            *history = std::mem::replace(history, RBTree::new())
+                .into_iter()
+                .filter(|(time, _)| {
+                    if *time >= before {
+                        return true;
+                    }
+                    let current = Some(time.div_euclid(step));
+                    let first = period != current;
+                    period = current;
+                    first
+                })
+                .collect();
+        };
+
+        thin(&mut *self.asks.write().await);
+        thin(&mut *self.bids.write().await);
+    }
+
     /// Extract a portion of the book history
     pub async fn extract_window(&self, start: i64, end: i64) -> BookHistory {
         let extract = |history: &RBTree<i64, RBTree<Price, f64>>| {
@@ -187,11 +567,73 @@ impl BookHistory {
 
         let readable_asks = self.asks.read().await;
         let readable_bids = self.bids.read().await;
+        let readable_trades = self.trades.read().await;
 
         BookHistory {
             time_window_in_seconds: (end - start).abs() as usize,
             asks: RwLock::new(extract(&readable_asks)),
             bids: RwLock::new(extract(&readable_bids)),
+            trades: RwLock::new(
+                readable_trades
+                    .iter()
+                    .filter(|(time, _)| (*time >= start) && (*time <= end))
+                    .cloned()
+                    .collect(),
+            ),
+        }
+    }
+
+    /// get best ask and best bid for every book in time window
+    pub async fn top_of_book_window(
+        &self,
+        start: i64,
+        end: i64,
+    ) -> Vec<(i64, Option<Order>, Option<Order>)> {
+        let readable_asks = self.asks.read().await;
+        let readable_bids = self.bids.read().await;
+
+        zip(readable_asks.iter(), readable_bids.iter())
+            .filter(|((time, _), _)| (**time >= start) && (**time <= end))
+            .map(|((time, asks), (_, bids))| {
+                (
+                    *time,
+                    asks.get_first().map(|(price, quantity)| Order {
+                        price: price.value,
+                        quantity: *quantity,
+                    }),
+                    bids.get_last().map(|(price, quantity)| Order {
+                        price: price.value,
+                        quantity: *quantity,
+                    }),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Spacing of the price axis of a grid
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum PriceScale {
+    /// evenly spaced prices
+    Linear,
+    /// evenly spaced log prices, giving the same resolution to the same relative move
+    Log,
+}
+
+impl PriceScale {
+    /// position of a price on the price axis
+    pub fn position(self, price: f64) -> f64 {
+        match self {
+            PriceScale::Linear => price,
+            PriceScale::Log => price.ln(),
+        }
+    }
+
+    /// price at a position on the price axis
+    pub fn price(self, value: f64) -> f64 {
+        match self {
+            PriceScale::Linear => value,
+            PriceScale::Log => value.exp(),
         }
     }
 }
@@ -203,6 +645,89 @@ pub struct RenderGrid {
     pub time_range: (i64, i64),
     pub number_price_values: usize,
     pub price_range: (f64, f64),
+    pub price_scale: PriceScale,
+    pub peg: Option<f64>,
+}
+
+impl RenderGrid {
+    /// range of the price axis, log prices on a log scale
+    pub fn price_axis(&self) -> (f64, f64) {
+        (
+            self.price_scale.position(self.price_range.0),
+            self.price_scale.position(self.price_range.1),
+        )
+    }
+}
+
+/// Assets pegged to a common reference, grouped by reference currency
+const PEGGED_ASSETS: [(f64, &[&str]); 2] = [
+    (
+        1.0,
+        &[
+            "USD", "USDT", "USDC", "DAI", "PYUSD", "TUSD", "USDP", "USDS", "USDG", "USDD", "RLUSD",
+            "FDUSD",
+        ],
+    ),
+    (1.0, &["EUR", "EURT", "EURC", "EURQ", "EURR"]),
+];
+
+/// Fixed price band around a peg used for pairs trading in a narrow range
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceBand {
+    pub peg: f64,
+    pub price_range: (f64, f64),
+    pub number_price_values: usize,
+}
+
+impl PriceBand {
+    /// constructor
+    pub fn new(peg: f64, half_width_in_bps: f64, number_price_values: usize) -> PriceBand {
+        let half_width = peg * half_width_in_bps / 10_000.0;
+        PriceBand {
+            peg,
+            price_range: (peg - half_width, peg + half_width),
+            number_price_values,
+        }
+    }
+
+    /// detect pairs of assets pegged to the same reference (e.g. USDT/USD) from the symbol
+    pub fn detect(
+        symbol: &str,
+        half_width_in_bps: f64,
+        number_price_values: usize,
+    ) -> Option<PriceBand> {
+        let (base, quote) = symbol.split_once('/')?;
+
+        PEGGED_ASSETS
+            .iter()
+            .find(|(_, assets)| base != quote && assets.contains(&base) && assets.contains(&quote))
+            .map(|(peg, _)| PriceBand::new(*peg, half_width_in_bps, number_price_values))
+    }
+}
+
+/// Enum of different ways the price range of a grid is fitted to the book
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PriceRange {
+    /// from the lowest bid to the highest ask over the time window
+    Full,
+    /// central fraction of the resting volume over the time window, e.g. 0.98 for the 1st to
+    /// 99th percentile, ignoring far out of range orders
+    Percentile(f64),
+    /// fixed distance in basis points around the latest mid price
+    AroundMid(f64),
+}
+
+/// private utility method for the price below which a fraction of the resting volume sits
+fn volume_quantile(levels: &[(f64, f64)], fraction: f64) -> Option<f64> {
+    let total = levels.iter().fold(0.0, |acc, (_, volume)| acc + volume);
+    let mut accumulated = 0.0;
+    for (price, volume) in levels.iter() {
+        accumulated += volume;
+        if accumulated >= fraction * total {
+            return Some(*price);
+        }
+    }
+    levels.last().map(|(price, _)| *price)
 }
 
 /// Construct and adapt (time, price) grid from order book
@@ -211,6 +736,11 @@ pub struct GenerateGrid {
     time_window_in_seconds: u64,
     number_time_values: usize,
     number_price_values: usize,
+    price_band: Option<PriceBand>,
+    price_scale: PriceScale,
+    price_range: PriceRange,
+    /// end of the time range shared with other histories, None to end at the latest book
+    time_end: Option<i64>,
 }
 
 impl GenerateGrid {
@@ -218,11 +748,16 @@ impl GenerateGrid {
         let readable_asks = history.asks.read().await;
         let readable_bids = history.bids.read().await;
 
-        let latest_time = match (readable_asks.get_last(), readable_bids.get_last()) {
-            (Some((time_asks, _)), Some((time_bids, _))) => max(time_asks, time_bids).clone(),
-            (Some((time_asks, _)), None) => time_asks.clone(),
-            (None, Some((time_bids, _))) => time_bids.clone(),
-            (None, None) => Utc::now().timestamp(),
+        let latest_time = match (
+            self.time_end,
+            readable_asks.get_last(),
+            readable_bids.get_last(),
+        ) {
+            (Some(time_end), _, _) => time_end,
+            (None, Some((time_asks, _)), Some((time_bids, _))) => *max(time_asks, time_bids),
+            (None, Some((time_asks, _)), None) => *time_asks,
+            (None, None, Some((time_bids, _))) => *time_bids,
+            (None, None, None) => clock::now().timestamp(),
         };
 
         let time_range = (
@@ -268,11 +803,132 @@ impl GenerateGrid {
             .value
             .clone();
 
-        RenderGrid {
-            number_time_values: self.number_time_values.clone(),
-            time_range: time_range,
-            number_price_values: self.number_price_values.clone(),
-            price_range: (minimal_bid, maximal_ask),
+        let price_range = match self.price_range {
+            PriceRange::Full => (minimal_bid, maximal_ask),
+            PriceRange::Percentile(fraction) => {
+                let mut levels = readable_asks
+                    .iter()
+                    .chain(readable_bids.iter())
+                    .filter(|(time, _)| **time >= time_range.0 && **time <= time_range.1)
+                    .flat_map(|(_, book)| book.iter().map(|(price, volume)| (price.value, *volume)))
+                    .collect::<Vec<_>>();
+                levels.sort_by(|lhs, rhs| f64::total_cmp(&lhs.0, &rhs.0));
+                let tail = (1.0 - fraction) / 2.0;
+                match (
+                    volume_quantile(&levels, tail),
+                    volume_quantile(&levels, 1.0 - tail),
+                ) {
+                    (Some(low), Some(high)) if low < high => (low, high),
+                    _ => (minimal_bid, maximal_ask),
+                }
+            }
+            PriceRange::AroundMid(half_width_in_bps) => {
+                let best_ask = readable_asks
+                    .get_last()
+                    .and_then(|(_, book)| book.get_first().map(|(price, _)| price.value));
+                let best_bid = readable_bids
+                    .get_last()
+                    .and_then(|(_, book)| book.get_last().map(|(price, _)| price.value));
+                match (best_ask, best_bid) {
+                    (Some(ask), Some(bid)) => {
+                        let mid = (ask + bid) / 2.0;
+                        let half_width = mid * half_width_in_bps / 10_000.0;
+                        (mid - half_width, mid + half_width)
+                    }
+                    _ => (minimal_bid, maximal_ask),
+                }
+            }
+        };
+
+        let mut grid = match &self.price_band {
+            Some(band) => RenderGrid {
+                number_time_values: self.number_time_values,
+                time_range,
+                number_price_values: band.number_price_values,
+                price_range: band.price_range,
+                price_scale: self.price_scale,
+                peg: Some(band.peg),
+            },
+            None => RenderGrid {
+                number_time_values: self.number_time_values,
+                time_range,
+                number_price_values: self.number_price_values,
+                price_range,
+                price_scale: self.price_scale,
+                peg: None,
+            },
+        };
+
+        // log prices are only defined for positive prices, e.g. not for an empty book
+        if grid.price_range.0 <= 0.0 {
+            grid.price_scale = PriceScale::Linear;
+        }
+        grid
+    }
+}
+
+/// Enum of different ways market depth can be represented
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthMode {
+    /// volume density per price level
+    Density,
+    /// volume accumulated outward from the mid price
+    Cumulative,
+}
+
+impl DepthMode {
+    /// get the next depth mode
+    pub fn toggled(&self) -> DepthMode {
+        match self {
+            DepthMode::Density => DepthMode::Cumulative,
+            DepthMode::Cumulative => DepthMode::Density,
+        }
+    }
+}
+
+/// Enum of the views whose splat mode can be switched independently
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum View {
+    Depth,
+    Map,
+    Profile,
+    Volume,
+    Imbalance,
+    Activity,
+}
+
+impl View {
+    /// every view, in the order they are named to the user
+    pub const ALL: [View; 6] = [
+        View::Depth,
+        View::Map,
+        View::Profile,
+        View::Volume,
+        View::Imbalance,
+        View::Activity,
+    ];
+
+    /// name of the view as written by the user
+    pub fn name(&self) -> &'static str {
+        match self {
+            View::Depth => "depth",
+            View::Map => "map",
+            View::Profile => "profile",
+            View::Volume => "volume",
+            View::Imbalance => "imbalance",
+            View::Activity => "activity",
+        }
+    }
+
+    /// parse a view from its name
+    pub fn parse(name: &str) -> Result<View, String> {
+        match View::ALL.into_iter().find(|view| view.name() == name) {
+            Some(view) => Ok(view),
+            None => Err(format!(
+                "Unknown view '{}', expected one of {}",
+                name,
+                View::ALL.map(|view| view.name()).join(", ")
+            )),
         }
     }
 }
@@ -280,36 +936,98 @@ impl GenerateGrid {
 /// Data structure representing market depth
 #[derive(Clone, Debug)]
 pub struct SplattedDepth {
+    pub mode: DepthMode,
+    /// range of the price axis, log prices on a log scale
     pub price_range: (f64, f64),
+    pub price_scale: PriceScale,
+    pub peg: Option<f64>,
     pub volumes: Vec<f64>,
 }
 
+/// private utility method accumulating asks upward and bids downward from the mid price
+fn accumulate_from_mid(
+    price_range: &(f64, f64),
+    mid: f64,
+    ask_support: Vec<f64>,
+    bid_support: Vec<f64>,
+) -> (Vec<f64>, Vec<f64>) {
+    let step = (price_range.1 - price_range.0) / (ask_support.len() as f64);
+    let price = |index: usize| step * (index as f64) + price_range.0;
+
+    let mut accumulated_asks = vec![0.0; ask_support.len()];
+    let mut total = 0.0;
+    for (index, volume) in ask_support.into_iter().enumerate() {
+        if price(index) >= mid {
+            total += volume;
+            accumulated_asks[index] = total;
+        }
+    }
+
+    let mut accumulated_bids = vec![0.0; bid_support.len()];
+    let mut total = 0.0;
+    for (index, volume) in bid_support.into_iter().enumerate().rev() {
+        if price(index) <= mid {
+            total += volume;
+            accumulated_bids[index] = total;
+        }
+    }
+
+    (accumulated_asks, accumulated_bids)
+}
+
 /// Functor like object for constructing market depth from order book
-pub struct SplatDepth {}
+#[derive(Clone, Debug)]
+pub struct SplatDepth {
+    pub mode: DepthMode,
+}
 
 impl SplatDepth {
-    pub async fn splat(grid: &RenderGrid, history: &BookHistory) -> SplattedDepth {
+    pub async fn splat(
+        &self,
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedDepth {
         let ((_, latest_asks), (_, latest_bids)) = history.get_latest_book().await;
-        let ask_support = splat_1d(
-            &grid.price_range,
+        let mid = match (latest_asks.get_first(), latest_bids.get_last()) {
+            (Some((ask, _)), Some((bid, _))) => (ask.value + bid.value) / 2.0,
+            _ => (grid.price_range.0 + grid.price_range.1) / 2.0,
+        };
+
+        let price_axis = grid.price_axis();
+        let ask_support = project_1d(
+            splat_mode,
+            &price_axis,
             grid.number_price_values,
             latest_asks
-                .into_iter()
-                .map(|(price, volume)| (price.value, volume))
-                .collect(),
+                .iter()
+                .map(|(price, volume)| (grid.price_scale.position(price.value), *volume)),
         );
 
-        let bid_support = splat_1d(
-            &grid.price_range,
+        let bid_support = project_1d(
+            splat_mode,
+            &price_axis,
             grid.number_price_values,
             latest_bids
-                .into_iter()
-                .map(|(price, volume)| (price.value, volume))
-                .collect(),
+                .iter()
+                .map(|(price, volume)| (grid.price_scale.position(price.value), *volume)),
         );
 
+        let (ask_support, bid_support) = match self.mode {
+            DepthMode::Density => (ask_support, bid_support),
+            DepthMode::Cumulative => accumulate_from_mid(
+                &price_axis,
+                grid.price_scale.position(mid),
+                ask_support,
+                bid_support,
+            ),
+        };
+
         SplattedDepth {
-            price_range: grid.price_range.clone(),
+            mode: self.mode,
+            price_range: price_axis,
+            price_scale: grid.price_scale,
+            peg: grid.peg,
             volumes: zip(ask_support, bid_support)
                 .map(|(ask, bid)| ask - bid)
                 .collect(),
@@ -323,363 +1041,3222 @@ pub struct SplattedVolumes {
     pub time_range: (i64, i64),
     pub ask_volumes: Vec<f64>,
     pub bid_volumes: Vec<f64>,
+    /// normalized bid/ask imbalance (bid - ask) / (bid + ask) oscillating in [-1, 1]
+    pub imbalance_ratios: Vec<f64>,
+}
+
+/// private utility method for the normalized imbalance between bid and ask volumes
+fn imbalance_ratio(ask_volume: f64, bid_volume: f64) -> f64 {
+    let total = ask_volume + bid_volume;
+    if total.abs() <= f64::EPSILON {
+        0.0
+    } else {
+        ((bid_volume - ask_volume) / total).clamp(-1.0, 1.0)
+    }
 }
 
 /// Functor like object for constructing market volumes from order book
 pub struct SplatVolume {}
 
 impl SplatVolume {
-    pub async fn splat(grid: &RenderGrid, history: &BookHistory) -> SplattedVolumes {
+    pub async fn splat(
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedVolumes {
         let (ask_volumes, bid_volumes) = history
             .integrate_window(grid.time_range.0, grid.time_range.1)
             .await;
 
-        let ask_support = splat_1d(
+        let ask_support = project_1d(
+            splat_mode.along_time(),
             &(grid.time_range.0 as f64, grid.time_range.1 as f64),
             grid.number_time_values,
             ask_volumes
-                .into_iter()
-                .map(|(time, volume)| (time as f64, volume))
-                .collect(),
+                .iter()
+                .map(|(time, volume)| (*time as f64, *volume)),
         );
 
-        let bid_support = splat_1d(
+        let bid_support = project_1d(
+            splat_mode.along_time(),
             &(grid.time_range.0 as f64, grid.time_range.1 as f64),
             grid.number_time_values,
             bid_volumes
-                .into_iter()
-                .map(|(time, volume)| (time as f64, volume))
-                .collect(),
+                .iter()
+                .map(|(time, volume)| (*time as f64, *volume)),
         );
 
+        let imbalance_ratios = zip(ask_support.iter(), bid_support.iter())
+            .map(|(ask, bid)| imbalance_ratio(*ask, *bid))
+            .collect();
+
         SplattedVolumes {
             time_range: grid.time_range.clone(),
             ask_volumes: ask_support,
             bid_volumes: bid_support,
+            imbalance_ratios,
         }
     }
 }
 
-/// Data structure representing portion of order book on 2D (time, price) grid
+/// Data structure representing volumes resting at each price over the time window
 #[derive(Clone, Debug)]
-pub struct SplattedBlocks {
-    pub grid: RenderGrid,
-    pub volumes: Array2<f64>,
+pub struct SplattedProfile {
+    pub mode: SplatMode,
+    /// range of the price axis, log prices on a log scale
+    pub price_range: (f64, f64),
+    pub ask_volumes: Vec<f64>,
+    pub bid_volumes: Vec<f64>,
 }
 
-/// Functor like object for constructing volume heat map from order book over 2D (time, price) grid
-pub struct SplatBlocks {}
+/// Functor like object for constructing volume profile (liquidity by price) from order book
+pub struct SplatProfile {}
 
-impl SplatBlocks {
-    pub async fn splat(grid: &RenderGrid, history: &BookHistory) -> SplattedBlocks {
-        let extract = history
-            .extract_window(grid.time_range.0, grid.time_range.1)
+impl SplatProfile {
+    pub async fn splat(
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedProfile {
+        let (ask_volumes, bid_volumes) = history
+            .integrate_prices(grid.time_range.0, grid.time_range.1)
             .await;
 
-        let mut source = Vec::new();
-        {
-            let readable_asks = extract.asks.read().await;
-            for (time, state) in readable_asks.iter() {
-                for (price, volume) in state.iter() {
-                    source.push((time.clone() as f64, price.value.clone(), volume.clone()));
-                }
-            }
-        }
+        let ask_support = project_1d(
+            splat_mode,
+            &grid.price_axis(),
+            grid.number_price_values,
+            ask_volumes
+                .iter()
+                .map(|(price, volume)| (grid.price_scale.position(price.value), *volume)),
+        );
 
-        let ask_support = splat_2d(
-            (
-                &(grid.time_range.0 as f64, grid.time_range.1 as f64),
-                &grid.price_range,
-            ),
-            (grid.number_time_values, grid.number_price_values),
-            source,
+        let bid_support = project_1d(
+            splat_mode,
+            &grid.price_axis(),
+            grid.number_price_values,
+            bid_volumes
+                .iter()
+                .map(|(price, volume)| (grid.price_scale.position(price.value), *volume)),
+        );
+
+        SplattedProfile {
+            mode: splat_mode,
+            price_range: grid.price_axis(),
+            ask_volumes: ask_support,
+            bid_volumes: bid_support,
+        }
+    }
+}
+
+/// Data structure representing how often price levels flickered in and out of the book
+#[derive(Clone, Debug)]
+pub struct SplattedFlicker {
+    /// range of the price axis, log prices on a log scale
+    pub price_range: (f64, f64),
+    pub horizon_in_seconds: i64,
+    pub ask_scores: Vec<f64>,
+    pub bid_scores: Vec<f64>,
+}
+
+/// Functor like object for constructing flicker scores of likely spoofed liquidity by price
+#[derive(Clone, Debug)]
+pub struct SplatFlicker {
+    /// longest lifetime of a level for its disappearance to count as a flicker
+    pub horizon_in_seconds: i64,
+}
+
+impl SplatFlicker {
+    pub async fn splat(
+        &self,
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedFlicker {
+        let (ask_flickers, bid_flickers) = history
+            .flicker_prices(
+                grid.time_range.0,
+                grid.time_range.1,
+                self.horizon_in_seconds,
+            )
+            .await;
+
+        let project = |flickers: RBTree<Price, f64>| {
+            project_1d(
+                splat_mode,
+                &grid.price_axis(),
+                grid.number_price_values,
+                flickers
+                    .iter()
+                    .map(|(price, count)| (grid.price_scale.position(price.value), *count)),
+            )
+        };
+
+        SplattedFlicker {
+            price_range: grid.price_axis(),
+            horizon_in_seconds: self.horizon_in_seconds,
+            ask_scores: project(ask_flickers),
+            bid_scores: project(bid_flickers),
+        }
+    }
+}
+
+/// Precision the cells of the order map are kept in, single precision with the `f32` feature to
+/// halve the memory of large grids and the cost of copying them to the interface every frame
+#[cfg(not(feature = "f32"))]
+pub type Volume = f64;
+/// Precision the cells of the order map are kept in, single precision with the `f32` feature to
+/// halve the memory of large grids and the cost of copying them to the interface every frame
+#[cfg(feature = "f32")]
+pub type Volume = f32;
+
+/// private utility method for narrowing volumes splatted in double precision to the precision of
+/// the order map
+#[cfg(not(feature = "f32"))]
+fn narrow(volumes: Array2<f64>) -> Array2<Volume> {
+    volumes
+}
+
+/// private utility method for narrowing volumes splatted in double precision to the precision of
+/// the order map
+#[cfg(feature = "f32")]
+fn narrow(volumes: Array2<f64>) -> Array2<Volume> {
+    volumes.mapv(|volume| volume as Volume)
+}
+
+/// volume of a cell of the order map in double precision, for arithmetic and color mapping
+#[cfg(not(feature = "f32"))]
+pub fn widen(volume: Volume) -> f64 {
+    volume
+}
+
+/// volume of a cell of the order map in double precision, for arithmetic and color mapping
+#[cfg(feature = "f32")]
+pub fn widen(volume: Volume) -> f64 {
+    f64::from(volume)
+}
+
+/// Data structure representing portion of order book on 2D (time, price) grid
+#[derive(Clone, Debug)]
+pub struct SplattedBlocks {
+    pub mode: SplatMode,
+    pub grid: RenderGrid,
+    pub volumes: Array2<Volume>,
+}
+
+impl SplattedBlocks {
+    /// time, price and volume at a point of the grid given as fractions of its time and price
+    /// axes, the volume interpolated bilinearly between the surrounding cells
+    pub fn sample(&self, time_fraction: f64, price_fraction: f64) -> (f64, f64, f64) {
+        let price_axis = self.grid.price_axis();
+        let time = self.grid.time_range.0 as f64
+            + time_fraction * (self.grid.time_range.1 - self.grid.time_range.0) as f64;
+        let price = self
+            .grid
+            .price_scale
+            .price(price_axis.0 + price_fraction * (price_axis.1 - price_axis.0));
+
+        // cells sit at the start of their step along both axes, as they are drawn
+        let (number_times, number_prices) = self.volumes.dim();
+        if number_times == 0 || number_prices == 0 {
+            return (time, price, 0.0);
+        }
+        let neighbours = |fraction: f64, number: usize| {
+            let position = (fraction * number as f64).clamp(0.0, (number - 1) as f64);
+            let lower = position.floor() as usize;
+            (lower, (lower + 1).min(number - 1), position - lower as f64)
+        };
+        let (t_lower, t_upper, t_weight) = neighbours(time_fraction, number_times);
+        let (p_lower, p_upper, p_weight) = neighbours(price_fraction, number_prices);
+        let along_prices = |t_grid: usize| {
+            widen(self.volumes[(t_grid, p_lower)]) * (1.0 - p_weight)
+                + widen(self.volumes[(t_grid, p_upper)]) * p_weight
+        };
+        let volume = along_prices(t_lower) * (1.0 - t_weight) + along_prices(t_upper) * t_weight;
+        (time, price, volume)
+    }
+}
+
+/// Functor like object for constructing volume heat map from order book over 2D (time, price) grid
+#[derive(Clone, Debug)]
+pub struct SplatBlocks {
+    /// age at which the weight of a book snapshot is halved, None to weight all history equally
+    pub half_life_in_seconds: Option<f64>,
+}
+
+impl SplatBlocks {
+    /// weight of a book snapshot taken at a time given the latest time of the grid
+    fn weight(&self, time: i64, latest_time: i64) -> f64 {
+        match self.half_life_in_seconds {
+            Some(half_life) => 0.5_f64.powf((latest_time - time).max(0) as f64 / half_life),
+            None => 1.0,
+        }
+    }
+
+    /// weighted (time, price position, volume) samples of book snapshots, streamed from the
+    /// snapshots without copying them
+    fn source<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        books: &'a RBTree<i64, RBTree<Price, f64>>,
+    ) -> impl Iterator<Item = (f64, f64, f64)> + Clone + 'a {
+        books.iter().flat_map(move |(time, state)| {
+            let weight = self.weight(*time, grid.time_range.1);
+            state.iter().map(move |(price, volume)| {
+                (
+                    *time as f64,
+                    grid.price_scale.position(price.value),
+                    weight * volume,
+                )
+            })
+        })
+    }
+
+    pub async fn splat(
+        &self,
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedBlocks {
+        let extract = history
+            .extract_window(grid.time_range.0, grid.time_range.1)
+            .await;
+        let axes = (
+            (grid.time_range.0 as f64, grid.time_range.1 as f64),
+            grid.price_axis(),
         );
+        let grid_sizes = (grid.number_time_values, grid.number_price_values);
 
-        let mut source = Vec::new();
+        let mut volumes = {
+            let readable_asks = extract.asks.read().await;
+            project_2d(
+                splat_mode,
+                (&axes.0, &axes.1),
+                grid_sizes,
+                self.source(grid, &readable_asks),
+            )
+        };
         {
             let readable_bids = extract.bids.read().await;
-            for (time, state) in readable_bids.iter() {
-                for (price, volume) in state.iter() {
-                    source.push((time.clone() as f64, price.value.clone(), volume.clone()));
-                }
+            volumes -= &project_2d(
+                splat_mode,
+                (&axes.0, &axes.1),
+                grid_sizes,
+                self.source(grid, &readable_bids),
+            );
+        }
+
+        SplattedBlocks {
+            mode: splat_mode,
+            grid: grid.clone(),
+            volumes: narrow(volumes),
+        }
+    }
+}
+
+/// Data structure representing order flow imbalance over time
+#[derive(Clone, Debug)]
+pub struct SplattedImbalance {
+    pub time_range: (i64, i64),
+    pub imbalances: Vec<f64>,
+}
+
+/// private utility method for the signed order flow contribution between two top of books
+fn order_flow(
+    previous: (&Option<Order>, &Option<Order>),
+    current: (&Option<Order>, &Option<Order>),
+) -> f64 {
+    let bid_flow = match (previous.1, current.1) {
+        (Some(previous_bid), Some(current_bid)) => {
+            let mut flow = 0.0;
+            if current_bid.price >= previous_bid.price {
+                flow += current_bid.quantity;
+            }
+            if current_bid.price <= previous_bid.price {
+                flow -= previous_bid.quantity;
             }
+            flow
         }
+        _ => 0.0,
+    };
 
-        let bid_support = splat_2d(
-            (
+    let ask_flow = match (previous.0, current.0) {
+        (Some(previous_ask), Some(current_ask)) => {
+            let mut flow = 0.0;
+            if current_ask.price <= previous_ask.price {
+                flow += current_ask.quantity;
+            }
+            if current_ask.price >= previous_ask.price {
+                flow -= previous_ask.quantity;
+            }
+            flow
+        }
+        _ => 0.0,
+    };
+
+    bid_flow - ask_flow
+}
+
+/// Functor like object for constructing order flow imbalance (signed changes in best bid/ask
+/// sizes) over time from order book
+pub struct SplatImbalance {}
+
+impl SplatImbalance {
+    pub async fn splat(
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedImbalance {
+        let tops = history
+            .top_of_book_window(grid.time_range.0, grid.time_range.1)
+            .await;
+
+        let flows = tops.iter().zip(tops.iter().skip(1)).map(
+            |((_, previous_ask, previous_bid), (time, ask, bid))| {
+                (
+                    *time as f64,
+                    order_flow((previous_ask, previous_bid), (ask, bid)),
+                )
+            },
+        );
+
+        SplattedImbalance {
+            time_range: grid.time_range,
+            imbalances: project_1d(
+                splat_mode.along_time(),
                 &(grid.time_range.0 as f64, grid.time_range.1 as f64),
-                &grid.price_range,
+                grid.number_time_values,
+                flows,
             ),
-            (grid.number_time_values, grid.number_price_values),
-            source,
-        );
+        }
+    }
+}
 
-        SplattedBlocks {
-            grid: grid.clone(),
-            volumes: ask_support - bid_support,
+/// Data structure representing rates of levels added, amended and removed per second over time
+#[derive(Clone, Debug)]
+pub struct SplattedActivity {
+    pub mode: SplatMode,
+    pub time_range: (i64, i64),
+    pub arrivals: Vec<f64>,
+    pub amendments: Vec<f64>,
+    pub cancellations: Vec<f64>,
+}
+
+/// Functor like object for constructing order arrival and cancellation rates from consecutive
+/// book snapshots
+pub struct SplatActivity {}
+
+impl SplatActivity {
+    pub async fn splat(
+        grid: &RenderGrid,
+        history: &BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplattedActivity {
+        let changes = history
+            .level_changes(grid.time_range.0, grid.time_range.1)
+            .await;
+
+        // kernel densities already are rates per second, while bins and masses hold counts over
+        // a step
+        let time_axis = (grid.time_range.0 as f64, grid.time_range.1 as f64);
+        let scale = match splat_mode {
+            SplatMode::Kernel(kernel) if kernel.normalization == Normalization::Density => 1.0,
+            _ => grid.number_time_values as f64 / (time_axis.1 - time_axis.0).max(1.0),
+        };
+        let rate = |count: fn(&LevelChanges) -> usize| {
+            project_1d(
+                splat_mode.along_time(),
+                &time_axis,
+                grid.number_time_values,
+                changes
+                    .iter()
+                    .map(|(time, changes)| (*time as f64, count(changes) as f64)),
+            )
+            .into_iter()
+            .map(|value| value * scale)
+            .collect::<Vec<_>>()
+        };
+
+        SplattedActivity {
+            mode: splat_mode,
+            time_range: grid.time_range,
+            arrivals: rate(|changes| changes.added),
+            amendments: rate(|changes| changes.amended),
+            cancellations: rate(|changes| changes.removed),
         }
     }
 }
 
-/// Encapsulating object for running all splatting of order book to different supports
-#[derive(Clone)]
-pub struct Pipeline {
-    grid_generator: GenerateGrid,
+/// Data structure representing executed trades as points on a (time, price) grid
+#[derive(Clone, Debug)]
+pub struct SplattedTrades {
+    /// trades of buying takers as (time, price axis position, quantity)
+    pub buys: Vec<(f64, f64, f64)>,
+    /// trades of selling takers as (time, price axis position, quantity)
+    pub sells: Vec<(f64, f64, f64)>,
 }
 
-impl Pipeline {
-    pub fn new(
-        time_window_in_seconds: u64,
-        number_time_values: usize,
-        number_price_values: usize,
-    ) -> Pipeline {
-        Pipeline {
-            grid_generator: GenerateGrid {
-                time_window_in_seconds,
-                number_time_values,
-                number_price_values,
-            },
+/// Functor like object for placing executed trades on the grid of the heat map
+pub struct SplatTrades {}
+
+impl SplatTrades {
+    pub async fn splat(grid: &RenderGrid, history: &BookHistory) -> SplattedTrades {
+        let readable_trades = history.trades.read().await;
+        let price_axis = grid.price_axis();
+
+        let mut splatted = SplattedTrades {
+            buys: vec![],
+            sells: vec![],
+        };
+        for (time, trade) in readable_trades.iter() {
+            let position = grid.price_scale.position(trade.price);
+            if *time < grid.time_range.0
+                || *time > grid.time_range.1
+                || position < price_axis.0
+                || position > price_axis.1
+            {
+                continue;
+            }
+            let point = (*time as f64, position, trade.quantity);
+            match trade.aggressor {
+                Aggressor::Buy => splatted.buys.push(point),
+                Aggressor::Sell => splatted.sells.push(point),
+            }
         }
+        splatted
     }
-    pub async fn run(
+}
+
+/// Price level where liquidity rested persistently over the time window
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    /// side of the resting liquidity, bids making a support and asks a resistance
+    pub side: Side,
+    /// volume weighted price of the cluster of levels
+    pub price: f64,
+    /// fraction of the snapshots in which the cluster held high volume
+    pub persistence: f64,
+    /// mean resting volume of the cluster over the snapshots
+    pub volume: f64,
+}
+
+/// Data structure representing support and resistance levels
+#[derive(Clone, Debug)]
+pub struct SplattedLevels {
+    /// levels sorted by price
+    pub levels: Vec<Level>,
+}
+
+/// Functor like object detecting support and resistance levels from clusters of price levels
+/// holding high volume over most of the time window
+#[derive(Clone, Debug)]
+pub struct SplatLevels {
+    /// quantile of the volumes of a snapshot above which a price bucket holds high volume
+    pub quantile: f64,
+    /// fraction of snapshots a cluster has to hold high volume in to count as a level
+    pub min_persistence: f64,
+    /// maximal number of levels kept per side, the most voluminous first
+    pub max_levels: usize,
+}
+
+impl SplatLevels {
+    /// detect the levels of one side of the book
+    fn side_levels(
         &self,
-        history: &BookHistory,
-    ) -> (SplattedDepth, SplattedVolumes, SplattedBlocks) {
-        let grid = self.grid_generator.grid(history).await;
+        grid: &RenderGrid,
+        side: Side,
+        history: &RBTree<i64, RBTree<Price, f64>>,
+    ) -> Vec<Level> {
+        let price_axis = grid.price_axis();
+        let step = (price_axis.1 - price_axis.0) / grid.number_price_values as f64;
+        let bucket = |price: f64| {
+            let position = grid.price_scale.position(price);
+            if step <= 0.0 || position < price_axis.0 || position > price_axis.1 {
+                return None;
+            }
+            Some((((position - price_axis.0) / step) as usize).min(grid.number_price_values - 1))
+        };
+
+        // per bucket: snapshots holding high volume, total volume and volume weighted price
+        let mut buckets: HashMap<usize, (usize, f64, f64)> = HashMap::new();
+        let mut number_snapshots = 0;
+        for (_, book) in history
+            .iter()
+            .filter(|(time, _)| **time >= grid.time_range.0 && **time <= grid.time_range.1)
+        {
+            number_snapshots += 1;
+            let mut volumes: HashMap<usize, (f64, f64)> = HashMap::new();
+            for (price, quantity) in book.iter() {
+                if let Some(index) = bucket(price.value) {
+                    let entry = volumes.entry(index).or_default();
+                    entry.0 += quantity;
+                    entry.1 += price.value * quantity;
+                }
+            }
+
+            let mut sorted = volumes
+                .values()
+                .map(|(volume, _)| *volume)
+                .collect::<Vec<_>>();
+            sorted.sort_by(f64::total_cmp);
+            let threshold = match sorted.len() {
+                0 => continue,
+                length => sorted[((self.quantile * length as f64) as usize).min(length - 1)],
+            };
+            for (index, (volume, weighted_price)) in volumes.into_iter() {
+                let entry = buckets.entry(index).or_default();
+                if volume >= threshold {
+                    entry.0 += 1;
+                }
+                entry.1 += volume;
+                entry.2 += weighted_price;
+            }
+        }
+
+        let mut persistent = buckets
+            .into_iter()
+            .filter(|(_, (high, _, _))| {
+                *high as f64 >= self.min_persistence * number_snapshots as f64
+            })
+            .collect::<Vec<_>>();
+        persistent.sort_by_key(|(index, _)| *index);
+
+        // merge adjacent persistent buckets into clusters
+        let mut clusters: Vec<(usize, usize, f64, f64)> = Vec::new();
+        let mut previous_index = None;
+        for (index, (high, volume, weighted_price)) in persistent.into_iter() {
+            match (clusters.last_mut(), previous_index) {
+                (Some(cluster), Some(previous)) if index == previous + 1 => {
+                    cluster.1 = cluster.1.max(high);
+                    cluster.2 += volume;
+                    cluster.3 += weighted_price;
+                }
+                _ => clusters.push((index, high, volume, weighted_price)),
+            }
+            previous_index = Some(index);
+        }
+
+        let mut levels = clusters
+            .into_iter()
+            .filter(|(_, _, volume, _)| *volume > 0.0)
+            .map(|(_, high, volume, weighted_price)| Level {
+                side,
+                price: weighted_price / volume,
+                persistence: high as f64 / number_snapshots as f64,
+                volume: volume / number_snapshots as f64,
+            })
+            .collect::<Vec<_>>();
+        levels.sort_by(|lhs, rhs| f64::total_cmp(&rhs.volume, &lhs.volume));
+        levels.truncate(self.max_levels);
+        levels
+    }
+
+    pub async fn splat(&self, grid: &RenderGrid, history: &BookHistory) -> SplattedLevels {
+        let readable_asks = history.asks.read().await;
+        let readable_bids = history.bids.read().await;
+
+        let mut levels = self.side_levels(grid, Side::Ask, &readable_asks);
+        levels.extend(self.side_levels(grid, Side::Bid, &readable_bids));
+        levels.sort_by(|lhs, rhs| f64::total_cmp(&lhs.price, &rhs.price));
+        SplattedLevels { levels }
+    }
+}
+
+/// Data structure representing the price impact of order flow, i.e. Kyle's lambda
+#[derive(Clone, Debug)]
+pub struct SplattedImpact {
+    /// slope of mid price changes regressed on order flow, in price per unit of quantity
+    pub lambda: Option<f64>,
+    /// coefficient of determination of the regression
+    pub r_squared: Option<f64>,
+    /// number of consecutive book pairs the regression was fitted on
+    pub samples: usize,
+}
+
+/// private utility method for the least squares slope and coefficient of determination of a
+/// linear regression of y on x, None when x is constant
+fn regress(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let count = points.len() as f64;
+    let (mean_x, mean_y) = points.iter().fold((0.0, 0.0), |acc, (x, y)| {
+        (acc.0 + x / count, acc.1 + y / count)
+    });
+    let (covariance, variance_x, variance_y) =
+        points.iter().fold((0.0, 0.0, 0.0), |acc, (x, y)| {
+            (
+                acc.0 + (x - mean_x) * (y - mean_y),
+                acc.1 + (x - mean_x).powi(2),
+                acc.2 + (y - mean_y).powi(2),
+            )
+        });
+    if variance_x <= 0.0 {
+        return None;
+    }
+    let r_squared = if variance_y > 0.0 {
+        covariance.powi(2) / (variance_x * variance_y)
+    } else {
+        0.0
+    };
+    Some((covariance / variance_x, r_squared))
+}
+
+/// Functor like object estimating the price impact of order flow over the whole cached history
+pub struct SplatImpact {}
+
+impl SplatImpact {
+    pub async fn splat(history: &BookHistory) -> SplattedImpact {
+        let tops = history.top_of_book_window(i64::MIN, i64::MAX).await;
+        let mid = |ask: &Option<Order>, bid: &Option<Order>| match (ask, bid) {
+            (Some(ask), Some(bid)) => Some((ask.price + bid.price) / 2.0),
+            _ => None,
+        };
+
+        let points = tops
+            .iter()
+            .zip(tops.iter().skip(1))
+            .filter_map(|((_, previous_ask, previous_bid), (_, ask, bid))| {
+                match (mid(previous_ask, previous_bid), mid(ask, bid)) {
+                    (Some(previous_mid), Some(mid)) => Some((
+                        order_flow((previous_ask, previous_bid), (ask, bid)),
+                        mid - previous_mid,
+                    )),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let regression = regress(&points);
+        SplattedImpact {
+            lambda: regression.map(|(slope, _)| slope),
+            r_squared: regression.map(|(_, r_squared)| r_squared),
+            samples: points.len(),
+        }
+    }
+}
+
+/// Data structure representing the time weighted average depth of both sides over the window
+#[derive(Clone, Debug)]
+pub struct SplattedTwaDepth {
+    pub time_range: (i64, i64),
+    pub ask_depth: Option<f64>,
+    pub bid_depth: Option<f64>,
+}
+
+/// Functor like object averaging total depth over the time window, weighting each book by how
+/// long it persisted rather than each second equally
+pub struct SplatTwaDepth {}
+
+impl SplatTwaDepth {
+    pub async fn splat(grid: &RenderGrid, history: &BookHistory) -> SplattedTwaDepth {
+        let (ask_depth, bid_depth) = history
+            .time_weighted_depth(grid.time_range.0, grid.time_range.1)
+            .await;
+        SplattedTwaDepth {
+            time_range: grid.time_range,
+            ask_depth,
+            bid_depth,
+        }
+    }
+}
+
+/// Data structure representing the levels closest to the spread in the latest book, each as
+/// (price, size, cumulative size from the best level)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplattedLadder {
+    /// asks from the best one upwards
+    pub asks: Vec<(f64, f64, f64)>,
+    /// bids from the best one downwards
+    pub bids: Vec<(f64, f64, f64)>,
+}
+
+/// Functor like object listing the top levels of both sides of the latest book, as a classic
+/// depth of market ladder
+#[derive(Clone, Debug)]
+pub struct SplatLadder {
+    /// number of levels listed per side
+    pub levels: usize,
+}
+
+impl SplatLadder {
+    pub async fn splat(&self, history: &BookHistory) -> SplattedLadder {
+        let ((_, asks), (_, bids)) = history.get_latest_book().await;
+        let ladder = |levels: &mut dyn Iterator<Item = (&Price, &f64)>| {
+            let mut cumulative = 0.0;
+            levels
+                .filter(|(_, size)| **size > 0.0)
+                .take(self.levels)
+                .map(|(price, size)| {
+                    cumulative += size;
+                    (price.value, *size, cumulative)
+                })
+                .collect::<Vec<_>>()
+        };
+        SplattedLadder {
+            asks: ladder(&mut asks.iter()),
+            bids: ladder(&mut bids.iter().rev()),
+        }
+    }
+}
+
+/// Data structure representing mid price and microprice over time
+#[derive(Clone, Debug)]
+pub struct SplattedPrices {
+    pub mid_prices: Vec<(f64, f64)>,
+    pub micro_prices: Vec<(f64, f64)>,
+}
+
+/// Functor like object for constructing mid price and size weighted microprice series from
+/// order book
+pub struct SplatPrices {}
+
+impl SplatPrices {
+    pub async fn splat(grid: &RenderGrid, history: &BookHistory) -> SplattedPrices {
+        let tops = history
+            .top_of_book_window(grid.time_range.0, grid.time_range.1)
+            .await;
+
+        let mut mid_prices = Vec::new();
+        let mut micro_prices = Vec::new();
+        for (time, ask, bid) in tops.into_iter() {
+            if let (Some(ask), Some(bid)) = (ask, bid) {
+                mid_prices.push((time as f64, (ask.price + bid.price) / 2.0));
+
+                let total_quantity = ask.quantity + bid.quantity;
+                if total_quantity > 0.0 {
+                    micro_prices.push((
+                        time as f64,
+                        (ask.price * bid.quantity + bid.price * ask.quantity) / total_quantity,
+                    ));
+                }
+            }
+        }
+
+        SplattedPrices {
+            mid_prices,
+            micro_prices,
+        }
+    }
+}
+
+/// Result of walking one side of the book to fill an order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill {
+    /// quantity actually available in the book, lower than requested when the book is too thin
+    pub filled_quantity: f64,
+    pub average_price: f64,
+    /// signed cost of the fill relative to mid price in basis points, positive being worse
+    pub slippage_in_bps: f64,
+    pub levels_consumed: usize,
+}
+
+/// Data structure representing the cost of filling a hypothetical order against the latest book
+#[derive(Clone, Debug)]
+pub struct SplattedSlippage {
+    pub quantity: f64,
+    pub mid_price: f64,
+    pub buy: Option<Fill>,
+    pub sell: Option<Fill>,
+}
+
+/// private utility method for filling a quantity against levels ordered from best to worst
+fn walk_book<'a>(
+    levels: impl Iterator<Item = (&'a Price, &'a f64)>,
+    quantity: f64,
+    mid_price: f64,
+    direction: f64,
+) -> Option<Fill> {
+    let mut remaining = quantity;
+    let mut notional = 0.0;
+    let mut levels_consumed = 0;
+    for (price, available) in levels {
+        if remaining <= 0.0 {
+            break;
+        }
+        let taken = remaining.min(*available);
+        notional += taken * price.value;
+        remaining -= taken;
+        levels_consumed += 1;
+    }
+
+    let filled_quantity = quantity - remaining.max(0.0);
+    if filled_quantity <= 0.0 || mid_price <= 0.0 {
+        return None;
+    }
+
+    let average_price = notional / filled_quantity;
+    Some(Fill {
+        filled_quantity,
+        average_price,
+        slippage_in_bps: direction * (average_price / mid_price - 1.0) * 10_000.0,
+        levels_consumed,
+    })
+}
+
+/// Functor like object for walking the latest book to estimate the slippage of an order size
+pub struct SplatSlippage {}
+
+impl SplatSlippage {
+    pub async fn splat(history: &BookHistory, quantity: f64) -> SplattedSlippage {
+        let ((_, asks), (_, bids)) = history.get_latest_book().await;
+
+        let mid_price = match (asks.get_first(), bids.get_last()) {
+            (Some((ask, _)), Some((bid, _))) => (ask.value + bid.value) / 2.0,
+            _ => 0.0,
+        };
+
+        SplattedSlippage {
+            quantity,
+            mid_price,
+            buy: walk_book(asks.iter(), quantity, mid_price, 1.0),
+            sell: walk_book(bids.iter().rev(), quantity, mid_price, -1.0),
+        }
+    }
+}
+
+/// Resting quantity that joined a price level at a given time
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueSegment {
+    pub time: i64,
+    pub quantity: f64,
+}
+
+/// Data structure representing the composition of a price level in queue order, front first
+#[derive(Clone, Debug)]
+pub struct SplattedQueue {
+    pub side: Side,
+    pub price: f64,
+    pub segments: Vec<QueueSegment>,
+}
+
+/// private utility method for inferring queue composition from the sizes of a level over time
+///
+/// without L3 data, every increase in size is taken as a new order joining the back of the queue
+/// and every decrease as the front of the queue being filled, in price-time priority
+fn infer_queue(sizes: impl Iterator<Item = (i64, f64)>) -> Vec<QueueSegment> {
+    let mut segments: Vec<QueueSegment> = vec![];
+    for (time, size) in sizes {
+        let resting = segments
+            .iter()
+            .fold(0.0, |acc, segment| acc + segment.quantity);
+        if size > resting {
+            segments.push(QueueSegment {
+                time,
+                quantity: size - resting,
+            });
+        } else {
+            let mut consumed = resting - size;
+            while consumed > 0.0
+                && let Some(front) = segments.first_mut()
+            {
+                if front.quantity > consumed {
+                    front.quantity -= consumed;
+                    consumed = 0.0;
+                } else {
+                    consumed -= front.quantity;
+                    segments.remove(0);
+                }
+            }
+        }
+    }
+    segments
+}
+
+/// Functor like object for inferring the queue of the level nearest to a focused price
+pub struct SplatQueue {}
+
+impl SplatQueue {
+    pub async fn splat(history: &BookHistory, price: f64) -> Option<SplattedQueue> {
+        let ((_, asks), (_, bids)) = history.get_latest_book().await;
+
+        let distance = |level: &Price| (level.value - price).abs();
+        let nearest = asks
+            .keys()
+            .map(|level| (Side::Ask, level))
+            .chain(bids.keys().map(|level| (Side::Bid, level)))
+            .min_by(|lhs, rhs| f64::total_cmp(&distance(lhs.1), &distance(rhs.1)))
+            .map(|(side, level)| (side, level.clone()));
+        let (side, level) = nearest?;
+
+        let readable = match side {
+            Side::Ask => history.asks.read().await,
+            Side::Bid => history.bids.read().await,
+        };
+        let segments = infer_queue(
+            readable
+                .iter()
+                .map(|(time, book)| (*time, book.get(&level).cloned().unwrap_or(0.0))),
+        );
+
+        Some(SplattedQueue {
+            side,
+            price: level.value,
+            segments,
+        })
+    }
+}
+
+/// Number of seconds in a year used to annualize volatilities
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// Data structure representing rolling realized volatility of mid prices over time
+#[derive(Clone, Debug)]
+pub struct SplattedVolatility {
+    pub window_in_seconds: i64,
+    /// annualized realized volatility as a fraction, e.g. 0.5 for 50%
+    pub volatilities: Vec<(f64, f64)>,
+}
+
+impl SplattedVolatility {
+    /// most recent volatility estimate
+    pub fn latest(&self) -> Option<f64> {
+        self.volatilities.last().map(|(_, volatility)| *volatility)
+    }
+}
+
+/// Functor like object for estimating rolling realized volatility from the mid price series
+#[derive(Clone, Debug)]
+pub struct SplatVolatility {
+    /// length of the rolling window of log returns
+    pub window_in_seconds: i64,
+}
+
+impl SplatVolatility {
+    pub fn splat(&self, prices: &SplattedPrices) -> SplattedVolatility {
+        let returns = prices
+            .mid_prices
+            .windows(2)
+            .filter(|pair| pair[0].1 > 0.0 && pair[1].1 > 0.0)
+            .map(|pair| (pair[1].0, (pair[1].1 / pair[0].1).ln()))
+            .collect::<Vec<_>>();
+
+        let window = self.window_in_seconds.max(1) as f64;
+        let mut volatilities = Vec::with_capacity(returns.len());
+        let mut start = 0;
+        let mut squared_sum = 0.0;
+        for (time, log_return) in returns.iter() {
+            squared_sum += log_return * log_return;
+            while returns[start].0 <= time - window {
+                squared_sum -= returns[start].1 * returns[start].1;
+                start += 1;
+            }
+            volatilities.push((
+                *time,
+                (squared_sum.max(0.0) * SECONDS_PER_YEAR / window).sqrt(),
+            ));
+        }
+
+        SplattedVolatility {
+            window_in_seconds: self.window_in_seconds,
+            volatilities,
+        }
+    }
+}
+
+/// Data structure representing executable arbitrage spreads between two symbols over time
+#[derive(Clone, Debug)]
+pub struct SplattedArbitrage {
+    pub time_range: (i64, i64),
+    pub symbols: (String, String),
+    /// round trip fees in basis points an arbitrage has to beat
+    pub fee_in_bps: f64,
+    /// spread in basis points when buying the first symbol and selling the second
+    pub first_to_second: Vec<(f64, f64)>,
+    /// spread in basis points when buying the second symbol and selling the first
+    pub second_to_first: Vec<(f64, f64)>,
+}
+
+impl SplattedArbitrage {
+    /// best latest spread in basis points over both directions
+    pub fn latest_best_spread(&self) -> Option<f64> {
+        match (self.first_to_second.last(), self.second_to_first.last()) {
+            (Some((_, forward)), Some((_, backward))) => Some(forward.max(*backward)),
+            _ => None,
+        }
+    }
+
+    /// whether the latest spread beats the fees
+    pub fn is_profitable(&self) -> bool {
+        self.latest_best_spread()
+            .is_some_and(|spread| spread > self.fee_in_bps)
+    }
+}
+
+/// private utility method for the spread in basis points of buying at ask and selling at bid
+fn executable_spread(ask: &Order, bid: &Order) -> Option<f64> {
+    if ask.price > 0.0 {
+        Some((bid.price - ask.price) / ask.price * 10_000.0)
+    } else {
+        None
+    }
+}
+
+/// Functor like object for constructing executable arbitrage spreads from the books of two
+/// symbols quoting the same asset
+pub struct SplatArbitrage {}
+
+impl SplatArbitrage {
+    pub async fn splat(
+        grid: &RenderGrid,
+        symbols: (String, String),
+        histories: (&BookHistory, &BookHistory),
+        fee_in_bps: f64,
+    ) -> SplattedArbitrage {
+        let first_tops = histories
+            .0
+            .top_of_book_window(grid.time_range.0, grid.time_range.1)
+            .await;
+        let second_tops = histories
+            .1
+            .top_of_book_window(i64::MIN, grid.time_range.1)
+            .await;
+
+        let mut first_to_second = Vec::new();
+        let mut second_to_first = Vec::new();
+
+        // as of join: each book of the first symbol is matched with the latest book of the second
+        let mut second_index = 0;
+        for (time, first_ask, first_bid) in first_tops.iter() {
+            while second_index + 1 < second_tops.len() && second_tops[second_index + 1].0 <= *time {
+                second_index += 1;
+            }
+
+            let Some((second_time, second_ask, second_bid)) = second_tops.get(second_index) else {
+                break;
+            };
+            if second_time > time {
+                continue;
+            }
+
+            if let (Some(ask), Some(bid)) = (first_ask, second_bid)
+                && let Some(spread) = executable_spread(ask, bid)
+            {
+                first_to_second.push((*time as f64, spread));
+            }
+            if let (Some(ask), Some(bid)) = (second_ask, first_bid)
+                && let Some(spread) = executable_spread(ask, bid)
+            {
+                second_to_first.push((*time as f64, spread));
+            }
+        }
+
+        SplattedArbitrage {
+            time_range: grid.time_range,
+            symbols,
+            fee_in_bps,
+            first_to_second,
+            second_to_first,
+        }
+    }
+}
+
+/// Operation combining the mid prices of two symbols into a synthetic basis series
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum BasisOperation {
+    /// first mid price minus second mid price
+    Difference,
+    /// first mid price over second mid price
+    Ratio,
+}
+
+impl BasisOperation {
+    /// value of the basis between two mid prices, None when undefined
+    pub fn apply(self, first: f64, second: f64) -> Option<f64> {
+        match self {
+            BasisOperation::Difference => Some(first - second),
+            BasisOperation::Ratio if second > 0.0 => Some(first / second),
+            BasisOperation::Ratio => None,
+        }
+    }
+}
+
+/// Data structure representing a synthetic series combining the mid prices of two symbols
+#[derive(Clone, Debug)]
+pub struct SplattedBasis {
+    pub time_range: (i64, i64),
+    pub symbols: (String, String),
+    pub operation: BasisOperation,
+    /// basis value over time
+    pub series: Vec<(f64, f64)>,
+    /// correlation of the consecutive mid price changes of both symbols
+    pub correlation: Option<f64>,
+}
+
+/// private utility method for the mid price of a top of book
+fn mid_of(ask: &Option<Order>, bid: &Option<Order>) -> Option<f64> {
+    match (ask, bid) {
+        (Some(ask), Some(bid)) => Some((ask.price + bid.price) / 2.0),
+        _ => None,
+    }
+}
+
+/// Functor like object for constructing a basis series from the mid prices of two symbols
+pub struct SplatBasis {}
+
+impl SplatBasis {
+    pub async fn splat(
+        grid: &RenderGrid,
+        symbols: (String, String),
+        histories: (&BookHistory, &BookHistory),
+        operation: BasisOperation,
+    ) -> SplattedBasis {
+        let first_tops = histories
+            .0
+            .top_of_book_window(grid.time_range.0, grid.time_range.1)
+            .await;
+        let second_tops = histories
+            .1
+            .top_of_book_window(i64::MIN, grid.time_range.1)
+            .await;
+
+        let mut series = Vec::new();
+        let mut mids: Vec<(f64, f64)> = Vec::new();
+
+        // as of join: each book of the first symbol is matched with the latest book of the second
+        let mut second_index = 0;
+        for (time, first_ask, first_bid) in first_tops.iter() {
+            while second_index + 1 < second_tops.len() && second_tops[second_index + 1].0 <= *time {
+                second_index += 1;
+            }
+
+            let Some((second_time, second_ask, second_bid)) = second_tops.get(second_index) else {
+                break;
+            };
+            if second_time > time {
+                continue;
+            }
+
+            if let (Some(first_mid), Some(second_mid)) =
+                (mid_of(first_ask, first_bid), mid_of(second_ask, second_bid))
+                && let Some(value) = operation.apply(first_mid, second_mid)
+            {
+                series.push((*time as f64, value));
+                mids.push((first_mid, second_mid));
+            }
+        }
+
+        let changes: Vec<(f64, f64)> = mids
+            .windows(2)
+            .map(|pair| (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1))
+            .collect();
+        let correlation = regress(&changes).map(|(slope, r_squared)| {
+            if slope < 0.0 {
+                -r_squared.sqrt()
+            } else {
+                r_squared.sqrt()
+            }
+        });
+
+        SplattedBasis {
+            time_range: grid.time_range,
+            symbols,
+            operation,
+            series,
+            correlation,
+        }
+    }
+}
+
+/// Enum of the supports produced by the stages of a pipeline
+#[derive(Clone, Debug)]
+pub enum Splatted {
+    Depth(SplattedDepth),
+    Volumes(SplattedVolumes),
+    Blocks(SplattedBlocks),
+    Imbalance(SplattedImbalance),
+    Prices(SplattedPrices),
+    Profile(SplattedProfile),
+    Flicker(SplattedFlicker),
+    Volatility(SplattedVolatility),
+    Impact(SplattedImpact),
+    Activity(SplattedActivity),
+    Trades(SplattedTrades),
+    Levels(SplattedLevels),
+    TwaDepth(SplattedTwaDepth),
+    Ladder(SplattedLadder),
+}
+
+impl Splatted {
+    /// name of the support, used to label the timing of the stage producing it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Splatted::Depth(_) => "Depth",
+            Splatted::Volumes(_) => "Volumes",
+            Splatted::Blocks(_) => "Blocks",
+            Splatted::Imbalance(_) => "Imbalance",
+            Splatted::Prices(_) => "Prices",
+            Splatted::Profile(_) => "Profile",
+            Splatted::Flicker(_) => "Flicker",
+            Splatted::Volatility(_) => "Volatility",
+            Splatted::Impact(_) => "Impact",
+            Splatted::Activity(_) => "Activity",
+            Splatted::Trades(_) => "Trades",
+            Splatted::Levels(_) => "Levels",
+            Splatted::TwaDepth(_) => "TwaDepth",
+            Splatted::Ladder(_) => "Ladder",
+        }
+    }
+}
+
+/// Time taken by one step of a pipeline run
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageTiming {
+    pub name: &'static str,
+    pub elapsed: Duration,
+}
+
+/// Future of a support being splatted by a stage
+pub type SplatFuture<'a> = Pin<Box<dyn Future<Output = Splatted> + Send + 'a>>;
+
+/// Common interface of the stages splatting an order book history onto a (time, price) grid
+pub trait Splatter: Send + Sync {
+    /// view whose splat mode the stage follows, if any
+    fn view(&self) -> Option<View>;
+
+    /// splat the history onto the grid
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a>;
+}
+
+impl Splatter for SplatDepth {
+    fn view(&self) -> Option<View> {
+        Some(View::Depth)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Depth(self.splat(grid, history, splat_mode).await) })
+    }
+}
+
+impl Splatter for SplatVolume {
+    fn view(&self) -> Option<View> {
+        Some(View::Volume)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(
+            async move { Splatted::Volumes(SplatVolume::splat(grid, history, splat_mode).await) },
+        )
+    }
+}
+
+impl Splatter for SplatBlocks {
+    fn view(&self) -> Option<View> {
+        Some(View::Map)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Blocks(self.splat(grid, history, splat_mode).await) })
+    }
+}
+
+impl Splatter for SplatImbalance {
+    fn view(&self) -> Option<View> {
+        Some(View::Imbalance)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move {
+            Splatted::Imbalance(SplatImbalance::splat(grid, history, splat_mode).await)
+        })
+    }
+}
+
+impl Splatter for SplatPrices {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Prices(SplatPrices::splat(grid, history).await) })
+    }
+}
+
+impl Splatter for SplatProfile {
+    fn view(&self) -> Option<View> {
+        Some(View::Profile)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(
+            async move { Splatted::Profile(SplatProfile::splat(grid, history, splat_mode).await) },
+        )
+    }
+}
+
+impl Splatter for SplatFlicker {
+    fn view(&self) -> Option<View> {
+        Some(View::Profile)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Flicker(self.splat(grid, history, splat_mode).await) })
+    }
+}
+
+impl Splatter for SplatVolatility {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move {
+            let prices = SplatPrices::splat(grid, history).await;
+            Splatted::Volatility(self.splat(&prices))
+        })
+    }
+}
+
+impl Splatter for SplatActivity {
+    fn view(&self) -> Option<View> {
+        Some(View::Activity)
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move {
+            Splatted::Activity(SplatActivity::splat(grid, history, splat_mode).await)
+        })
+    }
+}
+
+impl Splatter for SplatTrades {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Trades(SplatTrades::splat(grid, history).await) })
+    }
+}
+
+impl Splatter for SplatLevels {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Levels(self.splat(grid, history).await) })
+    }
+}
+
+impl Splatter for SplatImpact {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        _grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Impact(SplatImpact::splat(history).await) })
+    }
+}
+
+impl Splatter for SplatTwaDepth {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::TwaDepth(SplatTwaDepth::splat(grid, history).await) })
+    }
+}
+
+impl Splatter for SplatLadder {
+    fn view(&self) -> Option<View> {
+        None
+    }
+
+    fn run<'a>(
+        &'a self,
+        _grid: &'a RenderGrid,
+        history: &'a BookHistory,
+        _splat_mode: SplatMode,
+    ) -> SplatFuture<'a> {
+        Box::pin(async move { Splatted::Ladder(self.splat(history).await) })
+    }
+}
+
+/// Encapsulating object for running all splatting of order book to different supports
+#[derive(Clone)]
+pub struct Pipeline {
+    grid_generator: GenerateGrid,
+    depth_splatter: SplatDepth,
+    flicker_splatter: SplatFlicker,
+    blocks_splatter: SplatBlocks,
+    levels_detector: SplatLevels,
+    volatility_estimator: SplatVolatility,
+    ladder: SplatLadder,
+    kernel: Kernel,
+    splat_modes: HashMap<View, SplatMode>,
+}
+
+impl Pipeline {
+    pub fn new(
+        time_window_in_seconds: u64,
+        number_time_values: usize,
+        number_price_values: usize,
+        kernel: Kernel,
+    ) -> Pipeline {
+        Pipeline {
+            grid_generator: GenerateGrid {
+                time_window_in_seconds,
+                number_time_values,
+                number_price_values,
+                price_band: None,
+                price_scale: PriceScale::Linear,
+                price_range: PriceRange::Full,
+                time_end: None,
+            },
+            depth_splatter: SplatDepth {
+                mode: DepthMode::Density,
+            },
+            flicker_splatter: SplatFlicker {
+                horizon_in_seconds: 5,
+            },
+            blocks_splatter: SplatBlocks {
+                half_life_in_seconds: None,
+            },
+            levels_detector: SplatLevels {
+                quantile: 0.8,
+                min_persistence: 0.8,
+                max_levels: 3,
+            },
+            volatility_estimator: SplatVolatility {
+                window_in_seconds: 60,
+            },
+            ladder: SplatLadder { levels: 10 },
+            kernel,
+            splat_modes: HashMap::new(),
+        }
+    }
+
+    /// run arbitrage analysis between two symbols on the grid of the first one
+    pub async fn run_arbitrage(
+        &self,
+        symbols: (String, String),
+        histories: (&BookHistory, &BookHistory),
+        fee_in_bps: f64,
+    ) -> SplattedArbitrage {
+        let grid = self.grid_generator.grid(histories.0).await;
+
+        SplatArbitrage::splat(&grid, symbols, histories, fee_in_bps).await
+    }
+
+    /// run basis analysis between two symbols on the grid of the first one
+    pub async fn run_basis(
+        &self,
+        symbols: (String, String),
+        histories: (&BookHistory, &BookHistory),
+        operation: BasisOperation,
+    ) -> SplattedBasis {
+        let grid = self.grid_generator.grid(histories.0).await;
+
+        SplatBasis::splat(&grid, symbols, histories, operation).await
+    }
+
+    /// estimate the slippage of filling a quantity against the latest book
+    pub async fn run_slippage(&self, history: &BookHistory, quantity: f64) -> SplattedSlippage {
+        SplatSlippage::splat(history, quantity).await
+    }
+
+    /// run only the market depth of a transient history, e.g. a one-shot snapshot
+    pub async fn run_depth(&self, history: &BookHistory) -> SplattedDepth {
+        let grid = self.grid_generator.grid(history).await;
+
+        self.depth_splatter
+            .splat(&grid, history, self.splat_mode(View::Depth))
+            .await
+    }
+
+    /// infer the queue composition of the level nearest to a price in the latest book
+    pub async fn run_queue(&self, history: &BookHistory, price: f64) -> Option<SplattedQueue> {
+        SplatQueue::splat(history, price).await
+    }
+
+    /// fit the price range of the grid to the whole book, a percentile of its volume or a
+    /// distance around mid
+    pub fn set_price_range(&mut self, price_range: PriceRange) {
+        self.grid_generator.price_range = price_range;
+    }
+
+    /// set the number of time and price values of the grid, e.g. to the cells of the panel
+    pub fn set_resolution(&mut self, number_time_values: usize, number_price_values: usize) {
+        self.grid_generator.number_time_values = number_time_values;
+        self.grid_generator.number_price_values = number_price_values;
+    }
+
+    /// length in seconds of the time range of the grid
+    pub fn time_window(&self) -> u64 {
+        self.grid_generator.time_window_in_seconds
+    }
+
+    /// set the length in seconds of the time range of the grid, e.g. to zoom on recent books
+    pub fn set_time_window(&mut self, time_window_in_seconds: u64) {
+        self.grid_generator.time_window_in_seconds = time_window_in_seconds;
+    }
+
+    /// end the time range of the grid at a time shared with other histories, None to end it at
+    /// the latest book
+    pub fn set_time_end(&mut self, time_end: Option<i64>) {
+        self.grid_generator.time_end = time_end;
+    }
+
+    /// space the price axis linearly or logarithmically
+    pub fn set_price_scale(&mut self, price_scale: PriceScale) {
+        self.grid_generator.price_scale = price_scale;
+    }
+
+    /// fade older book snapshots in the order map with an exponential decay of given half life
+    pub fn set_decay_half_life(&mut self, half_life_in_seconds: Option<f64>) {
+        self.blocks_splatter.half_life_in_seconds = half_life_in_seconds;
+    }
+
+    /// set the number of levels per side listed by the ladder
+    pub fn set_ladder_levels(&mut self, levels: usize) {
+        self.ladder.levels = levels;
+    }
+
+    /// display prices in a fixed band around a peg instead of the book's range
+    pub fn set_price_band(&mut self, price_band: Option<PriceBand>) {
+        self.grid_generator.price_band = price_band;
+    }
+
+    /// splat mode of a view, kernel density estimation unless switched
+    pub fn splat_mode(&self, view: View) -> SplatMode {
+        match self.splat_modes.get(&view) {
+            Some(mode) => *mode,
+            None => SplatMode::Kernel(self.kernel),
+        }
+    }
+
+    /// switch a view, or all views following the order map, between kernel density estimation
+    /// and exact binning
+    pub fn toggle_splat_mode(&mut self, view: Option<View>) -> SplatMode {
+        let mode = match self.splat_mode(view.unwrap_or(View::Map)) {
+            SplatMode::Kernel(_) => SplatMode::Histogram,
+            SplatMode::Histogram => SplatMode::Kernel(self.kernel),
+        };
+        match view {
+            Some(view) => {
+                self.splat_modes.insert(view, mode);
+            }
+            None => View::ALL.into_iter().for_each(|view| {
+                self.splat_modes.insert(view, mode);
+            }),
+        }
+        mode
+    }
+
+    /// switch between density and cumulative market depth
+    pub fn toggle_depth_mode(&mut self) -> DepthMode {
+        self.depth_splatter.mode = self.depth_splatter.mode.toggled();
+        self.depth_splatter.mode
+    }
+
+    /// registry of the stages run on every update, in the order of their supports
+    fn stages(&self) -> Vec<&dyn Splatter> {
+        vec![
+            &self.depth_splatter,
+            &SplatVolume {},
+            &self.blocks_splatter,
+            &SplatImbalance {},
+            &SplatPrices {},
+            &SplatProfile {},
+            &self.flicker_splatter,
+            &self.volatility_estimator,
+            &SplatImpact {},
+            &SplatActivity {},
+            &SplatTrades {},
+            &self.levels_detector,
+            &SplatTwaDepth {},
+            &self.ladder,
+        ]
+    }
+
+    /// run every registered stage on a common grid, timing the grid generation and every stage
+    pub async fn run(&self, history: &BookHistory) -> (Vec<Splatted>, Vec<StageTiming>) {
+        let start = Instant::now();
+        let grid = self.grid_generator.grid(history).await;
+        let mut timings = vec![StageTiming {
+            name: "Grid",
+            elapsed: start.elapsed(),
+        }];
+
+        let mut supports = Vec::new();
+        for stage in self.stages() {
+            let splat_mode = match stage.view() {
+                Some(view) => self.splat_mode(view),
+                None => SplatMode::Kernel(self.kernel),
+            };
+            let start = Instant::now();
+            let splatted = stage.run(&grid, history, splat_mode).await;
+            let elapsed = start.elapsed();
+            trace!(stage = splatted.name(), ?elapsed, "pipeline stage");
+            timings.push(StageTiming {
+                name: splatted.name(),
+                elapsed,
+            });
+            supports.push(splatted);
+        }
+        (supports, timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use chrono::DateTime;
+
+    fn generic_booked_case() -> Booked {
+        Booked {
+            symbol: "ETH/EUR".to_string(),
+            timestamp: DateTime::from_timestamp(0, 0).unwrap().to_rfc3339(),
+            asks: vec![
+                Order {
+                    price: 5.0,
+                    quantity: 6.0,
+                },
+                Order {
+                    price: 7.0,
+                    quantity: 8.0,
+                },
+            ],
+            bids: vec![
+                Order {
+                    price: 1.0,
+                    quantity: 2.0,
+                },
+                Order {
+                    price: 3.0,
+                    quantity: 4.0,
+                },
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_empty_history() {
+        let history = BookHistory::new(60);
+
+        let latest = history.get_latest_book().await;
+        assert_eq!(latest.0.0, 0);
+        assert_eq!(latest.1.0, 0);
+
+        assert_eq!(latest.0.1.len(), 0);
+        assert_eq!(latest.1.1.len(), 0);
+
+        let integrated = history.integrate_window(0, 60).await;
+        assert_eq!(integrated.0.len(), 0);
+        assert_eq!(integrated.1.len(), 0);
+
+        let extracted = history.extract_window(0, 45).await;
+        assert_eq!(extracted.time_window_in_seconds, 45);
+
+        let readable_asks = extracted.asks.read().await;
+        let readable_bids = extracted.bids.read().await;
+        assert_eq!(readable_asks.len(), 0);
+        assert_eq!(readable_bids.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_book_updates() {
+        let mut history = BookHistory::new(60);
+
+        let updated = history.update(generic_booked_case()).await;
+        assert!(updated.is_ok());
+        assert!(!updated.unwrap().is_some());
+
+        let readable_asks = history.asks.read().await;
+        let readable_bids = history.bids.read().await;
+
+        assert_eq!(readable_asks.len(), 1);
+        assert_eq!(readable_bids.len(), 1);
+
+        let first_asks = readable_asks.get_first();
+        let first_bids = readable_bids.get_first();
+
+        assert!(first_asks.is_some());
+        assert!(first_bids.is_some());
+
+        let (asks_time, asks) = first_asks.unwrap();
+        let (bids_time, bids) = first_bids.unwrap();
+
+        assert_eq!(*asks_time, 0);
+        assert_eq!(*bids_time, 0);
+
+        assert_eq!(asks.len(), 2);
+        assert_eq!(bids.len(), 2);
+
+        itertools::assert_equal(
+            asks.clone().into_iter(),
+            vec![(Price { value: 5.0 }, 6.0), (Price { value: 7.0 }, 8.0)].into_iter(),
+        );
+
+        itertools::assert_equal(
+            bids.clone().into_iter(),
+            vec![(Price { value: 1.0 }, 2.0), (Price { value: 3.0 }, 4.0)].into_iter(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bad_timestamped_update() {
+        let mut history = BookHistory::new(60);
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = "Bad Timestamp".to_string();
+
+        let updated = history.update(booked).await;
+        assert!(updated.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backward_clock_jump() {
+        let mut history = BookHistory::new(60);
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(3_600, 0).unwrap().to_rfc3339();
+        let _ = history.update(booked).await;
+
+        // an update stamped before the whole history is merged into its oldest book
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(0, 0).unwrap().to_rfc3339();
+        booked.asks = vec![Order {
+            price: 5.0,
+            quantity: 1.0,
+        }];
+        let updated = history.update(booked).await;
+        assert!(updated.is_ok());
+        assert!(updated.unwrap().is_none());
+
+        let ((asks_time, asks), _) = history.get_latest_book().await;
+        assert_eq!(asks_time, 3_600);
+        assert_eq!(asks.get(&Price { value: 5.0 }), Some(&1.0));
+        assert!(history.asks.read().await.keys().all(|time| *time == 3_600));
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_update() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(2, 0).unwrap().to_rfc3339();
+        booked.asks = vec![Order {
+            price: 5.0,
+            quantity: 1.0,
+        }];
+        booked.bids = vec![];
+        let _ = history.update(booked).await;
+
+        // a late update changing a level the latest book already changed, and another one
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(1, 0).unwrap().to_rfc3339();
+        booked.asks = vec![
+            Order {
+                price: 5.0,
+                quantity: 3.0,
+            },
+            Order {
+                price: 7.0,
+                quantity: 0.0,
+            },
+        ];
+        booked.bids = vec![];
+        let updated = history.update(booked).await;
+        assert!(updated.unwrap().is_none());
+
+        let asks = history.asks.read().await;
+        itertools::assert_equal(asks.keys().copied(), [0, 1, 2]);
+        itertools::assert_equal(
+            asks.get(&1)
+                .unwrap()
+                .iter()
+                .map(|(price, quantity)| (price.value, *quantity)),
+            [(5.0, 3.0)],
+        );
+        // the latest book keeps its own level and takes the removal it did not change
+        itertools::assert_equal(
+            asks.get(&2)
+                .unwrap()
+                .iter()
+                .map(|(price, quantity)| (price.value, *quantity)),
+            [(5.0, 1.0)],
+        );
+        itertools::assert_equal(history.bids.read().await.keys().copied(), [0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_update() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let mut booked = generic_booked_case();
+        booked.asks = vec![Order {
+            price: 6.0,
+            quantity: 1.0,
+        }];
+        booked.bids = vec![Order {
+            price: 1.0,
+            quantity: 0.0,
+        }];
+        let _ = history.update(booked).await;
+
+        let ((asks_time, asks), (bids_time, bids)) = history.get_latest_book().await;
+        assert_eq!((asks_time, bids_time), (0, 0));
+        assert_eq!(asks.len(), 3);
+        assert_eq!(bids.len(), 1);
+        assert_eq!(history.asks.read().await.len(), 1);
+        assert_eq!(history.bids.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_latest_book() {
+        let mut history = BookHistory::new(60);
+
+        let _ = history.update(generic_booked_case()).await;
+
+        let ((asks_time, asks), (bids_time, bids)) = history.get_latest_book().await;
+
+        assert_eq!(asks_time, 0);
+        assert_eq!(bids_time, 0);
+
+        assert_eq!(asks.len(), 2);
+        assert_eq!(bids.len(), 2);
+
+        itertools::assert_equal(
+            asks.clone().into_iter(),
+            vec![(Price { value: 5.0 }, 6.0), (Price { value: 7.0 }, 8.0)].into_iter(),
+        );
+
+        itertools::assert_equal(
+            bids.clone().into_iter(),
+            vec![(Price { value: 1.0 }, 2.0), (Price { value: 3.0 }, 4.0)].into_iter(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_book_multiple_book_updates() {
+        let mut history = BookHistory::new(60);
+
+        let updated = history.update(generic_booked_case()).await;
+        assert!(updated.is_ok());
+        assert!(!updated.unwrap().is_some());
+
+        {
+            let readable_asks = history.asks.read().await;
+            let readable_bids = history.bids.read().await;
+
+            assert_eq!(readable_asks.len(), 1);
+            assert_eq!(readable_bids.len(), 1);
+        }
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(60, 0).unwrap().to_rfc3339();
+        let updated = history.update(booked).await;
+        assert!(updated.is_ok());
+        assert!(!updated.unwrap().is_some());
+
+        {
+            let readable_asks = history.asks.read().await;
+            let readable_bids = history.bids.read().await;
+
+            assert_eq!(readable_asks.len(), 2);
+            assert_eq!(readable_bids.len(), 2);
+        }
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(61, 0).unwrap().to_rfc3339();
+        let updated = history.update(booked).await;
+        assert!(updated.is_ok());
+        let updated_option = updated.unwrap();
+        assert!(updated_option.is_some());
+
+        let ((time_asks, _), (time_bids, _)) = updated_option.unwrap();
+        assert_eq!(time_asks, 0);
+        assert_eq!(time_bids, 0);
+
+        {
+            let readable_asks = history.asks.read().await;
+            let readable_bids = history.bids.read().await;
+
+            assert_eq!(readable_asks.len(), 2);
+            assert_eq!(readable_bids.len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thin_history() {
+        let mut history = BookHistory::new(60);
+        for i_time in 0..20 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let _ = history.update(booked).await;
+        }
+        let usage = history.memory_usage().await;
+        assert_eq!(usage, 2 * 20 * (64 + 2 * 48));
+
+        history.thin(10, 4).await;
+        {
+            let readable_asks = history.asks.read().await;
+            let readable_bids = history.bids.read().await;
+            itertools::assert_equal(
+                readable_asks.keys().cloned(),
+                [0, 4, 8].into_iter().chain(10..20),
+            );
+            itertools::assert_equal(readable_asks.keys(), readable_bids.keys());
+        }
+        assert_eq!(history.memory_usage().await, 2 * 13 * (64 + 2 * 48));
+
+        // thinning again with a multiple of the step keeps the same periods
+        history.thin(10, 8).await;
+        let readable_asks = history.asks.read().await;
+        itertools::assert_equal(
+            readable_asks.keys().cloned(),
+            [0, 8].into_iter().chain(10..20),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_time_weighted_depth() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(15, 0).unwrap().to_rfc3339();
+        booked.asks = vec![Order {
+            price: 5.0,
+            quantity: 26.0,
+        }];
+        booked.bids = vec![];
+        let _ = history.update(booked).await;
+
+        // the first book persisted for 15 seconds, the second for 5
+        let (ask_depth, bid_depth) = history.time_weighted_depth(0, 20).await;
+        assert_eq!(ask_depth, Some((14.0 * 15.0 + 34.0 * 5.0) / 20.0));
+        assert_eq!(bid_depth, Some(6.0));
+
+        let (ask_depth, _) = history.time_weighted_depth(10, 20).await;
+        assert_eq!(ask_depth, Some((14.0 * 5.0 + 34.0 * 5.0) / 10.0));
+
+        let (ask_depth, _) = history.time_weighted_depth(20, 20).await;
+        assert_eq!(ask_depth, Some(34.0));
+    }
+
+    #[tokio::test]
+    async fn test_integrate_window() {
+        let mut history = BookHistory::new(60);
+
+        for i_time in 0..60 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let updated = history.update(booked).await;
+            assert!(updated.is_ok());
+            assert!(!updated.unwrap().is_some());
+        }
+
+        let latest = history.get_latest_book().await;
+        assert_eq!(latest.0.0, 59);
+        assert_eq!(latest.1.0, 59);
+
+        let (integrated_asks, integrated_bids) = history.integrate_window(10, 40).await;
+
+        itertools::assert_equal(
+            integrated_asks.into_iter(),
+            (10..41).map(|time| (time, 14.0)),
+        );
+        itertools::assert_equal(
+            integrated_bids.into_iter(),
+            (10..41).map(|time| (time, 6.0)),
+        );
+
+        // regular snapshots weigh one second each, the latest one has not persisted yet
+        let (ask_depth, bid_depth) = history.time_weighted_depth(10, 59).await;
+        assert_eq!(ask_depth, Some(14.0));
+        assert_eq!(bid_depth, Some(6.0));
+
+        let extracted = history.extract_window(15, 35).await;
+
+        assert_eq!(extracted.time_window_in_seconds, 20);
+
+        let extracted_asks = extracted.asks.read().await;
+        let extracted_bids = extracted.bids.read().await;
+
+        itertools::assert_equal(
+            extracted_asks.clone().into_iter().map(|(time, _)| time),
+            15..36,
+        );
+        itertools::assert_equal(
+            extracted_bids.clone().into_iter().map(|(time, _)| time),
+            15..36,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_top_of_book_window() {
+        let mut history = BookHistory::new(60);
+
+        for i_time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let _ = history.update(booked).await;
+        }
+
+        let tops = history.top_of_book_window(2, 5).await;
+
+        itertools::assert_equal(tops.iter().map(|(time, _, _)| *time), 2..6);
+
+        for (_, ask, bid) in tops.into_iter() {
+            assert_eq!(
+                ask,
+                Some(Order {
+                    price: 5.0,
+                    quantity: 6.0
+                })
+            );
+            assert_eq!(
+                bid,
+                Some(Order {
+                    price: 3.0,
+                    quantity: 4.0
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_order_flow() {
+        let previous = (
+            Some(Order {
+                price: 5.0,
+                quantity: 6.0,
+            }),
+            Some(Order {
+                price: 3.0,
+                quantity: 4.0,
+            }),
+        );
+
+        assert_eq!(
+            order_flow((&previous.0, &previous.1), (&previous.0, &previous.1)),
+            0.0
+        );
+
+        let bigger_bid = Some(Order {
+            price: 3.0,
+            quantity: 10.0,
+        });
+        assert_eq!(
+            order_flow((&previous.0, &previous.1), (&previous.0, &bigger_bid)),
+            6.0
+        );
+
+        let higher_bid = Some(Order {
+            price: 4.0,
+            quantity: 1.0,
+        });
+        assert_eq!(
+            order_flow((&previous.0, &previous.1), (&previous.0, &higher_bid)),
+            1.0
+        );
+
+        let bigger_ask = Some(Order {
+            price: 5.0,
+            quantity: 8.0,
+        });
+        assert_eq!(
+            order_flow((&previous.0, &previous.1), (&bigger_ask, &previous.1)),
+            -2.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_level_changes() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+        assert!(history.level_changes(0, 10).await.is_empty());
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(2, 0).unwrap().to_rfc3339();
+        // amend the ask at 5, remove the one at 7 and add a bid at 2
+        booked.asks = vec![
+            Order {
+                price: 5.0,
+                quantity: 1.0,
+            },
+            Order {
+                price: 7.0,
+                quantity: 0.0,
+            },
+        ];
+        booked.bids = vec![Order {
+            price: 2.0,
+            quantity: 1.0,
+        }];
+        let _ = history.update(booked).await;
+
+        let changes = history.level_changes(0, 10).await;
+        assert_eq!(
+            changes,
+            vec![(
+                2,
+                LevelChanges {
+                    added: 1,
+                    amended: 1,
+                    removed: 1,
+                }
+            )]
+        );
+        assert!(history.level_changes(3, 10).await.is_empty());
+
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 5),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let splatted = SplatActivity::splat(&grid, &history, SplatMode::Histogram).await;
+        // one change within a half second bin is a rate of two per second
+        assert_eq!(splatted.arrivals[4], 2.0);
+        assert_eq!(splatted.cancellations.iter().sum::<f64>(), 2.0);
+        assert_eq!(splatted.amendments.iter().sum::<f64>(), 2.0);
+    }
+
+    fn generic_traded_case(time: i64, aggressor: Aggressor, price: f64) -> Traded {
+        Traded {
+            symbol: "ETH/EUR".to_string(),
+            timestamp: DateTime::from_timestamp(time, 0).unwrap().to_rfc3339(),
+            aggressor,
+            price,
+            quantity: 1.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_book() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(1, 0).unwrap().to_rfc3339();
+        booked.asks.remove(0);
+        booked.bids[1].quantity = 1.0;
+        let _ = history.replace(booked).await;
+
+        let ((time, asks), (_, bids)) = history.get_latest_book().await;
+        assert_eq!(time, 1);
+        itertools::assert_equal(
+            asks.iter()
+                .map(|(price, quantity)| (price.value, *quantity)),
+            [(7.0, 8.0)],
+        );
+        itertools::assert_equal(
+            bids.iter()
+                .map(|(price, quantity)| (price.value, *quantity)),
+            [(1.0, 2.0), (3.0, 1.0)],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_trades() {
+        let mut history = BookHistory::new(10);
+        let _ = history
+            .update_trades(vec![
+                generic_traded_case(0, Aggressor::Buy, 5.0),
+                generic_traded_case(5, Aggressor::Sell, 3.0),
+            ])
+            .await;
+        assert_eq!(history.trades.read().await.len(), 2);
+
+        let _ = history
+            .update_trades(vec![
+                generic_traded_case(12, Aggressor::Buy, 5.0),
+                generic_traded_case(11, Aggressor::Buy, 5.0),
+            ])
+            .await;
+        let times = history
+            .trades
+            .read()
+            .await
+            .iter()
+            .map(|(time, _)| *time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, vec![5, 12, 12]);
+
+        let extract = history.extract_window(10, 20).await;
+        assert_eq!(extract.trades.read().await.len(), 2);
+
+        let mut bad = generic_traded_case(0, Aggressor::Buy, 5.0);
+        bad.timestamp = "not a time".to_string();
+        assert!(history.update_trades(vec![bad]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_splat_trades() {
+        let mut history = BookHistory::new(60);
+        let _ = history
+            .update_trades(vec![
+                generic_traded_case(1, Aggressor::Buy, 5.0),
+                generic_traded_case(2, Aggressor::Sell, 3.0),
+                generic_traded_case(3, Aggressor::Sell, 100.0),
+                generic_traded_case(30, Aggressor::Buy, 5.0),
+            ])
+            .await;
+
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let splatted = SplatTrades::splat(&grid, &history).await;
+        assert_eq!(splatted.buys, vec![(1.0, 5.0, 1.0)]);
+        assert_eq!(splatted.sells, vec![(2.0, 3.0, 1.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_splat_levels() {
+        let mut history = BookHistory::new(60);
+        for time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(time, 0).unwrap().to_rfc3339();
+            // a persistent ask wall at 7 and a bid wall at 1 flickering away every other second
+            booked.asks = vec![
+                Order {
+                    price: 5.0,
+                    quantity: 1.0,
+                },
+                Order {
+                    price: 7.0,
+                    quantity: 20.0,
+                },
+            ];
+            booked.bids = vec![
+                Order {
+                    price: 1.0,
+                    quantity: if time % 2 == 0 { 20.0 } else { 0.0 },
+                },
+                Order {
+                    price: 3.0,
+                    quantity: 1.0,
+                },
+            ];
+            let _ = history.update(booked).await;
+        }
+
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let detector = SplatLevels {
+            quantile: 0.8,
+            min_persistence: 0.8,
+            max_levels: 3,
+        };
+        // the flickering bid wall and the small bid take turns holding the most volume, so
+        // neither persists
+        let splatted = detector.splat(&grid, &history).await;
+        assert_eq!(
+            splatted.levels,
+            vec![Level {
+                side: Side::Ask,
+                price: 7.0,
+                persistence: 1.0,
+                volume: 20.0,
+            }]
+        );
+
+        let detector = SplatLevels {
+            min_persistence: 0.5,
+            ..detector
+        };
+        let splatted = detector.splat(&grid, &history).await;
+        assert_eq!(splatted.levels.len(), 3);
+        assert_eq!(splatted.levels[0].side, Side::Bid);
+        assert_eq!(splatted.levels[0].price, 1.0);
+        assert_eq!(splatted.levels[0].persistence, 0.5);
+    }
+
+    #[test]
+    fn test_regress() {
+        let (slope, r_squared) = regress(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]).unwrap();
+        assert!((slope - 2.0).abs() < 1e-12);
+        assert!((r_squared - 1.0).abs() < 1e-12);
+
+        let (slope, r_squared) = regress(&[(-1.0, 0.0), (0.0, 1.0), (1.0, 0.0)]).unwrap();
+        assert_eq!(slope, 0.0);
+        assert_eq!(r_squared, 0.0);
+
+        assert_eq!(regress(&[(1.0, 1.0), (1.0, 2.0)]), None);
+        assert_eq!(regress(&[(1.0, 1.0)]), None);
+    }
+
+    #[tokio::test]
+    async fn test_splat_impact() {
+        let mut history = BookHistory::new(60);
+        let splatted = SplatImpact::splat(&history).await;
+        assert_eq!(splatted.samples, 0);
+        assert_eq!(splatted.lambda, None);
+
+        // buying pressure lifts the bid and the mid with it, selling pressure lowers them
+        for (time, bid_price, bid_quantity) in [(0, 3.0, 4.0), (1, 4.0, 2.0), (2, 3.0, 4.0)] {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(time, 0).unwrap().to_rfc3339();
+            booked.bids = vec![Order {
+                price: bid_price,
+                quantity: bid_quantity,
+            }];
+            let _ = history.update(booked).await;
+        }
+
+        let splatted = SplatImpact::splat(&history).await;
+        assert_eq!(splatted.samples, 2);
+        // flows of +2 then -2 move the mid by +0.5 then -0.5
+        assert_eq!(splatted.lambda, Some(0.25));
+        assert_eq!(splatted.r_squared, Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_splat_imbalance() {
+        let mut history = BookHistory::new(60);
+
+        for i_time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            booked.bids[1].quantity = 4.0 + i_time as f64;
+            let _ = history.update(booked).await;
+        }
+
+        let grid = RenderGrid {
+            number_time_values: 20,
+            time_range: (0, 10),
+            number_price_values: 20,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+
+        let splatted =
+            SplatImbalance::splat(&grid, &history, SplatMode::Kernel(Kernel::default())).await;
+
+        assert_eq!(splatted.time_range, (0, 10));
+        assert_eq!(splatted.imbalances.len(), 20);
+        assert!(
+            splatted
+                .imbalances
+                .iter()
+                .all(|imbalance| *imbalance >= 0.0)
+        );
+        assert!(splatted.imbalances.iter().any(|imbalance| *imbalance > 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_splat_depth_modes() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 60,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+
+        let density = SplatDepth {
+            mode: DepthMode::Density,
+        }
+        .splat(&grid, &history, SplatMode::Kernel(Kernel::default()))
+        .await;
+        let cumulative = SplatDepth {
+            mode: DepthMode::Cumulative,
+        }
+        .splat(&grid, &history, SplatMode::Kernel(Kernel::default()))
+        .await;
+
+        assert_eq!(density.mode, DepthMode::Density);
+        assert_eq!(cumulative.mode, DepthMode::Cumulative);
+        assert_eq!(cumulative.volumes.len(), 60);
+
+        // mid price is 4.0: asks grow upward and bids grow downward from it
+        let mid_index = 30;
+        for index in mid_index..59 {
+            assert!(cumulative.volumes[index + 1] >= cumulative.volumes[index]);
+        }
+        for index in 1..mid_index {
+            assert!(cumulative.volumes[index - 1] <= cumulative.volumes[index]);
+        }
+    }
+
+    #[test]
+    fn test_toggle_depth_mode() {
+        let mut pipeline = Pipeline::new(60, 10, 10, Kernel::default());
+
+        assert_eq!(pipeline.toggle_depth_mode(), DepthMode::Cumulative);
+        assert_eq!(pipeline.toggle_depth_mode(), DepthMode::Density);
+    }
+
+    #[test]
+    fn test_detect_price_band() {
+        let band = PriceBand::detect("USDT/USD", 50.0, 400).unwrap();
+        assert_eq!(band.peg, 1.0);
+        assert!((band.price_range.0 - 0.995).abs() < 1e-12);
+        assert!((band.price_range.1 - 1.005).abs() < 1e-12);
+        assert_eq!(band.number_price_values, 400);
+
+        assert!(PriceBand::detect("EURC/EUR", 50.0, 400).is_some());
+        assert!(PriceBand::detect("ETH/EUR", 50.0, 400).is_none());
+        assert!(PriceBand::detect("EUR/USD", 50.0, 400).is_none());
+        assert!(PriceBand::detect("USD/USD", 50.0, 400).is_none());
+        assert!(PriceBand::detect("USDT", 50.0, 400).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_grid_with_price_band() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let mut pipeline = Pipeline::new(60, 10, 20, Kernel::default());
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.price_range, (1.0, 7.0));
+        assert_eq!(grid.number_price_values, 20);
+        assert_eq!(grid.peg, None);
+
+        pipeline.set_price_band(Some(PriceBand::new(1.0, 100.0, 40)));
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert!((grid.price_range.0 - 0.99).abs() < 1e-12);
+        assert!((grid.price_range.1 - 1.01).abs() < 1e-12);
+        assert_eq!(grid.number_price_values, 40);
+        assert_eq!(grid.peg, Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_clamped_price_range() {
+        let mut history = BookHistory::new(60);
+        let mut booked = generic_booked_case();
+        booked.asks.push(Order {
+            price: 1000.0,
+            quantity: 0.1,
+        });
+        let _ = history.update(booked).await;
+
+        let mut pipeline = Pipeline::new(60, 10, 20, Kernel::default());
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.price_range, (1.0, 1000.0));
+
+        pipeline.set_price_range(PriceRange::Percentile(0.98));
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.price_range, (1.0, 7.0));
+
+        pipeline.set_price_range(PriceRange::AroundMid(5_000.0));
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.price_range, (2.0, 6.0));
+
+        pipeline.set_price_range(PriceRange::AroundMid(5_000.0));
+        let grid = pipeline.grid_generator.grid(&BookHistory::new(60)).await;
+        assert_eq!(grid.price_range, (0.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_grid_resolution() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let mut pipeline = Pipeline::new(60, 10, 20, Kernel::default());
+        pipeline.set_resolution(120, 45);
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.number_time_values, 120);
+        assert_eq!(grid.number_price_values, 45);
+
+        // price bands keep their own price resolution
+        pipeline.set_price_band(Some(PriceBand::new(4.0, 100.0, 30)));
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.number_time_values, 120);
+        assert_eq!(grid.number_price_values, 30);
+
+        // time ranges of symbols displayed side by side end together
+        assert_eq!(grid.time_range, (-60, 0));
+        pipeline.set_time_end(Some(5));
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.time_range, (-55, 5));
+
+        // zooming in shortens the time range towards its end
+        assert_eq!(pipeline.time_window(), 60);
+        pipeline.set_time_window(15);
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.time_range, (-10, 5));
+    }
+
+    #[test]
+    fn test_volume_quantile() {
+        let levels = vec![(1.0, 1.0), (2.0, 97.0), (3.0, 1.0), (100.0, 1.0)];
+        assert_eq!(volume_quantile(&levels, 0.01), Some(1.0));
+        assert_eq!(volume_quantile(&levels, 0.5), Some(2.0));
+        assert_eq!(volume_quantile(&levels, 0.99), Some(3.0));
+        assert_eq!(volume_quantile(&levels, 1.0), Some(100.0));
+        assert_eq!(volume_quantile(&[], 0.5), None);
+    }
+
+    #[tokio::test]
+    async fn test_log_price_grid() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+
+        let mut pipeline = Pipeline::new(60, 10, 20, Kernel::default());
+        pipeline.set_price_scale(PriceScale::Log);
+        let grid = pipeline.grid_generator.grid(&history).await;
+        assert_eq!(grid.price_scale, PriceScale::Log);
+        assert_eq!(grid.price_range, (1.0, 7.0));
+        assert_eq!(grid.price_axis(), (0.0, 7.0_f64.ln()));
+        assert!((PriceScale::Log.price(PriceScale::Log.position(3.0)) - 3.0).abs() < 1e-12);
+
+        let depth = pipeline
+            .depth_splatter
+            .splat(&grid, &history, SplatMode::Histogram)
+            .await;
+        assert_eq!(depth.price_range, grid.price_axis());
+        let step = 7.0_f64.ln() / 20.0;
+        let index = |price: f64| (price.ln() / step).round() as usize;
+        assert_eq!(depth.volumes[index(3.0)], -4.0);
+        assert_eq!(depth.volumes[index(5.0)], 6.0);
+
+        let grid = pipeline.grid_generator.grid(&BookHistory::new(60)).await;
+        assert_eq!(grid.price_scale, PriceScale::Linear);
+    }
+
+    #[tokio::test]
+    async fn test_splat_prices() {
+        let mut history = BookHistory::new(60);
+
+        for i_time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let _ = history.update(booked).await;
+        }
+
+        let grid = RenderGrid {
+            number_time_values: 20,
+            time_range: (5, 15),
+            number_price_values: 20,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
 
-        (
-            SplatDepth::splat(&grid, history).await,
-            SplatVolume::splat(&grid, history).await,
-            SplatBlocks::splat(&grid, history).await,
-        )
+        let splatted = SplatPrices::splat(&grid, &history).await;
+
+        itertools::assert_equal(
+            splatted.mid_prices.into_iter(),
+            (5..10).map(|time| (time as f64, 4.0)),
+        );
+        // best ask 5.0 x 6.0 and best bid 3.0 x 4.0 lean the microprice towards the bid
+        itertools::assert_equal(
+            splatted.micro_prices.into_iter(),
+            (5..10).map(|time| (time as f64, (5.0 * 4.0 + 3.0 * 6.0) / 10.0)),
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[tokio::test]
+    async fn test_splat_blocks_histogram() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
 
-    use super::*;
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
 
-    use chrono::DateTime;
+        let splatter = SplatBlocks {
+            half_life_in_seconds: None,
+        };
+        let splatted = splatter.splat(&grid, &history, SplatMode::Histogram).await;
 
-    fn generic_booked_case() -> Booked {
-        Booked {
-            symbol: "ETH/EUR".to_string(),
-            timestamp: DateTime::from_timestamp(0, 0).unwrap().to_rfc3339(),
-            asks: vec![
-                Order {
-                    price: 5.0,
-                    quantity: 6.0,
-                },
-                Order {
-                    price: 7.0,
-                    quantity: 8.0,
-                },
-            ],
-            bids: vec![
-                Order {
-                    price: 1.0,
-                    quantity: 2.0,
-                },
-                Order {
-                    price: 3.0,
-                    quantity: 4.0,
-                },
-            ],
-        }
+        assert_eq!(splatted.mode, SplatMode::Histogram);
+        assert_eq!(splatted.volumes[(0, 0)], -2.0);
+        assert_eq!(splatted.volumes[(0, 2)], -4.0);
+        assert_eq!(splatted.volumes[(0, 4)], 6.0);
+        assert_eq!(splatted.volumes[(0, 5)], 8.0);
+        assert_eq!(splatted.volumes.sum(), 8.0);
+    }
+
+    #[test]
+    fn test_sample_blocks() {
+        let blocks = SplattedBlocks {
+            mode: SplatMode::Histogram,
+            grid: RenderGrid {
+                number_time_values: 2,
+                time_range: (0, 10),
+                number_price_values: 2,
+                price_range: (1.0, 7.0),
+                price_scale: PriceScale::Linear,
+                peg: None,
+            },
+            volumes: Array2::from_shape_vec((2, 2), vec![0.0, 2.0, 4.0, 6.0]).unwrap(),
+        };
+
+        assert_eq!(blocks.sample(0.0, 0.0), (0.0, 1.0, 0.0));
+        assert_eq!(blocks.sample(0.25, 0.25), (2.5, 2.5, 3.0));
+        assert_eq!(blocks.sample(0.25, 0.0), (2.5, 1.0, 2.0));
+        // past the last cell the volume of the edge is kept
+        assert_eq!(blocks.sample(1.0, 1.0), (10.0, 7.0, 6.0));
     }
 
     #[tokio::test]
-    async fn test_empty_history() {
-        let history = BookHistory::new(60);
+    async fn test_splat_blocks_decay() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(5, 0).unwrap().to_rfc3339();
+        let _ = history.update(booked).await;
 
-        let latest = history.get_latest_book().await;
-        assert_eq!(latest.0.0, 0);
-        assert_eq!(latest.1.0, 0);
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let splatter = SplatBlocks {
+            half_life_in_seconds: Some(5.0),
+        };
+        assert_eq!(splatter.weight(10, 10), 1.0);
+        assert_eq!(splatter.weight(5, 10), 0.5);
+        assert_eq!(splatter.weight(0, 10), 0.25);
+        assert_eq!(splatter.weight(12, 10), 1.0);
 
-        assert_eq!(latest.0.1.len(), 0);
-        assert_eq!(latest.1.1.len(), 0);
+        let splatted = splatter.splat(&grid, &history, SplatMode::Histogram).await;
+        assert_eq!(splatted.volumes[(0, 5)], 8.0 * 0.25);
+        assert_eq!(splatted.volumes[(5, 5)], 8.0 * 0.5);
+    }
 
-        let integrated = history.integrate_window(0, 60).await;
-        assert_eq!(integrated.0.len(), 0);
-        assert_eq!(integrated.1.len(), 0);
+    #[tokio::test]
+    async fn test_splat_ladder() {
+        let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
 
-        let extracted = history.extract_window(0, 45).await;
-        assert_eq!(extracted.time_window_in_seconds, 45);
+        let splatted = SplatLadder { levels: 10 }.splat(&history).await;
+        assert_eq!(splatted.asks, vec![(5.0, 6.0, 6.0), (7.0, 8.0, 14.0)]);
+        assert_eq!(splatted.bids, vec![(3.0, 4.0, 4.0), (1.0, 2.0, 6.0)]);
 
-        let readable_asks = extracted.asks.read().await;
-        let readable_bids = extracted.bids.read().await;
-        assert_eq!(readable_asks.len(), 0);
-        assert_eq!(readable_bids.len(), 0);
+        let splatted = SplatLadder { levels: 1 }.splat(&history).await;
+        assert_eq!(splatted.asks, vec![(5.0, 6.0, 6.0)]);
+        assert_eq!(splatted.bids, vec![(3.0, 4.0, 4.0)]);
+
+        let splatted = SplatLadder { levels: 10 }
+            .splat(&BookHistory::new(60))
+            .await;
+        assert_eq!(
+            splatted,
+            SplattedLadder {
+                asks: vec![],
+                bids: vec![]
+            }
+        );
     }
 
     #[tokio::test]
-    async fn test_book_updates() {
+    async fn test_pipeline_stages() {
         let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
+        let mut pipeline = Pipeline::new(60, 10, 6, Kernel::default());
+        pipeline.toggle_splat_mode(Some(View::Map));
 
-        let updated = history.update(generic_booked_case()).await;
-        assert!(updated.is_ok());
-        assert!(!updated.unwrap().is_some());
+        let stages = pipeline.stages();
+        assert_eq!(stages[2].view(), Some(View::Map));
+        assert_eq!(stages[4].view(), None);
 
-        let readable_asks = history.asks.read().await;
-        let readable_bids = history.bids.read().await;
+        let (splatted, timings) = pipeline.run(&history).await;
+        assert_eq!(splatted.len(), stages.len());
+        assert_eq!(timings.len(), stages.len() + 1);
+        assert_eq!(timings[0].name, "Grid");
+        assert_eq!(timings[3].name, "Blocks");
+        assert!(matches!(splatted[0], Splatted::Depth(_)));
+        assert!(matches!(splatted[7], Splatted::Volatility(_)));
+        assert!(matches!(splatted[8], Splatted::Impact(_)));
+        assert!(matches!(splatted[9], Splatted::Activity(_)));
+        assert!(matches!(splatted[10], Splatted::Trades(_)));
+        assert!(matches!(splatted[11], Splatted::Levels(_)));
+        assert!(matches!(splatted[12], Splatted::TwaDepth(_)));
+        assert!(matches!(splatted[13], Splatted::Ladder(_)));
+        match &splatted[2] {
+            Splatted::Blocks(blocks) => assert_eq!(blocks.mode, SplatMode::Histogram),
+            _ => panic!("order map should be the third stage"),
+        }
+        match &splatted[5] {
+            Splatted::Profile(profile) => assert!(profile.mode != SplatMode::Histogram),
+            _ => panic!("profile should be the sixth stage"),
+        }
+    }
 
-        assert_eq!(readable_asks.len(), 1);
-        assert_eq!(readable_bids.len(), 1);
+    #[test]
+    fn test_toggle_splat_mode() {
+        let mut pipeline = Pipeline::new(60, 10, 10, Kernel::default());
 
-        let first_asks = readable_asks.get_first();
-        let first_bids = readable_bids.get_first();
+        assert_eq!(pipeline.toggle_splat_mode(None), SplatMode::Histogram);
+        assert_eq!(
+            pipeline.toggle_splat_mode(None),
+            SplatMode::Kernel(Kernel::default())
+        );
 
-        assert!(first_asks.is_some());
-        assert!(first_bids.is_some());
+        assert_eq!(
+            pipeline.toggle_splat_mode(Some(View::Profile)),
+            SplatMode::Histogram
+        );
+        assert_eq!(pipeline.splat_mode(View::Profile), SplatMode::Histogram);
+        assert_eq!(
+            pipeline.splat_mode(View::Map),
+            SplatMode::Kernel(Kernel::default())
+        );
+        assert_eq!(pipeline.toggle_splat_mode(None), SplatMode::Histogram);
+        assert_eq!(pipeline.splat_mode(View::Profile), SplatMode::Histogram);
+        assert_eq!(pipeline.splat_mode(View::Depth), SplatMode::Histogram);
+    }
 
-        let (asks_time, asks) = first_asks.unwrap();
-        let (bids_time, bids) = first_bids.unwrap();
+    #[tokio::test]
+    async fn test_integrate_prices() {
+        let mut history = BookHistory::new(60);
 
-        assert_eq!(*asks_time, 0);
-        assert_eq!(*bids_time, 0);
+        for i_time in 0..60 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let _ = history.update(booked).await;
+        }
 
-        assert_eq!(asks.len(), 2);
-        assert_eq!(bids.len(), 2);
+        let (integrated_asks, integrated_bids) = history.integrate_prices(10, 19).await;
 
         itertools::assert_equal(
-            asks.clone().into_iter(),
-            vec![(Price { value: 5.0 }, 6.0), (Price { value: 7.0 }, 8.0)].into_iter(),
+            integrated_asks.into_iter(),
+            vec![(Price { value: 5.0 }, 60.0), (Price { value: 7.0 }, 80.0)].into_iter(),
         );
-
         itertools::assert_equal(
-            bids.clone().into_iter(),
-            vec![(Price { value: 1.0 }, 2.0), (Price { value: 3.0 }, 4.0)].into_iter(),
+            integrated_bids.into_iter(),
+            vec![(Price { value: 1.0 }, 20.0), (Price { value: 3.0 }, 40.0)].into_iter(),
         );
     }
 
     #[tokio::test]
-    async fn test_bad_timestamped_update() {
+    async fn test_splat_profile() {
         let mut history = BookHistory::new(60);
 
-        let mut booked = generic_booked_case();
-        booked.timestamp = "Bad Timestamp".to_string();
+        for i_time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let _ = history.update(booked).await;
+        }
 
-        let updated = history.update(booked).await;
-        assert!(updated.is_err());
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+
+        let splatted = SplatProfile::splat(&grid, &history, SplatMode::Histogram).await;
+
+        assert_eq!(splatted.price_range, (1.0, 7.0));
+        assert_eq!(splatted.ask_volumes, vec![0.0, 0.0, 0.0, 0.0, 60.0, 80.0]);
+        assert_eq!(splatted.bid_volumes, vec![20.0, 0.0, 40.0, 0.0, 0.0, 0.0]);
     }
 
     #[tokio::test]
-    async fn test_latest_book() {
+    async fn test_splat_flicker() {
         let mut history = BookHistory::new(60);
 
+        for i_time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            // a level at 6.0 flickers every other second while the level at 4.0 rests for long
+            if i_time > 0 {
+                booked.asks.push(Order {
+                    price: 6.0,
+                    quantity: if i_time % 2 == 1 { 1.0 } else { 0.0 },
+                });
+                booked.bids.push(Order {
+                    price: 4.0,
+                    quantity: if i_time < 8 { 1.0 } else { 0.0 },
+                });
+            }
+            let _ = history.update(booked).await;
+        }
+
+        let (ask_flickers, bid_flickers) = history.flicker_prices(0, 10, 2).await;
+        assert_eq!(ask_flickers.get(&Price { value: 6.0 }), Some(&4.0));
+        assert_eq!(bid_flickers.get(&Price { value: 4.0 }), None);
+
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+
+        let splatted = SplatFlicker {
+            horizon_in_seconds: 2,
+        }
+        .splat(&grid, &history, SplatMode::Histogram)
+        .await;
+
+        assert_eq!(splatted.ask_scores, vec![0.0, 0.0, 0.0, 0.0, 0.0, 4.0]);
+        assert_eq!(splatted.bid_scores, vec![0.0; 6]);
+    }
+
+    #[test]
+    fn test_splat_volatility() {
+        let prices = SplattedPrices {
+            mid_prices: vec![(0.0, 100.0), (1.0, 101.0), (2.0, 100.0), (3.0, 100.0)],
+            micro_prices: vec![],
+        };
+
+        let splatted = SplatVolatility {
+            window_in_seconds: 2,
+        }
+        .splat(&prices);
+
+        assert_eq!(splatted.window_in_seconds, 2);
+        assert_eq!(splatted.volatilities.len(), 3);
+
+        let up = (101.0_f64 / 100.0).ln();
+        let down = (100.0_f64 / 101.0).ln();
+        let expected = [
+            (up * up * SECONDS_PER_YEAR / 2.0).sqrt(),
+            ((up * up + down * down) * SECONDS_PER_YEAR / 2.0).sqrt(),
+            (down * down * SECONDS_PER_YEAR / 2.0).sqrt(),
+        ];
+        for ((time, volatility), (index, expected)) in
+            zip(splatted.volatilities.iter(), expected.iter().enumerate())
+        {
+            assert_eq!(*time, (index + 1) as f64);
+            assert!((volatility - expected).abs() < 1e-9 * expected);
+        }
+        assert_eq!(splatted.latest(), Some(splatted.volatilities[2].1));
+    }
+
+    #[tokio::test]
+    async fn test_splat_slippage() {
+        let mut history = BookHistory::new(60);
         let _ = history.update(generic_booked_case()).await;
 
-        let ((asks_time, asks), (bids_time, bids)) = history.get_latest_book().await;
+        let splatted = SplatSlippage::splat(&history, 10.0).await;
+        assert_eq!(splatted.mid_price, 4.0);
 
-        assert_eq!(asks_time, 0);
-        assert_eq!(bids_time, 0);
+        let buy = splatted.buy.unwrap();
+        assert_eq!(buy.filled_quantity, 10.0);
+        assert_eq!(buy.levels_consumed, 2);
+        assert_eq!(buy.average_price, (6.0 * 5.0 + 4.0 * 7.0) / 10.0);
+        assert!((buy.slippage_in_bps - (5.8 / 4.0 - 1.0) * 10_000.0).abs() < 1e-9);
 
-        assert_eq!(asks.len(), 2);
-        assert_eq!(bids.len(), 2);
+        let sell = splatted.sell.unwrap();
+        assert_eq!(sell.filled_quantity, 6.0);
+        assert_eq!(sell.levels_consumed, 2);
+        assert_eq!(sell.average_price, (4.0 * 3.0 + 2.0 * 1.0) / 6.0);
+        assert!(sell.slippage_in_bps > 0.0);
 
-        itertools::assert_equal(
-            asks.clone().into_iter(),
-            vec![(Price { value: 5.0 }, 6.0), (Price { value: 7.0 }, 8.0)].into_iter(),
+        let splatted = SplatSlippage::splat(&BookHistory::new(60), 1.0).await;
+        assert_eq!(splatted.buy, None);
+        assert_eq!(splatted.sell, None);
+    }
+
+    #[test]
+    fn test_infer_queue() {
+        let segments = infer_queue(vec![(0, 6.0), (1, 9.0), (2, 7.0), (3, 8.0)].into_iter());
+        assert_eq!(
+            segments,
+            vec![
+                QueueSegment {
+                    time: 0,
+                    quantity: 4.0
+                },
+                QueueSegment {
+                    time: 1,
+                    quantity: 3.0
+                },
+                QueueSegment {
+                    time: 3,
+                    quantity: 1.0
+                },
+            ]
         );
 
-        itertools::assert_equal(
-            bids.clone().into_iter(),
-            vec![(Price { value: 1.0 }, 2.0), (Price { value: 3.0 }, 4.0)].into_iter(),
+        let segments = infer_queue(vec![(0, 6.0), (1, 0.0), (2, 2.0)].into_iter());
+        assert_eq!(
+            segments,
+            vec![QueueSegment {
+                time: 2,
+                quantity: 2.0
+            }]
         );
     }
 
     #[tokio::test]
-    async fn test_book_multiple_book_updates() {
+    async fn test_splat_queue() {
         let mut history = BookHistory::new(60);
+        let _ = history.update(generic_booked_case()).await;
 
-        let updated = history.update(generic_booked_case()).await;
-        assert!(updated.is_ok());
-        assert!(!updated.unwrap().is_some());
+        let mut booked = generic_booked_case();
+        booked.timestamp = DateTime::from_timestamp(1, 0).unwrap().to_rfc3339();
+        booked.asks = vec![Order {
+            price: 5.0,
+            quantity: 9.0,
+        }];
+        booked.bids = vec![];
+        let _ = history.update(booked).await;
 
-        {
-            let readable_asks = history.asks.read().await;
-            let readable_bids = history.bids.read().await;
+        let splatted = SplatQueue::splat(&history, 5.2).await.unwrap();
+        assert_eq!(splatted.side, Side::Ask);
+        assert_eq!(splatted.price, 5.0);
+        assert_eq!(
+            splatted
+                .segments
+                .iter()
+                .map(|segment| (segment.time, segment.quantity))
+                .collect::<Vec<_>>(),
+            vec![(0, 6.0), (1, 3.0)]
+        );
 
-            assert_eq!(readable_asks.len(), 1);
-            assert_eq!(readable_bids.len(), 1);
-        }
+        let splatted = SplatQueue::splat(&history, 0.0).await.unwrap();
+        assert_eq!(splatted.side, Side::Bid);
+        assert_eq!(splatted.price, 1.0);
 
-        let mut booked = generic_booked_case();
-        booked.timestamp = DateTime::from_timestamp(60, 0).unwrap().to_rfc3339();
-        let updated = history.update(booked).await;
-        assert!(updated.is_ok());
-        assert!(!updated.unwrap().is_some());
+        assert!(
+            SplatQueue::splat(&BookHistory::new(60), 1.0)
+                .await
+                .is_none()
+        );
+    }
 
-        {
-            let readable_asks = history.asks.read().await;
-            let readable_bids = history.bids.read().await;
+    #[test]
+    fn test_imbalance_ratio() {
+        assert_eq!(imbalance_ratio(0.0, 0.0), 0.0);
+        assert_eq!(imbalance_ratio(1.0, 1.0), 0.0);
+        assert_eq!(imbalance_ratio(0.0, 2.0), 1.0);
+        assert_eq!(imbalance_ratio(2.0, 0.0), -1.0);
+        assert_eq!(imbalance_ratio(1.0, 3.0), 0.5);
+    }
 
-            assert_eq!(readable_asks.len(), 2);
-            assert_eq!(readable_bids.len(), 2);
-        }
+    #[tokio::test]
+    async fn test_splat_volume_imbalance_ratios() {
+        let mut history = BookHistory::new(60);
 
-        let mut booked = generic_booked_case();
-        booked.timestamp = DateTime::from_timestamp(61, 0).unwrap().to_rfc3339();
-        let updated = history.update(booked).await;
-        assert!(updated.is_ok());
-        let updated_option = updated.unwrap();
-        assert!(updated_option.is_some());
+        for i_time in 0..10 {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            let _ = history.update(booked).await;
+        }
 
-        let ((time_asks, _), (time_bids, _)) = updated_option.unwrap();
-        assert_eq!(time_asks, 0);
-        assert_eq!(time_bids, 0);
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 6,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
 
-        {
-            let readable_asks = history.asks.read().await;
-            let readable_bids = history.bids.read().await;
+        let splatted = SplatVolume::splat(&grid, &history, SplatMode::Histogram).await;
 
-            assert_eq!(readable_asks.len(), 2);
-            assert_eq!(readable_bids.len(), 2);
+        assert_eq!(splatted.imbalance_ratios.len(), 10);
+        // 14.0 resting on asks against 6.0 on bids
+        for ratio in splatted.imbalance_ratios.into_iter() {
+            assert_eq!(ratio, -0.4);
         }
     }
 
     #[tokio::test]
-    async fn test_integrate_window() {
-        let mut history = BookHistory::new(60);
+    async fn test_splat_arbitrage() {
+        let mut first = BookHistory::new(60);
+        let mut second = BookHistory::new(60);
 
-        for i_time in 0..60 {
+        for i_time in 0..10 {
             let mut booked = generic_booked_case();
             booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
-            let updated = history.update(booked).await;
-            assert!(updated.is_ok());
-            assert!(!updated.unwrap().is_some());
+            let _ = first.update(booked).await;
         }
 
-        let latest = history.get_latest_book().await;
-        assert_eq!(latest.0.0, 59);
-        assert_eq!(latest.1.0, 59);
+        // second symbol only updates every other second and its bids cross the first's asks
+        for i_time in (1..10).step_by(2) {
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            booked.bids[1].price = 5.5;
+            let _ = second.update(booked).await;
+        }
 
-        let (integrated_asks, integrated_bids) = history.integrate_window(10, 40).await;
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 10,
+            price_range: (1.0, 7.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+
+        let splatted = SplatArbitrage::splat(
+            &grid,
+            ("A/EUR".to_string(), "B/EUR".to_string()),
+            (&first, &second),
+            26.0,
+        )
+        .await;
 
         itertools::assert_equal(
-            integrated_asks.into_iter(),
-            (10..41).map(|time| (time, 14.0)),
+            splatted.first_to_second.iter().map(|(time, _)| *time),
+            (1..10).map(|time| time as f64),
         );
-        itertools::assert_equal(
-            integrated_bids.into_iter(),
-            (10..41).map(|time| (time, 6.0)),
+        assert!(
+            splatted
+                .first_to_second
+                .iter()
+                .all(|(_, spread)| *spread == 1_000.0)
         );
+        assert!(
+            splatted
+                .second_to_first
+                .iter()
+                .all(|(_, spread)| *spread == -4_000.0)
+        );
+        assert_eq!(splatted.latest_best_spread(), Some(1_000.0));
+        assert!(splatted.is_profitable());
+    }
 
-        let extracted = history.extract_window(15, 35).await;
+    #[tokio::test]
+    async fn test_splat_basis() {
+        let mut first = BookHistory::new(60);
+        let mut second = BookHistory::new(60);
 
-        assert_eq!(extracted.time_window_in_seconds, 20);
+        // the second symbol quotes the same moves at twice the price of the first, asks resting
+        // from the first update stay at the top while bids climb
+        for i_time in 0..10 {
+            let shift = (i_time * i_time) as f64;
+            let mut booked = generic_booked_case();
+            booked.timestamp = DateTime::from_timestamp(i_time, 0).unwrap().to_rfc3339();
+            for order in booked.asks.iter_mut().chain(booked.bids.iter_mut()) {
+                order.price += shift;
+            }
+            let mut doubled = generic_booked_case();
+            doubled.timestamp = booked.timestamp.clone();
+            for order in doubled.asks.iter_mut().chain(doubled.bids.iter_mut()) {
+                order.price = 2.0 * (order.price + shift);
+            }
+            let _ = first.update(booked).await;
+            let _ = second.update(doubled).await;
+        }
 
-        let extracted_asks = extracted.asks.read().await;
-        let extracted_bids = extracted.bids.read().await;
+        let grid = RenderGrid {
+            number_time_values: 10,
+            time_range: (0, 10),
+            number_price_values: 10,
+            price_range: (1.0, 100.0),
+            price_scale: PriceScale::Linear,
+            peg: None,
+        };
+        let symbols = ("A/USD".to_string(), "A/EUR".to_string());
 
+        let ratio = SplatBasis::splat(
+            &grid,
+            symbols.clone(),
+            (&first, &second),
+            BasisOperation::Ratio,
+        )
+        .await;
+        assert_eq!(ratio.series.len(), 10);
+        assert!(ratio.series.iter().all(|(_, value)| *value == 0.5));
+        assert!((ratio.correlation.unwrap() - 1.0).abs() < 1e-9);
+
+        let difference = SplatBasis::splat(
+            &grid,
+            symbols,
+            (&first, &second),
+            BasisOperation::Difference,
+        )
+        .await;
         itertools::assert_equal(
-            extracted_asks.clone().into_iter().map(|(time, _)| time),
-            15..36,
-        );
-        itertools::assert_equal(
-            extracted_bids.clone().into_iter().map(|(time, _)| time),
-            15..36,
+            difference.series.into_iter(),
+            (0..10).map(|time| (time as f64, -(8.0 + (time * time) as f64) / 2.0)),
         );
     }
 }
@@ -0,0 +1,122 @@
+use crate::feed::TickerState;
+use crate::pipeline::Splatted;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+/// Plugin panels registered so far, read once by the interface when it starts
+static REGISTERED: Mutex<Vec<Arc<dyn PanelPlugin>>> = Mutex::new(Vec::new());
+
+/// Data of the displayed symbol handed to plugin panels when they are drawn
+pub struct PanelContext<'a> {
+    pub symbol: &'a str,
+    /// latest ticker of the symbol, None until the first one is received
+    pub ticker: Option<&'a TickerState>,
+    /// results of the last pipeline run of the symbol
+    pub splatted: &'a [Splatted],
+}
+
+/// Panel added by a third-party crate, drawn below the ticker panel of the ticker page
+pub trait PanelPlugin: Send + Sync {
+    /// title of the bordered block the panel is drawn in
+    fn name(&self) -> String;
+
+    /// draw the panel for the displayed symbol inside its block
+    fn render(&self, context: &PanelContext, area: Rect, buf: &mut Buffer);
+}
+
+/// Plugin panels drawn by the interface, in order of registration
+#[derive(Clone, Default)]
+pub struct PanelPlugins {
+    plugins: Vec<Arc<dyn PanelPlugin>>,
+}
+
+impl PanelPlugins {
+    /// registered panels
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn PanelPlugin>> {
+        self.plugins.iter()
+    }
+
+    /// number of registered panels
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// whether no panel was registered
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+impl Debug for PanelPlugins {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.plugins.iter().map(|plugin| plugin.name()))
+            .finish()
+    }
+}
+
+/// Register a plugin panel, to be called before the interface starts
+pub fn register_panel(plugin: impl PanelPlugin + 'static) {
+    if let Ok(mut registered) = REGISTERED.lock() {
+        registered.push(Arc::new(plugin));
+    }
+}
+
+/// Plugin panels registered so far
+pub fn registered_panels() -> PanelPlugins {
+    PanelPlugins {
+        plugins: match REGISTERED.lock() {
+            Ok(registered) => registered.clone(),
+            Err(_) => vec![],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ratatui::widgets::{Paragraph, Widget};
+
+    struct SymbolPanel;
+
+    impl PanelPlugin for SymbolPanel {
+        fn name(&self) -> String {
+            "Symbol".to_string()
+        }
+
+        fn render(&self, context: &PanelContext, area: Rect, buf: &mut Buffer) {
+            let last = context.ticker.map(|ticker| ticker.last).unwrap_or_default();
+            Paragraph::new(format!("{} {}", context.symbol, last)).render(area, buf);
+        }
+    }
+
+    #[test]
+    fn test_panel_plugins() {
+        register_panel(SymbolPanel);
+        let panels = registered_panels();
+        assert_eq!(panels.len(), 1);
+        assert_eq!(format!("{:?}", panels), r#"["Symbol"]"#);
+
+        let area = Rect::new(0, 0, 12, 1);
+        let mut buf = Buffer::empty(area);
+        let context = PanelContext {
+            symbol: "BTC/USD",
+            ticker: None,
+            splatted: &[],
+        };
+        for panel in panels.iter() {
+            panel.render(&context, area, &mut buf);
+        }
+        let text = buf
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+        assert_eq!(text, "BTC/USD 0   ");
+    }
+}
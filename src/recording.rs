@@ -0,0 +1,312 @@
+use crate::error::BookedError;
+use crate::feed::{Booked, Order};
+use crate::persistence::{HistoryPersister, write_arrow, write_slice};
+use crate::pipeline::BookHistory;
+use crate::storage::Storage;
+
+use chrono::DateTime;
+use clap::ValueEnum;
+
+use std::fs::{metadata, read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+/// Archive formats recordings are exported to
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ArchiveFormat {
+    /// parquet files of the books, partitioned by symbol and day as in the persist dir
+    Parquet,
+    /// SQLite archive of the book updates, as recorded with --sqlite
+    Sqlite,
+    /// Arrow IPC file of the books, in the columns of the parquet files
+    Arrow,
+}
+
+impl ArchiveFormat {
+    /// extension of the archive written next to a recording when no output is given
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Parquet => "parquet",
+            ArchiveFormat::Sqlite => "sqlite",
+            ArchiveFormat::Arrow => "arrow",
+        }
+    }
+}
+
+/// Metadata of a recording found on disk
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordingInfo {
+    pub path: PathBuf,
+    pub symbol: String,
+    pub time_range: (i64, i64),
+    pub size_in_bytes: u64,
+    /// number of book updates, one per timestamp
+    pub message_count: usize,
+}
+
+/// load a recording made of `<unix seconds> <ask|bid> <price> <quantity>` lines into
+/// book updates, one update per timestamp
+pub fn load_recording(path: &Path, symbol: &str) -> Result<Vec<Booked>, BookedError> {
+    let content = read_to_string(path)?;
+
+    let mut updates: Vec<(i64, Booked)> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let parsed = match fields.as_slice() {
+            [time, side, price, quantity] => {
+                match (
+                    time.parse::<i64>(),
+                    price.parse::<f64>(),
+                    quantity.parse::<f64>(),
+                ) {
+                    (Ok(time), Ok(price), Ok(quantity)) => (time, *side, Order { price, quantity }),
+                    _ => {
+                        return Err(BookedError::Storage(format!(
+                            "Malformed values on line {}",
+                            number + 1
+                        )));
+                    }
+                }
+            }
+            _ => {
+                return Err(BookedError::Storage(format!(
+                    "Expected 4 fields on line {}",
+                    number + 1
+                )));
+            }
+        };
+        let (time, side, order) = parsed;
+
+        if updates
+            .last()
+            .is_none_or(|(last_time, _)| *last_time != time)
+        {
+            let timestamp = match DateTime::from_timestamp(time, 0) {
+                Some(timestamp) => timestamp.to_rfc3339(),
+                None => {
+                    return Err(BookedError::Storage(format!(
+                        "Invalid timestamp on line {}",
+                        number + 1
+                    )));
+                }
+            };
+            updates.push((
+                time,
+                Booked {
+                    symbol: symbol.to_string(),
+                    timestamp,
+                    bids: Vec::new(),
+                    asks: Vec::new(),
+                },
+            ));
+        }
+
+        let (_, booked) = updates.last_mut().unwrap();
+        match side {
+            "ask" => booked.asks.push(order),
+            "bid" => booked.bids.push(order),
+            _ => {
+                return Err(BookedError::Storage(format!(
+                    "Unknown side {} on line {}",
+                    side,
+                    number + 1
+                )));
+            }
+        }
+    }
+
+    Ok(updates.into_iter().map(|(_, booked)| booked).collect())
+}
+
+/// symbol of a recording, read from a `# symbol: <SYMBOL>` header or the file name
+fn recording_symbol(path: &Path, content: &str) -> String {
+    content
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .find_map(|line| line.trim_start_matches('#').trim().strip_prefix("symbol:"))
+        .map(|symbol| symbol.trim().to_string())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
+
+/// read the metadata of a recording
+pub fn inspect_recording(path: &Path) -> Result<RecordingInfo, BookedError> {
+    let size_in_bytes = metadata(path)?.len();
+    let content = read_to_string(path)?;
+    let symbol = recording_symbol(path, &content);
+    let updates = load_recording(path, &symbol)?;
+
+    let times = updates
+        .iter()
+        .filter_map(|booked| DateTime::parse_from_rfc3339(&booked.timestamp).ok())
+        .map(|time| time.timestamp())
+        .collect::<Vec<_>>();
+
+    Ok(RecordingInfo {
+        path: path.to_path_buf(),
+        symbol,
+        time_range: (
+            times.first().cloned().unwrap_or(0),
+            times.last().cloned().unwrap_or(0),
+        ),
+        size_in_bytes,
+        message_count: updates.len(),
+    })
+}
+
+/// list the recordings of a directory, skipping files that are not recordings
+pub fn scan_recordings(directory: &Path) -> Result<Vec<RecordingInfo>, BookedError> {
+    let entries = read_dir(directory)?;
+
+    let mut recordings = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| inspect_recording(&path).ok())
+        .collect::<Vec<_>>();
+    recordings.sort_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+
+    Ok(recordings)
+}
+
+/// export a recording to an archive, a directory of parquet slices of a length in seconds or a
+/// SQLite file that --from resumes from, or an Arrow file batched in slices of that length,
+/// returning the number of book updates archived
+pub async fn archive_recording(
+    path: &Path,
+    format: ArchiveFormat,
+    output: &Path,
+    slice_in_seconds: i64,
+) -> Result<usize, BookedError> {
+    let info = inspect_recording(path)?;
+    let updates = load_recording(path, &info.symbol)?;
+    let count = updates.len();
+    match format {
+        ArchiveFormat::Parquet | ArchiveFormat::Arrow => {
+            // the whole recording fits in the history for every book to be rebuilt
+            let (start, end) = info.time_range;
+            let mut history = BookHistory::new((end - start + 1).max(1) as usize);
+            for booked in updates {
+                history.update(booked).await?;
+            }
+            let mut persister = HistoryPersister::new(output.to_path_buf(), slice_in_seconds);
+            let slices = persister.remaining_slices(&info.symbol, &history).await;
+            if format == ArchiveFormat::Arrow {
+                return write_arrow(output, &info.symbol, &slices).map(|_| count);
+            }
+            for slice in slices.iter() {
+                write_slice(output, slice)?;
+            }
+        }
+        ArchiveFormat::Sqlite => {
+            let mut storage = Storage::open(output)?;
+            for booked in updates.iter() {
+                storage.insert_book(booked)?;
+            }
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/recordings")
+            .join(name)
+    }
+
+    #[test]
+    fn test_load_recording() {
+        let updates = load_recording(&recording("synthetic.txt"), "Fixture/Symbol").unwrap();
+
+        assert_eq!(updates.len(), 90);
+        assert_eq!(updates[0].asks.len(), 11);
+        assert_eq!(updates[0].bids.len(), 10);
+        assert!(
+            updates
+                .iter()
+                .all(|booked| booked.symbol == "Fixture/Symbol")
+        );
+    }
+
+    #[test]
+    fn test_scan_recordings() {
+        let recordings = scan_recordings(&recording("")).unwrap();
+
+        assert_eq!(recordings.len(), 1);
+        assert_eq!(recordings[0].path, recording("synthetic.txt"));
+        assert_eq!(recordings[0].symbol, "synthetic");
+        assert_eq!(recordings[0].time_range, (1700000000, 1700000089));
+        assert_eq!(recordings[0].message_count, 90);
+        assert!(recordings[0].size_in_bytes > 0);
+
+        assert!(scan_recordings(&recording("missing")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archive_recording() {
+        let temporary = tempfile::tempdir().unwrap();
+        let directory = temporary.path().join("archive");
+
+        let parquet = directory.join("synthetic.parquet");
+        let count = archive_recording(
+            &recording("synthetic.txt"),
+            ArchiveFormat::Parquet,
+            &parquet,
+            60,
+        )
+        .await
+        .unwrap();
+        assert_eq!(count, 90);
+        // the 90 seconds of the recording straddle two slices, the last one included
+        let books = crate::persistence::load_history(&parquet, "synthetic", 0).unwrap();
+        assert_eq!(books.len(), 90);
+        assert_eq!(books[0].timestamp, "2023-11-14T22:13:20+00:00");
+
+        let sqlite = directory.join("synthetic.sqlite");
+        archive_recording(
+            &recording("synthetic.txt"),
+            ArchiveFormat::Sqlite,
+            &sqlite,
+            60,
+        )
+        .await
+        .unwrap();
+        let storage = Storage::open(&sqlite).unwrap();
+        assert_eq!(storage.books("synthetic", 0).unwrap().len(), 90);
+
+        let arrow = directory.join("synthetic.arrow");
+        archive_recording(
+            &recording("synthetic.txt"),
+            ArchiveFormat::Arrow,
+            &arrow,
+            60,
+        )
+        .await
+        .unwrap();
+        let reader =
+            arrow_ipc::reader::FileReader::try_new(std::fs::File::open(&arrow).unwrap(), None)
+                .unwrap();
+        assert_eq!(reader.count(), 2);
+    }
+
+    #[test]
+    fn test_recording_symbol() {
+        let path = Path::new("session.txt");
+        assert_eq!(recording_symbol(path, "1 ask 1.0 1.0"), "session");
+        assert_eq!(
+            recording_symbol(path, "# recording\n# symbol: BTC/USD\n1 ask 1.0 1.0"),
+            "BTC/USD"
+        );
+    }
+}
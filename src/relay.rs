@@ -0,0 +1,469 @@
+use crate::actions::{Action, Query};
+use crate::clock;
+use crate::error::BookedError;
+use crate::feed::{Booked, Order, TickerState, Traded};
+use crate::pipeline::{BookHistory, Price};
+
+use chrono::{DateTime, Utc};
+use rbtree::RBTree;
+use serde::{Deserialize, Serialize};
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{self, Receiver};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{Mutex, oneshot};
+use tokio::task::{JoinHandle, spawn};
+use tracing::{info, warn};
+
+use std::collections::HashSet;
+use std::fs::remove_file;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Number of messages kept for interfaces lagging behind before they get detached
+const CLIENT_BUFFER_SIZE: usize = 1024;
+
+/// Message relayed by a daemon to the interfaces attached to it, one JSON object per line keyed
+/// by its type, numbers of internally tagged messages not reading back with arbitrary precision
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relayed {
+    /// symbol collected by the daemon, sent on attaching and on every new subscription
+    Subscribed { symbol: String },
+    /// full book of a symbol at one time of the history kept by the daemon
+    Snapshot(Booked),
+    /// book update as received from the exchange
+    Book(Booked),
+    /// ticker update as received from the exchange
+    Ticker(TickerState),
+    /// trades executed, either received from the exchange or kept by the daemon
+    Trades { trades: Vec<Traded> },
+}
+
+/// Request of an attached interface to the daemon, one JSON object per line keyed by its type
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Requested {
+    /// collect a symbol if not collected yet, and replay its history to the requester
+    Subscribe { symbol: String },
+}
+
+/// private utility method serializing a message on its own line
+fn line<T: Serialize>(message: &T) -> Result<String, BookedError> {
+    match serde_json::to_string(message) {
+        Ok(mut line) => {
+            line.push('\n');
+            Ok(line)
+        }
+        Err(message) => Err(BookedError::Relay(format!("{:?}", message))),
+    }
+}
+
+/// lines replaying the history of a symbol, one full book per time followed by the trades kept
+pub async fn replay_lines(symbol: &str, history: &BookHistory) -> Result<String, BookedError> {
+    let orders = |levels: &RBTree<Price, f64>| {
+        levels
+            .iter()
+            .map(|(price, quantity)| Order {
+                price: price.value,
+                quantity: *quantity,
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut lines = String::new();
+    let readable_asks = history.asks.read().await;
+    let readable_bids = history.bids.read().await;
+    for ((time, asks), (_, bids)) in readable_asks.iter().zip(readable_bids.iter()) {
+        let Some(timestamp) = DateTime::<Utc>::from_timestamp(*time, 0) else {
+            continue;
+        };
+        lines.push_str(&line(&Relayed::Snapshot(Booked {
+            symbol: symbol.to_string(),
+            timestamp: timestamp.to_rfc3339(),
+            bids: orders(bids),
+            asks: orders(asks),
+        }))?);
+    }
+    let trades = history
+        .trades
+        .read()
+        .await
+        .iter()
+        .map(|(_, trade)| trade.clone())
+        .collect::<Vec<_>>();
+    if !trades.is_empty() {
+        lines.push_str(&line(&Relayed::Trades { trades })?);
+    }
+    Ok(lines)
+}
+
+/// private utility method asking a question through the action queue
+async fn ask(actions: &Sender<Action>, query: Query) -> Result<Option<String>, BookedError> {
+    let (responder, answered) = oneshot::channel();
+    actions.send(Action::Query(query, responder)).await?;
+    Ok(answered.await.unwrap_or(None))
+}
+
+/// private utility method writing lines to a socket
+async fn write(writer: &mut OwnedWriteHalf, lines: &str) -> Result<(), BookedError> {
+    match writer.write_all(lines.as_bytes()).await {
+        Ok(()) => Ok(()),
+        Err(message) => Err(BookedError::Relay(format!("{:?}", message))),
+    }
+}
+
+/// Unix socket server of a daemon, relaying the books, tickers and trades it collects to every
+/// attached interface and replaying the history of the symbols they subscribe to
+#[derive(Clone)]
+pub struct Relay {
+    sender: broadcast::Sender<String>,
+}
+
+impl Relay {
+    /// constructor, listening at a socket path, symbols and histories being asked for through
+    /// the action queue
+    pub fn bind(path: &Path, actions: Sender<Action>) -> Result<Relay, BookedError> {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(BookedError::Relay(format!(
+                "{} is used by a running daemon",
+                path.display()
+            )));
+        }
+        // socket left behind by a daemon that did not exit cleanly
+        let _ = remove_file(path);
+        let listener = match UnixListener::bind(path) {
+            Ok(listener) => listener,
+            Err(message) => return Err(BookedError::Relay(format!("{:?}", message))),
+        };
+        let (sender, _) = broadcast::channel(CLIENT_BUFFER_SIZE);
+        spawn(Relay::accept(listener, sender.clone(), actions));
+        Ok(Relay { sender })
+    }
+
+    /// announce a symbol newly collected
+    pub fn subscribed(&self, symbol: &str) {
+        self.send(|| Relayed::Subscribed {
+            symbol: symbol.to_string(),
+        });
+    }
+
+    /// relay a book update
+    pub fn book(&self, booked: &Booked) {
+        self.send(|| Relayed::Book(booked.clone()));
+    }
+
+    /// relay a ticker update
+    pub fn ticker(&self, ticker: &TickerState) {
+        self.send(|| Relayed::Ticker(ticker.clone()));
+    }
+
+    /// relay trades executed
+    pub fn trades(&self, trades: &[Traded]) {
+        self.send(|| Relayed::Trades {
+            trades: trades.to_vec(),
+        });
+    }
+
+    /// private utility method serializing a message for the attached interfaces, if any
+    fn send(&self, relayed: impl FnOnce() -> Relayed) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        match line(&relayed()) {
+            // failing to send only means every interface detached meanwhile
+            Ok(line) => {
+                let _ = self.sender.send(line);
+            }
+            Err(message) => warn!(%message, "could not serialize relayed message"),
+        }
+    }
+
+    /// private utility method accepting interfaces until the daemon exits
+    async fn accept(
+        listener: UnixListener,
+        sender: broadcast::Sender<String>,
+        actions: Sender<Action>,
+    ) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let (receiver, actions) = (sender.subscribe(), actions.clone());
+                    spawn(async move {
+                        info!("interface attached");
+                        match Relay::serve(stream, receiver, actions).await {
+                            Ok(()) => info!("interface detached"),
+                            Err(message) => warn!(%message, "interface detached"),
+                        }
+                    });
+                }
+                Err(message) => warn!(%message, "could not accept interface"),
+            }
+        }
+    }
+
+    /// private utility method announcing the symbols collected to an interface, then relaying
+    /// messages and answering its requests until it detaches, detaching it when lagging behind
+    async fn serve(
+        stream: UnixStream,
+        mut receiver: Receiver<String>,
+        actions: Sender<Action>,
+    ) -> Result<(), BookedError> {
+        let (reader, mut writer) = stream.into_split();
+        let mut requests = BufReader::new(reader).lines();
+
+        let symbols = match ask(&actions, Query::Symbols).await? {
+            Some(symbols) => serde_json::from_str::<Vec<String>>(&symbols).unwrap_or_default(),
+            None => vec![],
+        };
+        for symbol in symbols {
+            write(&mut writer, &line(&Relayed::Subscribed { symbol })?).await?;
+        }
+
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(lines) => write(&mut writer, &lines).await?,
+                    Err(RecvError::Lagged(skipped)) => {
+                        return Err(BookedError::Relay(format!(
+                            "interface lagged {} messages behind",
+                            skipped
+                        )));
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                request = requests.next_line() => match request {
+                    Ok(Some(request)) => match serde_json::from_str::<Requested>(&request) {
+                        Ok(Requested::Subscribe { symbol }) => {
+                            actions.send(Action::SubscribeTicker(symbol.clone())).await?;
+                            if let Some(lines) = ask(&actions, Query::Replay(symbol)).await? {
+                                write(&mut writer, &lines).await?;
+                            }
+                        }
+                        Err(message) => warn!(%message, request, "unreadable request"),
+                    },
+                    Ok(None) => return Ok(()),
+                    Err(message) => return Err(BookedError::Relay(format!("{:?}", message))),
+                },
+            }
+        }
+    }
+}
+
+/// Connection of an interface to a daemon, with the symbols it follows
+pub struct Attached {
+    writer: Mutex<OwnedWriteHalf>,
+    subscribed: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Attached {
+    /// constructor, connecting to the socket of a daemon and spawning the listener forwarding
+    /// what it relays to the action queue, the symbols given being subscribed again
+    pub async fn connect(
+        path: &Path,
+        subscribed: HashSet<String>,
+        sender: Sender<Action>,
+    ) -> Result<(Attached, JoinHandle<Result<(), BookedError>>), BookedError> {
+        let stream = match UnixStream::connect(path).await {
+            Ok(stream) => stream,
+            Err(message) => {
+                return Err(BookedError::Relay(format!(
+                    "{}: {:?}",
+                    path.display(),
+                    message
+                )));
+            }
+        };
+        let (reader, writer) = stream.into_split();
+        let attached = Attached {
+            writer: Mutex::new(writer),
+            subscribed: Arc::new(Mutex::new(HashSet::new())),
+        };
+        let listened = attached.subscribed.clone();
+        let listener_handle = spawn(async move {
+            listen_to_daemon(sender, BufReader::new(reader).lines(), listened).await
+        });
+        for symbol in subscribed {
+            attached.subscribe(symbol).await?;
+        }
+        Ok((attached, listener_handle))
+    }
+
+    /// follow a symbol, requesting its history from the daemon unless already followed
+    pub async fn subscribe(&self, symbol: String) -> Result<(), BookedError> {
+        if !self.subscribed.lock().await.insert(symbol.clone()) {
+            return Ok(());
+        }
+        let request = line(&Requested::Subscribe { symbol })?;
+        write(&mut *self.writer.lock().await, &request).await
+    }
+
+    /// stop following a symbol, the daemon collecting it on for the other interfaces
+    pub async fn unsubscribe(&self, symbol: &str) {
+        self.subscribed.lock().await.remove(symbol);
+    }
+
+    /// symbols followed
+    pub async fn subscribed(&self) -> HashSet<String> {
+        self.subscribed.lock().await.clone()
+    }
+}
+
+/// method to be spawned in separate thread that listens to a daemon and forwards the messages on
+/// the symbols followed to action queue, symbols newly collected being subscribed
+async fn listen_to_daemon(
+    sender: Sender<Action>,
+    mut lines: Lines<BufReader<OwnedReadHalf>>,
+    subscribed: Arc<Mutex<HashSet<String>>>,
+) -> Result<(), BookedError> {
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => {
+                info!("daemon connection closed");
+                return Ok(());
+            }
+            Err(message) => return Err(BookedError::Relay(format!("{:?}", message))),
+        };
+        let relayed = match serde_json::from_str::<Relayed>(&line) {
+            Ok(relayed) => relayed,
+            Err(message) => {
+                warn!(%message, "daemon message could not be read");
+                continue;
+            }
+        };
+        let followed = subscribed.lock().await;
+        let action = match relayed {
+            Relayed::Subscribed { symbol } if !followed.contains(&symbol) => {
+                Action::SubscribeTicker(symbol)
+            }
+            Relayed::Snapshot(booked) if followed.contains(&booked.symbol) => {
+                Action::ReplayBook(booked)
+            }
+            Relayed::Book(booked) if followed.contains(&booked.symbol) => {
+                if let Ok(time) = DateTime::parse_from_rfc3339(&booked.timestamp) {
+                    clock::observe(time.with_timezone(&Utc));
+                }
+                Action::UpdateBook(booked)
+            }
+            Relayed::Ticker(ticker) if followed.contains(&ticker.symbol) => {
+                Action::UpdateTicker(ticker)
+            }
+            Relayed::Trades { trades }
+                if trades
+                    .first()
+                    .is_some_and(|trade| followed.contains(&trade.symbol)) =>
+            {
+                Action::UpdateTrades(trades)
+            }
+            _ => continue,
+        };
+        drop(followed);
+        sender.send(action).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn test_relay() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("relay.sock");
+        let (actions, mut queue) = channel(8);
+        let relay = Relay::bind(&path, actions.clone()).unwrap();
+        assert!(Relay::bind(&path, actions).is_err());
+
+        // the daemon side answering from a history of one book
+        let mut history = BookHistory::new(60);
+        history
+            .update(Booked {
+                symbol: "BTC/USD".to_string(),
+                timestamp: "2023-11-14T22:13:20+00:00".to_string(),
+                bids: vec![Order {
+                    price: 99.5,
+                    quantity: 2.0,
+                }],
+                asks: vec![Order {
+                    price: 100.5,
+                    quantity: 1.0,
+                }],
+            })
+            .await
+            .unwrap();
+        spawn(async move {
+            while let Some(action) = queue.recv().await {
+                if let Action::Query(query, responder) = action {
+                    let _ = responder.send(match query {
+                        Query::Symbols => Some(r#"["BTC/USD"]"#.to_string()),
+                        Query::Replay(symbol) => replay_lines(&symbol, &history).await.ok(),
+                        _ => None,
+                    });
+                }
+            }
+        });
+
+        let (sender, mut received) = channel(8);
+        let (attached, _) = Attached::connect(&path, HashSet::new(), sender)
+            .await
+            .unwrap();
+        let Some(Action::SubscribeTicker(symbol)) = received.recv().await else {
+            panic!("expected the symbol collected to be subscribed");
+        };
+        assert_eq!(symbol, "BTC/USD");
+        attached.subscribe(symbol).await.unwrap();
+        let Some(Action::ReplayBook(booked)) = received.recv().await else {
+            panic!("expected the history to be replayed");
+        };
+        assert_eq!(booked.timestamp, "2023-11-14T22:13:20+00:00");
+        assert_eq!(
+            booked.bids,
+            vec![Order {
+                price: 99.5,
+                quantity: 2.0
+            }]
+        );
+        assert_eq!(
+            booked.asks,
+            vec![Order {
+                price: 100.5,
+                quantity: 1.0
+            }]
+        );
+
+        // live updates only reach the interfaces following their symbol
+        relay.ticker(&TickerState {
+            ask: 100.5,
+            ask_quantity: 1.0,
+            bid: 99.5,
+            bid_quantity: 2.0,
+            change: 0.0,
+            change_pct: 0.0,
+            high: 101.0,
+            last: 100.0,
+            low: 99.0,
+            symbol: "ETH/USD".to_string(),
+            volume: 10.0,
+            vwap: 100.0,
+        });
+        relay.subscribed("BTC/USD");
+        relay.book(&Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: "2023-11-14T22:13:21+00:00".to_string(),
+            bids: vec![],
+            asks: vec![Order {
+                price: 100.5,
+                quantity: 0.0,
+            }],
+        });
+        let Some(Action::UpdateBook(booked)) = received.recv().await else {
+            panic!("expected the book update to be relayed");
+        };
+        assert_eq!(booked.timestamp, "2023-11-14T22:13:21+00:00");
+    }
+}
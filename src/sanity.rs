@@ -0,0 +1,210 @@
+use crate::feed::Booked;
+use crate::pipeline::Price;
+use crate::walls::Side;
+
+use rbtree::RBTree;
+
+/// Inconsistency found in a book update or in the book it produced
+#[derive(Clone, Debug, PartialEq)]
+pub enum Anomaly {
+    /// best bid at or above the best ask
+    Crossed { bid: f64, ask: f64 },
+    /// level updated with a quantity below zero
+    NegativeQuantity {
+        side: Side,
+        price: f64,
+        quantity: f64,
+    },
+    /// level updated further away from the recent mid than the spike multiple allows
+    PriceSpike { side: Side, price: f64, mid: f64 },
+}
+
+impl Anomaly {
+    /// human readable description of the anomaly for a given symbol
+    pub fn describe(&self, symbol: &str) -> String {
+        let side_name = |side: &Side| match side {
+            Side::Ask => "ask",
+            Side::Bid => "bid",
+        };
+        match self {
+            Anomaly::Crossed { bid, ask } => {
+                format!(
+                    "Crossed book of {}: best bid {} >= best ask {}",
+                    symbol, bid, ask
+                )
+            }
+            Anomaly::NegativeQuantity {
+                side,
+                price,
+                quantity,
+            } => format!(
+                "Negative {} quantity {} of {} at {}",
+                side_name(side),
+                quantity,
+                symbol,
+                price
+            ),
+            Anomaly::PriceSpike { side, price, mid } => format!(
+                "Price spike of {} {} at {} against a mid of {}",
+                symbol,
+                side_name(side),
+                price,
+                mid
+            ),
+        }
+    }
+}
+
+/// Checker of book updates against the last sane book of a ticker
+#[derive(Clone, Debug)]
+pub struct BookChecker {
+    /// multiple of the recent mid beyond which, either way, an updated level is a spike
+    pub spike_multiple: f64,
+    /// mid price of the last book that was not crossed
+    mid: Option<f64>,
+}
+
+impl BookChecker {
+    /// constructor
+    pub fn new(spike_multiple: f64) -> BookChecker {
+        BookChecker {
+            spike_multiple,
+            mid: None,
+        }
+    }
+
+    /// forget the recent mid, to be called when the book is replaced by a fresh snapshot
+    pub fn reset(&mut self) {
+        self.mid = None;
+    }
+
+    /// check the levels of an update for negative quantities and spikes away from the recent mid
+    pub fn check_update(&self, update: &Booked) -> Vec<Anomaly> {
+        let orders = update
+            .asks
+            .iter()
+            .map(|order| (Side::Ask, order))
+            .chain(update.bids.iter().map(|order| (Side::Bid, order)));
+        let mut anomalies = vec![];
+        for (side, order) in orders {
+            if order.quantity < 0.0 {
+                anomalies.push(Anomaly::NegativeQuantity {
+                    side,
+                    price: order.price,
+                    quantity: order.quantity,
+                });
+            } else if let Some(mid) = self.mid
+                && order.quantity > 0.0
+                && (order.price > mid * self.spike_multiple
+                    || order.price < mid / self.spike_multiple)
+            {
+                anomalies.push(Anomaly::PriceSpike {
+                    side,
+                    price: order.price,
+                    mid,
+                });
+            }
+        }
+        anomalies
+    }
+
+    /// check that a book an update was merged into is not crossed, remembering its mid otherwise
+    pub fn check_book(
+        &mut self,
+        asks: &RBTree<Price, f64>,
+        bids: &RBTree<Price, f64>,
+    ) -> Option<Anomaly> {
+        let (Some((ask, _)), Some((bid, _))) = (asks.get_first(), bids.get_last()) else {
+            return None;
+        };
+        if bid.value >= ask.value {
+            return Some(Anomaly::Crossed {
+                bid: bid.value,
+                ask: ask.value,
+            });
+        }
+        self.mid = Some((ask.value + bid.value) / 2.0);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::Order;
+
+    fn book(levels: &[(f64, f64)]) -> RBTree<Price, f64> {
+        levels
+            .iter()
+            .map(|(price, quantity)| (Price { value: *price }, *quantity))
+            .collect()
+    }
+
+    fn booked(asks: &[(f64, f64)], bids: &[(f64, f64)]) -> Booked {
+        let orders = |levels: &[(f64, f64)]| {
+            levels
+                .iter()
+                .map(|(price, quantity)| Order {
+                    price: *price,
+                    quantity: *quantity,
+                })
+                .collect()
+        };
+        Booked {
+            symbol: "BTC/USD".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            asks: orders(asks),
+            bids: orders(bids),
+        }
+    }
+
+    #[test]
+    fn test_book_checker() {
+        let mut checker = BookChecker::new(2.0);
+
+        // far away levels are only spikes once a mid is known
+        let update = booked(&[(250.0, 1.0)], &[]);
+        assert_eq!(checker.check_update(&update), vec![]);
+
+        let asks = book(&[(101.0, 1.0), (102.0, 1.0)]);
+        let bids = book(&[(99.0, 1.0), (98.0, 1.0)]);
+        assert_eq!(checker.check_book(&asks, &bids), None);
+
+        // zero quantities remove levels wherever they are
+        let update = booked(&[(250.0, 1.0), (300.0, 0.0)], &[(-1.0, -2.0), (40.0, 1.0)]);
+        assert_eq!(
+            checker.check_update(&update),
+            vec![
+                Anomaly::PriceSpike {
+                    side: Side::Ask,
+                    price: 250.0,
+                    mid: 100.0
+                },
+                Anomaly::NegativeQuantity {
+                    side: Side::Bid,
+                    price: -1.0,
+                    quantity: -2.0
+                },
+                Anomaly::PriceSpike {
+                    side: Side::Bid,
+                    price: 40.0,
+                    mid: 100.0
+                },
+            ]
+        );
+
+        // crossed books do not move the recent mid
+        let crossed = book(&[(99.0, 1.0), (99.5, 1.0)]);
+        assert_eq!(
+            checker.check_book(&crossed, &bids),
+            Some(Anomaly::Crossed {
+                bid: 99.0,
+                ask: 99.0
+            })
+        );
+        assert_eq!(checker.check_update(&booked(&[(199.0, 1.0)], &[])), vec![]);
+
+        checker.reset();
+        assert_eq!(checker.check_update(&booked(&[(1000.0, 1.0)], &[])), vec![]);
+    }
+}
@@ -0,0 +1,553 @@
+use crate::error::BookedError;
+
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+
+use itertools::Itertools;
+
+use std::fs::{create_dir_all, write};
+use std::path::{Path, PathBuf};
+
+/// Width in pixels of a terminal cell in SVG and PNG screenshots
+const CELL_WIDTH: usize = 8;
+
+/// Height in pixels of a terminal cell in SVG and PNG screenshots
+const CELL_HEIGHT: usize = 16;
+
+/// Colors of the cells drawn with the default colors of the terminal
+const DEFAULT_FOREGROUND: (u8, u8, u8) = (229, 229, 229);
+const DEFAULT_BACKGROUND: (u8, u8, u8) = (0, 0, 0);
+
+/// File format of screenshots of the interface
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScreenshotFormat {
+    /// text with the escape sequences of the colors, shown back with cat in a terminal
+    Ansi,
+    /// vector image, text kept as text
+    Svg,
+    /// raster image, block and braille characters drawn exactly and text drawn as bars
+    Png,
+}
+
+impl ScreenshotFormat {
+    /// every format, in the order they are listed to the user
+    pub const ALL: [ScreenshotFormat; 3] = [
+        ScreenshotFormat::Ansi,
+        ScreenshotFormat::Svg,
+        ScreenshotFormat::Png,
+    ];
+
+    /// name of the format
+    pub fn name(self) -> &'static str {
+        match self {
+            ScreenshotFormat::Ansi => "ansi",
+            ScreenshotFormat::Svg => "svg",
+            ScreenshotFormat::Png => "png",
+        }
+    }
+
+    /// extension of the files of the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ScreenshotFormat::Ansi => "ans",
+            ScreenshotFormat::Svg => "svg",
+            ScreenshotFormat::Png => "png",
+        }
+    }
+
+    /// format from its name
+    pub fn parse(name: &str) -> Result<ScreenshotFormat, BookedError> {
+        match ScreenshotFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+        {
+            Some(format) => Ok(format),
+            None => Err(BookedError::Parse(format!(
+                "Unknown format '{}', expected one of {}",
+                name,
+                ScreenshotFormat::ALL.map(|format| format.name()).join(", ")
+            ))),
+        }
+    }
+}
+
+/// write a frame to `<directory>/<name>-<unix seconds>.<extension>`, returning the file
+pub fn write_screenshot(
+    directory: &Path,
+    name: &str,
+    time: i64,
+    format: ScreenshotFormat,
+    frame: &Buffer,
+) -> Result<PathBuf, BookedError> {
+    create_dir_all(directory)?;
+    let path = directory.join(format!(
+        "{}-{}.{}",
+        name.replace('/', "-"),
+        time,
+        format.extension()
+    ));
+    let bytes = match format {
+        ScreenshotFormat::Ansi => ansi(frame).into_bytes(),
+        ScreenshotFormat::Svg => svg(frame).into_bytes(),
+        ScreenshotFormat::Png => png(frame)?,
+    };
+    write(&path, bytes)?;
+    Ok(path)
+}
+
+/// frame as lines of text, the colors and boldness of the cells set with escape sequences
+pub fn ansi(frame: &Buffer) -> String {
+    let mut text = String::new();
+    for row in frame.content().chunks(frame.area.width.max(1) as usize) {
+        let mut current = None;
+        for cell in row.iter() {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if current != Some(style) {
+                let mut codes = vec!["0".to_string()];
+                if cell.modifier.contains(Modifier::BOLD) {
+                    codes.push("1".to_string());
+                }
+                if cell.modifier.contains(Modifier::REVERSED) {
+                    codes.push("7".to_string());
+                }
+                codes.push(color_code(cell.fg, false));
+                codes.push(color_code(cell.bg, true));
+                text.push_str(&format!("\x1b[{}m", codes.join(";")));
+                current = Some(style);
+            }
+            text.push_str(cell.symbol());
+        }
+        text.push_str("\x1b[0m\n");
+    }
+    text
+}
+
+/// frame as an SVG image, one rectangle per run of background color and text per run of
+/// foreground color, block and braille characters being drawn as rectangles
+pub fn svg(frame: &Buffer) -> String {
+    let width = frame.area.width as usize;
+    let (background, foreground) = draw_frame(frame);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"monospace\" font-size=\"{}\">\n",
+        width * CELL_WIDTH,
+        frame.area.height as usize * CELL_HEIGHT,
+        CELL_HEIGHT * 3 / 4
+    );
+    for (x, y, width, height, color) in background.into_iter().chain(foreground) {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            x,
+            y,
+            width,
+            height,
+            hex(color)
+        ));
+    }
+    for (y, row) in frame.content().chunks(width.max(1)).enumerate() {
+        // runs of text sharing a color, stretched over their cells to stay aligned
+        let texts = row
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| {
+                coverage(cell.symbol()).is_none() && !cell.symbol().trim().is_empty()
+            })
+            .chunk_by(|(x, cell)| (runs_start(row, *x), colors(cell).0, bold(cell)));
+        for ((_, color, bold), run) in texts.into_iter() {
+            let run = run.collect::<Vec<_>>();
+            let x = run[0].0;
+            let text = run
+                .iter()
+                .map(|(_, cell)| cell.symbol())
+                .collect::<String>();
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" \
+                 fill=\"{}\"{}>{}</text>\n",
+                x * CELL_WIDTH,
+                y * CELL_HEIGHT + CELL_HEIGHT * 3 / 4,
+                run.len() * CELL_WIDTH,
+                hex(color),
+                if bold { " font-weight=\"bold\"" } else { "" },
+                escape(&text)
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// frame as a PNG image, block and braille characters drawn exactly and text drawn as bars of
+/// its color, no font being at hand
+pub fn png(frame: &Buffer) -> Result<Vec<u8>, BookedError> {
+    let width = frame.area.width as usize * CELL_WIDTH;
+    let height = frame.area.height as usize * CELL_HEIGHT;
+    let mut pixels = vec![0; 3 * width * height];
+    let (background, foreground) = draw_frame(frame);
+    for (x, y, rect_width, rect_height, color) in background.into_iter().chain(foreground) {
+        for row in y..(y + rect_height).min(height) {
+            for column in x..(x + rect_width).min(width) {
+                let index = 3 * (row * width + column);
+                pixels[index..index + 3].copy_from_slice(&[color.0, color.1, color.2]);
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(bytes)
+}
+
+/// private rectangle in pixels, x, y, width, height and color
+type Rectangle = (usize, usize, usize, usize, (u8, u8, u8));
+
+/// private part of a cell as fractions of the cell, left, top, width and height
+type Part = (f64, f64, f64, f64);
+
+/// private utility method for the rectangles of the backgrounds, merged along rows, and of the
+/// foregrounds of a frame, text being drawn as bars
+fn draw_frame(frame: &Buffer) -> (Vec<Rectangle>, Vec<Rectangle>) {
+    let width = frame.area.width as usize;
+    let mut background = Vec::new();
+    let mut foreground = Vec::new();
+    for (y, row) in frame.content().chunks(width.max(1)).enumerate() {
+        let mut x = 0;
+        for (color, run) in &row.iter().chunk_by(|cell| colors(cell).1) {
+            let length = run.count();
+            background.push((
+                x * CELL_WIDTH,
+                y * CELL_HEIGHT,
+                length * CELL_WIDTH,
+                CELL_HEIGHT,
+                color,
+            ));
+            x += length;
+        }
+        for (x, cell) in row.iter().enumerate() {
+            let (fg, bg) = colors(cell);
+            let (parts, opacity) = match coverage(cell.symbol()) {
+                Some(covered) => covered,
+                None if cell.symbol().trim().is_empty() => continue,
+                // text drawn as a bar covering the body of lowercase letters
+                None => (vec![(0.125, 0.375, 0.75, 0.375)], 0.5),
+            };
+            let color = blend(fg, bg, opacity);
+            for (left, top, part_width, part_height) in parts {
+                foreground.push((
+                    x * CELL_WIDTH + (left * CELL_WIDTH as f64).round() as usize,
+                    y * CELL_HEIGHT + (top * CELL_HEIGHT as f64).round() as usize,
+                    ((part_width * CELL_WIDTH as f64).round() as usize).max(1),
+                    ((part_height * CELL_HEIGHT as f64).round() as usize).max(1),
+                    color,
+                ));
+            }
+        }
+    }
+    (background, foreground)
+}
+
+/// private utility method for the parts of a cell covered by a block, braille or box drawing
+/// character, as fractions of the cell (left, top, width, height), along with the opacity of
+/// the foreground, None for other characters
+fn coverage(symbol: &str) -> Option<(Vec<Part>, f64)> {
+    let mut characters = symbol.chars();
+    let character = match (characters.next(), characters.next()) {
+        (Some(character), None) => character,
+        _ => return None,
+    };
+    let code = character as u32;
+    let quadrants = |upper_left, upper_right, lower_left, lower_right| {
+        [
+            (upper_left, (0.0, 0.0, 0.5, 0.5)),
+            (upper_right, (0.5, 0.0, 0.5, 0.5)),
+            (lower_left, (0.0, 0.5, 0.5, 0.5)),
+            (lower_right, (0.5, 0.5, 0.5, 0.5)),
+        ]
+        .into_iter()
+        .filter(|(covered, _)| *covered)
+        .map(|(_, part)| part)
+        .collect::<Vec<_>>()
+    };
+    let parts = match character {
+        '█' => vec![(0.0, 0.0, 1.0, 1.0)],
+        '▀' => vec![(0.0, 0.0, 1.0, 0.5)],
+        '▐' => vec![(0.5, 0.0, 0.5, 1.0)],
+        '▔' => vec![(0.0, 0.0, 1.0, 0.125)],
+        '▕' => vec![(0.875, 0.0, 0.125, 1.0)],
+        '░' => return Some((vec![(0.0, 0.0, 1.0, 1.0)], 0.25)),
+        '▒' => return Some((vec![(0.0, 0.0, 1.0, 1.0)], 0.5)),
+        '▓' => return Some((vec![(0.0, 0.0, 1.0, 1.0)], 0.75)),
+        // lower eighths, from ▁ to ▄ to ▇
+        '\u{2581}'..='\u{2587}' => {
+            let height = (code - 0x2580) as f64 / 8.0;
+            vec![(0.0, 1.0 - height, 1.0, height)]
+        }
+        // left eighths, from ▉ to ▌ to ▏
+        '\u{2589}'..='\u{258f}' => vec![(0.0, 0.0, (0x2590 - code) as f64 / 8.0, 1.0)],
+        '▖' => quadrants(false, false, true, false),
+        '▗' => quadrants(false, false, false, true),
+        '▘' => quadrants(true, false, false, false),
+        '▙' => quadrants(true, false, true, true),
+        '▚' => quadrants(true, false, false, true),
+        '▛' => quadrants(true, true, true, false),
+        '▜' => quadrants(true, true, false, true),
+        '▝' => quadrants(false, true, false, false),
+        '▞' => quadrants(false, true, true, false),
+        '▟' => quadrants(false, true, true, true),
+        // braille dots, numbered down the left column then down the right one, the bottom row
+        // coming last
+        '\u{2800}'..='\u{28ff}' => [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (0, 3),
+            (1, 3),
+        ]
+        .into_iter()
+        .enumerate()
+        .filter(|(bit, _)| (code - 0x2800) & (1 << bit) != 0)
+        .map(|(_, (column, row))| {
+            (
+                column as f64 * 0.5 + 0.125,
+                row as f64 * 0.25 + 0.0625,
+                0.25,
+                0.125,
+            )
+        })
+        .collect(),
+        _ => {
+            // arms of box drawing characters, going up, down, left and right from the center
+            let (up, down, left, right) = match character {
+                '─' | '━' | '═' => (false, false, true, true),
+                '│' | '┃' | '║' => (true, true, false, false),
+                '┌' | '╭' | '╔' | '┏' => (false, true, false, true),
+                '┐' | '╮' | '╗' | '┓' => (false, true, true, false),
+                '└' | '╰' | '╚' | '┗' => (true, false, false, true),
+                '┘' | '╯' | '╝' | '┛' => (true, false, true, false),
+                '├' | '╠' | '┣' => (true, true, false, true),
+                '┤' | '╣' | '┫' => (true, true, true, false),
+                '┬' | '╦' | '┳' => (false, true, true, true),
+                '┴' | '╩' | '┻' => (true, false, true, true),
+                '┼' | '╬' | '╋' => (true, true, true, true),
+                _ => return None,
+            };
+            let (thickness_x, thickness_y) = (0.125, 0.0625);
+            let (center_x, center_y) = (0.5 - thickness_x / 2.0, 0.5 - thickness_y / 2.0);
+            [
+                (up, (center_x, 0.0, thickness_x, 0.5)),
+                (down, (center_x, 0.5, thickness_x, 0.5)),
+                (left, (0.0, center_y, 0.5, thickness_y)),
+                (right, (0.5, center_y, 0.5, thickness_y)),
+            ]
+            .into_iter()
+            .filter(|(drawn, _)| *drawn)
+            .map(|(_, part)| part)
+            .collect()
+        }
+    };
+    Some((parts, 1.0))
+}
+
+/// private utility method for the index of the first cell of the run of text a cell belongs to,
+/// runs being broken by spaces and drawn characters
+fn runs_start(row: &[Cell], x: usize) -> usize {
+    let mut start = x;
+    while start > 0 {
+        let symbol = row[start - 1].symbol();
+        if coverage(symbol).is_some() || symbol.trim().is_empty() {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+/// private utility method for the foreground and background colors a cell is drawn with
+fn colors(cell: &Cell) -> ((u8, u8, u8), (u8, u8, u8)) {
+    let fg = rgb(cell.fg).unwrap_or(DEFAULT_FOREGROUND);
+    let bg = rgb(cell.bg).unwrap_or(DEFAULT_BACKGROUND);
+    match cell.modifier.contains(Modifier::REVERSED) {
+        true => (bg, fg),
+        false => (fg, bg),
+    }
+}
+
+/// private utility method for whether a cell is bold
+fn bold(cell: &Cell) -> bool {
+    cell.modifier.contains(Modifier::BOLD)
+}
+
+/// private utility method converting a terminal color to RGB, with the xterm palette, None for
+/// the default color
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const PALETTE: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(red, green, blue) => return Some((red, green, blue)),
+        Color::Indexed(index) => index,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    Some(match index {
+        0..=15 => PALETTE[index as usize],
+        16..=231 => {
+            let level = |value: u8| match value {
+                0 => 0,
+                _ => 55 + 40 * value,
+            };
+            let cube = index - 16;
+            (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        }
+    })
+}
+
+/// private utility method for the select graphic rendition parameter of a foreground or
+/// background color
+fn color_code(color: Color, background: bool) -> String {
+    let offset = if background { 10 } else { 0 };
+    let named = |code: u8| (code + offset).to_string();
+    match color {
+        Color::Reset => named(39),
+        Color::Black => named(30),
+        Color::Red => named(31),
+        Color::Green => named(32),
+        Color::Yellow => named(33),
+        Color::Blue => named(34),
+        Color::Magenta => named(35),
+        Color::Cyan => named(36),
+        Color::Gray => named(37),
+        Color::DarkGray => named(90),
+        Color::LightRed => named(91),
+        Color::LightGreen => named(92),
+        Color::LightYellow => named(93),
+        Color::LightBlue => named(94),
+        Color::LightMagenta => named(95),
+        Color::LightCyan => named(96),
+        Color::White => named(97),
+        Color::Indexed(index) => format!("{};5;{}", 38 + offset, index),
+        Color::Rgb(red, green, blue) => format!("{};2;{};{};{}", 38 + offset, red, green, blue),
+    }
+}
+
+/// private utility method mixing a foreground color over a background with an opacity
+fn blend(fg: (u8, u8, u8), bg: (u8, u8, u8), opacity: f64) -> (u8, u8, u8) {
+    let mix = |fg: u8, bg: u8| (fg as f64 * opacity + bg as f64 * (1.0 - opacity)).round() as u8;
+    (mix(fg.0, bg.0), mix(fg.1, bg.1), mix(fg.2, bg.2))
+}
+
+/// private utility method writing a color as #rrggbb
+fn hex(color: (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}
+
+/// private utility method escaping text for XML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ratatui::layout::Rect;
+    use ratatui::style::{Style, Stylize};
+
+    #[test]
+    fn test_screenshot() {
+        let mut frame = Buffer::empty(Rect::new(0, 0, 4, 2));
+        frame.set_string(0, 0, "A<", Style::new().red().bold());
+        frame.set_string(2, 0, "█▄", Style::new().fg(Color::Rgb(0, 128, 255)));
+        frame.set_string(0, 1, "⣿", Style::new().on_blue());
+
+        assert_eq!(
+            ansi(&frame),
+            "\x1b[0;1;31;49mA<\x1b[0;38;2;0;128;255;49m█▄\x1b[0m\n\
+             \x1b[0;39;44m⣿\x1b[0;39;49m   \x1b[0m\n"
+        );
+
+        let svg = svg(&frame);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"32\""));
+        assert!(svg.contains(
+            "<text x=\"0\" y=\"12\" textLength=\"16\" lengthAdjust=\"spacingAndGlyphs\" \
+             fill=\"#cd0000\" font-weight=\"bold\">A&lt;</text>"
+        ));
+        // the full and lower half blocks are drawn as rectangles, not text
+        assert!(
+            svg.contains("<rect x=\"16\" y=\"0\" width=\"8\" height=\"16\" fill=\"#0080ff\"/>")
+        );
+        assert!(svg.contains("<rect x=\"24\" y=\"8\" width=\"8\" height=\"8\" fill=\"#0080ff\"/>"));
+        // the blue background of the braille cell, then its eight dots
+        assert!(
+            svg.contains("<rect x=\"0\" y=\"16\" width=\"8\" height=\"16\" fill=\"#0000ee\"/>")
+        );
+        assert_eq!(coverage("⣿").map(|(parts, _)| parts.len()), Some(8));
+        assert_eq!(coverage("┌").map(|(parts, _)| parts.len()), Some(2));
+        assert_eq!(coverage("a"), None);
+
+        let bytes = png(&frame).unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (32, 32));
+        let pixel = |x: usize, y: usize| {
+            let index = 3 * (y * 32 + x);
+            (pixels[index], pixels[index + 1], pixels[index + 2])
+        };
+        assert_eq!(pixel(20, 2), (0, 128, 255));
+        // upper half of the lower half block left to the background
+        assert_eq!(pixel(28, 2), DEFAULT_BACKGROUND);
+        assert_eq!(pixel(28, 14), (0, 128, 255));
+
+        assert_eq!(
+            ScreenshotFormat::parse("svg").unwrap(),
+            ScreenshotFormat::Svg
+        );
+        assert!(ScreenshotFormat::parse("jpeg").is_err());
+        assert_eq!(rgb(Color::Indexed(196)), Some((255, 0, 0)));
+        assert_eq!(rgb(Color::Indexed(232)), Some((8, 8, 8)));
+    }
+}
@@ -0,0 +1,333 @@
+use crate::error::BookedError;
+use crate::pipeline::{BookHistory, Splatted};
+
+use rhai::{AST, Array, CallFnOptions, Dynamic, Engine, Map, Scope};
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Number of operations a hook may run before being interrupted, guarding against endless loops
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Number of points kept for every series plotted by scripts
+pub const SERIES_LENGTH: usize = 600;
+
+/// Points plotted and alerts raised by a script from a book and pipeline result
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScriptOutput {
+    pub points: Vec<(String, f64)>,
+    pub alerts: Vec<String>,
+}
+
+/// Rhai script hooked onto the books and pipeline results of every symbol, through its optional
+/// `on_book(book)` and `on_pipeline(result)` functions calling `plot(name, value)` and
+/// `alert(message)`
+pub struct Script {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    /// map bound to `this` in the hooks, kept between calls
+    state: Dynamic,
+    output: Arc<Mutex<ScriptOutput>>,
+}
+
+impl Script {
+    /// constructor, compiling a script file named after its stem
+    pub fn load(path: &Path) -> Result<Script, BookedError> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(message) => {
+                return Err(BookedError::Script(format!(
+                    "{}: {}",
+                    path.display(),
+                    message
+                )));
+            }
+        };
+        let name = match path.file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => path.display().to_string(),
+        };
+        Script::compile(name, &source)
+    }
+
+    /// constructor, compiling the source of a script
+    pub fn compile(name: String, source: &str) -> Result<Script, BookedError> {
+        let output = Arc::new(Mutex::new(ScriptOutput::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let plotted = output.clone();
+        engine.register_fn("plot", move |series: &str, value: f64| {
+            if let Ok(mut output) = plotted.lock() {
+                output.points.push((series.to_string(), value));
+            }
+        });
+        let plotted = output.clone();
+        engine.register_fn("plot", move |series: &str, value: i64| {
+            if let Ok(mut output) = plotted.lock() {
+                output.points.push((series.to_string(), value as f64));
+            }
+        });
+        let alerted = output.clone();
+        engine.register_fn("alert", move |message: &str| {
+            if let Ok(mut output) = alerted.lock() {
+                output.alerts.push(message.to_string());
+            }
+        });
+        let ast = match engine.compile(source) {
+            Ok(ast) => ast,
+            Err(message) => return Err(BookedError::Script(format!("{}: {}", name, message))),
+        };
+        Ok(Script {
+            name,
+            engine,
+            ast,
+            state: Dynamic::from_map(Map::new()),
+            output,
+        })
+    }
+
+    /// name of the script, used to label its series and errors
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// call the hooks defined by the script with the latest book of a symbol and the results of
+    /// its pipeline run, returning what they plotted and raised
+    pub fn run(&mut self, book: Dynamic, result: Dynamic) -> Result<ScriptOutput, BookedError> {
+        for (hook, argument) in [("on_book", book), ("on_pipeline", result)] {
+            if !self
+                .ast
+                .iter_functions()
+                .any(|function| function.name == hook && function.params.len() == 1)
+            {
+                continue;
+            }
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut self.state);
+            if let Err(message) = self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                &self.ast,
+                hook,
+                (argument,),
+            ) {
+                self.take_output();
+                return Err(BookedError::Script(format!(
+                    "{} in {}: {}",
+                    self.name, hook, message
+                )));
+            }
+        }
+        Ok(self.take_output())
+    }
+
+    /// private utility method emptying what the hooks plotted and raised so far
+    fn take_output(&self) -> ScriptOutput {
+        match self.output.lock() {
+            Ok(mut output) => std::mem::take(&mut *output),
+            Err(_) => ScriptOutput::default(),
+        }
+    }
+}
+
+/// Scripts run after every pipeline run, shared by the pipeline threads
+#[derive(Clone)]
+pub struct Scripts {
+    scripts: Arc<Mutex<Vec<Script>>>,
+}
+
+impl Scripts {
+    /// constructor, compiling every script file
+    pub fn load(paths: &[PathBuf]) -> Result<Scripts, BookedError> {
+        let scripts = paths
+            .iter()
+            .map(|path| Script::load(path))
+            .collect::<Result<Vec<_>, BookedError>>()?;
+        Ok(Scripts {
+            scripts: Arc::new(Mutex::new(scripts)),
+        })
+    }
+
+    /// run every script on the latest book of a symbol and the results of its pipeline run,
+    /// series being named after their script, along with the errors of the scripts that failed
+    pub async fn run(
+        &self,
+        symbol: &str,
+        history: &BookHistory,
+        splatted: &[Splatted],
+    ) -> (ScriptOutput, Vec<BookedError>) {
+        let ((time, asks), (_, bids)) = history.get_latest_book().await;
+        let levels = |levels: Vec<(f64, f64)>| {
+            levels
+                .into_iter()
+                .map(|(price, quantity)| {
+                    Dynamic::from_array(vec![Dynamic::from(price), Dynamic::from(quantity)])
+                })
+                .collect::<Array>()
+        };
+        let mut book = Map::new();
+        book.insert("symbol".into(), symbol.into());
+        book.insert("time".into(), time.into());
+        book.insert(
+            "asks".into(),
+            levels(
+                asks.iter()
+                    .map(|(price, quantity)| (price.value, *quantity))
+                    .collect(),
+            )
+            .into(),
+        );
+        book.insert(
+            "bids".into(),
+            levels(
+                bids.iter()
+                    .rev()
+                    .map(|(price, quantity)| (price.value, *quantity))
+                    .collect(),
+            )
+            .into(),
+        );
+        let result = pipeline_result(symbol, time, splatted);
+
+        let mut output = ScriptOutput::default();
+        let mut errors = vec![];
+        let Ok(mut scripts) = self.scripts.lock() else {
+            return (output, errors);
+        };
+        for script in scripts.iter_mut() {
+            match script.run(Dynamic::from_map(book.clone()), result.clone()) {
+                Ok(ran) => {
+                    output.points.extend(
+                        ran.points.into_iter().map(|(series, value)| {
+                            (format!("{}.{}", script.name(), series), value)
+                        }),
+                    );
+                    output.alerts.extend(ran.alerts);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        (output, errors)
+    }
+}
+
+/// private utility method converting the depth and volumes of a pipeline run into a script map
+fn pipeline_result(symbol: &str, time: i64, splatted: &[Splatted]) -> Dynamic {
+    let floats = |values: &[f64]| {
+        values
+            .iter()
+            .map(|value| Dynamic::from(*value))
+            .collect::<Array>()
+    };
+    let mut result = Map::new();
+    result.insert("symbol".into(), symbol.into());
+    result.insert("time".into(), time.into());
+    for splatted in splatted.iter() {
+        match splatted {
+            Splatted::Depth(depth) => {
+                let mut map = Map::new();
+                map.insert(
+                    "price_range".into(),
+                    floats(&[depth.price_range.0, depth.price_range.1]).into(),
+                );
+                map.insert("volumes".into(), floats(&depth.volumes).into());
+                result.insert("depth".into(), map.into());
+            }
+            Splatted::Volumes(volumes) => {
+                let mut map = Map::new();
+                map.insert("ask_volumes".into(), floats(&volumes.ask_volumes).into());
+                map.insert("bid_volumes".into(), floats(&volumes.bid_volumes).into());
+                map.insert(
+                    "imbalance_ratios".into(),
+                    floats(&volumes.imbalance_ratios).into(),
+                );
+                result.insert("volumes".into(), map.into());
+            }
+            _ => (),
+        }
+    }
+    Dynamic::from_map(result)
+}
+
+/// Series plotted by scripts for a symbol, the latest points of every series being kept
+#[derive(Clone, Debug, Default)]
+pub struct Indicators {
+    pub series: BTreeMap<String, VecDeque<(i64, f64)>>,
+}
+
+impl Indicators {
+    /// append points plotted at a time in seconds, forgetting the oldest beyond the series length
+    pub fn push(&mut self, time: i64, points: &[(String, f64)]) {
+        for (name, value) in points.iter() {
+            let series = self.series.entry(name.clone()).or_default();
+            series.push_back((time, *value));
+            if series.len() > SERIES_LENGTH {
+                series.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script() {
+        let source = r#"
+            fn on_book(book) {
+                let spread = book.asks[0][0] - book.bids[0][0];
+                plot("spread", spread);
+                if spread > 1.0 {
+                    alert(`Wide spread on ${book.symbol}`);
+                }
+            }
+
+            fn on_pipeline(result) {
+                this.runs = (this.runs ?? 0) + 1;
+                plot("runs", this.runs);
+            }
+        "#;
+        let mut script = Script::compile("spread".to_string(), source).unwrap();
+        let book = |ask: f64, bid: f64| {
+            let mut book = Map::new();
+            book.insert("symbol".into(), "BTC/USD".into());
+            book.insert(
+                "asks".into(),
+                Dynamic::from_array(vec![Dynamic::from_array(vec![ask.into(), 1.0.into()])]),
+            );
+            book.insert(
+                "bids".into(),
+                Dynamic::from_array(vec![Dynamic::from_array(vec![bid.into(), 1.0.into()])]),
+            );
+            Dynamic::from_map(book)
+        };
+        let result = pipeline_result("BTC/USD", 0, &[]);
+
+        let output = script.run(book(100.5, 100.0), result.clone()).unwrap();
+        assert_eq!(
+            output.points,
+            vec![("spread".to_string(), 0.5), ("runs".to_string(), 1.0)]
+        );
+        assert!(output.alerts.is_empty());
+        let output = script.run(book(102.0, 100.0), result.clone()).unwrap();
+        assert_eq!(output.points[1], ("runs".to_string(), 2.0));
+        assert_eq!(output.alerts, vec!["Wide spread on BTC/USD".to_string()]);
+
+        let mut looping =
+            Script::compile("looping".to_string(), "fn on_pipeline(result) { loop {} }").unwrap();
+        assert!(looping.run(book(1.0, 1.0), result).is_err());
+        assert!(Script::compile("broken".to_string(), "fn on_book(book) {").is_err());
+
+        let mut indicators = Indicators::default();
+        for time in 0..SERIES_LENGTH as i64 + 5 {
+            indicators.push(time, &[("spread.spread".to_string(), 1.0)]);
+        }
+        let series = &indicators.series["spread.spread"];
+        assert_eq!(series.len(), SERIES_LENGTH);
+        assert_eq!(series.front(), Some(&(5, 1.0)));
+    }
+}
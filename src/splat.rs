@@ -1,161 +1,922 @@
-use ndarray::Array2;
+use clap::ValueEnum;
+use ndarray::{Array, Array2, ArrayBase, ArrayViewMut1, Axis, DataMut, Dim, Dimension, Slice};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Splatting onto grids through wgpu compute shaders
+#[cfg(feature = "wgpu")]
+pub mod gpu;
+
+/// Number of neighbours the adaptive bandwidth of a sample reaches by default
+pub const ADAPTIVE_NEIGHBOURS: usize = 4;
+
+/// Shape of the kernel used for kernel density estimation
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum KernelShape {
+    /// normal distribution, smooth but with infinite support
+    Gaussian,
+    /// parabola vanishing at one bandwidth, optimal in the mean integrated squared error sense
+    Epanechnikov,
+    /// uniform weight within one bandwidth
+    TopHat,
+}
+
+impl KernelShape {
+    /// density at a value of the kernel centered on a mean
+    fn density_1d(&self, value: f64, deviation: &f64, mean: &f64) -> f64 {
+        let offset = (value - mean) / deviation;
+        match self {
+            KernelShape::Gaussian => gaussian_kernel_1d(value, deviation, mean),
+            KernelShape::Epanechnikov if offset.abs() <= 1.0 => {
+                0.75 * (1.0 - offset.powi(2)) / deviation
+            }
+            KernelShape::TopHat if offset.abs() <= 1.0 => 0.5 / deviation,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Width of the kernel used for kernel density estimation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bandwidth {
+    /// derived from the number of samples, half the mean spacing of samples spread over the range
+    Samples,
+    /// fixed width in units of the splatted axis
+    Absolute(f64),
+    /// fixed fraction of the splatted range
+    Fraction(f64),
+    /// per sample, the distance to its k-th nearest neighbour along the axis, narrow within
+    /// dense clusters and wide over sparse levels, never below a grid step
+    Adaptive(usize),
+}
+
+impl Bandwidth {
+    /// kernel deviation over a range of given width holding a number of samples along the axis
+    fn deviation(&self, width: f64, samples: f64) -> f64 {
+        match self {
+            Bandwidth::Samples | Bandwidth::Adaptive(_) => width / (2.0 * samples),
+            Bandwidth::Absolute(bandwidth) => *bandwidth,
+            Bandwidth::Fraction(fraction) => fraction * width,
+        }
+    }
+}
+
+/// Quantity the splatted grids hold
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Normalization {
+    /// sum of the kernel densities, depending on the grid resolution and bandwidth
+    #[default]
+    Density,
+    /// volume per grid cell, the volume of every sample within the grid being preserved
+    Mass,
+}
+
+/// Parameters of the kernel used for kernel density estimation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Kernel {
+    pub shape: KernelShape,
+    pub bandwidth: Bandwidth,
+    pub normalization: Normalization,
+    /// number of standard deviations beyond which the gaussian kernel is truncated, trading
+    /// accuracy of the tails for splat cost
+    pub truncation_in_deviations: f64,
+}
+
+impl Default for Kernel {
+    fn default() -> Kernel {
+        Kernel {
+            shape: KernelShape::Gaussian,
+            bandwidth: Bandwidth::Samples,
+            normalization: Normalization::Density,
+            truncation_in_deviations: 5.0,
+        }
+    }
+}
+
+impl Kernel {
+    /// number of deviations beyond which the kernel is ignored
+    fn radius_in_deviations(&self) -> f64 {
+        match self.shape {
+            KernelShape::Gaussian => self.truncation_in_deviations,
+            KernelShape::Epanechnikov | KernelShape::TopHat => 1.0,
+        }
+    }
+
+    /// number of grid steps on either side of a sample the kernel reaches
+    fn bloom(&self, deviation: f64, step: f64) -> usize {
+        (self.radius_in_deviations() * deviation / step)
+            .abs()
+            .round() as usize
+    }
+
+    /// whether the bandwidth varies from one sample to the next
+    fn is_adaptive(&self) -> bool {
+        matches!(self.bandwidth, Bandwidth::Adaptive(_))
+    }
+
+    /// same kernel for splatting along time, where absolute bandwidths in price units are
+    /// meaningless and samples are evenly spaced, both falling back to the sample count
+    pub fn along_time(&self) -> Kernel {
+        match self.bandwidth {
+            Bandwidth::Absolute(_) | Bandwidth::Adaptive(_) => Kernel {
+                bandwidth: Bandwidth::Samples,
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+}
+
+/// Enum of different methods for projecting a source sample onto a regular grid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplatMode {
+    /// gaussian kernel density estimation
+    Kernel(Kernel),
+    /// exact binning into the nearest grid point
+    Histogram,
+}
+
+impl SplatMode {
+    /// same mode for splatting along time
+    pub fn along_time(&self) -> SplatMode {
+        match self {
+            SplatMode::Kernel(kernel) => SplatMode::Kernel(kernel.along_time()),
+            SplatMode::Histogram => SplatMode::Histogram,
+        }
+    }
+}
 
 fn gaussian_kernel_1d(value: f64, deviation: &f64, mean: &f64) -> f64 {
     (1.0 / (deviation * (2.0 * std::f64::consts::PI).sqrt()))
         * (-(value - mean).powi(2) / (2.0 * deviation.powi(2))).exp()
 }
 
-/// method for gaussian kernel density estimation from a source sample onto regular 1D grid
-pub fn splat_1d(range: &(f64, f64), grid_size: usize, source: Vec<(f64, f64)>) -> Vec<f64> {
-    let mut support = vec![0.0; grid_size];
+/// private utility method for the number of samples of a source, only going through a copy of
+/// it when its length is not known upfront
+fn sample_count(source: &(impl Iterator + Clone)) -> usize {
+    match source.size_hint() {
+        (lower, Some(upper)) if lower == upper => lower,
+        _ => source.clone().count(),
+    }
+}
 
-    if source.len() == 0 {
-        return support;
+/// private utility method for the ndarray dimension of a grid with a number of points per axis
+fn grid_dimension<const N: usize>(grid_sizes: [usize; N]) -> Dim<[usize; N]>
+where
+    Dim<[usize; N]>: Dimension,
+{
+    let mut dimension = Dim::<[usize; N]>::zeros(N);
+    dimension.slice_mut().copy_from_slice(&grid_sizes);
+    dimension
+}
+
+/// private utility method for the kernel deviation and grid step along every axis of a grid
+/// holding a number of samples, the samples being taken as spread evenly over the axes
+fn axis_parameters<const N: usize>(
+    kernels: &[Kernel; N],
+    ranges: [&(f64, f64); N],
+    grid_sizes: [usize; N],
+    count: usize,
+) -> ([f64; N], [f64; N]) {
+    let samples = (count as f64).powf(1.0 / N as f64);
+    (
+        std::array::from_fn(|axis| {
+            kernels[axis]
+                .bandwidth
+                .deviation(ranges[axis].1 - ranges[axis].0, samples)
+        }),
+        std::array::from_fn(|axis| (ranges[axis].1 - ranges[axis].0) / (grid_sizes[axis] as f64)),
+    )
+}
+
+/// method for kernel density estimation from a source sample onto regular grid of any number of
+/// dimensions, the kernel being the product of one kernel per axis
+///
+/// this is the machinery behind splat_1d and splat_2d, for analytics over more axes such as
+/// time, price and order lifetime
+pub fn splat_nd<const N: usize>(
+    kernels: &[Kernel; N],
+    ranges: [&(f64, f64); N],
+    grid_sizes: [usize; N],
+    source: impl IntoIterator<Item = ([f64; N], f64), IntoIter: Clone>,
+) -> Array<f64, Dim<[usize; N]>>
+where
+    Dim<[usize; N]>: Dimension,
+{
+    let mut support = Array::zeros(grid_dimension(grid_sizes));
+    splat_nd_into(kernels, ranges, &mut support, source);
+    support
+}
+
+/// method for kernel density estimation from a source sample onto a caller provided regular grid
+/// of any number of dimensions, adding to the values it already holds
+pub fn splat_nd_into<const N: usize>(
+    kernels: &[Kernel; N],
+    ranges: [&(f64, f64); N],
+    support: &mut ArrayBase<impl DataMut<Elem = f64>, Dim<[usize; N]>>,
+    source: impl IntoIterator<Item = ([f64; N], f64), IntoIter: Clone>,
+) where
+    Dim<[usize; N]>: Dimension,
+{
+    let source = source.into_iter();
+    let count = sample_count(&source);
+
+    if count == 0 || support.is_empty() {
+        return;
     }
 
-    if range.0 == range.1 {
-        support.fill(1.0);
-        return support;
+    if ranges.iter().any(|range| range.0 == range.1) {
+        *support += 1.0;
+        return;
     }
 
-    let grid_size = support.len().clone();
-    let deviation = (range.1 - range.0) / (2.0 * source.len() as f64);
-    let step = (range.1 - range.0) / (grid_size as f64);
-    let kernel_bloom = (5.0 * deviation / step).round() as i64;
+    let grid_sizes: [usize; N] = std::array::from_fn(|axis| support.shape()[axis]);
+    let (deviations, steps) = axis_parameters(kernels, ranges, grid_sizes, count);
+    let adaptive = adaptive_axis_deviations(kernels, &source, deviations, steps);
 
-    let influence = |value: f64| {
-        let grid_point = ((value - range.0) / step).round() as i64;
-        let mut extent = (grid_point - kernel_bloom, grid_point + kernel_bloom + 1);
-        if extent.0 < 0 {
-            extent.0 = 0;
-        }
-        if extent.1 > grid_size as i64 {
-            extent.1 = grid_size as i64;
+    // the kernel being a product, its weights are those of every axis multiplied together
+    let mut axis_weights: [Vec<(usize, f64)>; N] = std::array::from_fn(|_| Vec::new());
+    'samples: for (sample, (keys, value)) in source.enumerate() {
+        for (axis, weights) in axis_weights.iter_mut().enumerate() {
+            let kernel = &kernels[axis];
+            let deviation = adaptive[axis]
+                .as_ref()
+                .map_or(deviations[axis], |deviations| deviations[sample]);
+            let bloom = kernel.bloom(deviation, steps[axis]) as i64;
+            let grid_point = ((keys[axis] - ranges[axis].0) / steps[axis]).round() as i64;
+            let extent = (
+                (grid_point - bloom).max(0),
+                (grid_point + bloom + 1).min(grid_sizes[axis] as i64),
+            );
+
+            weights.clear();
+            weights.extend((extent.0..extent.1).map(|index| {
+                let weight = kernel.shape.density_1d(
+                    steps[axis] * (index as f64) + ranges[axis].0,
+                    &deviation,
+                    &keys[axis],
+                );
+                (index as usize, weight)
+            }));
+            if kernel.normalization == Normalization::Mass {
+                let Some(nearest) = nearest_grid_point(keys[axis], ranges[axis], grid_sizes[axis])
+                else {
+                    continue 'samples;
+                };
+                match weights.iter().map(|(_, weight)| weight).sum::<f64>() {
+                    total if total > 0.0 => {
+                        weights.iter_mut().for_each(|(_, weight)| *weight /= total);
+                    }
+                    _ => {
+                        weights.clear();
+                        weights.push((nearest, 1.0));
+                    }
+                }
+            }
         }
-        extent
-    };
+        add_product(support, &axis_weights, value);
+    }
+}
 
-    for (key, value) in source.into_iter() {
-        let splat_extent = influence(key);
+/// private utility method for adding a value spread over the product of the weights of every
+/// axis to a grid
+fn add_product<const N: usize>(
+    support: &mut ArrayBase<impl DataMut<Elem = f64>, Dim<[usize; N]>>,
+    axis_weights: &[Vec<(usize, f64)>; N],
+    value: f64,
+) where
+    Dim<[usize; N]>: Dimension,
+{
+    if axis_weights.iter().any(Vec::is_empty) {
+        return;
+    }
 
-        let _ = ((splat_extent.0)..(splat_extent.1))
-            .map(|index| {
-                support[index as usize] +=
-                    value * gaussian_kernel_1d(step * (index as f64) + range.0, &deviation, &key)
-            })
-            .collect::<Vec<_>>();
+    let mut positions = [0; N];
+    let mut index = Dim::<[usize; N]>::zeros(N);
+    loop {
+        let mut weight = value;
+        for (axis, (weights, position)) in axis_weights.iter().zip(positions).enumerate() {
+            index[axis] = weights[position].0;
+            weight *= weights[position].1;
+        }
+        support[index] += weight;
+
+        // step through the weights like an odometer, the last axis turning fastest
+        let mut axis = N;
+        loop {
+            if axis == 0 {
+                return;
+            }
+            axis -= 1;
+            positions[axis] += 1;
+            if positions[axis] < axis_weights[axis].len() {
+                break;
+            }
+            positions[axis] = 0;
+        }
     }
+}
 
+/// method for kernel density estimation from a source sample onto regular 1D grid
+pub fn splat_1d(
+    kernel: &Kernel,
+    range: &(f64, f64),
+    grid_size: usize,
+    source: impl IntoIterator<Item = (f64, f64), IntoIter: Clone>,
+) -> Vec<f64> {
+    let mut support = vec![0.0; grid_size];
+    splat_1d_into(kernel, range, &mut support, source);
     support
 }
 
-fn gaussian_kernel_2d(values: (f64, f64), deviations: &(f64, f64), means: &(f64, f64)) -> f64 {
-    (1.0 / (deviations.0 * deviations.1 * 2.0 * std::f64::consts::PI))
-        * ((-1.0 / 2.0)
-            * (((values.0 - means.0) / deviations.0).powi(2)
-                + ((values.1 - means.1) / deviations.1).powi(2)))
-        .exp()
+/// method for kernel density estimation from a source sample onto a caller provided regular 1D
+/// grid, adding to the values it already holds
+pub fn splat_1d_into(
+    kernel: &Kernel,
+    range: &(f64, f64),
+    support: &mut [f64],
+    source: impl IntoIterator<Item = (f64, f64), IntoIter: Clone>,
+) {
+    splat_nd_into(
+        &[*kernel],
+        [range],
+        &mut ArrayViewMut1::from(support),
+        source.into_iter().map(|(key, value)| ([key], value)),
+    );
 }
 
-/// method for gaussian kernel density estimation from a source sample onto regular 2D grid
+/// method for kernel density estimation from a source sample onto regular 2D grid
+///
+/// absolute bandwidths are taken in units of the second (price) axis, the first (time) axis
+/// falling back to the sample count
 pub fn splat_2d(
+    kernel: &Kernel,
     ranges: (&(f64, f64), &(f64, f64)),
     grid_sizes: (usize, usize),
-    source: Vec<(f64, f64, f64)>,
+    source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
 ) -> Array2<f64> {
     let mut support = Array2::zeros(grid_sizes);
+    splat_2d_into(kernel, ranges, &mut support, source);
+    support
+}
 
-    if source.len() == 0 {
-        return support;
+/// method for kernel density estimation from a source sample onto regular 2D grid through a
+/// compute shader, falling back to splat_2d on the CPU without a GPU adapter to run it on
+#[cfg(feature = "wgpu")]
+pub fn splat_2d_gpu(
+    kernel: &Kernel,
+    ranges: (&(f64, f64), &(f64, f64)),
+    grid_sizes: (usize, usize),
+    source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
+) -> Array2<f64> {
+    let source = source.into_iter();
+    gpu::splat_2d(kernel, ranges, grid_sizes, source.clone())
+        .unwrap_or_else(|| splat_2d(kernel, ranges, grid_sizes, source))
+}
+
+/// method for kernel density estimation from a source sample onto a caller provided regular 2D
+/// grid, adding to the values it already holds
+pub fn splat_2d_into(
+    kernel: &Kernel,
+    ranges: (&(f64, f64), &(f64, f64)),
+    support: &mut Array2<f64>,
+    source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
+) {
+    splat_nd_into(
+        &[kernel.along_time(), *kernel],
+        [ranges.0, ranges.1],
+        support,
+        source
+            .into_iter()
+            .map(|(key0, key1, value)| ([key0, key1], value)),
+    );
+}
+
+/// private utility method for the adaptive deviations of every sample along the axes of adaptive
+/// bandwidth, None along the others
+///
+/// along an axis, the deviations adapt between the samples sharing their keys on the other axes,
+/// as the levels of a same book along price
+fn adaptive_axis_deviations<const N: usize>(
+    kernels: &[Kernel; N],
+    source: &(impl Iterator<Item = ([f64; N], f64)> + Clone),
+    deviations: [f64; N],
+    steps: [f64; N],
+) -> [Option<Vec<f64>>; N] {
+    if !kernels.iter().any(Kernel::is_adaptive) {
+        return std::array::from_fn(|_| None);
     }
 
-    if (ranges.0.0 == ranges.0.1) || (ranges.1.0 == ranges.1.1) {
-        support += 1.0;
-        return support;
+    let keys = source.clone().map(|(keys, _)| keys).collect::<Vec<_>>();
+    std::array::from_fn(|axis| {
+        let Bandwidth::Adaptive(neighbours) = kernels[axis].bandwidth else {
+            return None;
+        };
+        let mut groups: HashMap<[u64; N], Vec<usize>> = HashMap::new();
+        for (index, keys) in keys.iter().enumerate() {
+            let mut others = keys.map(f64::to_bits);
+            others[axis] = 0;
+            groups.entry(others).or_default().push(index);
+        }
+        let mut adapted = vec![deviations[axis]; keys.len()];
+        for indices in groups.values() {
+            let along = indices
+                .iter()
+                .map(|index| keys[*index][axis])
+                .collect::<Vec<_>>();
+            let group =
+                adaptive_deviations(&along, neighbours, deviations[axis], steps[axis].abs());
+            for (index, deviation) in indices.iter().zip(group) {
+                adapted[*index] = deviation;
+            }
+        }
+        Some(adapted)
+    })
+}
+
+/// private utility method for the adaptive deviations of samples, the distance from each to its
+/// k-th nearest neighbour bounded below by a floor, or a fallback for lone samples
+fn adaptive_deviations(keys: &[f64], neighbours: usize, fallback: f64, floor: f64) -> Vec<f64> {
+    let mut order = (0..keys.len()).collect::<Vec<_>>();
+    order.sort_by(|lhs, rhs| keys[*lhs].total_cmp(&keys[*rhs]));
+    let sorted = order.iter().map(|index| keys[*index]).collect::<Vec<_>>();
+
+    let mut deviations = vec![fallback; keys.len()];
+    for (position, index) in order.into_iter().enumerate() {
+        // walk outwards from the sample, taking the nearer of the next lower and higher keys
+        let (mut lower, mut upper) = (position, position + 1);
+        let mut distance = None;
+        for _ in 0..neighbours.max(1) {
+            let below = lower
+                .checked_sub(1)
+                .map(|below| sorted[position] - sorted[below]);
+            let above = sorted.get(upper).map(|above| above - sorted[position]);
+            distance = match (below, above) {
+                (Some(below), Some(above)) if below <= above => {
+                    lower -= 1;
+                    Some(below)
+                }
+                (_, Some(above)) => {
+                    upper += 1;
+                    Some(above)
+                }
+                (Some(below), None) => {
+                    lower -= 1;
+                    Some(below)
+                }
+                (None, None) => break,
+            };
+        }
+        if let Some(distance) = distance {
+            deviations[index] = distance.max(floor);
+        }
     }
+    deviations
+}
 
-    let grid_sizes = (support.shape()[0].clone(), support.shape()[1].clone());
-    let deviations = (
-        (ranges.0.1 - ranges.0.0) / (2.0 * (source.len() as f64).sqrt()),
-        (ranges.1.1 - ranges.1.0) / (2.0 * (source.len() as f64).sqrt()),
-    );
-    let steps = (
-        (ranges.0.1 - ranges.0.0) / (grid_sizes.0 as f64),
-        (ranges.1.1 - ranges.1.0) / (grid_sizes.1 as f64),
-    );
-    let kernel_blooms = (
-        (5.0 * deviations.0 / steps.0).round() as i64,
-        (5.0 * deviations.1 / steps.1).round() as i64,
-    );
+/// ratio of kernel evaluations of direct splatting to FFT operations above which splatting goes
+/// through binning and FFT convolution
+const FFT_SWITCH_RATIO: f64 = 4.0;
 
-    let influence = |value: (f64, f64)| {
-        let grid_point = (
-            ((value.0 - ranges.0.0) / steps.0).round() as i64,
-            ((value.1 - ranges.1.0) / steps.1).round() as i64,
-        );
-        let mut extents = (
-            (
-                grid_point.0 - kernel_blooms.0,
-                grid_point.0 + kernel_blooms.0 + 1,
-            ),
+/// private utility method deciding whether binning then convolving via FFT is cheaper than
+/// splatting every sample, from the number of kernel evaluations of direct splatting and the
+/// lengths of the convolved lines
+fn prefers_fft(evaluations: f64, lengths: &[(usize, usize)]) -> bool {
+    let operations = lengths.iter().fold(0.0, |acc, (count, length)| {
+        acc + (*count * length) as f64 * (*length as f64).log2().max(1.0)
+    });
+    evaluations > FFT_SWITCH_RATIO * operations
+}
+
+/// private utility method deciding whether splatting a number of samples onto a grid is cheaper
+/// via FFT, never the case for adaptive bandwidths as a convolution applies the same kernel to
+/// every sample
+fn prefers_fft_nd<const N: usize>(
+    kernels: &[Kernel; N],
+    ranges: [&(f64, f64); N],
+    grid_sizes: [usize; N],
+    samples: usize,
+) -> bool {
+    if samples == 0 || ranges.iter().any(|range| range.0 == range.1) || grid_sizes.contains(&0) {
+        return false;
+    }
+    if kernels.iter().any(Kernel::is_adaptive) {
+        return false;
+    }
+    let (deviations, steps) = axis_parameters(kernels, ranges, grid_sizes, samples);
+    let blooms: [usize; N] =
+        std::array::from_fn(|axis| kernels[axis].bloom(deviations[axis], steps[axis]));
+    let extended: [usize; N] = std::array::from_fn(|axis| grid_sizes[axis] + 2 * blooms[axis]);
+    let evaluations = blooms
+        .iter()
+        .fold(samples, |acc, bloom| acc * (2 * bloom + 1));
+    // every axis convolves all the lines of the padded grid running along it
+    let lengths = (0..N)
+        .map(|axis| {
             (
-                grid_point.1 - kernel_blooms.1,
-                grid_point.1 + kernel_blooms.1 + 1,
-            ),
-        );
+                extended.iter().product::<usize>() / extended[axis],
+                extended[axis] + 2 * blooms[axis],
+            )
+        })
+        .collect::<Vec<_>>();
+    prefers_fft(evaluations as f64, &lengths)
+}
+
+/// Linear convolution of signals of fixed length with a centered kernel through FFT
+struct FftConvolution {
+    forward: Arc<dyn Fft<f64>>,
+    inverse: Arc<dyn Fft<f64>>,
+    kernel_spectrum: Vec<Complex<f64>>,
+    bloom: usize,
+}
+
+impl FftConvolution {
+    /// constructor, the kernel holding 2 * bloom + 1 weights centered on its middle
+    fn new(weights: &[f64], signal_length: usize) -> FftConvolution {
+        let length = signal_length + weights.len() - 1;
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(length);
+        let inverse = planner.plan_fft_inverse(length);
 
-        if extents.0.0 < 0 {
-            extents.0.0 = 0;
+        let mut kernel_spectrum = vec![Complex::new(0.0, 0.0); length];
+        for (index, weight) in weights.iter().enumerate() {
+            kernel_spectrum[index].re = *weight / length as f64;
         }
-        if extents.1.0 < 0 {
-            extents.1.0 = 0;
+        forward.process(&mut kernel_spectrum);
+
+        FftConvolution {
+            forward,
+            inverse,
+            kernel_spectrum,
+            bloom: weights.len() / 2,
         }
+    }
 
-        if extents.0.1 > grid_sizes.0 as i64 {
-            extents.0.1 = grid_sizes.0 as i64;
+    /// for every position of a signal padded by the bloom on both sides, the sum of the kernel
+    /// weights falling back within the unpadded grid of a number of points
+    fn retained(&self, grid_size: usize) -> Vec<f64> {
+        let mut indicator = vec![0.0; grid_size + 2 * self.bloom];
+        indicator[self.bloom..self.bloom + grid_size].fill(1.0);
+        // the kernel being symmetric, correlating with it is convolving with it
+        self.apply(&mut indicator);
+        indicator
+    }
+
+    /// convolve a signal in place, keeping the part aligned with the signal
+    fn apply(&self, signal: &mut [f64]) {
+        let mut buffer = vec![Complex::new(0.0, 0.0); self.kernel_spectrum.len()];
+        for (index, value) in signal.iter().enumerate() {
+            buffer[index].re = *value;
+        }
+        self.forward.process(&mut buffer);
+        for (value, weight) in buffer.iter_mut().zip(self.kernel_spectrum.iter()) {
+            *value *= weight;
         }
-        if extents.1.1 > grid_sizes.1 as i64 {
-            extents.1.1 = grid_sizes.1 as i64;
+        self.inverse.process(&mut buffer);
+
+        for (index, value) in signal.iter_mut().enumerate() {
+            *value = buffer[index + self.bloom].re;
         }
+    }
+}
 
-        extents
-    };
+/// private utility method for the weights of a kernel sampled at every grid step within a bloom
+fn kernel_weights(kernel: &Kernel, deviation: f64, step: f64, bloom: usize) -> Vec<f64> {
+    (-(bloom as i64)..=bloom as i64)
+        .map(|offset| {
+            kernel
+                .shape
+                .density_1d(offset as f64 * step, &deviation, &0.0)
+        })
+        .collect()
+}
 
-    for (key0, key1, value) in source.into_iter() {
-        let splat_extents = influence((key0, key1));
+/// method for kernel density estimation from a source sample onto regular grid of any number of
+/// dimensions, binning samples onto the grid before convolving them with the separable kernel
+/// via FFT along every axis
+///
+/// samples are moved to their nearest grid point, which is faster than splat_nd on large
+/// sources at the price of an error of at most half a grid step on their position
+pub fn splat_nd_fft<const N: usize>(
+    kernels: &[Kernel; N],
+    ranges: [&(f64, f64); N],
+    grid_sizes: [usize; N],
+    source: impl IntoIterator<Item = ([f64; N], f64), IntoIter: Clone>,
+) -> Array<f64, Dim<[usize; N]>>
+where
+    Dim<[usize; N]>: Dimension,
+{
+    let source = source.into_iter();
+    let count = sample_count(&source);
 
-        for index0 in (splat_extents.0.0)..(splat_extents.0.1) {
-            for index1 in (splat_extents.1.0)..(splat_extents.1.1) {
-                match support.get_mut((index0 as usize, index1 as usize)) {
-                    Some(val) => {
-                        *val += value
-                            * gaussian_kernel_2d(
-                                (
-                                    steps.0 * (index0 as f64) + ranges.0.0,
-                                    steps.1 * (index1 as f64) + ranges.1.0,
-                                ),
-                                &deviations,
-                                &(key0, key1),
-                            )
+    if count == 0 || grid_sizes.contains(&0) {
+        return Array::zeros(grid_dimension(grid_sizes));
+    }
+
+    if ranges.iter().any(|range| range.0 == range.1) {
+        return Array::ones(grid_dimension(grid_sizes));
+    }
+
+    let (deviations, steps) = axis_parameters(kernels, ranges, grid_sizes, count);
+    let blooms: [usize; N] =
+        std::array::from_fn(|axis| kernels[axis].bloom(deviations[axis], steps[axis]));
+
+    let mut binned = Array::zeros(grid_dimension(std::array::from_fn(|axis| {
+        grid_sizes[axis] + 2 * blooms[axis]
+    })));
+    let mut index = Dim::<[usize; N]>::zeros(N);
+    'samples: for (keys, value) in source {
+        for (axis, kernel) in kernels.iter().enumerate() {
+            let grid_point = match kernel.normalization {
+                Normalization::Density => {
+                    ((keys[axis] - ranges[axis].0) / steps[axis]).round() as i64
+                }
+                Normalization::Mass => {
+                    match nearest_grid_point(keys[axis], ranges[axis], grid_sizes[axis]) {
+                        Some(grid_point) => grid_point as i64,
+                        None => continue 'samples,
+                    }
+                }
+            } + blooms[axis] as i64;
+            if grid_point < 0 {
+                continue 'samples;
+            }
+            index[axis] = grid_point as usize;
+        }
+        if let Some(binned_value) = binned.get_mut(index) {
+            *binned_value += value;
+        }
+    }
+
+    let mut line = Vec::new();
+    for (axis, kernel) in kernels.iter().enumerate() {
+        let convolution = FftConvolution::new(
+            &kernel_weights(kernel, deviations[axis], steps[axis], blooms[axis]),
+            binned.shape()[axis],
+        );
+        if kernel.normalization == Normalization::Mass {
+            let retained = convolution.retained(grid_sizes[axis]);
+            for mut lane in binned.lanes_mut(Axis(axis)) {
+                for (value, retained) in lane.iter_mut().zip(retained.iter()) {
+                    if *retained > 0.0 {
+                        *value /= retained;
                     }
-                    None => (),
                 }
             }
         }
+        for mut lane in binned.lanes_mut(Axis(axis)) {
+            line.clear();
+            line.extend(lane.iter());
+            convolution.apply(&mut line);
+            lane.iter_mut()
+                .zip(line.iter())
+                .for_each(|(value, convolved)| *value = *convolved);
+        }
+    }
+
+    binned
+        .slice_each_axis(|description| {
+            let axis = description.axis.index();
+            Slice::from(blooms[axis]..blooms[axis] + grid_sizes[axis])
+        })
+        .to_owned()
+}
+
+/// method for kernel density estimation from a source sample onto regular 1D grid, binning
+/// samples onto the grid before convolving them with the kernel via FFT
+///
+/// samples are moved to their nearest grid point, which is faster than splat_1d on large
+/// sources at the price of an error of at most half a grid step on their position
+pub fn splat_1d_fft(
+    kernel: &Kernel,
+    range: &(f64, f64),
+    grid_size: usize,
+    source: impl IntoIterator<Item = (f64, f64), IntoIter: Clone>,
+) -> Vec<f64> {
+    splat_nd_fft(
+        &[*kernel],
+        [range],
+        [grid_size],
+        source.into_iter().map(|(key, value)| ([key], value)),
+    )
+    .to_vec()
+}
+
+/// method for kernel density estimation from a source sample onto regular 2D grid, binning
+/// samples onto the grid before convolving them with the separable kernel via FFT
+///
+/// samples are moved to their nearest grid point, which is faster than splat_2d on large
+/// sources at the price of an error of at most half a grid step on their position
+pub fn splat_2d_fft(
+    kernel: &Kernel,
+    ranges: (&(f64, f64), &(f64, f64)),
+    grid_sizes: (usize, usize),
+    source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
+) -> Array2<f64> {
+    splat_nd_fft(
+        &[kernel.along_time(), *kernel],
+        [ranges.0, ranges.1],
+        [grid_sizes.0, grid_sizes.1],
+        source
+            .into_iter()
+            .map(|(key0, key1, value)| ([key0, key1], value)),
+    )
+}
+
+/// private utility method for finding the nearest grid point of a value, None if out of range
+fn nearest_grid_point(value: f64, range: &(f64, f64), grid_size: usize) -> Option<usize> {
+    if value < range.0.min(range.1) || value > range.0.max(range.1) {
+        return None;
+    }
+
+    let step = (range.1 - range.0) / (grid_size as f64);
+    let grid_point = ((value - range.0) / step).round() as usize;
+    Some(grid_point.min(grid_size - 1))
+}
+
+/// method for exact binning of a source sample onto regular grid of any number of dimensions
+pub fn bin_nd<const N: usize>(
+    ranges: [&(f64, f64); N],
+    grid_sizes: [usize; N],
+    source: impl IntoIterator<Item = ([f64; N], f64)>,
+) -> Array<f64, Dim<[usize; N]>>
+where
+    Dim<[usize; N]>: Dimension,
+{
+    let mut support = Array::zeros(grid_dimension(grid_sizes));
+    let mut source = source.into_iter().peekable();
+
+    if source.peek().is_none() || grid_sizes.contains(&0) {
+        return support;
+    }
+
+    if ranges.iter().any(|range| range.0 == range.1) {
+        support.fill(1.0);
+        return support;
+    }
+
+    let mut index = Dim::<[usize; N]>::zeros(N);
+    'samples: for (keys, value) in source {
+        for (axis, (key, range)) in keys.iter().zip(ranges).enumerate() {
+            match nearest_grid_point(*key, range, grid_sizes[axis]) {
+                Some(grid_point) => index[axis] = grid_point,
+                None => continue 'samples,
+            }
+        }
+        support[index] += value;
     }
 
     support
 }
 
+/// method for exact binning of a source sample onto regular 1D grid
+pub fn bin_1d(
+    range: &(f64, f64),
+    grid_size: usize,
+    source: impl IntoIterator<Item = (f64, f64)>,
+) -> Vec<f64> {
+    bin_nd(
+        [range],
+        [grid_size],
+        source.into_iter().map(|(key, value)| ([key], value)),
+    )
+    .to_vec()
+}
+
+/// method for exact binning of a source sample onto regular 2D grid
+pub fn bin_2d(
+    ranges: (&(f64, f64), &(f64, f64)),
+    grid_sizes: (usize, usize),
+    source: impl IntoIterator<Item = (f64, f64, f64)>,
+) -> Array2<f64> {
+    bin_nd(
+        [ranges.0, ranges.1],
+        [grid_sizes.0, grid_sizes.1],
+        source
+            .into_iter()
+            .map(|(key0, key1, value)| ([key0, key1], value)),
+    )
+}
+
+/// Iterator of a source whose number of samples was counted once, for the splat functions not
+/// to count it again
+#[derive(Clone)]
+struct Counted<I> {
+    inner: I,
+    remaining: usize,
+}
+
+impl<I: Iterator + Clone> Counted<I> {
+    /// constructor, counting the samples of the source
+    fn new(inner: I) -> Counted<I> {
+        let remaining = sample_count(&inner);
+        Counted { inner, remaining }
+    }
+}
+
+impl<I: Iterator> Iterator for Counted<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// method for projecting a source sample onto regular grid of any number of dimensions with a
+/// splat mode per axis, binning exactly as soon as one axis is given the histogram mode
+pub fn project_nd<const N: usize>(
+    modes: [SplatMode; N],
+    ranges: [&(f64, f64); N],
+    grid_sizes: [usize; N],
+    source: impl IntoIterator<Item = ([f64; N], f64), IntoIter: Clone>,
+) -> Array<f64, Dim<[usize; N]>>
+where
+    Dim<[usize; N]>: Dimension,
+{
+    let source = Counted::new(source.into_iter());
+    let mut kernels = [Kernel::default(); N];
+    for (kernel, mode) in kernels.iter_mut().zip(modes) {
+        match mode {
+            SplatMode::Kernel(axis_kernel) => *kernel = axis_kernel,
+            SplatMode::Histogram => return bin_nd(ranges, grid_sizes, source),
+        }
+    }
+    if prefers_fft_nd(&kernels, ranges, grid_sizes, source.remaining) {
+        splat_nd_fft(&kernels, ranges, grid_sizes, source)
+    } else {
+        splat_nd(&kernels, ranges, grid_sizes, source)
+    }
+}
+
+/// method for projecting a source sample onto regular 1D grid with the given splat mode
+pub fn project_1d(
+    mode: SplatMode,
+    range: &(f64, f64),
+    grid_size: usize,
+    source: impl IntoIterator<Item = (f64, f64), IntoIter: Clone>,
+) -> Vec<f64> {
+    project_nd(
+        [mode],
+        [range],
+        [grid_size],
+        source.into_iter().map(|(key, value)| ([key], value)),
+    )
+    .to_vec()
+}
+
+/// method for projecting a source sample onto regular 2D grid with the given splat mode
+///
+/// built with the wgpu feature, kernels are splatted on the GPU when one is available
+pub fn project_2d(
+    mode: SplatMode,
+    ranges: (&(f64, f64), &(f64, f64)),
+    grid_sizes: (usize, usize),
+    source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
+) -> Array2<f64> {
+    let source = source.into_iter();
+    #[cfg(feature = "wgpu")]
+    if let SplatMode::Kernel(kernel) = mode
+        && let Some(support) = gpu::splat_2d(&kernel, ranges, grid_sizes, source.clone())
+    {
+        return support;
+    }
+    project_nd(
+        [mode.along_time(), mode],
+        [ranges.0, ranges.1],
+        [grid_sizes.0, grid_sizes.1],
+        source
+            .into_iter()
+            .map(|(key0, key1, value)| ([key0, key1], value)),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::iter::zip;
+
     const TOLERANCE: f64 = 1e-2;
 
+    fn gaussian_kernel_2d(values: (f64, f64), deviations: &(f64, f64), means: &(f64, f64)) -> f64 {
+        (1.0 / (deviations.0 * deviations.1 * 2.0 * std::f64::consts::PI))
+            * ((-1.0 / 2.0)
+                * (((values.0 - means.0) / deviations.0).powi(2)
+                    + ((values.1 - means.1) / deviations.1).powi(2)))
+            .exp()
+    }
+
     #[test]
     fn test_splat_1d_empty_source() {
-        let splatted = splat_1d(&(0.0, 1.0), 10, Vec::new());
+        let splatted = splat_1d(&Kernel::default(), &(0.0, 1.0), 10, Vec::new());
 
         assert!(splatted.len() == 10);
 
@@ -166,7 +927,12 @@ mod tests {
 
     #[test]
     fn test_splat_1d_compact_range() {
-        let splatted = splat_1d(&(0.0, 0.0), 10, vec![(0.0, 0.0), (1.0, 1.0)]);
+        let splatted = splat_1d(
+            &Kernel::default(),
+            &(0.0, 0.0),
+            10,
+            vec![(0.0, 0.0), (1.0, 1.0)],
+        );
 
         assert!(splatted.len() == 10);
 
@@ -177,7 +943,7 @@ mod tests {
 
     #[test]
     fn test_splat_1d_one_source() {
-        let splatted = splat_1d(&(0.0, 1.0), 10, vec![(0.5, 1.0)]);
+        let splatted = splat_1d(&Kernel::default(), &(0.0, 1.0), 10, vec![(0.5, 1.0)]);
 
         assert!(splatted.len() == 10);
 
@@ -191,7 +957,7 @@ mod tests {
 
     #[test]
     fn test_splat_1d_volume() {
-        let splatted = splat_1d(&(0.0, 1.0), 20, vec![(0.5, 0.3)]);
+        let splatted = splat_1d(&Kernel::default(), &(0.0, 1.0), 20, vec![(0.5, 0.3)]);
 
         assert!(splatted.len() == 20);
 
@@ -207,6 +973,7 @@ mod tests {
     #[test]
     fn test_splat_1d_multiple_sources() {
         let splatted = splat_1d(
+            &Kernel::default(),
             &(0.0, 1.0),
             50,
             vec![(0.0, 0.4), (0.2, 0.3), (0.4, 1.0), (0.6, 0.8), (1.0, 0.2)],
@@ -229,7 +996,12 @@ mod tests {
 
     #[test]
     fn test_splat_2d_empty_source() {
-        let splatted = splat_2d((&(0.0, 1.0), &(0.0, 1.0)), (20, 10), Vec::new());
+        let splatted = splat_2d(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            (20, 10),
+            Vec::new(),
+        );
 
         assert!(splatted.shape()[0] == 20);
         assert!(splatted.shape()[1] == 10);
@@ -241,7 +1013,12 @@ mod tests {
 
     #[test]
     fn test_splat_2d_compact_horizontal_range() {
-        let splatted = splat_2d((&(0.0, 0.0), &(0.0, 1.0)), (20, 10), vec![(0.0, 0.0, 0.0)]);
+        let splatted = splat_2d(
+            &Kernel::default(),
+            (&(0.0, 0.0), &(0.0, 1.0)),
+            (20, 10),
+            vec![(0.0, 0.0, 0.0)],
+        );
 
         assert!(splatted.shape()[0] == 20);
         assert!(splatted.shape()[1] == 10);
@@ -253,7 +1030,12 @@ mod tests {
 
     #[test]
     fn test_splat_2d_compact_vertical_range() {
-        let splatted = splat_2d((&(0.0, 1.0), &(1.0, 1.0)), (20, 10), vec![(0.0, 0.0, 0.0)]);
+        let splatted = splat_2d(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(1.0, 1.0)),
+            (20, 10),
+            vec![(0.0, 0.0, 0.0)],
+        );
 
         assert!(splatted.shape()[0] == 20);
         assert!(splatted.shape()[1] == 10);
@@ -265,7 +1047,12 @@ mod tests {
 
     #[test]
     fn test_splat_2d_one_source() {
-        let splatted = splat_2d((&(0.0, 1.0), &(0.0, 1.0)), (10, 20), vec![(0.5, 0.5, 1.0)]);
+        let splatted = splat_2d(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            (10, 20),
+            vec![(0.5, 0.5, 1.0)],
+        );
 
         assert!(splatted.shape()[0] == 10);
         assert!(splatted.shape()[1] == 20);
@@ -288,7 +1075,12 @@ mod tests {
 
     #[test]
     fn test_splat_2d_volume() {
-        let splatted = splat_2d((&(1.0, 2.0), &(1.0, 2.0)), (10, 20), vec![(1.5, 1.5, 0.25)]);
+        let splatted = splat_2d(
+            &Kernel::default(),
+            (&(1.0, 2.0), &(1.0, 2.0)),
+            (10, 20),
+            vec![(1.5, 1.5, 0.25)],
+        );
 
         assert!(splatted.shape()[0] == 10);
         assert!(splatted.shape()[1] == 20);
@@ -313,6 +1105,7 @@ mod tests {
     #[test]
     fn test_splat_2d_multiple_sources() {
         let splatted = splat_2d(
+            &Kernel::default(),
             (&(1.0, 2.0), &(-1.0, 0.0)),
             (10, 20),
             vec![
@@ -345,4 +1138,448 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_bin_1d_empty_source() {
+        let binned = bin_1d(&(0.0, 1.0), 10, Vec::new());
+
+        assert_eq!(binned, vec![0.0; 10]);
+    }
+
+    #[test]
+    fn test_bin_1d_compact_range() {
+        let binned = bin_1d(&(0.0, 0.0), 10, vec![(0.0, 0.0), (1.0, 1.0)]);
+
+        assert_eq!(binned, vec![1.0; 10]);
+    }
+
+    #[test]
+    fn test_bin_1d_multiple_sources() {
+        let binned = bin_1d(
+            &(0.0, 1.0),
+            10,
+            vec![(0.0, 0.4), (0.21, 0.3), (0.19, 1.0), (1.0, 0.2), (1.5, 5.0)],
+        );
+
+        let mut expected = vec![0.0; 10];
+        expected[0] = 0.4;
+        expected[2] = 1.3;
+        expected[9] = 0.2;
+
+        assert_eq!(binned.len(), 10);
+        for (value, expected_value) in binned.into_iter().zip(expected) {
+            assert!((value - expected_value).abs() < TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_bin_2d_multiple_sources() {
+        let binned = bin_2d(
+            (&(1.0, 2.0), &(-1.0, 0.0)),
+            (10, 20),
+            vec![
+                (1.0, -1.0, 1.2),
+                (1.5, -0.5, 0.25),
+                (1.5, -0.5, 0.7),
+                (2.0, 0.0, 1.4),
+                (3.0, 0.0, 1.4),
+            ],
+        );
+
+        assert_eq!(binned.shape(), &[10, 20]);
+        assert!((binned[(0, 0)] - 1.2).abs() < TOLERANCE);
+        assert!((binned[(5, 10)] - 0.95).abs() < TOLERANCE);
+        assert!((binned[(9, 19)] - 1.4).abs() < TOLERANCE);
+        assert!((binned.sum() - 3.55).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_kernel_truncation() {
+        let source = (0..50)
+            .map(|index| (index as f64 / 50.0, 1.0 + (index % 7) as f64))
+            .collect::<Vec<_>>();
+        let narrow = Kernel {
+            truncation_in_deviations: 3.0,
+            ..Kernel::default()
+        };
+
+        let reference = splat_1d(&Kernel::default(), &(0.0, 1.0), 400, source.clone());
+        let truncated = splat_1d(&narrow, &(0.0, 1.0), 400, source.clone());
+        let difference = zip(reference.iter(), truncated.iter())
+            .fold(0.0, |acc, (lhs, rhs)| acc + (lhs - rhs).abs());
+        assert!(difference / reference.iter().sum::<f64>() < 0.01);
+
+        let source = source
+            .into_iter()
+            .map(|(key, value)| (key, 1.0 - key, value))
+            .collect::<Vec<_>>();
+        let reference = splat_2d(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            (100, 100),
+            source.clone(),
+        );
+        let truncated = splat_2d(&narrow, (&(0.0, 1.0), &(0.0, 1.0)), (100, 100), source);
+        assert!((&reference - &truncated).abs().sum() / reference.sum() < 0.01);
+    }
+
+    #[test]
+    fn test_kernel_shapes() {
+        for shape in [
+            KernelShape::Gaussian,
+            KernelShape::Epanechnikov,
+            KernelShape::TopHat,
+        ] {
+            let kernel = Kernel {
+                shape,
+                bandwidth: Bandwidth::Fraction(0.1),
+                ..Kernel::default()
+            };
+            let splatted = splat_1d(&kernel, &(0.0, 1.0), 1000, vec![(0.5, 2.0)]);
+            assert!((splatted.iter().sum::<f64>() / 1000.0 - 2.0).abs() < 1e-2);
+
+            let splatted = splat_2d(
+                &kernel,
+                (&(0.0, 1.0), &(0.0, 1.0)),
+                (200, 200),
+                vec![(0.5, 0.5, 2.0)],
+            );
+            assert!((splatted.sum() / 40000.0 - 2.0).abs() < 0.15);
+        }
+
+        let kernel = Kernel {
+            shape: KernelShape::TopHat,
+            bandwidth: Bandwidth::Absolute(0.045),
+            ..Kernel::default()
+        };
+        let splatted = splat_1d(&kernel, &(0.0, 1.0), 100, vec![(0.5, 1.0)]);
+        assert_eq!(splatted.iter().filter(|value| **value > 0.0).count(), 9);
+        assert!((splatted[50] - 0.5 / 0.045).abs() < TOLERANCE);
+        assert_eq!(splatted[45], 0.0);
+    }
+
+    #[test]
+    fn test_bandwidth_deviation() {
+        assert_eq!(Bandwidth::Samples.deviation(2.0, 4.0), 0.25);
+        assert_eq!(Bandwidth::Absolute(0.3).deviation(2.0, 4.0), 0.3);
+        assert_eq!(Bandwidth::Fraction(0.1).deviation(2.0, 4.0), 0.2);
+        assert_eq!(
+            Kernel {
+                bandwidth: Bandwidth::Absolute(0.3),
+                ..Kernel::default()
+            }
+            .along_time()
+            .bandwidth,
+            Bandwidth::Samples
+        );
+    }
+
+    #[test]
+    fn test_adaptive_bandwidth() {
+        assert_eq!(
+            adaptive_deviations(&[0.5, 0.0, 0.125, 0.1875], 1, 0.3, 0.01),
+            vec![0.3125, 0.125, 0.0625, 0.0625]
+        );
+        assert_eq!(
+            adaptive_deviations(&[0.5, 0.0, 0.125, 0.1875], 2, 0.3, 0.01),
+            vec![0.375, 0.1875, 0.125, 0.1875]
+        );
+        // duplicated keys are kept a grid step wide, lone keys fall back
+        assert_eq!(
+            adaptive_deviations(&[0.2, 0.2], 1, 0.3, 0.01),
+            vec![0.01, 0.01]
+        );
+        assert_eq!(adaptive_deviations(&[0.2], 4, 0.3, 0.01), vec![0.3]);
+
+        let kernel = Kernel {
+            bandwidth: Bandwidth::Adaptive(1),
+            ..Kernel::default()
+        };
+        assert!(!prefers_fft_nd(&[kernel], [&(0.0, 1.0)], [100], 10_000));
+        assert_eq!(kernel.along_time().bandwidth, Bandwidth::Samples);
+
+        // a dense cluster stays sharp while a sparse level is spread wide
+        let source = vec![(0.2, 1.0), (0.21, 1.0), (0.22, 1.0), (0.8, 1.0)];
+        let splatted = splat_1d(&kernel, &(0.0, 1.0), 100, source.clone());
+        let cluster = [0.2, 0.21, 0.22]
+            .map(|mean| gaussian_kernel_1d(0.21, &0.01, &mean))
+            .iter()
+            .sum::<f64>();
+        assert!((splatted[21] - cluster).abs() / cluster < TOLERANCE);
+        assert!((splatted[80] - gaussian_kernel_1d(0.8, &0.58, &0.8)).abs() < TOLERANCE);
+        assert!(splatted[21] > 10.0 * splatted[80]);
+
+        let splatted = splat_2d(
+            &kernel,
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            (10, 100),
+            source
+                .iter()
+                .map(|(price, quantity)| (0.5, *price, *quantity)),
+        );
+        assert!(splatted[(5, 21)] > 10.0 * splatted[(5, 80)]);
+    }
+
+    #[test]
+    fn test_mass_normalization() {
+        let source = vec![(0.0, 0.4), (0.3, 1.0), (0.5, 0.8), (0.98, 0.2), (1.5, 3.0)];
+        for bandwidth in [Bandwidth::Fraction(0.02), Bandwidth::Fraction(0.1)] {
+            let kernel = Kernel {
+                bandwidth,
+                normalization: Normalization::Mass,
+                ..Kernel::default()
+            };
+            // the sample beyond the range is left out, those near the edges kept whole
+            for grid_size in [50, 400] {
+                let direct = splat_1d(&kernel, &(0.0, 1.0), grid_size, source.clone());
+                assert!((direct.iter().sum::<f64>() - 2.4).abs() < 1e-9);
+                let fft = splat_1d_fft(&kernel, &(0.0, 1.0), grid_size, source.clone());
+                assert!((fft.iter().sum::<f64>() - 2.4).abs() < 1e-9);
+            }
+
+            let source = source
+                .iter()
+                .map(|(price, volume)| (0.5, *price, *volume))
+                .collect::<Vec<_>>();
+            let direct = splat_2d(
+                &kernel,
+                (&(0.0, 1.0), &(0.0, 1.0)),
+                (20, 100),
+                source.clone(),
+            );
+            assert!((direct.sum() - 2.4).abs() < 1e-9);
+            let fft = splat_2d_fft(&kernel, (&(0.0, 1.0), &(0.0, 1.0)), (20, 100), source);
+            assert!((fft.sum() - 2.4).abs() < 1e-9);
+        }
+
+        // magnitudes no longer depend on the resolution
+        let kernel = Kernel {
+            bandwidth: Bandwidth::Absolute(0.05),
+            normalization: Normalization::Mass,
+            ..Kernel::default()
+        };
+        let coarse = splat_1d(&kernel, &(0.0, 1.0), 100, vec![(0.5, 1.0)]);
+        let fine = splat_1d(&kernel, &(0.0, 1.0), 200, vec![(0.5, 1.0)]);
+        assert!((coarse[50] - 2.0 * fine[100]).abs() < TOLERANCE);
+
+        let kernel = Kernel {
+            bandwidth: Bandwidth::Adaptive(1),
+            normalization: Normalization::Mass,
+            ..Kernel::default()
+        };
+        let splatted = splat_1d(&kernel, &(0.0, 1.0), 100, source);
+        assert!((splatted.iter().sum::<f64>() - 2.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_splat_streaming() {
+        let books = [
+            (0.25, vec![(0.4, 1.0), (0.5, 2.0)]),
+            (0.75, vec![(0.45, 0.5)]),
+        ];
+        // samples flattened from nested books, their count unknown upfront
+        let source = books.iter().flat_map(|(time, levels)| {
+            levels
+                .iter()
+                .map(move |(price, volume)| (*time, *price, *volume))
+        });
+        assert_eq!(source.size_hint().1, None);
+        assert_eq!(sample_count(&source), 3);
+        let collected = source.clone().collect::<Vec<_>>();
+        for mode in [
+            SplatMode::Kernel(Kernel::default()),
+            SplatMode::Kernel(Kernel {
+                bandwidth: Bandwidth::Adaptive(1),
+                ..Kernel::default()
+            }),
+            SplatMode::Histogram,
+        ] {
+            let ranges = (&(0.0, 1.0), &(0.0, 1.0));
+            assert_eq!(
+                project_2d(mode, ranges, (10, 20), source.clone()),
+                project_2d(mode, ranges, (10, 20), collected.clone())
+            );
+        }
+
+        // caller provided buffers are added to
+        let mut support = vec![1.0; 10];
+        splat_1d_into(&Kernel::default(), &(0.0, 1.0), &mut support, [(0.5, 1.0)]);
+        let splatted = splat_1d(&Kernel::default(), &(0.0, 1.0), 10, [(0.5, 1.0)]);
+        for (summed, splatted) in zip(support, splatted) {
+            assert_eq!(summed, 1.0 + splatted);
+        }
+        let mut support = Array2::zeros((10, 20));
+        splat_2d_into(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            &mut support,
+            collected.iter().copied(),
+        );
+        splat_2d_into(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            &mut support,
+            collected.iter().copied(),
+        );
+        let splatted = splat_2d(
+            &Kernel::default(),
+            (&(0.0, 1.0), &(0.0, 1.0)),
+            (10, 20),
+            collected,
+        );
+        assert!((&support - &(2.0 * &splatted)).abs().sum() < 1e-9);
+    }
+
+    #[test]
+    fn test_splat_nd() {
+        let source = vec![([0.5, 0.25, 0.75], 2.0), ([0.25, 0.5, 0.5], 1.0)];
+        let kernel = Kernel {
+            bandwidth: Bandwidth::Fraction(0.1),
+            truncation_in_deviations: 10.0,
+            ..Kernel::default()
+        };
+        let ranges = [&(0.0, 1.0), &(0.0, 1.0), &(0.0, 1.0)];
+
+        // the kernel is the product of the kernels along every axis
+        let splatted = splat_nd(&[kernel; 3], ranges, [8, 16, 8], source.clone());
+        assert_eq!(splatted.shape(), &[8, 16, 8]);
+        for ((index0, index1, index2), value) in splatted.indexed_iter() {
+            let point = [
+                index0 as f64 / 8.0,
+                index1 as f64 / 16.0,
+                index2 as f64 / 8.0,
+            ];
+            let expected = source
+                .iter()
+                .map(|(keys, volume)| {
+                    zip(point, keys).fold(*volume, |acc, (value, key)| {
+                        acc * gaussian_kernel_1d(value, &0.1, key)
+                    })
+                })
+                .sum::<f64>();
+            assert!((value - expected).abs() < 1e-9);
+        }
+
+        // samples on grid points are not moved by binning, so both paths should agree
+        let fft = splat_nd_fft(&[kernel; 3], ranges, [8, 16, 8], source.clone());
+        assert!((&fft - &splatted).abs().sum() < 1e-9);
+
+        let kernel = Kernel {
+            normalization: Normalization::Mass,
+            ..kernel
+        };
+        let mass = splat_nd(&[kernel; 3], ranges, [8, 16, 8], source.clone());
+        assert!((mass.sum() - 3.0).abs() < 1e-9);
+        let binned = bin_nd(ranges, [8, 16, 8], source.clone());
+        assert_eq!(binned[(4, 4, 6)], 2.0);
+        assert_eq!(binned[(2, 8, 4)], 1.0);
+        assert_eq!(binned.sum(), 3.0);
+        assert_eq!(
+            project_nd([SplatMode::Kernel(kernel); 3], ranges, [8, 16, 8], source),
+            mass
+        );
+
+        // the 2D splat goes through the same machinery
+        let source = [(0.5, 0.25, 2.0), (0.25, 0.5, 1.0)];
+        assert_eq!(
+            splat_2d(&kernel, (&(0.0, 1.0), &(0.0, 1.0)), (10, 20), source),
+            splat_nd(
+                &[kernel.along_time(), kernel],
+                [&(0.0, 1.0), &(0.0, 1.0)],
+                [10, 20],
+                source.map(|(key0, key1, value)| ([key0, key1], value))
+            )
+        );
+    }
+
+    #[test]
+    fn test_project_modes() {
+        let source = vec![(0.5, 1.0)];
+
+        assert_eq!(
+            project_1d(
+                SplatMode::Kernel(Kernel::default()),
+                &(0.0, 1.0),
+                10,
+                source.clone()
+            ),
+            splat_1d(&Kernel::default(), &(0.0, 1.0), 10, source.clone())
+        );
+        assert_eq!(
+            project_1d(SplatMode::Histogram, &(0.0, 1.0), 10, source.clone()),
+            bin_1d(&(0.0, 1.0), 10, source)
+        );
+    }
+
+    #[test]
+    fn test_splat_1d_fft() {
+        // samples on grid points are not moved by binning, so both paths should agree
+        let source = (0..20)
+            .map(|index| (index as f64 * 0.5 / 10.0, 1.0 + (index % 3) as f64))
+            .collect::<Vec<_>>();
+
+        for shape in [
+            KernelShape::Gaussian,
+            KernelShape::Epanechnikov,
+            KernelShape::TopHat,
+        ] {
+            let kernel = Kernel {
+                shape,
+                bandwidth: Bandwidth::Fraction(0.03),
+                ..Kernel::default()
+            };
+            let direct = splat_1d(&kernel, &(0.0, 1.0), 20, source.clone());
+            let fft = splat_1d_fft(&kernel, &(0.0, 1.0), 20, source.clone());
+            for (lhs, rhs) in zip(direct.iter(), fft.iter()) {
+                assert!((lhs - rhs).abs() < 1e-9);
+            }
+        }
+
+        assert_eq!(
+            splat_1d_fft(&Kernel::default(), &(0.0, 1.0), 5, vec![]),
+            vec![0.0; 5]
+        );
+    }
+
+    #[test]
+    fn test_splat_2d_fft() {
+        let source = (0..10)
+            .flat_map(|time| {
+                (0..8)
+                    .map(move |price| (time as f64 / 10.0, price as f64 / 8.0, 1.0 + price as f64))
+            })
+            .collect::<Vec<_>>();
+        let kernel = Kernel {
+            bandwidth: Bandwidth::Fraction(0.1),
+            ..Kernel::default()
+        };
+        let ranges = (&(0.0, 1.0), &(0.0, 1.0));
+
+        let direct = splat_2d(&kernel, ranges, (10, 8), source.clone());
+        let fft = splat_2d_fft(&kernel, ranges, (10, 8), source);
+        for (lhs, rhs) in zip(direct.iter(), fft.iter()) {
+            assert!((lhs - rhs).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_prefers_fft() {
+        let kernel = Kernel {
+            bandwidth: Bandwidth::Fraction(0.05),
+            ..Kernel::default()
+        };
+        assert!(!prefers_fft_nd(&[kernel], [&(0.0, 1.0)], [100], 10));
+        assert!(prefers_fft_nd(&[kernel], [&(0.0, 1.0)], [100], 10_000));
+        assert!(!prefers_fft_nd(&[kernel], [&(1.0, 1.0)], [100], 10_000));
+        assert!(!prefers_fft_nd(
+            &[Kernel::default()],
+            [&(0.0, 1.0)],
+            [100],
+            0
+        ));
+        let kernels = [kernel.along_time(), kernel];
+        let ranges = [&(0.0, 1.0), &(0.0, 1.0)];
+        assert!(!prefers_fft_nd(&kernels, ranges, [370, 200], 100));
+        assert!(prefers_fft_nd(&kernels, ranges, [370, 200], 300_000));
+    }
 }
@@ -0,0 +1,425 @@
+use super::{
+    Kernel, KernelShape, Normalization, adaptive_axis_deviations, axis_parameters,
+    nearest_grid_point, sample_count,
+};
+
+use ndarray::Array2;
+use tracing::{info, warn};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+use std::sync::{OnceLock, mpsc};
+
+/// Number of invocations of a workgroup of the splat shader
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Number of 32 bit words of a sample as laid out by the splat shader, padding included
+const SAMPLE_WORDS: usize = 10;
+
+/// Number of 32 bit words of the parameters of the splat shader, padded for a uniform buffer
+const PARAMETERS_WORDS: usize = 8;
+
+/// Device the splat shader runs on, set up on first use, None without a hardware GPU adapter able
+/// to run compute shaders
+static SPLATTER: OnceLock<Option<Splatter>> = OnceLock::new();
+
+/// private utility method for the little endian bytes of 32 bit words
+fn bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// private utility method for the index of a kernel shape in the splat shader
+fn shape_index(shape: KernelShape) -> u32 {
+    match shape {
+        KernelShape::Gaussian => 0,
+        KernelShape::Epanechnikov => 1,
+        KernelShape::TopHat => 2,
+    }
+}
+
+/// GPU device along with the compiled splat shader
+struct Splatter {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl Splatter {
+    /// constructor, None without an adapter able to run compute shaders, or with a software one
+    /// unless accepted, software rasterizers splatting slower than the CPU does directly
+    fn new(software: bool) -> Option<Splatter> {
+        pollster::block_on(async {
+            let instance = wgpu::Instance::default();
+            let adapter = match instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    ..Default::default()
+                })
+                .await
+            {
+                Ok(adapter) => adapter,
+                Err(error) => {
+                    warn!(%error, "No GPU adapter, splatting on the CPU");
+                    return None;
+                }
+            };
+            let info = adapter.get_info();
+            if info.device_type == wgpu::DeviceType::Cpu && !software {
+                warn!(
+                    adapter = info.name,
+                    "Software GPU adapter, splatting on the CPU"
+                );
+                return None;
+            }
+            if !adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+            {
+                warn!(
+                    adapter = info.name,
+                    "GPU adapter without compute shaders, splatting on the CPU"
+                );
+                return None;
+            }
+            let (device, queue) = match adapter
+                .request_device(&wgpu::DeviceDescriptor {
+                    label: Some("splat"),
+                    required_limits: adapter.limits(),
+                    ..Default::default()
+                })
+                .await
+            {
+                Ok(device) => device,
+                Err(error) => {
+                    warn!(%error, adapter = info.name, "Could not open the GPU, splatting on the CPU");
+                    return None;
+                }
+            };
+
+            let module = device.create_shader_module(wgpu::include_wgsl!("splat.wgsl"));
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("splat"),
+                layout: None,
+                module: &module,
+                entry_point: Some("splat"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+            info!(adapter = info.name, backend = %info.backend, "Splatting on the GPU");
+
+            Some(Splatter {
+                device,
+                queue,
+                pipeline,
+            })
+        })
+    }
+
+    /// splat samples laid out as the shader reads them onto a grid, None if the buffers exceed
+    /// the limits of the device
+    fn run(
+        &self,
+        grid_sizes: (usize, usize),
+        shapes: [u32; 2],
+        samples: &[u32],
+    ) -> Option<Vec<f32>> {
+        let count = samples.len() / SAMPLE_WORDS;
+        let cells = grid_sizes.0 * grid_sizes.1;
+        let limits = self.device.limits();
+        let largest = (4 * samples.len()).max(4 * cells) as u64;
+        let workgroups = (count as u32).div_ceil(WORKGROUP_SIZE);
+        if largest
+            > limits
+                .max_storage_buffer_binding_size
+                .min(limits.max_buffer_size)
+            || workgroups.div_ceil(limits.max_compute_workgroups_per_dimension)
+                > limits.max_compute_workgroups_per_dimension
+        {
+            return None;
+        }
+
+        let mut parameters = [0; PARAMETERS_WORDS];
+        parameters[..5].copy_from_slice(&[
+            grid_sizes.0 as u32,
+            grid_sizes.1 as u32,
+            shapes[0],
+            shapes[1],
+            count as u32,
+        ]);
+        let parameters = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("splat parameters"),
+            contents: &bytes(&parameters),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let samples = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("splat samples"),
+            contents: &bytes(samples),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let size = 4 * cells as u64;
+        let support = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("splat support"),
+            contents: &vec![0; size as usize],
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("splat readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("splat"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: parameters.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: samples.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: support.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("splat"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("splat"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            // workgroups beyond the limit of a dimension wrap onto the next row
+            let columns = workgroups.min(limits.max_compute_workgroups_per_dimension);
+            pass.dispatch_workgroups(columns, workgroups.div_ceil(columns), 1);
+        }
+        encoder.copy_buffer_to_buffer(&support, 0, &readback, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |mapped| {
+                let _ = sender.send(mapped);
+            });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).ok()?;
+        receiver.recv().ok()?.ok()?;
+        let splatted = readback
+            .slice(..)
+            .get_mapped_range()
+            .chunks_exact(4)
+            .map(|word| f32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        readback.unmap();
+        Some(splatted)
+    }
+
+    /// kernel density estimation from a source sample onto regular 2D grid on this device, None
+    /// for degenerate grids or for buffers exceeding its limits
+    fn splat_2d(
+        &self,
+        kernel: &Kernel,
+        ranges: (&(f64, f64), &(f64, f64)),
+        grid_sizes: (usize, usize),
+        source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
+    ) -> Option<Array2<f64>> {
+        let source = source
+            .into_iter()
+            .map(|(key0, key1, value)| ([key0, key1], value));
+        let count = sample_count(&source);
+        let ranges = [ranges.0, ranges.1];
+        let sizes = [grid_sizes.0, grid_sizes.1];
+        if count == 0
+            || sizes.contains(&0)
+            || ranges.iter().any(|range| range.0 == range.1)
+            || sizes.iter().any(|size| *size > i32::MAX as usize)
+        {
+            return None;
+        }
+
+        let kernels = [kernel.along_time(), *kernel];
+        let (deviations, steps) = axis_parameters(&kernels, ranges, sizes, count);
+        let adaptive = adaptive_axis_deviations(&kernels, &source, deviations, steps);
+
+        // every sample is handed over as its position, kernel width and normalization along every
+        // axis, the shader evaluating the kernels over the cells they reach
+        let mut samples = Vec::with_capacity(count * SAMPLE_WORDS);
+        'samples: for (sample, (keys, value)) in source.enumerate() {
+            let mut words = [0; SAMPLE_WORDS];
+            let mut scale = value;
+            for (axis, kernel) in kernels.iter().enumerate() {
+                let deviation = adaptive[axis]
+                    .as_ref()
+                    .map_or(deviations[axis], |deviations| deviations[sample]);
+                let position = (keys[axis] - ranges[axis].0) / steps[axis];
+                let bloom = kernel.bloom(deviation, steps[axis]) as i64;
+                let grid_point = (position.round() as i64).clamp(-bloom - 1, sizes[axis] as i64);
+                let weight = |index: i64| {
+                    kernel.shape.density_1d(
+                        steps[axis] * (index as f64) + ranges[axis].0,
+                        &deviation,
+                        &keys[axis],
+                    )
+                };
+                let mut extent = (
+                    (grid_point - bloom).max(0),
+                    (grid_point + bloom + 1).min(sizes[axis] as i64),
+                );
+                let mut inverse_deviation = steps[axis] / deviation;
+                match kernel.normalization {
+                    Normalization::Density => scale /= deviation,
+                    Normalization::Mass => {
+                        let Some(nearest) =
+                            nearest_grid_point(keys[axis], ranges[axis], sizes[axis])
+                        else {
+                            continue 'samples;
+                        };
+                        match (extent.0..extent.1).map(weight).sum::<f64>() {
+                            total if total > 0.0 => scale /= deviation * total,
+                            _ => {
+                                // all of the value goes to the nearest grid point, where the kernel
+                                // is evaluated at its center
+                                extent = (nearest as i64, nearest as i64 + 1);
+                                inverse_deviation = 0.0;
+                                scale /= kernel.shape.density_1d(0.0, &1.0, &0.0);
+                            }
+                        }
+                    }
+                }
+                // the cells at the edges of compact kernels are settled here in double precision,
+                // the shader only evaluating the kernel within the extent
+                if inverse_deviation != 0.0 {
+                    while extent.0 < extent.1 && weight(extent.0) == 0.0 {
+                        extent.0 += 1;
+                    }
+                    while extent.0 < extent.1 && weight(extent.1 - 1) == 0.0 {
+                        extent.1 -= 1;
+                    }
+                }
+                if extent.0 >= extent.1 {
+                    continue 'samples;
+                }
+                words[axis] = (position as f32).to_bits();
+                words[2 + axis] = (inverse_deviation as f32).to_bits();
+                words[4 + axis] = extent.0 as u32;
+                words[6 + axis] = extent.1 as u32;
+            }
+            words[8] = (scale as f32).to_bits();
+            samples.extend_from_slice(&words);
+        }
+
+        let mut support = Array2::zeros(grid_sizes);
+        if samples.is_empty() {
+            return Some(support);
+        }
+        let splatted = self.run(
+            grid_sizes,
+            [shape_index(kernels[0].shape), shape_index(kernels[1].shape)],
+            &samples,
+        )?;
+        support
+            .iter_mut()
+            .zip(splatted)
+            .for_each(|(value, splatted)| *value = splatted as f64);
+        Some(support)
+    }
+}
+
+/// method for kernel density estimation from a source sample onto regular 2D grid through a
+/// compute shader, with the same kernels as splat_2d
+///
+/// None without a hardware GPU adapter, for degenerate grids or for buffers exceeding the limits
+/// of the device, leaving the splat to the CPU
+pub fn splat_2d(
+    kernel: &Kernel,
+    ranges: (&(f64, f64), &(f64, f64)),
+    grid_sizes: (usize, usize),
+    source: impl IntoIterator<Item = (f64, f64, f64), IntoIter: Clone>,
+) -> Option<Array2<f64>> {
+    SPLATTER
+        .get_or_init(|| Splatter::new(false))
+        .as_ref()?
+        .splat_2d(kernel, ranges, grid_sizes, source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::splat::{ADAPTIVE_NEIGHBOURS, Bandwidth, splat_2d_gpu};
+
+    use std::iter::zip;
+
+    const TOLERANCE: f64 = 1e-2;
+
+    #[test]
+    fn test_splat_2d() {
+        // two minutes of a deep book, some levels of which fall outside the price range
+        let source = (0..120).flat_map(|second| {
+            (0..200).map(move |level| {
+                (
+                    second as f64,
+                    95.0 + 0.05 * level as f64,
+                    1.0 + ((7 * second + 13 * level) % 11) as f64,
+                )
+            })
+        });
+        let kernels = [
+            Kernel::default(),
+            Kernel {
+                shape: KernelShape::Epanechnikov,
+                bandwidth: Bandwidth::Fraction(0.02),
+                normalization: Normalization::Mass,
+                ..Kernel::default()
+            },
+            Kernel {
+                shape: KernelShape::TopHat,
+                bandwidth: Bandwidth::Absolute(0.3),
+                ..Kernel::default()
+            },
+            Kernel {
+                bandwidth: Bandwidth::Adaptive(ADAPTIVE_NEIGHBOURS),
+                normalization: Normalization::Mass,
+                ..Kernel::default()
+            },
+        ];
+        // software adapters are accepted for the shader to run on machines without a GPU
+        let splatter = Splatter::new(true);
+
+        let ranges = (&(0.0, 120.0), &(96.0, 104.0));
+        for kernel in kernels {
+            let cpu = crate::splat::splat_2d(&kernel, ranges, (120, 80), source.clone());
+            let largest = cpu.iter().fold(0.0_f64, |acc, value| acc.max(value.abs()));
+            assert!(largest > 0.0);
+
+            let mut splatted = vec![splat_2d_gpu(&kernel, ranges, (120, 80), source.clone())];
+            if let Some(splatter) = &splatter {
+                splatted.push(
+                    splatter
+                        .splat_2d(&kernel, ranges, (120, 80), source.clone())
+                        .unwrap(),
+                );
+            }
+            for gpu in splatted {
+                for (cpu, gpu) in zip(cpu.iter(), gpu.iter()) {
+                    assert!((cpu - gpu).abs() / largest < TOLERANCE);
+                }
+            }
+        }
+
+        assert!(splatter.is_none_or(|splatter| {
+            splatter
+                .splat_2d(&Kernel::default(), ranges, (120, 80), vec![])
+                .is_none()
+        }));
+    }
+}
@@ -0,0 +1,86 @@
+// Kernel density estimation of samples onto a regular 2D grid, one invocation per sample adding
+// the product of its time and price kernels to the cells within its bloom
+
+struct Parameters {
+    grid_sizes: vec2<u32>,
+    // shape of the kernel along time and price: 0 gaussian, 1 epanechnikov, 2 top hat
+    shapes: vec2<u32>,
+    samples: u32,
+}
+
+struct Sample {
+    // position of the sample in grid steps from the start of the ranges
+    center: vec2<f32>,
+    // grid step over kernel deviation, zero to weigh only the nearest grid point
+    inverse_deviation: vec2<f32>,
+    // first and past the last cells the kernel reaches
+    start: vec2<u32>,
+    end: vec2<u32>,
+    // value of the sample times the normalization of both kernels
+    scale: f32,
+}
+
+@group(0) @binding(0) var<uniform> parameters: Parameters;
+@group(0) @binding(1) var<storage, read> samples: array<Sample>;
+// f32 values stored as bits, WGSL only having integer atomics
+@group(0) @binding(2) var<storage, read_write> support: array<atomic<u32>>;
+
+const WORKGROUP_SIZE: u32 = 64u;
+
+// density of the standardized kernel at an offset in deviations, compact kernels being only
+// evaluated within their support
+fn density(shape: u32, offset: f32) -> f32 {
+    switch shape {
+        case 0u: {
+            return 0.3989422804 * exp(-0.5 * offset * offset);
+        }
+        case 1u: {
+            return max(0.75 * (1.0 - offset * offset), 0.0);
+        }
+        default: {
+            return 0.5;
+        }
+    }
+}
+
+fn accumulate(index: u32, value: f32) {
+    var current = atomicLoad(&support[index]);
+    loop {
+        let swapped = atomicCompareExchangeWeak(
+            &support[index],
+            current,
+            bitcast<u32>(bitcast<f32>(current) + value),
+        );
+        if swapped.exchanged {
+            break;
+        }
+        current = swapped.old_value;
+    }
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn splat(
+    @builtin(global_invocation_id) invocation: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let index = invocation.x + invocation.y * workgroups.x * WORKGROUP_SIZE;
+    if index >= parameters.samples {
+        return;
+    }
+    let sample = samples[index];
+
+    for (var time = sample.start.x; time < sample.end.x; time++) {
+        let time_offset = (f32(time) - sample.center.x) * sample.inverse_deviation.x;
+        let time_weight = sample.scale * density(parameters.shapes.x, time_offset);
+        if time_weight == 0.0 {
+            continue;
+        }
+        for (var price = sample.start.y; price < sample.end.y; price++) {
+            let price_offset = (f32(price) - sample.center.y) * sample.inverse_deviation.y;
+            let weight = time_weight * density(parameters.shapes.y, price_offset);
+            if weight != 0.0 {
+                accumulate(time * parameters.grid_sizes.y + price, weight);
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use crate::error::BookedError;
+use crate::feed::{Aggressor, Booked, Order, TickerState, Traded};
+
+use chrono::DateTime;
+use rusqlite::{Connection, params};
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Tables of the archive, one row per book level update, ticker update and trade
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS books (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL,
+    price REAL NOT NULL,
+    quantity REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS books_by_symbol ON books (symbol, time);
+CREATE TABLE IF NOT EXISTS tickers (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    ask REAL NOT NULL,
+    ask_quantity REAL NOT NULL,
+    bid REAL NOT NULL,
+    bid_quantity REAL NOT NULL,
+    change REAL NOT NULL,
+    change_pct REAL NOT NULL,
+    high REAL NOT NULL,
+    last REAL NOT NULL,
+    low REAL NOT NULL,
+    volume REAL NOT NULL,
+    vwap REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tickers_by_symbol ON tickers (symbol, time);
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    aggressor TEXT NOT NULL,
+    price REAL NOT NULL,
+    quantity REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS trades_by_symbol ON trades (symbol, time);
+";
+
+/// private utility method for the unix milliseconds of an RFC 3339 timestamp
+fn milliseconds(timestamp: &str) -> Result<i64, BookedError> {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(time) => Ok(time.timestamp_millis()),
+        Err(source) => Err(BookedError::Timestamp {
+            timestamp: timestamp.to_string(),
+            source,
+        }),
+    }
+}
+
+/// private utility method for the RFC 3339 timestamp of unix milliseconds
+fn timestamp(milliseconds: i64) -> Result<String, BookedError> {
+    match DateTime::from_timestamp_millis(milliseconds) {
+        Some(time) => Ok(time.to_rfc3339()),
+        None => Err(BookedError::Storage(format!(
+            "Invalid timestamp {}",
+            milliseconds
+        ))),
+    }
+}
+
+/// private utility method for the orders of a book, keyed by the bits of their positive price
+/// which sort like the prices themselves
+fn orders(book: &BTreeMap<u64, f64>) -> Vec<Order> {
+    book.iter()
+        .map(|(price, quantity)| Order {
+            price: f64::from_bits(*price),
+            quantity: *quantity,
+        })
+        .collect()
+}
+
+/// Single file SQLite archive of book updates, tickers and trades
+pub struct Storage {
+    connection: Connection,
+}
+
+impl Storage {
+    /// open or create an archive in write ahead logging mode so it can be read while written
+    pub fn open(path: &Path) -> Result<Storage, BookedError> {
+        let connection = Connection::open(path)?;
+        connection
+            .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Storage { connection })
+    }
+
+    /// record every level of a book update
+    pub fn insert_book(&mut self, booked: &Booked) -> Result<(), BookedError> {
+        let time = milliseconds(&booked.timestamp)?;
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT INTO books (time, symbol, side, price, quantity) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (side, levels) in [("ask", &booked.asks), ("bid", &booked.bids)] {
+                for order in levels.iter() {
+                    statement.execute(params![
+                        time,
+                        booked.symbol,
+                        side,
+                        order.price,
+                        order.quantity
+                    ])?;
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// record a ticker update received at a time in unix milliseconds
+    pub fn insert_ticker(&mut self, time: i64, ticker: &TickerState) -> Result<(), BookedError> {
+        self.connection.execute(
+            "INSERT INTO tickers (time, symbol, ask, ask_quantity, bid, bid_quantity, change, \
+             change_pct, high, last, low, volume, vwap) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                time,
+                ticker.symbol,
+                ticker.ask,
+                ticker.ask_quantity,
+                ticker.bid,
+                ticker.bid_quantity,
+                ticker.change,
+                ticker.change_pct,
+                ticker.high,
+                ticker.last,
+                ticker.low,
+                ticker.volume,
+                ticker.vwap
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// record executed trades
+    pub fn insert_trades(&mut self, trades: &[Traded]) -> Result<(), BookedError> {
+        let times = trades
+            .iter()
+            .map(|trade| milliseconds(&trade.timestamp))
+            .collect::<Result<Vec<_>, BookedError>>()?;
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT INTO trades (time, symbol, aggressor, price, quantity) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (time, trade) in times.iter().zip(trades.iter()) {
+                let aggressor = match trade.aggressor {
+                    Aggressor::Buy => "buy",
+                    Aggressor::Sell => "sell",
+                };
+                statement.execute(params![
+                    time,
+                    trade.symbol,
+                    aggressor,
+                    trade.price,
+                    trade.quantity
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// full books of a symbol at the end of every second holding updates from a time on in unix
+    /// seconds, rebuilt from every update recorded before
+    pub fn books(&self, symbol: &str, since: i64) -> Result<Vec<Booked>, BookedError> {
+        let mut statement = self.connection.prepare(
+            "SELECT time, side, price, quantity FROM books WHERE symbol = ?1 ORDER BY time, id",
+        )?;
+        let rows = statement
+            .query_map(params![symbol], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, f64>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut asks: BTreeMap<u64, f64> = BTreeMap::new();
+        let mut bids: BTreeMap<u64, f64> = BTreeMap::new();
+        let mut books = Vec::new();
+        for (index, (time, side, price, quantity)) in rows.iter().enumerate() {
+            let book = match side.as_str() {
+                "ask" => &mut asks,
+                "bid" => &mut bids,
+                _ => return Err(BookedError::Storage(format!("Unknown side {}", side))),
+            };
+            if *quantity == 0.0 {
+                book.remove(&price.to_bits());
+            } else {
+                book.insert(price.to_bits(), *quantity);
+            }
+
+            let second = time.div_euclid(1000);
+            let closes_second = rows
+                .get(index + 1)
+                .is_none_or(|(next, _, _, _)| next.div_euclid(1000) != second);
+            if closes_second && second >= since {
+                books.push(Booked {
+                    symbol: symbol.to_string(),
+                    timestamp: timestamp(second * 1000)?,
+                    asks: orders(&asks),
+                    bids: orders(&bids),
+                });
+            }
+        }
+        Ok(books)
+    }
+
+    /// trades of a symbol from a time on in unix seconds, oldest first
+    pub fn trades(&self, symbol: &str, since: i64) -> Result<Vec<Traded>, BookedError> {
+        let mut statement = self.connection.prepare(
+            "SELECT time, aggressor, price, quantity FROM trades \
+             WHERE symbol = ?1 AND time >= ?2 ORDER BY time, id",
+        )?;
+        let rows = statement
+            .query_map(params![symbol, since * 1000], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, f64>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(time, aggressor, price, quantity)| {
+                Ok(Traded {
+                    symbol: symbol.to_string(),
+                    timestamp: timestamp(time)?,
+                    aggressor: match aggressor.as_str() {
+                        "buy" => Aggressor::Buy,
+                        "sell" => Aggressor::Sell,
+                        _ => {
+                            return Err(BookedError::Storage(format!(
+                                "Unknown aggressor {}",
+                                aggressor
+                            )));
+                        }
+                    },
+                    price,
+                    quantity,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn booked_at(milliseconds: i64, asks: Vec<(f64, f64)>, bids: Vec<(f64, f64)>) -> Booked {
+        let levels = |levels: Vec<(f64, f64)>| {
+            levels
+                .into_iter()
+                .map(|(price, quantity)| Order { price, quantity })
+                .collect()
+        };
+        Booked {
+            symbol: "ETH/EUR".to_string(),
+            timestamp: timestamp(milliseconds).unwrap(),
+            asks: levels(asks),
+            bids: levels(bids),
+        }
+    }
+
+    #[test]
+    fn test_storage() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("storage.sqlite");
+        let mut storage = Storage::open(&path).unwrap();
+
+        let mode: String = storage
+            .connection
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        storage
+            .insert_book(&booked_at(
+                100_000,
+                vec![(5.0, 6.0), (7.0, 8.0)],
+                vec![(1.0, 2.0), (3.0, 4.0)],
+            ))
+            .unwrap();
+        storage
+            .insert_book(&booked_at(101_200, vec![(5.0, 0.0)], vec![]))
+            .unwrap();
+        storage
+            .insert_book(&booked_at(101_700, vec![(6.0, 1.0)], vec![(3.0, 3.0)]))
+            .unwrap();
+        storage
+            .insert_book(&booked_at(102_000, vec![], vec![(2.0, 1.0)]))
+            .unwrap();
+
+        let books = storage.books("ETH/EUR", 101).unwrap();
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].timestamp, timestamp(101_000).unwrap());
+        assert_eq!(
+            books[0].asks,
+            vec![
+                Order {
+                    price: 6.0,
+                    quantity: 1.0
+                },
+                Order {
+                    price: 7.0,
+                    quantity: 8.0
+                }
+            ]
+        );
+        assert_eq!(books[0].bids[1].quantity, 3.0);
+        assert_eq!(books[1].bids.len(), 3);
+        assert!(storage.books("BTC/EUR", 0).unwrap().is_empty());
+
+        let traded = |milliseconds: i64, aggressor: Aggressor| Traded {
+            symbol: "ETH/EUR".to_string(),
+            timestamp: timestamp(milliseconds).unwrap(),
+            aggressor,
+            price: 5.0,
+            quantity: 0.5,
+        };
+        storage
+            .insert_trades(&[
+                traded(99_000, Aggressor::Buy),
+                traded(101_500, Aggressor::Sell),
+            ])
+            .unwrap();
+        let trades = storage.trades("ETH/EUR", 100).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].aggressor, Aggressor::Sell);
+        assert_eq!(trades[0].timestamp, timestamp(101_500).unwrap());
+
+        let ticker = TickerState {
+            ask: 5.0,
+            ask_quantity: 6.0,
+            bid: 3.0,
+            bid_quantity: 4.0,
+            change: 0.0,
+            change_pct: 0.0,
+            high: 7.0,
+            last: 4.0,
+            low: 1.0,
+            symbol: "ETH/EUR".to_string(),
+            volume: 10.0,
+            vwap: 4.0,
+        };
+        storage.insert_ticker(100_000, &ticker).unwrap();
+        let count: i64 = storage
+            .connection
+            .query_row("SELECT COUNT(*) FROM tickers", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,128 @@
+use crossterm::cursor::Show;
+use crossterm::event::DisableMouseCapture;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::DefaultTerminal;
+use tracing::error;
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether a panic left the terminal while the interface may still be running
+static LEFT_ON_PANIC: AtomicBool = AtomicBool::new(false);
+
+/// Leave the terminal as found, out of raw mode, alternate screen and mouse capture
+pub fn restore() {
+    // restoring is best effort, there is nowhere left to report failures to, raw mode being left
+    // first for the commands to be written to a terminal in its usual mode
+    let _ = disable_raw_mode();
+    let _ = leave(&mut std::io::stdout());
+}
+
+/// Write the commands releasing the mouse, leaving the alternate screen and showing the cursor
+fn leave(writer: &mut impl Write) -> std::io::Result<()> {
+    crossterm::execute!(writer, DisableMouseCapture, LeaveAlternateScreen, Show)
+}
+
+/// Terminal drawn on by the interface, restored when dropped, be it on return, error, abort or
+/// unwinding
+pub struct TerminalGuard {
+    pub terminal: DefaultTerminal,
+}
+
+impl TerminalGuard {
+    /// constructor, switching the terminal to raw mode and alternate screen
+    pub fn new() -> TerminalGuard {
+        TerminalGuard {
+            terminal: ratatui::init(),
+        }
+    }
+
+    /// Switch the terminal back to raw mode and alternate screen once a panicking task left
+    /// them, true if so and the whole interface is to be drawn again
+    pub fn reenter(&mut self) -> std::io::Result<bool> {
+        if !LEFT_ON_PANIC.swap(false, Ordering::SeqCst) {
+            return Ok(false);
+        }
+        enable_raw_mode()?;
+        crossterm::execute!(std::io::stdout(), EnterAlternateScreen)?;
+        self.terminal.clear()?;
+        Ok(true)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Restore the terminal before any panic is logged and printed by the previous hook, the panic
+/// then unwinding as usual: a panicking task ends in a JoinError for its supervisor, while a panic
+/// of the main task drops the log guard on its way out, flushing the panic to the log file
+pub fn install_panic_hook() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        LEFT_ON_PANIC.store(true, Ordering::SeqCst);
+        error!(%info, "panicked");
+        hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    /// Log writer appending to a shared buffer
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_panic_hook() {
+        let logged = Arc::new(Mutex::new(vec![]));
+        let writer = logged.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || Captured(writer.clone()))
+            .with_ansi(false)
+            .finish();
+
+        install_panic_hook();
+        let unwound = tracing::subscriber::with_default(subscriber, || {
+            std::panic::catch_unwind(|| panic!("logged panic"))
+        });
+        drop(std::panic::take_hook());
+
+        // the panic unwinds instead of ending the process, logged and leaving the terminal
+        assert!(unwound.is_err());
+        assert!(LEFT_ON_PANIC.swap(false, Ordering::SeqCst));
+        let logged = String::from_utf8(logged.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("ERROR"));
+        assert!(logged.contains("logged panic"));
+    }
+
+    #[test]
+    fn test_leave() {
+        let mut written = vec![];
+        leave(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+
+        // mouse reporting disabled, main screen back and cursor visible, in that order
+        let mouse = written.find("\x1b[?1000l").unwrap();
+        let screen = written.find("\x1b[?1049l").unwrap();
+        let cursor = written.find("\x1b[?25h").unwrap();
+        assert!(mouse < screen && screen < cursor);
+    }
+}
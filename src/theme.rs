@@ -0,0 +1,310 @@
+use crate::app::ColorScale;
+
+use clap::ValueEnum;
+use ratatui::style::Color;
+
+/// Set of colors the interface is drawn with
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Theme {
+    /// green asks and red bids
+    Default,
+    /// shades of gray only, for terminals without colors or printing
+    Monochrome,
+    /// light colors and brighter heat map cells, for bright rooms or low contrast screens
+    HighContrast,
+    /// blue asks and orange bids from the Okabe-Ito palette, told apart with any color vision
+    ColorblindSafe,
+}
+
+impl Theme {
+    /// next theme in the cycle
+    pub fn cycled(self) -> Theme {
+        match self {
+            Theme::Default => Theme::Monochrome,
+            Theme::Monochrome => Theme::HighContrast,
+            Theme::HighContrast => Theme::ColorblindSafe,
+            Theme::ColorblindSafe => Theme::Default,
+        }
+    }
+
+    /// name of the theme, as shown in the interface
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::Monochrome => "monochrome",
+            Theme::HighContrast => "high contrast",
+            Theme::ColorblindSafe => "colorblind safe",
+        }
+    }
+
+    /// color of asks, and of liquidity being added or prices going up
+    pub fn positive(self) -> Color {
+        match self {
+            Theme::Default => Color::Green,
+            Theme::Monochrome => Color::White,
+            Theme::HighContrast => Color::LightGreen,
+            Theme::ColorblindSafe => Color::Rgb(86, 180, 233),
+        }
+    }
+
+    /// color of bids, and of liquidity being removed or prices going down
+    pub fn negative(self) -> Color {
+        match self {
+            Theme::Default => Color::Red,
+            Theme::Monochrome => Color::Gray,
+            Theme::HighContrast => Color::LightRed,
+            Theme::ColorblindSafe => Color::Rgb(230, 159, 0),
+        }
+    }
+
+    /// color of the series drawn over both sides, e.g. the microprice
+    pub fn accent(self) -> Color {
+        match self {
+            Theme::Default => Color::Yellow,
+            Theme::Monochrome => Color::White,
+            Theme::HighContrast => Color::LightYellow,
+            Theme::ColorblindSafe => Color::Rgb(240, 228, 66),
+        }
+    }
+
+    /// darkest and brightest color of the order map cells of a side, and the fraction of the
+    /// brightest color the faintest cells keep
+    fn shades(self, bids: bool) -> ((u8, u8, u8), f64) {
+        match (self, bids) {
+            (Theme::Default, true) => ((255, 0, 0), 0.0),
+            (Theme::Default, false) => ((0, 255, 0), 0.0),
+            (Theme::Monochrome, true) => ((160, 160, 160), 0.0),
+            (Theme::Monochrome, false) => ((255, 255, 255), 0.0),
+            (Theme::HighContrast, true) => ((255, 40, 40), 0.35),
+            (Theme::HighContrast, false) => ((40, 255, 40), 0.35),
+            (Theme::ColorblindSafe, true) => ((230, 159, 0), 0.0),
+            (Theme::ColorblindSafe, false) => ((86, 180, 233), 0.0),
+        }
+    }
+}
+
+/// Number of colors per side of the order map gradients, fine enough to look continuous while
+/// bounding the number of chart layers drawn
+const GRADIENT_STEPS: f64 = 64.0;
+
+/// Stops of the viridis style gradient of the asks, from the faintest to the largest volumes
+const VIRIDIS_ASKS: [(u8, u8, u8); 4] =
+    [(38, 30, 90), (33, 120, 142), (53, 183, 121), (200, 224, 32)];
+
+/// Stops of the viridis style gradient of the bids, from the faintest to the largest volumes
+const VIRIDIS_BIDS: [(u8, u8, u8); 4] =
+    [(60, 15, 80), (150, 40, 100), (230, 90, 60), (252, 190, 70)];
+
+/// Enum of the gradients order map volumes are colored along
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Gradient {
+    /// brightening shades of the ask and bid colors of the theme
+    Shades,
+    /// perceptually uniform ramps, blue to yellow for asks and purple to orange for bids
+    Viridis,
+}
+
+/// Enum of the color palettes terminals support
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ColorDepth {
+    /// 24 bit colors
+    TrueColor,
+    /// the 256 indexed colors of xterm
+    Indexed,
+    /// the 16 standard ANSI colors
+    Basic,
+}
+
+/// Colors of the 16 standard ANSI colors in xterm
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+impl ColorDepth {
+    /// color depth of the terminal, as advertised by the COLORTERM and TERM environment variables
+    pub fn detect() -> ColorDepth {
+        let variable = |name| std::env::var(name).unwrap_or_default();
+        ColorDepth::advertised(&variable("COLORTERM"), &variable("TERM"))
+    }
+
+    /// private utility method giving the color depth advertised by the values of the COLORTERM
+    /// and TERM environment variables
+    fn advertised(colorterm: &str, term: &str) -> ColorDepth {
+        let (colorterm, term) = (colorterm.to_lowercase(), term.to_lowercase());
+        if colorterm.contains("truecolor") || colorterm.contains("24bit") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Indexed
+        } else {
+            ColorDepth::Basic
+        }
+    }
+
+    /// closest color to a 24 bit one the terminal displays
+    pub fn fit(self, color: Color) -> Color {
+        let Color::Rgb(red, green, blue) = color else {
+            return color;
+        };
+        match self {
+            ColorDepth::TrueColor => color,
+            ColorDepth::Indexed => {
+                let level = |value: u8| match value {
+                    0..48 => 0,
+                    48..115 => 1,
+                    _ => (value - 35) / 40,
+                };
+                Color::Indexed(16 + 36 * level(red) + 6 * level(green) + level(blue))
+            }
+            ColorDepth::Basic => {
+                let distance = |(other_red, other_green, other_blue): (u8, u8, u8)| {
+                    (red as i32 - other_red as i32).pow(2)
+                        + (green as i32 - other_green as i32).pow(2)
+                        + (blue as i32 - other_blue as i32).pow(2)
+                };
+                BASIC_COLORS
+                    .iter()
+                    .min_by_key(|(_, rgb)| distance(*rgb))
+                    .map(|(basic, _)| *basic)
+                    .unwrap_or(color)
+            }
+        }
+    }
+}
+
+/// Everything order map volumes are turned into colors with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeatColors {
+    pub theme: Theme,
+    pub scale: ColorScale,
+    pub gradient: Gradient,
+    pub depth: ColorDepth,
+}
+
+impl HeatColors {
+    /// color of an order map cell, negative volumes being bids, brighter with more volume
+    pub fn color(&self, volume: f64, max_volume: f64) -> Color {
+        let normalized = self.scale.normalize(volume, max_volume);
+        let fraction = (normalized * (GRADIENT_STEPS - 1.0)).round() / (GRADIENT_STEPS - 1.0);
+        let bids = volume < 0.0;
+        let rgb = match self.gradient {
+            Gradient::Shades => {
+                let ((red, green, blue), floor) = self.theme.shades(bids);
+                let intensity = floor + (1.0 - floor) * (0.1 + 0.9 * fraction);
+                let shade = |value: u8| (value as f64 * intensity) as u8;
+                (shade(red), shade(green), shade(blue))
+            }
+            Gradient::Viridis => {
+                let stops = if bids { VIRIDIS_BIDS } else { VIRIDIS_ASKS };
+                let position = fraction * (stops.len() - 1) as f64;
+                let index = (position.floor() as usize).min(stops.len() - 2);
+                let weight = position - index as f64;
+                let (from, to) = (stops[index], stops[index + 1]);
+                let mix =
+                    |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * weight) as u8;
+                (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+            }
+        };
+        self.depth.fit(Color::Rgb(rgb.0, rgb.1, rgb.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heat() {
+        let mut colors = HeatColors {
+            theme: Theme::Default,
+            scale: ColorScale::Linear,
+            gradient: Gradient::Shades,
+            depth: ColorDepth::TrueColor,
+        };
+        assert_eq!(colors.color(10.0, 10.0), Color::Rgb(0, 255, 0));
+        assert_eq!(colors.color(-10.0, 10.0), Color::Rgb(255, 0, 0));
+        assert_eq!(colors.color(0.0, 10.0), Color::Rgb(0, 25, 0));
+        // volumes between the ten former buckets get colors of their own
+        assert_ne!(colors.color(5.0, 10.0), colors.color(5.3, 10.0));
+
+        colors.theme = Theme::HighContrast;
+        assert_eq!(colors.color(10.0, 10.0), Color::Rgb(40, 255, 40));
+        // the dimmest cells keep most of the brightness of the largest ones
+        assert_eq!(colors.color(0.0, 10.0), Color::Rgb(16, 105, 16));
+
+        colors.gradient = Gradient::Viridis;
+        assert_eq!(colors.color(10.0, 10.0), Color::Rgb(200, 224, 32));
+        assert_eq!(colors.color(-10.0, 10.0), Color::Rgb(252, 190, 70));
+
+        colors.depth = ColorDepth::Indexed;
+        assert_eq!(
+            colors.color(10.0, 10.0),
+            Color::Indexed(16 + 36 * 4 + 6 * 4)
+        );
+        colors.depth = ColorDepth::Basic;
+        assert_eq!(colors.color(10.0, 10.0), Color::Yellow);
+        assert_eq!(ColorDepth::Basic.fit(Color::Red), Color::Red);
+
+        let mut theme = Theme::Default;
+        for _ in 0..4 {
+            theme = theme.cycled();
+        }
+        assert_eq!(theme, Theme::Default);
+    }
+
+    #[test]
+    fn test_gradient() {
+        // volumes are colored along many more steps than the ten former buckets, each side
+        // brightening steadily
+        for gradient in [Gradient::Shades, Gradient::Viridis] {
+            let colors = HeatColors {
+                theme: Theme::Default,
+                scale: ColorScale::Linear,
+                gradient,
+                depth: ColorDepth::TrueColor,
+            };
+            let brightness = |volume: f64| match colors.color(volume, 100.0) {
+                Color::Rgb(red, green, blue) => red as u32 + green as u32 + blue as u32,
+                color => panic!("{:?} is not a 24 bit color", color),
+            };
+            let mut asks = (0..=100)
+                .map(|volume| colors.color(volume as f64, 100.0))
+                .collect::<Vec<_>>();
+            asks.dedup();
+            assert!(asks.len() > 50);
+            for volume in 1..=100 {
+                let volume = volume as f64;
+                assert!(brightness(volume) >= brightness(volume - 1.0));
+                assert!(brightness(-volume) >= brightness(1.0 - volume));
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        for (colorterm, term, depth) in [
+            ("truecolor", "xterm-256color", ColorDepth::TrueColor),
+            ("24BIT", "", ColorDepth::TrueColor),
+            ("", "xterm-256color", ColorDepth::Indexed),
+            ("", "screen-256color", ColorDepth::Indexed),
+            ("", "xterm", ColorDepth::Basic),
+            ("", "", ColorDepth::Basic),
+        ] {
+            assert_eq!(ColorDepth::advertised(colorterm, term), depth);
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::error::BookedError;
+use crate::pipeline::BookHistory;
+
+use hyper::Request;
+use hyper_tls::HttpsConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use tokio::sync::mpsc::{Receiver, Sender, channel};
+use tokio::task::spawn;
+use tokio_postgres::{NoTls, Statement};
+use tracing::{error, warn};
+
+use std::collections::HashMap;
+
+/// Environment variable holding the token authorizing writes to InfluxDB
+pub const INFLUX_TOKEN_VARIABLE: &str = "INFLUX_TOKEN";
+
+/// Number of batches of aggregates waiting to be written before newer ones are dropped
+const QUEUE_SIZE: usize = 60;
+
+/// Measurement of InfluxDB and table of TimescaleDB the aggregates are written to
+const MEASUREMENT: &str = "book_aggregates";
+
+/// Table of the aggregates in TimescaleDB
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS book_aggregates (
+    time TIMESTAMPTZ NOT NULL,
+    symbol TEXT NOT NULL,
+    best_bid DOUBLE PRECISION,
+    best_ask DOUBLE PRECISION,
+    spread DOUBLE PRECISION,
+    bid_depth DOUBLE PRECISION NOT NULL,
+    ask_depth DOUBLE PRECISION NOT NULL,
+    imbalance DOUBLE PRECISION NOT NULL
+);
+";
+
+/// Time series database the per second aggregates of the books are written to
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeSeriesSink {
+    /// write endpoint of InfluxDB taking line protocol, e.g.
+    /// http://localhost:8086/api/v2/write?org=me&bucket=books
+    Influx(String),
+    /// connection string of a PostgreSQL database with the TimescaleDB extension, e.g.
+    /// postgres://me@localhost/books
+    Timescale(String),
+}
+
+impl TimeSeriesSink {
+    /// parse a sink written influx:URL or timescale:CONNECTION
+    pub fn parse(text: &str) -> Result<TimeSeriesSink, BookedError> {
+        match text.split_once(':') {
+            Some(("influx", url)) if url.starts_with("http") => {
+                Ok(TimeSeriesSink::Influx(url.to_string()))
+            }
+            Some(("timescale", connection)) if !connection.is_empty() => {
+                Ok(TimeSeriesSink::Timescale(connection.to_string()))
+            }
+            _ => Err(BookedError::Parse(
+                "expected influx:URL or timescale:CONNECTION".to_string(),
+            )),
+        }
+    }
+
+    /// name of the database, as shown in the logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeSeriesSink::Influx(_) => "InfluxDB",
+            TimeSeriesSink::Timescale(_) => "TimescaleDB",
+        }
+    }
+}
+
+/// Aggregates of the latest book of a symbol, at most one per second
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    pub symbol: String,
+    /// unix seconds of the book
+    pub time: i64,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub spread: Option<f64>,
+    /// total quantity resting on each side
+    pub bid_depth: f64,
+    pub ask_depth: f64,
+    /// normalized depth imbalance (bid - ask) / (bid + ask) in [-1, 1]
+    pub imbalance: f64,
+}
+
+impl Aggregate {
+    /// constructor, from the latest book of a history
+    pub async fn new(symbol: &str, history: &BookHistory) -> Aggregate {
+        let ((time, asks), (_, bids)) = history.get_latest_book().await;
+        let best_ask = asks.get_first().map(|(price, _)| price.value);
+        let best_bid = bids.get_last().map(|(price, _)| price.value);
+        let ask_depth = asks.values().sum::<f64>();
+        let bid_depth = bids.values().sum::<f64>();
+        let total = ask_depth + bid_depth;
+        Aggregate {
+            symbol: symbol.to_string(),
+            time,
+            best_bid,
+            best_ask,
+            spread: best_ask.zip(best_bid).map(|(ask, bid)| ask - bid),
+            bid_depth,
+            ask_depth,
+            imbalance: match total.abs() <= f64::EPSILON {
+                true => 0.0,
+                false => ((bid_depth - ask_depth) / total).clamp(-1.0, 1.0),
+            },
+        }
+    }
+
+    /// point in the InfluxDB line protocol, tagged with the symbol and timed in seconds
+    pub fn line(&self) -> String {
+        let symbol = self
+            .symbol
+            .replace(',', "\\,")
+            .replace('=', "\\=")
+            .replace(' ', "\\ ");
+        let fields = [
+            ("best_bid", self.best_bid),
+            ("best_ask", self.best_ask),
+            ("spread", self.spread),
+            ("bid_depth", Some(self.bid_depth)),
+            ("ask_depth", Some(self.ask_depth)),
+            ("imbalance", Some(self.imbalance)),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| format!("{}={}", name, value)))
+        .collect::<Vec<_>>()
+        .join(",");
+        format!("{},symbol={} {} {}", MEASUREMENT, symbol, fields, self.time)
+    }
+}
+
+/// Writer of the aggregates of the books to a time series database from a separate task, so
+/// that a slow database never holds up the books
+pub struct TimeSeriesWriter {
+    sender: Sender<Vec<Aggregate>>,
+    /// time of the last aggregate queued for every symbol
+    last_times: HashMap<String, i64>,
+}
+
+impl TimeSeriesWriter {
+    /// constructor, connecting to the database and creating the hypertable of the aggregates
+    /// with TimescaleDB
+    pub async fn connect(sink: TimeSeriesSink) -> Result<TimeSeriesWriter, BookedError> {
+        let (sender, receiver) = channel(QUEUE_SIZE);
+        match sink {
+            TimeSeriesSink::Influx(url) => {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                let url = match url.contains("precision=") {
+                    true => url,
+                    false => format!("{}{}precision=s", url, separator),
+                };
+                spawn(write_influx(url, receiver));
+            }
+            TimeSeriesSink::Timescale(connection) => {
+                let (client, connected) = tokio_postgres::connect(&connection, NoTls).await?;
+                spawn(async move {
+                    if let Err(message) = connected.await {
+                        error!(%message, "TimescaleDB connection closed");
+                    }
+                });
+                client.batch_execute(SCHEMA).await?;
+                client
+                    .execute(
+                        "SELECT create_hypertable('book_aggregates', 'time', \
+                         if_not_exists => TRUE)",
+                        &[],
+                    )
+                    .await?;
+                let statement = client
+                    .prepare(
+                        "INSERT INTO book_aggregates (time, symbol, best_bid, best_ask, spread, \
+                         bid_depth, ask_depth, imbalance) \
+                         VALUES (to_timestamp($1), $2, $3, $4, $5, $6, $7, $8)",
+                    )
+                    .await?;
+                spawn(write_timescale(client, statement, receiver));
+            }
+        }
+        Ok(TimeSeriesWriter {
+            sender,
+            last_times: HashMap::new(),
+        })
+    }
+
+    /// queue the aggregates of the latest books of histories not written yet, dropping them if
+    /// the database falls too far behind
+    pub async fn push(
+        &mut self,
+        histories: impl Iterator<Item = (&String, &BookHistory)>,
+    ) -> Result<(), BookedError> {
+        let mut aggregates = Vec::new();
+        for (symbol, history) in histories {
+            let aggregate = Aggregate::new(symbol, history).await;
+            if self
+                .last_times
+                .get(symbol)
+                .is_some_and(|time| *time >= aggregate.time)
+            {
+                continue;
+            }
+            self.last_times.insert(symbol.clone(), aggregate.time);
+            aggregates.push(aggregate);
+        }
+        if aggregates.is_empty() {
+            return Ok(());
+        }
+        match self.sender.try_send(aggregates) {
+            Ok(()) => Ok(()),
+            Err(message) => Err(BookedError::TimeSeries(message.to_string())),
+        }
+    }
+}
+
+/// private utility method posting the batches of aggregates to InfluxDB until the writer is
+/// dropped
+async fn write_influx(url: String, mut receiver: Receiver<Vec<Aggregate>>) {
+    let token = std::env::var(INFLUX_TOKEN_VARIABLE).ok();
+    let client: Client<_, String> =
+        Client::builder(TokioExecutor::new()).build(HttpsConnector::new());
+    while let Some(aggregates) = receiver.recv().await {
+        let body = aggregates
+            .iter()
+            .map(|aggregate| aggregate.line())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut request =
+            Request::post(url.as_str()).header("Content-Type", "text/plain; charset=utf-8");
+        if let Some(token) = &token {
+            request = request.header("Authorization", format!("Token {}", token));
+        }
+        let request = match request.body(body) {
+            Ok(request) => request,
+            Err(message) => {
+                warn!(%message, "could not build InfluxDB request");
+                continue;
+            }
+        };
+        match client.request(request).await {
+            Ok(response) if response.status().is_success() => (),
+            Ok(response) => warn!(status = %response.status(), "InfluxDB refused aggregates"),
+            Err(message) => warn!(%message, "could not write to InfluxDB"),
+        }
+    }
+}
+
+/// private utility method inserting the batches of aggregates into TimescaleDB until the writer
+/// is dropped
+async fn write_timescale(
+    client: tokio_postgres::Client,
+    statement: Statement,
+    mut receiver: Receiver<Vec<Aggregate>>,
+) {
+    while let Some(aggregates) = receiver.recv().await {
+        for aggregate in aggregates {
+            if let Err(message) = client
+                .execute(
+                    &statement,
+                    &[
+                        &(aggregate.time as f64),
+                        &aggregate.symbol,
+                        &aggregate.best_bid,
+                        &aggregate.best_ask,
+                        &aggregate.spread,
+                        &aggregate.bid_depth,
+                        &aggregate.ask_depth,
+                        &aggregate.imbalance,
+                    ],
+                )
+                .await
+            {
+                warn!(symbol = aggregate.symbol, %message, "could not write to TimescaleDB");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::{Booked, Order};
+
+    #[tokio::test]
+    async fn test_aggregate() {
+        assert_eq!(
+            TimeSeriesSink::parse("influx:http://localhost:8086/api/v2/write?bucket=books")
+                .unwrap(),
+            TimeSeriesSink::Influx("http://localhost:8086/api/v2/write?bucket=books".to_string())
+        );
+        assert_eq!(
+            TimeSeriesSink::parse("timescale:postgres://me@localhost/books").unwrap(),
+            TimeSeriesSink::Timescale("postgres://me@localhost/books".to_string())
+        );
+        assert!(TimeSeriesSink::parse("influx:localhost").is_err());
+        assert!(TimeSeriesSink::parse("prometheus:localhost").is_err());
+
+        let mut history = BookHistory::new(60);
+        history
+            .update(Booked {
+                symbol: "BTC/USD".to_string(),
+                timestamp: "2023-11-14T22:13:20+00:00".to_string(),
+                bids: vec![
+                    Order {
+                        price: 99.0,
+                        quantity: 2.0,
+                    },
+                    Order {
+                        price: 99.5,
+                        quantity: 1.0,
+                    },
+                ],
+                asks: vec![Order {
+                    price: 100.5,
+                    quantity: 1.0,
+                }],
+            })
+            .await
+            .unwrap();
+        let aggregate = Aggregate::new("BTC/USD", &history).await;
+        assert_eq!(
+            aggregate,
+            Aggregate {
+                symbol: "BTC/USD".to_string(),
+                time: 1_700_000_000,
+                best_bid: Some(99.5),
+                best_ask: Some(100.5),
+                spread: Some(1.0),
+                bid_depth: 3.0,
+                ask_depth: 1.0,
+                imbalance: 0.5,
+            }
+        );
+        assert_eq!(
+            aggregate.line(),
+            "book_aggregates,symbol=BTC/USD best_bid=99.5,best_ask=100.5,spread=1,bid_depth=3,\
+             ask_depth=1,imbalance=0.5 1700000000"
+        );
+    }
+}
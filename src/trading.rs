@@ -0,0 +1,329 @@
+use crate::actions::Action;
+use crate::error::BookedError;
+use crate::feed::fetch_websockets_token;
+use crate::walls::Side;
+
+use kraken_async_rs::crypto::secrets::Token;
+use kraken_async_rs::response_types::{BuySell, OrderType};
+use kraken_async_rs::wss::{
+    AddOrderParams, CancelOrderParams, KrakenMessageStream, KrakenWSSClient, Message,
+    MethodMessage, WS_KRAKEN, WS_KRAKEN_AUTH, WssMessage,
+};
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn;
+use tokio::time::{Duration, timeout};
+use tokio_stream::StreamExt;
+use tracing::info;
+
+use std::sync::Arc;
+
+/// Time the listener waits for an answer before letting orders be written to the connection
+const LISTEN_PERIOD: Duration = Duration::from_millis(200);
+
+/// Order entered by the user, sent to the exchange once confirmed
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrderTicket {
+    /// place an order of a quantity at a limit price, or at market if None
+    Place {
+        symbol: String,
+        side: Side,
+        quantity: f64,
+        price: Option<f64>,
+    },
+    /// cancel a resting order of the user
+    Cancel { order_id: String },
+}
+
+impl OrderTicket {
+    /// summary of the order shown when asking the user for confirmation
+    pub fn describe(&self) -> String {
+        match self {
+            OrderTicket::Place {
+                symbol,
+                side,
+                quantity,
+                price,
+            } => {
+                let side = match side {
+                    Side::Bid => "Buy",
+                    Side::Ask => "Sell",
+                };
+                match price {
+                    Some(price) => format!("{} {} {} at {}", side, quantity, symbol, price),
+                    None => format!("{} {} {} at market", side, quantity, symbol),
+                }
+            }
+            OrderTicket::Cancel { order_id } => format!("Cancel order {}", order_id),
+        }
+    }
+}
+
+/// Authenticated websocket connection placing and cancelling the orders of the user, orders only
+/// being validated by the exchange in dry run
+pub struct Trader {
+    connection: Arc<Mutex<KrakenMessageStream<WssMessage>>>,
+    token: Token,
+    dry_run: bool,
+    request_id: i64,
+}
+
+impl Trader {
+    /// constructor, forwarding the answers of the exchange to the action queue from a separate
+    /// thread
+    pub async fn connect(dry_run: bool, sender: Sender<Action>) -> Result<Trader, BookedError> {
+        let token = fetch_websockets_token().await?;
+        let mut client = KrakenWSSClient::new_with_urls(WS_KRAKEN, WS_KRAKEN_AUTH);
+        let connection = Arc::new(Mutex::new(client.connect_auth::<WssMessage>().await?));
+        info!(url = WS_KRAKEN_AUTH, dry_run, "order entry connected");
+        spawn(listen(connection.clone(), sender));
+        Ok(Trader {
+            connection,
+            token,
+            dry_run,
+            request_id: 0,
+        })
+    }
+
+    /// whether orders are only validated by the exchange instead of being placed
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// send an order to the exchange, returning what was sent for the user to be informed
+    pub async fn submit(&mut self, ticket: &OrderTicket) -> Result<String, BookedError> {
+        self.request_id += 1;
+        match ticket {
+            OrderTicket::Place {
+                symbol,
+                side,
+                quantity,
+                price,
+            } => {
+                let params = add_order_params(
+                    symbol,
+                    *side,
+                    *quantity,
+                    *price,
+                    self.dry_run,
+                    self.token.clone(),
+                )?;
+                let message = Message {
+                    method: "add_order".to_string(),
+                    params,
+                    req_id: self.request_id,
+                };
+                self.connection.lock().await.send(&message).await?;
+            }
+            OrderTicket::Cancel { order_id } => {
+                // the exchange offers no validation of cancellations, dry runs keep them local
+                if self.dry_run {
+                    return Ok(format!("Dry run, not sent: {}", ticket.describe()));
+                }
+                let message = Message {
+                    method: "cancel_order".to_string(),
+                    params: CancelOrderParams {
+                        order_id: Some(vec![order_id.clone()]),
+                        client_order_id: None,
+                        order_user_ref: None,
+                        token: self.token.clone(),
+                    },
+                    req_id: self.request_id,
+                };
+                self.connection.lock().await.send(&message).await?;
+            }
+        }
+        Ok(match self.dry_run {
+            true => format!("Dry run, sent for validation: {}", ticket.describe()),
+            false => format!("Sent: {}", ticket.describe()),
+        })
+    }
+}
+
+/// parameters of a limit order, or of a market order without price, only validated in dry run
+pub fn add_order_params(
+    symbol: &str,
+    side: Side,
+    quantity: f64,
+    price: Option<f64>,
+    validate: bool,
+    token: Token,
+) -> Result<AddOrderParams, BookedError> {
+    let decimal = |value: f64| match Decimal::from_f64(value) {
+        Some(decimal) if value > 0.0 => Ok(decimal),
+        _ => Err(BookedError::Order(format!(
+            "{} is not a positive number",
+            value
+        ))),
+    };
+    Ok(AddOrderParams {
+        order_type: match price {
+            Some(_) => OrderType::Limit,
+            None => OrderType::Market,
+        },
+        side: match side {
+            Side::Bid => BuySell::Buy,
+            Side::Ask => BuySell::Sell,
+        },
+        symbol: symbol.to_string(),
+        limit_price: price.map(decimal).transpose()?,
+        limit_price_type: None,
+        triggers: None,
+        time_in_force: None,
+        order_quantity: decimal(quantity)?,
+        margin: None,
+        post_only: None,
+        reduce_only: None,
+        expire_time: None,
+        deadline: None,
+        effective_time: None,
+        client_order_id: None,
+        order_user_ref: None,
+        conditional: None,
+        display_quantity: None,
+        fee_preference: None,
+        no_market_price_protection: None,
+        stp_type: None,
+        cash_order_quantity: None,
+        validate: Some(validate),
+        sender_sub_id: None,
+        token,
+    })
+}
+
+/// private utility method forwarding the answers to the orders sent until the connection closes
+async fn listen(connection: Arc<Mutex<KrakenMessageStream<WssMessage>>>, sender: Sender<Action>) {
+    loop {
+        // the lock is released between reads for orders to be sent meanwhile
+        let communication = {
+            let mut stream = connection.lock().await;
+            match timeout(LISTEN_PERIOD, stream.next()).await {
+                Ok(Some(communication)) => communication,
+                Ok(None) => break,
+                Err(_) => continue,
+            }
+        };
+        let action = match communication {
+            Ok(WssMessage::Method(method)) => match answer(method) {
+                Some(action) => action,
+                None => continue,
+            },
+            Ok(WssMessage::Error(err)) => Action::Warn(format!("{:?}", err)),
+            Err(err) => Action::Warn(format!("{:?}", err)),
+            Ok(_) => continue,
+        };
+        if sender.send(action).await.is_err() {
+            return;
+        }
+    }
+    let _ = sender
+        .send(Action::Warn("Order entry connection closed".to_string()))
+        .await;
+}
+
+/// action reporting the answer of the exchange to an order, None for other methods
+pub fn answer(method: MethodMessage) -> Option<Action> {
+    let (request, result, error) = match method {
+        MethodMessage::AddOrder(response) => (
+            "Order",
+            response.result.map(|result| result.order_id),
+            response.error,
+        ),
+        MethodMessage::CancelOrder(response) => (
+            "Cancellation",
+            response.result.and_then(|result| result.order_id),
+            response.error,
+        ),
+        _ => return None,
+    };
+    Some(match (error, result) {
+        (Some(error), _) => Action::Warn(format!("{} rejected: {}", request, error)),
+        (None, Some(order_id)) => Action::Inform(format!("{} accepted: {}", request, order_id)),
+        (None, None) => Action::Inform(format!("{} accepted", request)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use kraken_async_rs::wss::{AddOrderResult, ResultResponse};
+
+    #[test]
+    fn test_order_entry() {
+        let ticket = OrderTicket::Place {
+            symbol: "BTC/USD".to_string(),
+            side: Side::Bid,
+            quantity: 0.5,
+            price: Some(100.5),
+        };
+        assert_eq!(ticket.describe(), "Buy 0.5 BTC/USD at 100.5");
+        assert_eq!(
+            OrderTicket::Cancel {
+                order_id: "OABC".to_string()
+            }
+            .describe(),
+            "Cancel order OABC"
+        );
+
+        let params =
+            add_order_params("BTC/USD", Side::Ask, 0.5, None, true, Token::new("token")).unwrap();
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["order_type"], "market");
+        assert_eq!(value["side"], "sell");
+        assert_eq!(value["order_qty"], 0.5);
+        assert_eq!(value["validate"], true);
+        assert!(value.get("limit_price").is_none());
+        let params = add_order_params(
+            "BTC/USD",
+            Side::Bid,
+            1.0,
+            Some(99.5),
+            false,
+            Token::new("token"),
+        );
+        assert_eq!(params.unwrap().limit_price, Decimal::from_f64(99.5));
+        assert!(
+            add_order_params("BTC/USD", Side::Bid, -1.0, None, true, Token::new("token")).is_err()
+        );
+        assert!(
+            add_order_params(
+                "BTC/USD",
+                Side::Bid,
+                1.0,
+                Some(0.0),
+                true,
+                Token::new("token")
+            )
+            .is_err()
+        );
+
+        let response = |error: Option<&str>| {
+            MethodMessage::AddOrder(ResultResponse {
+                result: Some(AddOrderResult {
+                    order_id: "OABC".to_string(),
+                    order_user_ref: None,
+                    warning: None,
+                    client_order_id: None,
+                }),
+                error: error.map(|error| error.to_string()),
+                success: error.is_none(),
+                req_id: 1,
+                time_in: String::new(),
+                time_out: String::new(),
+            })
+        };
+        assert!(matches!(
+            answer(response(None)),
+            Some(Action::Inform(message)) if message == "Order accepted: OABC"
+        ));
+        assert!(matches!(
+            answer(response(Some("EOrder:Insufficient funds"))),
+            Some(Action::Warn(message)) if message == "Order rejected: EOrder:Insufficient funds"
+        ));
+    }
+}
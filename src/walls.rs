@@ -0,0 +1,185 @@
+use crate::pipeline::Price;
+
+use rbtree::RBTree;
+
+/// Side of the book a resting order sits on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Ask,
+    Bid,
+}
+
+/// Unusually large resting order tracked over its lifetime
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wall {
+    pub side: Side,
+    pub price: f64,
+    /// largest quantity seen resting at the price
+    pub quantity: f64,
+    /// time of appearance
+    pub start: i64,
+    /// time of disappearance, None while still resting
+    pub end: Option<i64>,
+}
+
+/// Change in the set of walls resting in the book
+#[derive(Clone, Debug, PartialEq)]
+pub enum WallEvent {
+    Appeared(Wall),
+    Disappeared(Wall),
+}
+
+/// private utility method for finding levels at least multiple times the median level size
+fn find_walls(book: &RBTree<Price, f64>, multiple: f64) -> Vec<(f64, f64)> {
+    let mut sizes = book.values().cloned().collect::<Vec<_>>();
+    if sizes.len() < 3 {
+        return vec![];
+    }
+    sizes.sort_by(f64::total_cmp);
+    let median = sizes[sizes.len() / 2];
+
+    book.iter()
+        .filter(|(_, quantity)| **quantity >= multiple * median)
+        .map(|(price, quantity)| (price.value, *quantity))
+        .collect()
+}
+
+/// Detector of liquidity walls tracking their appearance and disappearance across snapshots
+#[derive(Clone, Debug)]
+pub struct WallDetector {
+    /// multiple of the median level size above which a level is considered a wall
+    pub multiple: f64,
+    /// size of the kept wall history in seconds
+    pub time_window_in_seconds: i64,
+    /// walls currently resting followed by the ones that disappeared within the time window
+    walls: Vec<Wall>,
+}
+
+impl WallDetector {
+    /// constructor
+    pub fn new(multiple: f64, time_window_in_seconds: i64) -> WallDetector {
+        WallDetector {
+            multiple,
+            time_window_in_seconds,
+            walls: vec![],
+        }
+    }
+
+    /// scan a book snapshot and report walls that appeared or disappeared since the last one
+    pub fn scan(
+        &mut self,
+        time: i64,
+        asks: &RBTree<Price, f64>,
+        bids: &RBTree<Price, f64>,
+    ) -> Vec<WallEvent> {
+        let found = find_walls(asks, self.multiple)
+            .into_iter()
+            .map(|level| (Side::Ask, level))
+            .chain(
+                find_walls(bids, self.multiple)
+                    .into_iter()
+                    .map(|level| (Side::Bid, level)),
+            )
+            .collect::<Vec<_>>();
+
+        let mut events = vec![];
+
+        for wall in self.walls.iter_mut().filter(|wall| wall.end.is_none()) {
+            match found
+                .iter()
+                .find(|(side, (price, _))| *side == wall.side && *price == wall.price)
+            {
+                Some((_, (_, quantity))) => wall.quantity = wall.quantity.max(*quantity),
+                None => {
+                    wall.end = Some(time);
+                    events.push(WallEvent::Disappeared(wall.clone()));
+                }
+            }
+        }
+
+        for (side, (price, quantity)) in found.into_iter() {
+            if !self
+                .walls
+                .iter()
+                .any(|wall| wall.end.is_none() && wall.side == side && wall.price == price)
+            {
+                let wall = Wall {
+                    side,
+                    price,
+                    quantity,
+                    start: time,
+                    end: None,
+                };
+                events.push(WallEvent::Appeared(wall.clone()));
+                self.walls.push(wall);
+            }
+        }
+
+        let horizon = time - self.time_window_in_seconds;
+        self.walls
+            .retain(|wall| wall.end.is_none_or(|end| end >= horizon));
+
+        events
+    }
+
+    /// walls resting or seen within the time window
+    pub fn walls(&self) -> Vec<Wall> {
+        self.walls.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(levels: &[(f64, f64)]) -> RBTree<Price, f64> {
+        RBTree::from_iter(
+            levels
+                .iter()
+                .map(|(price, quantity)| (Price { value: *price }, *quantity)),
+        )
+    }
+
+    #[test]
+    fn test_find_walls() {
+        let asks = book(&[(10.0, 1.0), (11.0, 2.0), (12.0, 1.0), (13.0, 30.0)]);
+        assert_eq!(find_walls(&asks, 10.0), vec![(13.0, 30.0)]);
+        assert_eq!(find_walls(&asks, 40.0), vec![]);
+        assert_eq!(
+            find_walls(&book(&[(10.0, 1.0), (11.0, 30.0)]), 10.0),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_scan_walls() {
+        let mut detector = WallDetector::new(10.0, 60);
+        let bids = book(&[(8.0, 1.0), (9.0, 1.0), (9.5, 1.0)]);
+
+        let events = detector.scan(0, &book(&[(10.0, 1.0), (11.0, 1.0), (12.0, 50.0)]), &bids);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            WallEvent::Appeared(wall) => {
+                assert_eq!(wall.side, Side::Ask);
+                assert_eq!(wall.price, 12.0);
+                assert_eq!(wall.start, 0);
+            }
+            WallEvent::Disappeared(_) => panic!("Wall should have appeared"),
+        }
+
+        let events = detector.scan(1, &book(&[(10.0, 1.0), (11.0, 1.0), (12.0, 60.0)]), &bids);
+        assert!(events.is_empty());
+        assert_eq!(detector.walls()[0].quantity, 60.0);
+
+        let events = detector.scan(2, &book(&[(10.0, 1.0), (11.0, 1.0), (12.0, 1.0)]), &bids);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            WallEvent::Disappeared(wall) => assert_eq!(wall.end, Some(2)),
+            WallEvent::Appeared(_) => panic!("Wall should have disappeared"),
+        }
+        assert_eq!(detector.walls().len(), 1);
+
+        detector.scan(100, &book(&[(10.0, 1.0), (11.0, 1.0), (12.0, 1.0)]), &bids);
+        assert!(detector.walls().is_empty());
+    }
+}